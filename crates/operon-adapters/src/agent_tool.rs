@@ -0,0 +1,166 @@
+use anyhow::{bail, Context, Result};
+use async_trait::async_trait;
+use operon_runtime::{
+    Agent, AgentConfig, LLMProvider, PermissionLevel, Runtime, Session, Tool, ToolSchemaInfo, Usage,
+};
+use serde_json::{json, Value};
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use tracing::info;
+
+tokio::task_local! {
+    /// Delegations the current tool call is nested in
+    static DEPTH: usize;
+}
+
+/// Delegations allowed below the first agent by default
+const DEFAULT_MAX_DEPTH: usize = 2;
+
+/// Exposes a secondary agent (own system prompt, model and budgets) as a
+/// tool, so a coordinator agent can hand sub-tasks to it.
+///
+/// Each call runs in a fresh session unless the input names one to continue.
+/// `max_iterations` and `max_cost_usd` of the sub-agent's config bound each
+/// session. Delegations nest at most `max_depth` deep, even when sub-agents
+/// can reach agent tools themselves. A delegated call holds its execution
+/// queue slot while the sub-agent's tools run, so give the sub-agent its own
+/// runtime or one with more parallel slots than `max_depth`.
+pub struct AgentTool {
+    name: String,
+    description: String,
+    config: AgentConfig,
+    provider: Arc<dyn LLMProvider>,
+    runtime: Arc<Runtime>,
+    max_depth: usize,
+    /// Named sessions kept for follow-up calls
+    sessions: Mutex<HashMap<String, Session>>,
+}
+
+impl AgentTool {
+    pub fn new(
+        name: &str,
+        config: AgentConfig,
+        provider: Arc<dyn LLMProvider>,
+        runtime: Arc<Runtime>,
+    ) -> Self {
+        Self {
+            name: name.to_string(),
+            description: format!("Delegate a task to the '{}' agent", config.name),
+            config,
+            provider,
+            runtime,
+            max_depth: DEFAULT_MAX_DEPTH,
+            sessions: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// What the coordinator is told this agent is for
+    pub fn with_description(mut self, description: &str) -> Self {
+        self.description = description.to_string();
+        self
+    }
+
+    /// Delegations allowed to nest below the first agent (default 2)
+    pub fn with_max_depth(mut self, max_depth: usize) -> Self {
+        self.max_depth = max_depth;
+        self
+    }
+}
+
+#[async_trait]
+impl Tool for AgentTool {
+    async fn execute(&self, input: Value) -> Result<Value> {
+        let task = input["task"]
+            .as_str()
+            .context("Missing required field 'task'")?;
+        let session_name = input["session"].as_str().filter(|name| !name.is_empty());
+
+        let depth = DEPTH.try_with(|depth| *depth).unwrap_or(0);
+        if depth >= self.max_depth {
+            bail!(
+                "Delegation depth limit of {} reached: '{}' cannot delegate further",
+                self.max_depth,
+                self.name
+            );
+        }
+
+        let mut agent = Agent::new(
+            self.config.clone(),
+            self.provider.clone(),
+            self.runtime.clone(),
+        );
+        if let Some(name) = session_name {
+            let session = self.sessions.lock().unwrap().get(name).cloned();
+            if let Some(session) = session {
+                agent = agent.with_session(session);
+            }
+        }
+
+        info!(
+            tool = %self.name,
+            agent = %self.config.name,
+            session_id = %agent.session.id,
+            depth = depth + 1,
+            "Delegating task to sub-agent"
+        );
+        let before = agent.session.cumulative_usage.clone();
+        let reply = DEPTH.scope(depth + 1, agent.process_message(task)).await;
+
+        match session_name {
+            Some(name) => {
+                self.sessions
+                    .lock()
+                    .unwrap()
+                    .insert(name.to_string(), agent.session.clone());
+            }
+            None => agent.end_session().await?,
+        }
+        let response = reply.with_context(|| format!("Agent '{}' failed", self.config.name))?;
+
+        let after = &agent.session.cumulative_usage;
+        let usage = Usage {
+            input_tokens: after.input_tokens - before.input_tokens,
+            output_tokens: after.output_tokens - before.output_tokens,
+            cost_usd: (after.cost_usd - before.cost_usd).max(0.0),
+        };
+        Ok(json!({
+            "agent": self.config.name,
+            "session": session_name,
+            "response": response,
+            "usage": usage,
+        }))
+    }
+
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn schema(&self) -> ToolSchemaInfo {
+        ToolSchemaInfo {
+            name: self.name.clone(),
+            description: self.description.clone(),
+            parameters: json!({
+                "type": "object",
+                "properties": {
+                    "task": {
+                        "type": "string",
+                        "description": "Task for the agent, with everything it needs to know; it does not see this conversation"
+                    },
+                    "session": {
+                        "type": "string",
+                        "description": "Name of a session to continue, so the agent remembers earlier tasks given under the same name (default: a fresh session)"
+                    }
+                },
+                "required": ["task"]
+            }),
+        }
+    }
+
+    /// The coordinator needs what the sub-agent may use
+    fn permission_level(&self) -> PermissionLevel {
+        self.config
+            .max_permission
+            .clone()
+            .unwrap_or(PermissionLevel::Execute)
+    }
+}
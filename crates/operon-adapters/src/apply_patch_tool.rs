@@ -5,7 +5,7 @@ use serde_json::{json, Value};
 use std::io::Write;
 use std::sync::Arc;
 
-use crate::diff_parser::{apply_hunk, parse_unified_diff};
+use crate::diff_parser::{apply_hunk, parse_unified_diff, Hunk};
 use crate::workspace_guard::WorkspaceGuard;
 
 pub struct ApplyPatchTool {
@@ -24,6 +24,8 @@ impl Tool for ApplyPatchTool {
         let patch = input["patch"]
             .as_str()
             .context("Missing required field 'patch'")?;
+        // Undo a previously applied patch: match against its `+` side
+        let reverse = input["reverse"].as_bool().unwrap_or(false);
 
         let file_patches = parse_unified_diff(patch)?;
         let mut files_modified = 0;
@@ -41,8 +43,12 @@ impl Tool for ApplyPatchTool {
             let mut lines: Vec<String> = content.lines().map(String::from).collect();
 
             // Apply hunks in reverse order to preserve line numbers
-            let mut sorted_hunks = fp.hunks.clone();
-            sorted_hunks.sort_by(|a, b| b.old_start.cmp(&a.old_start));
+            let mut sorted_hunks: Vec<Hunk> = if reverse {
+                fp.hunks.iter().map(Hunk::reversed).collect()
+            } else {
+                fp.hunks.clone()
+            };
+            sorted_hunks.sort_by_key(|b| std::cmp::Reverse(b.old_start));
 
             for hunk in &sorted_hunks {
                 lines = apply_hunk(&lines, hunk)?;
@@ -51,11 +57,15 @@ impl Tool for ApplyPatchTool {
 
             // Atomic write
             let new_content = lines.join("\n") + if content.ends_with('\n') { "\n" } else { "" };
+            let bytes = new_content.len() as u64;
+            let charge = self.guard.check_write(bytes, bytes)?;
+            self.guard.before_write(&path)?;
             let parent = path.parent().unwrap_or(self.guard.root());
             let mut tmp = tempfile::NamedTempFile::new_in(parent)?;
             tmp.write_all(new_content.as_bytes())?;
             tmp.flush()?;
             tmp.persist(&path)?;
+            charge.commit();
 
             files_modified += 1;
         }
@@ -63,6 +73,7 @@ impl Tool for ApplyPatchTool {
         Ok(json!({
             "files_modified": files_modified,
             "hunks_applied": hunks_applied,
+            "reversed": reverse,
         }))
     }
 
@@ -77,7 +88,11 @@ impl Tool for ApplyPatchTool {
             parameters: json!({
                 "type": "object",
                 "properties": {
-                    "patch": { "type": "string", "description": "Unified diff format patch" }
+                    "patch": { "type": "string", "description": "Unified diff format patch" },
+                    "reverse": {
+                        "type": "boolean",
+                        "description": "Undo the patch instead: remove its additions and restore its removals (default: false)"
+                    }
                 },
                 "required": ["patch"]
             }),
@@ -87,4 +102,8 @@ impl Tool for ApplyPatchTool {
     fn permission_level(&self) -> PermissionLevel {
         PermissionLevel::Write
     }
+
+    fn end_session(&self, session: &str) {
+        self.guard.end_session(session);
+    }
 }
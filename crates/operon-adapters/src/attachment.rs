@@ -0,0 +1,46 @@
+use crate::screenshot_tool::DEFAULT_MAX_IMAGE_BYTES;
+use crate::workspace_guard::WorkspaceGuard;
+use anyhow::{bail, Context, Result};
+use operon_runtime::Content;
+
+/// MIME type from the file signature, for image types the providers accept
+pub fn image_mime(data: &[u8]) -> Option<&'static str> {
+    if data.starts_with(b"\x89PNG\r\n\x1a\n") {
+        Some("image/png")
+    } else if data.starts_with(&[0xFF, 0xD8, 0xFF]) {
+        Some("image/jpeg")
+    } else if data.starts_with(b"GIF87a") || data.starts_with(b"GIF89a") {
+        Some("image/gif")
+    } else if data.len() >= 12 && data.starts_with(b"RIFF") && &data[8..12] == b"WEBP" {
+        Some("image/webp")
+    } else {
+        None
+    }
+}
+
+/// Load an image the user attaches to a message. The path is resolved
+/// through the workspace guard, so it cannot point outside the workspace.
+pub async fn load_image(guard: &WorkspaceGuard, path_str: &str) -> Result<Content> {
+    let path = guard.resolve(path_str)?;
+    if !path.is_file() {
+        bail!("File not found: {}", path_str);
+    }
+    guard.check_size(&path).await?;
+    let data = tokio::fs::read(&path)
+        .await
+        .with_context(|| format!("Failed to read {}", path_str))?;
+    if data.len() > DEFAULT_MAX_IMAGE_BYTES {
+        bail!(
+            "Image too large: {} bytes (max {} bytes)",
+            data.len(),
+            DEFAULT_MAX_IMAGE_BYTES
+        );
+    }
+    let Some(mime) = image_mime(&data) else {
+        bail!("Not a PNG, JPEG, GIF or WebP image: {}", path_str);
+    };
+    Ok(Content::Image {
+        data,
+        mime: mime.to_string(),
+    })
+}
@@ -0,0 +1,279 @@
+use crate::shell_tool::validate_command;
+use anyhow::{bail, Context, Result};
+use async_trait::async_trait;
+use operon_runtime::{ExecutionBackend, Tool, ToolInvocation};
+use serde_json::Value;
+use std::collections::{HashMap, HashSet};
+use std::path::{Path, PathBuf};
+use std::process::Stdio;
+use std::time::Duration;
+use tokio::io::AsyncWriteExt;
+use tokio::process::Command;
+use tokio::sync::Mutex;
+use tracing::{info, warn};
+
+/// Where the workspace is mounted inside containers
+pub const CONTAINER_WORKSPACE: &str = "/workspace";
+
+/// Container key for calls made outside any agent session (e.g. plan steps)
+const DEFAULT_SESSION: &str = "default";
+
+/// Runs selected tools inside a per-session podman/docker container. Each
+/// call is `<engine> exec -i <container> <entrypoint> <tool>` with the JSON
+/// input on stdin; the entrypoint (by default `warden exec-tool`, which the
+/// image must provide) prints `{"output": ...}` or `{"error": "..."}`.
+/// Tools not listed run in-process as usual.
+pub struct ContainerBackend {
+    engine: String,
+    image: String,
+    tools: HashSet<String>,
+    entrypoint: Vec<String>,
+    workspace: Option<PathBuf>,
+    network: bool,
+    /// Checked against `shell` commands on the host, before they reach the container
+    shell_blocklist: Vec<String>,
+    shell_allowlist: Vec<String>,
+    /// Per-tool timeouts handed to the entrypoint
+    timeouts: HashMap<String, Duration>,
+    /// Session → running container name
+    containers: Mutex<HashMap<String, String>>,
+}
+
+impl ContainerBackend {
+    /// Run `tools` in containers of `image` using `engine` ("docker" or "podman")
+    pub fn new(engine: &str, image: &str, tools: impl IntoIterator<Item = String>) -> Self {
+        Self {
+            engine: engine.to_string(),
+            image: image.to_string(),
+            tools: tools.into_iter().collect(),
+            entrypoint: vec!["warden".into(), "exec-tool".into()],
+            workspace: None,
+            network: false,
+            shell_blocklist: Vec::new(),
+            shell_allowlist: Vec::new(),
+            timeouts: HashMap::new(),
+            containers: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Bind-mount `workspace` read-write at `/workspace`, the containers' working directory
+    pub fn with_workspace(mut self, workspace: &Path) -> Result<Self> {
+        let workspace = workspace
+            .canonicalize()
+            .context(format!("Container workspace not found: {:?}", workspace))?;
+        self.workspace = Some(workspace);
+        Ok(self)
+    }
+
+    /// Command run inside the container for each call; `--timeout-secs` (when
+    /// set) and the tool name are appended
+    pub fn with_entrypoint(mut self, entrypoint: Vec<String>) -> Self {
+        self.entrypoint = entrypoint;
+        self
+    }
+
+    /// Give containers network access (off by default)
+    pub fn with_network(mut self, network: bool) -> Self {
+        self.network = network;
+        self
+    }
+
+    /// Check `shell` commands against these lists (and the built-in blocklist)
+    /// on the host, as the in-process shell tool would
+    pub fn with_shell_validation(mut self, blocklist: Vec<String>, allowlist: Vec<String>) -> Self {
+        self.shell_blocklist = blocklist;
+        self.shell_allowlist = allowlist;
+        self
+    }
+
+    /// Pass each tool's timeout to the entrypoint as `--timeout-secs`, so a
+    /// timed-out call is stopped inside the container, not just abandoned
+    pub fn with_timeouts(mut self, timeouts: HashMap<String, Duration>) -> Self {
+        self.timeouts = timeouts;
+        self
+    }
+
+    /// Arguments that start the long-lived container `name`
+    pub fn run_args(&self, name: &str) -> Vec<String> {
+        let mut args = vec![
+            "run".to_string(),
+            "-d".into(),
+            "--rm".into(),
+            "--name".into(),
+            name.to_string(),
+        ];
+        if !self.network {
+            args.extend(["--network".into(), "none".into()]);
+        }
+        if let Some(workspace) = &self.workspace {
+            args.extend([
+                "-v".into(),
+                format!("{}:{}", workspace.display(), CONTAINER_WORKSPACE),
+                "-w".into(),
+                CONTAINER_WORKSPACE.into(),
+            ]);
+        }
+        args.extend([self.image.clone(), "sleep".into(), "infinity".into()]);
+        args
+    }
+
+    /// Arguments that run `tool` once inside container `name`
+    pub fn exec_args(&self, name: &str, tool: &str) -> Vec<String> {
+        let mut args = vec!["exec".to_string(), "-i".into(), name.to_string()];
+        args.extend(self.entrypoint.iter().cloned());
+        if let Some(timeout) = self.timeouts.get(tool) {
+            args.extend([
+                "--timeout-secs".into(),
+                timeout.as_secs().max(1).to_string(),
+            ]);
+        }
+        args.push(tool.to_string());
+        args
+    }
+
+    /// Container for `session`, started on first use
+    async fn container(&self, session: &str) -> Result<String> {
+        let mut containers = self.containers.lock().await;
+        if let Some(name) = containers.get(session) {
+            return Ok(name.clone());
+        }
+        let name = container_name(session);
+        let output = Command::new(&self.engine)
+            .args(self.run_args(&name))
+            .stdin(Stdio::null())
+            .output()
+            .await
+            .context(format!("Failed to run container engine '{}'", self.engine))?;
+        if !output.status.success() {
+            bail!(
+                "Failed to start container for session '{}': {}",
+                session,
+                String::from_utf8_lossy(&output.stderr).trim()
+            );
+        }
+        info!(session, container = %name, image = %self.image, "Started tool container");
+        containers.insert(session.to_string(), name.clone());
+        Ok(name)
+    }
+
+    async fn remove(&self, name: &str) -> Result<()> {
+        let status = Command::new(&self.engine)
+            .args(["rm", "-f", name])
+            .stdout(Stdio::null())
+            .stderr(Stdio::null())
+            .status()
+            .await
+            .context(format!("Failed to run container engine '{}'", self.engine))?;
+        if !status.success() {
+            warn!(container = name, "Failed to remove tool container");
+        }
+        Ok(())
+    }
+}
+
+#[async_trait]
+impl ExecutionBackend for ContainerBackend {
+    fn name(&self) -> &str {
+        "container"
+    }
+
+    async fn execute(&self, call: ToolInvocation, tool: &dyn Tool) -> Result<Value> {
+        if !self.tools.contains(&call.tool) {
+            return tool.execute(call.input).await;
+        }
+        if call.tool == "shell" {
+            let cmd = call.input["cmd"]
+                .as_str()
+                .context("Input missing 'cmd' field")?;
+            validate_command(cmd, &self.shell_blocklist, &self.shell_allowlist)?;
+        }
+        let session = call.session.as_deref().unwrap_or(DEFAULT_SESSION);
+        let name = self.container(session).await?;
+
+        let mut child = Command::new(&self.engine)
+            .args(self.exec_args(&name, &call.tool))
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            // A runtime timeout drops this future; don't leave the exec behind
+            .kill_on_drop(true)
+            .spawn()
+            .context(format!("Failed to run container engine '{}'", self.engine))?;
+        if let Some(mut stdin) = child.stdin.take() {
+            stdin.write_all(&serde_json::to_vec(&call.input)?).await?;
+        }
+        let output = child.wait_with_output().await?;
+
+        let response: Value = serde_json::from_slice(&output.stdout).map_err(|_| {
+            anyhow::anyhow!(
+                "Tool '{}' in container '{}' returned no result (exit {}): {}",
+                call.tool,
+                name,
+                output.status.code().unwrap_or(-1),
+                String::from_utf8_lossy(&output.stderr).trim()
+            )
+        })?;
+        parse_response(response)
+    }
+
+    async fn end_session(&self, session: &str) -> Result<()> {
+        let name = self.containers.lock().await.remove(session);
+        match name {
+            Some(name) => self.remove(&name).await,
+            None => Ok(()),
+        }
+    }
+
+    async fn shutdown(&self) -> Result<()> {
+        let names: Vec<String> = self
+            .containers
+            .lock()
+            .await
+            .drain()
+            .map(|(_, n)| n)
+            .collect();
+        for name in names {
+            self.remove(&name).await?;
+        }
+        Ok(())
+    }
+}
+
+impl Drop for ContainerBackend {
+    /// Containers outlive the process otherwise (`sleep infinity`)
+    fn drop(&mut self) {
+        for name in self.containers.get_mut().values() {
+            let _ = std::process::Command::new(&self.engine)
+                .args(["rm", "-f", name])
+                .stdout(Stdio::null())
+                .stderr(Stdio::null())
+                .status();
+        }
+    }
+}
+
+/// Container-side reply: `{"output": ...}` or `{"error": "..."}`
+pub fn parse_response(response: Value) -> Result<Value> {
+    if let Some(error) = response.get("error") {
+        bail!("{}", error.as_str().unwrap_or(&error.to_string()));
+    }
+    match response {
+        Value::Object(mut map) if map.contains_key("output") => Ok(map.remove("output").unwrap()),
+        other => bail!("Malformed container tool response: {}", other),
+    }
+}
+
+/// Engine-safe, per-process container name for `session`
+fn container_name(session: &str) -> String {
+    let session: String = session
+        .chars()
+        .map(|c| {
+            if c.is_ascii_alphanumeric() || c == '-' {
+                c
+            } else {
+                '_'
+            }
+        })
+        .collect();
+    format!("silentclaw-{}-{}", std::process::id(), session)
+}
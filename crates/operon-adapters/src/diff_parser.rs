@@ -11,9 +11,31 @@ pub enum HunkLine {
 #[derive(Clone)]
 pub struct Hunk {
     pub old_start: usize, // 0-based line index
+    pub new_start: usize, // 0-based line index in the patched file
     pub lines: Vec<HunkLine>,
 }
 
+impl Hunk {
+    /// The hunk that undoes this one on the patched file: additions become
+    /// removals and removals additions
+    pub fn reversed(&self) -> Hunk {
+        let lines = self
+            .lines
+            .iter()
+            .map(|line| match line {
+                HunkLine::Context(ctx) => HunkLine::Context(ctx.clone()),
+                HunkLine::Remove(rem) => HunkLine::Add(rem.clone()),
+                HunkLine::Add(add) => HunkLine::Remove(add.clone()),
+            })
+            .collect();
+        Hunk {
+            old_start: self.new_start,
+            new_start: self.old_start,
+            lines,
+        }
+    }
+}
+
 pub struct FilePatch {
     pub path: String,
     pub hunks: Vec<Hunk>,
@@ -50,9 +72,10 @@ pub fn parse_unified_diff(patch: &str) -> Result<Vec<FilePatch>> {
             if let Some(h) = current_hunk.take() {
                 current_hunks.push(h);
             }
-            let old_start = parse_hunk_header(line)?;
+            let (old_start, new_start) = parse_hunk_header(line)?;
             current_hunk = Some(Hunk {
                 old_start,
+                new_start,
                 lines: Vec::new(),
             });
         } else if let Some(ref mut hunk) = current_hunk {
@@ -84,20 +107,27 @@ pub fn parse_unified_diff(patch: &str) -> Result<Vec<FilePatch>> {
     Ok(file_patches)
 }
 
-/// Parse `@@ -start,count +start,count @@` → old_start (1-based → 0-based)
-fn parse_hunk_header(line: &str) -> Result<usize> {
+/// Parse `@@ -start,count +start,count @@` → (old_start, new_start), 1-based → 0-based.
+/// A header without a `+` range reuses the old start.
+fn parse_hunk_header(line: &str) -> Result<(usize, usize)> {
     let part = line
         .split("@@")
         .nth(1)
         .context("Invalid hunk header")?
         .trim();
-    let old_part = part.split(' ').next().context("Invalid hunk range")?;
-    let start_str = old_part
-        .strip_prefix('-')
-        .unwrap_or(old_part)
-        .split(',')
-        .next()
-        .context("Invalid hunk start")?;
+    let mut ranges = part.split(' ');
+    let old_part = ranges.next().context("Invalid hunk range")?;
+    let old_start = parse_range_start(old_part.strip_prefix('-').unwrap_or(old_part))?;
+    let new_start = match ranges.next().and_then(|r| r.strip_prefix('+')) {
+        Some(new_part) => parse_range_start(new_part)?,
+        None => old_start,
+    };
+    Ok((old_start, new_start))
+}
+
+/// `start[,count]` → 0-based start
+fn parse_range_start(range: &str) -> Result<usize> {
+    let start_str = range.split(',').next().context("Invalid hunk start")?;
     let start: usize = start_str.parse().context("Invalid hunk line number")?;
     Ok(start.saturating_sub(1)) // 1-based → 0-based
 }
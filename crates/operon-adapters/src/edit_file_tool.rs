@@ -1,6 +1,7 @@
 use anyhow::{bail, Context, Result};
 use async_trait::async_trait;
 use operon_runtime::{PermissionLevel, Tool, ToolSchemaInfo};
+use regex::Regex;
 use serde_json::{json, Value};
 use std::io::Write;
 use std::sync::Arc;
@@ -30,6 +31,15 @@ impl Tool for EditFileTool {
             .as_str()
             .context("Missing required field 'new_string'")?;
         let replace_all = input["replace_all"].as_bool().unwrap_or(false);
+        let use_regex = input["regex"].as_bool().unwrap_or(false);
+        let max_replacements = input["max_replacements"].as_u64().map(|n| n as usize);
+        let preview = input["preview"].as_bool().unwrap_or(false);
+
+        let pattern = if use_regex {
+            Some(Regex::new(old_string).context("Invalid regex in old_string")?)
+        } else {
+            None
+        };
 
         let path = self.guard.resolve(path_str)?;
 
@@ -42,7 +52,18 @@ impl Tool for EditFileTool {
         let content = tokio::fs::read_to_string(&path)
             .await
             .context("Failed to read file")?;
-        let match_count = content.matches(old_string).count();
+        let changes = match &pattern {
+            Some(re) => regex_changes(re, &content, new_string),
+            None => content
+                .match_indices(old_string)
+                .map(|(start, found)| Change {
+                    start,
+                    found: found.to_string(),
+                    replacement: new_string.to_string(),
+                })
+                .collect(),
+        };
+        let match_count = changes.len();
 
         if match_count == 0 {
             bail!("old_string not found in file: {}", path_str);
@@ -55,13 +76,41 @@ impl Tool for EditFileTool {
             );
         }
 
-        let new_content = if replace_all {
-            content.replace(old_string, new_string)
-        } else {
-            content.replacen(old_string, new_string, 1)
+        if let Some(max) = max_replacements {
+            if match_count > max {
+                bail!(
+                    "old_string found {} times in file, more than max_replacements={}",
+                    match_count,
+                    max
+                );
+            }
+        }
+
+        if preview {
+            return Ok(json!({
+                "replacements": match_count,
+                "path": path_str,
+                "preview": true,
+                "changes": changes
+                    .iter()
+                    .map(|c| json!({
+                        "line": content[..c.start].matches('\n').count() + 1,
+                        "match": c.found,
+                        "replacement": c.replacement,
+                    }))
+                    .collect::<Vec<_>>(),
+            }));
+        }
+
+        let new_content = match &pattern {
+            Some(re) => re.replace_all(&content, new_string).into_owned(),
+            None => content.replace(old_string, new_string),
         };
 
         // Atomic write
+        let bytes = new_content.len() as u64;
+        let charge = self.guard.check_write(bytes, bytes)?;
+        self.guard.before_write(&path)?;
         let parent = path.parent().unwrap_or(self.guard.root());
         let mut tmp = tempfile::NamedTempFile::new_in(parent)
             .context("Failed to create temp file for atomic write")?;
@@ -69,9 +118,10 @@ impl Tool for EditFileTool {
         tmp.flush()?;
         tmp.persist(&path)
             .context(format!("Failed to persist edited file: {:?}", path))?;
+        charge.commit();
 
         Ok(json!({
-            "replacements": if replace_all { match_count } else { 1 },
+            "replacements": match_count,
             "path": path_str,
         }))
     }
@@ -83,14 +133,17 @@ impl Tool for EditFileTool {
     fn schema(&self) -> ToolSchemaInfo {
         ToolSchemaInfo {
             name: "edit_file".to_string(),
-            description: "Find and replace exact string in a file".to_string(),
+            description: "Find and replace exact string (or regex) in a file".to_string(),
             parameters: json!({
                 "type": "object",
                 "properties": {
                     "path": { "type": "string", "description": "File path relative to workspace" },
-                    "old_string": { "type": "string", "description": "Exact string to find" },
-                    "new_string": { "type": "string", "description": "Replacement string" },
-                    "replace_all": { "type": "boolean", "description": "Replace all occurrences (default: false)" }
+                    "old_string": { "type": "string", "description": "Exact string to find (a pattern when regex=true)" },
+                    "new_string": { "type": "string", "description": "Replacement string; with regex=true, $1 / ${name} expand capture groups" },
+                    "replace_all": { "type": "boolean", "description": "Replace all occurrences (default: false)" },
+                    "regex": { "type": "boolean", "description": "Treat old_string as a regular expression (default: false)" },
+                    "max_replacements": { "type": "integer", "description": "Fail without editing if more matches than this are found" },
+                    "preview": { "type": "boolean", "description": "Report the matches and their replacements without writing (default: false)" }
                 },
                 "required": ["path", "old_string", "new_string"]
             }),
@@ -100,4 +153,30 @@ impl Tool for EditFileTool {
     fn permission_level(&self) -> PermissionLevel {
         PermissionLevel::Write
     }
+
+    fn end_session(&self, session: &str) {
+        self.guard.end_session(session);
+    }
+}
+
+/// A single match and the text that would replace it.
+struct Change {
+    start: usize,
+    found: String,
+    replacement: String,
+}
+
+fn regex_changes(re: &Regex, content: &str, template: &str) -> Vec<Change> {
+    re.captures_iter(content)
+        .map(|caps| {
+            let whole = caps.get(0).expect("group 0 always participates");
+            let mut replacement = String::new();
+            caps.expand(template, &mut replacement);
+            Change {
+                start: whole.start(),
+                found: whole.as_str().to_string(),
+                replacement,
+            }
+        })
+        .collect()
 }
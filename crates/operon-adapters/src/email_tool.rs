@@ -0,0 +1,218 @@
+use anyhow::{bail, Context, Result};
+use async_trait::async_trait;
+use lettre::message::header::ContentType;
+use lettre::message::Mailbox;
+use lettre::transport::smtp::authentication::Credentials;
+use lettre::transport::smtp::client::{Tls, TlsParameters};
+use lettre::{AsyncSmtpTransport, AsyncTransport, Message, Tokio1Executor};
+use operon_runtime::{PermissionLevel, Tool, ToolSchemaInfo};
+use serde_json::{json, Value};
+use std::time::Duration;
+use tracing::info;
+
+/// Largest body accepted per message
+const MAX_BODY_BYTES: usize = 1024 * 1024;
+
+/// How the connection to the relay is secured
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SmtpSecurity {
+    /// Plain connection, upgraded with STARTTLS (required)
+    StartTls,
+    /// TLS from the first byte (SMTPS)
+    Tls,
+    /// No encryption; only for a relay on localhost or a trusted network
+    None,
+}
+
+impl SmtpSecurity {
+    /// Usual port for this kind of connection
+    pub fn default_port(&self) -> u16 {
+        match self {
+            SmtpSecurity::StartTls => 587,
+            SmtpSecurity::Tls => 465,
+            SmtpSecurity::None => 25,
+        }
+    }
+}
+
+impl std::str::FromStr for SmtpSecurity {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s.to_lowercase().as_str() {
+            "starttls" => Ok(SmtpSecurity::StartTls),
+            "tls" => Ok(SmtpSecurity::Tls),
+            "none" => Ok(SmtpSecurity::None),
+            other => bail!(
+                "Unknown SMTP security '{}' (expected starttls, tls or none)",
+                other
+            ),
+        }
+    }
+}
+
+/// Sends plain-text email through a configured SMTP relay, only to
+/// allowlisted recipients
+pub struct EmailTool {
+    host: String,
+    port: u16,
+    security: SmtpSecurity,
+    credentials: Option<Credentials>,
+    from: Mailbox,
+    allowed_recipients: Vec<String>,
+}
+
+impl EmailTool {
+    /// Tool sending as `from` through `host` with STARTTLS on port 587
+    pub fn new(host: &str, from: &str) -> Result<Self> {
+        Ok(Self {
+            host: host.to_string(),
+            port: SmtpSecurity::StartTls.default_port(),
+            security: SmtpSecurity::StartTls,
+            credentials: None,
+            from: from
+                .parse()
+                .with_context(|| format!("Invalid sender address '{}'", from))?,
+            allowed_recipients: Vec::new(),
+        })
+    }
+
+    /// Connection security; also resets the port to its usual value
+    pub fn with_security(mut self, security: SmtpSecurity) -> Self {
+        self.security = security;
+        self.port = security.default_port();
+        self
+    }
+
+    pub fn with_port(mut self, port: u16) -> Self {
+        self.port = port;
+        self
+    }
+
+    /// Log in to the relay with these credentials
+    pub fn with_credentials(mut self, username: &str, password: &str) -> Self {
+        self.credentials = Some(Credentials::new(username.to_string(), password.to_string()));
+        self
+    }
+
+    /// Addresses mail may go to: exact addresses, or `@domain` for a whole domain
+    pub fn with_allowed_recipients(mut self, allowed_recipients: Vec<String>) -> Self {
+        self.allowed_recipients = allowed_recipients
+            .into_iter()
+            .map(|r| r.trim().to_lowercase())
+            .collect();
+        self
+    }
+
+    /// Whether `address` matches the allowlist
+    pub fn is_allowed(&self, address: &str) -> bool {
+        let address = address.to_lowercase();
+        self.allowed_recipients.iter().any(|allowed| {
+            if allowed.starts_with('@') {
+                address.ends_with(allowed.as_str())
+            } else {
+                address == *allowed
+            }
+        })
+    }
+
+    fn transport(&self) -> Result<AsyncSmtpTransport<Tokio1Executor>> {
+        let tls = match self.security {
+            SmtpSecurity::None => Tls::None,
+            SmtpSecurity::StartTls => Tls::Required(TlsParameters::new(self.host.clone())?),
+            SmtpSecurity::Tls => Tls::Wrapper(TlsParameters::new(self.host.clone())?),
+        };
+        let mut builder = AsyncSmtpTransport::<Tokio1Executor>::builder_dangerous(&self.host)
+            .port(self.port)
+            .tls(tls)
+            .timeout(Some(Duration::from_secs(30)));
+        if let Some(credentials) = &self.credentials {
+            builder = builder.credentials(credentials.clone());
+        }
+        Ok(builder.build())
+    }
+}
+
+#[async_trait]
+impl Tool for EmailTool {
+    async fn execute(&self, input: Value) -> Result<Value> {
+        let to: Vec<&str> = match &input["to"] {
+            Value::String(address) => vec![address.as_str()],
+            Value::Array(addresses) => addresses
+                .iter()
+                .map(Value::as_str)
+                .collect::<Option<_>>()
+                .context("'to' array must contain only strings")?,
+            _ => bail!("Missing required field 'to'"),
+        };
+        if to.is_empty() {
+            bail!("'to' must name at least one recipient");
+        }
+        let subject = input["subject"]
+            .as_str()
+            .context("Missing required field 'subject'")?;
+        let body = input["body"]
+            .as_str()
+            .context("Missing required field 'body'")?;
+        if body.len() > MAX_BODY_BYTES {
+            bail!("Email body exceeds {} bytes", MAX_BODY_BYTES);
+        }
+
+        let mut message = Message::builder().from(self.from.clone()).subject(subject);
+        for address in &to {
+            let mailbox: Mailbox = address
+                .parse()
+                .with_context(|| format!("Invalid recipient address '{}'", address))?;
+            if !self.is_allowed(mailbox.email.as_ref()) {
+                bail!("Recipient '{}' is not in the email allowlist", address);
+            }
+            message = message.to(mailbox);
+        }
+        let message = message
+            .header(ContentType::TEXT_PLAIN)
+            .body(body.to_string())?;
+
+        self.transport()?
+            .send(message)
+            .await
+            .with_context(|| format!("Failed to send email through {}", self.host))?;
+        info!(relay = %self.host, recipients = to.len(), subject, "Sent email");
+        Ok(json!({ "sent": true, "to": to }))
+    }
+
+    fn name(&self) -> &str {
+        "email"
+    }
+
+    fn schema(&self) -> ToolSchemaInfo {
+        ToolSchemaInfo {
+            name: "email".to_string(),
+            description: format!(
+                "Send a plain-text email (allowed recipients: {})",
+                self.allowed_recipients.join(", ")
+            ),
+            parameters: json!({
+                "type": "object",
+                "properties": {
+                    "to": {
+                        "description": "Recipient address or list of addresses",
+                        "oneOf": [
+                            { "type": "string" },
+                            { "type": "array", "items": { "type": "string" } }
+                        ]
+                    },
+                    "subject": { "type": "string" },
+                    "body": {
+                        "type": "string",
+                        "description": "Plain-text message, e.g. an alert or a generated report"
+                    }
+                },
+                "required": ["to", "subject", "body"]
+            }),
+        }
+    }
+
+    fn permission_level(&self) -> PermissionLevel {
+        PermissionLevel::Network
+    }
+}
@@ -0,0 +1,399 @@
+use anyhow::{bail, Context, Result};
+use async_trait::async_trait;
+use base64::Engine;
+use operon_runtime::{PermissionLevel, Tool, ToolSchemaInfo};
+use serde_json::{json, Value};
+use std::path::{Path, PathBuf};
+use std::process::Stdio;
+use std::time::Duration;
+use tokio::process::Command;
+use tracing::info;
+
+/// Everything the tool can do; `with_actions` narrows it
+pub const GITHUB_ACTIONS: &[&str] = &[
+    "list_issues",
+    "create_issue",
+    "get_pr_diff",
+    "review_pr",
+    "open_pr",
+];
+
+/// Issues listed per call when the input sets no limit
+const DEFAULT_ISSUE_LIMIT: u64 = 30;
+
+/// Works on one GitHub repository with a (preferably fine-grained, repo-scoped)
+/// token: list and create issues, read pull request diffs, post reviews, and
+/// open pull requests from the workspace's current branch.
+pub struct GitHubTool {
+    repo: String,
+    token: String,
+    api_url: String,
+    git_url: String,
+    workspace: Option<PathBuf>,
+    base_branch: String,
+    actions: Vec<String>,
+    max_diff_bytes: usize,
+    client: reqwest::Client,
+}
+
+impl GitHubTool {
+    /// Tool for `repo` ("owner/name")
+    pub fn new(repo: &str, token: &str) -> Result<Self> {
+        let valid = repo.split_once('/').is_some_and(|(owner, name)| {
+            !owner.is_empty() && !name.is_empty() && !name.contains('/')
+        });
+        if !valid {
+            bail!("GitHub repository must be 'owner/name', got '{}'", repo);
+        }
+        Ok(Self {
+            repo: repo.to_string(),
+            token: token.to_string(),
+            api_url: "https://api.github.com".to_string(),
+            git_url: "https://github.com".to_string(),
+            workspace: None,
+            base_branch: "main".to_string(),
+            actions: GITHUB_ACTIONS.iter().map(|a| a.to_string()).collect(),
+            max_diff_bytes: 512 * 1024,
+            client: reqwest::Client::new(),
+        })
+    }
+
+    /// API root, e.g. `https://github.example.com/api/v3` for GitHub Enterprise
+    pub fn with_api_url(mut self, api_url: &str) -> Self {
+        self.api_url = api_url.trim_end_matches('/').to_string();
+        self
+    }
+
+    /// Git host `open_pr` pushes to, e.g. `https://github.example.com` for
+    /// GitHub Enterprise; the token is only sent to this host
+    pub fn with_git_url(mut self, git_url: &str) -> Self {
+        self.git_url = git_url.trim_end_matches('/').to_string();
+        self
+    }
+
+    /// Git checkout whose current branch `open_pr` pushes and proposes
+    pub fn with_workspace(mut self, workspace: &Path) -> Self {
+        self.workspace = Some(workspace.to_path_buf());
+        self
+    }
+
+    /// Branch pull requests target unless the call names another (default "main")
+    pub fn with_base_branch(mut self, base_branch: &str) -> Self {
+        self.base_branch = base_branch.to_string();
+        self
+    }
+
+    /// Only allow these actions (see [`GITHUB_ACTIONS`])
+    pub fn with_actions(mut self, actions: Vec<String>) -> Result<Self> {
+        if let Some(unknown) = actions
+            .iter()
+            .find(|a| !GITHUB_ACTIONS.contains(&a.as_str()))
+        {
+            bail!("Unknown GitHub action '{}'", unknown);
+        }
+        self.actions = actions;
+        Ok(self)
+    }
+
+    /// Diff bytes returned by `get_pr_diff`; the rest is cut off
+    pub fn with_max_diff_bytes(mut self, max_diff_bytes: usize) -> Self {
+        self.max_diff_bytes = max_diff_bytes;
+        self
+    }
+
+    fn url(&self, path: &str) -> String {
+        format!("{}/repos/{}{}", self.api_url, self.repo, path)
+    }
+
+    /// Send an API request and fail on non-2xx, quoting GitHub's message
+    async fn send(&self, request: reqwest::RequestBuilder) -> Result<reqwest::Response> {
+        let response = request
+            .bearer_auth(&self.token)
+            .header("User-Agent", "silentclaw")
+            .header("X-GitHub-Api-Version", "2022-11-28")
+            .timeout(Duration::from_secs(30))
+            .send()
+            .await
+            .context("Failed to reach the GitHub API")?;
+        let status = response.status();
+        if !status.is_success() {
+            let body: Value = response.json().await.unwrap_or(Value::Null);
+            bail!(
+                "GitHub API returned {}: {}",
+                status,
+                body["message"].as_str().unwrap_or("no message")
+            );
+        }
+        Ok(response)
+    }
+
+    async fn json(&self, request: reqwest::RequestBuilder) -> Result<Value> {
+        let request = request.header("Accept", "application/vnd.github+json");
+        Ok(self.send(request).await?.json().await?)
+    }
+
+    async fn list_issues(&self, input: &Value) -> Result<Value> {
+        let state = input["state"].as_str().unwrap_or("open");
+        let limit = input["limit"]
+            .as_u64()
+            .unwrap_or(DEFAULT_ISSUE_LIMIT)
+            .clamp(1, 100);
+        let mut query = vec![
+            ("state", state.to_string()),
+            ("per_page", limit.to_string()),
+        ];
+        if let Some(labels) = input["labels"].as_str() {
+            query.push(("labels", labels.to_string()));
+        }
+        let items = self
+            .json(self.client.get(self.url("/issues")).query(&query))
+            .await?;
+        // The issues endpoint also returns pull requests
+        let issues: Vec<Value> = items
+            .as_array()
+            .into_iter()
+            .flatten()
+            .filter(|item| item.get("pull_request").is_none())
+            .map(|issue| {
+                json!({
+                    "number": issue["number"],
+                    "title": issue["title"],
+                    "state": issue["state"],
+                    "labels": issue["labels"]
+                        .as_array()
+                        .into_iter()
+                        .flatten()
+                        .filter_map(|l| l["name"].as_str())
+                        .collect::<Vec<_>>(),
+                    "url": issue["html_url"],
+                })
+            })
+            .collect();
+        Ok(json!({ "issues": issues }))
+    }
+
+    async fn create_issue(&self, input: &Value) -> Result<Value> {
+        let title = input["title"]
+            .as_str()
+            .context("Missing required field 'title'")?;
+        let mut body = json!({ "title": title, "body": input["body"].as_str().unwrap_or("") });
+        if let Some(labels) = input["labels"].as_array() {
+            body["labels"] = json!(labels);
+        }
+        let issue = self
+            .json(self.client.post(self.url("/issues")).json(&body))
+            .await?;
+        info!(repo = %self.repo, number = %issue["number"], "Created GitHub issue");
+        Ok(json!({ "number": issue["number"], "url": issue["html_url"] }))
+    }
+
+    async fn get_pr_diff(&self, input: &Value) -> Result<Value> {
+        let number = pr_number(input)?;
+        let request = self
+            .client
+            .get(self.url(&format!("/pulls/{}", number)))
+            .header("Accept", "application/vnd.github.diff");
+        let diff = self.send(request).await?.text().await?;
+        let truncated = diff.len() > self.max_diff_bytes;
+        let mut end = diff.len().min(self.max_diff_bytes);
+        while !diff.is_char_boundary(end) {
+            end -= 1;
+        }
+        Ok(json!({
+            "number": number,
+            "diff": &diff[..end],
+            "truncated": truncated,
+        }))
+    }
+
+    async fn review_pr(&self, input: &Value) -> Result<Value> {
+        let number = pr_number(input)?;
+        let body = input["body"].as_str().unwrap_or("");
+        let comments: Vec<Value> = input["comments"]
+            .as_array()
+            .into_iter()
+            .flatten()
+            .map(|c| {
+                Ok(json!({
+                    "path": c["path"].as_str().context("Review comment needs 'path'")?,
+                    "line": c["line"].as_u64().context("Review comment needs 'line'")?,
+                    "body": c["body"].as_str().context("Review comment needs 'body'")?,
+                }))
+            })
+            .collect::<Result<_>>()?;
+        if body.is_empty() && comments.is_empty() {
+            bail!("A review needs a 'body' or line 'comments'");
+        }
+        // COMMENT only: an agent never approves or blocks a pull request
+        let review = self
+            .json(
+                self.client
+                    .post(self.url(&format!("/pulls/{}/reviews", number)))
+                    .json(&json!({ "event": "COMMENT", "body": body, "comments": comments })),
+            )
+            .await?;
+        info!(repo = %self.repo, number, "Posted GitHub review");
+        Ok(json!({ "id": review["id"], "url": review["html_url"] }))
+    }
+
+    async fn open_pr(&self, input: &Value) -> Result<Value> {
+        let title = input["title"]
+            .as_str()
+            .context("Missing required field 'title'")?;
+        let workspace = self
+            .workspace
+            .as_deref()
+            .context("open_pr needs a workspace git checkout")?;
+        let branch = self
+            .git(workspace, &["rev-parse", "--abbrev-ref", "HEAD"])
+            .await?;
+        let base = input["base"].as_str().unwrap_or(&self.base_branch);
+        if branch == "HEAD" || branch == base {
+            bail!(
+                "Workspace is on '{}'; commit the change on a feature branch first",
+                branch
+            );
+        }
+        // Push to the configured repository rather than whatever `origin` is
+        let remote = format!("{}/{}.git", self.git_url, self.repo);
+        self.git(
+            workspace,
+            &["push", &remote, &format!("HEAD:refs/heads/{}", branch)],
+        )
+        .await?;
+
+        let pr = self
+            .json(self.client.post(self.url("/pulls")).json(&json!({
+                "title": title,
+                "body": input["body"].as_str().unwrap_or(""),
+                "head": branch,
+                "base": base,
+                "draft": input["draft"].as_bool().unwrap_or(false),
+            })))
+            .await?;
+        info!(repo = %self.repo, branch = %branch, number = %pr["number"], "Opened pull request");
+        Ok(json!({ "number": pr["number"], "url": pr["html_url"], "head": branch, "base": base }))
+    }
+
+    /// Run git in `dir`, authenticating requests to the git host with the
+    /// token through the environment so it never shows up in process
+    /// arguments; other remotes never see it
+    async fn git(&self, dir: &Path, args: &[&str]) -> Result<String> {
+        let credentials = base64::engine::general_purpose::STANDARD
+            .encode(format!("x-access-token:{}", self.token));
+        let output = Command::new("git")
+            .arg("-C")
+            .arg(dir)
+            .args(args)
+            .env("GIT_TERMINAL_PROMPT", "0")
+            .env("GIT_CONFIG_COUNT", "1")
+            .env(
+                "GIT_CONFIG_KEY_0",
+                format!("http.{}/.extraheader", self.git_url),
+            )
+            .env(
+                "GIT_CONFIG_VALUE_0",
+                format!("AUTHORIZATION: basic {}", credentials),
+            )
+            .stdin(Stdio::null())
+            .kill_on_drop(true)
+            .output()
+            .await
+            .context("Failed to run git")?;
+        if !output.status.success() {
+            bail!(
+                "git {} failed: {}",
+                args[0],
+                String::from_utf8_lossy(&output.stderr).trim()
+            );
+        }
+        Ok(String::from_utf8_lossy(&output.stdout).trim().to_string())
+    }
+}
+
+fn pr_number(input: &Value) -> Result<u64> {
+    input["number"]
+        .as_u64()
+        .context("Missing required field 'number'")
+}
+
+#[async_trait]
+impl Tool for GitHubTool {
+    async fn execute(&self, input: Value) -> Result<Value> {
+        let action = input["action"]
+            .as_str()
+            .context("Missing required field 'action'")?;
+        if !self.actions.iter().any(|a| a == action) {
+            bail!(
+                "GitHub action '{}' is not enabled (allowed: {})",
+                action,
+                self.actions.join(", ")
+            );
+        }
+        match action {
+            "list_issues" => self.list_issues(&input).await,
+            "create_issue" => self.create_issue(&input).await,
+            "get_pr_diff" => self.get_pr_diff(&input).await,
+            "review_pr" => self.review_pr(&input).await,
+            "open_pr" => self.open_pr(&input).await,
+            other => bail!("Unknown GitHub action '{}'", other),
+        }
+    }
+
+    fn name(&self) -> &str {
+        "github"
+    }
+
+    fn schema(&self) -> ToolSchemaInfo {
+        ToolSchemaInfo {
+            name: "github".to_string(),
+            description: format!(
+                "Work with GitHub repository {}: {}",
+                self.repo,
+                self.actions.join(", ")
+            ),
+            parameters: json!({
+                "type": "object",
+                "properties": {
+                    "action": {
+                        "type": "string",
+                        "enum": self.actions,
+                        "description": "list_issues(state, labels, limit) | create_issue(title, body, labels) | get_pr_diff(number) | review_pr(number, body, comments) | open_pr(title, body, base, draft) from the workspace branch"
+                    },
+                    "number": { "type": "integer", "description": "Pull request number" },
+                    "title": { "type": "string" },
+                    "body": { "type": "string" },
+                    "labels": {
+                        "description": "Comma-separated filter (list_issues) or label names (create_issue)",
+                        "oneOf": [
+                            { "type": "string" },
+                            { "type": "array", "items": { "type": "string" } }
+                        ]
+                    },
+                    "state": { "type": "string", "enum": ["open", "closed", "all"] },
+                    "limit": { "type": "integer" },
+                    "comments": {
+                        "type": "array",
+                        "description": "Line comments for review_pr",
+                        "items": {
+                            "type": "object",
+                            "properties": {
+                                "path": { "type": "string" },
+                                "line": { "type": "integer" },
+                                "body": { "type": "string" }
+                            },
+                            "required": ["path", "line", "body"]
+                        }
+                    },
+                    "base": { "type": "string", "description": "Target branch for open_pr" },
+                    "draft": { "type": "boolean" }
+                },
+                "required": ["action"]
+            }),
+        }
+    }
+
+    fn permission_level(&self) -> PermissionLevel {
+        PermissionLevel::Network
+    }
+}
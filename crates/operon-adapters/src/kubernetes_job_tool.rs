@@ -0,0 +1,389 @@
+use anyhow::{bail, Context, Result};
+use async_trait::async_trait;
+use operon_runtime::{PermissionLevel, Tool, ToolSchemaInfo};
+use serde_json::{json, Value};
+use std::process::Stdio;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::{Duration, Instant};
+use tokio::io::AsyncWriteExt;
+use tokio::process::Command;
+use tracing::{info, warn};
+
+/// Job manifest used when no template is configured. Placeholders are
+/// replaced with JSON values, which are valid in both JSON and YAML templates:
+/// `{{name}}`, `{{namespace}}`, `{{image}}`, `{{command}}` (array), `{{cpu}}`,
+/// `{{memory}}` and `{{active_deadline_secs}}` (number).
+pub const DEFAULT_JOB_TEMPLATE: &str = r#"{
+  "apiVersion": "batch/v1",
+  "kind": "Job",
+  "metadata": {
+    "name": {{name}},
+    "namespace": {{namespace}},
+    "labels": { "app.kubernetes.io/managed-by": "silentclaw" }
+  },
+  "spec": {
+    "backoffLimit": 0,
+    "activeDeadlineSeconds": {{active_deadline_secs}},
+    "ttlSecondsAfterFinished": 3600,
+    "template": {
+      "spec": {
+        "restartPolicy": "Never",
+        "containers": [{
+          "name": "job",
+          "image": {{image}},
+          "command": {{command}},
+          "resources": {
+            "requests": { "cpu": {{cpu}}, "memory": {{memory}} },
+            "limits": { "cpu": {{cpu}}, "memory": {{memory}} }
+          }
+        }]
+      }
+    }
+  }
+}"#;
+
+/// `value` as JSON that YAML reads the same way: characters YAML takes as
+/// line breaks or won't accept unescaped in a quoted string are escaped too
+fn scalar(value: &Value) -> String {
+    let mut out = String::new();
+    for c in value.to_string().chars() {
+        match c {
+            '\u{7f}'..='\u{9f}' | '\u{2028}' | '\u{2029}' | '\u{feff}' => {
+                out.push_str(&format!("\\u{:04x}", c as u32))
+            }
+            c => out.push(c),
+        }
+    }
+    out
+}
+
+/// Time `kubectl` calls get after the job deadline to fetch logs and clean up
+const WRAP_UP_MARGIN: Duration = Duration::from_secs(30);
+
+/// Distinguishes jobs started in the same second
+static JOB_COUNTER: AtomicU64 = AtomicU64::new(0);
+
+/// Submits a Kubernetes Job through `kubectl`, waits for it to finish (at most
+/// the configured timeout) and returns its logs. The namespace, template and
+/// resource limits come from configuration; the caller only picks the image
+/// and command.
+pub struct KubernetesJobTool {
+    namespace: String,
+    context: Option<String>,
+    kubectl: String,
+    template: String,
+    cpu: String,
+    memory: String,
+    timeout: Duration,
+    poll_interval: Duration,
+    max_log_bytes: usize,
+}
+
+impl KubernetesJobTool {
+    pub fn new(namespace: &str) -> Self {
+        Self {
+            namespace: namespace.to_string(),
+            context: None,
+            kubectl: "kubectl".to_string(),
+            template: DEFAULT_JOB_TEMPLATE.to_string(),
+            cpu: "500m".to_string(),
+            memory: "512Mi".to_string(),
+            timeout: Duration::from_secs(600),
+            poll_interval: Duration::from_secs(2),
+            max_log_bytes: 1024 * 1024,
+        }
+    }
+
+    /// kubeconfig context selecting the cluster (default: current context)
+    pub fn with_context(mut self, context: &str) -> Self {
+        self.context = Some(context.to_string());
+        self
+    }
+
+    /// kubectl binary to run (default `kubectl` on PATH)
+    pub fn with_kubectl(mut self, kubectl: &str) -> Self {
+        self.kubectl = kubectl.to_string();
+        self
+    }
+
+    /// Job manifest (JSON or YAML) with the placeholders of [`DEFAULT_JOB_TEMPLATE`]
+    pub fn with_template(mut self, template: String) -> Self {
+        self.template = template;
+        self
+    }
+
+    /// CPU and memory request/limit of the job container
+    pub fn with_resources(mut self, cpu: &str, memory: &str) -> Self {
+        self.cpu = cpu.to_string();
+        self.memory = memory.to_string();
+        self
+    }
+
+    /// Longest a job may run; calls may ask for less, never more
+    pub fn with_timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = timeout;
+        self
+    }
+
+    /// How often job status is checked while waiting
+    pub fn with_poll_interval(mut self, poll_interval: Duration) -> Self {
+        self.poll_interval = poll_interval;
+        self
+    }
+
+    /// Log bytes returned; earlier output is dropped past this
+    pub fn with_max_log_bytes(mut self, max_log_bytes: usize) -> Self {
+        self.max_log_bytes = max_log_bytes;
+        self
+    }
+
+    /// Longest a call can take; a runtime timeout for this tool should be at
+    /// least this, or it drops the call before the job is reported and cleaned up
+    pub fn max_duration(&self) -> Duration {
+        self.timeout + WRAP_UP_MARGIN
+    }
+
+    /// Manifest for one job, with every placeholder filled in. One pass over
+    /// the template, so a value that contains a placeholder is left as is.
+    pub fn render(
+        &self,
+        name: &str,
+        image: &str,
+        command: &[String],
+        deadline: Duration,
+    ) -> String {
+        let values = [
+            ("name", json!(name)),
+            ("namespace", json!(self.namespace)),
+            ("image", json!(image)),
+            ("command", json!(command)),
+            ("cpu", json!(self.cpu)),
+            ("memory", json!(self.memory)),
+            ("active_deadline_secs", json!(deadline.as_secs().max(1))),
+        ];
+        let mut manifest = String::with_capacity(self.template.len());
+        let mut rest = self.template.as_str();
+        while let Some(start) = rest.find("{{") {
+            manifest.push_str(&rest[..start]);
+            let after = &rest[start + 2..];
+            let value = after.find("}}").and_then(|end| {
+                values
+                    .iter()
+                    .find(|(key, _)| *key == &after[..end])
+                    .map(|(key, value)| (key.len(), value))
+            });
+            match value {
+                Some((len, value)) => {
+                    manifest.push_str(&scalar(value));
+                    rest = &after[len + 2..];
+                }
+                None => {
+                    manifest.push_str("{{");
+                    rest = after;
+                }
+            }
+        }
+        manifest.push_str(rest);
+        manifest
+    }
+
+    /// Run `kubectl <args>` against the configured context and namespace
+    async fn kubectl(&self, args: &[&str], stdin: Option<&str>) -> Result<String> {
+        let mut command = Command::new(&self.kubectl);
+        if let Some(context) = &self.context {
+            command.args(["--context", context]);
+        }
+        command
+            .args(["--namespace", &self.namespace])
+            .args(args)
+            .stdin(if stdin.is_some() {
+                Stdio::piped()
+            } else {
+                Stdio::null()
+            })
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .kill_on_drop(true);
+        let mut child = command
+            .spawn()
+            .context(format!("Failed to run '{}'", self.kubectl))?;
+        if let (Some(data), Some(mut pipe)) = (stdin, child.stdin.take()) {
+            pipe.write_all(data.as_bytes()).await?;
+        }
+        let output = child.wait_with_output().await?;
+        if !output.status.success() {
+            bail!(
+                "kubectl {} failed: {}",
+                args.first().unwrap_or(&""),
+                String::from_utf8_lossy(&output.stderr).trim()
+            );
+        }
+        Ok(String::from_utf8_lossy(&output.stdout).to_string())
+    }
+
+    /// "succeeded" or "failed" once the job has finished, else `None`
+    async fn job_state(&self, job: &str) -> Result<Option<&'static str>> {
+        let status: Value =
+            serde_json::from_str(&self.kubectl(&["get", job, "-o", "json"], None).await?)
+                .context("kubectl returned invalid job JSON")?;
+        let conditions = status["status"]["conditions"].as_array();
+        let finished = |kind: &str| {
+            conditions.is_some_and(|conditions| {
+                conditions
+                    .iter()
+                    .any(|c| c["type"] == kind && c["status"] == "True")
+            })
+        };
+        Ok(if finished("Complete") {
+            Some("succeeded")
+        } else if finished("Failed") {
+            Some("failed")
+        } else {
+            None
+        })
+    }
+
+    /// Job logs, keeping the last `max_log_bytes`
+    async fn logs(&self, job: &str) -> (String, bool) {
+        match self.kubectl(&["logs", job, "--all-containers"], None).await {
+            Ok(logs) if logs.len() > self.max_log_bytes => {
+                let mut start = logs.len() - self.max_log_bytes;
+                while !logs.is_char_boundary(start) {
+                    start += 1;
+                }
+                (logs[start..].to_string(), true)
+            }
+            Ok(logs) => (logs, false),
+            Err(e) => (format!("[logs unavailable: {:#}]", e), false),
+        }
+    }
+}
+
+#[async_trait]
+impl Tool for KubernetesJobTool {
+    async fn execute(&self, input: Value) -> Result<Value> {
+        let image = input["image"]
+            .as_str()
+            .context("Missing required field 'image'")?;
+        let command: Vec<String> = match &input["command"] {
+            Value::String(cmd) => vec!["sh".into(), "-c".into(), cmd.clone()],
+            Value::Array(args) => args
+                .iter()
+                .map(|a| a.as_str().map(str::to_string))
+                .collect::<Option<_>>()
+                .context("'command' array must contain only strings")?,
+            Value::Null => Vec::new(),
+            _ => bail!("'command' must be a string or an array of strings"),
+        };
+        let timeout = input["timeout_secs"]
+            .as_u64()
+            .map(|secs| Duration::from_secs(secs).min(self.timeout))
+            .unwrap_or(self.timeout);
+
+        let name = job_name(input["name"].as_str().unwrap_or("job"));
+        let job = format!("job/{}", name);
+        let manifest = self.render(&name, image, &command, timeout);
+
+        info!(job = %name, namespace = %self.namespace, image, "Submitting Kubernetes job");
+        let started = Instant::now();
+        self.kubectl(&["create", "-f", "-"], Some(&manifest))
+            .await?;
+
+        let state = loop {
+            if let Some(state) = self.job_state(&job).await? {
+                break state;
+            }
+            if started.elapsed() >= timeout {
+                warn!(job = %name, timeout_secs = timeout.as_secs(), "Kubernetes job timed out, deleting");
+                break "timed_out";
+            }
+            tokio::time::sleep(self.poll_interval).await;
+        };
+
+        let (logs, truncated) = self.logs(&job).await;
+        if state == "timed_out" {
+            if let Err(e) = self
+                .kubectl(
+                    &["delete", &job, "--ignore-not-found", "--wait=false"],
+                    None,
+                )
+                .await
+            {
+                warn!(job = %name, error = %e, "Failed to delete timed-out job");
+            }
+        }
+
+        Ok(json!({
+            "job": name,
+            "namespace": self.namespace,
+            "status": state,
+            "logs": logs,
+            "logs_truncated": truncated,
+            "duration_ms": started.elapsed().as_millis() as u64,
+        }))
+    }
+
+    fn name(&self) -> &str {
+        "kubernetes_job"
+    }
+
+    fn schema(&self) -> ToolSchemaInfo {
+        ToolSchemaInfo {
+            name: "kubernetes_job".to_string(),
+            description: format!(
+                "Run a batch job in Kubernetes namespace {}, wait for it and return its logs",
+                self.namespace
+            ),
+            parameters: json!({
+                "type": "object",
+                "properties": {
+                    "image": {
+                        "type": "string",
+                        "description": "Container image to run"
+                    },
+                    "command": {
+                        "description": "Command as an argument array, or a string run with sh -c (default: the image's entrypoint)",
+                        "oneOf": [
+                            { "type": "string" },
+                            { "type": "array", "items": { "type": "string" } }
+                        ]
+                    },
+                    "name": {
+                        "type": "string",
+                        "description": "Job name prefix"
+                    },
+                    "timeout_secs": {
+                        "type": "integer",
+                        "description": "Seconds to wait before the job is stopped (capped by configuration)"
+                    }
+                },
+                "required": ["image"]
+            }),
+        }
+    }
+
+    fn permission_level(&self) -> PermissionLevel {
+        PermissionLevel::Network
+    }
+}
+
+/// Unique DNS-1123 job name starting with `prefix`
+fn job_name(prefix: &str) -> String {
+    let prefix: String = prefix
+        .to_lowercase()
+        .chars()
+        .map(|c| if c.is_ascii_alphanumeric() { c } else { '-' })
+        .take(30)
+        .collect();
+    let prefix = prefix.trim_matches('-');
+    let secs = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or(0);
+    format!(
+        "{}-{}-{}",
+        if prefix.is_empty() { "job" } else { prefix },
+        secs,
+        JOB_COUNTER.fetch_add(1, Ordering::Relaxed)
+    )
+}
@@ -1,48 +1,133 @@
+pub mod agent_tool;
 pub mod apply_patch_tool;
+pub mod attachment;
+pub mod container_backend;
 pub mod diff_parser;
 pub mod edit_file_tool;
+pub mod email_tool;
+pub mod github_tool;
+pub mod kubernetes_job_tool;
 pub mod memory_search_tool;
+pub mod notify;
 pub mod python_adapter;
 pub mod read_file_tool;
+pub mod screenshot_tool;
+pub mod shell_sandbox;
 pub mod shell_tool;
+pub mod ssh_tool;
 pub mod workspace_guard;
+pub mod workspace_snapshot;
 pub mod write_file_tool;
 
+pub use agent_tool::AgentTool;
 pub use apply_patch_tool::ApplyPatchTool;
+pub use attachment::load_image;
+pub use container_backend::ContainerBackend;
 pub use edit_file_tool::EditFileTool;
+pub use email_tool::{EmailTool, SmtpSecurity};
+pub use github_tool::GitHubTool;
+pub use kubernetes_job_tool::KubernetesJobTool;
 pub use memory_search_tool::MemorySearchTool;
+pub use notify::{NotificationHook, Notifier, NotifyTool, WebhookKind};
 pub use python_adapter::PyAdapter;
-pub use read_file_tool::ReadFileTool;
+pub use read_file_tool::{ReadFileTool, ReadFilesTool};
+pub use screenshot_tool::{CaptureTarget, ScreenshotTool};
+pub use shell_sandbox::{SandboxProgram, ShellSandbox};
 pub use shell_tool::ShellTool;
-pub use workspace_guard::WorkspaceGuard;
+pub use ssh_tool::{SshReadFileTool, SshShellTool, SshTarget, SshWriteFileTool};
+pub use workspace_guard::{WorkspaceGuard, WriteCharge, WriteLimits};
+pub use workspace_snapshot::WorkspaceSnapshot;
 pub use write_file_tool::WriteFileTool;
 
 use anyhow::Result;
 use operon_runtime::Runtime;
 use std::path::PathBuf;
 use std::sync::Arc;
+use std::time::Duration;
 
-/// Register shell tool on the runtime if enabled.
+/// Register the shell tool on the runtime. Commands running longer than
+/// `timeout` get SIGTERM, then SIGKILL after `grace_period`.
 pub fn register_shell_tool(
     runtime: &Runtime,
     dry_run: bool,
     blocklist: Vec<String>,
     allowlist: Vec<String>,
+    sandbox: Option<ShellSandbox>,
+    timeout: Duration,
+    grace_period: Duration,
 ) -> Result<()> {
-    let shell_tool = ShellTool::new(dry_run).with_validation(blocklist, allowlist);
+    let mut shell_tool = ShellTool::new(dry_run)
+        .with_validation(blocklist, allowlist)
+        .with_timeout(timeout)
+        .with_grace_period(grace_period);
+    if let Some(sandbox) = sandbox {
+        shell_tool = shell_tool.with_sandbox(sandbox);
+    }
+
+    // The tool stops timed-out commands itself and reports their partial
+    // output; the runtime timeout is only a backstop past that
+    if let Some(max_duration) = shell_tool.max_duration() {
+        runtime.configure_timeout("shell".to_string(), max_duration);
+    }
+
     runtime.register_tool("shell".to_string(), Arc::new(shell_tool))
 }
 
-/// Register all filesystem tools (read, write, edit, patch) on the runtime.
+/// Register the remote tools (ssh_shell, ssh_read_file, ssh_write_file) for `target`.
+/// Remote commands go through the same blocklist/allowlist as the local shell.
+pub fn register_ssh_tools(
+    runtime: &Runtime,
+    target: SshTarget,
+    dry_run: bool,
+    blocklist: Vec<String>,
+    allowlist: Vec<String>,
+) -> Result<()> {
+    let target = Arc::new(target);
+    let shell = SshShellTool::new(target.clone(), dry_run).with_validation(blocklist, allowlist);
+    runtime.register_tool("ssh_shell".into(), Arc::new(shell))?;
+    runtime.register_tool(
+        "ssh_read_file".into(),
+        Arc::new(SshReadFileTool::new(target.clone())),
+    )?;
+    runtime.register_tool(
+        "ssh_write_file".into(),
+        Arc::new(SshWriteFileTool::new(target)),
+    )?;
+    Ok(())
+}
+
+/// Register all filesystem tools (read, batch read, write, edit, patch) on the runtime.
+/// With a snapshot, every file is saved before the tools first change it.
+/// `write_limits` caps each written file and the total bytes the tools may write.
 pub fn register_filesystem_tools(
     runtime: &Runtime,
     workspace: PathBuf,
     max_file_size_mb: u64,
+    write_limits: WriteLimits,
+    snapshot: Option<Arc<WorkspaceSnapshot>>,
 ) -> Result<()> {
-    let guard = Arc::new(WorkspaceGuard::new(workspace, max_file_size_mb)?);
-    runtime.register_tool("read_file".into(), Arc::new(ReadFileTool::new(guard.clone())))?;
-    runtime.register_tool("write_file".into(), Arc::new(WriteFileTool::new(guard.clone())))?;
-    runtime.register_tool("edit_file".into(), Arc::new(EditFileTool::new(guard.clone())))?;
+    let mut guard =
+        WorkspaceGuard::new(workspace, max_file_size_mb)?.with_write_limits(write_limits);
+    if let Some(snapshot) = snapshot {
+        guard = guard.with_snapshot(snapshot);
+    }
+    let guard = Arc::new(guard);
+    runtime.register_tool(
+        "read_file".into(),
+        Arc::new(ReadFileTool::new(guard.clone())),
+    )?;
+    runtime.register_tool(
+        "read_files".into(),
+        Arc::new(ReadFilesTool::new(guard.clone())),
+    )?;
+    runtime.register_tool(
+        "write_file".into(),
+        Arc::new(WriteFileTool::new(guard.clone())),
+    )?;
+    runtime.register_tool(
+        "edit_file".into(),
+        Arc::new(EditFileTool::new(guard.clone())),
+    )?;
     runtime.register_tool("apply_patch".into(), Arc::new(ApplyPatchTool::new(guard)))?;
     Ok(())
 }
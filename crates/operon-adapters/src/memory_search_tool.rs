@@ -1,6 +1,6 @@
 use anyhow::{Context, Result};
 use async_trait::async_trait;
-use operon_runtime::memory::types::{SearchQuery, SearchSource};
+use operon_runtime::memory::types::{MergeStrategy, SearchQuery, SearchSource};
 use operon_runtime::memory::MemoryManager;
 use operon_runtime::{PermissionLevel, Tool, ToolSchemaInfo};
 use serde_json::{json, Value};
@@ -29,11 +29,43 @@ impl Tool for MemorySearchTool {
             "fts" => SearchSource::FullText,
             _ => SearchSource::Hybrid,
         };
+        let path_glob = input["path_glob"]
+            .as_str()
+            .filter(|glob| !glob.is_empty())
+            .map(str::to_string);
+        let extensions = match &input["extensions"] {
+            Value::Array(items) => items
+                .iter()
+                .filter_map(Value::as_str)
+                .map(str::to_string)
+                .collect(),
+            Value::String(ext) => ext.split(',').map(|e| e.trim().to_string()).collect(),
+            _ => Vec::new(),
+        };
+        let vector_weight = input["vector_weight"]
+            .as_f64()
+            .unwrap_or(0.5)
+            .clamp(0.0, 1.0);
+        let fts_weight = 1.0 - vector_weight;
+        let merge = match input["merge"].as_str().unwrap_or("rrf") {
+            "min_max" => MergeStrategy::MinMax {
+                vector_weight,
+                fts_weight,
+            },
+            "z_score" => MergeStrategy::ZScore {
+                vector_weight,
+                fts_weight,
+            },
+            _ => MergeStrategy::Rrf,
+        };
 
         let query = SearchQuery {
             query: query_str.to_string(),
             limit,
             source,
+            path_glob,
+            extensions,
+            merge,
         };
 
         let results = self.manager.search(query).await?;
@@ -63,7 +95,8 @@ impl Tool for MemorySearchTool {
     fn schema(&self) -> ToolSchemaInfo {
         ToolSchemaInfo {
             name: "memory_search".to_string(),
-            description: "Search workspace files using hybrid vector + full-text search".to_string(),
+            description: "Search workspace files using hybrid vector + full-text search"
+                .to_string(),
             parameters: json!({
                 "type": "object",
                 "properties": {
@@ -73,6 +106,24 @@ impl Tool for MemorySearchTool {
                         "type": "string",
                         "enum": ["hybrid", "vector", "fts"],
                         "description": "Search mode (default: hybrid)"
+                    },
+                    "path_glob": {
+                        "type": "string",
+                        "description": "Only files whose workspace-relative path matches this glob, e.g. 'src/**/*.rs'; a pattern without '/' matches file names"
+                    },
+                    "extensions": {
+                        "type": "array",
+                        "items": { "type": "string" },
+                        "description": "Only files with one of these extensions, e.g. [\"rs\", \"md\"]"
+                    },
+                    "merge": {
+                        "type": "string",
+                        "enum": ["rrf", "min_max", "z_score"],
+                        "description": "How hybrid search combines results: rank fusion (default) or normalized, weighted scores"
+                    },
+                    "vector_weight": {
+                        "type": "number",
+                        "description": "Weight of vector scores for min_max/z_score, 0..1 (default: 0.5); full-text gets the rest"
                     }
                 },
                 "required": ["query"]
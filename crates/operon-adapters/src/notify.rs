@@ -0,0 +1,224 @@
+use anyhow::{bail, Context, Result};
+use async_trait::async_trait;
+use operon_runtime::{
+    Hook, HookContext, HookEvent, HookResult, PermissionLevel, Tool, ToolSchemaInfo,
+};
+use serde_json::{json, Value};
+use std::sync::Arc;
+use std::time::Duration;
+use tracing::info;
+
+/// Longest session result quoted in a summary
+const MAX_RESULT_CHARS: usize = 500;
+
+/// Chat service a webhook posts to; decides the payload shape
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum WebhookKind {
+    Slack,
+    Discord,
+}
+
+impl std::str::FromStr for WebhookKind {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s.to_lowercase().as_str() {
+            "slack" => Ok(WebhookKind::Slack),
+            "discord" => Ok(WebhookKind::Discord),
+            other => bail!(
+                "Unknown webhook kind '{}' (expected slack or discord)",
+                other
+            ),
+        }
+    }
+}
+
+/// Posts plain-text messages to a Slack or Discord incoming webhook
+pub struct Notifier {
+    kind: WebhookKind,
+    url: String,
+    client: reqwest::Client,
+}
+
+impl Notifier {
+    pub fn new(kind: WebhookKind, url: &str) -> Self {
+        Self {
+            kind,
+            url: url.to_string(),
+            client: reqwest::Client::new(),
+        }
+    }
+
+    /// JSON body the webhook expects for `text`
+    pub fn payload(&self, text: &str) -> Value {
+        match self.kind {
+            WebhookKind::Slack => json!({ "text": text }),
+            WebhookKind::Discord => json!({ "content": text }),
+        }
+    }
+
+    pub async fn send(&self, text: &str) -> Result<()> {
+        let response = self
+            .client
+            .post(&self.url)
+            .timeout(Duration::from_secs(10))
+            .json(&self.payload(text))
+            .send()
+            .await
+            .context("Failed to reach notification webhook")?;
+        if !response.status().is_success() {
+            bail!("Notification webhook returned {}", response.status());
+        }
+        Ok(())
+    }
+}
+
+/// Lets agents and plans post a message to the configured channel
+pub struct NotifyTool {
+    notifier: Arc<Notifier>,
+}
+
+impl NotifyTool {
+    pub fn new(notifier: Arc<Notifier>) -> Self {
+        Self { notifier }
+    }
+}
+
+#[async_trait]
+impl Tool for NotifyTool {
+    async fn execute(&self, input: Value) -> Result<Value> {
+        let message = input["message"]
+            .as_str()
+            .context("Missing required field 'message'")?;
+        if message.trim().is_empty() {
+            bail!("'message' must not be empty");
+        }
+        self.notifier.send(message).await?;
+        Ok(json!({ "sent": true }))
+    }
+
+    fn name(&self) -> &str {
+        "notify"
+    }
+
+    fn schema(&self) -> ToolSchemaInfo {
+        ToolSchemaInfo {
+            name: "notify".to_string(),
+            description: "Post a message to the team's notification channel".to_string(),
+            parameters: json!({
+                "type": "object",
+                "properties": {
+                    "message": {
+                        "type": "string",
+                        "description": "Message text"
+                    }
+                },
+                "required": ["message"]
+            }),
+        }
+    }
+
+    fn permission_level(&self) -> PermissionLevel {
+        PermissionLevel::Network
+    }
+}
+
+/// Posts a summary (result, duration, token cost) when a session or plan ends
+pub struct NotificationHook {
+    notifier: Arc<Notifier>,
+    events: Vec<HookEvent>,
+}
+
+impl NotificationHook {
+    /// Report the given events; only `SessionEnd` and `PlanEnd` are summarized
+    pub fn new(notifier: Arc<Notifier>, events: Vec<HookEvent>) -> Self {
+        Self { notifier, events }
+    }
+}
+
+#[async_trait]
+impl Hook for NotificationHook {
+    fn name(&self) -> &str {
+        "notification"
+    }
+
+    fn events(&self) -> &[HookEvent] {
+        &self.events
+    }
+
+    async fn on_event(&self, ctx: &HookContext) -> Result<HookResult> {
+        if let Some(text) = summary(ctx) {
+            self.notifier.send(&text).await?;
+            info!(event = ?ctx.event, "Posted notification");
+        }
+        Ok(HookResult::default())
+    }
+
+    fn timeout(&self) -> Duration {
+        Duration::from_secs(15)
+    }
+}
+
+/// Message for a `SessionEnd` or `PlanEnd` event
+pub fn summary(ctx: &HookContext) -> Option<String> {
+    let data = &ctx.data;
+    let duration = format_duration(data["duration_ms"].as_u64().unwrap_or(0));
+    match ctx.event {
+        HookEvent::PlanEnd => {
+            let plan_id = data["plan_id"].as_str().unwrap_or("unknown");
+            let dry_run = if data["dry_run"] == true {
+                " (dry-run)"
+            } else {
+                ""
+            };
+            Some(match data["error"].as_str() {
+                Some(error) => format!(
+                    "Plan `{}` failed after {}{}: {}",
+                    plan_id, duration, dry_run, error
+                ),
+                None => format!(
+                    "Plan `{}` succeeded in {}{}, {} steps",
+                    plan_id,
+                    duration,
+                    dry_run,
+                    data["steps"].as_u64().unwrap_or(0)
+                ),
+            })
+        }
+        HookEvent::SessionEnd => {
+            let usage = &data["usage"];
+            let input_tokens = usage["input_tokens"].as_u64().unwrap_or(0);
+            let output_tokens = usage["output_tokens"].as_u64().unwrap_or(0);
+            let mut text = format!(
+                "Session `{}` ({}) ended after {}: {} messages, {} tokens ({} in / {} out)",
+                ctx.session_id.as_deref().unwrap_or("unknown"),
+                data["agent"].as_str().unwrap_or("default"),
+                duration,
+                data["messages"].as_u64().unwrap_or(0),
+                input_tokens + output_tokens,
+                input_tokens,
+                output_tokens
+            );
+            let result = data["result"].as_str().unwrap_or("").trim();
+            if !result.is_empty() {
+                let mut quoted: String = result.chars().take(MAX_RESULT_CHARS).collect();
+                if quoted.len() < result.len() {
+                    quoted.push('…');
+                }
+                text.push_str("\n> ");
+                text.push_str(&quoted.replace('\n', "\n> "));
+            }
+            Some(text)
+        }
+        _ => None,
+    }
+}
+
+fn format_duration(ms: u64) -> String {
+    let secs = ms / 1000;
+    match secs {
+        0..=59 => format!("{:.1}s", ms as f64 / 1000.0),
+        60..=3599 => format!("{}m {}s", secs / 60, secs % 60),
+        _ => format!("{}h {}m", secs / 3600, secs % 3600 / 60),
+    }
+}
@@ -24,75 +24,158 @@ impl Tool for ReadFileTool {
             .context("Missing required field 'path'")?;
         let offset = input["offset"].as_u64().unwrap_or(0) as usize;
         let limit = input["limit"].as_u64().unwrap_or(0) as usize;
+        read_lines(&self.guard, path_str, offset, limit).await
+    }
 
-        let path = self.guard.resolve(path_str)?;
+    fn name(&self) -> &str {
+        "read_file"
+    }
 
-        if !path.exists() {
-            bail!("File not found: {}", path_str);
+    fn schema(&self) -> ToolSchemaInfo {
+        ToolSchemaInfo {
+            name: "read_file".to_string(),
+            description: "Read a file with optional line offset and limit".to_string(),
+            parameters: json!({
+                "type": "object",
+                "properties": {
+                    "path": { "type": "string", "description": "File path relative to workspace" },
+                    "offset": { "type": "integer", "description": "Line offset (0-based)" },
+                    "limit": { "type": "integer", "description": "Max lines to read (0 = all)" }
+                },
+                "required": ["path"]
+            }),
         }
+    }
+
+    fn permission_level(&self) -> PermissionLevel {
+        PermissionLevel::Read
+    }
+}
 
-        self.guard.check_size(&path).await?;
-
-        // Read once, check binary inline (avoids double read)
-        let bytes = tokio::fs::read(&path)
-            .await
-            .context("Failed to read file")?;
-        if bytes.is_empty() {
-            return Ok(json!({
-                "content": "",
-                "total_lines": 0,
-                "lines_shown": 0,
-                "offset": 0,
-            }));
+/// Default combined size cap of one `read_files` call
+const DEFAULT_BATCH_BYTES: usize = 100 * 1024;
+/// Upper bound for a caller-supplied `max_bytes`
+const MAX_BATCH_BYTES: usize = 1024 * 1024;
+/// Most files one `read_files` call accepts
+const MAX_BATCH_FILES: usize = 50;
+
+/// Reads several files (or line ranges) in one call, under a combined size cap
+pub struct ReadFilesTool {
+    guard: Arc<WorkspaceGuard>,
+}
+
+impl ReadFilesTool {
+    pub fn new(guard: Arc<WorkspaceGuard>) -> Self {
+        Self { guard }
+    }
+}
+
+#[async_trait]
+impl Tool for ReadFilesTool {
+    async fn execute(&self, input: Value) -> Result<Value> {
+        let requests = input["files"]
+            .as_array()
+            .context("Missing required field 'files'")?;
+        if requests.len() > MAX_BATCH_FILES {
+            bail!(
+                "Too many files: {} (at most {} per call)",
+                requests.len(),
+                MAX_BATCH_FILES
+            );
         }
-        let check_len = bytes.len().min(8192);
-        if bytes[..check_len].contains(&0) {
-            bail!("Binary file detected, cannot read: {}", path_str);
+        let max_bytes = input["max_bytes"]
+            .as_u64()
+            .map_or(DEFAULT_BATCH_BYTES, |n| n as usize)
+            .min(MAX_BATCH_BYTES);
+
+        let mut remaining = max_bytes;
+        let mut total_bytes = 0;
+        let mut truncated = false;
+        let mut files = Vec::with_capacity(requests.len());
+        for request in requests {
+            // Either "path" or {"path", "offset"?, "limit"?}
+            let (path_str, offset, limit) = match request {
+                Value::String(path) => (path.as_str(), 0, 0),
+                _ => (
+                    request["path"]
+                        .as_str()
+                        .context("Each entry in 'files' needs a 'path'")?,
+                    request["offset"].as_u64().unwrap_or(0) as usize,
+                    request["limit"].as_u64().unwrap_or(0) as usize,
+                ),
+            };
+            if remaining == 0 {
+                truncated = true;
+                files.push(json!({
+                    "path": path_str,
+                    "error": format!("Skipped: combined size cap of {} bytes reached", max_bytes),
+                }));
+                continue;
+            }
+            // One unreadable file doesn't fail the batch
+            let mut result = match read_lines(&self.guard, path_str, offset, limit).await {
+                Ok(result) => result,
+                Err(e) => {
+                    files.push(json!({"path": path_str, "error": e.to_string()}));
+                    continue;
+                }
+            };
+            let cut = fit_to_budget(&mut result, remaining);
+            let size = result["content"].as_str().map_or(0, str::len);
+            remaining -= size;
+            total_bytes += size;
+            if cut {
+                // Later files would only get fragments; skip them instead
+                truncated = true;
+                remaining = 0;
+            }
+            result["path"] = json!(path_str);
+            files.push(result);
         }
-        let content = String::from_utf8(bytes).context("File is not valid UTF-8")?;
-
-        let lines: Vec<&str> = content.lines().collect();
-        let total_lines = lines.len();
-
-        // Apply offset and limit
-        let start = offset.min(total_lines);
-        let end = if limit > 0 {
-            (start + limit).min(total_lines)
-        } else {
-            total_lines
-        };
-
-        // Format with line numbers (cat -n style)
-        let numbered: Vec<String> = lines[start..end]
-            .iter()
-            .enumerate()
-            .map(|(i, line)| format!("{:>6}\t{}", start + i + 1, line))
-            .collect();
 
         Ok(json!({
-            "content": numbered.join("\n"),
-            "total_lines": total_lines,
-            "lines_shown": end - start,
-            "offset": start,
+            "files": files,
+            "total_bytes": total_bytes,
+            "truncated": truncated,
         }))
     }
 
     fn name(&self) -> &str {
-        "read_file"
+        "read_files"
     }
 
     fn schema(&self) -> ToolSchemaInfo {
         ToolSchemaInfo {
-            name: "read_file".to_string(),
-            description: "Read a file with optional line offset and limit".to_string(),
+            name: "read_files".to_string(),
+            description: "Read several files or line ranges in one call, with a combined size cap"
+                .to_string(),
             parameters: json!({
                 "type": "object",
                 "properties": {
-                    "path": { "type": "string", "description": "File path relative to workspace" },
-                    "offset": { "type": "integer", "description": "Line offset (0-based)" },
-                    "limit": { "type": "integer", "description": "Max lines to read (0 = all)" }
+                    "files": {
+                        "type": "array",
+                        "description": "Paths relative to workspace, or objects with a line range",
+                        "items": {
+                            "oneOf": [
+                                { "type": "string" },
+                                {
+                                    "type": "object",
+                                    "properties": {
+                                        "path": { "type": "string" },
+                                        "offset": { "type": "integer", "description": "Line offset (0-based)" },
+                                        "limit": { "type": "integer", "description": "Max lines to read (0 = all)" }
+                                    },
+                                    "required": ["path"]
+                                }
+                            ]
+                        }
+                    },
+                    "max_bytes": {
+                        "type": "integer",
+                        "description": "Combined content size cap (default: 102400, max: 1048576); later lines and files are cut off"
+                    }
                 },
-                "required": ["path"]
+                "required": ["files"]
             }),
         }
     }
@@ -101,3 +184,85 @@ impl Tool for ReadFileTool {
         PermissionLevel::Read
     }
 }
+
+/// Cut a `read_lines` result down to whole lines totalling at most `budget`
+/// bytes. Returns true if anything was cut.
+fn fit_to_budget(result: &mut Value, budget: usize) -> bool {
+    let content = result["content"].as_str().unwrap_or_default().to_string();
+    if content.len() <= budget {
+        return false;
+    }
+    let mut kept = 0;
+    let mut lines = 0;
+    for line in content.split('\n') {
+        let len = line.len() + usize::from(lines > 0);
+        if kept + len > budget {
+            break;
+        }
+        kept += len;
+        lines += 1;
+    }
+    result["content"] = json!(content[..kept]);
+    result["lines_shown"] = json!(lines);
+    result["truncated"] = json!(true);
+    true
+}
+
+/// Read `limit` lines (0 = all) from `offset`, numbered cat -n style
+async fn read_lines(
+    guard: &WorkspaceGuard,
+    path_str: &str,
+    offset: usize,
+    limit: usize,
+) -> Result<Value> {
+    let path = guard.resolve(path_str)?;
+
+    if !path.exists() {
+        bail!("File not found: {}", path_str);
+    }
+
+    guard.check_size(&path).await?;
+
+    // Read once, check binary inline (avoids double read)
+    let bytes = tokio::fs::read(&path)
+        .await
+        .context("Failed to read file")?;
+    if bytes.is_empty() {
+        return Ok(json!({
+            "content": "",
+            "total_lines": 0,
+            "lines_shown": 0,
+            "offset": 0,
+        }));
+    }
+    let check_len = bytes.len().min(8192);
+    if bytes[..check_len].contains(&0) {
+        bail!("Binary file detected, cannot read: {}", path_str);
+    }
+    let content = String::from_utf8(bytes).context("File is not valid UTF-8")?;
+
+    let lines: Vec<&str> = content.lines().collect();
+    let total_lines = lines.len();
+
+    // Apply offset and limit
+    let start = offset.min(total_lines);
+    let end = if limit > 0 {
+        (start + limit).min(total_lines)
+    } else {
+        total_lines
+    };
+
+    // Format with line numbers (cat -n style)
+    let numbered: Vec<String> = lines[start..end]
+        .iter()
+        .enumerate()
+        .map(|(i, line)| format!("{:>6}\t{}", start + i + 1, line))
+        .collect();
+
+    Ok(json!({
+        "content": numbered.join("\n"),
+        "total_lines": total_lines,
+        "lines_shown": end - start,
+        "offset": start,
+    }))
+}
@@ -0,0 +1,209 @@
+use crate::attachment::image_mime;
+use anyhow::{bail, Context, Result};
+use async_trait::async_trait;
+use base64::Engine;
+use operon_runtime::{PermissionLevel, Tool, ToolSchemaInfo};
+use serde_json::{json, Value};
+use std::time::Duration;
+use tokio::process::Command;
+use tracing::info;
+
+/// Largest image most vision APIs accept inline
+pub const DEFAULT_MAX_IMAGE_BYTES: usize = 5 * 1024 * 1024;
+
+/// What a [`ScreenshotTool`] captures
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CaptureTarget {
+    Screen,
+    Window,
+}
+
+/// Captures the screen (capture_screen) or one window (capture_window) with a
+/// platform screenshot command and returns the image for vision-capable
+/// models. The command is an argument list where `{output}` is the file to
+/// write and `{window}` the requested window id.
+pub struct ScreenshotTool {
+    target: CaptureTarget,
+    command: Vec<String>,
+    timeout: Duration,
+    max_bytes: usize,
+}
+
+impl ScreenshotTool {
+    pub fn new(target: CaptureTarget) -> Self {
+        Self {
+            target,
+            command: default_command(target),
+            timeout: Duration::from_secs(30),
+            max_bytes: DEFAULT_MAX_IMAGE_BYTES,
+        }
+    }
+
+    /// Replace the platform default capture command
+    pub fn with_command(mut self, command: Vec<String>) -> Self {
+        self.command = command;
+        self
+    }
+
+    pub fn with_timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = timeout;
+        self
+    }
+
+    pub fn with_max_bytes(mut self, max_bytes: usize) -> Self {
+        self.max_bytes = max_bytes;
+        self
+    }
+
+    /// Tool name for the target
+    pub fn tool_name(&self) -> &'static str {
+        match self.target {
+            CaptureTarget::Screen => "capture_screen",
+            CaptureTarget::Window => "capture_window",
+        }
+    }
+
+    async fn capture(&self, window: Option<&str>) -> Result<Vec<u8>> {
+        let (program, args) = self
+            .command
+            .split_first()
+            .context("Screenshot command is empty")?;
+        let file = tempfile::Builder::new()
+            .prefix("silentclaw-capture-")
+            .suffix(".png")
+            .tempfile()
+            .context("Failed to create screenshot file")?;
+        let output_path = file.path().to_string_lossy().to_string();
+        let args: Vec<String> = args
+            .iter()
+            .map(|arg| {
+                arg.replace("{output}", &output_path)
+                    .replace("{window}", window.unwrap_or_default())
+            })
+            .collect();
+
+        let output = tokio::time::timeout(
+            self.timeout,
+            Command::new(program)
+                .args(&args)
+                .kill_on_drop(true)
+                .output(),
+        )
+        .await
+        .map_err(|_| anyhow::anyhow!("Screenshot command timed out after {:?}", self.timeout))?
+        .with_context(|| format!("Failed to run screenshot command '{}'", program))?;
+        if !output.status.success() {
+            bail!(
+                "Screenshot command '{}' failed: {}",
+                program,
+                String::from_utf8_lossy(&output.stderr).trim()
+            );
+        }
+
+        let data = tokio::fs::read(file.path())
+            .await
+            .context("Failed to read screenshot")?;
+        if data.is_empty() {
+            bail!("Screenshot command '{}' produced no image", program);
+        }
+        if data.len() > self.max_bytes {
+            bail!(
+                "Screenshot is {} bytes, above the {} byte limit",
+                data.len(),
+                self.max_bytes
+            );
+        }
+        Ok(data)
+    }
+}
+
+#[async_trait]
+impl Tool for ScreenshotTool {
+    async fn execute(&self, input: Value) -> Result<Value> {
+        let window = match self.target {
+            CaptureTarget::Screen => None,
+            CaptureTarget::Window => {
+                let window = input["window"]
+                    .as_str()
+                    .map(str::trim)
+                    .filter(|w| !w.is_empty())
+                    .context("Missing 'window' field")?;
+                // Passed as a command argument; never let it read as an option
+                if window.starts_with('-') {
+                    bail!("Invalid window id '{}'", window);
+                }
+                Some(window)
+            }
+        };
+
+        let data = self.capture(window).await?;
+        let mime = image_mime(&data).context("Screenshot command did not write a PNG or JPEG")?;
+        info!(
+            tool = self.tool_name(),
+            bytes = data.len(),
+            "Captured screenshot"
+        );
+        Ok(json!({
+            "image": {
+                "mime": mime,
+                "data": base64::engine::general_purpose::STANDARD.encode(&data),
+            },
+            "bytes": data.len(),
+            "target": window.map_or("screen".to_string(), |w| format!("window {}", w)),
+        }))
+    }
+
+    fn name(&self) -> &str {
+        self.tool_name()
+    }
+
+    fn schema(&self) -> ToolSchemaInfo {
+        match self.target {
+            CaptureTarget::Screen => ToolSchemaInfo {
+                name: "capture_screen".to_string(),
+                description: "Take a screenshot of the whole screen and look at it".to_string(),
+                parameters: json!({
+                    "type": "object",
+                    "properties": {}
+                }),
+            },
+            CaptureTarget::Window => ToolSchemaInfo {
+                name: "capture_window".to_string(),
+                description: "Take a screenshot of one window and look at it".to_string(),
+                parameters: json!({
+                    "type": "object",
+                    "properties": {
+                        "window": {
+                            "type": "string",
+                            "description": "Window id (X11 window id or name on Linux, CGWindowID on macOS)"
+                        }
+                    },
+                    "required": ["window"]
+                }),
+            },
+        }
+    }
+
+    fn permission_level(&self) -> PermissionLevel {
+        PermissionLevel::Admin
+    }
+}
+
+/// `screencapture` on macOS, ImageMagick `import` elsewhere
+fn default_command(target: CaptureTarget) -> Vec<String> {
+    let command: &[&str] = match (cfg!(target_os = "macos"), target) {
+        (true, CaptureTarget::Screen) => &["screencapture", "-x", "-t", "png", "{output}"],
+        (true, CaptureTarget::Window) => &[
+            "screencapture",
+            "-x",
+            "-t",
+            "png",
+            "-l",
+            "{window}",
+            "{output}",
+        ],
+        (false, CaptureTarget::Screen) => &["import", "-window", "root", "{output}"],
+        (false, CaptureTarget::Window) => &["import", "-window", "{window}", "{output}"],
+    };
+    command.iter().map(|s| s.to_string()).collect()
+}
@@ -0,0 +1,143 @@
+use anyhow::{bail, Context, Result};
+use operon_runtime::PermissionLevel;
+use std::ffi::OsString;
+use std::path::{Path, PathBuf};
+
+/// System directories mounted read-only so ordinary commands still work
+const SYSTEM_DIRS: &[&str] = &["/usr", "/bin", "/sbin", "/lib", "/lib64", "/etc"];
+
+/// Program that confines sandboxed shell commands
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SandboxProgram {
+    Bubblewrap,
+    Firejail,
+}
+
+impl std::str::FromStr for SandboxProgram {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s.to_lowercase().as_str() {
+            "bubblewrap" | "bwrap" => Ok(SandboxProgram::Bubblewrap),
+            "firejail" => Ok(SandboxProgram::Firejail),
+            other => bail!(
+                "Unknown sandbox program '{}' (expected bubblewrap or firejail)",
+                other
+            ),
+        }
+    }
+}
+
+impl SandboxProgram {
+    pub fn executable(self) -> &'static str {
+        match self {
+            SandboxProgram::Bubblewrap => "bwrap",
+            SandboxProgram::Firejail => "firejail",
+        }
+    }
+}
+
+/// Runs shell commands inside bubblewrap or firejail with only the workspace
+/// visible. What the command may do follows `level`:
+/// - `read`: workspace mounted read-only, no network
+/// - `write` / `execute`: workspace writable, no network
+/// - `network` / `admin`: workspace writable, host network
+#[derive(Debug, Clone)]
+pub struct ShellSandbox {
+    program: SandboxProgram,
+    workspace: PathBuf,
+    level: PermissionLevel,
+}
+
+impl ShellSandbox {
+    pub fn new(program: SandboxProgram, workspace: &Path) -> Result<Self> {
+        let workspace = workspace
+            .canonicalize()
+            .context(format!("Sandbox workspace not found: {:?}", workspace))?;
+        Ok(Self {
+            program,
+            workspace,
+            level: PermissionLevel::Execute,
+        })
+    }
+
+    /// What sandboxed commands may do (default `execute`)
+    pub fn with_level(mut self, level: PermissionLevel) -> Self {
+        self.level = level;
+        self
+    }
+
+    /// Fail unless the sandbox program is on PATH, so a missing sandbox is
+    /// reported at startup instead of on the first command
+    pub fn check_available(&self) -> Result<()> {
+        let executable = self.program.executable();
+        let found = std::env::var_os("PATH")
+            .map(|path| std::env::split_paths(&path).any(|dir| dir.join(executable).is_file()))
+            .unwrap_or(false);
+        if !found {
+            bail!("Shell sandbox program '{}' not found on PATH", executable);
+        }
+        Ok(())
+    }
+
+    pub fn workspace(&self) -> &Path {
+        &self.workspace
+    }
+
+    fn writable(&self) -> bool {
+        self.level >= PermissionLevel::Write
+    }
+
+    fn network(&self) -> bool {
+        self.level >= PermissionLevel::Network
+    }
+
+    /// Program and arguments that run `sh -c cmd` inside the sandbox
+    pub fn command_line(&self, cmd: &str) -> (&'static str, Vec<OsString>) {
+        let mut args: Vec<OsString> = Vec::new();
+        match self.program {
+            SandboxProgram::Bubblewrap => {
+                for dir in SYSTEM_DIRS {
+                    args.extend(["--ro-bind-try".into(), (*dir).into(), (*dir).into()]);
+                }
+                args.extend(
+                    ["--proc", "/proc", "--dev", "/dev", "--tmpfs", "/tmp"].map(OsString::from),
+                );
+                let bind = if self.writable() {
+                    "--bind"
+                } else {
+                    "--ro-bind"
+                };
+                args.extend([
+                    bind.into(),
+                    self.workspace.clone().into(),
+                    self.workspace.clone().into(),
+                    "--chdir".into(),
+                    self.workspace.clone().into(),
+                ]);
+                // New pid namespace: killing bwrap takes every command process with it
+                args.extend(["--unshare-all", "--die-with-parent"].map(OsString::from));
+                if self.network() {
+                    args.push("--share-net".into());
+                }
+                args.push("--".into());
+            }
+            SandboxProgram::Firejail => {
+                args.extend(["--quiet", "--noprofile", "--private-tmp"].map(OsString::from));
+                let mut whitelist = OsString::from("--whitelist=");
+                whitelist.push(&self.workspace);
+                args.push(whitelist);
+                if !self.writable() {
+                    let mut read_only = OsString::from("--read-only=");
+                    read_only.push(&self.workspace);
+                    args.push(read_only);
+                }
+                if !self.network() {
+                    args.push("--net=none".into());
+                }
+            }
+        }
+        args.extend(["sh".into(), "-c".into(), cmd.into()]);
+        (self.program.executable(), args)
+    }
+}
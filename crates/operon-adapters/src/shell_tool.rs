@@ -1,10 +1,22 @@
+use crate::shell_sandbox::ShellSandbox;
 use anyhow::{Context, Result};
 use async_trait::async_trait;
 use operon_runtime::{PermissionLevel, Tool, ToolSchemaInfo};
 use serde_json::{json, Value};
-use tokio::process::Command;
+use std::process::Stdio;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tokio::io::{AsyncRead, AsyncReadExt};
+use tokio::process::{Child, Command};
+use tokio::task::JoinHandle;
 use tracing::{info, warn};
 
+/// Time a timed-out command gets to exit after SIGTERM before SIGKILL
+pub const DEFAULT_GRACE_PERIOD: Duration = Duration::from_secs(5);
+
+/// How long to keep collecting output after a timed-out command is killed
+const PIPE_DRAIN_TIMEOUT: Duration = Duration::from_secs(1);
+
 /// Default dangerous patterns blocked regardless of config
 const BUILTIN_BLOCKLIST: &[&str] = &[
     "rm -rf /",
@@ -28,6 +40,9 @@ pub struct ShellTool {
     dry_run: bool,
     blocklist: Vec<String>,
     allowlist: Vec<String>,
+    timeout: Option<Duration>,
+    grace_period: Duration,
+    sandbox: Option<ShellSandbox>,
 }
 
 impl ShellTool {
@@ -37,9 +52,38 @@ impl ShellTool {
             dry_run,
             blocklist: Vec::new(),
             allowlist: Vec::new(),
+            timeout: None,
+            grace_period: DEFAULT_GRACE_PERIOD,
+            sandbox: None,
         }
     }
 
+    /// Stop commands running longer than `timeout` (SIGTERM, then SIGKILL
+    /// after the grace period) and report the output captured so far
+    pub fn with_timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = Some(timeout);
+        self
+    }
+
+    /// Time between SIGTERM and SIGKILL for a timed-out command
+    pub fn with_grace_period(mut self, grace_period: Duration) -> Self {
+        self.grace_period = grace_period;
+        self
+    }
+
+    /// Longest a call can take with a timeout set; a runtime timeout for this
+    /// tool should be at least this, or it drops the call before output is reported
+    pub fn max_duration(&self) -> Option<Duration> {
+        self.timeout
+            .map(|timeout| timeout + self.grace_period + PIPE_DRAIN_TIMEOUT)
+    }
+
+    /// Run commands inside `sandbox` instead of directly under `sh`
+    pub fn with_sandbox(mut self, sandbox: ShellSandbox) -> Self {
+        self.sandbox = Some(sandbox);
+        self
+    }
+
     /// Configure command validation lists
     pub fn with_validation(mut self, blocklist: Vec<String>, allowlist: Vec<String>) -> Self {
         self.blocklist = blocklist;
@@ -47,7 +91,7 @@ impl ShellTool {
         self
     }
 
-    /// Execute shell command (no internal timeout — runtime manages timeout)
+    /// Execute shell command in its own process group, stopping it at the timeout (if set)
     async fn execute_command(&self, cmd: &str) -> Result<Value> {
         // Validate command before any execution
         validate_command(cmd, &self.blocklist, &self.allowlist)?;
@@ -62,25 +106,170 @@ impl ShellTool {
         }
 
         // Audit log: record exact command being executed
-        info!(cmd, "Executing shell command");
+        info!(
+            cmd,
+            sandboxed = self.sandbox.is_some(),
+            "Executing shell command"
+        );
 
-        let output = Command::new("sh")
-            .arg("-c")
-            .arg(cmd)
-            .output()
-            .await
-            .context("Command execution failed")?;
+        let mut command = match &self.sandbox {
+            Some(sandbox) => {
+                let (program, args) = sandbox.command_line(cmd);
+                let mut command = Command::new(program);
+                command.args(args);
+                command
+            }
+            None => {
+                let mut command = Command::new("sh");
+                command.arg("-c").arg(cmd);
+                command
+            }
+        };
+        command
+            .stdin(Stdio::null())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped());
+        #[cfg(unix)]
+        command.process_group(0);
+        let mut child = command.spawn().context("Command execution failed")?;
+        // If the call is dropped (e.g. runtime timeout), take the whole group down
+        let group = GroupGuard(child.id());
+        let stdout = Capture::start(child.stdout.take());
+        let stderr = Capture::start(child.stderr.take());
+
+        let finished = match self.timeout {
+            Some(timeout) => tokio::time::timeout(timeout, child.wait()).await.ok(),
+            None => Some(child.wait().await),
+        };
+        if let Some(status) = finished {
+            let status = status.context("Command execution failed")?;
+            group.disarm();
+            return Ok(json!({
+                "exit_code": status.code().unwrap_or(-1),
+                "stdout": stdout.finish(None).await,
+                "stderr": stderr.finish(None).await
+            }));
+        }
 
-        let exit_code = output.status.code().unwrap_or(-1);
-        let stdout = String::from_utf8_lossy(&output.stdout).to_string();
-        let stderr = String::from_utf8_lossy(&output.stderr).to_string();
+        let timeout = self.timeout.unwrap_or_default();
+        warn!(
+            cmd,
+            timeout_secs = timeout.as_secs_f64(),
+            "Command timed out, terminating"
+        );
+        let signal = self.terminate(&mut child, &group).await;
+        group.disarm();
 
         Ok(json!({
-            "exit_code": exit_code,
-            "stdout": stdout,
-            "stderr": stderr
+            "exit_code": -1,
+            "stdout": stdout.finish(Some(PIPE_DRAIN_TIMEOUT)).await,
+            "stderr": stderr.finish(Some(PIPE_DRAIN_TIMEOUT)).await,
+            "timed_out": true,
+            "signal": signal
         }))
     }
+
+    /// SIGTERM the command's process group, wait out the grace period, then
+    /// SIGKILL whatever is left. Returns the signal that stopped the shell.
+    async fn terminate(&self, child: &mut Child, group: &GroupGuard) -> &'static str {
+        group.signal(Signal::Term);
+        let exited = tokio::time::timeout(self.grace_period, child.wait())
+            .await
+            .is_ok();
+        // Children that ignored SIGTERM (or outlived the shell) die here too
+        group.signal(Signal::Kill);
+        if exited {
+            return "SIGTERM";
+        }
+        let _ = child.start_kill();
+        let _ = child.wait().await;
+        "SIGKILL"
+    }
+}
+
+#[derive(Clone, Copy)]
+enum Signal {
+    Term,
+    Kill,
+}
+
+/// Process group of a running command; SIGKILLs the group when dropped unless disarmed
+struct GroupGuard(Option<u32>);
+
+impl GroupGuard {
+    #[cfg(unix)]
+    fn signal(&self, signal: Signal) {
+        // Never hand 0 or values that wrap negative to kill(2)
+        let Some(pgid) = self.0.filter(|&pid| pid > 0 && pid <= i32::MAX as u32) else {
+            return;
+        };
+        let signal = match signal {
+            Signal::Term => libc::SIGTERM,
+            Signal::Kill => libc::SIGKILL,
+        };
+        // A negative pid signals every process in the group
+        unsafe { libc::kill(-(pgid as libc::pid_t), signal) };
+    }
+
+    /// Without process groups only the shell itself can be killed (by `terminate`)
+    #[cfg(not(unix))]
+    fn signal(&self, _signal: Signal) {}
+
+    fn disarm(mut self) {
+        self.0 = None;
+    }
+}
+
+impl Drop for GroupGuard {
+    fn drop(&mut self) {
+        self.signal(Signal::Kill);
+    }
+}
+
+/// Output read from a pipe in the background; what arrived so far stays
+/// available even if the reader never sees EOF
+struct Capture {
+    buf: Arc<Mutex<Vec<u8>>>,
+    task: JoinHandle<()>,
+}
+
+impl Capture {
+    fn start<R: AsyncRead + Unpin + Send + 'static>(pipe: Option<R>) -> Self {
+        let buf = Arc::new(Mutex::new(Vec::new()));
+        let sink = buf.clone();
+        let task = tokio::spawn(async move {
+            let Some(mut pipe) = pipe else {
+                return;
+            };
+            let mut chunk = [0u8; 8192];
+            while let Ok(n) = pipe.read(&mut chunk).await {
+                if n == 0 {
+                    break;
+                }
+                sink.lock()
+                    .unwrap_or_else(|e| e.into_inner())
+                    .extend_from_slice(&chunk[..n]);
+            }
+        });
+        Self { buf, task }
+    }
+
+    /// Wait for EOF (at most `wait`, if given) and return the text read
+    async fn finish(mut self, wait: Option<Duration>) -> String {
+        match wait {
+            Some(wait) => {
+                if tokio::time::timeout(wait, &mut self.task).await.is_err() {
+                    // A process outside the group still holds the pipe open
+                    self.task.abort();
+                }
+            }
+            None => {
+                let _ = (&mut self.task).await;
+            }
+        }
+        let buf = self.buf.lock().unwrap_or_else(|e| e.into_inner());
+        String::from_utf8_lossy(&buf).to_string()
+    }
 }
 
 #[async_trait]
@@ -117,7 +306,11 @@ impl Tool for ShellTool {
 }
 
 /// Validate command against blocklist and optional allowlist.
-fn validate_command(cmd: &str, blocklist: &[String], allowlist: &[String]) -> Result<()> {
+pub(crate) fn validate_command(
+    cmd: &str,
+    blocklist: &[String],
+    allowlist: &[String],
+) -> Result<()> {
     let cmd_lower = cmd.to_lowercase();
 
     // Check built-in blocklist
@@ -0,0 +1,426 @@
+use crate::shell_tool::validate_command;
+use anyhow::{bail, Context, Result};
+use async_trait::async_trait;
+use operon_runtime::{PermissionLevel, Tool, ToolSchemaInfo};
+use serde_json::{json, Value};
+use std::path::{Component, Path, PathBuf};
+use std::process::Stdio;
+use std::sync::Arc;
+use tokio::io::{AsyncRead, AsyncReadExt, AsyncWriteExt};
+use tokio::process::Command;
+use tracing::{info, warn};
+
+/// Default cap on stdout/stderr (each) kept from a remote command
+pub const DEFAULT_MAX_OUTPUT_BYTES: usize = 1024 * 1024;
+
+/// Remote host the ssh tools run against. Connections use the system `ssh`
+/// client in batch mode, so only key (or agent) authentication works and a
+/// password prompt fails the call instead of hanging it.
+#[derive(Debug, Clone)]
+pub struct SshTarget {
+    host: String,
+    user: Option<String>,
+    port: u16,
+    identity_file: Option<PathBuf>,
+    remote_root: String,
+    program: String,
+    max_output_bytes: usize,
+}
+
+impl SshTarget {
+    pub fn new(host: &str) -> Self {
+        Self {
+            host: host.to_string(),
+            user: None,
+            port: 22,
+            identity_file: None,
+            remote_root: ".".to_string(),
+            program: "ssh".to_string(),
+            max_output_bytes: DEFAULT_MAX_OUTPUT_BYTES,
+        }
+    }
+
+    pub fn with_user(mut self, user: &str) -> Self {
+        self.user = Some(user.to_string());
+        self
+    }
+
+    pub fn with_port(mut self, port: u16) -> Self {
+        self.port = port;
+        self
+    }
+
+    /// Private key passed as `-i`; only this key is offered to the host
+    pub fn with_identity_file(mut self, identity_file: &Path) -> Self {
+        self.identity_file = Some(identity_file.to_path_buf());
+        self
+    }
+
+    /// Remote directory commands start in and file paths resolve against (default: login dir)
+    pub fn with_remote_root(mut self, remote_root: &str) -> Self {
+        self.remote_root = remote_root.to_string();
+        self
+    }
+
+    /// ssh client to run (default `ssh` on PATH)
+    pub fn with_program(mut self, program: &str) -> Self {
+        self.program = program.to_string();
+        self
+    }
+
+    /// Bytes of stdout and of stderr kept per call; the rest is dropped and reported as truncated
+    pub fn with_max_output_bytes(mut self, max_output_bytes: usize) -> Self {
+        self.max_output_bytes = max_output_bytes;
+        self
+    }
+
+    pub fn host(&self) -> &str {
+        &self.host
+    }
+
+    /// `ssh` arguments that run `remote_cmd` from the remote root
+    pub fn args(&self, remote_cmd: &str) -> Vec<String> {
+        let mut args = vec![
+            "-o".to_string(),
+            "BatchMode=yes".into(),
+            "-p".into(),
+            self.port.to_string(),
+        ];
+        if let Some(identity_file) = &self.identity_file {
+            args.extend([
+                "-i".into(),
+                identity_file.display().to_string(),
+                "-o".into(),
+                "IdentitiesOnly=yes".into(),
+            ]);
+        }
+        let destination = match &self.user {
+            Some(user) => format!("{}@{}", user, self.host),
+            None => self.host.clone(),
+        };
+        args.extend([
+            destination,
+            "--".into(),
+            format!("cd {} && {}", shell_quote(&self.remote_root), remote_cmd),
+        ]);
+        args
+    }
+
+    /// Run `remote_cmd`, feeding `stdin` if given
+    async fn run(&self, remote_cmd: &str, stdin: Option<&[u8]>) -> Result<RemoteOutput> {
+        let mut child = Command::new(&self.program)
+            .args(self.args(remote_cmd))
+            .stdin(if stdin.is_some() {
+                Stdio::piped()
+            } else {
+                Stdio::null()
+            })
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            // A runtime timeout drops this future; don't leave the session behind
+            .kill_on_drop(true)
+            .spawn()
+            .context(format!("Failed to run ssh client '{}'", self.program))?;
+
+        if let (Some(data), Some(mut pipe)) = (stdin, child.stdin.take()) {
+            pipe.write_all(data).await?;
+        }
+        let (stdout, stderr) = tokio::join!(
+            read_limited(child.stdout.take(), self.max_output_bytes),
+            read_limited(child.stderr.take(), self.max_output_bytes)
+        );
+        let status = child.wait().await.context("ssh execution failed")?;
+        let (stdout, stdout_truncated) = stdout?;
+        let (stderr, stderr_truncated) = stderr?;
+        Ok(RemoteOutput {
+            exit_code: status.code().unwrap_or(-1),
+            stdout,
+            stderr,
+            truncated: stdout_truncated || stderr_truncated,
+        })
+    }
+}
+
+struct RemoteOutput {
+    exit_code: i32,
+    stdout: Vec<u8>,
+    stderr: Vec<u8>,
+    truncated: bool,
+}
+
+impl RemoteOutput {
+    fn stderr(&self) -> String {
+        String::from_utf8_lossy(&self.stderr).trim().to_string()
+    }
+}
+
+/// Read `pipe` to EOF keeping at most `limit` bytes, so a chatty command
+/// can neither block on a full pipe nor exhaust memory
+async fn read_limited<R: AsyncRead + Unpin>(
+    pipe: Option<R>,
+    limit: usize,
+) -> Result<(Vec<u8>, bool)> {
+    let mut buf = Vec::new();
+    let mut truncated = false;
+    let Some(mut pipe) = pipe else {
+        return Ok((buf, truncated));
+    };
+    let mut chunk = [0u8; 8192];
+    loop {
+        let n = pipe.read(&mut chunk).await?;
+        if n == 0 {
+            break;
+        }
+        let keep = n.min(limit - buf.len());
+        buf.extend_from_slice(&chunk[..keep]);
+        truncated |= keep < n;
+    }
+    Ok((buf, truncated))
+}
+
+/// Remote file paths resolve against the remote root and must stay under it
+fn check_remote_path(path: &str) -> Result<()> {
+    let inside = !path.is_empty()
+        && Path::new(path)
+            .components()
+            .all(|c| matches!(c, Component::Normal(_) | Component::CurDir));
+    if !inside {
+        bail!("Remote path must be relative to the remote root: {}", path);
+    }
+    Ok(())
+}
+
+/// Single-quote `s` for the remote POSIX shell
+fn shell_quote(s: &str) -> String {
+    format!("'{}'", s.replace('\'', r"'\''"))
+}
+
+/// Shell commands on the remote host, validated like the local `shell` tool
+pub struct SshShellTool {
+    target: Arc<SshTarget>,
+    dry_run: bool,
+    blocklist: Vec<String>,
+    allowlist: Vec<String>,
+}
+
+impl SshShellTool {
+    pub fn new(target: Arc<SshTarget>, dry_run: bool) -> Self {
+        Self {
+            target,
+            dry_run,
+            blocklist: Vec::new(),
+            allowlist: Vec::new(),
+        }
+    }
+
+    /// Configure command validation lists
+    pub fn with_validation(mut self, blocklist: Vec<String>, allowlist: Vec<String>) -> Self {
+        self.blocklist = blocklist;
+        self.allowlist = allowlist;
+        self
+    }
+}
+
+#[async_trait]
+impl Tool for SshShellTool {
+    async fn execute(&self, input: Value) -> Result<Value> {
+        let cmd = input["cmd"].as_str().context("Input missing 'cmd' field")?;
+        validate_command(cmd, &self.blocklist, &self.allowlist)?;
+
+        if self.dry_run {
+            warn!(cmd, host = %self.target.host, "SANDBOX MODE - remote command not executed");
+            return Ok(json!({
+                "exit_code": 0,
+                "stdout": "[dry-run]",
+                "stderr": ""
+            }));
+        }
+
+        // Audit log: record exact command and where it runs
+        info!(cmd, host = %self.target.host, "Executing remote shell command");
+        let output = self.target.run(cmd, None).await?;
+        Ok(json!({
+            "exit_code": output.exit_code,
+            "stdout": String::from_utf8_lossy(&output.stdout),
+            "stderr": String::from_utf8_lossy(&output.stderr),
+            "truncated": output.truncated
+        }))
+    }
+
+    fn name(&self) -> &str {
+        "ssh_shell"
+    }
+
+    fn schema(&self) -> ToolSchemaInfo {
+        ToolSchemaInfo {
+            name: "ssh_shell".to_string(),
+            description: format!(
+                "Execute a shell command on remote host {}",
+                self.target.host
+            ),
+            parameters: json!({
+                "type": "object",
+                "properties": {
+                    "cmd": {
+                        "type": "string",
+                        "description": "Shell command to execute on the remote host"
+                    }
+                },
+                "required": ["cmd"]
+            }),
+        }
+    }
+
+    fn permission_level(&self) -> PermissionLevel {
+        PermissionLevel::Execute
+    }
+}
+
+/// Read a text file under the remote root
+pub struct SshReadFileTool {
+    target: Arc<SshTarget>,
+}
+
+impl SshReadFileTool {
+    pub fn new(target: Arc<SshTarget>) -> Self {
+        Self { target }
+    }
+}
+
+#[async_trait]
+impl Tool for SshReadFileTool {
+    async fn execute(&self, input: Value) -> Result<Value> {
+        let path_str = input["path"]
+            .as_str()
+            .context("Missing required field 'path'")?;
+        check_remote_path(path_str)?;
+
+        let output = self
+            .target
+            .run(&format!("cat -- {}", shell_quote(path_str)), None)
+            .await?;
+        if output.exit_code != 0 {
+            bail!(
+                "Failed to read remote file {}: {}",
+                path_str,
+                output.stderr()
+            );
+        }
+        let check_len = output.stdout.len().min(8192);
+        if output.stdout[..check_len].contains(&0) {
+            bail!("Binary file detected, cannot read: {}", path_str);
+        }
+        let content = String::from_utf8(output.stdout).context("File is not valid UTF-8")?;
+        Ok(json!({
+            "content": content,
+            "truncated": output.truncated,
+        }))
+    }
+
+    fn name(&self) -> &str {
+        "ssh_read_file"
+    }
+
+    fn schema(&self) -> ToolSchemaInfo {
+        ToolSchemaInfo {
+            name: "ssh_read_file".to_string(),
+            description: format!("Read a text file on remote host {}", self.target.host),
+            parameters: json!({
+                "type": "object",
+                "properties": {
+                    "path": {
+                        "type": "string",
+                        "description": "File path relative to the remote root"
+                    }
+                },
+                "required": ["path"]
+            }),
+        }
+    }
+
+    fn permission_level(&self) -> PermissionLevel {
+        PermissionLevel::Read
+    }
+}
+
+/// Create or overwrite a file under the remote root
+pub struct SshWriteFileTool {
+    target: Arc<SshTarget>,
+}
+
+impl SshWriteFileTool {
+    pub fn new(target: Arc<SshTarget>) -> Self {
+        Self { target }
+    }
+}
+
+#[async_trait]
+impl Tool for SshWriteFileTool {
+    async fn execute(&self, input: Value) -> Result<Value> {
+        let path_str = input["path"]
+            .as_str()
+            .context("Missing required field 'path'")?;
+        let content = input["content"]
+            .as_str()
+            .context("Missing required field 'content'")?;
+        check_remote_path(path_str)?;
+
+        let quoted = shell_quote(path_str);
+        let remote_cmd = match Path::new(path_str).parent() {
+            Some(parent) if !parent.as_os_str().is_empty() => format!(
+                "mkdir -p -- {} && cat > {}",
+                shell_quote(&parent.to_string_lossy()),
+                quoted
+            ),
+            _ => format!("cat > {}", quoted),
+        };
+        info!(path = path_str, host = %self.target.host, "Writing remote file");
+        let output = self
+            .target
+            .run(&remote_cmd, Some(content.as_bytes()))
+            .await?;
+        if output.exit_code != 0 {
+            bail!(
+                "Failed to write remote file {}: {}",
+                path_str,
+                output.stderr()
+            );
+        }
+        Ok(json!({
+            "path": path_str,
+            "bytes_written": content.len(),
+        }))
+    }
+
+    fn name(&self) -> &str {
+        "ssh_write_file"
+    }
+
+    fn schema(&self) -> ToolSchemaInfo {
+        ToolSchemaInfo {
+            name: "ssh_write_file".to_string(),
+            description: format!(
+                "Create or overwrite a file on remote host {}",
+                self.target.host
+            ),
+            parameters: json!({
+                "type": "object",
+                "properties": {
+                    "path": {
+                        "type": "string",
+                        "description": "File path relative to the remote root"
+                    },
+                    "content": {
+                        "type": "string",
+                        "description": "Full file content to write"
+                    }
+                },
+                "required": ["path", "content"]
+            }),
+        }
+    }
+
+    fn permission_level(&self) -> PermissionLevel {
+        PermissionLevel::Write
+    }
+}
@@ -1,12 +1,28 @@
+use crate::workspace_snapshot::WorkspaceSnapshot;
 use anyhow::{bail, Context, Result};
+use std::collections::HashMap;
 use std::path::{Component, Path, PathBuf};
+use std::sync::{Arc, Mutex, MutexGuard};
 use tokio::io::AsyncReadExt;
 
+/// Caps on what the filesystem tools may write, in bytes
+#[derive(Debug, Clone, Copy, Default)]
+pub struct WriteLimits {
+    /// Largest file a write may produce (defaults to the read limit)
+    pub max_file_size: Option<u64>,
+    /// Total bytes tools may write per session (unlimited if None)
+    pub quota: Option<u64>,
+}
+
 /// Workspace-scoped path resolver — prevents path traversal attacks.
 /// All file operations must resolve paths through this guard.
 pub struct WorkspaceGuard {
     root: PathBuf,
     max_file_size: u64,
+    write_limits: WriteLimits,
+    /// Bytes charged to each session's quota (None: calls outside a session)
+    bytes_written: Mutex<HashMap<Option<String>, u64>>,
+    snapshot: Option<Arc<WorkspaceSnapshot>>,
 }
 
 impl WorkspaceGuard {
@@ -17,9 +33,85 @@ impl WorkspaceGuard {
         Ok(Self {
             root,
             max_file_size: max_file_size_mb * 1024 * 1024,
+            write_limits: WriteLimits::default(),
+            bytes_written: Mutex::new(HashMap::new()),
+            snapshot: None,
+        })
+    }
+
+    pub fn with_write_limits(mut self, limits: WriteLimits) -> Self {
+        self.write_limits = limits;
+        self
+    }
+
+    /// Save each file's original into `snapshot` before it is first changed
+    pub fn with_snapshot(mut self, snapshot: Arc<WorkspaceSnapshot>) -> Self {
+        self.snapshot = Some(snapshot);
+        self
+    }
+
+    /// Must be called with a resolved path before modifying or creating it
+    pub fn before_write(&self, path: &Path) -> Result<()> {
+        match &self.snapshot {
+            Some(snapshot) => snapshot.record(path),
+            None => Ok(()),
+        }
+    }
+
+    /// Must be called before writing `bytes` that leave the file at `file_size`.
+    /// Reserves `bytes` of the calling session's write quota; the reservation
+    /// is handed back unless the returned charge is committed once the write
+    /// has landed.
+    pub fn check_write(&self, file_size: u64, bytes: u64) -> Result<WriteCharge<'_>> {
+        let max = self
+            .write_limits
+            .max_file_size
+            .unwrap_or(self.max_file_size);
+        if file_size > max {
+            bail!(
+                "Write too large: file would be {} bytes (max {} bytes)",
+                file_size,
+                max
+            );
+        }
+        let session = operon_runtime::current_session();
+        let mut written = self.written();
+        let used = written.entry(session.clone()).or_default();
+        let total = used.saturating_add(bytes);
+        if let Some(quota) = self.write_limits.quota.filter(|quota| total > *quota) {
+            bail!(
+                "Write quota exceeded: {} bytes already written, {} more requested (quota {} bytes)",
+                used,
+                bytes,
+                quota
+            );
+        }
+        *used = total;
+        Ok(WriteCharge {
+            guard: self,
+            session,
+            bytes,
+            committed: false,
         })
     }
 
+    /// Bytes `session` has written (None: calls outside a session)
+    pub fn bytes_written(&self, session: Option<&str>) -> u64 {
+        self.written()
+            .get(&session.map(str::to_string))
+            .copied()
+            .unwrap_or(0)
+    }
+
+    /// Drop `session`'s count once it has ended
+    pub fn end_session(&self, session: &str) {
+        self.written().remove(&Some(session.to_string()));
+    }
+
+    fn written(&self) -> MutexGuard<'_, HashMap<Option<String>, u64>> {
+        self.bytes_written.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
     /// Resolve a user-provided path relative to workspace root.
     /// Rejects paths that escape the workspace via `..` or symlinks.
     pub fn resolve(&self, input_path: &str) -> Result<PathBuf> {
@@ -46,10 +138,9 @@ impl WorkspaceGuard {
     /// Check if file is a text file (no null bytes in first 8KB).
     /// Only reads up to 8KB instead of the entire file.
     pub async fn is_text_file(path: &Path) -> Result<bool> {
-        let mut file =
-            tokio::fs::File::open(path)
-                .await
-                .context("Failed to open file for binary check")?;
+        let mut file = tokio::fs::File::open(path)
+            .await
+            .context("Failed to open file for binary check")?;
         let mut buf = vec![0u8; 8192];
         let n = file
             .read(&mut buf)
@@ -78,6 +169,34 @@ impl WorkspaceGuard {
     }
 }
 
+/// Bytes reserved by `WorkspaceGuard::check_write`, given back on drop unless
+/// committed
+#[must_use = "commit the charge once the write has landed"]
+pub struct WriteCharge<'a> {
+    guard: &'a WorkspaceGuard,
+    session: Option<String>,
+    bytes: u64,
+    committed: bool,
+}
+
+impl WriteCharge<'_> {
+    /// Keep the bytes charged: the write went through
+    pub fn commit(mut self) {
+        self.committed = true;
+    }
+}
+
+impl Drop for WriteCharge<'_> {
+    fn drop(&mut self) {
+        if self.committed {
+            return;
+        }
+        if let Some(used) = self.guard.written().get_mut(&self.session) {
+            *used = used.saturating_sub(self.bytes);
+        }
+    }
+}
+
 /// Normalize a path by resolving `.` and `..` components without filesystem access.
 fn normalize_path(path: &Path) -> PathBuf {
     let mut parts: Vec<Component> = Vec::new();
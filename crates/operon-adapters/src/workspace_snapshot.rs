@@ -0,0 +1,216 @@
+use anyhow::{bail, Context, Result};
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+
+const MANIFEST_FILE: &str = "manifest.json";
+const BLOB_DIR: &str = "files";
+
+/// What a path looked like before it was first changed
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case", tag = "kind")]
+pub enum Original {
+    /// File whose content is saved as `blob` in the snapshot
+    File { blob: String },
+    /// File that did not exist; restore deletes it
+    Absent,
+    /// Directory created for a new file; restore removes it if empty
+    CreatedDir,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SnapshotEntry {
+    /// Relative to the workspace root
+    pub path: PathBuf,
+    pub original: Original,
+}
+
+/// Index of a snapshot, stored next to the saved files
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SnapshotManifest {
+    pub id: String,
+    pub workspace: PathBuf,
+    /// Unix seconds
+    pub created_at: u64,
+    pub entries: Vec<SnapshotEntry>,
+}
+
+/// What `restore` changed
+#[derive(Debug, Default, Serialize)]
+pub struct RestoreSummary {
+    pub id: String,
+    pub restored: usize,
+    pub removed: usize,
+}
+
+/// Copy-on-write snapshot of a workspace: each file's original is saved the
+/// first time a tool is about to change it, so everything changed through the
+/// filesystem tools can be reverted. Changes made by shell commands are not seen.
+pub struct WorkspaceSnapshot {
+    dir: PathBuf,
+    manifest: Mutex<SnapshotManifest>,
+}
+
+impl WorkspaceSnapshot {
+    /// Start snapshot `id` under `snapshots_dir`; nothing is written until a file is recorded
+    pub fn new(snapshots_dir: &Path, id: &str, workspace: &Path) -> Result<Self> {
+        validate_id(id)?;
+        let dir = snapshots_dir.join(id);
+        if dir.exists() {
+            bail!("Snapshot '{}' already exists", id);
+        }
+        let workspace = workspace
+            .canonicalize()
+            .context(format!("Workspace root not found: {:?}", workspace))?;
+        Ok(Self {
+            dir,
+            manifest: Mutex::new(SnapshotManifest {
+                id: id.to_string(),
+                workspace,
+                created_at: unix_now(),
+                entries: Vec::new(),
+            }),
+        })
+    }
+
+    pub fn id(&self) -> String {
+        self.lock().id.clone()
+    }
+
+    /// Save the original of `path` (absolute, inside the workspace) unless it
+    /// was already recorded. Call before every write.
+    pub fn record(&self, path: &Path) -> Result<()> {
+        let mut manifest = self.lock();
+        let rel = path
+            .strip_prefix(&manifest.workspace)
+            .context(format!("{:?} is outside the snapshot workspace", path))?
+            .to_path_buf();
+        if manifest.entries.iter().any(|e| e.path == rel) {
+            return Ok(());
+        }
+
+        let blobs = self.dir.join(BLOB_DIR);
+        std::fs::create_dir_all(&blobs).context("Failed to create snapshot directory")?;
+        if path.is_file() {
+            let blob = manifest.entries.len().to_string();
+            std::fs::copy(path, blobs.join(&blob))
+                .context(format!("Failed to snapshot {:?}", path))?;
+            manifest.entries.push(SnapshotEntry {
+                path: rel,
+                original: Original::File { blob },
+            });
+        } else {
+            // Parent directories the write will create, outermost first
+            let mut created = Vec::new();
+            let mut parent = rel.parent();
+            while let Some(dir) = parent.filter(|d| !d.as_os_str().is_empty()) {
+                if manifest.workspace.join(dir).exists() {
+                    break;
+                }
+                created.push(dir.to_path_buf());
+                parent = dir.parent();
+            }
+            for dir in created.into_iter().rev() {
+                manifest.entries.push(SnapshotEntry {
+                    path: dir,
+                    original: Original::CreatedDir,
+                });
+            }
+            manifest.entries.push(SnapshotEntry {
+                path: rel,
+                original: Original::Absent,
+            });
+        }
+
+        // Rewritten on every change so a crash still leaves a usable snapshot
+        let content = serde_json::to_string_pretty(&*manifest)?;
+        std::fs::write(self.dir.join(MANIFEST_FILE), content)
+            .context("Failed to write snapshot manifest")?;
+        Ok(())
+    }
+
+    /// Snapshots under `snapshots_dir`, oldest first
+    pub fn list(snapshots_dir: &Path) -> Result<Vec<SnapshotManifest>> {
+        if !snapshots_dir.exists() {
+            return Ok(Vec::new());
+        }
+        let mut manifests = Vec::new();
+        for entry in std::fs::read_dir(snapshots_dir)? {
+            let path = entry?.path().join(MANIFEST_FILE);
+            if path.exists() {
+                manifests.push(read_manifest(&path)?);
+            }
+        }
+        manifests.sort_by(|a, b| (a.created_at, &a.id).cmp(&(b.created_at, &b.id)));
+        Ok(manifests)
+    }
+
+    /// Put every recorded path back as it was, then delete the snapshot
+    pub fn restore(snapshots_dir: &Path, id: &str) -> Result<RestoreSummary> {
+        validate_id(id)?;
+        let dir = snapshots_dir.join(id);
+        let manifest = read_manifest(&dir.join(MANIFEST_FILE))?;
+        let mut summary = RestoreSummary {
+            id: id.to_string(),
+            ..Default::default()
+        };
+
+        for entry in &manifest.entries {
+            let path = manifest.workspace.join(&entry.path);
+            match &entry.original {
+                Original::File { blob } => {
+                    if let Some(parent) = path.parent() {
+                        std::fs::create_dir_all(parent)?;
+                    }
+                    std::fs::copy(dir.join(BLOB_DIR).join(blob), &path)
+                        .context(format!("Failed to restore {:?}", path))?;
+                    summary.restored += 1;
+                }
+                Original::Absent if path.is_file() => {
+                    std::fs::remove_file(&path).context(format!("Failed to remove {:?}", path))?;
+                    summary.removed += 1;
+                }
+                Original::Absent | Original::CreatedDir => {}
+            }
+        }
+        // Innermost first; directories that gained other files stay
+        for entry in manifest.entries.iter().rev() {
+            if entry.original == Original::CreatedDir {
+                let _ = std::fs::remove_dir(manifest.workspace.join(&entry.path));
+            }
+        }
+
+        std::fs::remove_dir_all(&dir).context("Failed to delete restored snapshot")?;
+        Ok(summary)
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, SnapshotManifest> {
+        self.manifest.lock().unwrap_or_else(|e| e.into_inner())
+    }
+}
+
+fn read_manifest(path: &Path) -> Result<SnapshotManifest> {
+    let content =
+        std::fs::read_to_string(path).context(format!("Failed to read snapshot: {:?}", path))?;
+    serde_json::from_str(&content).context(format!("Invalid snapshot manifest: {:?}", path))
+}
+
+/// Ids become directory names
+fn validate_id(id: &str) -> Result<()> {
+    if id.is_empty()
+        || id.starts_with('.')
+        || !id
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || matches!(c, '-' | '_' | '.'))
+    {
+        bail!("Invalid snapshot id: {:?}", id);
+    }
+    Ok(())
+}
+
+fn unix_now() -> u64 {
+    std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_secs()
+}
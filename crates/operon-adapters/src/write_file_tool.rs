@@ -1,9 +1,10 @@
-use anyhow::{Context, Result};
+use anyhow::{bail, Context, Result};
 use async_trait::async_trait;
 use operon_runtime::{PermissionLevel, Tool, ToolSchemaInfo};
 use serde_json::{json, Value};
 use std::io::Write;
 use std::sync::Arc;
+use tokio::io::AsyncWriteExt;
 
 use crate::workspace_guard::WorkspaceGuard;
 
@@ -26,8 +27,27 @@ impl Tool for WriteFileTool {
         let content = input["content"]
             .as_str()
             .context("Missing required field 'content'")?;
+        let mode = input["mode"].as_str().unwrap_or("overwrite");
+        if !matches!(mode, "overwrite" | "append" | "create_new") {
+            bail!(
+                "Unknown write mode '{}' (expected overwrite, append or create_new)",
+                mode
+            );
+        }
 
         let path = self.guard.resolve(path_str)?;
+        let bytes = content.len() as u64;
+        let file_size = if mode == "append" {
+            let existing = match tokio::fs::metadata(&path).await {
+                Ok(meta) => meta.len(),
+                Err(_) => 0,
+            };
+            existing + bytes
+        } else {
+            bytes
+        };
+        let charge = self.guard.check_write(file_size, bytes)?;
+        self.guard.before_write(&path)?;
 
         // Create parent directories
         if let Some(parent) = path.parent() {
@@ -36,21 +56,49 @@ impl Tool for WriteFileTool {
                 .context(format!("Failed to create directories: {:?}", parent))?;
         }
 
-        // Atomic write: temp file + rename
-        let parent = path.parent().unwrap_or(self.guard.root());
-        let mut tmp = tempfile::NamedTempFile::new_in(parent)
-            .context("Failed to create temp file for atomic write")?;
+        if mode == "append" {
+            // Appends land in one write call; no read-modify-write round trip
+            let mut file = tokio::fs::OpenOptions::new()
+                .create(true)
+                .append(true)
+                .open(&path)
+                .await
+                .context(format!("Failed to open for append: {:?}", path))?;
+            file.write_all(content.as_bytes())
+                .await
+                .context("Failed to append to file")?;
+            file.flush().await?;
+        } else {
+            // Atomic write: temp file + rename
+            let parent = path.parent().unwrap_or(self.guard.root());
+            let mut tmp = tempfile::NamedTempFile::new_in(parent)
+                .context("Failed to create temp file for atomic write")?;
 
-        tmp.write_all(content.as_bytes())
-            .context("Failed to write to temp file")?;
-        tmp.flush()?;
+            tmp.write_all(content.as_bytes())
+                .context("Failed to write to temp file")?;
+            tmp.flush()?;
 
-        tmp.persist(&path)
-            .context(format!("Failed to persist file: {:?}", path))?;
+            if mode == "create_new" {
+                // Fails instead of replacing a file created in the meantime
+                tmp.persist_noclobber(&path).map_err(|e| {
+                    if e.error.kind() == std::io::ErrorKind::AlreadyExists {
+                        anyhow::anyhow!("File already exists: {} (mode create_new)", path_str)
+                    } else {
+                        anyhow::Error::new(e.error)
+                            .context(format!("Failed to persist file: {:?}", path))
+                    }
+                })?;
+            } else {
+                tmp.persist(&path)
+                    .context(format!("Failed to persist file: {:?}", path))?;
+            }
+        }
+        charge.commit();
 
         Ok(json!({
             "bytes_written": content.len(),
             "path": path_str,
+            "mode": mode,
         }))
     }
 
@@ -66,7 +114,12 @@ impl Tool for WriteFileTool {
                 "type": "object",
                 "properties": {
                     "path": { "type": "string", "description": "File path relative to workspace" },
-                    "content": { "type": "string", "description": "Content to write" }
+                    "content": { "type": "string", "description": "Content to write" },
+                    "mode": {
+                        "type": "string",
+                        "enum": ["overwrite", "append", "create_new"],
+                        "description": "overwrite (default) replaces the file atomically; append adds to the end, creating the file if needed; create_new fails if the file exists"
+                    }
                 },
                 "required": ["path", "content"]
             }),
@@ -76,4 +129,8 @@ impl Tool for WriteFileTool {
     fn permission_level(&self) -> PermissionLevel {
         PermissionLevel::Write
     }
+
+    fn end_session(&self, session: &str) {
+        self.guard.end_session(session);
+    }
 }
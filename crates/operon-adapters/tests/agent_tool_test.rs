@@ -0,0 +1,151 @@
+//! Delegation to sub-agents through a scripted provider: each user task is
+//! answered with a call to `delegate` when the agent has it, and each tool
+//! result is echoed back as the final reply.
+
+use anyhow::Result;
+use async_trait::async_trait;
+use operon_adapters::AgentTool;
+use operon_runtime::{
+    AgentConfig, Content, GenerateConfig, GenerateResponse, LLMProvider, Message, Runtime,
+    StopReason, Tool, ToolCall, ToolSchema, Usage,
+};
+use serde_json::json;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+
+struct ScriptedProvider {
+    calls: AtomicUsize,
+    /// History length of each request
+    history: std::sync::Mutex<Vec<usize>>,
+}
+
+impl ScriptedProvider {
+    fn new() -> Self {
+        Self {
+            calls: AtomicUsize::new(0),
+            history: std::sync::Mutex::new(Vec::new()),
+        }
+    }
+}
+
+#[async_trait]
+impl LLMProvider for ScriptedProvider {
+    async fn generate(
+        &self,
+        messages: &[Message],
+        tools: &[ToolSchema],
+        _config: &GenerateConfig,
+    ) -> Result<GenerateResponse> {
+        let n = self.calls.fetch_add(1, Ordering::Relaxed);
+        self.history.lock().unwrap().push(messages.len());
+        let usage = Usage {
+            input_tokens: 10,
+            output_tokens: 5,
+            cost_usd: 0.0,
+        };
+        let (content, stop_reason) = match &messages.last().unwrap().content {
+            Content::Text { text } if tools.iter().any(|tool| tool.name == "delegate") => (
+                Content::ToolCall(ToolCall {
+                    id: format!("call-{}", n),
+                    name: "delegate".into(),
+                    input: json!({"task": text}),
+                }),
+                StopReason::ToolUse,
+            ),
+            Content::Text { text } => (
+                Content::Text {
+                    text: format!("done: {}", text),
+                },
+                StopReason::EndTurn,
+            ),
+            Content::ToolResult(result) => (
+                Content::Text {
+                    text: result.output.clone(),
+                },
+                StopReason::EndTurn,
+            ),
+            other => panic!("unexpected message {:?}", other),
+        };
+        Ok(GenerateResponse {
+            content,
+            stop_reason,
+            usage,
+            model: "scripted".into(),
+        })
+    }
+
+    fn supports_vision(&self) -> bool {
+        false
+    }
+
+    fn model_name(&self) -> &str {
+        "scripted"
+    }
+}
+
+fn runtime(dir: &tempfile::TempDir) -> Arc<Runtime> {
+    let db = dir.path().join("agent_tool.db");
+    Arc::new(Runtime::with_db(db.to_str().unwrap(), false, Duration::from_secs(30)).unwrap())
+}
+
+fn researcher() -> AgentConfig {
+    AgentConfig {
+        name: "researcher".into(),
+        system_prompt: "Research what you are asked.".into(),
+        ..Default::default()
+    }
+}
+
+#[tokio::test]
+async fn test_delegation_and_named_sessions() {
+    let dir = tempfile::tempdir().unwrap();
+    let provider = Arc::new(ScriptedProvider::new());
+    let tool = AgentTool::new("research", researcher(), provider.clone(), runtime(&dir));
+
+    let output = tool.execute(json!({"task": "find X"})).await.unwrap();
+    assert_eq!(output["agent"], "researcher");
+    assert_eq!(output["response"], "done: find X");
+    assert_eq!(output["usage"]["input_tokens"], 10);
+    assert!(output["session"].is_null());
+
+    // A named session carries its history into the next call
+    tool.execute(json!({"task": "a", "session": "s"}))
+        .await
+        .unwrap();
+    let output = tool
+        .execute(json!({"task": "b", "session": "s"}))
+        .await
+        .unwrap();
+    assert_eq!(output["response"], "done: b");
+    assert_eq!(output["usage"]["output_tokens"], 5);
+    assert_eq!(*provider.history.lock().unwrap(), [1, 1, 3]);
+
+    let err = tool.execute(json!({})).await.unwrap_err().to_string();
+    assert!(err.contains("Missing required field 'task'"));
+}
+
+#[tokio::test]
+async fn test_delegation_depth_limit() {
+    let dir = tempfile::tempdir().unwrap();
+    let runtime = runtime(&dir);
+    let provider = Arc::new(ScriptedProvider::new());
+    // The sub-agent can reach the tool that runs it: every task recurses
+    let tool = Arc::new(
+        AgentTool::new("delegate", researcher(), provider.clone(), runtime.clone())
+            .with_max_depth(2),
+    );
+    runtime
+        .register_tool("delegate".into(), tool.clone())
+        .unwrap();
+
+    let output = tool.execute(json!({"task": "loop"})).await.unwrap();
+    let response = output["response"].as_str().unwrap();
+    assert!(
+        response.contains("Delegation depth limit of 2 reached"),
+        "{}",
+        response
+    );
+    // Two agents, each one call to delegate and one reply
+    assert_eq!(provider.calls.load(Ordering::Relaxed), 4);
+}
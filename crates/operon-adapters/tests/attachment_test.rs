@@ -0,0 +1,44 @@
+use operon_adapters::{load_image, WorkspaceGuard};
+use operon_runtime::Content;
+
+#[tokio::test]
+async fn test_load_image_detects_mime() {
+    let dir = tempfile::tempdir().unwrap();
+    std::fs::write(dir.path().join("shot.png"), b"\x89PNG\r\n\x1a\nbody").unwrap();
+    std::fs::write(dir.path().join("anim.gif"), b"GIF89a body").unwrap();
+    std::fs::write(dir.path().join("notes.txt"), b"not an image").unwrap();
+    let guard = WorkspaceGuard::new(dir.path().to_path_buf(), 10).unwrap();
+
+    match load_image(&guard, "shot.png").await.unwrap() {
+        Content::Image { data, mime } => {
+            assert_eq!(mime, "image/png");
+            assert!(data.ends_with(b"body"));
+        }
+        other => panic!("unexpected content: {:?}", other),
+    }
+    assert!(matches!(
+        load_image(&guard, "anim.gif").await.unwrap(),
+        Content::Image { mime, .. } if mime == "image/gif"
+    ));
+
+    let err = load_image(&guard, "notes.txt").await.unwrap_err();
+    assert!(err
+        .to_string()
+        .contains("Not a PNG, JPEG, GIF or WebP image"));
+    let err = load_image(&guard, "missing.png").await.unwrap_err();
+    assert!(err.to_string().contains("File not found"));
+}
+
+#[tokio::test]
+async fn test_load_image_stays_in_workspace() {
+    let outside = tempfile::tempdir().unwrap();
+    std::fs::write(outside.path().join("secret.png"), b"\x89PNG\r\n\x1a\n").unwrap();
+    let workspace = tempfile::tempdir().unwrap();
+    let guard = WorkspaceGuard::new(workspace.path().to_path_buf(), 10).unwrap();
+
+    let path = outside.path().join("secret.png");
+    let err = load_image(&guard, path.to_str().unwrap())
+        .await
+        .unwrap_err();
+    assert!(err.to_string().contains("Path traversal denied"));
+}
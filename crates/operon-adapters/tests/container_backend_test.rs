@@ -0,0 +1,182 @@
+//! ContainerBackend against a fake engine script that logs its arguments and
+//! answers `exec` the way `warden exec-tool` would.
+
+use anyhow::Result;
+use async_trait::async_trait;
+use operon_adapters::ContainerBackend;
+use operon_runtime::{PermissionLevel, Runtime, Tool};
+use serde_json::{json, Value};
+use std::path::Path;
+use std::sync::Arc;
+use std::time::Duration;
+
+struct LocalTool;
+
+#[async_trait]
+impl Tool for LocalTool {
+    async fn execute(&self, input: Value) -> Result<Value> {
+        Ok(json!({"local": input}))
+    }
+
+    fn name(&self) -> &str {
+        "local"
+    }
+}
+
+/// Engine stand-in: `exec` echoes stdin back as the output, or fails for tool "broken"
+fn fake_engine(dir: &Path) -> String {
+    let log = dir.join("engine.log");
+    let script = dir.join("engine.sh");
+    std::fs::write(
+        &script,
+        format!(
+            r#"#!/bin/sh
+echo "$@" >> {log}
+case "$1" in
+  exec)
+    for last; do :; done
+    input=$(cat)
+    if [ "$last" = "broken" ]; then
+      echo '{{"error": "tool failed"}}'
+    else
+      echo "{{\"output\": {{\"tool\": \"$last\", \"input\": $input}}}}"
+    fi
+    ;;
+esac
+"#,
+            log = log.display()
+        ),
+    )
+    .unwrap();
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        std::fs::set_permissions(&script, std::fs::Permissions::from_mode(0o755)).unwrap();
+    }
+    script.display().to_string()
+}
+
+#[test]
+fn test_container_args_isolate_network_and_mount_workspace() {
+    let dir = tempfile::tempdir().unwrap();
+    let ws = dir.path().canonicalize().unwrap();
+    let backend = ContainerBackend::new("podman", "tools:latest", ["shell".to_string()])
+        .with_workspace(dir.path())
+        .unwrap();
+
+    let run = backend.run_args("c1").join(" ");
+    assert!(run.starts_with("run -d --rm --name c1 --network none"));
+    assert!(run.contains(&format!("-v {}:/workspace -w /workspace", ws.display())));
+    assert!(run.ends_with("tools:latest sleep infinity"));
+    assert_eq!(
+        backend.exec_args("c1", "shell"),
+        ["exec", "-i", "c1", "warden", "exec-tool", "shell"]
+    );
+    let networked = ContainerBackend::new("docker", "img", []).with_network(true);
+    assert!(!networked.run_args("c2").contains(&"--network".to_string()));
+
+    // The tool name stays last after the timeout flag
+    let timed = ContainerBackend::new("docker", "img", ["shell".to_string()])
+        .with_timeouts([("shell".to_string(), Duration::from_secs(45))].into());
+    assert_eq!(
+        timed.exec_args("c3", "shell"),
+        [
+            "exec",
+            "-i",
+            "c3",
+            "warden",
+            "exec-tool",
+            "--timeout-secs",
+            "45",
+            "shell"
+        ]
+    );
+}
+
+#[cfg(unix)]
+#[tokio::test]
+async fn test_container_backend_marshals_calls_per_session() {
+    let dir = tempfile::tempdir().unwrap();
+    let engine = fake_engine(dir.path());
+    let backend = Arc::new(ContainerBackend::new(
+        &engine,
+        "img",
+        ["remote".to_string(), "broken".to_string()],
+    ));
+    let db_path = dir.path().join("state.db");
+    let runtime = Runtime::with_db(db_path.to_str().unwrap(), false, Duration::from_secs(30))
+        .unwrap()
+        .with_execution_backend(backend);
+    for name in ["remote", "broken", "local"] {
+        runtime
+            .register_tool(name.to_string(), Arc::new(LocalTool))
+            .unwrap();
+    }
+
+    for session in ["s1", "s1", "s2"] {
+        let output = runtime
+            .execute_tool_in_session("remote", json!({"n": 1}), PermissionLevel::Execute, session)
+            .await
+            .unwrap();
+        assert_eq!(output, json!({"tool": "remote", "input": {"n": 1}}));
+    }
+    // Unrouted tools stay in-process
+    let output = runtime
+        .execute_tool("local", json!({"n": 2}))
+        .await
+        .unwrap();
+    assert_eq!(output, json!({"local": {"n": 2}}));
+    let err = runtime.execute_tool("broken", json!({})).await.unwrap_err();
+    assert!(format!("{:#}", err).contains("tool failed"));
+
+    runtime.end_session("s1").await.unwrap();
+    runtime.stop().await.unwrap();
+
+    let log = std::fs::read_to_string(dir.path().join("engine.log")).unwrap();
+    let commands: Vec<&str> = log
+        .lines()
+        .map(|line| line.split_whitespace().next().unwrap())
+        .collect();
+    // One container per session (plus "default" for the session-less call)
+    assert_eq!(
+        commands,
+        ["run", "exec", "exec", "run", "exec", "run", "exec", "rm", "rm", "rm"]
+    );
+    assert!(log.contains("-s1 --network none img sleep infinity"));
+}
+
+#[cfg(unix)]
+#[tokio::test]
+async fn test_container_backend_validates_shell_commands_on_the_host() {
+    let dir = tempfile::tempdir().unwrap();
+    let engine = fake_engine(dir.path());
+    let backend = Arc::new(
+        ContainerBackend::new(&engine, "img", ["shell".to_string()])
+            .with_shell_validation(vec!["curl".to_string()], vec![]),
+    );
+    let db_path = dir.path().join("state.db");
+    let runtime = Runtime::with_db(db_path.to_str().unwrap(), false, Duration::from_secs(30))
+        .unwrap()
+        .with_execution_backend(backend);
+    runtime
+        .register_tool("shell".to_string(), Arc::new(LocalTool))
+        .unwrap();
+
+    for cmd in ["curl http://example.com", "rm -rf /"] {
+        let err = runtime
+            .execute_tool("shell", json!({"cmd": cmd}))
+            .await
+            .unwrap_err();
+        assert!(format!("{:#}", err).contains("blocked"), "{:#}", err);
+    }
+    let output = runtime
+        .execute_tool("shell", json!({"cmd": "ls"}))
+        .await
+        .unwrap();
+    assert_eq!(output["tool"], "shell");
+    runtime.stop().await.unwrap();
+
+    // Only the allowed command reached the engine
+    let log = std::fs::read_to_string(dir.path().join("engine.log")).unwrap();
+    assert_eq!(log.lines().filter(|l| l.starts_with("exec")).count(), 1);
+}
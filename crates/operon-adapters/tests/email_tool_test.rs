@@ -0,0 +1,106 @@
+//! EmailTool against a minimal local SMTP server standing in for the relay.
+
+use operon_adapters::{EmailTool, SmtpSecurity};
+use operon_runtime::Tool;
+use serde_json::json;
+use tokio::io::{AsyncBufReadExt, AsyncWriteExt, BufReader};
+use tokio::net::TcpListener;
+
+/// Accept one SMTP session and hand back the envelope commands and the message data
+async fn relay() -> (u16, tokio::task::JoinHandle<(Vec<String>, String)>) {
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let port = listener.local_addr().unwrap().port();
+    let handle = tokio::spawn(async move {
+        let (socket, _) = listener.accept().await.unwrap();
+        let (reader, mut writer) = socket.into_split();
+        let mut lines = BufReader::new(reader).lines();
+        writer.write_all(b"220 localhost ready\r\n").await.unwrap();
+        let mut commands = Vec::new();
+        let mut data = String::new();
+        while let Some(line) = lines.next_line().await.unwrap() {
+            let verb = line.split([' ', ':']).next().unwrap_or("").to_uppercase();
+            let reply: &[u8] = match verb.as_str() {
+                "EHLO" => b"250 localhost\r\n",
+                "DATA" => {
+                    writer.write_all(b"354 go ahead\r\n").await.unwrap();
+                    while let Some(line) = lines.next_line().await.unwrap() {
+                        if line == "." {
+                            break;
+                        }
+                        data.push_str(&line);
+                        data.push('\n');
+                    }
+                    b"250 queued\r\n"
+                }
+                "QUIT" => {
+                    writer.write_all(b"221 bye\r\n").await.unwrap();
+                    break;
+                }
+                _ => b"250 ok\r\n",
+            };
+            commands.push(line);
+            writer.write_all(reply).await.unwrap();
+        }
+        (commands, data)
+    });
+    (port, handle)
+}
+
+fn tool(port: u16) -> EmailTool {
+    EmailTool::new("127.0.0.1", "SilentClaw <bot@example.com>")
+        .unwrap()
+        .with_security(SmtpSecurity::None)
+        .with_port(port)
+        .with_allowed_recipients(vec![
+            "oncall@ops.example.com".into(),
+            "@reports.example.com".into(),
+        ])
+}
+
+#[tokio::test]
+async fn test_email_is_sent_through_relay() {
+    let (port, session) = relay().await;
+
+    let result = tool(port)
+        .execute(json!({
+            "to": ["oncall@ops.example.com", "Team <weekly@reports.example.com>"],
+            "subject": "Nightly report",
+            "body": "All 12 jobs succeeded."
+        }))
+        .await
+        .unwrap();
+    assert_eq!(result["sent"], true);
+
+    let (commands, data) = session.await.unwrap();
+    assert!(commands.contains(&"MAIL FROM:<bot@example.com>".to_string()));
+    assert!(commands.contains(&"RCPT TO:<oncall@ops.example.com>".to_string()));
+    assert!(commands.contains(&"RCPT TO:<weekly@reports.example.com>".to_string()));
+    assert!(data.contains("Subject: Nightly report"));
+    assert!(data.contains("All 12 jobs succeeded."));
+}
+
+#[tokio::test]
+async fn test_recipients_outside_allowlist_are_rejected() {
+    let tool = tool(1);
+    assert!(tool.is_allowed("OnCall@ops.example.com"));
+    assert!(tool.is_allowed("anyone@reports.example.com"));
+    assert!(!tool.is_allowed("anyone@evilreports.example.com"));
+
+    // Rejected before any connection is made (nothing listens on port 1)
+    let err = tool
+        .execute(json!({
+            "to": ["oncall@ops.example.com", "someone@gmail.com"],
+            "subject": "Report",
+            "body": "secret"
+        }))
+        .await
+        .unwrap_err();
+    assert_eq!(
+        err.to_string(),
+        "Recipient 'someone@gmail.com' is not in the email allowlist"
+    );
+
+    assert!("starttls".parse::<SmtpSecurity>().is_ok());
+    assert!("ssl".parse::<SmtpSecurity>().is_err());
+    assert!(EmailTool::new("relay", "not an address").is_err());
+}
@@ -1,6 +1,9 @@
-//! Tests for filesystem tools: workspace guard, read, write, edit, apply_patch.
+//! Tests for filesystem tools: workspace guard, read, batch read, write, edit, apply_patch.
 
-use operon_adapters::{ApplyPatchTool, EditFileTool, ReadFileTool, WorkspaceGuard, WriteFileTool};
+use operon_adapters::{
+    ApplyPatchTool, EditFileTool, ReadFileTool, ReadFilesTool, WorkspaceGuard, WorkspaceSnapshot,
+    WriteFileTool, WriteLimits,
+};
 use operon_runtime::Tool;
 use serde_json::json;
 use std::sync::Arc;
@@ -51,6 +54,143 @@ async fn test_binary_file_detection() {
     assert!(!WorkspaceGuard::is_text_file(&bin_path).await.unwrap());
 }
 
+#[tokio::test]
+async fn test_write_size_and_quota_limits() {
+    let dir = tempfile::tempdir().unwrap();
+    let guard = Arc::new(
+        WorkspaceGuard::new(dir.path().to_path_buf(), 10)
+            .unwrap()
+            .with_write_limits(WriteLimits {
+                max_file_size: Some(8),
+                quota: Some(12),
+            }),
+    );
+    let write = WriteFileTool::new(guard.clone());
+
+    let err = write
+        .execute(json!({ "path": "big.txt", "content": "123456789" }))
+        .await
+        .unwrap_err();
+    assert!(err.to_string().contains("Write too large"));
+    assert!(!dir.path().join("big.txt").exists());
+
+    write
+        .execute(json!({ "path": "a.txt", "content": "12345" }))
+        .await
+        .unwrap();
+    // Appending counts the existing content toward the file size limit
+    let err = write
+        .execute(json!({ "path": "a.txt", "content": "6789", "mode": "append" }))
+        .await
+        .unwrap_err();
+    assert!(err.to_string().contains("Write too large"));
+
+    write
+        .execute(json!({ "path": "b.txt", "content": "1234567" }))
+        .await
+        .unwrap();
+    assert_eq!(guard.bytes_written(None), 12);
+
+    let edit = EditFileTool::new(guard);
+    let err = edit
+        .execute(json!({ "path": "a.txt", "old_string": "1", "new_string": "0" }))
+        .await
+        .unwrap_err();
+    assert!(err.to_string().contains("Write quota exceeded"));
+    assert_eq!(
+        std::fs::read_to_string(dir.path().join("a.txt")).unwrap(),
+        "12345"
+    );
+}
+
+#[tokio::test]
+async fn test_write_quota_is_per_session_and_skips_failed_writes() {
+    use operon_runtime::tool_middleware::execute_with;
+    use operon_runtime::ToolInvocation;
+
+    let dir = tempfile::tempdir().unwrap();
+    let guard = Arc::new(
+        WorkspaceGuard::new(dir.path().to_path_buf(), 10)
+            .unwrap()
+            .with_write_limits(WriteLimits {
+                max_file_size: None,
+                quota: Some(8),
+            }),
+    );
+    let write = WriteFileTool::new(guard.clone());
+    let call = |session: &str, path: &str, mode: &str| ToolInvocation {
+        tool: "write_file".into(),
+        input: json!({ "path": path, "content": "12345", "mode": mode }),
+        session: Some(session.into()),
+    };
+
+    execute_with(&[], &write, call("s1", "a.txt", "overwrite"))
+        .await
+        .unwrap();
+    // Refused by the filesystem after passing the quota check: not charged
+    let err = execute_with(&[], &write, call("s2", "a.txt", "create_new"))
+        .await
+        .unwrap_err();
+    assert!(err.to_string().contains("already exists"));
+    assert_eq!(guard.bytes_written(Some("s2")), 0);
+
+    // Another session's writes don't use up s1's quota
+    execute_with(&[], &write, call("s2", "b.txt", "overwrite"))
+        .await
+        .unwrap();
+    let err = execute_with(&[], &write, call("s1", "c.txt", "overwrite"))
+        .await
+        .unwrap_err();
+    assert!(err.to_string().contains("Write quota exceeded"));
+    assert_eq!(guard.bytes_written(Some("s1")), 5);
+    assert_eq!(guard.bytes_written(Some("s2")), 5);
+    assert_eq!(guard.bytes_written(None), 0);
+}
+
+#[tokio::test]
+async fn test_write_quota_released_when_session_ends() {
+    use operon_runtime::{PermissionLevel, Runtime};
+    use std::time::Duration;
+
+    let dir = tempfile::tempdir().unwrap();
+    let workspace = dir.path().join("workspace");
+    std::fs::create_dir(&workspace).unwrap();
+    let guard = Arc::new(
+        WorkspaceGuard::new(workspace, 10)
+            .unwrap()
+            .with_write_limits(WriteLimits {
+                max_file_size: None,
+                quota: Some(8),
+            }),
+    );
+    let runtime = Runtime::with_db(
+        dir.path().join("test.db").to_str().unwrap(),
+        false,
+        Duration::from_secs(30),
+    )
+    .unwrap();
+    runtime
+        .register_tool(
+            "write_file".into(),
+            Arc::new(WriteFileTool::new(guard.clone())),
+        )
+        .unwrap();
+
+    runtime
+        .execute_tool_in_session(
+            "write_file",
+            json!({ "path": "a.txt", "content": "12345" }),
+            PermissionLevel::Write,
+            "s1",
+        )
+        .await
+        .unwrap();
+    assert_eq!(guard.bytes_written(Some("s1")), 5);
+
+    runtime.end_session("s1").await.unwrap();
+    assert_eq!(guard.bytes_written(Some("s1")), 0);
+}
+
 // ── ReadFileTool ────────────────────────────────────────────────────────
 
 #[tokio::test]
@@ -110,6 +250,61 @@ async fn test_read_binary_rejected() {
     assert!(result.unwrap_err().to_string().contains("Binary"));
 }
 
+// ── ReadFilesTool ───────────────────────────────────────────────────────
+
+#[tokio::test]
+async fn test_read_files_batch_with_ranges_and_errors() {
+    let dir = tempfile::tempdir().unwrap();
+    std::fs::write(dir.path().join("a.txt"), "a1\na2\n").unwrap();
+    std::fs::write(dir.path().join("b.txt"), "b1\nb2\nb3\nb4\n").unwrap();
+    let tool = ReadFilesTool::new(make_guard(dir.path()));
+
+    let result = tool
+        .execute(json!({"files": [
+            "a.txt",
+            {"path": "b.txt", "offset": 1, "limit": 2},
+            "missing.txt"
+        ]}))
+        .await
+        .unwrap();
+    let files = result["files"].as_array().unwrap();
+    assert_eq!(files.len(), 3);
+    assert_eq!(files[0]["path"], "a.txt");
+    assert_eq!(files[0]["content"], "     1\ta1\n     2\ta2");
+    assert_eq!(files[1]["content"], "     2\tb2\n     3\tb3");
+    assert_eq!(files[1]["total_lines"], 4);
+    assert!(files[2]["error"].as_str().unwrap().contains("not found"));
+    assert_eq!(result["truncated"], false);
+}
+
+#[tokio::test]
+async fn test_read_files_combined_size_cap() {
+    let dir = tempfile::tempdir().unwrap();
+    let long: String = (0..100).map(|i| format!("line {}\n", i)).collect();
+    std::fs::write(dir.path().join("long.txt"), &long).unwrap();
+    std::fs::write(dir.path().join("next.txt"), "next\n").unwrap();
+    let tool = ReadFilesTool::new(make_guard(dir.path()));
+
+    let result = tool
+        .execute(json!({"files": ["long.txt", "next.txt"], "max_bytes": 100}))
+        .await
+        .unwrap();
+    assert_eq!(result["truncated"], true);
+    assert!(result["total_bytes"].as_u64().unwrap() <= 100);
+    let files = result["files"].as_array().unwrap();
+    // Cut at a line boundary
+    let content = files[0]["content"].as_str().unwrap();
+    assert!(content.ends_with(&format!(
+        "\tline {}",
+        files[0]["lines_shown"].as_u64().unwrap() - 1
+    )));
+    assert_eq!(files[0]["truncated"], true);
+    assert!(files[1]["error"].as_str().unwrap().contains("size cap"));
+
+    let too_many: Vec<String> = (0..51).map(|i| format!("{}.txt", i)).collect();
+    assert!(tool.execute(json!({"files": too_many})).await.is_err());
+}
+
 // ── WriteFileTool ───────────────────────────────────────────────────────
 
 #[tokio::test]
@@ -157,6 +352,57 @@ async fn test_write_file_overwrite() {
     );
 }
 
+#[tokio::test]
+async fn test_write_file_append_mode() {
+    let dir = tempfile::tempdir().unwrap();
+    let tool = WriteFileTool::new(make_guard(dir.path()));
+
+    for line in ["first\n", "second\n"] {
+        let result = tool
+            .execute(json!({"path": "notes/log.md", "content": line, "mode": "append"}))
+            .await
+            .unwrap();
+        assert_eq!(result["mode"], "append");
+    }
+    assert_eq!(
+        std::fs::read_to_string(dir.path().join("notes/log.md")).unwrap(),
+        "first\nsecond\n"
+    );
+}
+
+#[tokio::test]
+async fn test_write_file_create_new_refuses_existing() {
+    let dir = tempfile::tempdir().unwrap();
+    std::fs::write(dir.path().join("important.txt"), "keep").unwrap();
+    let tool = WriteFileTool::new(make_guard(dir.path()));
+
+    let err = tool
+        .execute(json!({"path": "important.txt", "content": "oops", "mode": "create_new"}))
+        .await
+        .unwrap_err();
+    assert!(err.to_string().contains("already exists"), "{}", err);
+    assert_eq!(
+        std::fs::read_to_string(dir.path().join("important.txt")).unwrap(),
+        "keep"
+    );
+
+    tool.execute(json!({"path": "fresh.txt", "content": "new", "mode": "create_new"}))
+        .await
+        .unwrap();
+    assert_eq!(
+        std::fs::read_to_string(dir.path().join("fresh.txt")).unwrap(),
+        "new"
+    );
+
+    let result = tool
+        .execute(json!({"path": "x.txt", "content": "", "mode": "truncate"}))
+        .await;
+    assert!(result
+        .unwrap_err()
+        .to_string()
+        .contains("Unknown write mode"));
+}
+
 #[tokio::test]
 async fn test_write_file_path_traversal() {
     let dir = tempfile::tempdir().unwrap();
@@ -243,6 +489,64 @@ async fn test_edit_ambiguous_match() {
     assert!(result.unwrap_err().to_string().contains("not unique"));
 }
 
+#[tokio::test]
+async fn test_edit_regex_with_captures_and_preview() {
+    let dir = tempfile::tempdir().unwrap();
+    let original = "let a  =  1;\nlet b = 2;\n";
+    std::fs::write(dir.path().join("vars.rs"), original).unwrap();
+
+    let tool = EditFileTool::new(make_guard(dir.path()));
+    let input = json!({
+        "path": "vars.rs",
+        "old_string": r"let (\w+)\s*=\s*(\d+);",
+        "new_string": "const ${1}: i32 = $2;",
+        "regex": true,
+        "replace_all": true,
+        "preview": true
+    });
+    let preview = tool.execute(input.clone()).await.unwrap();
+    assert_eq!(preview["preview"], true);
+    assert_eq!(preview["replacements"], 2);
+    assert_eq!(preview["changes"][1]["line"], 2);
+    assert_eq!(preview["changes"][0]["replacement"], "const a: i32 = 1;");
+    assert_eq!(
+        std::fs::read_to_string(dir.path().join("vars.rs")).unwrap(),
+        original
+    );
+
+    let mut limited = input.clone();
+    limited["preview"] = json!(false);
+    limited["max_replacements"] = json!(1);
+    let err = tool.execute(limited).await.unwrap_err();
+    assert!(err.to_string().contains("max_replacements"));
+
+    let mut apply = input;
+    apply["preview"] = json!(false);
+    let result = tool.execute(apply).await.unwrap();
+    assert_eq!(result["replacements"], 2);
+    assert_eq!(
+        std::fs::read_to_string(dir.path().join("vars.rs")).unwrap(),
+        "const a: i32 = 1;\nconst b: i32 = 2;\n"
+    );
+}
+
+#[tokio::test]
+async fn test_edit_invalid_regex() {
+    let dir = tempfile::tempdir().unwrap();
+    std::fs::write(dir.path().join("f.txt"), "hello").unwrap();
+
+    let tool = EditFileTool::new(make_guard(dir.path()));
+    let result = tool
+        .execute(json!({
+            "path": "f.txt",
+            "old_string": "(unclosed",
+            "new_string": "x",
+            "regex": true
+        }))
+        .await;
+    assert!(result.unwrap_err().to_string().contains("Invalid regex"));
+}
+
 // ── ApplyPatchTool ──────────────────────────────────────────────────────
 
 #[tokio::test]
@@ -270,6 +574,48 @@ async fn test_apply_patch_simple() {
     assert!(!content.contains("\nline2\n"));
 }
 
+#[tokio::test]
+async fn test_apply_patch_reverse_restores_original() {
+    let dir = tempfile::tempdir().unwrap();
+    let original = "a\nb\nc\nd\ne\nf\ng\nh\n";
+    std::fs::write(dir.path().join("file.txt"), original).unwrap();
+
+    // The first hunk adds lines, so the second starts later in the new file
+    let patch = "\
+--- a/file.txt
++++ b/file.txt
+@@ -1,3 +1,5 @@
+ a
++a1
++a2
+ b
+ c
+@@ -6,3 +8,2 @@
+ f
+-g
+ h
+";
+    let tool = ApplyPatchTool::new(make_guard(dir.path()));
+    tool.execute(json!({"patch": patch})).await.unwrap();
+    let patched = std::fs::read_to_string(dir.path().join("file.txt")).unwrap();
+    assert_eq!(patched, "a\na1\na2\nb\nc\nd\ne\nf\nh\n");
+
+    let result = tool
+        .execute(json!({"patch": patch, "reverse": true}))
+        .await
+        .unwrap();
+    assert_eq!(result["hunks_applied"], 2);
+    assert_eq!(result["reversed"], true);
+    let content = std::fs::read_to_string(dir.path().join("file.txt")).unwrap();
+    assert_eq!(content, original);
+
+    // Reversing a patch that is not applied fails instead of corrupting the file
+    let result = tool.execute(json!({"patch": patch, "reverse": true})).await;
+    assert!(result.unwrap_err().to_string().contains("mismatch"));
+    let content = std::fs::read_to_string(dir.path().join("file.txt")).unwrap();
+    assert_eq!(content, original);
+}
+
 #[tokio::test]
 async fn test_apply_patch_file_not_found() {
     let dir = tempfile::tempdir().unwrap();
@@ -295,3 +641,65 @@ async fn test_apply_patch_invalid() {
         .await;
     assert!(result.is_err());
 }
+
+// ── WorkspaceSnapshot ───────────────────────────────────────────────────
+
+#[tokio::test]
+async fn test_snapshot_restore_reverts_tool_changes() {
+    let dir = tempfile::tempdir().unwrap();
+    let snapshots = tempfile::tempdir().unwrap();
+    std::fs::write(dir.path().join("a.txt"), "original\n").unwrap();
+
+    let snapshot = Arc::new(WorkspaceSnapshot::new(snapshots.path(), "s1", dir.path()).unwrap());
+    let guard = Arc::new(
+        WorkspaceGuard::new(dir.path().to_path_buf(), 10)
+            .unwrap()
+            .with_snapshot(snapshot.clone()),
+    );
+    let write = WriteFileTool::new(guard.clone());
+    let edit = EditFileTool::new(guard);
+
+    edit.execute(json!({"path": "a.txt", "old_string": "original", "new_string": "edited"}))
+        .await
+        .unwrap();
+    // Second change to the same file keeps the first original
+    write
+        .execute(json!({"path": "a.txt", "content": "rewritten\n"}))
+        .await
+        .unwrap();
+    write
+        .execute(json!({"path": "new/deep/b.txt", "content": "new"}))
+        .await
+        .unwrap();
+
+    let listed = WorkspaceSnapshot::list(snapshots.path()).unwrap();
+    assert_eq!(listed.len(), 1);
+    assert_eq!(listed[0].id, "s1");
+
+    let summary = WorkspaceSnapshot::restore(snapshots.path(), "s1").unwrap();
+    assert_eq!((summary.restored, summary.removed), (1, 1));
+    assert_eq!(
+        std::fs::read_to_string(dir.path().join("a.txt")).unwrap(),
+        "original\n"
+    );
+    // Created file and its new directories are gone, as is the snapshot
+    assert!(!dir.path().join("new").exists());
+    assert!(WorkspaceSnapshot::list(snapshots.path())
+        .unwrap()
+        .is_empty());
+}
+
+#[test]
+fn test_snapshot_rejects_bad_ids_and_is_lazy() {
+    let dir = tempfile::tempdir().unwrap();
+    let snapshots = tempfile::tempdir().unwrap();
+    assert!(WorkspaceSnapshot::new(snapshots.path(), "../escape", dir.path()).is_err());
+    assert!(WorkspaceSnapshot::restore(snapshots.path(), "a/b").is_err());
+
+    // Nothing is written until a file is recorded
+    let snapshot = WorkspaceSnapshot::new(snapshots.path(), "empty", dir.path()).unwrap();
+    assert_eq!(snapshot.id(), "empty");
+    assert!(WorkspaceSnapshot::list(snapshots.path())
+        .unwrap()
+        .is_empty());
+}
@@ -0,0 +1,210 @@
+//! GitHubTool against a local HTTP server standing in for the GitHub API, and
+//! a bare repository standing in for the `origin` remote.
+
+use operon_adapters::GitHubTool;
+use operon_runtime::Tool;
+use serde_json::{json, Value};
+use std::path::Path;
+use std::process::Command;
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::TcpListener;
+
+/// A request the fake API received: request line, lowercased headers, body
+struct Received {
+    line: String,
+    headers: String,
+    body: String,
+}
+
+/// Answer one request per entry of `responses` (status, body) in order and
+/// hand back what was received
+async fn api(responses: Vec<(u16, String)>) -> (String, tokio::task::JoinHandle<Vec<Received>>) {
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let url = format!("http://{}", listener.local_addr().unwrap());
+    let handle = tokio::spawn(async move {
+        let mut received = Vec::new();
+        for (status, response_body) in responses {
+            let (mut socket, _) = listener.accept().await.unwrap();
+            let mut request = Vec::new();
+            let mut chunk = [0u8; 4096];
+            let (head, body) = loop {
+                let n = socket.read(&mut chunk).await.unwrap();
+                request.extend_from_slice(&chunk[..n]);
+                let text = String::from_utf8_lossy(&request).to_string();
+                if let Some((head, body)) = text.split_once("\r\n\r\n") {
+                    let length: usize = head
+                        .lines()
+                        .find_map(|l| {
+                            l.to_lowercase()
+                                .strip_prefix("content-length:")
+                                .map(|v| v.trim().parse().unwrap())
+                        })
+                        .unwrap_or(0);
+                    if body.len() >= length {
+                        break (head.to_string(), body.to_string());
+                    }
+                }
+            };
+            let (line, headers) = head.split_once("\r\n").unwrap_or((&head, ""));
+            received.push(Received {
+                line: line.to_string(),
+                headers: headers.to_lowercase(),
+                body,
+            });
+            let response = format!(
+                "HTTP/1.1 {} X\r\ncontent-length: {}\r\nconnection: close\r\n\r\n{}",
+                status,
+                response_body.len(),
+                response_body
+            );
+            socket.write_all(response.as_bytes()).await.unwrap();
+        }
+        received
+    });
+    (url, handle)
+}
+
+fn git(dir: &Path, args: &[&str]) -> String {
+    let output = Command::new("git")
+        .arg("-C")
+        .arg(dir)
+        .args(["-c", "user.name=test", "-c", "user.email=test@example.com"])
+        .args(args)
+        .output()
+        .unwrap();
+    assert!(output.status.success(), "git {:?} failed", args);
+    String::from_utf8_lossy(&output.stdout).trim().to_string()
+}
+
+#[tokio::test]
+async fn test_list_issues_skips_pull_requests() {
+    let issues = json!([
+        {"number": 7, "title": "Crash on start", "state": "open", "labels": [{"name": "bug"}], "html_url": "https://github.com/acme/app/issues/7"},
+        {"number": 8, "title": "Fix crash", "state": "open", "labels": [], "pull_request": {}, "html_url": "https://github.com/acme/app/pull/8"}
+    ]);
+    let (url, requests) = api(vec![(200, issues.to_string())]).await;
+    let tool = GitHubTool::new("acme/app", "secret")
+        .unwrap()
+        .with_api_url(&url);
+
+    let result = tool
+        .execute(json!({"action": "list_issues", "labels": "bug", "limit": 5}))
+        .await
+        .unwrap();
+
+    assert_eq!(
+        result["issues"],
+        json!([{"number": 7, "title": "Crash on start", "state": "open", "labels": ["bug"], "url": "https://github.com/acme/app/issues/7"}])
+    );
+    let requests = requests.await.unwrap();
+    assert!(requests[0]
+        .line
+        .starts_with("GET /repos/acme/app/issues?state=open&per_page=5&labels=bug "));
+    assert!(requests[0].headers.contains("authorization: bearer secret"));
+}
+
+#[tokio::test]
+async fn test_open_pr_pushes_workspace_branch() {
+    let dir = tempfile::tempdir().unwrap();
+    let origin = dir.path().join("acme/app.git");
+    let workspace = dir.path().join("workspace");
+    std::fs::create_dir_all(&origin).unwrap();
+    std::fs::create_dir_all(&workspace).unwrap();
+    git(&origin, &["init", "--bare", "-q"]);
+    git(&workspace, &["init", "-q", "-b", "main"]);
+    std::fs::write(workspace.join("README.md"), "hello\n").unwrap();
+    git(&workspace, &["add", "."]);
+    git(&workspace, &["commit", "-q", "-m", "init"]);
+
+    let (url, requests) = api(vec![(
+        201,
+        json!({"number": 12, "html_url": "https://github.com/acme/app/pull/12"}).to_string(),
+    )])
+    .await;
+    let tool = GitHubTool::new("acme/app", "secret")
+        .unwrap()
+        .with_api_url(&url)
+        .with_git_url(dir.path().to_str().unwrap())
+        .with_workspace(&workspace);
+
+    // Still on the base branch: nothing to propose
+    let err = tool
+        .execute(json!({"action": "open_pr", "title": "Fix"}))
+        .await
+        .unwrap_err();
+    assert!(err.to_string().contains("feature branch"));
+
+    git(&workspace, &["checkout", "-q", "-b", "fix-crash"]);
+    std::fs::write(workspace.join("README.md"), "fixed\n").unwrap();
+    git(&workspace, &["commit", "-q", "-am", "Fix crash"]);
+
+    let result = tool
+        .execute(
+            json!({"action": "open_pr", "title": "Fix crash", "body": "Closes #7", "draft": true}),
+        )
+        .await
+        .unwrap();
+
+    assert_eq!(result["number"], 12);
+    assert_eq!(result["head"], "fix-crash");
+    assert_eq!(
+        git(&origin, &["rev-parse", "refs/heads/fix-crash"]),
+        git(&workspace, &["rev-parse", "HEAD"])
+    );
+    let requests = requests.await.unwrap();
+    assert!(requests[0].line.starts_with("POST /repos/acme/app/pulls "));
+    let body: Value = serde_json::from_str(&requests[0].body).unwrap();
+    assert_eq!(
+        body,
+        json!({"title": "Fix crash", "body": "Closes #7", "head": "fix-crash", "base": "main", "draft": true})
+    );
+}
+
+#[tokio::test]
+async fn test_actions_can_be_restricted_and_diffs_are_capped() {
+    let (url, requests) = api(vec![
+        (200, "diff --git a/x b/x\n+added\n".to_string()),
+        (404, json!({"message": "Not Found"}).to_string()),
+    ])
+    .await;
+    let tool = GitHubTool::new("acme/app", "secret")
+        .unwrap()
+        .with_api_url(&url)
+        .with_max_diff_bytes(10)
+        .with_actions(vec!["get_pr_diff".into()])
+        .unwrap();
+
+    let err = tool
+        .execute(json!({"action": "create_issue", "title": "x"}))
+        .await
+        .unwrap_err();
+    assert!(err.to_string().contains("not enabled"));
+
+    let result = tool
+        .execute(json!({"action": "get_pr_diff", "number": 3}))
+        .await
+        .unwrap();
+    assert_eq!(result["diff"], "diff --git");
+    assert_eq!(result["truncated"], true);
+
+    let err = tool
+        .execute(json!({"action": "get_pr_diff", "number": 4}))
+        .await
+        .unwrap_err();
+    assert_eq!(
+        err.to_string(),
+        "GitHub API returned 404 Not Found: Not Found"
+    );
+
+    let requests = requests.await.unwrap();
+    assert!(requests[0].line.starts_with("GET /repos/acme/app/pulls/3 "));
+    assert!(requests[0]
+        .headers
+        .contains("accept: application/vnd.github.diff"));
+
+    assert!(GitHubTool::new("acme", "secret").is_err());
+    assert!(GitHubTool::new("acme/app", "secret")
+        .unwrap()
+        .with_actions(vec!["merge_pr".into()])
+        .is_err());
+}
@@ -0,0 +1,142 @@
+//! KubernetesJobTool against a fake kubectl script that records the manifest
+//! it is given and reports the job state from a file.
+
+use operon_adapters::KubernetesJobTool;
+use operon_runtime::Tool;
+use serde_json::{json, Value};
+use std::path::Path;
+use std::time::Duration;
+
+/// kubectl stand-in: `create` saves stdin, `get` prints the condition named
+/// in `state` (none while it is "running"), `logs` prints two lines
+fn fake_kubectl(dir: &Path, state: &str) -> String {
+    std::fs::write(dir.join("state"), state).unwrap();
+    let script = dir.join("kubectl.sh");
+    std::fs::write(
+        &script,
+        format!(
+            r#"#!/bin/sh
+dir={dir}
+echo "$@" >> $dir/kubectl.log
+while [ "${{1#--}}" != "$1" ]; do shift 2; done
+case "$1" in
+  create) cat > $dir/manifest.json ;;
+  get)
+    state=$(cat $dir/state)
+    if [ "$state" = running ]; then
+      echo '{{"status": {{"active": 1}}}}'
+    else
+      echo "{{\"status\": {{\"conditions\": [{{\"type\": \"$state\", \"status\": \"True\"}}]}}}}"
+    fi
+    ;;
+  logs) printf 'step 1\nstep 2\n' ;;
+esac
+"#,
+            dir = dir.display()
+        ),
+    )
+    .unwrap();
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        std::fs::set_permissions(&script, std::fs::Permissions::from_mode(0o755)).unwrap();
+    }
+    script.display().to_string()
+}
+
+#[test]
+fn test_default_template_renders_valid_manifest() {
+    let tool = KubernetesJobTool::new("batch").with_resources("2", "1Gi");
+    let manifest = tool.render(
+        "report-1",
+        "python:3.12",
+        &["python".into(), "-c".into(), "print(\"hi\")".into()],
+        Duration::from_secs(120),
+    );
+
+    let manifest: Value = serde_json::from_str(&manifest).unwrap();
+    assert_eq!(manifest["metadata"]["name"], "report-1");
+    assert_eq!(manifest["metadata"]["namespace"], "batch");
+    assert_eq!(manifest["spec"]["activeDeadlineSeconds"], 120);
+    let container = &manifest["spec"]["template"]["spec"]["containers"][0];
+    assert_eq!(container["image"], "python:3.12");
+    assert_eq!(container["command"][2], "print(\"hi\")");
+    assert_eq!(container["resources"]["limits"]["cpu"], "2");
+    assert_eq!(container["resources"]["limits"]["memory"], "1Gi");
+}
+
+#[test]
+fn test_values_cannot_inject_into_the_manifest() {
+    let tool = KubernetesJobTool::new("batch").with_template(
+        "name: {{name}}\nimage: {{image}}\ncommand: {{command}}\nother: {{unknown}}\n".into(),
+    );
+    let manifest = tool.render(
+        "job-1",
+        "evil:{{command}}\u{2028}privileged: true",
+        &["sh".into(), "-c".into(), "\"}, {{name}}".into()],
+        Duration::from_secs(60),
+    );
+
+    // Placeholders inside values stay literal, YAML line breaks are escaped
+    assert_eq!(
+        manifest,
+        "name: \"job-1\"\n\
+         image: \"evil:{{command}}\\u2028privileged: true\"\n\
+         command: [\"sh\",\"-c\",\"\\\"}, {{name}}\"]\n\
+         other: {{unknown}}\n"
+    );
+}
+
+#[tokio::test]
+async fn test_job_runs_to_completion_and_returns_logs() {
+    let dir = tempfile::tempdir().unwrap();
+    let tool = KubernetesJobTool::new("batch")
+        .with_context("staging")
+        .with_kubectl(&fake_kubectl(dir.path(), "Complete"))
+        .with_poll_interval(Duration::from_millis(10));
+
+    let result = tool
+        .execute(json!({"image": "alpine", "command": "echo hi", "name": "Nightly Report"}))
+        .await
+        .unwrap();
+
+    assert_eq!(result["status"], "succeeded");
+    assert_eq!(result["logs"], "step 1\nstep 2\n");
+    let job = result["job"].as_str().unwrap();
+    assert!(job.starts_with("nightly-report-"));
+
+    let manifest: Value =
+        serde_json::from_str(&std::fs::read_to_string(dir.path().join("manifest.json")).unwrap())
+            .unwrap();
+    assert_eq!(manifest["metadata"]["name"], job);
+    assert_eq!(
+        manifest["spec"]["template"]["spec"]["containers"][0]["command"],
+        json!(["sh", "-c", "echo hi"])
+    );
+    let log = std::fs::read_to_string(dir.path().join("kubectl.log")).unwrap();
+    assert!(log.starts_with("--context staging --namespace batch create -f -"));
+    assert!(!log.contains("delete"));
+}
+
+#[tokio::test]
+async fn test_job_past_timeout_is_deleted() {
+    let dir = tempfile::tempdir().unwrap();
+    let tool = KubernetesJobTool::new("batch")
+        .with_kubectl(&fake_kubectl(dir.path(), "running"))
+        .with_timeout(Duration::from_secs(60))
+        .with_poll_interval(Duration::from_millis(50))
+        .with_max_log_bytes(7);
+
+    // The call's own timeout applies (whole seconds), capped by the tool's
+    let result = tool
+        .execute(json!({"image": "alpine", "command": ["sleep", "600"], "timeout_secs": 1}))
+        .await
+        .unwrap();
+
+    assert_eq!(result["status"], "timed_out");
+    assert_eq!(result["logs"], "step 2\n");
+    assert_eq!(result["logs_truncated"], true);
+    let log = std::fs::read_to_string(dir.path().join("kubectl.log")).unwrap();
+    let job = result["job"].as_str().unwrap();
+    assert!(log.contains(&format!("delete job/{} --ignore-not-found", job)));
+}
@@ -0,0 +1,128 @@
+//! Notifications against a one-request local HTTP server standing in for the webhook.
+
+use operon_adapters::notify::summary;
+use operon_adapters::{NotificationHook, Notifier, NotifyTool, WebhookKind};
+use operon_runtime::{Hook, HookContext, HookEvent, Tool};
+use serde_json::{json, Value};
+use std::sync::Arc;
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::TcpListener;
+
+/// Accept one POST, answer `status`, and hand back its JSON body
+async fn webhook(status: u16) -> (String, tokio::task::JoinHandle<Value>) {
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let url = format!("http://{}/hook", listener.local_addr().unwrap());
+    let handle = tokio::spawn(async move {
+        let (mut socket, _) = listener.accept().await.unwrap();
+        let mut request = Vec::new();
+        let mut chunk = [0u8; 4096];
+        let body = loop {
+            let n = socket.read(&mut chunk).await.unwrap();
+            request.extend_from_slice(&chunk[..n]);
+            let text = String::from_utf8_lossy(&request).to_string();
+            if let Some((head, body)) = text.split_once("\r\n\r\n") {
+                let length: usize = head
+                    .lines()
+                    .find_map(|l| {
+                        l.to_lowercase()
+                            .strip_prefix("content-length:")
+                            .map(|v| v.trim().parse().unwrap())
+                    })
+                    .unwrap_or(0);
+                if body.len() >= length {
+                    break body.to_string();
+                }
+            }
+        };
+        let response = format!(
+            "HTTP/1.1 {} X\r\ncontent-length: 0\r\nconnection: close\r\n\r\n",
+            status
+        );
+        socket.write_all(response.as_bytes()).await.unwrap();
+        serde_json::from_str(&body).unwrap()
+    });
+    (url, handle)
+}
+
+fn ctx(event: HookEvent, data: Value) -> HookContext {
+    HookContext {
+        event,
+        data,
+        agent_id: None,
+        session_id: Some("s-42".into()),
+    }
+}
+
+#[tokio::test]
+async fn test_notify_tool_posts_slack_payload() {
+    let (url, request) = webhook(200).await;
+    let tool = NotifyTool::new(Arc::new(Notifier::new(WebhookKind::Slack, &url)));
+
+    let result = tool
+        .execute(json!({"message": "deploy done"}))
+        .await
+        .unwrap();
+    assert_eq!(result["sent"], true);
+    assert_eq!(request.await.unwrap(), json!({"text": "deploy done"}));
+}
+
+#[tokio::test]
+async fn test_plan_end_hook_posts_discord_summary_and_reports_errors() {
+    let (url, request) = webhook(200).await;
+    let hook = NotificationHook::new(
+        Arc::new(Notifier::new(WebhookKind::Discord, &url)),
+        vec![HookEvent::PlanEnd],
+    );
+    hook.on_event(&ctx(
+        HookEvent::PlanEnd,
+        json!({"plan_id": "nightly", "status": "succeeded", "duration_ms": 125_000, "steps": 4, "dry_run": false}),
+    ))
+    .await
+    .unwrap();
+    assert_eq!(
+        request.await.unwrap(),
+        json!({"content": "Plan `nightly` succeeded in 2m 5s, 4 steps"})
+    );
+
+    let (url, _request) = webhook(500).await;
+    let hook = NotificationHook::new(
+        Arc::new(Notifier::new(WebhookKind::Discord, &url)),
+        vec![HookEvent::PlanEnd],
+    );
+    let err = hook
+        .on_event(&ctx(HookEvent::PlanEnd, json!({"plan_id": "p"})))
+        .await
+        .unwrap_err();
+    assert!(err.to_string().contains("500"));
+}
+
+#[test]
+fn test_summaries_cover_result_duration_and_cost() {
+    let failed = summary(&ctx(
+        HookEvent::PlanEnd,
+        json!({"plan_id": "deploy", "status": "failed", "error": "step 'b' timed out", "duration_ms": 1500, "dry_run": true}),
+    ))
+    .unwrap();
+    assert_eq!(
+        failed,
+        "Plan `deploy` failed after 1.5s (dry-run): step 'b' timed out"
+    );
+
+    let session = summary(&ctx(
+        HookEvent::SessionEnd,
+        json!({
+            "agent": "reviewer",
+            "result": "Found 2 issues.\nSee PR.",
+            "messages": 6,
+            "duration_ms": 3_725_000,
+            "usage": {"input_tokens": 1000, "output_tokens": 234}
+        }),
+    ))
+    .unwrap();
+    assert_eq!(
+        session,
+        "Session `s-42` (reviewer) ended after 1h 2m: 6 messages, 1234 tokens (1000 in / 234 out)\n> Found 2 issues.\n> See PR."
+    );
+
+    assert!(summary(&ctx(HookEvent::ConfigReload, json!({}))).is_none());
+}
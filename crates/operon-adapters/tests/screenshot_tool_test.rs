@@ -0,0 +1,72 @@
+use operon_adapters::{CaptureTarget, ScreenshotTool};
+use operon_runtime::{PermissionLevel, Tool};
+use serde_json::json;
+
+const PNG: &[u8] = b"\x89PNG\r\n\x1a\nfake image body";
+
+/// Fake capture command that copies `source` to the output path
+fn copy_command(source: &std::path::Path) -> Vec<String> {
+    vec![
+        "cp".to_string(),
+        source.to_string_lossy().to_string(),
+        "{output}".to_string(),
+    ]
+}
+
+#[tokio::test]
+async fn test_capture_screen_returns_image() {
+    let dir = tempfile::tempdir().unwrap();
+    let source = dir.path().join("screen.png");
+    std::fs::write(&source, PNG).unwrap();
+    let tool = ScreenshotTool::new(CaptureTarget::Screen).with_command(copy_command(&source));
+
+    assert_eq!(tool.name(), "capture_screen");
+    assert_eq!(tool.permission_level(), PermissionLevel::Admin);
+    let output = tool.execute(json!({})).await.unwrap();
+    assert_eq!(output["image"]["mime"], "image/png");
+    assert_eq!(output["bytes"], PNG.len());
+    assert_eq!(output["target"], "screen");
+    use base64::Engine;
+    let data = base64::engine::general_purpose::STANDARD
+        .decode(output["image"]["data"].as_str().unwrap())
+        .unwrap();
+    assert_eq!(data, PNG);
+}
+
+#[tokio::test]
+async fn test_capture_window_substitutes_window_id() {
+    let dir = tempfile::tempdir().unwrap();
+    std::fs::write(dir.path().join("0x3a00007.png"), PNG).unwrap();
+    let source = dir.path().join("{window}.png");
+    let tool = ScreenshotTool::new(CaptureTarget::Window).with_command(copy_command(&source));
+
+    let output = tool.execute(json!({"window": "0x3a00007"})).await.unwrap();
+    assert_eq!(output["target"], "window 0x3a00007");
+
+    let err = tool.execute(json!({})).await.unwrap_err();
+    assert!(err.to_string().contains("Missing 'window'"));
+    let err = tool.execute(json!({"window": "-help"})).await.unwrap_err();
+    assert!(err.to_string().contains("Invalid window id"));
+}
+
+#[tokio::test]
+async fn test_capture_rejects_bad_output() {
+    let dir = tempfile::tempdir().unwrap();
+    let source = dir.path().join("notes.txt");
+    std::fs::write(&source, "not an image").unwrap();
+
+    let tool = ScreenshotTool::new(CaptureTarget::Screen).with_command(vec!["true".to_string()]);
+    let err = tool.execute(json!({})).await.unwrap_err();
+    assert!(err.to_string().contains("produced no image"));
+
+    let tool = ScreenshotTool::new(CaptureTarget::Screen).with_command(copy_command(&source));
+    let err = tool.execute(json!({})).await.unwrap_err();
+    assert!(err.to_string().contains("PNG or JPEG"));
+
+    std::fs::write(&source, PNG).unwrap();
+    let tool = ScreenshotTool::new(CaptureTarget::Screen)
+        .with_command(copy_command(&source))
+        .with_max_bytes(4);
+    let err = tool.execute(json!({})).await.unwrap_err();
+    assert!(err.to_string().contains("byte limit"));
+}
@@ -1,6 +1,7 @@
-use operon_adapters::ShellTool;
-use operon_runtime::Tool;
+use operon_adapters::{SandboxProgram, ShellSandbox, ShellTool};
+use operon_runtime::{PermissionLevel, Tool};
 use serde_json::json;
+use std::time::{Duration, Instant};
 
 #[tokio::test]
 async fn test_shell_tool_execute_echo() {
@@ -131,3 +132,108 @@ async fn test_shell_tool_blocks_eval() {
         .await;
     assert!(result.is_err());
 }
+
+#[tokio::test]
+async fn test_shell_tool_timeout_reports_partial_output() {
+    let tool = ShellTool::new(false)
+        .with_timeout(Duration::from_millis(300))
+        .with_grace_period(Duration::from_secs(5));
+    let start = Instant::now();
+    let result = tool
+        .execute(json!({"cmd": "echo started; sleep 30"}))
+        .await
+        .unwrap();
+
+    assert_eq!(result["timed_out"], true);
+    assert_eq!(result["signal"], "SIGTERM");
+    assert_eq!(result["exit_code"], -1);
+    assert_eq!(result["stdout"], "started\n");
+    // SIGTERM was enough: no need to wait out the grace period
+    assert!(start.elapsed() < Duration::from_secs(3));
+}
+
+#[cfg(target_os = "linux")]
+#[tokio::test]
+async fn test_shell_tool_timeout_kills_process_group() {
+    let tool = ShellTool::new(false)
+        .with_timeout(Duration::from_millis(300))
+        .with_grace_period(Duration::from_millis(300));
+    // The shell and its background child both ignore SIGTERM
+    let result = tool
+        .execute(json!({"cmd": "trap '' TERM; sleep 30 & echo $!; wait"}))
+        .await
+        .unwrap();
+
+    assert_eq!(result["timed_out"], true);
+    assert_eq!(result["signal"], "SIGKILL");
+    let pid: u32 = result["stdout"].as_str().unwrap().trim().parse().unwrap();
+    tokio::time::sleep(Duration::from_millis(100)).await;
+    // Gone, or a zombie waiting to be reaped by init
+    let stat = std::fs::read_to_string(format!("/proc/{}/stat", pid)).unwrap_or_default();
+    assert!(stat.is_empty() || stat.contains(") Z "), "{}", stat);
+}
+
+#[tokio::test]
+async fn test_shell_tool_max_duration_covers_grace_period() {
+    assert_eq!(ShellTool::new(false).max_duration(), None);
+    let tool = ShellTool::new(false)
+        .with_timeout(Duration::from_secs(30))
+        .with_grace_period(Duration::from_secs(5));
+    assert!(tool.max_duration().unwrap() > Duration::from_secs(35));
+}
+
+// ── ShellSandbox ────────────────────────────────────────────────────────
+
+fn args(sandbox: &ShellSandbox) -> Vec<String> {
+    let (_, args) = sandbox.command_line("ls");
+    args.iter()
+        .map(|a| a.to_string_lossy().into_owned())
+        .collect()
+}
+
+#[test]
+fn test_bubblewrap_follows_permission_level() {
+    let dir = tempfile::tempdir().unwrap();
+    let ws = dir.path().canonicalize().unwrap().display().to_string();
+
+    let sandbox = ShellSandbox::new(SandboxProgram::Bubblewrap, dir.path())
+        .unwrap()
+        .with_level(PermissionLevel::Read);
+    let read = args(&sandbox);
+    assert_eq!(sandbox.command_line("ls").0, "bwrap");
+    assert!(read.windows(3).any(|w| w == ["--ro-bind", &ws, &ws]));
+    assert!(read.contains(&"--unshare-all".to_string()));
+    assert!(!read.contains(&"--share-net".to_string()));
+    assert_eq!(read[read.len() - 4..], ["--", "sh", "-c", "ls"]);
+
+    let write = args(&sandbox.clone().with_level(PermissionLevel::Write));
+    assert!(write.windows(3).any(|w| w == ["--bind", &ws, &ws]));
+    assert!(!write.contains(&"--share-net".to_string()));
+
+    let network = args(&sandbox.with_level(PermissionLevel::Network));
+    assert!(network.contains(&"--share-net".to_string()));
+}
+
+#[test]
+fn test_firejail_follows_permission_level_and_program_parses() {
+    let dir = tempfile::tempdir().unwrap();
+    let ws = dir.path().canonicalize().unwrap().display().to_string();
+
+    assert_eq!(
+        "bwrap".parse::<SandboxProgram>().unwrap(),
+        SandboxProgram::Bubblewrap
+    );
+    assert!("docker".parse::<SandboxProgram>().is_err());
+
+    let sandbox = ShellSandbox::new(SandboxProgram::Firejail, dir.path())
+        .unwrap()
+        .with_level(PermissionLevel::Read);
+    let read = args(&sandbox);
+    assert!(read.contains(&format!("--whitelist={}", ws)));
+    assert!(read.contains(&format!("--read-only={}", ws)));
+    assert!(read.contains(&"--net=none".to_string()));
+
+    let network = args(&sandbox.with_level(PermissionLevel::Admin));
+    assert!(!network.iter().any(|a| a.starts_with("--read-only")));
+    assert!(!network.contains(&"--net=none".to_string()));
+}
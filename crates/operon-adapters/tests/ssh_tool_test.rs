@@ -0,0 +1,142 @@
+//! SSH tools against a fake client script that runs the remote command
+//! locally, the way `ssh host -- cmd` hands it to the remote shell.
+
+use operon_adapters::{register_ssh_tools, SshShellTool, SshTarget};
+use operon_runtime::{Runtime, Tool};
+use serde_json::json;
+use std::path::Path;
+use std::sync::Arc;
+use std::time::Duration;
+
+/// Client stand-in: logs its arguments and runs everything after `--` with sh
+fn fake_ssh(dir: &Path) -> String {
+    let script = dir.join("ssh.sh");
+    std::fs::write(
+        &script,
+        format!(
+            r#"#!/bin/sh
+echo "$@" >> {log}
+while [ "$1" != "--" ]; do shift; done
+shift
+exec sh -c "$1"
+"#,
+            log = dir.join("ssh.log").display()
+        ),
+    )
+    .unwrap();
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        std::fs::set_permissions(&script, std::fs::Permissions::from_mode(0o755)).unwrap();
+    }
+    script.display().to_string()
+}
+
+#[test]
+fn test_ssh_args_use_key_auth_and_remote_root() {
+    let target = SshTarget::new("build.example.com")
+        .with_user("deploy")
+        .with_port(2222)
+        .with_identity_file(Path::new("/keys/id_ed25519"))
+        .with_remote_root("/srv/app's");
+
+    let args = target.args("ls -la");
+    let joined = args.join(" ");
+    assert!(joined.starts_with("-o BatchMode=yes -p 2222"));
+    assert!(joined.contains("-i /keys/id_ed25519 -o IdentitiesOnly=yes"));
+    assert_eq!(
+        &args[args.len() - 3..args.len() - 1],
+        ["deploy@build.example.com", "--"]
+    );
+    assert_eq!(args.last().unwrap(), r"cd '/srv/app'\''s' && ls -la");
+}
+
+#[tokio::test]
+async fn test_ssh_shell_validates_and_limits_output() {
+    let dir = tempfile::tempdir().unwrap();
+    let remote = tempfile::tempdir().unwrap();
+    let target = SshTarget::new("host")
+        .with_program(&fake_ssh(dir.path()))
+        .with_remote_root(&remote.path().display().to_string())
+        .with_max_output_bytes(16);
+    let tool = SshShellTool::new(Arc::new(target), false)
+        .with_validation(vec!["shutdown".into()], vec!["ls".into(), "seq".into()]);
+    std::fs::write(remote.path().join("marker.txt"), "").unwrap();
+
+    // Commands start in the remote root
+    let result = tool.execute(json!({"cmd": "ls"})).await.unwrap();
+    assert_eq!(result["exit_code"], 0);
+    assert_eq!(result["stdout"], "marker.txt\n");
+    assert_eq!(result["truncated"], false);
+
+    let result = tool.execute(json!({"cmd": "seq 1 10000"})).await.unwrap();
+    assert_eq!(result["stdout"].as_str().unwrap().len(), 16);
+    assert_eq!(result["truncated"], true);
+
+    let err = tool
+        .execute(json!({"cmd": "shutdown now"}))
+        .await
+        .unwrap_err();
+    assert!(err.to_string().contains("config blocklist"));
+    let err = tool.execute(json!({"cmd": "rm -rf /"})).await.unwrap_err();
+    assert!(err.to_string().contains("dangerous pattern"));
+    let err = tool
+        .execute(json!({"cmd": "ls && whoami"}))
+        .await
+        .unwrap_err();
+    assert!(err.to_string().contains("shell operator"));
+
+    // Rejected commands never reach the client
+    let log = std::fs::read_to_string(dir.path().join("ssh.log")).unwrap();
+    assert_eq!(log.lines().count(), 2);
+}
+
+#[tokio::test]
+async fn test_ssh_file_tools_round_trip_under_remote_root() {
+    let dir = tempfile::tempdir().unwrap();
+    let remote = tempfile::tempdir().unwrap();
+    let target = SshTarget::new("host")
+        .with_program(&fake_ssh(dir.path()))
+        .with_remote_root(&remote.path().display().to_string());
+    let runtime = Runtime::with_db(
+        dir.path().join("state.db").to_str().unwrap(),
+        false,
+        Duration::from_secs(10),
+    )
+    .unwrap();
+    register_ssh_tools(&runtime, target, false, Vec::new(), Vec::new()).unwrap();
+
+    let written = runtime
+        .execute_tool(
+            "ssh_write_file",
+            json!({"path": "conf/app.toml", "content": "port = 8080\n"}),
+        )
+        .await
+        .unwrap();
+    assert_eq!(written["bytes_written"], 12);
+    assert_eq!(
+        std::fs::read_to_string(remote.path().join("conf/app.toml")).unwrap(),
+        "port = 8080\n"
+    );
+
+    let read = runtime
+        .execute_tool("ssh_read_file", json!({"path": "conf/app.toml"}))
+        .await
+        .unwrap();
+    assert_eq!(read["content"], "port = 8080\n");
+    assert_eq!(read["truncated"], false);
+
+    let err = runtime
+        .execute_tool("ssh_read_file", json!({"path": "missing.txt"}))
+        .await
+        .unwrap_err();
+    assert!(format!("{:#}", err).contains("Failed to read remote file"));
+
+    for path in ["../outside.txt", "/etc/passwd"] {
+        let err = runtime
+            .execute_tool("ssh_read_file", json!({"path": path}))
+            .await
+            .unwrap_err();
+        assert!(format!("{:#}", err).contains("relative to the remote root"));
+    }
+}
@@ -0,0 +1,150 @@
+//! Tool call approvals asked of the clients watching a session.
+
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex, OnceLock};
+use std::time::Duration;
+
+use anyhow::{anyhow, Result};
+use async_trait::async_trait;
+use operon_runtime::{ApprovalRequest, Approver};
+use tokio::sync::{broadcast, oneshot, RwLock};
+use tracing::warn;
+
+use crate::types::SessionEvent;
+
+/// Event channels of the live sessions, by session ID
+pub(crate) type EventBuses = Arc<RwLock<HashMap<String, broadcast::Sender<SessionEvent>>>>;
+
+/// How long a held call waits for an answer by default
+const DEFAULT_TIMEOUT: Duration = Duration::from_secs(300);
+
+struct PendingApproval {
+    session_id: String,
+    answer: oneshot::Sender<bool>,
+}
+
+/// Removes a held call's pending entry however its wait ends: answered,
+/// timed out, or dropped with a cancelled turn or a closed connection
+struct PendingGuard<'a> {
+    pending: &'a Mutex<HashMap<String, PendingApproval>>,
+    call_id: &'a str,
+}
+
+impl Drop for PendingGuard<'_> {
+    fn drop(&mut self) {
+        self.pending
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .remove(self.call_id);
+    }
+}
+
+/// Asks a session's WebSocket clients whether a tool call may run: sends
+/// `approval_required`, then waits for an answer posted to
+/// /api/v1/sessions/{id}/approvals/{call_id}. Calls nobody is watching, or
+/// that get no answer in time, are denied.
+pub struct ApprovalBroker {
+    pending: Mutex<HashMap<String, PendingApproval>>,
+    buses: OnceLock<EventBuses>,
+    timeout: Duration,
+}
+
+impl ApprovalBroker {
+    pub fn new() -> Self {
+        Self {
+            pending: Mutex::new(HashMap::new()),
+            buses: OnceLock::new(),
+            timeout: DEFAULT_TIMEOUT,
+        }
+    }
+
+    /// How long a held call waits for an answer (default 5 minutes)
+    pub fn with_timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = timeout;
+        self
+    }
+
+    /// Send requests to the clients of these session channels
+    pub(crate) fn connect(&self, buses: EventBuses) {
+        let _ = self.buses.set(buses);
+    }
+
+    /// Answer the request `call_id` of session `session_id`
+    pub fn resolve(&self, session_id: &str, call_id: &str, approved: bool) -> Result<()> {
+        let mut pending = self.pending.lock().unwrap();
+        match pending.get(call_id) {
+            Some(request) if request.session_id == session_id => {}
+            _ => {
+                return Err(anyhow!(
+                    "No pending approval {} in session {}",
+                    call_id,
+                    session_id
+                ))
+            }
+        }
+        let request = pending
+            .remove(call_id)
+            .expect("pending approval checked above");
+        let _ = request.answer.send(approved);
+        Ok(())
+    }
+}
+
+impl Default for ApprovalBroker {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[async_trait]
+impl Approver for ApprovalBroker {
+    async fn approve(&self, request: &ApprovalRequest) -> Result<bool> {
+        let Some(session_id) = request.session_id.as_deref() else {
+            warn!(tool = %request.tool_name, "No session to ask for approval");
+            return Ok(false);
+        };
+        let bus = match self.buses.get() {
+            Some(buses) => buses.read().await.get(session_id).cloned(),
+            None => None,
+        };
+        let Some(bus) = bus else {
+            return Ok(false);
+        };
+
+        let (answer, answered) = oneshot::channel();
+        self.pending.lock().unwrap().insert(
+            request.call_id.clone(),
+            PendingApproval {
+                session_id: session_id.to_string(),
+                answer,
+            },
+        );
+        let _pending = PendingGuard {
+            pending: &self.pending,
+            call_id: &request.call_id,
+        };
+        let event = SessionEvent::ApprovalRequired {
+            call_id: request.call_id.clone(),
+            tool: request.tool_name.clone(),
+            input: request.input.clone(),
+            description: request.description.clone(),
+        };
+        if bus.send(event).is_err() {
+            warn!(session_id, tool = %request.tool_name, "No client watching the session to ask for approval");
+            return Ok(false);
+        }
+
+        match tokio::time::timeout(self.timeout, answered).await {
+            Ok(Ok(approved)) => Ok(approved),
+            _ => {
+                warn!(
+                    session_id,
+                    call_id = %request.call_id,
+                    "Tool call approval timed out after {}s",
+                    self.timeout.as_secs()
+                );
+                Ok(false)
+            }
+        }
+    }
+}
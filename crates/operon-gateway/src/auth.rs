@@ -2,35 +2,101 @@ use axum::extract::Request;
 use axum::http::StatusCode;
 use axum::middleware::Next;
 use axum::response::{IntoResponse, Response};
+use std::collections::{HashMap, HashSet};
 use std::sync::Arc;
 use subtle::ConstantTimeEq;
 
+/// Who a request is made by: the API key name, `default` for the shared
+/// token, or the client address when auth is off
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Identity(pub String);
+
+/// Name of the identity that authenticates with `api_token`
+pub const DEFAULT_IDENTITY: &str = "default";
+
 /// Bearer token authentication state
 #[derive(Clone)]
 pub struct AuthConfig {
     pub api_token: Option<String>,
+    /// Named API keys (name -> token); the name becomes the request's identity
+    pub api_keys: HashMap<String, String>,
+    /// Paths served without a token; a trailing `*` matches any suffix
+    pub exempt_paths: Vec<String>,
+    /// Identities allowed to see every caller's records (e.g. the audit trail)
+    pub admins: HashSet<String>,
 }
 
 impl AuthConfig {
     pub fn new(api_token: Option<String>) -> Self {
-        Self { api_token }
+        Self {
+            api_token,
+            api_keys: HashMap::new(),
+            exempt_paths: vec!["/health".to_string()],
+            admins: HashSet::new(),
+        }
+    }
+
+    pub fn with_api_keys(mut self, api_keys: HashMap<String, String>) -> Self {
+        self.api_keys = api_keys;
+        self
+    }
+
+    /// Replace the default exemption (`/health`)
+    pub fn with_exempt_paths(mut self, exempt_paths: Vec<String>) -> Self {
+        self.exempt_paths = exempt_paths;
+        self
+    }
+
+    pub fn with_admins(mut self, admins: impl IntoIterator<Item = String>) -> Self {
+        self.admins = admins.into_iter().collect();
+        self
+    }
+
+    /// Whether `identity` may see other callers' records; everyone may when
+    /// auth is off
+    pub fn is_admin(&self, identity: Option<&str>) -> bool {
+        !self.is_enabled() || identity.is_some_and(|identity| self.admins.contains(identity))
+    }
+
+    pub fn is_exempt(&self, path: &str) -> bool {
+        self.exempt_paths
+            .iter()
+            .any(|exempt| match exempt.strip_suffix('*') {
+                Some(prefix) => path.starts_with(prefix),
+                None => path == exempt,
+            })
     }
 
     pub fn is_enabled(&self) -> bool {
-        self.api_token.is_some()
+        self.api_token.is_some() || !self.api_keys.is_empty()
+    }
+
+    /// Identity a bearer token belongs to
+    fn identify(&self, token: &str) -> Option<Identity> {
+        let matches =
+            |expected: &str| -> bool { token.as_bytes().ct_eq(expected.as_bytes()).into() };
+        if let Some(name) = self
+            .api_keys
+            .iter()
+            .find_map(|(name, key)| matches(key).then_some(name))
+        {
+            return Some(Identity(name.clone()));
+        }
+        self.api_token
+            .as_deref()
+            .filter(|expected| matches(expected))
+            .map(|_| Identity(DEFAULT_IDENTITY.to_string()))
     }
 }
 
 /// Authentication middleware for API endpoints
 pub async fn auth_middleware(
     auth_config: Arc<AuthConfig>,
-    request: Request,
+    mut request: Request,
     next: Next,
 ) -> Response {
-    let path = request.uri().path();
-
-    // Skip auth for health endpoint
-    if path == "/health" {
+    // Skip auth for exempt endpoints (/health by default)
+    if auth_config.is_exempt(request.uri().path()) {
         return next.run(request).await;
     }
 
@@ -48,10 +114,9 @@ pub async fn auth_middleware(
     match auth_header {
         Some(header) if header.starts_with("Bearer ") => {
             let token = &header[7..];
-            if let Some(expected_token) = &auth_config.api_token {
-                if token.as_bytes().ct_eq(expected_token.as_bytes()).into() {
-                    return next.run(request).await;
-                }
+            if let Some(identity) = auth_config.identify(token) {
+                request.extensions_mut().insert(identity);
+                return next.run(request).await;
             }
         }
         _ => {}
@@ -1,10 +1,14 @@
+pub mod approvals;
 pub mod auth;
+pub mod quota;
 pub mod rate_limiter;
 pub mod server;
 pub mod session_manager;
 pub mod types;
 
-pub use auth::AuthConfig;
-pub use rate_limiter::RateLimiter;
-pub use server::{create_router, start_server, AppState};
-pub use session_manager::SessionManager;
+pub use approvals::ApprovalBroker;
+pub use auth::{AuthConfig, Identity};
+pub use quota::{QuotaLimits, QuotaTracker};
+pub use rate_limiter::{client_ip, RateLimiter};
+pub use server::{create_router, start_server, AppState, Reloadable};
+pub use session_manager::{ProviderFactory, SessionManager};
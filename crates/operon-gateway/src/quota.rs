@@ -0,0 +1,280 @@
+//! Daily and monthly request/token quotas per gateway identity, counted in a
+//! Storage so they survive restarts.
+
+use std::collections::HashMap;
+use std::sync::Mutex;
+
+use anyhow::Result;
+use axum::extract::Request;
+use axum::http::{HeaderValue, StatusCode};
+use axum::middleware::Next;
+use axum::response::{IntoResponse, Response};
+use chrono::{DateTime, Datelike, NaiveDate, Utc};
+use operon_runtime::storage::blocking;
+use operon_runtime::Storage;
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use std::sync::Arc;
+use tracing::{info, warn};
+
+use crate::auth::{AuthConfig, Identity};
+
+/// Limits for one identity; `None` means unlimited
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct QuotaLimits {
+    #[serde(default)]
+    pub daily_requests: Option<u64>,
+    #[serde(default)]
+    pub daily_tokens: Option<u64>,
+    #[serde(default)]
+    pub monthly_requests: Option<u64>,
+    #[serde(default)]
+    pub monthly_tokens: Option<u64>,
+}
+
+/// Requests and LLM tokens counted in one period
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+pub struct QuotaUsage {
+    pub requests: u64,
+    pub tokens: u64,
+}
+
+/// The quota a request ran into
+#[derive(Debug, Clone, PartialEq)]
+pub struct QuotaExceeded {
+    /// e.g. "daily-tokens"
+    pub scope: String,
+    pub limit: u64,
+    pub used: u64,
+    /// When the period's counters start over
+    pub reset: DateTime<Utc>,
+}
+
+#[derive(Clone, Copy)]
+enum Period {
+    Day,
+    Month,
+}
+
+impl Period {
+    fn key(self, now: DateTime<Utc>) -> String {
+        match self {
+            Period::Day => format!("day/{}", now.format("%Y-%m-%d")),
+            Period::Month => format!("month/{}", now.format("%Y-%m")),
+        }
+    }
+
+    fn name(self) -> &'static str {
+        match self {
+            Period::Day => "daily",
+            Period::Month => "monthly",
+        }
+    }
+
+    /// Start of the next period (UTC)
+    fn reset(self, now: DateTime<Utc>) -> DateTime<Utc> {
+        let today = now.date_naive();
+        let next = match self {
+            Period::Day => today.succ_opt(),
+            Period::Month if today.month() == 12 => NaiveDate::from_ymd_opt(today.year() + 1, 1, 1),
+            Period::Month => NaiveDate::from_ymd_opt(today.year(), today.month() + 1, 1),
+        };
+        next.and_then(|date| date.and_hms_opt(0, 0, 0))
+            .map(|time| time.and_utc())
+            .unwrap_or(now)
+    }
+
+    fn limits(self, limits: &QuotaLimits) -> (Option<u64>, Option<u64>) {
+        match self {
+            Period::Day => (limits.daily_requests, limits.daily_tokens),
+            Period::Month => (limits.monthly_requests, limits.monthly_tokens),
+        }
+    }
+}
+
+/// Counts requests and tokens per identity and enforces their limits. Its
+/// methods hit storage; async callers run them through `storage::blocking`.
+pub struct QuotaTracker {
+    storage: Storage,
+    default_limits: QuotaLimits,
+    limits: HashMap<String, QuotaLimits>,
+    /// Serializes read-modify-write of the counters; holds the day expired
+    /// counters were last evicted
+    lock: Mutex<Option<NaiveDate>>,
+}
+
+impl QuotaTracker {
+    /// Counters kept in `storage`; every identity gets `default_limits`
+    pub fn new(storage: Storage, default_limits: QuotaLimits) -> Self {
+        Self {
+            storage,
+            default_limits,
+            limits: HashMap::new(),
+            lock: Mutex::new(None),
+        }
+    }
+
+    /// Limits for one identity instead of the defaults
+    pub fn with_limits(mut self, identity: &str, limits: QuotaLimits) -> Self {
+        self.limits.insert(identity.to_string(), limits);
+        self
+    }
+
+    fn limits_for(&self, identity: &str) -> &QuotaLimits {
+        self.limits.get(identity).unwrap_or(&self.default_limits)
+    }
+
+    /// Usage of `identity` so far today and this month
+    pub fn usage(&self, identity: &str) -> Result<(QuotaUsage, QuotaUsage)> {
+        let now = Utc::now();
+        Ok((
+            self.load(identity, Period::Day, now)?,
+            self.load(identity, Period::Month, now)?,
+        ))
+    }
+
+    /// Count a request for `identity`, or report the quota it is over
+    pub fn admit(&self, identity: &str) -> Result<Option<QuotaExceeded>> {
+        // The guarded state is only the eviction day, so a poisoned lock is
+        // still good to use
+        let mut evicted_on = self.lock.lock().unwrap_or_else(|e| e.into_inner());
+        let now = Utc::now();
+        if *evicted_on != Some(now.date_naive()) {
+            self.evict_expired(now)?;
+            *evicted_on = Some(now.date_naive());
+        }
+        let limits = self.limits_for(identity);
+        let mut usage = Vec::new();
+        for period in [Period::Day, Period::Month] {
+            let used = self.load(identity, period, now)?;
+            let (max_requests, max_tokens) = period.limits(limits);
+            for (kind, used, limit) in [
+                ("requests", used.requests, max_requests),
+                ("tokens", used.tokens, max_tokens),
+            ] {
+                if let Some(limit) = limit.filter(|limit| used >= *limit) {
+                    return Ok(Some(QuotaExceeded {
+                        scope: format!("{}-{}", period.name(), kind),
+                        limit,
+                        used,
+                        reset: period.reset(now),
+                    }));
+                }
+            }
+            usage.push((period, used));
+        }
+        for (period, mut used) in usage {
+            used.requests += 1;
+            self.store(identity, period, now, used)?;
+        }
+        Ok(None)
+    }
+
+    /// Charge LLM tokens to `identity`
+    pub fn add_tokens(&self, identity: &str, tokens: u64) -> Result<()> {
+        if tokens == 0 {
+            return Ok(());
+        }
+        let _guard = self.lock.lock().unwrap_or_else(|e| e.into_inner());
+        let now = Utc::now();
+        for period in [Period::Day, Period::Month] {
+            let mut used = self.load(identity, period, now)?;
+            used.tokens += tokens;
+            self.store(identity, period, now, used)?;
+        }
+        Ok(())
+    }
+
+    /// Drop the counters of past days and months, so identities seen once
+    /// (e.g. client addresses when auth is off) do not pile up
+    fn evict_expired(&self, now: DateTime<Utc>) -> Result<()> {
+        let current = [Period::Day, Period::Month].map(|period| format!("/{}", period.key(now)));
+        let mut evicted = 0;
+        for key in self.storage.list_keys()? {
+            if !current.iter().any(|suffix| key.ends_with(suffix.as_str())) {
+                evicted += self.storage.delete_prefix(&key)?;
+            }
+        }
+        if evicted > 0 {
+            info!(evicted, "Evicted expired quota counters");
+        }
+        Ok(())
+    }
+
+    fn load(&self, identity: &str, period: Period, now: DateTime<Utc>) -> Result<QuotaUsage> {
+        let key = format!("{}/{}", identity, period.key(now));
+        Ok(match self.storage.load_state(&key)? {
+            Some(value) => serde_json::from_value(value)?,
+            None => QuotaUsage::default(),
+        })
+    }
+
+    fn store(
+        &self,
+        identity: &str,
+        period: Period,
+        now: DateTime<Utc>,
+        usage: QuotaUsage,
+    ) -> Result<()> {
+        let key = format!("{}/{}", identity, period.key(now));
+        self.storage.save_state(&key, &serde_json::to_value(usage)?)
+    }
+}
+
+/// Quota middleware: runs after auth, which sets the request's [`Identity`]
+/// (the client address stands in when auth is off). Paths exempt from auth
+/// are not counted either. Requests over quota get 429 with `X-Quota-*` and
+/// `Retry-After` headers.
+pub async fn quota_middleware(
+    quota: Option<Arc<QuotaTracker>>,
+    auth: Arc<AuthConfig>,
+    client: Identity,
+    mut request: Request,
+    next: Next,
+) -> Response {
+    let Some(quota) = quota else {
+        return next.run(request).await;
+    };
+    if auth.is_exempt(request.uri().path()) {
+        return next.run(request).await;
+    }
+    let identity = request
+        .extensions()
+        .get::<Identity>()
+        .cloned()
+        .unwrap_or(client);
+
+    let id = identity.0.clone();
+    match blocking(move || quota.admit(&id)).await {
+        Ok(None) => {}
+        Ok(Some(exceeded)) => {
+            info!(identity = %identity.0, scope = %exceeded.scope, limit = exceeded.limit, "Quota exceeded");
+            return quota_exceeded_response(&exceeded);
+        }
+        // Counting is best effort; a broken quota store must not take the API down
+        Err(e) => warn!(identity = %identity.0, error = %e, "Failed to check quota"),
+    }
+    request.extensions_mut().insert(identity);
+    next.run(request).await
+}
+
+fn quota_exceeded_response(exceeded: &QuotaExceeded) -> Response {
+    let retry_after = (exceeded.reset - Utc::now()).num_seconds().max(1);
+    let body = json!({
+        "error": format!("Quota exceeded: {} limit of {}", exceeded.scope, exceeded.limit),
+    });
+    let mut response = (StatusCode::TOO_MANY_REQUESTS, axum::Json(body)).into_response();
+    let headers = response.headers_mut();
+    for (name, value) in [
+        ("x-quota-scope", exceeded.scope.clone()),
+        ("x-quota-limit", exceeded.limit.to_string()),
+        ("x-quota-used", exceeded.used.to_string()),
+        ("x-quota-reset", exceeded.reset.to_rfc3339()),
+        ("retry-after", retry_after.to_string()),
+    ] {
+        if let Ok(value) = HeaderValue::from_str(&value) {
+            headers.insert(name, value);
+        }
+    }
+    response
+}
@@ -1,10 +1,9 @@
-use axum::extract::ConnectInfo;
 use axum::extract::Request;
-use axum::http::StatusCode;
+use axum::http::{HeaderMap, StatusCode};
 use axum::middleware::Next;
 use axum::response::{IntoResponse, Response};
 use dashmap::DashMap;
-use std::net::{IpAddr, SocketAddr};
+use std::net::IpAddr;
 use std::sync::Arc;
 use tokio::time::Instant;
 
@@ -53,9 +52,20 @@ impl RateLimiter {
     }
 }
 
+/// Client address for rate limits and quotas: the first trusted proxy header
+/// that holds an IP, else the peer. For `X-Forwarded-For` the last entry is
+/// used, since that is the one the nearest proxy appended.
+pub fn client_ip(headers: &HeaderMap, peer: IpAddr, trusted_headers: &[String]) -> IpAddr {
+    trusted_headers
+        .iter()
+        .filter_map(|name| headers.get(name.as_str())?.to_str().ok())
+        .find_map(|value| value.rsplit(',').next()?.trim().parse().ok())
+        .unwrap_or(peer)
+}
+
 /// Rate limiting middleware
 pub async fn rate_limit_middleware(
-    ConnectInfo(addr): ConnectInfo<SocketAddr>,
+    ip: IpAddr,
     rate_limiter: Arc<RateLimiter>,
     request: Request,
     next: Next,
@@ -65,8 +75,6 @@ pub async fn rate_limit_middleware(
         return next.run(request).await;
     }
 
-    let ip = addr.ip();
-
     if !rate_limiter.check(ip) {
         return (StatusCode::TOO_MANY_REQUESTS, "Rate limit exceeded").into_response();
     }
@@ -1,19 +1,23 @@
 use std::net::SocketAddr;
-use std::sync::Arc;
+use std::sync::{Arc, RwLock};
 
 use axum::extract::ws::{Message, WebSocket};
-use axum::extract::{ConnectInfo, Path, State, WebSocketUpgrade};
+use axum::extract::{ConnectInfo, Path, Query, Request, State, WebSocketUpgrade};
 use axum::http::StatusCode;
 use axum::middleware;
 use axum::response::IntoResponse;
-use axum::routing::{get, post};
+use axum::routing::{get, post, put};
+use axum::Extension;
 use axum::{Json, Router};
-use tower_http::cors::{Any, CorsLayer};
+use operon_runtime::storage::blocking;
+use operon_runtime::{AuditFilter, AuditRecord, Content, PlanSchedule};
+use tower_http::cors::{AllowOrigin, Any, CorsLayer};
 use tower_http::trace::TraceLayer;
 use tracing::info;
 
-use crate::auth::{auth_middleware, AuthConfig};
-use crate::rate_limiter::{rate_limit_middleware, RateLimiter};
+use crate::auth::{auth_middleware, AuthConfig, Identity};
+use crate::quota::quota_middleware;
+use crate::rate_limiter::{client_ip, rate_limit_middleware, RateLimiter};
 use crate::session_manager::SessionManager;
 use crate::types::*;
 
@@ -21,32 +25,63 @@ use crate::types::*;
 #[derive(Clone)]
 pub struct AppState {
     pub session_manager: Arc<SessionManager>,
-    pub auth_config: Arc<AuthConfig>,
+    pub auth_config: Reloadable<AuthConfig>,
     pub rate_limiter: Arc<RateLimiter>,
-    pub allowed_origins: Vec<String>,
+    /// Origins allowed by CORS; empty = any (for development)
+    pub allowed_origins: Reloadable<Vec<String>>,
+    /// Headers (e.g. `X-Forwarded-For`) set by a reverse proxy in front of the
+    /// gateway, trusted for the client address; empty = use the peer address
+    pub trusted_proxy_headers: Vec<String>,
+}
+
+/// A setting the running gateway reads on each request, so a config reload
+/// can replace it without a restart
+pub struct Reloadable<T>(Arc<RwLock<Arc<T>>>);
+
+impl<T> Reloadable<T> {
+    pub fn new(value: T) -> Self {
+        Self(Arc::new(RwLock::new(Arc::new(value))))
+    }
+
+    /// The current value
+    pub fn get(&self) -> Arc<T> {
+        self.0.read().unwrap_or_else(|e| e.into_inner()).clone()
+    }
+
+    /// Replace the value for every request from now on
+    pub fn set(&self, value: T) {
+        *self.0.write().unwrap_or_else(|e| e.into_inner()) = Arc::new(value);
+    }
+}
+
+impl<T> Clone for Reloadable<T> {
+    fn clone(&self) -> Self {
+        Self(self.0.clone())
+    }
 }
 
 /// Create the Axum router with all routes
 pub fn create_router(state: AppState) -> Router {
-    // Build CORS layer
-    let cors = if state.allowed_origins.is_empty() {
-        // Permissive for development
-        CorsLayer::permissive()
-    } else {
-        CorsLayer::new()
-            .allow_origin(
-                state
-                    .allowed_origins
+    // Origins are checked per request so reloads apply; none listed allows
+    // any (for development)
+    let origins = state.allowed_origins.clone();
+    let cors = CorsLayer::new()
+        .allow_origin(AllowOrigin::predicate(move |origin, _| {
+            let origins = origins.get();
+            origins.is_empty()
+                || origins
                     .iter()
-                    .map(|s| s.parse().unwrap())
-                    .collect::<Vec<_>>(),
-            )
-            .allow_methods(Any)
-            .allow_headers(Any)
-    };
+                    .any(|allowed| allowed.as_bytes() == origin.as_bytes())
+        }))
+        .allow_methods(Any)
+        .allow_headers(Any);
 
     let auth_config = state.auth_config.clone();
+    let quota_auth_config = auth_config.clone();
     let rate_limiter = state.rate_limiter.clone();
+    let quota = state.session_manager.quota();
+    let proxy_headers = Arc::new(state.trusted_proxy_headers.clone());
+    let quota_proxy_headers = proxy_headers.clone();
 
     Router::new()
         .route("/health", get(health_check))
@@ -56,16 +91,40 @@ pub fn create_router(state: AppState) -> Router {
             get(get_session).delete(delete_session),
         )
         .route("/api/v1/sessions/{id}/messages", post(send_message))
+        .route("/api/v1/sessions/{id}/model", put(switch_model))
+        .route("/api/v1/sessions/{id}/fork", post(fork_session))
+        .route("/api/v1/sessions/{id}/messages/async", post(submit_message))
+        .route(
+            "/api/v1/sessions/{id}/approvals/{call_id}",
+            post(answer_approval),
+        )
+        .route("/api/v1/jobs/{id}", get(get_job))
+        .route("/api/v1/plans/schedule", post(plan_schedule))
+        .route("/api/v1/audit", get(list_audit_records))
         .route("/ws/sessions/{id}", get(ws_upgrade))
+        // Quotas are counted per identity, so they run after auth too
+        .layer(middleware::from_fn(
+            move |ConnectInfo(addr): ConnectInfo<SocketAddr>, req: Request, next| {
+                let ip = client_ip(req.headers(), addr.ip(), &quota_proxy_headers);
+                quota_middleware(
+                    quota.clone(),
+                    quota_auth_config.get(),
+                    Identity(ip.to_string()),
+                    req,
+                    next,
+                )
+            },
+        ))
         // Rate limiter runs after auth (innermost = last in request pipeline)
         .layer(middleware::from_fn(
-            move |addr: ConnectInfo<SocketAddr>, req, next| {
+            move |ConnectInfo(addr): ConnectInfo<SocketAddr>, req: Request, next| {
+                let ip = client_ip(req.headers(), addr.ip(), &proxy_headers);
                 let rl = rate_limiter.clone();
-                async move { rate_limit_middleware(addr, rl, req, next).await }
+                async move { rate_limit_middleware(ip, rl, req, next).await }
             },
         ))
         .layer(middleware::from_fn(move |req, next| {
-            auth_middleware(auth_config.clone(), req, next)
+            auth_middleware(auth_config.get(), req, next)
         }))
         .layer(TraceLayer::new_for_http())
         .layer(cors)
@@ -91,10 +150,28 @@ pub async fn start_server(state: AppState, host: &str, port: u16) -> anyhow::Res
     Ok(())
 }
 
+/// Resolves on Ctrl+C, or SIGTERM on Unix (e.g. `warden serve stop` or init scripts)
 async fn shutdown_signal() {
-    tokio::signal::ctrl_c()
-        .await
-        .expect("Failed to listen for Ctrl+C");
+    let ctrl_c = async {
+        tokio::signal::ctrl_c()
+            .await
+            .expect("Failed to listen for Ctrl+C");
+    };
+
+    #[cfg(unix)]
+    let terminate = async {
+        tokio::signal::unix::signal(tokio::signal::unix::SignalKind::terminate())
+            .expect("Failed to listen for SIGTERM")
+            .recv()
+            .await;
+    };
+    #[cfg(not(unix))]
+    let terminate = std::future::pending::<()>();
+
+    tokio::select! {
+        _ = ctrl_c => {}
+        _ = terminate => {}
+    }
     info!("Shutdown signal received, draining connections...");
     tokio::time::sleep(tokio::time::Duration::from_secs(10)).await;
     info!("Drain complete, shutting down");
@@ -102,11 +179,30 @@ async fn shutdown_signal() {
 
 // --- REST Handlers ---
 
-async fn health_check() -> Json<HealthResponse> {
-    Json(HealthResponse {
-        status: "ok".to_string(),
-        version: env!("CARGO_PKG_VERSION").to_string(),
-    })
+async fn health_check(State(state): State<AppState>) -> (StatusCode, Json<HealthResponse>) {
+    let providers = state.session_manager.provider_health().await;
+    let healthy = providers.iter().filter(|p| p.healthy).count();
+
+    let (code, status) = if providers.is_empty() {
+        // No probe has run yet (startup check off, first refresh pending)
+        (StatusCode::OK, "unknown")
+    } else if healthy == providers.len() {
+        (StatusCode::OK, "ok")
+    } else if healthy > 0 {
+        (StatusCode::OK, "degraded")
+    } else {
+        (StatusCode::SERVICE_UNAVAILABLE, "unhealthy")
+    };
+
+    (
+        code,
+        Json(HealthResponse {
+            status: status.to_string(),
+            version: env!("CARGO_PKG_VERSION").to_string(),
+            providers,
+            queue: state.session_manager.queue_stats(),
+        }),
+    )
 }
 
 async fn create_session(
@@ -116,7 +212,7 @@ async fn create_session(
     let agent_name = req.agent_id.as_deref();
     match state.session_manager.create(agent_name).await {
         Ok(session_id) => {
-            let (name, created_at, count) = state
+            let (name, created_at, count, usage) = state
                 .session_manager
                 .get_session_info(&session_id)
                 .await
@@ -128,11 +224,13 @@ async fn create_session(
                     agent_name: name,
                     created_at,
                     message_count: count,
+                    usage,
                 }),
             ))
         }
+        // Only an unknown agent_id makes session creation fail
         Err(e) => Err((
-            StatusCode::INTERNAL_SERVER_ERROR,
+            StatusCode::BAD_REQUEST,
             Json(ErrorResponse {
                 error: e.to_string(),
             }),
@@ -149,11 +247,12 @@ async fn get_session(
     Path(id): Path<String>,
 ) -> Result<Json<SessionResponse>, (StatusCode, Json<ErrorResponse>)> {
     match state.session_manager.get_session_info(&id).await {
-        Ok((name, created_at, count)) => Ok(Json(SessionResponse {
+        Ok((name, created_at, count, usage)) => Ok(Json(SessionResponse {
             session_id: id,
             agent_name: name,
             created_at,
             message_count: count,
+            usage,
         })),
         Err(e) => Err((
             StatusCode::NOT_FOUND,
@@ -179,11 +278,110 @@ async fn delete_session(
     }
 }
 
+/// Approve or deny a tool call held for approval
+async fn answer_approval(
+    State(state): State<AppState>,
+    Path((id, call_id)): Path<(String, String)>,
+    Json(req): Json<ApprovalAnswer>,
+) -> Result<StatusCode, (StatusCode, Json<ErrorResponse>)> {
+    match state
+        .session_manager
+        .resolve_approval(&id, &call_id, req.approved)
+    {
+        Ok(()) => Ok(StatusCode::NO_CONTENT),
+        Err(e) => Err((
+            StatusCode::NOT_FOUND,
+            Json(ErrorResponse {
+                error: e.to_string(),
+            }),
+        )),
+    }
+}
+
+/// Hand a session off to another provider/model; later turns see its history
+async fn switch_model(
+    State(state): State<AppState>,
+    Path(id): Path<String>,
+    Json(req): Json<SwitchModelRequest>,
+) -> Result<Json<ModelResponse>, (StatusCode, Json<ErrorResponse>)> {
+    let error = |code: StatusCode, message: String| (code, Json(ErrorResponse { error: message }));
+    if let Err(e) = state.session_manager.get_session_info(&id).await {
+        return Err(error(StatusCode::NOT_FOUND, e.to_string()));
+    }
+    state
+        .session_manager
+        .switch_model(&id, &req.provider, &req.model)
+        .await
+        .map_err(|e| error(StatusCode::BAD_REQUEST, e.to_string()))?;
+    Ok(Json(ModelResponse {
+        session_id: id,
+        provider: req.provider,
+        model: req.model,
+    }))
+}
+
+async fn fork_session(
+    State(state): State<AppState>,
+    Path(id): Path<String>,
+    Json(req): Json<ForkSessionRequest>,
+) -> Result<(StatusCode, Json<SessionResponse>), (StatusCode, Json<ErrorResponse>)> {
+    let error = |code: StatusCode, message: String| (code, Json(ErrorResponse { error: message }));
+    if let Err(e) = state.session_manager.get_session_info(&id).await {
+        return Err(error(StatusCode::NOT_FOUND, e.to_string()));
+    }
+    let session_id = state
+        .session_manager
+        .fork(&id, req.at)
+        .await
+        .map_err(|e| error(StatusCode::BAD_REQUEST, e.to_string()))?;
+    let (agent_name, created_at, message_count, usage) = state
+        .session_manager
+        .get_session_info(&session_id)
+        .await
+        .map_err(|e| error(StatusCode::NOT_FOUND, e.to_string()))?;
+    Ok((
+        StatusCode::CREATED,
+        Json(SessionResponse {
+            session_id,
+            agent_name,
+            created_at,
+            message_count,
+            usage,
+        }),
+    ))
+}
+
 const MAX_MESSAGE_LENGTH: usize = 50_000; // 50KB
+const MAX_ATTACHMENTS: usize = 8;
+
+/// Images named by `req.attachments`, loaded from the gateway workspace
+async fn load_attachments(
+    state: &AppState,
+    req: &SendMessageRequest,
+) -> Result<Vec<Content>, (StatusCode, Json<ErrorResponse>)> {
+    let error = |message: String| {
+        (
+            StatusCode::BAD_REQUEST,
+            Json(ErrorResponse { error: message }),
+        )
+    };
+    if req.attachments.len() > MAX_ATTACHMENTS {
+        return Err(error(format!(
+            "At most {} attachments per message",
+            MAX_ATTACHMENTS
+        )));
+    }
+    state
+        .session_manager
+        .load_attachments(&req.attachments)
+        .await
+        .map_err(|e| error(format!("{:#}", e)))
+}
 
 async fn send_message(
     State(state): State<AppState>,
     Path(id): Path<String>,
+    identity: Option<Extension<Identity>>,
     Json(req): Json<SendMessageRequest>,
 ) -> Result<Json<MessageResponse>, (StatusCode, Json<ErrorResponse>)> {
     // Input validation
@@ -199,7 +397,13 @@ async fn send_message(
         ));
     }
 
-    match state.session_manager.send_message(&id, &req.content).await {
+    let attachments = load_attachments(&state, &req).await?;
+    let identity = identity.map(|Extension(identity)| identity.0);
+    match state
+        .session_manager
+        .send_message_as(&id, &req.content, attachments, identity.as_deref())
+        .await
+    {
         Ok(content) => Ok(Json(MessageResponse {
             content,
             session_id: id,
@@ -213,19 +417,140 @@ async fn send_message(
     }
 }
 
+/// Accept a message for background processing; poll the returned job at
+/// /api/v1/jobs/{id} or watch the session's WebSocket for `job_finished`
+async fn submit_message(
+    State(state): State<AppState>,
+    Path(id): Path<String>,
+    identity: Option<Extension<Identity>>,
+    Json(req): Json<SendMessageRequest>,
+) -> Result<(StatusCode, Json<JobResponse>), (StatusCode, Json<ErrorResponse>)> {
+    let error = |code: StatusCode, message: String| (code, Json(ErrorResponse { error: message }));
+    if req.content.len() > MAX_MESSAGE_LENGTH {
+        return Err(error(
+            StatusCode::PAYLOAD_TOO_LARGE,
+            format!(
+                "Message content exceeds maximum length of {} bytes",
+                MAX_MESSAGE_LENGTH
+            ),
+        ));
+    }
+    if let Err(e) = state.session_manager.get_session_info(&id).await {
+        return Err(error(StatusCode::NOT_FOUND, e.to_string()));
+    }
+    let attachments = load_attachments(&state, &req).await?;
+
+    let job_id = state
+        .session_manager
+        .submit_message_as(
+            &id,
+            &req.content,
+            attachments,
+            identity.map(|Extension(i)| i.0),
+        )
+        .await
+        .map_err(|e| error(StatusCode::CONFLICT, e.to_string()))?;
+    let job = state
+        .session_manager
+        .get_job(&job_id)
+        .await
+        .map_err(|e| error(StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
+    Ok((StatusCode::ACCEPTED, Json(job)))
+}
+
+async fn get_job(
+    State(state): State<AppState>,
+    Path(id): Path<String>,
+) -> Result<Json<JobResponse>, (StatusCode, Json<ErrorResponse>)> {
+    state
+        .session_manager
+        .get_job(&id)
+        .await
+        .map(Json)
+        .map_err(|e| {
+            (
+                StatusCode::NOT_FOUND,
+                Json(ErrorResponse {
+                    error: e.to_string(),
+                }),
+            )
+        })
+}
+
+/// Validate a plan and return its execution levels without running it
+async fn plan_schedule(
+    State(state): State<AppState>,
+    Json(plan): Json<serde_json::Value>,
+) -> Result<Json<PlanSchedule>, (StatusCode, Json<ErrorResponse>)> {
+    state
+        .session_manager
+        .plan_schedule(&plan)
+        .map(Json)
+        .map_err(|e| {
+            (
+                StatusCode::UNPROCESSABLE_ENTITY,
+                Json(ErrorResponse {
+                    error: e.to_string(),
+                }),
+            )
+        })
+}
+
+/// Recorded tool calls, newest first; filter with `session_id`, `tool`,
+/// `principal`, `since`/`until` (RFC 3339) and `limit`. Callers other than
+/// admins only see calls made for their own identity.
+async fn list_audit_records(
+    State(state): State<AppState>,
+    Query(mut filter): Query<AuditFilter>,
+    identity: Option<Extension<Identity>>,
+) -> Result<Json<Vec<AuditRecord>>, (StatusCode, Json<ErrorResponse>)> {
+    let identity = identity.map(|Extension(identity)| identity.0);
+    if !state.auth_config.get().is_admin(identity.as_deref()) {
+        let Some(identity) = identity else {
+            return Err((
+                StatusCode::FORBIDDEN,
+                Json(ErrorResponse {
+                    error: "The audit trail needs an API key".to_string(),
+                }),
+            ));
+        };
+        filter.principal = Some(identity);
+    }
+    state
+        .session_manager
+        .audit_records(filter)
+        .await
+        .map(Json)
+        .map_err(|e| {
+            (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                Json(ErrorResponse {
+                    error: e.to_string(),
+                }),
+            )
+        })
+}
+
 // --- WebSocket Handler ---
 
 async fn ws_upgrade(
     ws: WebSocketUpgrade,
     State(state): State<AppState>,
     Path(session_id): Path<String>,
+    identity: Option<Extension<Identity>>,
 ) -> impl IntoResponse {
-    ws.on_upgrade(move |socket| handle_ws_connection(socket, session_id, state))
+    let identity = identity.map(|Extension(identity)| identity.0);
+    ws.on_upgrade(move |socket| handle_ws_connection(socket, session_id, identity, state))
 }
 
 const WS_IDLE_TIMEOUT: tokio::time::Duration = tokio::time::Duration::from_secs(300);
 
-async fn handle_ws_connection(socket: WebSocket, session_id: String, state: AppState) {
+async fn handle_ws_connection(
+    socket: WebSocket,
+    session_id: String,
+    identity: Option<String>,
+    state: AppState,
+) {
     use futures_util::{SinkExt, StreamExt};
     use tokio::time::timeout;
 
@@ -264,10 +589,29 @@ async fn handle_ws_connection(socket: WebSocket, session_id: String, state: AppS
                     if let Ok(client_msg) = serde_json::from_str::<ClientMessage>(&text) {
                         match client_msg {
                             ClientMessage::SendMessage { content } => {
+                                // The upgrade request was counted; count each message too
+                                if let (Some(quota), Some(identity)) = (sm.quota(), &identity) {
+                                    let id = identity.clone();
+                                    if let Ok(Some(exceeded)) =
+                                        blocking(move || quota.admit(&id)).await
+                                    {
+                                        info!(identity = %identity, scope = %exceeded.scope, "Quota exceeded, ignoring WebSocket message");
+                                        continue;
+                                    }
+                                }
                                 let sm = sm.clone();
                                 let sid = sid.clone();
+                                let identity = identity.clone();
                                 tokio::spawn(async move {
-                                    if let Err(e) = sm.send_message(&sid, &content).await {
+                                    if let Err(e) = sm
+                                        .send_message_as(
+                                            &sid,
+                                            &content,
+                                            Vec::new(),
+                                            identity.as_deref(),
+                                        )
+                                        .await
+                                    {
                                         tracing::error!(error = %e, "WebSocket message processing failed");
                                     }
                                 });
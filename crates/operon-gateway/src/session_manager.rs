@@ -3,18 +3,54 @@ use std::sync::Arc;
 
 use anyhow::{anyhow, Result};
 use chrono::{DateTime, Utc};
+use futures_util::StreamExt;
 use tokio::sync::{broadcast, RwLock};
 
-use operon_runtime::{Agent, AgentConfig, LLMProvider, Runtime};
+use operon_adapters::{load_image, WorkspaceGuard};
+use operon_runtime::storage::blocking;
+use operon_runtime::{
+    Agent, AgentConfig, AgentEvent, AuditFilter, AuditRecord, Content, LLMProvider, PlanSchedule,
+    ProviderHealth, QueueStats, Runtime, SessionBackend, Usage,
+};
 
-use crate::types::SessionEvent;
+use crate::approvals::ApprovalBroker;
+use crate::quota::QuotaTracker;
+use crate::types::{JobResponse, JobStatus, SessionEvent};
+
+/// Builds a client for a provider name and model (empty: the provider's
+/// default), used to hand sessions off to another model
+pub type ProviderFactory = Arc<dyn Fn(&str, &str) -> Result<Arc<dyn LLMProvider>> + Send + Sync>;
+
+/// How long finished jobs stay queryable
+const JOB_RETENTION: chrono::TimeDelta = chrono::TimeDelta::hours(1);
+
+/// Audit records returned when a query sets no limit
+const DEFAULT_AUDIT_LIMIT: usize = 100;
 
 /// Manages active agent sessions with broadcast support
 pub struct SessionManager {
     sessions: Arc<RwLock<HashMap<String, AgentSession>>>,
     event_buses: Arc<RwLock<HashMap<String, broadcast::Sender<SessionEvent>>>>,
-    provider: Arc<dyn LLMProvider>,
+    /// Asynchronous message jobs by job ID
+    jobs: Arc<RwLock<HashMap<String, JobResponse>>>,
+    /// Provider new sessions start on
+    provider: RwLock<Arc<dyn LLMProvider>>,
     runtime: Arc<Runtime>,
+    /// Last provider health check result, served by /health
+    provider_health: Arc<RwLock<Vec<ProviderHealth>>>,
+    /// Agent definitions sessions select by `agent_id`
+    agents: HashMap<String, AgentConfig>,
+    /// Settings for sessions created without an `agent_id`
+    default_agent: AgentConfig,
+    provider_factory: Option<ProviderFactory>,
+    /// Request/token quotas enforced per identity
+    quota: Option<Arc<QuotaTracker>>,
+    /// Where message attachments are read from
+    workspace: Option<Arc<WorkspaceGuard>>,
+    /// Where held tool calls wait for their answers
+    approvals: Option<Arc<ApprovalBroker>>,
+    /// Where sessions are saved after each message
+    store: Option<Arc<dyn SessionBackend>>,
 }
 
 /// Active agent session
@@ -29,19 +65,163 @@ impl SessionManager {
         Self {
             sessions: Arc::new(RwLock::new(HashMap::new())),
             event_buses: Arc::new(RwLock::new(HashMap::new())),
-            provider,
+            jobs: Arc::new(RwLock::new(HashMap::new())),
+            provider: RwLock::new(provider),
             runtime,
+            provider_health: Arc::new(RwLock::new(Vec::new())),
+            agents: HashMap::new(),
+            default_agent: AgentConfig::default(),
+            provider_factory: None,
+            quota: None,
+            workspace: None,
+            approvals: None,
+            store: None,
+        }
+    }
+
+    /// Agent definitions sessions may select by name. Once any are set, an
+    /// unknown `agent_id` is rejected instead of naming a default agent.
+    pub fn with_agents(mut self, agents: HashMap<String, AgentConfig>) -> Self {
+        self.agents = agents;
+        self
+    }
+
+    /// Settings for sessions created without an `agent_id`
+    pub fn with_default_agent(mut self, config: AgentConfig) -> Self {
+        self.default_agent = config;
+        self
+    }
+
+    /// Allow `switch_model` to move sessions to clients built by `factory`
+    pub fn with_provider_factory(mut self, factory: ProviderFactory) -> Self {
+        self.provider_factory = Some(factory);
+        self
+    }
+
+    /// Enforce usage quotas; LLM tokens of each message are charged to the
+    /// identity that sent it
+    pub fn with_quota(mut self, quota: Arc<QuotaTracker>) -> Self {
+        self.quota = Some(quota);
+        self
+    }
+
+    /// Read message attachments from `guard`'s workspace; without one,
+    /// attachments are rejected
+    pub fn with_workspace(mut self, guard: Arc<WorkspaceGuard>) -> Self {
+        self.workspace = Some(guard);
+        self
+    }
+
+    /// Ask the WebSocket clients of a session to approve its held tool
+    /// calls; `broker` must also be the runtime's approver
+    pub fn with_approvals(mut self, broker: Arc<ApprovalBroker>) -> Self {
+        broker.connect(self.event_buses.clone());
+        self.approvals = Some(broker);
+        self
+    }
+
+    /// Save each session to `store` after every message and fork
+    pub fn with_session_store(mut self, store: Arc<dyn SessionBackend>) -> Self {
+        self.store = Some(store);
+        self
+    }
+
+    /// Save `agent`'s session if a store is set; a failed save is logged, not
+    /// returned, so the reply still reaches the caller
+    async fn persist(&self, agent: &Agent) {
+        if let Some(store) = &self.store {
+            if let Err(e) = store.save(&agent.session).await {
+                tracing::warn!(session_id = %agent.session.id, error = %e, "Failed to save session");
+            }
         }
     }
 
+    /// Load the images at workspace `paths` for a message
+    pub async fn load_attachments(&self, paths: &[String]) -> Result<Vec<Content>> {
+        if paths.is_empty() {
+            return Ok(Vec::new());
+        }
+        let guard = self
+            .workspace
+            .as_ref()
+            .ok_or_else(|| anyhow!("Attachments are disabled: the gateway has no workspace"))?;
+        let mut attachments = Vec::with_capacity(paths.len());
+        for path in paths {
+            attachments.push(load_image(guard, path).await?);
+        }
+        Ok(attachments)
+    }
+
+    pub fn quota(&self) -> Option<Arc<QuotaTracker>> {
+        self.quota.clone()
+    }
+
+    /// Settings for a session asking for `agent_name`
+    fn agent_config(&self, agent_name: Option<&str>) -> Result<AgentConfig> {
+        match agent_name {
+            None => Ok(self.default_agent.clone()),
+            Some(name) => match self.agents.get(name) {
+                Some(config) => Ok(config.clone()),
+                None if self.agents.is_empty() => Ok(AgentConfig {
+                    name: name.to_string(),
+                    ..self.default_agent.clone()
+                }),
+                None => {
+                    let mut known: Vec<_> = self.agents.keys().map(String::as_str).collect();
+                    known.sort_unstable();
+                    Err(anyhow!(
+                        "Unknown agent: {} (available: {})",
+                        name,
+                        known.join(", ")
+                    ))
+                }
+            },
+        }
+    }
+
+    /// Start new sessions on `provider` (e.g. after a config reload); running
+    /// sessions keep the one they started on
+    pub async fn set_provider(&self, provider: Arc<dyn LLMProvider>) {
+        *self.provider.write().await = provider;
+    }
+
+    /// Probe the LLM provider(s) and cache the result for /health
+    pub async fn refresh_provider_health(&self) -> Vec<ProviderHealth> {
+        let provider = self.provider.read().await.clone();
+        let report = provider.health_report().await;
+        *self.provider_health.write().await = report.clone();
+        report
+    }
+
+    /// Cached provider health (empty until `refresh_provider_health` runs)
+    pub async fn provider_health(&self) -> Vec<ProviderHealth> {
+        self.provider_health.read().await.clone()
+    }
+
+    /// Tool execution queue depth on the shared runtime
+    pub fn queue_stats(&self) -> QueueStats {
+        self.runtime.queue_stats()
+    }
+
+    /// Execution levels and parallelism of a plan on the shared runtime
+    pub fn plan_schedule(&self, plan: &serde_json::Value) -> Result<PlanSchedule> {
+        self.runtime.plan_schedule(plan)
+    }
+
+    /// Recorded tool calls matching `filter`, newest first (at most 100
+    /// unless it sets a limit)
+    pub async fn audit_records(&self, mut filter: AuditFilter) -> Result<Vec<AuditRecord>> {
+        filter.limit.get_or_insert(DEFAULT_AUDIT_LIMIT);
+        self.runtime.storage().audit_records_async(filter).await
+    }
+
     /// Create a new agent session, returns session ID
     pub async fn create(&self, agent_name: Option<&str>) -> Result<String> {
-        let config = AgentConfig {
-            name: agent_name.unwrap_or("default").to_string(),
-            ..AgentConfig::default()
-        };
+        let config = self.agent_config(agent_name)?;
 
-        let agent = Agent::new(config, self.provider.clone(), self.runtime.clone());
+        let provider = self.provider.read().await.clone();
+        let agent = Agent::new(config, provider, self.runtime.clone());
+        agent.start_session().await;
         let session_id = agent.session.id.clone();
         let now = Utc::now();
 
@@ -70,6 +250,19 @@ impl SessionManager {
     /// Uses remove/insert pattern to avoid holding write lock during LLM call.
     /// If two concurrent sends target the same session, the second gets "Session not found".
     pub async fn send_message(&self, session_id: &str, content: &str) -> Result<String> {
+        self.send_message_as(session_id, content, Vec::new(), None)
+            .await
+    }
+
+    /// `send_message` with `attachments` (e.g. images from
+    /// `load_attachments`), charging the tokens it uses to `identity`'s quota
+    pub async fn send_message_as(
+        &self,
+        session_id: &str,
+        content: &str,
+        attachments: Vec<Content>,
+        identity: Option<&str>,
+    ) -> Result<String> {
         // 1. Remove session from map (short write lock)
         let mut session = {
             let mut sessions = self.sessions.write().await;
@@ -81,7 +274,26 @@ impl SessionManager {
 
         // 2. Process message without holding any lock
         session.last_active = Utc::now();
-        let response = session.agent.process_message(content).await;
+        for part in attachments {
+            session.agent.attach(part);
+        }
+        self.runtime.set_session_principal(session_id, identity);
+        let tokens_before = session.agent.session.cumulative_usage.total();
+        let bus = self.event_buses.read().await.get(session_id).cloned();
+        let response = stream_reply(&mut session.agent, content, bus).await;
+        let tokens = session
+            .agent
+            .session
+            .cumulative_usage
+            .total()
+            .saturating_sub(tokens_before);
+        if let (Some(quota), Some(identity)) = (self.quota.clone(), identity) {
+            let id = identity.to_string();
+            if let Err(e) = blocking(move || quota.add_tokens(&id, tokens as u64)).await {
+                tracing::warn!(identity, error = %e, "Failed to record token usage");
+            }
+        }
+        self.persist(&session.agent).await;
 
         // 3. Re-insert session (short write lock) — even on error to prevent session loss
         {
@@ -97,19 +309,174 @@ impl SessionManager {
         }
 
         // 4. Handle result and broadcast
-        let response = response?;
+        let bus = self.event_buses.read().await.get(session_id).cloned();
+        let (response, usage) = match response {
+            Ok(reply) => reply,
+            Err(e) => {
+                if let Some(tx) = bus {
+                    let _ = tx.send(SessionEvent::Error {
+                        message: format!("{:#}", e),
+                    });
+                }
+                return Err(e);
+            }
+        };
 
-        if let Some(tx) = self.event_buses.read().await.get(session_id) {
+        if let Some(tx) = bus {
             let _ = tx.send(SessionEvent::AgentResponse {
                 content: response.clone(),
             });
+            let _ = tx.send(SessionEvent::Done { usage });
         }
 
         Ok(response)
     }
 
-    /// Get session info (non-mutable)
-    pub async fn get_session_info(&self, session_id: &str) -> Result<(String, String, usize)> {
+    /// Start processing `content` in the background and return a job ID right
+    /// away. The outcome is kept for `get_job` and announced on the session's
+    /// event channel. A session runs one job at a time.
+    pub async fn submit_message(
+        self: &Arc<Self>,
+        session_id: &str,
+        content: &str,
+    ) -> Result<String> {
+        self.submit_message_as(session_id, content, Vec::new(), None)
+            .await
+    }
+
+    /// `submit_message` with `attachments`, charging the tokens it uses to
+    /// `identity`'s quota
+    pub async fn submit_message_as(
+        self: &Arc<Self>,
+        session_id: &str,
+        content: &str,
+        attachments: Vec<Content>,
+        identity: Option<String>,
+    ) -> Result<String> {
+        if !self.sessions.read().await.contains_key(session_id) {
+            return Err(anyhow!("Session not found: {}", session_id));
+        }
+        let job_id = uuid::Uuid::new_v4().to_string();
+        {
+            let mut jobs = self.jobs.write().await;
+            let now = Utc::now();
+            jobs.retain(|_, job| {
+                job.finished_at
+                    .as_deref()
+                    .and_then(|t| DateTime::parse_from_rfc3339(t).ok())
+                    .is_none_or(|finished| now.signed_duration_since(finished) < JOB_RETENTION)
+            });
+            if let Some(busy) = jobs
+                .values()
+                .find(|job| job.session_id == session_id && job.status == JobStatus::Running)
+            {
+                return Err(anyhow!(
+                    "Session {} is busy with job {}",
+                    session_id,
+                    busy.job_id
+                ));
+            }
+            jobs.insert(
+                job_id.clone(),
+                JobResponse {
+                    job_id: job_id.clone(),
+                    session_id: session_id.to_string(),
+                    status: JobStatus::Running,
+                    created_at: now.to_rfc3339(),
+                    finished_at: None,
+                    content: None,
+                    error: None,
+                },
+            );
+        }
+
+        let manager = self.clone();
+        let session_id = session_id.to_string();
+        let content = content.to_string();
+        let id = job_id.clone();
+        tokio::spawn(async move {
+            let result = manager
+                .send_message_as(&session_id, &content, attachments, identity.as_deref())
+                .await;
+            let (status, error) = match &result {
+                Ok(_) => (JobStatus::Succeeded, None),
+                Err(e) => (JobStatus::Failed, Some(e.to_string())),
+            };
+            if let Some(job) = manager.jobs.write().await.get_mut(&id) {
+                job.status = status;
+                job.finished_at = Some(Utc::now().to_rfc3339());
+                job.content = result.ok();
+                job.error = error.clone();
+            }
+            if let Some(tx) = manager.event_buses.read().await.get(&session_id) {
+                let _ = tx.send(SessionEvent::JobFinished {
+                    job_id: id,
+                    status,
+                    error,
+                });
+            }
+        });
+        Ok(job_id)
+    }
+
+    /// Status (and result, once finished) of an asynchronous message job
+    pub async fn get_job(&self, job_id: &str) -> Result<JobResponse> {
+        self.jobs
+            .read()
+            .await
+            .get(job_id)
+            .cloned()
+            .ok_or_else(|| anyhow!("Job not found: {}", job_id))
+    }
+
+    /// Continue a session on another provider and model, keeping its history
+    pub async fn switch_model(&self, session_id: &str, provider: &str, model: &str) -> Result<()> {
+        let factory = self
+            .provider_factory
+            .as_ref()
+            .ok_or_else(|| anyhow!("Switching providers is not enabled on this gateway"))?;
+        let client = factory(provider, model)?;
+        let mut sessions = self.sessions.write().await;
+        let session = sessions
+            .get_mut(session_id)
+            .ok_or_else(|| anyhow!("Session not found: {}", session_id))?;
+        session.agent.hand_off(provider, client, model);
+        Ok(())
+    }
+
+    /// New session continuing from the first `at` messages of `session_id`
+    /// (None: all of them) with the same agent; returns the new session ID
+    pub async fn fork(&self, session_id: &str, at: Option<usize>) -> Result<String> {
+        let agent = {
+            let sessions = self.sessions.read().await;
+            let session = sessions
+                .get(session_id)
+                .ok_or_else(|| anyhow!("Session not found: {}", session_id))?;
+            session.agent.fork(at)?
+        };
+        agent.start_session().await;
+        self.persist(&agent).await;
+        let fork_id = agent.session.id.clone();
+        let now = Utc::now();
+        self.sessions.write().await.insert(
+            fork_id.clone(),
+            AgentSession {
+                agent,
+                created_at: now,
+                last_active: now,
+            },
+        );
+        let (tx, _) = broadcast::channel(100);
+        self.event_buses.write().await.insert(fork_id.clone(), tx);
+        Ok(fork_id)
+    }
+
+    /// Get session info (non-mutable): agent name, creation time, message
+    /// count and cumulative usage
+    pub async fn get_session_info(
+        &self,
+        session_id: &str,
+    ) -> Result<(String, String, usize, Usage)> {
         let sessions = self.sessions.read().await;
         let session = sessions
             .get(session_id)
@@ -119,6 +486,7 @@ impl SessionManager {
             session.agent.config.name.clone(),
             session.created_at.to_rfc3339(),
             session.agent.session.message_count(),
+            session.agent.session.cumulative_usage.clone(),
         ))
     }
 
@@ -129,13 +497,26 @@ impl SessionManager {
 
     /// Delete a session
     pub async fn delete_session(&self, session_id: &str) -> Result<()> {
-        self.sessions
+        let session = self
+            .sessions
             .write()
             .await
             .remove(session_id)
             .ok_or_else(|| anyhow!("Session not found: {}", session_id))?;
         self.event_buses.write().await.remove(session_id);
-        Ok(())
+        // Run SessionEnd hooks and tear down per-session execution resources
+        // (e.g. a tool container)
+        session.agent.end_session().await
+    }
+
+    /// Approve or deny held tool call `call_id` of a session
+    pub fn resolve_approval(&self, session_id: &str, call_id: &str, approved: bool) -> Result<()> {
+        // The session itself is checked out while its turn runs, so only
+        // the broker knows which calls are waiting
+        self.approvals
+            .as_ref()
+            .ok_or_else(|| anyhow!("Approvals are not enabled on this gateway"))?
+            .resolve(session_id, call_id, approved)
     }
 
     /// Subscribe to session events (for WebSocket)
@@ -147,3 +528,39 @@ impl SessionManager {
         Ok(tx.subscribe())
     }
 }
+
+/// Run a streamed turn, passing text deltas and tool calls on to the
+/// session's subscribers as they happen. Returns the reply and its usage.
+async fn stream_reply(
+    agent: &mut Agent,
+    content: &str,
+    bus: Option<broadcast::Sender<SessionEvent>>,
+) -> Result<(String, Usage)> {
+    let mut events = agent.process_message_stream(content);
+    while let Some(event) = events.next().await {
+        let event = match event {
+            AgentEvent::TextDelta(content) => SessionEvent::TextDelta { content },
+            AgentEvent::ToolCallStarted { id, name, input } => {
+                SessionEvent::ToolCall { id, name, input }
+            }
+            AgentEvent::ToolCallFinished {
+                id,
+                name,
+                output,
+                is_error,
+            } => SessionEvent::ToolResult {
+                id,
+                name,
+                output,
+                is_error,
+            },
+            AgentEvent::Done { text, usage } => return Ok((text, usage)),
+            AgentEvent::Error(e) => return Err(anyhow!(e)),
+            AgentEvent::Thinking(_) => continue,
+        };
+        if let Some(bus) = &bus {
+            let _ = bus.send(event);
+        }
+    }
+    Err(anyhow!("Agent stream ended without a reply"))
+}
@@ -1,3 +1,4 @@
+use operon_runtime::{ProviderHealth, QueueStats, Usage};
 use serde::{Deserialize, Serialize};
 
 /// Create session request
@@ -6,6 +7,14 @@ pub struct CreateSessionRequest {
     pub agent_id: Option<String>,
 }
 
+/// Fork a session at a message index
+#[derive(Debug, Default, Deserialize)]
+pub struct ForkSessionRequest {
+    /// Messages to keep (default: all)
+    #[serde(default)]
+    pub at: Option<usize>,
+}
+
 /// Session info response
 #[derive(Debug, Serialize)]
 pub struct SessionResponse {
@@ -13,12 +22,34 @@ pub struct SessionResponse {
     pub agent_name: String,
     pub created_at: String,
     pub message_count: usize,
+    /// Tokens and estimated cost so far
+    pub usage: Usage,
 }
 
 /// Send message request
 #[derive(Debug, Deserialize)]
 pub struct SendMessageRequest {
     pub content: String,
+    /// Workspace paths of images sent along with the message
+    #[serde(default)]
+    pub attachments: Vec<String>,
+}
+
+/// Switch a session to another provider and model
+#[derive(Debug, Deserialize)]
+pub struct SwitchModelRequest {
+    pub provider: String,
+    /// Empty for the provider's default model
+    #[serde(default)]
+    pub model: String,
+}
+
+/// Provider and model a session now uses
+#[derive(Debug, Serialize)]
+pub struct ModelResponse {
+    pub session_id: String,
+    pub provider: String,
+    pub model: String,
 }
 
 /// Message response
@@ -28,6 +59,31 @@ pub struct MessageResponse {
     pub session_id: String,
 }
 
+/// Where an asynchronous message job is
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum JobStatus {
+    Running,
+    Succeeded,
+    Failed,
+}
+
+/// Asynchronous message job: accepted right away, polled at /api/v1/jobs/{id}
+#[derive(Debug, Clone, Serialize)]
+pub struct JobResponse {
+    pub job_id: String,
+    pub session_id: String,
+    pub status: JobStatus,
+    pub created_at: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub finished_at: Option<String>,
+    /// Agent reply, once succeeded
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub content: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
 /// WebSocket client message
 #[derive(Debug, Deserialize)]
 #[serde(tag = "type", rename_all = "snake_case")]
@@ -43,17 +99,54 @@ pub enum SessionEvent {
     AgentResponse {
         content: String,
     },
+    /// Piece of a reply as it is generated; the whole reply still follows
+    /// as `agent_response`
+    TextDelta {
+        content: String,
+    },
+    /// A tool call started
     ToolCall {
+        id: String,
         name: String,
         input: serde_json::Value,
     },
+    /// A tool call finished (output is what the LLM sees)
     ToolResult {
+        id: String,
         name: String,
         output: String,
+        is_error: bool,
+    },
+    /// Last frame of a successful turn, after `agent_response`
+    Done {
+        usage: Usage,
     },
+    /// The turn failed; no `agent_response` or `done` follows
     Error {
         message: String,
     },
+    /// An asynchronous message job finished; the reply itself arrives as
+    /// `agent_response`
+    JobFinished {
+        job_id: String,
+        status: JobStatus,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        error: Option<String>,
+    },
+    /// A tool call is held until it is approved or denied at
+    /// /api/v1/sessions/{id}/approvals/{call_id}
+    ApprovalRequired {
+        call_id: String,
+        tool: String,
+        input: serde_json::Value,
+        description: String,
+    },
+}
+
+/// Answer to a held tool call
+#[derive(Debug, Deserialize)]
+pub struct ApprovalAnswer {
+    pub approved: bool,
 }
 
 /// API error response
@@ -65,6 +158,12 @@ pub struct ErrorResponse {
 /// Health check response
 #[derive(Debug, Serialize)]
 pub struct HealthResponse {
+    /// "ok", "degraded" (some providers failing), "unhealthy" (all failing)
+    /// or "unknown" (not probed yet)
     pub status: String,
     pub version: String,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub providers: Vec<ProviderHealth>,
+    /// Tool execution queue depth on the shared runtime
+    pub queue: QueueStats,
 }
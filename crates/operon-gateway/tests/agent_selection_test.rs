@@ -0,0 +1,214 @@
+//! Tests for choosing an agent definition per session by `agent_id`, and
+//! for handing a session off to another provider/model.
+
+mod test_helpers;
+
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use anyhow::Result;
+use async_trait::async_trait;
+use axum::body::Body;
+use axum::http::{Request, StatusCode};
+use http_body_util::BodyExt;
+use operon_runtime::llm::{
+    Content, GenerateConfig, GenerateResponse, LLMProvider, Message, StopReason, ToolSchema, Usage,
+};
+use operon_runtime::{AgentConfig, Runtime};
+use tower::ServiceExt;
+
+use operon_gateway::{
+    create_router, AppState, AuthConfig, RateLimiter, Reloadable, SessionManager,
+};
+use test_helpers::with_connect_info;
+
+/// Records the model and system prompt of every request
+#[derive(Default)]
+struct CapturingProvider {
+    seen: Mutex<Vec<(String, Option<String>)>>,
+}
+
+#[async_trait]
+impl LLMProvider for CapturingProvider {
+    async fn generate(
+        &self,
+        _messages: &[Message],
+        _tools: &[ToolSchema],
+        config: &GenerateConfig,
+    ) -> Result<GenerateResponse> {
+        self.seen
+            .lock()
+            .unwrap()
+            .push((config.model.clone(), config.system_prompt.clone()));
+        Ok(GenerateResponse {
+            content: Content::Text {
+                text: "ok".to_string(),
+            },
+            stop_reason: StopReason::EndTurn,
+            usage: Usage::default(),
+            model: config.model.clone(),
+        })
+    }
+
+    fn supports_vision(&self) -> bool {
+        false
+    }
+
+    fn model_name(&self) -> &str {
+        "capturing"
+    }
+}
+
+fn manager(provider: Arc<CapturingProvider>, dir: &tempfile::TempDir) -> SessionManager {
+    let runtime = Runtime::with_db(
+        dir.path().join("test.db").to_str().unwrap(),
+        true,
+        Duration::from_secs(30),
+    )
+    .unwrap();
+    let agents = HashMap::from([(
+        "reviewer".to_string(),
+        AgentConfig {
+            name: "reviewer".to_string(),
+            model: "strong-model".to_string(),
+            system_prompt: "You review code.".to_string(),
+            ..AgentConfig::default()
+        },
+    )]);
+    SessionManager::new(provider, Arc::new(runtime))
+        .with_default_agent(AgentConfig {
+            model: "cheap-model".to_string(),
+            ..AgentConfig::default()
+        })
+        .with_agents(agents)
+}
+
+#[tokio::test]
+async fn test_sessions_use_the_selected_agent_definition() {
+    let dir = tempfile::tempdir().unwrap();
+    let provider = Arc::new(CapturingProvider::default());
+    let manager = Arc::new(manager(provider.clone(), &dir));
+
+    let reviewer = manager.create(Some("reviewer")).await.unwrap();
+    let default = manager.create(None).await.unwrap();
+    manager
+        .send_message(&reviewer, "look at this")
+        .await
+        .unwrap();
+    manager.send_message(&default, "hi").await.unwrap();
+
+    assert_eq!(
+        manager.get_session_info(&reviewer).await.unwrap().0,
+        "reviewer"
+    );
+    assert_eq!(
+        manager.get_session_info(&default).await.unwrap().0,
+        "default"
+    );
+    let seen = provider.seen.lock().unwrap().clone();
+    assert_eq!(
+        seen[0],
+        (
+            "strong-model".to_string(),
+            Some("You review code.".to_string())
+        )
+    );
+    assert_eq!(seen[1].0, "cheap-model");
+}
+
+#[tokio::test]
+async fn test_unknown_agent_is_rejected() {
+    let dir = tempfile::tempdir().unwrap();
+    let state = AppState {
+        session_manager: Arc::new(manager(Arc::new(CapturingProvider::default()), &dir)),
+        auth_config: Reloadable::new(AuthConfig::new(None)),
+        rate_limiter: Arc::new(RateLimiter::new(1000)),
+        allowed_origins: Reloadable::new(vec![]),
+        trusted_proxy_headers: vec![],
+    };
+
+    let req = Request::builder()
+        .method("POST")
+        .uri("/api/v1/sessions")
+        .header("content-type", "application/json")
+        .body(Body::from(r#"{"agent_id":"ghost"}"#))
+        .unwrap();
+    let resp = create_router(state)
+        .oneshot(with_connect_info(req))
+        .await
+        .unwrap();
+
+    assert_eq!(resp.status(), StatusCode::BAD_REQUEST);
+    let bytes = resp.into_body().collect().await.unwrap().to_bytes();
+    let body: serde_json::Value = serde_json::from_slice(&bytes).unwrap();
+    assert_eq!(body["error"], "Unknown agent: ghost (available: reviewer)");
+}
+
+#[tokio::test]
+async fn test_switch_model_keeps_history_on_new_provider() {
+    let dir = tempfile::tempdir().unwrap();
+    let cheap = Arc::new(CapturingProvider::default());
+    let strong = Arc::new(CapturingProvider::default());
+    let factory_strong = strong.clone();
+    let manager = Arc::new(manager(cheap.clone(), &dir).with_provider_factory(Arc::new(
+        move |provider, _model| match provider {
+            "openai" => Ok(factory_strong.clone() as Arc<dyn LLMProvider>),
+            other => Err(anyhow::anyhow!(
+                "Provider '{}' has no API key configured",
+                other
+            )),
+        },
+    )));
+    let state = AppState {
+        session_manager: manager.clone(),
+        auth_config: Reloadable::new(AuthConfig::new(None)),
+        rate_limiter: Arc::new(RateLimiter::new(1000)),
+        allowed_origins: Reloadable::new(vec![]),
+        trusted_proxy_headers: vec![],
+    };
+    let session_id = manager.create(None).await.unwrap();
+    manager.send_message(&session_id, "first").await.unwrap();
+
+    let switch = |body: &'static str, id: &str| {
+        Request::builder()
+            .method("PUT")
+            .uri(format!("/api/v1/sessions/{}/model", id))
+            .header("content-type", "application/json")
+            .body(Body::from(body))
+            .unwrap()
+    };
+    let resp = create_router(state.clone())
+        .oneshot(with_connect_info(switch(
+            r#"{"provider":"openai","model":"gpt-4o"}"#,
+            &session_id,
+        )))
+        .await
+        .unwrap();
+    assert_eq!(resp.status(), StatusCode::OK);
+    let bytes = resp.into_body().collect().await.unwrap().to_bytes();
+    let body: serde_json::Value = serde_json::from_slice(&bytes).unwrap();
+    assert_eq!(body["model"], "gpt-4o");
+
+    manager.send_message(&session_id, "second").await.unwrap();
+    assert_eq!(cheap.seen.lock().unwrap().len(), 1);
+    assert_eq!(strong.seen.lock().unwrap()[0].0, "gpt-4o");
+    assert_eq!(manager.get_session_info(&session_id).await.unwrap().2, 4);
+
+    let resp = create_router(state.clone())
+        .oneshot(with_connect_info(switch(
+            r#"{"provider":"gemini"}"#,
+            &session_id,
+        )))
+        .await
+        .unwrap();
+    assert_eq!(resp.status(), StatusCode::BAD_REQUEST);
+    let resp = create_router(state)
+        .oneshot(with_connect_info(switch(
+            r#"{"provider":"openai"}"#,
+            "missing",
+        )))
+        .await
+        .unwrap();
+    assert_eq!(resp.status(), StatusCode::NOT_FOUND);
+}
@@ -0,0 +1,255 @@
+//! Tests for tool calls held for approval: the approval_required event and
+//! answers posted to /api/v1/sessions/{id}/approvals/{call_id}.
+
+mod test_helpers;
+
+use std::sync::Arc;
+use std::time::Duration;
+
+use anyhow::Result;
+use async_trait::async_trait;
+use axum::body::Body;
+use axum::http::{Request, StatusCode};
+use http_body_util::BodyExt;
+use operon_runtime::llm::{
+    Content, GenerateConfig, GenerateResponse, LLMProvider, Message, StopReason, ToolCall,
+    ToolSchema, Usage,
+};
+use operon_runtime::tool_policy::layers::ApprovalLayer;
+use operon_runtime::{Runtime, Tool, ToolPolicyPipeline, ToolSchemaInfo};
+use serde_json::{json, Value};
+use tokio::sync::broadcast;
+use tower::ServiceExt;
+
+use operon_gateway::types::SessionEvent;
+use operon_gateway::{
+    create_router, AppState, ApprovalBroker, AuthConfig, RateLimiter, Reloadable, SessionManager,
+};
+use test_helpers::{make_test_state, with_connect_info};
+
+/// Answers each user message with a `shell` call running it, and each tool
+/// result with the result itself
+struct ShellCallingProvider;
+
+#[async_trait]
+impl LLMProvider for ShellCallingProvider {
+    async fn generate(
+        &self,
+        messages: &[Message],
+        _tools: &[ToolSchema],
+        _config: &GenerateConfig,
+    ) -> Result<GenerateResponse> {
+        let (content, stop_reason) = match &messages.last().unwrap().content {
+            Content::Text { text } => (
+                Content::ToolCall(ToolCall {
+                    id: "call-1".into(),
+                    name: "shell".into(),
+                    input: json!({"command": text}),
+                }),
+                StopReason::ToolUse,
+            ),
+            Content::ToolResult(result) => (
+                Content::Text {
+                    text: result.output.clone(),
+                },
+                StopReason::EndTurn,
+            ),
+            other => panic!("unexpected message {:?}", other),
+        };
+        Ok(GenerateResponse {
+            content,
+            stop_reason,
+            usage: Usage::default(),
+            model: "shell-calling".into(),
+        })
+    }
+
+    fn supports_vision(&self) -> bool {
+        false
+    }
+
+    fn model_name(&self) -> &str {
+        "shell-calling"
+    }
+}
+
+/// Stands in for the shell tool: reports the command it was given
+struct FakeShell;
+
+#[async_trait]
+impl Tool for FakeShell {
+    async fn execute(&self, input: Value) -> Result<Value> {
+        Ok(json!({"ran": input["command"]}))
+    }
+
+    fn name(&self) -> &str {
+        "shell"
+    }
+
+    fn schema(&self) -> ToolSchemaInfo {
+        ToolSchemaInfo {
+            name: "shell".into(),
+            description: "Run a command".into(),
+            parameters: json!({"type": "object"}),
+        }
+    }
+}
+
+fn make_approval_state() -> (AppState, tempfile::TempDir) {
+    let dir = tempfile::tempdir().unwrap();
+    let db_path = dir.path().join("test.db");
+    let broker = Arc::new(ApprovalBroker::new().with_timeout(Duration::from_secs(5)));
+    let runtime = Runtime::with_db(db_path.to_str().unwrap(), false, Duration::from_secs(30))
+        .unwrap()
+        .with_policy(
+            ToolPolicyPipeline::new().add_layer(Box::new(ApprovalLayer::new(vec!["shell".into()]))),
+        )
+        .with_approver(broker.clone());
+    runtime
+        .register_tool("shell".into(), Arc::new(FakeShell))
+        .unwrap();
+    let session_manager = SessionManager::new(Arc::new(ShellCallingProvider), Arc::new(runtime))
+        .with_approvals(broker);
+
+    (
+        AppState {
+            session_manager: Arc::new(session_manager),
+            auth_config: Reloadable::new(AuthConfig::new(None)),
+            rate_limiter: Arc::new(RateLimiter::new(1000)),
+            allowed_origins: Reloadable::new(vec![]),
+            trusted_proxy_headers: vec![],
+        },
+        dir,
+    )
+}
+
+async fn answer(state: &AppState, session_id: &str, call_id: &str, approved: bool) -> StatusCode {
+    let req = Request::builder()
+        .method("POST")
+        .uri(format!(
+            "/api/v1/sessions/{}/approvals/{}",
+            session_id, call_id
+        ))
+        .header("content-type", "application/json")
+        .body(Body::from(json!({ "approved": approved }).to_string()))
+        .unwrap();
+    let resp = create_router(state.clone())
+        .oneshot(with_connect_info(req))
+        .await
+        .unwrap();
+    let status = resp.status();
+    let _ = resp.into_body().collect().await.unwrap();
+    status
+}
+
+/// Wait for the next approval request on a session channel
+async fn next_approval(events: &mut broadcast::Receiver<SessionEvent>) -> Value {
+    loop {
+        let event = tokio::time::timeout(Duration::from_secs(5), events.recv())
+            .await
+            .unwrap()
+            .unwrap();
+        let frame = serde_json::to_value(&event).unwrap();
+        if frame["type"] == "approval_required" {
+            return frame;
+        }
+    }
+}
+
+#[tokio::test]
+async fn test_held_call_runs_once_approved() {
+    let (state, _dir) = make_approval_state();
+    let manager = state.session_manager.clone();
+    let sid = manager.create(None).await.unwrap();
+    let mut events = manager.subscribe(&sid).await.unwrap();
+
+    let turn = {
+        let manager = manager.clone();
+        let sid = sid.clone();
+        tokio::spawn(async move { manager.send_message(&sid, "rm -rf build").await })
+    };
+    let frame = next_approval(&mut events).await;
+    assert_eq!(frame["tool"], "shell");
+    assert_eq!(frame["input"]["command"], "rm -rf build");
+    assert_eq!(frame["description"], "shell: rm -rf build");
+    let call_id = frame["call_id"].as_str().unwrap();
+
+    // Answers must name the session the call belongs to
+    assert_eq!(
+        answer(&state, "other-session", call_id, true).await,
+        StatusCode::NOT_FOUND
+    );
+    assert_eq!(
+        answer(&state, &sid, call_id, true).await,
+        StatusCode::NO_CONTENT
+    );
+    let reply = turn.await.unwrap().unwrap();
+    assert!(reply.contains("rm -rf build"), "{}", reply);
+
+    // Each call is answered once
+    assert_eq!(
+        answer(&state, &sid, call_id, true).await,
+        StatusCode::NOT_FOUND
+    );
+}
+
+#[tokio::test]
+async fn test_denied_or_unwatched_calls_do_not_run() {
+    let (state, _dir) = make_approval_state();
+    let manager = state.session_manager.clone();
+
+    let sid = manager.create(None).await.unwrap();
+    let mut events = manager.subscribe(&sid).await.unwrap();
+    let turn = {
+        let manager = manager.clone();
+        let sid = sid.clone();
+        tokio::spawn(async move { manager.send_message(&sid, "rm -rf build").await })
+    };
+    let frame = next_approval(&mut events).await;
+    let call_id = frame["call_id"].as_str().unwrap();
+    assert_eq!(
+        answer(&state, &sid, call_id, false).await,
+        StatusCode::NO_CONTENT
+    );
+    let reply = turn.await.unwrap().unwrap();
+    assert!(reply.contains("was not approved"), "{}", reply);
+
+    // Nobody is subscribed to this session, so nobody can approve
+    let sid = manager.create(None).await.unwrap();
+    let reply = manager.send_message(&sid, "ls").await.unwrap();
+    assert!(reply.contains("was not approved"), "{}", reply);
+}
+
+#[tokio::test]
+async fn test_cancelled_turn_leaves_no_pending_approval() {
+    let (state, _dir) = make_approval_state();
+    let manager = state.session_manager.clone();
+    let sid = manager.create(None).await.unwrap();
+    let mut events = manager.subscribe(&sid).await.unwrap();
+
+    let turn = {
+        let manager = manager.clone();
+        let sid = sid.clone();
+        tokio::spawn(async move { manager.send_message(&sid, "rm -rf build").await })
+    };
+    let frame = next_approval(&mut events).await;
+    let call_id = frame["call_id"].as_str().unwrap();
+
+    // The waiting call is dropped with its turn, so it can't be approved later
+    turn.abort();
+    assert!(turn.await.unwrap_err().is_cancelled());
+    assert_eq!(
+        answer(&state, &sid, call_id, true).await,
+        StatusCode::NOT_FOUND
+    );
+}
+
+#[tokio::test]
+async fn test_answers_rejected_without_approvals() {
+    let (state, _dir) = make_test_state();
+    let sid = state.session_manager.create(None).await.unwrap();
+    assert_eq!(
+        answer(&state, &sid, "call-1", true).await,
+        StatusCode::NOT_FOUND
+    );
+}
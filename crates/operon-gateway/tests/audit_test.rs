@@ -0,0 +1,156 @@
+//! Tests for the tool call audit trail served at /api/v1/audit.
+
+mod test_helpers;
+
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::time::Duration;
+
+use anyhow::Result;
+use async_trait::async_trait;
+use axum::body::Body;
+use axum::http::{Request, StatusCode};
+use http_body_util::BodyExt;
+use operon_runtime::{PermissionLevel, Runtime, Tool};
+use serde_json::{json, Value};
+use tower::ServiceExt;
+
+use operon_gateway::{
+    create_router, AppState, AuthConfig, RateLimiter, Reloadable, SessionManager,
+};
+use test_helpers::{with_connect_info, MockLLMProvider};
+
+struct EchoTool;
+
+#[async_trait]
+impl Tool for EchoTool {
+    async fn execute(&self, input: Value) -> Result<Value> {
+        Ok(input)
+    }
+
+    fn name(&self) -> &str {
+        "echo"
+    }
+}
+
+fn make_audit_state() -> (AppState, Arc<Runtime>, tempfile::TempDir) {
+    let dir = tempfile::tempdir().unwrap();
+    let db_path = dir.path().join("test.db");
+    let runtime = Arc::new(
+        Runtime::with_db(db_path.to_str().unwrap(), false, Duration::from_secs(30))
+            .unwrap()
+            .with_audit_trail(),
+    );
+    runtime
+        .register_tool("echo".into(), Arc::new(EchoTool))
+        .unwrap();
+    let session_manager = SessionManager::new(Arc::new(MockLLMProvider), runtime.clone());
+
+    (
+        AppState {
+            session_manager: Arc::new(session_manager),
+            auth_config: Reloadable::new(AuthConfig::new(None)),
+            rate_limiter: Arc::new(RateLimiter::new(1000)),
+            allowed_origins: Reloadable::new(vec![]),
+            trusted_proxy_headers: vec![],
+        },
+        runtime,
+        dir,
+    )
+}
+
+async fn get(state: &AppState, uri: &str) -> (StatusCode, Value) {
+    get_as(state, uri, None).await
+}
+
+async fn get_as(state: &AppState, uri: &str, token: Option<&str>) -> (StatusCode, Value) {
+    let mut req = Request::builder().uri(uri);
+    if let Some(token) = token {
+        req = req.header("Authorization", format!("Bearer {}", token));
+    }
+    let req = req.body(Body::empty()).unwrap();
+    let resp = create_router(state.clone())
+        .oneshot(with_connect_info(req))
+        .await
+        .unwrap();
+    let status = resp.status();
+    let body = resp.into_body().collect().await.unwrap().to_bytes();
+    (status, serde_json::from_slice(&body).unwrap_or(Value::Null))
+}
+
+#[tokio::test]
+async fn test_audit_records_filtered_by_session_tool_and_time() {
+    let (state, runtime, _dir) = make_audit_state();
+    for session in ["s1", "s1", "s2"] {
+        runtime
+            .execute_tool_in_session("echo", json!({"n": 1}), PermissionLevel::Execute, session)
+            .await
+            .unwrap();
+    }
+    runtime
+        .execute_tool("missing", json!({}))
+        .await
+        .unwrap_err();
+
+    let (status, records) = get(&state, "/api/v1/audit").await;
+    assert_eq!(status, StatusCode::OK);
+    let records = records.as_array().unwrap();
+    assert_eq!(records.len(), 4);
+    assert_eq!(records[0]["tool"], "missing");
+    assert_eq!(records[0]["outcome"], "error");
+    assert_eq!(records[3]["decision"], "allowed");
+    assert_eq!(records[3]["session_id"], "s1");
+
+    let (_, records) = get(&state, "/api/v1/audit?session_id=s1&tool=echo").await;
+    assert_eq!(records.as_array().unwrap().len(), 2);
+    let (_, records) = get(&state, "/api/v1/audit?tool=echo&limit=1").await;
+    assert_eq!(records[0]["session_id"], "s2");
+    let (_, records) = get(&state, "/api/v1/audit?since=2999-01-01T00:00:00Z").await;
+    assert!(records.as_array().unwrap().is_empty());
+    let (_, records) = get(&state, "/api/v1/audit?until=2999-01-01T00:00:00Z").await;
+    assert_eq!(records.as_array().unwrap().len(), 4);
+
+    let (status, _) = get(&state, "/api/v1/audit?since=yesterday").await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+}
+
+#[tokio::test]
+async fn test_audit_records_scoped_to_the_caller_unless_admin() {
+    let (state, runtime, _dir) = make_audit_state();
+    let api_keys = HashMap::from([
+        ("alice".to_string(), "alice-token".to_string()),
+        ("bob".to_string(), "bob-token".to_string()),
+        ("ops".to_string(), "ops-token".to_string()),
+    ]);
+    let auth = AuthConfig::new(None)
+        .with_api_keys(api_keys)
+        .with_admins(["ops".to_string()]);
+    state.auth_config.set(auth.clone());
+    for (session, principal) in [("s1", "alice"), ("s2", "bob")] {
+        runtime.set_session_principal(session, Some(principal));
+        runtime
+            .execute_tool_in_session("echo", json!({}), PermissionLevel::Execute, session)
+            .await
+            .unwrap();
+    }
+
+    let (_, records) = get_as(&state, "/api/v1/audit", Some("alice-token")).await;
+    let records = records.as_array().unwrap();
+    assert_eq!(records.len(), 1);
+    assert_eq!(records[0]["principal"], "alice");
+    // Asking for someone else's calls only narrows to the caller's own
+    let (_, records) = get_as(&state, "/api/v1/audit?principal=bob", Some("alice-token")).await;
+    assert_eq!(records[0]["principal"], "alice");
+
+    let (_, records) = get_as(&state, "/api/v1/audit", Some("ops-token")).await;
+    assert_eq!(records.as_array().unwrap().len(), 2);
+    let (_, records) = get_as(&state, "/api/v1/audit?principal=bob", Some("ops-token")).await;
+    assert_eq!(records.as_array().unwrap().len(), 1);
+
+    // Exempting the path from auth doesn't make it readable without a key
+    state
+        .auth_config
+        .set(auth.with_exempt_paths(vec!["/api/v1/audit".into()]));
+    let (status, _) = get(&state, "/api/v1/audit").await;
+    assert_eq!(status, StatusCode::FORBIDDEN);
+}
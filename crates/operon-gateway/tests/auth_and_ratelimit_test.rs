@@ -1,4 +1,5 @@
-//! Tests for auth middleware (401 on missing/invalid token) and rate limiter (429).
+//! Tests for auth middleware (401 on missing/invalid token, exempt paths), rate limiter
+//! (429) and client address resolution behind a proxy.
 
 mod test_helpers;
 
@@ -61,6 +62,93 @@ async fn test_health_bypasses_auth() {
     assert_eq!(status, StatusCode::OK);
 }
 
+#[tokio::test]
+async fn test_auth_exempt_paths_are_configurable() {
+    let (state, _dir) = make_auth_test_state("secret-token");
+    state.auth_config.set(
+        operon_gateway::AuthConfig::new(Some("secret-token".into()))
+            .with_exempt_paths(vec!["/api/v1/sessions*".into()]),
+    );
+    assert!(state.auth_config.get().is_exempt("/api/v1/sessions/abc"));
+    assert!(!state.auth_config.get().is_exempt("/health"));
+    assert_eq!(auth_call(None, &state).await, StatusCode::OK);
+
+    let app = create_router(state);
+    let req = with_connect_info(
+        Request::builder()
+            .method("GET")
+            .uri("/health")
+            .body(Body::empty())
+            .unwrap(),
+    );
+    let status = app.oneshot(req).await.unwrap().status();
+    assert_eq!(status, StatusCode::UNAUTHORIZED);
+}
+
+#[tokio::test]
+async fn test_running_router_follows_reloaded_auth_and_origins() {
+    let (state, _dir) = make_auth_test_state("secret-token");
+    let app = create_router(state.clone());
+    let call = |token: &str, origin: &str| {
+        with_connect_info(
+            Request::builder()
+                .method("GET")
+                .uri("/api/v1/sessions")
+                .header("Authorization", format!("Bearer {}", token))
+                .header("Origin", origin)
+                .body(Body::empty())
+                .unwrap(),
+        )
+    };
+    let allowed_origin = |response: &axum::response::Response| {
+        response
+            .headers()
+            .get("access-control-allow-origin")
+            .map(|value| value.to_str().unwrap().to_string())
+    };
+
+    // No origins listed: any is allowed
+    let response = app
+        .clone()
+        .oneshot(call("secret-token", "https://dev.local"))
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    assert_eq!(
+        allowed_origin(&response).as_deref(),
+        Some("https://dev.local")
+    );
+
+    state.auth_config.set(operon_gateway::AuthConfig::new(Some(
+        "rotated-token".into(),
+    )));
+    state
+        .allowed_origins
+        .set(vec!["https://app.example.com".into()]);
+
+    let response = app
+        .clone()
+        .oneshot(call("secret-token", "https://app.example.com"))
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+    let response = app
+        .clone()
+        .oneshot(call("rotated-token", "https://app.example.com"))
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    assert_eq!(
+        allowed_origin(&response).as_deref(),
+        Some("https://app.example.com")
+    );
+    let response = app
+        .oneshot(call("rotated-token", "https://dev.local"))
+        .await
+        .unwrap();
+    assert_eq!(allowed_origin(&response), None);
+}
+
 // ── Rate Limiter (unit tests on RateLimiter struct directly) ────────────
 
 #[test]
@@ -113,6 +201,48 @@ fn test_rate_limiter_cleanup() {
     limiter.cleanup();
 }
 
+#[test]
+fn test_client_ip_from_trusted_proxy_headers() {
+    let peer: std::net::IpAddr = "10.0.0.1".parse().unwrap();
+    let mut headers = axum::http::HeaderMap::new();
+    headers.insert("x-forwarded-for", "1.1.1.1, 203.0.113.7".parse().unwrap());
+    let trusted = vec!["X-Real-IP".to_string(), "X-Forwarded-For".to_string()];
+
+    // Headers are ignored unless trusted
+    assert_eq!(operon_gateway::client_ip(&headers, peer, &[]), peer);
+    assert_eq!(
+        operon_gateway::client_ip(&headers, peer, &trusted),
+        "203.0.113.7".parse::<std::net::IpAddr>().unwrap()
+    );
+
+    headers.insert("x-forwarded-for", "not-an-ip".parse().unwrap());
+    assert_eq!(operon_gateway::client_ip(&headers, peer, &trusted), peer);
+}
+
+#[tokio::test]
+async fn test_rate_limit_uses_trusted_proxy_header() {
+    let (mut state, _dir) = make_ratelimit_test_state(1);
+    state.trusted_proxy_headers = vec!["X-Forwarded-For".to_string()];
+    let app = create_router(state);
+
+    let call = |client: &'static str| {
+        let app = app.clone();
+        async move {
+            let req = Request::builder()
+                .method("GET")
+                .uri("/api/v1/sessions")
+                .header("X-Forwarded-For", client)
+                .body(Body::empty())
+                .unwrap();
+            app.oneshot(with_connect_info(req)).await.unwrap().status()
+        }
+    };
+    // Same peer address, but each forwarded client gets its own bucket
+    assert_eq!(call("203.0.113.1").await, StatusCode::OK);
+    assert_eq!(call("203.0.113.2").await, StatusCode::OK);
+    assert_eq!(call("203.0.113.1").await, StatusCode::TOO_MANY_REQUESTS);
+}
+
 #[tokio::test]
 async fn test_health_not_rate_limited() {
     let (state, _dir) = make_ratelimit_test_state(1); // 1 req/min limit
@@ -2,13 +2,18 @@
 
 mod test_helpers;
 
+use std::sync::Arc;
+
 use axum::body::Body;
 use axum::http::{Request, StatusCode};
 use http_body_util::BodyExt;
 use tower::ServiceExt;
 
 use operon_gateway::create_router;
-use test_helpers::{make_test_state, with_connect_info};
+use test_helpers::{
+    make_test_state, make_test_state_with_provider, make_test_state_with_workspace,
+    with_connect_info, FailingLLMProvider, MockLLMProvider, VisionLLMProvider,
+};
 
 /// Helper: build a request and call the router, return (status, body_bytes).
 async fn call(method: &str, uri: &str, body: Option<&str>) -> (StatusCode, Vec<u8>) {
@@ -74,13 +79,57 @@ impl TestApp {
 // ── Health ──────────────────────────────────────────────────────────────
 
 #[tokio::test]
-async fn test_health_returns_ok() {
+async fn test_health_unknown_before_first_probe() {
     let (status, body) = call("GET", "/health", None).await;
     assert_eq!(status, StatusCode::OK);
 
     let json: serde_json::Value = serde_json::from_slice(&body).unwrap();
-    assert_eq!(json["status"], "ok");
+    // No provider probe has run yet
+    assert_eq!(json["status"], "unknown");
     assert!(json["version"].is_string());
+    assert_eq!(json["queue"]["running"], 0);
+    assert!(json["queue"]["capacity"].as_u64().unwrap() >= 1);
+}
+
+#[tokio::test]
+async fn test_health_reports_provider_status() {
+    let (state, _dir) = make_test_state_with_provider(Arc::new(MockLLMProvider));
+    let report = state.session_manager.refresh_provider_health().await;
+    assert!(report[0].healthy);
+
+    let (status, json) = get_health(state).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(json["status"], "ok");
+    assert_eq!(json["providers"][0]["model"], "mock");
+    assert_eq!(json["providers"][0]["healthy"], true);
+}
+
+#[tokio::test]
+async fn test_health_unavailable_when_provider_fails() {
+    let (state, _dir) = make_test_state_with_provider(Arc::new(FailingLLMProvider));
+    state.session_manager.refresh_provider_health().await;
+
+    let (status, json) = get_health(state).await;
+    assert_eq!(status, StatusCode::SERVICE_UNAVAILABLE);
+    assert_eq!(json["status"], "unhealthy");
+    assert!(json["providers"][0]["error"]
+        .as_str()
+        .unwrap()
+        .contains("401"));
+}
+
+async fn get_health(state: operon_gateway::AppState) -> (StatusCode, serde_json::Value) {
+    let req = with_connect_info(
+        Request::builder()
+            .method("GET")
+            .uri("/health")
+            .body(Body::empty())
+            .unwrap(),
+    );
+    let resp = create_router(state).oneshot(req).await.unwrap();
+    let status = resp.status();
+    let bytes = resp.into_body().collect().await.unwrap().to_bytes();
+    (status, serde_json::from_slice(&bytes).unwrap())
 }
 
 // ── Session CRUD ────────────────────────────────────────────────────────
@@ -182,6 +231,90 @@ async fn test_send_message_success() {
     assert!(json["content"].as_str().unwrap().contains("mock"));
 }
 
+#[tokio::test]
+async fn test_send_message_with_attachments() {
+    let workspace = tempfile::tempdir().unwrap();
+    std::fs::write(workspace.path().join("shot.png"), b"\x89PNG\r\n\x1a\nbody").unwrap();
+    let app = |provider: Arc<dyn operon_runtime::LLMProvider>| {
+        let (state, _dir) = make_test_state_with_workspace(provider, workspace.path());
+        TestApp { state, _dir }
+    };
+    let session = |app: &TestApp| {
+        let app = app.state.session_manager.clone();
+        async move { app.create(None).await.unwrap() }
+    };
+    let message = |paths: &[&str]| {
+        serde_json::json!({ "content": "What is this?", "attachments": paths }).to_string()
+    };
+
+    let vision = app(Arc::new(VisionLLMProvider));
+    let uri = format!("/api/v1/sessions/{}/messages", session(&vision).await);
+    let (status, _) = vision
+        .call("POST", &uri, Some(&message(&["shot.png"])))
+        .await;
+    assert_eq!(status, StatusCode::OK);
+
+    let (status, body) = vision
+        .call("POST", &uri, Some(&message(&["../outside.png"])))
+        .await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+    assert!(String::from_utf8_lossy(&body).contains("Path traversal denied"));
+
+    // Models without vision reject the turn
+    let blind = app(Arc::new(MockLLMProvider));
+    let uri = format!("/api/v1/sessions/{}/messages", session(&blind).await);
+    let (status, body) = blind
+        .call("POST", &uri, Some(&message(&["shot.png"])))
+        .await;
+    assert_eq!(status, StatusCode::INTERNAL_SERVER_ERROR);
+    assert!(String::from_utf8_lossy(&body).contains("does not accept images"));
+
+    // Without a workspace, attachments are refused
+    let app = TestApp::new();
+    let uri = format!("/api/v1/sessions/{}/messages", session(&app).await);
+    let (status, body) = app.call("POST", &uri, Some(&message(&["shot.png"]))).await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+    assert!(String::from_utf8_lossy(&body).contains("Attachments are disabled"));
+}
+
+#[tokio::test]
+async fn test_fork_session() {
+    let app = TestApp::new();
+    let (_, body) = app.call("POST", "/api/v1/sessions", Some(r#"{}"#)).await;
+    let created: serde_json::Value = serde_json::from_slice(&body).unwrap();
+    let sid = created["session_id"].as_str().unwrap();
+    let uri = format!("/api/v1/sessions/{}/messages", sid);
+    for content in ["first", "second"] {
+        let payload = serde_json::json!({ "content": content }).to_string();
+        app.call("POST", &uri, Some(&payload)).await;
+    }
+
+    let uri = format!("/api/v1/sessions/{}/fork", sid);
+    let (status, body) = app.call("POST", &uri, Some(r#"{"at":2}"#)).await;
+    assert_eq!(status, StatusCode::CREATED);
+    let fork: serde_json::Value = serde_json::from_slice(&body).unwrap();
+    assert_ne!(fork["session_id"], sid);
+    assert_eq!(fork["message_count"], 2);
+
+    // The original keeps its full history
+    let (_, body) = app
+        .call("GET", &format!("/api/v1/sessions/{}", sid), None)
+        .await;
+    let original: serde_json::Value = serde_json::from_slice(&body).unwrap();
+    assert_eq!(original["message_count"], 4);
+    assert_eq!(original["usage"]["input_tokens"], 20);
+    assert_eq!(original["usage"]["output_tokens"], 10);
+    // No pricing for the mock model
+    assert_eq!(original["usage"]["cost_usd"], 0.0);
+
+    let (status, _) = app.call("POST", &uri, Some(r#"{"at":9}"#)).await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+    let (status, _) = app
+        .call("POST", "/api/v1/sessions/missing/fork", Some(r#"{}"#))
+        .await;
+    assert_eq!(status, StatusCode::NOT_FOUND);
+}
+
 #[tokio::test]
 async fn test_message_too_large() {
     let app = TestApp::new();
@@ -198,3 +331,56 @@ async fn test_message_too_large() {
     let (status, _) = app.call("POST", &uri, Some(&payload)).await;
     assert_eq!(status, StatusCode::PAYLOAD_TOO_LARGE);
 }
+
+#[tokio::test]
+async fn test_sessions_saved_to_the_store_after_each_message() {
+    let dir = tempfile::tempdir().unwrap();
+    let runtime = operon_runtime::Runtime::with_db(
+        dir.path().join("test.db").to_str().unwrap(),
+        true,
+        std::time::Duration::from_secs(30),
+    )
+    .unwrap();
+    let store = Arc::new(
+        operon_runtime::SqliteSessionStore::open(&dir.path().join("sessions.db")).unwrap(),
+    );
+    let manager = operon_gateway::SessionManager::new(Arc::new(MockLLMProvider), Arc::new(runtime))
+        .with_session_store(store.clone());
+
+    let sid = manager.create(None).await.unwrap();
+    manager.send_message(&sid, "hello").await.unwrap();
+    assert_eq!(store.load(&sid).await.unwrap().message_count(), 2);
+
+    let fork = manager.fork(&sid, Some(1)).await.unwrap();
+    assert_eq!(store.load(&fork).await.unwrap().message_count(), 1);
+}
+
+// ── Plans ───────────────────────────────────────────────────────────────
+
+#[tokio::test]
+async fn test_plan_schedule_returns_levels() {
+    let plan = r#"{"id":"p1","steps":[
+        {"id":"a","tool":"shell","depends_on":[]},
+        {"id":"b","tool":"shell","depends_on":[]},
+        {"id":"c","tool":"shell","depends_on":["a","b"]}]}"#;
+    let (status, body) = call("POST", "/api/v1/plans/schedule", Some(plan)).await;
+    assert_eq!(status, StatusCode::OK);
+
+    let json: serde_json::Value = serde_json::from_slice(&body).unwrap();
+    assert_eq!(json["plan_id"], "p1");
+    assert_eq!(json["levels"], serde_json::json!([["a", "b"], ["c"]]));
+    assert_eq!(json["max_concurrency"], 2);
+    assert_eq!(json["sequential"], false);
+}
+
+#[tokio::test]
+async fn test_plan_schedule_rejects_cycle() {
+    let plan = r#"{"steps":[
+        {"id":"a","tool":"shell","depends_on":["b"]},
+        {"id":"b","tool":"shell","depends_on":["a"]}]}"#;
+    let (status, body) = call("POST", "/api/v1/plans/schedule", Some(plan)).await;
+    assert_eq!(status, StatusCode::UNPROCESSABLE_ENTITY);
+
+    let json: serde_json::Value = serde_json::from_slice(&body).unwrap();
+    assert!(json["error"].as_str().unwrap().contains("Cycle"));
+}
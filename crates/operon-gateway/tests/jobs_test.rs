@@ -0,0 +1,147 @@
+//! Tests for asynchronous message jobs: submit, poll, events and conflicts.
+
+mod test_helpers;
+
+use std::sync::Arc;
+use std::time::Duration;
+
+use anyhow::Result;
+use async_trait::async_trait;
+use axum::body::Body;
+use axum::http::{Request, StatusCode};
+use http_body_util::BodyExt;
+use operon_runtime::llm::{
+    Content, GenerateConfig, GenerateResponse, LLMProvider, Message, StopReason, ToolSchema, Usage,
+};
+use serde_json::Value;
+use tower::ServiceExt;
+
+use operon_gateway::types::SessionEvent;
+use operon_gateway::{create_router, AppState};
+use test_helpers::{make_test_state_with_provider, with_connect_info, MockLLMProvider};
+
+/// Replies like `MockLLMProvider`, but only after a delay
+struct SlowLLMProvider;
+
+#[async_trait]
+impl LLMProvider for SlowLLMProvider {
+    async fn generate(
+        &self,
+        _messages: &[Message],
+        _tools: &[ToolSchema],
+        _config: &GenerateConfig,
+    ) -> Result<GenerateResponse> {
+        tokio::time::sleep(Duration::from_millis(300)).await;
+        Ok(GenerateResponse {
+            content: Content::Text {
+                text: "slow response".to_string(),
+            },
+            stop_reason: StopReason::EndTurn,
+            usage: Usage::default(),
+            model: "slow".to_string(),
+        })
+    }
+
+    fn supports_vision(&self) -> bool {
+        false
+    }
+
+    fn model_name(&self) -> &str {
+        "slow"
+    }
+}
+
+async fn call(
+    state: &AppState,
+    method: &str,
+    uri: &str,
+    body: Option<&str>,
+) -> (StatusCode, Value) {
+    let builder = Request::builder().method(method).uri(uri);
+    let req = match body {
+        Some(json) => builder
+            .header("content-type", "application/json")
+            .body(Body::from(json.to_string()))
+            .unwrap(),
+        None => builder.body(Body::empty()).unwrap(),
+    };
+    let resp = create_router(state.clone())
+        .oneshot(with_connect_info(req))
+        .await
+        .unwrap();
+    let status = resp.status();
+    let bytes = resp.into_body().collect().await.unwrap().to_bytes();
+    (
+        status,
+        serde_json::from_slice(&bytes).unwrap_or(Value::Null),
+    )
+}
+
+async fn create_session(state: &AppState) -> String {
+    let (status, body) = call(state, "POST", "/api/v1/sessions", Some("{}")).await;
+    assert_eq!(status, StatusCode::CREATED);
+    body["session_id"].as_str().unwrap().to_string()
+}
+
+#[tokio::test]
+async fn test_async_message_returns_job_and_reports_result() {
+    let (state, _dir) = make_test_state_with_provider(Arc::new(MockLLMProvider));
+    let session_id = create_session(&state).await;
+    let mut events = state.session_manager.subscribe(&session_id).await.unwrap();
+
+    let (status, job) = call(
+        &state,
+        "POST",
+        &format!("/api/v1/sessions/{}/messages/async", session_id),
+        Some(r#"{"content": "hello"}"#),
+    )
+    .await;
+    assert_eq!(status, StatusCode::ACCEPTED);
+    assert_eq!(job["session_id"], session_id.as_str());
+    let job_id = job["job_id"].as_str().unwrap().to_string();
+
+    // The streamed reply, then the job outcome, arrive on the session channel
+    let finished = loop {
+        let event = tokio::time::timeout(Duration::from_secs(5), events.recv())
+            .await
+            .unwrap()
+            .unwrap();
+        if let SessionEvent::JobFinished { job_id, error, .. } = event {
+            break Some((job_id, error));
+        }
+    };
+    assert_eq!(finished, Some((job_id.clone(), None)));
+
+    let (status, job) = call(&state, "GET", &format!("/api/v1/jobs/{}", job_id), None).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(job["status"], "succeeded");
+    assert_eq!(job["content"], "mock response");
+    assert!(job["finished_at"].is_string());
+}
+
+#[tokio::test]
+async fn test_busy_session_and_unknown_ids_are_rejected() {
+    let (state, _dir) = make_test_state_with_provider(Arc::new(SlowLLMProvider));
+    let session_id = create_session(&state).await;
+    let uri = format!("/api/v1/sessions/{}/messages/async", session_id);
+
+    let (status, job) = call(&state, "POST", &uri, Some(r#"{"content": "one"}"#)).await;
+    assert_eq!(status, StatusCode::ACCEPTED);
+    assert_eq!(job["status"], "running");
+
+    let (status, body) = call(&state, "POST", &uri, Some(r#"{"content": "two"}"#)).await;
+    assert_eq!(status, StatusCode::CONFLICT);
+    assert!(body["error"].as_str().unwrap().contains("is busy with job"));
+
+    let (status, _) = call(
+        &state,
+        "POST",
+        "/api/v1/sessions/missing/messages/async",
+        Some(r#"{"content": "x"}"#),
+    )
+    .await;
+    assert_eq!(status, StatusCode::NOT_FOUND);
+
+    let (status, _) = call(&state, "GET", "/api/v1/jobs/missing", None).await;
+    assert_eq!(status, StatusCode::NOT_FOUND);
+}
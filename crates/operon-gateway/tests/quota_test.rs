@@ -0,0 +1,226 @@
+//! Tests for per-identity usage quotas: counting, limits and 429 responses.
+
+mod test_helpers;
+
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::time::Duration;
+
+use axum::body::Body;
+use axum::http::{Request, StatusCode};
+use axum::response::Response;
+use axum::Router;
+use chrono::{DateTime, Utc};
+use operon_runtime::llm::LLMProvider;
+use operon_runtime::{Runtime, Storage};
+use tower::ServiceExt;
+
+use operon_gateway::{
+    create_router, AppState, AuthConfig, QuotaLimits, QuotaTracker, RateLimiter, Reloadable,
+    SessionManager,
+};
+use test_helpers::{with_connect_info, MockLLMProvider};
+
+fn tracker(dir: &tempfile::TempDir, limits: QuotaLimits) -> QuotaTracker {
+    let storage = Storage::open(dir.path().join("quotas.db").to_str().unwrap()).unwrap();
+    QuotaTracker::new(storage, limits)
+}
+
+/// Router with API keys `alice` and `bob` and the given quota tracker
+fn quota_app(quota: QuotaTracker, dir: &tempfile::TempDir) -> (Router, Arc<QuotaTracker>) {
+    let api_keys = HashMap::from([
+        ("alice".to_string(), "alice-key".to_string()),
+        ("bob".to_string(), "bob-key".to_string()),
+    ]);
+    quota_app_with_auth(quota, dir, AuthConfig::new(None).with_api_keys(api_keys))
+}
+
+fn quota_app_with_auth(
+    quota: QuotaTracker,
+    dir: &tempfile::TempDir,
+    auth_config: AuthConfig,
+) -> (Router, Arc<QuotaTracker>) {
+    let runtime = Arc::new(
+        Runtime::with_db(
+            dir.path().join("test.db").to_str().unwrap(),
+            true,
+            Duration::from_secs(30),
+        )
+        .unwrap(),
+    );
+    let provider: Arc<dyn LLMProvider> = Arc::new(MockLLMProvider);
+    let quota = Arc::new(quota);
+    let session_manager =
+        Arc::new(SessionManager::new(provider, runtime).with_quota(quota.clone()));
+    let state = AppState {
+        session_manager,
+        auth_config: Reloadable::new(auth_config),
+        rate_limiter: Arc::new(RateLimiter::new(1000)),
+        allowed_origins: Reloadable::new(vec![]),
+        trusted_proxy_headers: vec![],
+    };
+    (create_router(state), quota)
+}
+
+async fn call(app: &Router, method: &str, uri: &str, key: &str, body: Option<&str>) -> Response {
+    let req = Request::builder()
+        .method(method)
+        .uri(uri)
+        .header("Authorization", format!("Bearer {}", key))
+        .header("Content-Type", "application/json")
+        .body(body.map_or(Body::empty(), |b| Body::from(b.to_string())))
+        .unwrap();
+    app.clone().oneshot(with_connect_info(req)).await.unwrap()
+}
+
+#[test]
+fn test_tracker_counts_requests_and_tokens() {
+    let dir = tempfile::tempdir().unwrap();
+    let quota = tracker(
+        &dir,
+        QuotaLimits {
+            daily_requests: Some(2),
+            monthly_tokens: Some(100),
+            ..Default::default()
+        },
+    )
+    .with_limits("vip", QuotaLimits::default());
+
+    assert!(quota.admit("alice").unwrap().is_none());
+    assert!(quota.admit("alice").unwrap().is_none());
+    let exceeded = quota.admit("alice").unwrap().unwrap();
+    assert_eq!(exceeded.scope, "daily-requests");
+    assert_eq!((exceeded.limit, exceeded.used), (2, 2));
+    let tomorrow = Utc::now().date_naive().succ_opt().unwrap();
+    assert_eq!(exceeded.reset.date_naive(), tomorrow);
+    assert_eq!(exceeded.reset.format("%H:%M:%S").to_string(), "00:00:00");
+
+    // Tokens count per period; identities are independent
+    quota.add_tokens("bob", 100).unwrap();
+    let exceeded = quota.admit("bob").unwrap().unwrap();
+    assert_eq!(exceeded.scope, "monthly-tokens");
+    assert_eq!(exceeded.reset.format("%d").to_string(), "01");
+    let (today, month) = quota.usage("bob").unwrap();
+    assert_eq!((today.requests, today.tokens), (0, 100));
+    assert_eq!((month.requests, month.tokens), (0, 100));
+
+    // Identity-specific limits replace the defaults
+    for _ in 0..5 {
+        assert!(quota.admit("vip").unwrap().is_none());
+    }
+}
+
+#[test]
+fn test_usage_survives_reopening_storage() {
+    let dir = tempfile::tempdir().unwrap();
+    tracker(&dir, QuotaLimits::default())
+        .admit("alice")
+        .unwrap();
+    let (today, _) = tracker(&dir, QuotaLimits::default())
+        .usage("alice")
+        .unwrap();
+    assert_eq!(today.requests, 1);
+}
+
+#[test]
+fn test_expired_counters_are_evicted() {
+    let dir = tempfile::tempdir().unwrap();
+    let storage = Storage::open(dir.path().join("quotas.db").to_str().unwrap()).unwrap();
+    let old = serde_json::json!({"requests": 3, "tokens": 0});
+    for key in ["10.0.0.1/day/2020-01-01", "10.0.0.1/month/2020-01"] {
+        storage.save_state(key, &old).unwrap();
+    }
+    let quota = QuotaTracker::new(storage.clone(), QuotaLimits::default());
+
+    assert!(quota.admit("alice").unwrap().is_none());
+    let mut keys = storage.list_keys().unwrap();
+    keys.sort();
+    assert_eq!(
+        keys,
+        [
+            format!("alice/day/{}", Utc::now().format("%Y-%m-%d")),
+            format!("alice/month/{}", Utc::now().format("%Y-%m")),
+        ]
+    );
+}
+
+#[tokio::test]
+async fn test_requests_over_quota_get_429_with_headers() {
+    let dir = tempfile::tempdir().unwrap();
+    let limits = QuotaLimits {
+        daily_requests: Some(2),
+        ..Default::default()
+    };
+    let (app, _) = quota_app(tracker(&dir, limits), &dir);
+
+    for _ in 0..2 {
+        let resp = call(&app, "GET", "/api/v1/sessions", "alice-key", None).await;
+        assert_eq!(resp.status(), StatusCode::OK);
+    }
+    let resp = call(&app, "GET", "/api/v1/sessions", "alice-key", None).await;
+    assert_eq!(resp.status(), StatusCode::TOO_MANY_REQUESTS);
+    let header = |name: &str| resp.headers()[name].to_str().unwrap().to_string();
+    assert_eq!(header("x-quota-scope"), "daily-requests");
+    assert_eq!(header("x-quota-limit"), "2");
+    assert_eq!(header("x-quota-used"), "2");
+    assert!(DateTime::parse_from_rfc3339(&header("x-quota-reset")).is_ok());
+    assert!(header("retry-after").parse::<i64>().unwrap() > 0);
+
+    // Another key has its own count, and /health is never counted
+    let resp = call(&app, "GET", "/api/v1/sessions", "bob-key", None).await;
+    assert_eq!(resp.status(), StatusCode::OK);
+    let resp = call(&app, "GET", "/health", "alice-key", None).await;
+    assert_eq!(resp.status(), StatusCode::OK);
+}
+
+#[tokio::test]
+async fn test_auth_exempt_paths_are_not_counted() {
+    let dir = tempfile::tempdir().unwrap();
+    let limits = QuotaLimits {
+        daily_requests: Some(1),
+        ..Default::default()
+    };
+    let (app, quota) = quota_app_with_auth(
+        tracker(&dir, limits),
+        &dir,
+        AuthConfig::new(None).with_exempt_paths(vec!["/api/v1/sessions*".into()]),
+    );
+
+    for _ in 0..3 {
+        let resp = call(&app, "GET", "/api/v1/sessions", "", None).await;
+        assert_eq!(resp.status(), StatusCode::OK);
+    }
+    // /health is no longer exempt, so it counts
+    let resp = call(&app, "GET", "/health", "", None).await;
+    assert_eq!(resp.status(), StatusCode::OK);
+    let resp = call(&app, "GET", "/health", "", None).await;
+    assert_eq!(resp.status(), StatusCode::TOO_MANY_REQUESTS);
+    let (today, _) = quota.usage("127.0.0.1").unwrap();
+    assert_eq!(today.requests, 1);
+}
+
+#[tokio::test]
+async fn test_message_tokens_are_charged_to_the_key() {
+    let dir = tempfile::tempdir().unwrap();
+    let (app, quota) = quota_app(tracker(&dir, QuotaLimits::default()), &dir);
+
+    let resp = call(&app, "POST", "/api/v1/sessions", "alice-key", Some("{}")).await;
+    assert_eq!(resp.status(), StatusCode::CREATED);
+    let body = http_body_util::BodyExt::collect(resp.into_body())
+        .await
+        .unwrap()
+        .to_bytes();
+    let session: serde_json::Value = serde_json::from_slice(&body).unwrap();
+    let uri = format!(
+        "/api/v1/sessions/{}/messages",
+        session["session_id"].as_str().unwrap()
+    );
+    let resp = call(&app, "POST", &uri, "alice-key", Some(r#"{"content":"hi"}"#)).await;
+    assert_eq!(resp.status(), StatusCode::OK);
+
+    // MockLLMProvider reports 10 input + 5 output tokens
+    let (today, month) = quota.usage("alice").unwrap();
+    assert_eq!((today.requests, today.tokens), (2, 15));
+    assert_eq!(month.tokens, 15);
+    assert_eq!(quota.usage("bob").unwrap().0.requests, 0);
+}
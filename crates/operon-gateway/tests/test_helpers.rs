@@ -9,13 +9,14 @@ use anyhow::Result;
 use async_trait::async_trait;
 use axum::extract::ConnectInfo;
 use axum::http::Request;
+use operon_adapters::WorkspaceGuard;
 use operon_runtime::llm::{
     Content, GenerateConfig, GenerateResponse, LLMProvider, Message, StopReason, StreamChunk,
     ToolSchema, Usage,
 };
 use operon_runtime::Runtime;
 
-use operon_gateway::{AppState, AuthConfig, RateLimiter, SessionManager};
+use operon_gateway::{AppState, AuthConfig, RateLimiter, Reloadable, SessionManager};
 
 /// Add ConnectInfo extension to a request (required by rate limiter middleware).
 pub fn with_connect_info<B>(mut req: Request<B>) -> Request<B> {
@@ -43,6 +44,7 @@ impl LLMProvider for MockLLMProvider {
             usage: Usage {
                 input_tokens: 10,
                 output_tokens: 5,
+                cost_usd: 0.0,
             },
             model: "mock".to_string(),
         })
@@ -56,13 +58,15 @@ impl LLMProvider for MockLLMProvider {
     ) -> Result<tokio::sync::mpsc::Receiver<StreamChunk>> {
         let (tx, rx) = tokio::sync::mpsc::channel(1);
         tokio::spawn(async move {
-            let _ = tx.send(StreamChunk::TextDelta("mock".into())).await;
+            let _ = tx.send(StreamChunk::TextDelta("mock ".into())).await;
+            let _ = tx.send(StreamChunk::TextDelta("response".into())).await;
             let _ = tx
                 .send(StreamChunk::Done {
                     stop_reason: StopReason::EndTurn,
                     usage: Usage {
                         input_tokens: 10,
                         output_tokens: 5,
+                        cost_usd: 0.0,
                     },
                 })
                 .await;
@@ -79,6 +83,63 @@ impl LLMProvider for MockLLMProvider {
     }
 }
 
+/// `MockLLMProvider` that accepts images
+pub struct VisionLLMProvider;
+
+#[async_trait]
+impl LLMProvider for VisionLLMProvider {
+    async fn generate(
+        &self,
+        messages: &[Message],
+        tools: &[ToolSchema],
+        config: &GenerateConfig,
+    ) -> Result<GenerateResponse> {
+        MockLLMProvider.generate(messages, tools, config).await
+    }
+
+    async fn generate_stream(
+        &self,
+        messages: &[Message],
+        tools: &[ToolSchema],
+        config: &GenerateConfig,
+    ) -> Result<tokio::sync::mpsc::Receiver<StreamChunk>> {
+        MockLLMProvider
+            .generate_stream(messages, tools, config)
+            .await
+    }
+
+    fn supports_vision(&self) -> bool {
+        true
+    }
+
+    fn model_name(&self) -> &str {
+        "vision"
+    }
+}
+
+/// Mock LLM provider whose every call fails (e.g. a revoked API key)
+pub struct FailingLLMProvider;
+
+#[async_trait]
+impl LLMProvider for FailingLLMProvider {
+    async fn generate(
+        &self,
+        _messages: &[Message],
+        _tools: &[ToolSchema],
+        _config: &GenerateConfig,
+    ) -> Result<GenerateResponse> {
+        Err(anyhow::anyhow!("API error (401): invalid x-api-key"))
+    }
+
+    fn supports_vision(&self) -> bool {
+        false
+    }
+
+    fn model_name(&self) -> &str {
+        "failing"
+    }
+}
+
 /// Build runtime with tempdir-backed DB (auto-cleaned on drop).
 fn make_test_runtime() -> (Arc<Runtime>, tempfile::TempDir) {
     let dir = tempfile::tempdir().unwrap();
@@ -99,9 +160,51 @@ pub fn make_test_state() -> (AppState, tempfile::TempDir) {
     (
         AppState {
             session_manager,
-            auth_config: Arc::new(AuthConfig::new(None)),
+            auth_config: Reloadable::new(AuthConfig::new(None)),
+            rate_limiter: Arc::new(RateLimiter::new(1000)),
+            allowed_origins: Reloadable::new(vec![]),
+            trusted_proxy_headers: vec![],
+        },
+        dir,
+    )
+}
+
+/// Build a test AppState backed by the given provider.
+pub fn make_test_state_with_provider(
+    provider: Arc<dyn LLMProvider>,
+) -> (AppState, tempfile::TempDir) {
+    let (runtime, dir) = make_test_runtime();
+    let session_manager = Arc::new(SessionManager::new(provider, runtime));
+
+    (
+        AppState {
+            session_manager,
+            auth_config: Reloadable::new(AuthConfig::new(None)),
+            rate_limiter: Arc::new(RateLimiter::new(1000)),
+            allowed_origins: Reloadable::new(vec![]),
+            trusted_proxy_headers: vec![],
+        },
+        dir,
+    )
+}
+
+/// Build a test AppState whose sessions may attach files from `workspace`.
+pub fn make_test_state_with_workspace(
+    provider: Arc<dyn LLMProvider>,
+    workspace: &std::path::Path,
+) -> (AppState, tempfile::TempDir) {
+    let (runtime, dir) = make_test_runtime();
+    let guard = WorkspaceGuard::new(workspace.to_path_buf(), 10).unwrap();
+    let session_manager =
+        Arc::new(SessionManager::new(provider, runtime).with_workspace(Arc::new(guard)));
+
+    (
+        AppState {
+            session_manager,
+            auth_config: Reloadable::new(AuthConfig::new(None)),
             rate_limiter: Arc::new(RateLimiter::new(1000)),
-            allowed_origins: vec![],
+            allowed_origins: Reloadable::new(vec![]),
+            trusted_proxy_headers: vec![],
         },
         dir,
     )
@@ -116,9 +219,10 @@ pub fn make_auth_test_state(token: &str) -> (AppState, tempfile::TempDir) {
     (
         AppState {
             session_manager,
-            auth_config: Arc::new(AuthConfig::new(Some(token.to_string()))),
+            auth_config: Reloadable::new(AuthConfig::new(Some(token.to_string()))),
             rate_limiter: Arc::new(RateLimiter::new(1000)),
-            allowed_origins: vec![],
+            allowed_origins: Reloadable::new(vec![]),
+            trusted_proxy_headers: vec![],
         },
         dir,
     )
@@ -133,9 +237,10 @@ pub fn make_ratelimit_test_state(max_rpm: u32) -> (AppState, tempfile::TempDir)
     (
         AppState {
             session_manager,
-            auth_config: Arc::new(AuthConfig::new(None)),
+            auth_config: Reloadable::new(AuthConfig::new(None)),
             rate_limiter: Arc::new(RateLimiter::new(max_rpm)),
-            allowed_origins: vec![],
+            allowed_origins: Reloadable::new(vec![]),
+            trusted_proxy_headers: vec![],
         },
         dir,
     )
@@ -13,7 +13,7 @@ use http_body_util::BodyExt;
 use tower::ServiceExt;
 
 use operon_gateway::create_router;
-use test_helpers::{make_test_state, with_connect_info};
+use test_helpers::{make_test_state, make_test_state_with_provider, with_connect_info};
 
 /// WebSocket upgrade requires specific headers. Without them, axum returns 400/upgrade required.
 #[tokio::test]
@@ -108,17 +108,59 @@ async fn test_session_event_broadcast() {
     // This uses MockLLMProvider so the agent returns "mock response"
     let _ = state.session_manager.send_message(&sid, "hello").await;
 
-    // Should receive the broadcast event
-    let event = tokio::time::timeout(std::time::Duration::from_secs(2), rx.recv())
-        .await
-        .expect("timed out waiting for event")
-        .expect("channel closed");
-
-    let json = serde_json::to_value(&event).unwrap();
+    // Should receive the broadcast reply (after the streamed text deltas)
+    let json = loop {
+        let event = tokio::time::timeout(std::time::Duration::from_secs(2), rx.recv())
+            .await
+            .expect("timed out waiting for event")
+            .expect("channel closed");
+        let json = serde_json::to_value(&event).unwrap();
+        if json["type"] != "text_delta" {
+            break json;
+        }
+    };
     assert_eq!(json["type"], "agent_response");
     assert!(json["content"].as_str().unwrap().contains("mock"));
 }
 
+/// A turn streams as text_delta frames, then agent_response and done.
+#[tokio::test]
+async fn test_turn_streams_deltas_then_response_and_done() {
+    let (state, _dir) = make_test_state();
+    let manager = state.session_manager;
+    let sid = manager.create(None).await.unwrap();
+    let mut rx = manager.subscribe(&sid).await.unwrap();
+
+    let reply = manager.send_message(&sid, "hello").await.unwrap();
+    assert_eq!(reply, "mock response");
+
+    let frames: Vec<serde_json::Value> = std::iter::from_fn(|| rx.try_recv().ok())
+        .map(|event| serde_json::to_value(&event).unwrap())
+        .collect();
+    let types: Vec<&str> = frames.iter().map(|f| f["type"].as_str().unwrap()).collect();
+    assert_eq!(
+        types,
+        vec!["text_delta", "text_delta", "agent_response", "done"]
+    );
+    assert_eq!(frames[0]["content"], "mock ");
+    assert_eq!(frames[3]["usage"]["output_tokens"], 5);
+}
+
+/// A failed turn ends with an error frame instead of a reply.
+#[tokio::test]
+async fn test_failed_turn_sends_error_frame() {
+    let (state, _dir) =
+        make_test_state_with_provider(std::sync::Arc::new(test_helpers::FailingLLMProvider));
+    let manager = state.session_manager;
+    let sid = manager.create(None).await.unwrap();
+    let mut rx = manager.subscribe(&sid).await.unwrap();
+
+    assert!(manager.send_message(&sid, "hello").await.is_err());
+    let frame = serde_json::to_value(rx.try_recv().unwrap()).unwrap();
+    assert_eq!(frame["type"], "error");
+    assert!(rx.try_recv().is_err());
+}
+
 /// Subscribe to nonexistent session returns error.
 #[tokio::test]
 async fn test_subscribe_nonexistent_session() {
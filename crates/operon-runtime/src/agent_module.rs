@@ -1,14 +1,23 @@
 use std::sync::Arc;
 
-use anyhow::{anyhow, Context, Result};
+use anyhow::{anyhow, bail, Context, Result};
+use async_trait::async_trait;
 use chrono::{DateTime, Utc};
+use futures::stream::{self, BoxStream, StreamExt};
 use serde::{Deserialize, Serialize};
+use std::borrow::Cow;
 use std::collections::HashMap;
 use std::path::PathBuf;
-use tracing::{info, warn};
+use tokio::sync::{mpsc, oneshot};
+use tracing::{debug, info, warn};
 
+use crate::hooks::HookEvent;
 use crate::llm::provider::LLMProvider;
+use crate::llm::streaming::StreamAssembler;
+use crate::llm::tokenizer::{estimator_for_model, TokenBudget};
 use crate::llm::types::*;
+use crate::redaction::Redactor;
+use crate::tool::PermissionLevel;
 use crate::Runtime;
 
 // ============================================================================
@@ -37,6 +46,169 @@ pub struct AgentConfig {
     /// LLM model override (empty = use provider default)
     #[serde(default)]
     pub model: String,
+    /// Highest tool permission this agent may use (None = Execute; Admin
+    /// lifts the cap)
+    #[serde(default)]
+    pub max_permission: Option<PermissionLevel>,
+    /// Summarize history in the background once this many messages follow
+    /// the last summary (0 = never)
+    #[serde(default)]
+    pub summarize_every: usize,
+    /// Example conversations sent ahead of the session history
+    #[serde(default)]
+    pub examples: Vec<FewShotExample>,
+    /// Estimated tokens the examples may use; later examples that do not
+    /// fit are left out
+    #[serde(default = "default_examples_max_tokens")]
+    pub examples_max_tokens: usize,
+    /// Stream responses: text arrives as it is generated and the turn is
+    /// written to the session as it grows
+    #[serde(default)]
+    pub stream: bool,
+    /// How the history is kept within the context window
+    #[serde(default)]
+    pub context: ContextConfig,
+    /// Model context window in tokens. When set, each prompt is checked
+    /// against it, less `max_tokens` kept for the response, before it is
+    /// sent, and tool results that would not fit can be denied by the
+    /// `context_window` policy layer.
+    #[serde(default)]
+    pub context_window: Option<usize>,
+    /// Spend limit for the session in US dollars; the loop stops once the
+    /// estimated cost reaches it
+    #[serde(default)]
+    pub max_cost_usd: Option<f64>,
+}
+
+impl AgentConfig {
+    /// Budget from `context_window` and `max_tokens`, if a window is set
+    pub fn token_budget(&self) -> Option<TokenBudget> {
+        self.context_window
+            .map(|window| TokenBudget::new(window, self.max_tokens as usize))
+    }
+}
+
+/// How the history sent to the LLM is cut down in long sessions
+#[derive(
+    Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize, schemars::JsonSchema,
+)]
+#[serde(rename_all = "snake_case")]
+pub enum ContextStrategy {
+    /// Send the whole history
+    #[default]
+    None,
+    /// Leave out the oldest turns until the history fits `max_tokens`
+    TruncateOldest,
+    /// Send only the last `keep_recent` messages
+    SlidingWindow,
+    /// Once the history outgrows `max_tokens`, fold all but the last
+    /// `keep_recent` messages into the session summary and send the summary
+    /// in their place
+    Summarize,
+}
+
+/// Context window management, applied before each LLM call. The session keeps
+/// its full history; only what is sent is cut down, always at the start of a
+/// user turn so tool calls stay with their results.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, schemars::JsonSchema)]
+pub struct ContextConfig {
+    #[serde(default)]
+    pub strategy: ContextStrategy,
+    /// Estimated tokens (4 bytes each) the history may use
+    #[serde(default = "default_context_max_tokens")]
+    pub max_tokens: usize,
+    /// Latest messages `sliding_window` and `summarize` keep as they are
+    #[serde(default = "default_keep_recent")]
+    pub keep_recent: usize,
+}
+
+fn default_context_max_tokens() -> usize {
+    100_000
+}
+
+fn default_keep_recent() -> usize {
+    20
+}
+
+impl Default for ContextConfig {
+    fn default() -> Self {
+        Self {
+            strategy: ContextStrategy::None,
+            max_tokens: default_context_max_tokens(),
+            keep_recent: default_keep_recent(),
+        }
+    }
+}
+
+/// One example exchange: a user request, the tool calls the assistant makes
+/// for it, and its final answer
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct FewShotExample {
+    pub user: String,
+    #[serde(default)]
+    pub tool_calls: Vec<ExampleToolCall>,
+    pub assistant: String,
+}
+
+/// Tool call in a `FewShotExample`, with the output it returned
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ExampleToolCall {
+    pub name: String,
+    #[serde(default)]
+    pub input: serde_json::Value,
+    #[serde(default)]
+    pub output: String,
+}
+
+impl FewShotExample {
+    /// Conversation turns of this example; tool call IDs are prefixed with `id`
+    fn messages(&self, id: &str) -> Vec<Message> {
+        let mut messages = vec![Message::user(&self.user)];
+        if !self.tool_calls.is_empty() {
+            let calls = self
+                .tool_calls
+                .iter()
+                .enumerate()
+                .map(|(i, call)| {
+                    Content::ToolCall(ToolCall {
+                        id: format!("{}_{}", id, i),
+                        name: call.name.clone(),
+                        input: call.input.clone(),
+                    })
+                })
+                .collect();
+            messages.push(Message::assistant(Content::Mixed { parts: calls }));
+            for (i, call) in self.tool_calls.iter().enumerate() {
+                messages.push(Message {
+                    role: Role::User,
+                    content: Content::ToolResult(ToolResult {
+                        tool_use_id: format!("{}_{}", id, i),
+                        name: call.name.clone(),
+                        output: call.output.clone(),
+                        is_error: false,
+                    }),
+                });
+            }
+        }
+        messages.push(Message::assistant(Content::Text {
+            text: self.assistant.clone(),
+        }));
+        messages
+    }
+
+    /// Rough token count (4 bytes per token)
+    fn estimated_tokens(&self) -> usize {
+        let calls: usize = self
+            .tool_calls
+            .iter()
+            .map(|call| call.name.len() + call.input.to_string().len() + call.output.len())
+            .sum();
+        (self.user.len() + self.assistant.len() + calls).div_ceil(4)
+    }
+}
+
+fn default_examples_max_tokens() -> usize {
+    2000
 }
 
 fn default_max_iterations() -> usize {
@@ -61,6 +233,14 @@ impl Default for AgentConfig {
             max_tokens: default_max_tokens(),
             tools: Vec::new(),
             model: String::new(),
+            max_permission: None,
+            summarize_every: 0,
+            examples: Vec::new(),
+            examples_max_tokens: default_examples_max_tokens(),
+            stream: false,
+            context: ContextConfig::default(),
+            context_window: None,
+            max_cost_usd: None,
         }
     }
 }
@@ -69,6 +249,29 @@ impl Default for AgentConfig {
 // Session
 // ============================================================================
 
+/// Instructions for the background summarizer
+const SUMMARY_PROMPT: &str = "You maintain a running summary of a conversation between a user \
+and an assistant with tools. Merge the new messages into the previous summary. Keep decisions, \
+facts learned, files and commands involved, and open tasks. Reply with the summary only.";
+
+/// Max characters of one tool result quoted to the summarizer
+const SUMMARY_TOOL_OUTPUT_CHARS: usize = 500;
+
+/// Heads the session summary when it stands in for the history it covers
+const SUMMARY_CONTEXT_HEADER: &str = "Summary of the earlier conversation:";
+
+/// Latest session messages policy layers see with each tool call
+const POLICY_HISTORY_MESSAGES: usize = 20;
+
+/// Running summary of a session's history, kept in `metadata["summary"]`
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SessionSummary {
+    pub text: String,
+    /// Number of leading messages the summary covers
+    pub through: usize,
+    pub updated_at: DateTime<Utc>,
+}
+
 /// Conversation session with message history
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Session {
@@ -105,6 +308,53 @@ impl Session {
         self
     }
 
+    /// Copy of the first `at` messages (None: all of them) as a new session,
+    /// so another direction can be explored without touching this one. The
+    /// fork keeps the metadata and records where it came from in
+    /// `metadata["forked_from"]`.
+    pub fn fork(&self, at: Option<usize>) -> Result<Session> {
+        let at = at.unwrap_or(self.messages.len());
+        if at > self.messages.len() {
+            bail!(
+                "Session {} has only {} messages",
+                self.id,
+                self.messages.len()
+            );
+        }
+        // Every tool call must stay paired with its results
+        let splits_tool_call = at > 0
+            && self.messages[at - 1].role == Role::Assistant
+            && !self.messages[at - 1]
+                .content
+                .extract_tool_calls()
+                .is_empty();
+        let splits_results = matches!(
+            self.messages.get(at),
+            Some(Message {
+                content: Content::ToolResult(_),
+                ..
+            })
+        );
+        if splits_tool_call || splits_results {
+            bail!(
+                "Cannot fork at message {}: it falls inside a tool call and its results",
+                at
+            );
+        }
+
+        let mut fork = Session::new(&self.agent_name);
+        fork.messages = self.messages[..at].to_vec();
+        fork.metadata = self.metadata.clone();
+        if self.summary().is_some_and(|summary| summary.through > at) {
+            fork.metadata.remove("summary");
+        }
+        fork.metadata.insert(
+            "forked_from".to_string(),
+            serde_json::json!({ "session": self.id, "at": at }),
+        );
+        Ok(fork)
+    }
+
     /// Add a message to conversation history
     pub fn add_message(&mut self, msg: Message) {
         self.messages.push(msg);
@@ -125,6 +375,26 @@ impl Session {
     pub fn message_count(&self) -> usize {
         self.messages.len()
     }
+
+    /// Latest background summary of the history, if one has been written
+    pub fn summary(&self) -> Option<SessionSummary> {
+        self.metadata
+            .get("summary")
+            .and_then(|value| serde_json::from_value(value.clone()).ok())
+    }
+
+    /// Drop reasoning blocks from the history. They carry signatures only the
+    /// provider that produced them accepts.
+    pub fn strip_thinking(&mut self) {
+        self.messages.retain_mut(|msg| match &mut msg.content {
+            Content::Thinking { .. } => false,
+            Content::Mixed { parts } => {
+                parts.retain(|part| !matches!(part, Content::Thinking { .. }));
+                !parts.is_empty()
+            }
+            _ => true,
+        });
+    }
 }
 
 // ============================================================================
@@ -134,19 +404,37 @@ impl Session {
 /// Persistent session store (JSON files)
 pub struct SessionStore {
     base_path: PathBuf,
+    redactor: Option<Arc<Redactor>>,
 }
 
 impl SessionStore {
     pub fn new(base_path: PathBuf) -> Result<Self> {
         std::fs::create_dir_all(&base_path)
             .context(format!("Failed to create session dir: {:?}", base_path))?;
-        Ok(Self { base_path })
+        Ok(Self {
+            base_path,
+            redactor: None,
+        })
+    }
+
+    /// Redact sensitive values in what `save` writes (the session in memory
+    /// is left as is)
+    pub fn with_redactor(mut self, redactor: Arc<Redactor>) -> Self {
+        self.redactor = Some(redactor);
+        self
     }
 
     /// Save session to JSON file
     pub async fn save(&self, session: &Session) -> Result<()> {
         let path = self.base_path.join(format!("{}.json", session.id));
-        let json = serde_json::to_string_pretty(session)?;
+        let json = match &self.redactor {
+            Some(redactor) => {
+                let mut session = session.clone();
+                redactor.redact_session(&mut session);
+                serde_json::to_string_pretty(&session)?
+            }
+            None => serde_json::to_string_pretty(session)?,
+        };
         tokio::fs::write(&path, json)
             .await
             .context(format!("Failed to save session: {:?}", path))?;
@@ -178,6 +466,64 @@ impl SessionStore {
     }
 }
 
+/// Where sessions are kept: JSON files ([`SessionStore`]) or SQLite
+/// ([`crate::SqliteSessionStore`])
+#[async_trait]
+pub trait SessionBackend: Send + Sync {
+    async fn save(&self, session: &Session) -> Result<()>;
+
+    async fn load(&self, session_id: &str) -> Result<Session>;
+
+    /// IDs of all stored sessions
+    async fn list_sessions(&self) -> Result<Vec<String>>;
+}
+
+#[async_trait]
+impl SessionBackend for SessionStore {
+    async fn save(&self, session: &Session) -> Result<()> {
+        SessionStore::save(self, session).await
+    }
+
+    async fn load(&self, session_id: &str) -> Result<Session> {
+        SessionStore::load(self, session_id).await
+    }
+
+    async fn list_sessions(&self) -> Result<Vec<String>> {
+        SessionStore::list_sessions(self)
+    }
+}
+
+// ============================================================================
+// AgentEvent
+// ============================================================================
+
+/// Progress events emitted while the agent processes a message (for live UIs)
+#[derive(Debug, Clone)]
+pub enum AgentEvent {
+    /// Assistant text produced during a turn
+    TextDelta(String),
+    /// Streamed reasoning (only when the provider is set to show thinking)
+    Thinking(String),
+    /// A tool call is about to execute
+    ToolCallStarted {
+        id: String,
+        name: String,
+        input: serde_json::Value,
+    },
+    /// A tool call finished (output is the string sent back to the LLM)
+    ToolCallFinished {
+        id: String,
+        name: String,
+        output: String,
+        is_error: bool,
+    },
+    /// Last event of [`Agent::process_message_stream`]: the final reply and
+    /// the tokens the turn used
+    Done { text: String, usage: Usage },
+    /// Last event of [`Agent::process_message_stream`] when the turn failed
+    Error(String),
+}
+
 // ============================================================================
 // Agent
 // ============================================================================
@@ -188,6 +534,13 @@ pub struct Agent {
     provider: Arc<dyn LLMProvider>,
     runtime: Arc<Runtime>,
     pub session: Session,
+    events: Option<mpsc::UnboundedSender<AgentEvent>>,
+    /// Also gets the events of a turn run by `process_message_stream`
+    turn_events: Option<mpsc::UnboundedSender<AgentEvent>>,
+    /// Background summary in progress
+    pending_summary: Option<oneshot::Receiver<Result<SessionSummary>>>,
+    /// Images and other parts sent with the next user message
+    attachments: Vec<Content>,
 }
 
 impl Agent {
@@ -198,6 +551,10 @@ impl Agent {
             provider,
             runtime,
             session,
+            events: None,
+            turn_events: None,
+            pending_summary: None,
+            attachments: Vec::new(),
         }
     }
 
@@ -207,28 +564,236 @@ impl Agent {
         self
     }
 
+    /// Agent with the same settings and provider on a fork of this session
+    /// (see [`Session::fork`])
+    pub fn fork(&self, at: Option<usize>) -> Result<Agent> {
+        Ok(Self {
+            config: self.config.clone(),
+            provider: self.provider.clone(),
+            runtime: self.runtime.clone(),
+            session: self.session.fork(at)?,
+            events: None,
+            turn_events: None,
+            pending_summary: None,
+            attachments: Vec::new(),
+        })
+    }
+
+    /// Emit progress events (text, tool calls) to the given channel
+    pub fn with_event_sender(mut self, tx: mpsc::UnboundedSender<AgentEvent>) -> Self {
+        self.events = Some(tx);
+        self
+    }
+
+    /// Continue the session on another provider and model (empty: the
+    /// provider's own default). History is kept, minus reasoning blocks, and
+    /// the choice is recorded in the session metadata for resuming.
+    pub fn hand_off(&mut self, provider_name: &str, provider: Arc<dyn LLMProvider>, model: &str) {
+        info!(
+            session_id = %self.session.id,
+            from = %self.config.model,
+            provider = provider_name,
+            model,
+            "Handing session off to another model"
+        );
+        self.provider = provider;
+        self.config.model = model.to_string();
+        self.session.strip_thinking();
+        self.session
+            .metadata
+            .insert("provider".to_string(), provider_name.into());
+        self.session
+            .metadata
+            .insert("model".to_string(), model.into());
+    }
+
+    /// Send `content` (e.g. an image) along with the next user message
+    pub fn attach(&mut self, content: Content) {
+        self.attachments.push(content);
+    }
+
+    /// Parts waiting for the next user message
+    pub fn attachments(&self) -> &[Content] {
+        &self.attachments
+    }
+
+    fn emit(&self, event: AgentEvent) {
+        if let Some(ref tx) = self.turn_events {
+            let _ = tx.send(event.clone());
+        }
+        if let Some(ref tx) = self.events {
+            let _ = tx.send(event);
+        }
+    }
+
     /// Process user message through agent loop
     /// Returns final assistant text response
     pub async fn process_message(&mut self, user_msg: &str) -> Result<String> {
-        self.session.add_message(Message::user(user_msg));
+        self.run_turn(user_msg, self.config.stream).await
+    }
+
+    /// Process a user message with a streamed response, whatever
+    /// `config.stream` says. Yields text as it is generated and tool calls as
+    /// they run, then one `Done` or `Error`. Dropping the stream cancels the
+    /// turn, leaving a partial turn as an interrupted stream would.
+    pub fn process_message_stream<'a>(
+        &'a mut self,
+        user_msg: &'a str,
+    ) -> BoxStream<'a, AgentEvent> {
+        let (tx, mut rx) = mpsc::unbounded_channel();
+        let turn = async move {
+            self.turn_events = Some(tx.clone());
+            let before = self.session.cumulative_usage.clone();
+            let result = self.run_turn(user_msg, true).await;
+            self.turn_events = None;
+            let _ = tx.send(match result {
+                Ok(text) => {
+                    let after = &self.session.cumulative_usage;
+                    let usage = Usage {
+                        input_tokens: after.input_tokens.saturating_sub(before.input_tokens),
+                        output_tokens: after.output_tokens.saturating_sub(before.output_tokens),
+                        cost_usd: (after.cost_usd - before.cost_usd).max(0.0),
+                    };
+                    AgentEvent::Done { text, usage }
+                }
+                Err(e) => AgentEvent::Error(format!("{:#}", e)),
+            });
+        };
+        // The channel keeps events in order and ends once the turn drops its
+        // senders; the turn itself yields nothing
+        let events = stream::poll_fn(move |cx| rx.poll_recv(cx));
+        let turn = stream::once(turn).filter_map(|()| async { None });
+        stream::select(events, turn).boxed()
+    }
+
+    /// Answer `user_msg`, as rewritten by `MessageReceived` hooks, and report
+    /// the reply to `ResponseGenerated` hooks
+    async fn run_turn(&mut self, user_msg: &str, stream: bool) -> Result<String> {
+        self.runtime
+            .set_session_agent(&self.session.id, &self.config.name);
+        let payload = || serde_json::json!({"agent": self.config.name, "message": user_msg});
+        let received = self
+            .runtime
+            .trigger_hook_checked(HookEvent::MessageReceived, payload, Some(&self.session.id))
+            .await
+            .context("Message stopped by hook")?;
+        let user_msg = match received.as_ref().and_then(|data| data["message"].as_str()) {
+            Some(message) => message,
+            None => user_msg,
+        };
+
+        let response = self.run_loop(user_msg, stream).await?;
+        let generated = serde_json::json!({
+            "agent": self.config.name,
+            "message": user_msg,
+            "response": response,
+        });
+        self.runtime
+            .trigger_hook(
+                HookEvent::ResponseGenerated,
+                generated,
+                Some(&self.session.id),
+            )
+            .await;
+        Ok(response)
+    }
+
+    async fn run_loop(&mut self, user_msg: &str, stream: bool) -> Result<String> {
+        self.collect_summary();
+        let message = self.user_message(user_msg)?;
+        let committed = self.session.messages.len();
+        self.session.add_message(message);
 
+        let examples = self.example_messages();
         let mut iteration = 0;
         loop {
+            let (start, summary) = self.compact_history().await;
+            let system_prompt = match summary {
+                Some(summary) => format!(
+                    "{}\n\n{}\n{}",
+                    self.config.system_prompt, SUMMARY_CONTEXT_HEADER, summary
+                ),
+                None => self.config.system_prompt.clone(),
+            };
             let gen_config = GenerateConfig {
                 model: self.config.model.clone(),
                 max_tokens: self.config.max_tokens,
                 temperature: self.config.temperature,
-                system_prompt: Some(self.config.system_prompt.clone()),
+                system_prompt: Some(system_prompt),
+                ..Default::default()
             };
 
             let tools = self.available_tool_schemas();
 
-            let response = self
-                .provider
-                .generate(&self.session.messages, &tools, &gen_config)
-                .await?;
+            let history = &self.session.messages[start..];
+            let messages: Cow<[Message]> = if examples.is_empty() {
+                Cow::Borrowed(history)
+            } else {
+                Cow::Owned(examples.iter().chain(history).cloned().collect())
+            };
+
+            let estimator = estimator_for_model(self.provider.model_name());
+            let budget = self.config.token_budget();
+            let prompt_tokens = match budget {
+                Some(budget) => {
+                    let tokens = estimator.count_request(
+                        gen_config.system_prompt.as_deref(),
+                        &messages,
+                        &tools,
+                    );
+                    if let Err(e) = budget.check(tokens) {
+                        // The provider never saw the message; don't leave
+                        // it unanswered in the history
+                        if iteration == 0 {
+                            self.uncommit_user_message(committed);
+                        }
+                        return Err(e);
+                    }
+                    debug!(
+                        prompt_tokens = tokens,
+                        limit = budget.prompt_limit(),
+                        "Prompt within token budget"
+                    );
+                    tokens
+                }
+                None => 0,
+            };
+            if let Err(e) = self.check_cost_limit() {
+                if iteration == 0 {
+                    self.uncommit_user_message(committed);
+                }
+                return Err(e);
+            }
+            let mut response = if stream {
+                let rx = self
+                    .provider
+                    .generate_stream(&messages, &tools, &gen_config)
+                    .await?;
+                self.receive_stream(rx).await?
+            } else {
+                let response = self
+                    .provider
+                    .generate(&messages, &tools, &gen_config)
+                    .await?;
+                let text = response.content.extract_text();
+                if !text.is_empty() {
+                    self.emit(AgentEvent::TextDelta(text));
+                }
+                // Add assistant response to history
+                self.session
+                    .add_message(Message::assistant(response.content.clone()));
+                response
+            };
 
-            // Track cumulative usage
+            // Track cumulative usage, priced by the model that answered
+            let model = if response.model.is_empty() {
+                self.provider.model_name()
+            } else {
+                &response.model
+            };
+            if let Some(pricing) = ModelPricing::for_model(model) {
+                response.usage.cost_usd = pricing.cost(&response.usage);
+            }
             self.session.cumulative_usage += response.usage.clone();
 
             let total_tokens = self.session.cumulative_usage.total();
@@ -238,6 +803,7 @@ impl Agent {
                 input_tokens = response.usage.input_tokens,
                 output_tokens = response.usage.output_tokens,
                 cumulative_tokens = total_tokens,
+                cumulative_cost_usd = self.session.cumulative_usage.cost_usd,
                 "LLM response received"
             );
 
@@ -250,17 +816,43 @@ impl Agent {
                 );
             }
 
-            // Add assistant response to history
-            self.session
-                .add_message(Message::assistant(response.content.clone()));
-
             match response.stop_reason {
                 StopReason::EndTurn => {
+                    self.start_summary();
                     return Ok(response.content.extract_text());
                 }
                 StopReason::ToolUse => {
-                    let results = self.execute_tool_calls(&response.content).await?;
+                    if let Some(budget) = budget {
+                        let used = prompt_tokens + estimator.count_content(&response.content);
+                        self.runtime.set_context_tokens_left(
+                            &self.session.id,
+                            Some(budget.remaining(used)),
+                        );
+                    }
+                    if let Err(e) = self.check_cost_limit() {
+                        // Answer the calls so the history stays valid
+                        let skipped = response
+                            .content
+                            .extract_tool_calls()
+                            .into_iter()
+                            .map(|call| ToolResult {
+                                tool_use_id: call.id.clone(),
+                                name: call.name.clone(),
+                                output: format!("Not run: {}", e),
+                                is_error: true,
+                            })
+                            .collect();
+                        self.session.add_tool_results(skipped);
+                        return Err(e);
+                    }
+                    let (results, images) = self.execute_tool_calls(&response.content).await?;
                     self.session.add_tool_results(results);
+                    if !images.is_empty() {
+                        self.session.add_message(Message {
+                            role: Role::User,
+                            content: Content::Mixed { parts: images },
+                        });
+                    }
                 }
                 StopReason::MaxTokens => {
                     // Try to return partial text instead of hard error
@@ -287,98 +879,684 @@ impl Agent {
         }
     }
 
-    /// Execute tool calls from LLM response
-    async fn execute_tool_calls(&self, content: &Content) -> Result<Vec<ToolResult>> {
+    /// Take back the user turn added at `index`, returning its attachments
+    /// to the pending list
+    fn uncommit_user_message(&mut self, index: usize) {
+        let Some(message) = self.session.messages.drain(index..).next() else {
+            return;
+        };
+        if let Content::Mixed { parts } = message.content {
+            self.attachments = parts.into_iter().skip(1).collect();
+        }
+    }
+
+    /// The user turn for `text`, followed by any pending attachments
+    fn user_message(&mut self, text: &str) -> Result<Message> {
+        if self.attachments.is_empty() {
+            return Ok(Message::user(text));
+        }
+        let attachments = std::mem::take(&mut self.attachments);
+        let has_image = attachments
+            .iter()
+            .any(|part| matches!(part, Content::Image { .. }));
+        if has_image && !self.provider.supports_vision() {
+            bail!(
+                "Model '{}' does not accept images; {} attachment(s) dropped",
+                self.provider.model_name(),
+                attachments.len()
+            );
+        }
+        let mut parts = Vec::with_capacity(attachments.len() + 1);
+        parts.push(Content::Text {
+            text: text.to_string(),
+        });
+        parts.extend(attachments);
+        Ok(Message {
+            role: Role::User,
+            content: Content::Mixed { parts },
+        })
+    }
+
+    /// Fails once the session has spent `max_cost_usd`
+    fn check_cost_limit(&self) -> Result<()> {
+        let Some(limit) = self.config.max_cost_usd else {
+            return Ok(());
+        };
+        let spent = self.session.cumulative_usage.cost_usd;
+        if spent >= limit {
+            warn!(
+                spent,
+                limit, "Session cost limit reached, stopping agent loop"
+            );
+            return Err(anyhow!(
+                "Session cost ${:.4} reached the limit of ${:.4} (max_cost_usd)",
+                spent,
+                limit
+            ));
+        }
+        Ok(())
+    }
+
+    /// Turns of the configured examples, in order, as far as they fit
+    /// `examples_max_tokens`
+    fn example_messages(&self) -> Vec<Message> {
+        let mut budget = self.config.examples_max_tokens;
+        let mut messages = Vec::new();
+        for (i, example) in self.config.examples.iter().enumerate() {
+            let tokens = example.estimated_tokens();
+            if tokens > budget {
+                debug!(
+                    dropped = self.config.examples.len() - i,
+                    max_tokens = self.config.examples_max_tokens,
+                    "Few-shot examples trimmed to their token budget"
+                );
+                break;
+            }
+            budget -= tokens;
+            messages.extend(example.messages(&format!("example_{}", i)));
+        }
+        messages
+    }
+
+    /// Where the history sent to the LLM starts, per `config.context`, and
+    /// the summary standing in for what is left out, if the strategy keeps one
+    async fn compact_history(&mut self) -> (usize, Option<String>) {
+        let context = self.config.context.clone();
+        let messages = &self.session.messages;
+        let (start, summary) = match context.strategy {
+            ContextStrategy::None => return (0, None),
+            ContextStrategy::TruncateOldest => {
+                (fitting_start(messages, 0, context.max_tokens), None)
+            }
+            ContextStrategy::SlidingWindow => {
+                let at = messages.len().saturating_sub(context.keep_recent);
+                (turn_start_at_or_before(messages, at), None)
+            }
+            ContextStrategy::Summarize => self.summarized_history(&context).await,
+        };
+        if start > 0 {
+            debug!(
+                session_id = %self.session.id,
+                strategy = ?context.strategy,
+                left_out = start,
+                sent = self.session.messages.len() - start,
+                "History compacted"
+            );
+        }
+        (start, summary)
+    }
+
+    /// History start and summary for `ContextStrategy::Summarize`: the turns
+    /// the session summary covers are replaced by it, and once the rest
+    /// outgrows `max_tokens` all but the last `keep_recent` messages are
+    /// folded into it first. If summarizing fails, the oldest turns are left
+    /// out instead.
+    async fn summarized_history(&mut self, context: &ContextConfig) -> (usize, Option<String>) {
+        let messages = &self.session.messages;
+        // A summary that does not end at a turn cannot stand in for its messages
+        let summary = self
+            .session
+            .summary()
+            .filter(|summary| messages.get(summary.through).is_some_and(is_turn_start));
+        let from = summary.as_ref().map_or(0, |summary| summary.through);
+        if estimated_tokens(&messages[from..]) <= context.max_tokens {
+            return (from, summary.map(|summary| summary.text));
+        }
+
+        let cut =
+            turn_start_at_or_before(messages, messages.len().saturating_sub(context.keep_recent));
+        if cut <= from {
+            return (
+                fitting_start(messages, from, context.max_tokens),
+                summary.map(|summary| summary.text),
+            );
+        }
+        match self.summary_task(summary.clone(), from, cut).await {
+            Ok(folded) => {
+                let text = folded.text.clone();
+                self.store_summary(folded);
+                // What still does not fit is left out
+                let messages = &self.session.messages;
+                (fitting_start(messages, cut, context.max_tokens), Some(text))
+            }
+            Err(e) => {
+                warn!(
+                    session_id = %self.session.id,
+                    error = %e,
+                    "Summarizing history failed, leaving out the oldest turns instead"
+                );
+                (
+                    fitting_start(&self.session.messages, from, context.max_tokens),
+                    summary.map(|summary| summary.text),
+                )
+            }
+        }
+    }
+
+    /// Keep `summary` in the session metadata, unless the one there already
+    /// covers more of the history
+    fn store_summary(&mut self, summary: SessionSummary) {
+        if self
+            .session
+            .summary()
+            .is_some_and(|current| current.through > summary.through)
+        {
+            return;
+        }
+        info!(session_id = %self.session.id, through = summary.through, "Session summary updated");
+        match serde_json::to_value(&summary) {
+            Ok(value) => {
+                self.session.metadata.insert("summary".to_string(), value);
+            }
+            Err(e) => warn!(error = %e, "Failed to store session summary"),
+        }
+    }
+
+    /// Store a finished background summary in the session metadata; never
+    /// waits for one still running
+    fn collect_summary(&mut self) {
+        let Some(pending) = self.pending_summary.as_mut() else {
+            return;
+        };
+        match pending.try_recv() {
+            Err(oneshot::error::TryRecvError::Empty) => return,
+            Ok(Ok(summary)) => self.store_summary(summary),
+            Ok(Err(e)) => {
+                warn!(session_id = %self.session.id, error = %e, "Session summary failed")
+            }
+            Err(oneshot::error::TryRecvError::Closed) => {}
+        }
+        self.pending_summary = None;
+    }
+
+    /// Once `summarize_every` messages follow the last summary, fold them into
+    /// it on a background task (routed as `TaskKind::Summarize`), so the next
+    /// turn does not wait for it
+    fn start_summary(&mut self) {
+        let every = self.config.summarize_every;
+        if every == 0 || self.pending_summary.is_some() {
+            return;
+        }
+        let previous = self.session.summary();
+        let through = self.session.messages.len();
+        let from = previous.as_ref().map_or(0, |s| s.through.min(through));
+        if through - from < every {
+            return;
+        }
+
+        let task = self.summary_task(previous, from, through);
+        let (tx, rx) = oneshot::channel();
+        tokio::spawn(async move {
+            let _ = tx.send(task.await);
+        });
+        self.pending_summary = Some(rx);
+    }
+
+    /// Summarizer call folding `messages[from..through]` into `previous`
+    /// (routed as `TaskKind::Summarize`)
+    fn summary_task(
+        &self,
+        previous: Option<SessionSummary>,
+        from: usize,
+        through: usize,
+    ) -> impl std::future::Future<Output = Result<SessionSummary>> + Send + 'static {
+        let prompt = format!(
+            "Previous summary:\n{}\n\nNew messages:\n{}",
+            previous.map_or_else(|| "(none)".to_string(), |s| s.text),
+            transcript(&self.session.messages[from..through])
+        );
+        let config = GenerateConfig {
+            model: self.config.model.clone(),
+            max_tokens: 1024,
+            temperature: 0.2,
+            system_prompt: Some(SUMMARY_PROMPT.to_string()),
+            task: Some(TaskKind::Summarize),
+            ..Default::default()
+        };
+        let provider = self.provider.clone();
+        async move {
+            provider
+                .generate(&[Message::user(&prompt)], &[], &config)
+                .await
+                .map(|response| SessionSummary {
+                    text: response.content.extract_text(),
+                    through,
+                    updated_at: Utc::now(),
+                })
+        }
+    }
+
+    /// Read a streamed response into the session as it arrives. Until `Done`
+    /// the assistant message holds the text so far followed by
+    /// [`INTERRUPTED_MARKER`], so a stream that fails, or a turn that is
+    /// cancelled, leaves a marked partial turn; unfinished tool calls are
+    /// dropped. Signed reasoning blocks are kept, as the provider requires
+    /// them back with the tool results that follow.
+    async fn receive_stream(
+        &mut self,
+        rx: mpsc::Receiver<StreamChunk>,
+    ) -> Result<GenerateResponse> {
+        let index = self.session.messages.len();
+        self.session.add_message(Message::assistant(Content::Mixed {
+            parts: vec![
+                Content::Text {
+                    text: String::new(),
+                },
+                Content::Text {
+                    text: INTERRUPTED_MARKER.to_string(),
+                },
+            ],
+        }));
+        let mut stream = StreamAssembler::wrap(rx, self.provider.model_name());
+
+        let error = loop {
+            match stream.recv().await {
+                Some(StreamChunk::TextDelta(delta)) => {
+                    if let Some(text) = partial_text(&mut self.session.messages[index]) {
+                        text.push_str(&delta);
+                    }
+                    self.session.updated_at = Utc::now();
+                    self.emit(AgentEvent::TextDelta(delta));
+                }
+                // Tool calls and reasoning blocks are assembled by the stream
+                Some(
+                    StreamChunk::ToolCallStart { .. }
+                    | StreamChunk::ToolCallDelta { .. }
+                    | StreamChunk::ThinkingBlock { .. },
+                ) => {}
+                Some(StreamChunk::Thinking(delta)) => self.emit(AgentEvent::Thinking(delta)),
+                Some(StreamChunk::Done { .. }) => {
+                    if let Some(error) = stream.assembler().tool_input_error() {
+                        return Err(self.interrupted(error));
+                    }
+                    let response = stream.into_response();
+                    self.session.messages[index].content = response.content.clone();
+                    self.session.updated_at = Utc::now();
+                    return Ok(response);
+                }
+                Some(StreamChunk::Error(e)) => break e,
+                None => break "stream closed without completion".to_string(),
+            }
+        };
+        Err(self.interrupted(error))
+    }
+
+    /// Log an interrupted stream; the session keeps the marked partial turn
+    fn interrupted(&self, error: String) -> anyhow::Error {
+        warn!(session_id = %self.session.id, error = %error, "Response stream interrupted");
+        anyhow!("Response stream interrupted: {}", error)
+    }
+
+    /// Execute tool calls from LLM response. Images in tool output (see
+    /// [`take_image`]) come back separately, labelled, for a user message that
+    /// follows the results; providers without vision get a note instead.
+    async fn execute_tool_calls(
+        &self,
+        content: &Content,
+    ) -> Result<(Vec<ToolResult>, Vec<Content>)> {
         let tool_calls = content.extract_tool_calls();
         let mut results = Vec::new();
+        let mut images = Vec::new();
+        self.runtime
+            .set_session_agent(&self.session.id, &self.config.name);
+        let messages = &self.session.messages;
+        let recent = messages.len().saturating_sub(POLICY_HISTORY_MESSAGES);
+        self.runtime
+            .set_recent_messages(&self.session.id, messages[recent..].to_vec());
 
         for call in tool_calls {
             info!(tool = %call.name, id = %call.id, "Executing tool call");
+            self.emit(AgentEvent::ToolCallStarted {
+                id: call.id.clone(),
+                name: call.name.clone(),
+                input: call.input.clone(),
+            });
 
+            // Keyed by the call, so a call run again (e.g. after resuming an
+            // interrupted session) returns its first result
+            let key = format!("{}/{}", self.session.id, call.id);
             let output = match self
                 .runtime
-                .execute_tool(&call.name, call.input.clone())
+                .execute_tool_in_session_with_key(
+                    &call.name,
+                    call.input.clone(),
+                    self.caller_permission(),
+                    &self.session.id,
+                    &key,
+                )
                 .await
             {
-                Ok(value) => ToolResult {
-                    tool_use_id: call.id.clone(),
-                    name: call.name.clone(),
-                    output: value.to_string(),
-                    is_error: false,
-                },
+                Ok(mut value) => {
+                    if let Some(image) = take_image(&mut value) {
+                        if self.provider.supports_vision() {
+                            value["image"] = "attached below".into();
+                            images.push(Content::Text {
+                                text: format!("Image from {} ({}):", call.name, call.id),
+                            });
+                            images.push(image);
+                        } else {
+                            value["image"] = "omitted: the model cannot view images".into();
+                        }
+                    }
+                    ToolResult {
+                        tool_use_id: call.id.clone(),
+                        name: call.name.clone(),
+                        output: value.to_string(),
+                        is_error: false,
+                    }
+                }
                 Err(e) => {
                     warn!(tool = %call.name, error = %e, "Tool execution failed");
                     ToolResult {
                         tool_use_id: call.id.clone(),
                         name: call.name.clone(),
-                        output: format!("Error: {}", e),
+                        // With its causes, so the model learns why it failed
+                        output: format!("Error: {:#}", e),
                         is_error: true,
                     }
                 }
             };
 
+            self.emit(AgentEvent::ToolCallFinished {
+                id: output.tool_use_id.clone(),
+                name: output.name.clone(),
+                output: output.output.clone(),
+                is_error: output.is_error,
+            });
             results.push(output);
         }
 
-        Ok(results)
+        Ok((results, images))
+    }
+
+    /// Permission level this agent's tool calls are evaluated with
+    fn caller_permission(&self) -> PermissionLevel {
+        self.config
+            .max_permission
+            .clone()
+            .unwrap_or(PermissionLevel::Execute)
+    }
+
+    /// Start the session: report it to `SessionStart` hooks (agent, model and
+    /// messages so far, for resumed sessions)
+    pub async fn start_session(&self) {
+        let session = &self.session;
+        self.runtime
+            .set_session_agent(&session.id, &self.config.name);
+        let data = serde_json::json!({
+            "agent": self.config.name,
+            "model": self.provider.model_name(),
+            "messages": session.message_count(),
+        });
+        self.runtime
+            .trigger_hook(HookEvent::SessionStart, data, Some(&session.id))
+            .await;
+    }
+
+    /// End the session: report it to `SessionEnd` hooks (last reply, duration,
+    /// token usage) and release what the execution backend holds for it
+    pub async fn end_session(&self) -> Result<()> {
+        let session = &self.session;
+        let result = session
+            .messages
+            .iter()
+            .rev()
+            .find(|m| m.role == Role::Assistant)
+            .map(|m| m.content.extract_text())
+            .unwrap_or_default();
+        let duration_ms = (Utc::now() - session.created_at).num_milliseconds().max(0);
+        let summary = serde_json::json!({
+            "agent": session.agent_name,
+            "result": result,
+            "messages": session.message_count(),
+            "duration_ms": duration_ms,
+            "usage": session.cumulative_usage,
+        });
+        self.runtime
+            .set_session_agent(&session.id, &self.config.name);
+        self.runtime
+            .trigger_hook(HookEvent::SessionEnd, summary, Some(&session.id))
+            .await;
+        self.runtime.end_session(&session.id).await
     }
 
-    /// Build tool schemas from registered runtime tools
+    /// Schemas the registered tools declare, limited to the agent's allowlist
+    /// and permission cap
     fn available_tool_schemas(&self) -> Vec<ToolSchema> {
         let tool_names = if self.config.tools.is_empty() {
             self.runtime.tool_names()
         } else {
             self.config.tools.clone()
         };
+        let cap = self.caller_permission();
 
         tool_names
             .iter()
-            .map(|name| ToolSchema {
-                name: name.clone(),
-                description: format!("Execute the {} tool", name),
-                input_schema: serde_json::json!({
-                    "type": "object",
-                    "properties": {
-                        "input": {
-                            "type": "string",
-                            "description": "Input for the tool"
-                        }
-                    }
-                }),
+            .filter(|name| {
+                self.runtime
+                    .tool_permission(name)
+                    .is_none_or(|required| required <= cap)
+            })
+            .filter_map(|name| {
+                let Some(schema) = self.runtime.tool_schema(name) else {
+                    debug!(tool = %name, "Skipping unregistered tool");
+                    return None;
+                };
+                Some(ToolSchema {
+                    name: schema.name,
+                    description: schema.description,
+                    input_schema: schema.parameters,
+                })
             })
             .collect()
     }
 }
 
-#[cfg(test)]
-mod tests {
-    use super::*;
-    use async_trait::async_trait;
-    use std::sync::atomic::{AtomicUsize, Ordering};
+/// Whether sent history may start at `message`: a user turn, so no tool call
+/// is cut off from its results
+fn is_turn_start(message: &Message) -> bool {
+    message.role == Role::User && !matches!(message.content, Content::ToolResult(_))
+}
 
-    /// Mock LLM that returns predefined responses
-    struct MockLLM {
-        responses: Vec<GenerateResponse>,
-        call_count: AtomicUsize,
+/// Serialized size of a message, the basis of token estimates
+fn message_bytes(message: &Message) -> usize {
+    serde_json::to_string(message).map_or(0, |json| json.len())
+}
+
+/// Rough token count of `messages` (4 bytes per token)
+fn estimated_tokens(messages: &[Message]) -> usize {
+    messages
+        .iter()
+        .map(message_bytes)
+        .sum::<usize>()
+        .div_ceil(4)
+}
+
+/// Latest turn start at or before `at` that keeps at least one message
+/// (0 when there is none)
+fn turn_start_at_or_before(messages: &[Message], at: usize) -> usize {
+    (1..=at.min(messages.len().saturating_sub(1)))
+        .rev()
+        .find(|&i| is_turn_start(&messages[i]))
+        .unwrap_or(0)
+}
+
+/// Earliest turn start from `from` on whose history fits `max_tokens`, or the
+/// last turn start when none does
+fn fitting_start(messages: &[Message], from: usize, max_tokens: usize) -> usize {
+    let mut bytes: usize = messages[from..].iter().map(message_bytes).sum();
+    let mut start = from;
+    for i in from + 1..messages.len() {
+        if bytes.div_ceil(4) <= max_tokens {
+            break;
+        }
+        if is_turn_start(&messages[i]) {
+            bytes -= messages[start..i].iter().map(message_bytes).sum::<usize>();
+            start = i;
+        }
     }
+    start
+}
 
-    impl MockLLM {
-        fn new(responses: Vec<GenerateResponse>) -> Self {
-            Self {
-                responses,
-                call_count: AtomicUsize::new(0),
+/// Plain-text rendering of `messages` for the summarizer
+fn transcript(messages: &[Message]) -> String {
+    fn render(content: &Content, out: &mut Vec<String>) {
+        match content {
+            Content::Text { text } => out.push(text.clone()),
+            Content::Image { .. } => out.push("[image]".to_string()),
+            Content::Audio { .. } => out.push("[audio]".to_string()),
+            Content::ToolCall(call) => {
+                out.push(format!("[tool call {}: {}]", call.name, call.input))
             }
+            Content::ToolResult(result) => {
+                let output: String = result
+                    .output
+                    .chars()
+                    .take(SUMMARY_TOOL_OUTPUT_CHARS)
+                    .collect();
+                out.push(format!("[tool result {}: {}]", result.name, output));
+            }
+            Content::Mixed { parts } => parts.iter().for_each(|part| render(part, out)),
+            Content::Thinking { .. } => {}
         }
     }
 
-    #[async_trait]
-    impl LLMProvider for MockLLM {
-        async fn generate(
+    messages
+        .iter()
+        .map(|msg| {
+            let mut parts = Vec::new();
+            render(&msg.content, &mut parts);
+            let role = match msg.role {
+                Role::System => "system",
+                Role::User => "user",
+                Role::Assistant => "assistant",
+            };
+            format!("{}: {}", role, parts.join("\n"))
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+/// Closes an assistant turn whose response stream did not complete
+pub const INTERRUPTED_MARKER: &str = "[response interrupted]";
+
+/// Text received so far in a turn being streamed (see `receive_stream`)
+fn partial_text(message: &mut Message) -> Option<&mut String> {
+    match &mut message.content {
+        Content::Mixed { parts } => match parts.first_mut() {
+            Some(Content::Text { text }) => Some(text),
+            _ => None,
+        },
+        _ => None,
+    }
+}
+
+/// Remove an `{"image": {"mime": "image/...", "data": "<base64>"}}` field
+/// from tool output and decode it
+fn take_image(value: &mut serde_json::Value) -> Option<Content> {
+    use base64::Engine;
+    let image = value.get("image")?;
+    let mime = image.get("mime")?.as_str()?;
+    if !mime.starts_with("image/") {
+        return None;
+    }
+    let data = base64::engine::general_purpose::STANDARD
+        .decode(image.get("data")?.as_str()?)
+        .ok()?;
+    let mime = mime.to_string();
+    value.as_object_mut()?.remove("image");
+    Some(Content::Image { data, mime })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    /// Mock LLM that plays back predefined responses (or chunk sequences
+    /// when streamed) and records the system prompt and messages of each
+    /// turn request; summarizer calls are not recorded
+    struct MockLLM {
+        responses: Vec<GenerateResponse>,
+        call_count: AtomicUsize,
+        /// Replayed by `generate_stream`, one sequence per request
+        streams: std::sync::Mutex<Vec<Vec<StreamChunk>>>,
+        /// Answer every turn "ok" and every summarizer call "summary"
+        /// instead of playing back `responses`
+        reply_ok: bool,
+        vision: bool,
+        requests: std::sync::Mutex<Vec<(String, Vec<Message>)>>,
+    }
+
+    impl MockLLM {
+        fn new(responses: Vec<GenerateResponse>) -> Self {
+            Self {
+                responses,
+                call_count: AtomicUsize::new(0),
+                streams: std::sync::Mutex::new(Vec::new()),
+                reply_ok: false,
+                vision: false,
+                requests: std::sync::Mutex::new(Vec::new()),
+            }
+        }
+
+        fn replying_ok() -> Self {
+            Self {
+                reply_ok: true,
+                ..Self::new(Vec::new())
+            }
+        }
+
+        fn streaming(streams: Vec<Vec<StreamChunk>>) -> Self {
+            Self {
+                streams: std::sync::Mutex::new(streams),
+                ..Self::new(Vec::new())
+            }
+        }
+
+        fn with_vision(mut self) -> Self {
+            self.vision = true;
+            self
+        }
+
+        /// System prompt and messages of the `n`th turn request
+        fn request(&self, n: usize) -> (String, Vec<Message>) {
+            self.requests.lock().unwrap()[n].clone()
+        }
+
+        fn record(&self, messages: &[Message], config: &GenerateConfig) {
+            if config.task != Some(TaskKind::Summarize) {
+                let prompt = config.system_prompt.clone().unwrap_or_default();
+                self.requests
+                    .lock()
+                    .unwrap()
+                    .push((prompt, messages.to_vec()));
+            }
+        }
+    }
+
+    #[async_trait]
+    impl LLMProvider for MockLLM {
+        async fn generate(
             &self,
-            _messages: &[Message],
+            messages: &[Message],
             _tools: &[ToolSchema],
-            _config: &GenerateConfig,
+            config: &GenerateConfig,
         ) -> Result<GenerateResponse> {
+            self.record(messages, config);
+            if self.reply_ok {
+                let text = if config.task == Some(TaskKind::Summarize) {
+                    "summary"
+                } else {
+                    "ok"
+                };
+                return Ok(GenerateResponse {
+                    content: Content::Text { text: text.into() },
+                    stop_reason: StopReason::EndTurn,
+                    usage: Usage::default(),
+                    model: "mock".into(),
+                });
+            }
             let idx = self.call_count.fetch_add(1, Ordering::Relaxed);
             self.responses
                 .get(idx)
@@ -386,8 +1564,30 @@ mod tests {
                 .ok_or_else(|| anyhow!("No more mock responses"))
         }
 
+        async fn generate_stream(
+            &self,
+            messages: &[Message],
+            tools: &[ToolSchema],
+            config: &GenerateConfig,
+        ) -> Result<mpsc::Receiver<StreamChunk>> {
+            let chunks = {
+                let mut streams = self.streams.lock().unwrap();
+                (!streams.is_empty()).then(|| streams.remove(0))
+            };
+            let Some(chunks) = chunks else {
+                let response = self.generate(messages, tools, config).await?;
+                return Ok(crate::llm::provider::response_to_stream(response));
+            };
+            self.record(messages, config);
+            let (tx, rx) = mpsc::channel(chunks.len().max(1));
+            for chunk in chunks {
+                tx.try_send(chunk).unwrap();
+            }
+            Ok(rx)
+        }
+
         fn supports_vision(&self) -> bool {
-            false
+            self.vision
         }
 
         fn model_name(&self) -> &str {
@@ -427,6 +1627,413 @@ mod tests {
         assert_eq!(agent.session.message_count(), 2); // user + assistant
     }
 
+    #[test]
+    fn test_fork_copies_history_up_to_a_message() {
+        let mut session = Session::new("default");
+        session.add_message(Message::user("List files"));
+        session.add_message(Message::assistant(Content::ToolCall(ToolCall {
+            id: "t1".into(),
+            name: "shell".into(),
+            input: serde_json::json!({"command": "ls"}),
+        })));
+        session.add_tool_results(vec![ToolResult {
+            tool_use_id: "t1".into(),
+            name: "shell".into(),
+            output: "a.txt".into(),
+            is_error: false,
+        }]);
+        session.add_message(Message::assistant(Content::Text {
+            text: "One file.".into(),
+        }));
+        session.metadata.insert(
+            "summary".to_string(),
+            serde_json::json!({"text": "s", "through": 4, "updated_at": Utc::now()}),
+        );
+
+        let fork = session.fork(Some(1)).unwrap();
+        assert_ne!(fork.id, session.id);
+        assert_eq!(fork.message_count(), 1);
+        assert_eq!(fork.metadata["forked_from"]["session"], session.id.as_str());
+        assert!(fork.summary().is_none());
+        assert_eq!(session.fork(None).unwrap().message_count(), 4);
+        assert!(session.fork(None).unwrap().summary().is_some());
+
+        let err = session.fork(Some(2)).unwrap_err();
+        assert!(err.to_string().contains("inside a tool call"));
+        assert_eq!(session.fork(Some(3)).unwrap().message_count(), 3);
+        assert!(session.fork(Some(5)).is_err());
+        assert_eq!(session.message_count(), 4);
+    }
+
+    #[tokio::test]
+    async fn test_hand_off_keeps_history_without_thinking() {
+        let reply = |text: &str| GenerateResponse {
+            content: Content::Mixed {
+                parts: vec![
+                    Content::Thinking {
+                        thinking: "hmm".into(),
+                        signature: "sig".into(),
+                    },
+                    Content::Text { text: text.into() },
+                ],
+            },
+            stop_reason: StopReason::EndTurn,
+            usage: Usage::default(),
+            model: "mock".into(),
+        };
+        let (runtime, _dir) = make_runtime();
+        let mut agent = Agent::new(
+            AgentConfig::default(),
+            Arc::new(MockLLM::new(vec![reply("cheap answer")])),
+            runtime,
+        );
+        agent.process_message("First question").await.unwrap();
+
+        agent.hand_off(
+            "openai",
+            Arc::new(MockLLM::new(vec![reply("strong answer")])),
+            "gpt-4o",
+        );
+        assert_eq!(agent.config.model, "gpt-4o");
+        assert_eq!(agent.session.metadata["provider"], "openai");
+        assert_eq!(agent.session.message_count(), 2);
+        assert!(matches!(
+            &agent.session.messages[1].content,
+            Content::Mixed { parts } if parts.len() == 1
+        ));
+
+        let result = agent.process_message("Harder question").await.unwrap();
+        assert_eq!(result, "strong answer");
+        assert_eq!(agent.session.message_count(), 4);
+    }
+
+    #[tokio::test]
+    async fn test_background_summary_lands_on_next_turn() {
+        let text = |text: &str| GenerateResponse {
+            content: Content::Text { text: text.into() },
+            stop_reason: StopReason::EndTurn,
+            usage: Usage::default(),
+            model: "mock".into(),
+        };
+        let llm = Arc::new(MockLLM::new(vec![
+            text("Use cargo test."),
+            text("User asked how to run tests; answer: cargo test."),
+            text("Sure."),
+        ]));
+        let (runtime, _dir) = make_runtime();
+        let config = AgentConfig {
+            summarize_every: 2,
+            ..AgentConfig::default()
+        };
+        let mut agent = Agent::new(config, llm, runtime);
+
+        agent.process_message("How do I run tests?").await.unwrap();
+        assert!(agent.session.summary().is_none());
+        // Let the summarizer task finish before the next turn picks it up
+        tokio::time::sleep(std::time::Duration::from_millis(50)).await;
+        agent.process_message("Thanks").await.unwrap();
+
+        let summary = agent.session.summary().unwrap();
+        assert_eq!(summary.through, 2);
+        assert_eq!(
+            summary.text,
+            "User asked how to run tests; answer: cargo test."
+        );
+        // Two new messages again, so the next segment is already underway
+        assert!(agent.pending_summary.is_some());
+    }
+
+    /// Session of `turns` user/assistant exchanges of about 100 tokens each,
+    /// the first one with a tool call
+    fn long_session(turns: usize) -> Session {
+        let mut session = Session::new("default");
+        for i in 0..turns {
+            session.add_message(Message::user(&format!(
+                "question {} {}",
+                i,
+                "x".repeat(200)
+            )));
+            if i == 0 {
+                session.add_message(Message::assistant(Content::ToolCall(ToolCall {
+                    id: "call_0".into(),
+                    name: "shell".into(),
+                    input: serde_json::json!({"command": "ls"}),
+                })));
+                session.add_tool_results(vec![ToolResult {
+                    tool_use_id: "call_0".into(),
+                    name: "shell".into(),
+                    output: "src".into(),
+                    is_error: false,
+                }]);
+            }
+            session.add_message(Message::assistant(Content::Text {
+                text: format!("answer {} {}", i, "y".repeat(200)),
+            }));
+        }
+        session
+    }
+
+    fn context_agent(context: ContextConfig) -> (Agent, Arc<MockLLM>, tempfile::TempDir) {
+        let llm = Arc::new(MockLLM::replying_ok());
+        let (runtime, dir) = make_runtime();
+        let config = AgentConfig {
+            context,
+            ..AgentConfig::default()
+        };
+        let agent = Agent::new(config, llm.clone(), runtime).with_session(long_session(5));
+        (agent, llm, dir)
+    }
+
+    #[tokio::test]
+    async fn test_truncate_oldest_leaves_out_whole_turns() {
+        let (mut agent, llm, _dir) = context_agent(ContextConfig {
+            strategy: ContextStrategy::TruncateOldest,
+            max_tokens: 300,
+            ..ContextConfig::default()
+        });
+        agent.process_message("latest").await.unwrap();
+
+        // Turns are about 130 tokens, so two fit along with the new message
+        let (_, sent) = &llm.request(0);
+        assert!(estimated_tokens(sent) <= 300);
+        assert_eq!(sent.len(), 5);
+        assert!(sent[0].content.extract_text().starts_with("question 3"));
+        assert_eq!(sent.last().unwrap().content.extract_text(), "latest");
+        // The session itself keeps everything
+        assert_eq!(agent.session.message_count(), 14);
+    }
+
+    #[tokio::test]
+    async fn test_sliding_window_keeps_tool_results_with_their_call() {
+        let (mut agent, llm, _dir) = context_agent(ContextConfig {
+            strategy: ContextStrategy::SlidingWindow,
+            keep_recent: 10,
+            ..ContextConfig::default()
+        });
+        agent.process_message("latest").await.unwrap();
+
+        // The last 10 messages start inside the first turn's tool call, so
+        // the window widens to that whole turn
+        let (_, sent) = &llm.request(0);
+        assert_eq!(sent.len(), 13);
+        assert!(sent[0].content.extract_text().starts_with("question 0"));
+
+        // A window starting at an assistant reply widens to its question
+        let messages = &agent.session.messages;
+        assert_eq!(turn_start_at_or_before(messages, messages.len() - 5), 8);
+    }
+
+    #[tokio::test]
+    async fn test_summarize_replaces_early_turns_with_summary() {
+        let (mut agent, llm, _dir) = context_agent(ContextConfig {
+            strategy: ContextStrategy::Summarize,
+            max_tokens: 500,
+            keep_recent: 4,
+        });
+        agent.process_message("latest").await.unwrap();
+
+        let summary = agent.session.summary().unwrap();
+        assert_eq!(summary.text, "summary");
+        assert_eq!(summary.through, 8);
+        let (prompt, sent) = &llm.request(0);
+        assert!(prompt.ends_with("Summary of the earlier conversation:\nsummary"));
+        assert_eq!(sent.len(), 5);
+        assert!(sent[0].content.extract_text().starts_with("question 3"));
+    }
+
+    #[tokio::test]
+    async fn test_examples_precede_history_within_budget() {
+        let config = AgentConfig {
+            examples: vec![
+                FewShotExample {
+                    user: "List the files".into(),
+                    tool_calls: vec![ExampleToolCall {
+                        name: "shell".into(),
+                        input: serde_json::json!({"command": "ls"}),
+                        output: "Cargo.toml\nsrc".into(),
+                    }],
+                    assistant: "Cargo.toml and src.".into(),
+                },
+                FewShotExample {
+                    user: "x".repeat(400),
+                    tool_calls: Vec::new(),
+                    assistant: "too long for the budget".into(),
+                },
+            ],
+            examples_max_tokens: 50,
+            ..AgentConfig::default()
+        };
+        let llm = Arc::new(MockLLM::replying_ok());
+        let (runtime, _dir) = make_runtime();
+        let mut agent = Agent::new(config, llm.clone(), runtime);
+        agent.process_message("What is in here?").await.unwrap();
+
+        let (_, sent) = &llm.request(0);
+        assert_eq!(sent.len(), 5);
+        assert_eq!(sent[0].content.extract_text(), "List the files");
+        assert!(matches!(
+            &sent[2].content,
+            Content::ToolResult(result) if result.tool_use_id == "example_0_0"
+        ));
+        assert_eq!(sent[4].content.extract_text(), "What is in here?");
+        // Examples are not part of the session history
+        assert_eq!(agent.session.message_count(), 2);
+    }
+
+    #[tokio::test]
+    async fn test_end_session_reports_summary_to_hooks() {
+        use crate::hooks::{Hook, HookContext, HookRegistry, HookResult};
+
+        struct Capture(std::sync::Mutex<Vec<HookContext>>);
+
+        #[async_trait]
+        impl Hook for Capture {
+            fn name(&self) -> &str {
+                "capture"
+            }
+            fn events(&self) -> &[HookEvent] {
+                &[HookEvent::SessionEnd]
+            }
+            async fn on_event(&self, ctx: &HookContext) -> Result<HookResult> {
+                self.0.lock().unwrap().push(ctx.clone());
+                Ok(HookResult::default())
+            }
+        }
+
+        let llm = Arc::new(MockLLM::new(vec![GenerateResponse {
+            content: Content::Text {
+                text: "All green.".into(),
+            },
+            stop_reason: StopReason::EndTurn,
+            usage: Usage {
+                input_tokens: 120,
+                output_tokens: 30,
+                cost_usd: 0.0,
+            },
+            model: "mock".into(),
+        }]));
+        let capture = Arc::new(Capture(std::sync::Mutex::new(Vec::new())));
+        let hooks = Arc::new(HookRegistry::new());
+        hooks.register(capture.clone());
+        let dir = tempfile::tempdir().unwrap();
+        let runtime = Runtime::with_db(
+            dir.path().join("test.db").to_str().unwrap(),
+            true,
+            std::time::Duration::from_secs(30),
+        )
+        .unwrap()
+        .with_hooks(hooks);
+
+        let mut agent = Agent::new(AgentConfig::default(), llm, Arc::new(runtime));
+        agent.process_message("Run the checks").await.unwrap();
+        agent.end_session().await.unwrap();
+
+        let seen = capture.0.lock().unwrap();
+        assert_eq!(seen.len(), 1);
+        assert_eq!(
+            seen[0].session_id.as_deref(),
+            Some(agent.session.id.as_str())
+        );
+        assert_eq!(seen[0].data["result"], "All green.");
+        assert_eq!(seen[0].data["messages"], 2);
+        assert_eq!(seen[0].data["usage"]["input_tokens"], 120);
+    }
+
+    #[tokio::test]
+    async fn test_session_hooks_follow_the_conversation() {
+        use crate::hooks::{Hook, HookContext, HookRegistry, HookResult};
+
+        /// Records every event and redacts "password" from user messages;
+        /// aborts turns mentioning "forbidden"
+        struct Guardrail(std::sync::Mutex<Vec<HookContext>>);
+
+        #[async_trait]
+        impl Hook for Guardrail {
+            fn name(&self) -> &str {
+                "guardrail"
+            }
+            fn events(&self) -> &[HookEvent] {
+                &[
+                    HookEvent::SessionStart,
+                    HookEvent::MessageReceived,
+                    HookEvent::ResponseGenerated,
+                    HookEvent::SessionEnd,
+                ]
+            }
+            async fn on_event(&self, ctx: &HookContext) -> Result<HookResult> {
+                self.0.lock().unwrap().push(ctx.clone());
+                if ctx.event != HookEvent::MessageReceived {
+                    return Ok(HookResult::default());
+                }
+                let message = ctx.data["message"].as_str().unwrap_or_default();
+                let mut data = ctx.data.clone();
+                data["message"] = message.replace("password", "[redacted]").into();
+                Ok(HookResult {
+                    modified_data: Some(data),
+                    abort: message.contains("forbidden"),
+                })
+            }
+        }
+
+        let llm = Arc::new(MockLLM::new(vec![GenerateResponse {
+            content: Content::Text {
+                text: "Stored.".into(),
+            },
+            stop_reason: StopReason::EndTurn,
+            usage: Usage::default(),
+            model: "mock".into(),
+        }]));
+        let guardrail = Arc::new(Guardrail(std::sync::Mutex::new(Vec::new())));
+        let hooks = Arc::new(HookRegistry::new());
+        hooks.register(guardrail.clone());
+        let dir = tempfile::tempdir().unwrap();
+        let runtime = Runtime::with_db(
+            dir.path().join("test.db").to_str().unwrap(),
+            true,
+            std::time::Duration::from_secs(30),
+        )
+        .unwrap()
+        .with_hooks(hooks);
+
+        let config = AgentConfig {
+            name: "scribe".into(),
+            ..Default::default()
+        };
+        let mut agent = Agent::new(config, llm, Arc::new(runtime));
+        agent.start_session().await;
+        let reply = agent.process_message("Remember password hunter2").await;
+        assert_eq!(reply.unwrap(), "Stored.");
+        let err = agent.process_message("Do the forbidden thing").await;
+        assert!(format!("{:#}", err.unwrap_err()).contains("aborted"));
+        agent.end_session().await.unwrap();
+
+        // The agent saw the rewritten message; the aborted one never arrived
+        assert_eq!(agent.session.message_count(), 2);
+        assert_eq!(
+            agent.session.messages[0].content.extract_text(),
+            "Remember [redacted] hunter2"
+        );
+
+        let seen = guardrail.0.lock().unwrap();
+        let events: Vec<_> = seen.iter().map(|ctx| ctx.event.clone()).collect();
+        assert_eq!(
+            events,
+            vec![
+                HookEvent::SessionStart,
+                HookEvent::MessageReceived,
+                HookEvent::ResponseGenerated,
+                HookEvent::MessageReceived,
+                HookEvent::SessionEnd,
+            ]
+        );
+        for ctx in seen.iter() {
+            assert_eq!(ctx.agent_id.as_deref(), Some("scribe"));
+            assert_eq!(ctx.session_id.as_deref(), Some(agent.session.id.as_str()));
+        }
+        assert_eq!(seen[2].data["message"], "Remember [redacted] hunter2");
+        assert_eq!(seen[2].data["response"], "Stored.");
+    }
+
     #[tokio::test]
     async fn test_tool_call_then_response() {
         let llm = Arc::new(MockLLM::new(vec![
@@ -461,6 +2068,394 @@ mod tests {
         assert_eq!(agent.session.message_count(), 4);
     }
 
+    #[tokio::test]
+    async fn test_repeated_tool_call_ids_return_the_first_result() {
+        struct Counter(std::sync::atomic::AtomicUsize);
+
+        #[async_trait]
+        impl crate::Tool for Counter {
+            async fn execute(&self, _input: serde_json::Value) -> Result<serde_json::Value> {
+                Ok(serde_json::json!({ "call": self.0.fetch_add(1, Ordering::SeqCst) + 1 }))
+            }
+
+            fn name(&self) -> &str {
+                "counter"
+            }
+        }
+
+        let turn = |call_id: &str| {
+            vec![
+                GenerateResponse {
+                    content: Content::ToolCall(ToolCall {
+                        id: call_id.into(),
+                        name: "counter".into(),
+                        input: serde_json::json!({}),
+                    }),
+                    stop_reason: StopReason::ToolUse,
+                    usage: Usage::default(),
+                    model: "mock".into(),
+                },
+                GenerateResponse {
+                    content: Content::Text {
+                        text: "Counted.".into(),
+                    },
+                    stop_reason: StopReason::EndTurn,
+                    usage: Usage::default(),
+                    model: "mock".into(),
+                },
+            ]
+        };
+        let llm = Arc::new(MockLLM::new(
+            [turn("tc_1"), turn("tc_1"), turn("tc_2")].concat(),
+        ));
+        let dir = tempfile::tempdir().unwrap();
+        let runtime = Runtime::with_db(
+            dir.path().join("test.db").to_str().unwrap(),
+            false,
+            std::time::Duration::from_secs(30),
+        )
+        .unwrap();
+        let counter = Arc::new(Counter(AtomicUsize::new(0)));
+        runtime
+            .register_tool("counter".into(), counter.clone())
+            .unwrap();
+        let mut agent = Agent::new(AgentConfig::default(), llm, Arc::new(runtime));
+
+        for _ in 0..3 {
+            agent.process_message("Count").await.unwrap();
+        }
+        // tc_1 ran once; its repeat got the stored result
+        assert_eq!(counter.0.load(Ordering::SeqCst), 2);
+        let outputs: Vec<_> = agent
+            .session
+            .messages
+            .iter()
+            .filter_map(|m| match &m.content {
+                Content::ToolResult(result) => Some(result.output.clone()),
+                _ => None,
+            })
+            .collect();
+        assert_eq!(outputs, [r#"{"call":1}"#, r#"{"call":1}"#, r#"{"call":2}"#]);
+    }
+
+    #[tokio::test]
+    async fn test_token_budget_checks_prompts_and_tool_results() {
+        struct Verbose;
+
+        #[async_trait]
+        impl crate::Tool for Verbose {
+            async fn execute(&self, _input: serde_json::Value) -> Result<serde_json::Value> {
+                Ok(serde_json::json!({ "stdout": "x".repeat(5000) }))
+            }
+
+            fn name(&self) -> &str {
+                "verbose"
+            }
+        }
+
+        let llm = Arc::new(MockLLM::new(vec![
+            GenerateResponse {
+                content: Content::ToolCall(ToolCall {
+                    id: "tc_1".into(),
+                    name: "verbose".into(),
+                    input: serde_json::json!({}),
+                }),
+                stop_reason: StopReason::ToolUse,
+                usage: Usage::default(),
+                model: "mock".into(),
+            },
+            GenerateResponse {
+                content: Content::Text {
+                    text: "Too much output.".into(),
+                },
+                stop_reason: StopReason::EndTurn,
+                usage: Usage::default(),
+                model: "mock".into(),
+            },
+            GenerateResponse {
+                content: Content::Text {
+                    text: "Fits.".into(),
+                },
+                stop_reason: StopReason::EndTurn,
+                usage: Usage::default(),
+                model: "mock".into(),
+            },
+        ]));
+        let dir = tempfile::tempdir().unwrap();
+        let db_path = dir.path().join("test.db");
+        let mut runtime = Runtime::with_db(
+            db_path.to_str().unwrap(),
+            false,
+            std::time::Duration::from_secs(30),
+        )
+        .unwrap();
+        runtime
+            .register_tool("verbose".into(), Arc::new(Verbose))
+            .unwrap();
+        runtime.set_policy(crate::ToolPolicyPipeline::new().add_layer(Box::new(
+            crate::tool_policy::layers::ContextWindowLayer::new(estimator_for_model("mock")),
+        )));
+        let config = AgentConfig {
+            context_window: Some(1200),
+            max_tokens: 200,
+            ..AgentConfig::default()
+        };
+        let mut agent = Agent::new(config, llm, Arc::new(runtime));
+
+        // ~1250 tokens of output do not fit what is left of 1000
+        agent.process_message("Run it").await.unwrap();
+        let Content::ToolResult(result) = &agent.session.messages[2].content else {
+            panic!("expected a tool result");
+        };
+        assert!(result.is_error);
+        assert!(result
+            .output
+            .contains("Output of 'verbose' dropped by context_window (the call already ran)"));
+
+        let err = agent
+            .process_message(&"y".repeat(4000))
+            .await
+            .unwrap_err()
+            .to_string();
+        assert!(err.contains("exceeds the token budget of 1000"));
+        // The rejected message is not kept, so the next turn still works
+        assert_eq!(agent.session.message_count(), 4);
+        assert_eq!(agent.process_message("Shorter").await.unwrap(), "Fits.");
+        assert_eq!(agent.session.message_count(), 6);
+    }
+
+    #[tokio::test]
+    async fn test_tool_calls_outside_agent_tools_are_denied() {
+        let llm = Arc::new(MockLLM::new(vec![
+            GenerateResponse {
+                content: Content::ToolCall(ToolCall {
+                    id: "tc_1".into(),
+                    name: "shell".into(),
+                    input: serde_json::json!({"command": "rm -rf /"}),
+                }),
+                stop_reason: StopReason::ToolUse,
+                usage: Usage::default(),
+                model: "mock".into(),
+            },
+            GenerateResponse {
+                content: Content::Text {
+                    text: "Not allowed.".into(),
+                },
+                stop_reason: StopReason::EndTurn,
+                usage: Usage::default(),
+                model: "mock".into(),
+            },
+        ]));
+        let dir = tempfile::tempdir().unwrap();
+        let db_path = dir.path().join("test.db");
+        let mut runtime = Runtime::with_db(
+            db_path.to_str().unwrap(),
+            false,
+            std::time::Duration::from_secs(30),
+        )
+        .unwrap();
+        runtime.set_policy(crate::ToolPolicyPipeline::new().add_layer(Box::new(
+            crate::tool_policy::layers::AgentToolScopeLayer::new(HashMap::from([(
+                "reviewer".to_string(),
+                vec!["read_file".to_string()],
+            )])),
+        )));
+        let config = AgentConfig {
+            name: "reviewer".into(),
+            tools: vec!["read_file".into()],
+            ..AgentConfig::default()
+        };
+        let mut agent = Agent::new(config, llm, Arc::new(runtime));
+
+        // The model names a tool it was never shown
+        agent.process_message("Clean up").await.unwrap();
+        let Content::ToolResult(result) = &agent.session.messages[2].content else {
+            panic!("expected a tool result");
+        };
+        assert!(result.is_error);
+        assert!(result
+            .output
+            .contains("Policy denied by agent_tool_scope: agent 'reviewer' may not use 'shell'"));
+    }
+
+    /// Denies every tool call once the user has asked for read-only work,
+    /// after looking the conversation up like a remote policy service would
+    struct ReadOnlyRequestLayer;
+
+    #[async_trait]
+    impl crate::AsyncPolicyLayer for ReadOnlyRequestLayer {
+        fn name(&self) -> &str {
+            "read_only_request"
+        }
+
+        async fn evaluate(&self, ctx: &crate::PolicyContext) -> crate::PolicyDecision {
+            tokio::task::yield_now().await;
+            let asked = ctx.recent_messages.iter().any(|message| {
+                message.role == Role::User && message.content.extract_text().contains("read-only")
+            });
+            match ctx.recent_messages.last() {
+                Some(last) if asked && last.role == Role::Assistant => {
+                    crate::PolicyDecision::Deny(format!(
+                        "{} asked for read-only work",
+                        ctx.agent_name.as_deref().unwrap_or("the user")
+                    ))
+                }
+                _ => crate::PolicyDecision::Allow,
+            }
+        }
+    }
+
+    #[tokio::test]
+    async fn test_async_policy_layers_see_the_conversation() {
+        let tool_use = GenerateResponse {
+            content: Content::ToolCall(ToolCall {
+                id: "tc_1".into(),
+                name: "shell".into(),
+                input: serde_json::json!({"command": "rm -rf build"}),
+            }),
+            stop_reason: StopReason::ToolUse,
+            usage: Usage::default(),
+            model: "mock".into(),
+        };
+        let reply = GenerateResponse {
+            content: Content::Text {
+                text: "Done.".into(),
+            },
+            stop_reason: StopReason::EndTurn,
+            usage: Usage::default(),
+            model: "mock".into(),
+        };
+        let llm = Arc::new(MockLLM::new(vec![
+            tool_use.clone(),
+            reply.clone(),
+            tool_use,
+            reply,
+        ]));
+        let dir = tempfile::tempdir().unwrap();
+        let db_path = dir.path().join("test.db");
+        let mut runtime = Runtime::with_db(
+            db_path.to_str().unwrap(),
+            false,
+            std::time::Duration::from_secs(30),
+        )
+        .unwrap();
+        runtime.set_policy(
+            crate::ToolPolicyPipeline::new().add_async_layer(Box::new(ReadOnlyRequestLayer)),
+        );
+        let config = AgentConfig {
+            name: "reviewer".into(),
+            ..AgentConfig::default()
+        };
+        let mut agent = Agent::new(config, llm, Arc::new(runtime));
+
+        agent.process_message("Clean up").await.unwrap();
+        let Content::ToolResult(result) = &agent.session.messages[2].content else {
+            panic!("expected a tool result");
+        };
+        assert!(
+            !result.output.contains("Policy denied"),
+            "{}",
+            result.output
+        );
+
+        agent
+            .process_message("From now on, read-only please")
+            .await
+            .unwrap();
+        let Content::ToolResult(result) = &agent.session.messages[6].content else {
+            panic!("expected a tool result");
+        };
+        assert!(result.is_error);
+        assert!(result
+            .output
+            .contains("Policy denied by read_only_request: reviewer asked for read-only work"));
+    }
+
+    #[tokio::test]
+    async fn test_cost_is_tracked_and_limited() {
+        let tool_use = GenerateResponse {
+            content: Content::ToolCall(ToolCall {
+                id: "tc_1".into(),
+                name: "shell".into(),
+                input: serde_json::json!({}),
+            }),
+            stop_reason: StopReason::ToolUse,
+            usage: Usage {
+                input_tokens: 100_000,
+                output_tokens: 10_000,
+                cost_usd: 0.0,
+            },
+            model: "claude-sonnet-4-20250514".into(),
+        };
+        let llm = Arc::new(MockLLM::new(vec![tool_use]));
+        let (runtime, _dir) = make_runtime();
+        let config = AgentConfig {
+            max_cost_usd: Some(0.40),
+            ..AgentConfig::default()
+        };
+        let mut agent = Agent::new(config, llm, runtime);
+
+        // $0.30 input + $0.15 output is over the limit before the tool runs
+        let err = agent
+            .process_message("Run it")
+            .await
+            .unwrap_err()
+            .to_string();
+        assert!(err.contains("Session cost $0.4500 reached the limit of $0.4000"));
+        assert!((agent.session.cumulative_usage.cost_usd - 0.45).abs() < 1e-9);
+        // The skipped call still gets a result
+        assert_eq!(agent.session.messages.len(), 3);
+        let Content::ToolResult(result) = &agent.session.messages[2].content else {
+            panic!("expected a tool result");
+        };
+        assert_eq!(result.tool_use_id, "tc_1");
+        assert!(result.is_error && result.output.starts_with("Not run: Session cost"));
+
+        // Later messages are refused without being added
+        assert!(agent.process_message("Again").await.is_err());
+        assert_eq!(agent.session.messages.len(), 3);
+    }
+
+    #[tokio::test]
+    async fn test_event_sender_reports_tool_calls_and_text() {
+        let llm = Arc::new(MockLLM::new(vec![
+            GenerateResponse {
+                content: Content::ToolCall(ToolCall {
+                    id: "tc_1".into(),
+                    name: "shell".into(),
+                    input: serde_json::json!({"cmd": "date"}),
+                }),
+                stop_reason: StopReason::ToolUse,
+                usage: Usage::default(),
+                model: "mock".into(),
+            },
+            GenerateResponse {
+                content: Content::Text {
+                    text: "Done.".into(),
+                },
+                stop_reason: StopReason::EndTurn,
+                usage: Usage::default(),
+                model: "mock".into(),
+            },
+        ]));
+
+        let (runtime, _dir) = make_runtime();
+        let (tx, mut rx) = mpsc::unbounded_channel();
+        let mut agent = Agent::new(AgentConfig::default(), llm, runtime).with_event_sender(tx);
+        agent.process_message("run it").await.unwrap();
+
+        let mut events = Vec::new();
+        while let Ok(event) = rx.try_recv() {
+            events.push(event);
+        }
+        assert_eq!(events.len(), 3);
+        assert!(matches!(&events[0], AgentEvent::ToolCallStarted { name, .. } if name == "shell"));
+        assert!(
+            matches!(&events[1], AgentEvent::ToolCallFinished { id, is_error: false, .. } if id == "tc_1")
+        );
+        assert!(matches!(&events[2], AgentEvent::TextDelta(text) if text == "Done."));
+    }
+
     #[tokio::test]
     async fn test_max_iterations_limit() {
         // LLM always wants to call tools, never ends
@@ -489,4 +2484,385 @@ mod tests {
         assert!(result.is_err());
         assert!(result.unwrap_err().to_string().contains("Max iterations"));
     }
+
+    struct LeveledTool {
+        name: &'static str,
+        level: PermissionLevel,
+    }
+
+    #[async_trait]
+    impl crate::Tool for LeveledTool {
+        async fn execute(&self, _input: serde_json::Value) -> Result<serde_json::Value> {
+            Ok(serde_json::json!({}))
+        }
+
+        fn name(&self) -> &str {
+            self.name
+        }
+
+        fn permission_level(&self) -> PermissionLevel {
+            self.level.clone()
+        }
+    }
+
+    #[tokio::test]
+    async fn test_streamed_turns_are_kept_and_marked_when_interrupted() {
+        let done = |stop_reason| StreamChunk::Done {
+            stop_reason,
+            usage: Usage {
+                input_tokens: 10,
+                output_tokens: 5,
+                cost_usd: 0.0,
+            },
+        };
+        let llm = Arc::new(MockLLM::streaming(vec![
+            vec![
+                StreamChunk::ThinkingBlock {
+                    thinking: "List the files.".into(),
+                    signature: "sig-1".into(),
+                },
+                StreamChunk::TextDelta("Let me ".into()),
+                StreamChunk::TextDelta("check.".into()),
+                StreamChunk::ToolCallStart {
+                    id: "t1".into(),
+                    name: "shell".into(),
+                },
+                StreamChunk::ToolCallDelta {
+                    id: "t1".into(),
+                    input_delta: r#"{"command":"#.into(),
+                },
+                StreamChunk::ToolCallDelta {
+                    id: "t1".into(),
+                    input_delta: r#""ls"}"#.into(),
+                },
+                done(StopReason::ToolUse),
+            ],
+            vec![
+                StreamChunk::TextDelta("Two files.".into()),
+                done(StopReason::EndTurn),
+            ],
+            vec![
+                StreamChunk::TextDelta("Partial ans".into()),
+                StreamChunk::Error("connection reset".into()),
+            ],
+        ]));
+        let (runtime, _dir) = make_runtime();
+        let config = AgentConfig {
+            stream: true,
+            ..AgentConfig::default()
+        };
+        let mut agent = Agent::new(config, llm, runtime);
+
+        let result = agent.process_message("What is here?").await.unwrap();
+        assert_eq!(result, "Two files.");
+        assert_eq!(agent.session.message_count(), 4);
+        let calls = agent.session.messages[1].content.extract_tool_calls();
+        assert_eq!(calls[0].input, serde_json::json!({"command": "ls"}));
+        assert_eq!(
+            agent.session.messages[1].content.extract_text(),
+            "Let me check."
+        );
+        // The signed reasoning goes back to the provider with the tool result
+        match &agent.session.messages[1].content {
+            Content::Mixed { parts } => assert!(matches!(
+                &parts[0],
+                Content::Thinking { thinking, signature }
+                    if thinking == "List the files." && signature == "sig-1"
+            )),
+            other => panic!("Expected Mixed, got {:?}", other),
+        }
+        assert_eq!(agent.session.cumulative_usage.total(), 30);
+
+        let err = agent.process_message("And more?").await.unwrap_err();
+        assert!(err.to_string().contains("connection reset"));
+        assert_eq!(agent.session.message_count(), 6);
+        assert_eq!(
+            agent.session.messages[5].content.extract_text(),
+            format!("Partial ans{}", INTERRUPTED_MARKER)
+        );
+    }
+
+    #[tokio::test]
+    async fn test_process_message_stream_yields_deltas_tools_and_done() {
+        let llm = Arc::new(MockLLM::new(vec![
+            GenerateResponse {
+                content: Content::ToolCall(ToolCall {
+                    id: "tc_1".into(),
+                    name: "missing".into(),
+                    input: serde_json::json!({}),
+                }),
+                stop_reason: StopReason::ToolUse,
+                usage: Usage {
+                    input_tokens: 10,
+                    output_tokens: 5,
+                    cost_usd: 0.0,
+                },
+                model: "mock".into(),
+            },
+            GenerateResponse {
+                content: Content::Text {
+                    text: "All done.".into(),
+                },
+                stop_reason: StopReason::EndTurn,
+                usage: Usage {
+                    input_tokens: 20,
+                    output_tokens: 3,
+                    cost_usd: 0.0,
+                },
+                model: "mock".into(),
+            },
+        ]));
+        let (runtime, _dir) = make_runtime();
+        let mut agent = Agent::new(AgentConfig::default(), llm, runtime);
+
+        let events: Vec<AgentEvent> = agent.process_message_stream("Go").collect().await;
+        assert_eq!(events.len(), 4, "{:?}", events);
+        assert!(
+            matches!(&events[0], AgentEvent::ToolCallStarted { name, .. } if name == "missing")
+        );
+        assert!(matches!(
+            &events[1],
+            AgentEvent::ToolCallFinished { id, .. } if id == "tc_1"
+        ));
+        assert!(matches!(&events[2], AgentEvent::TextDelta(text) if text == "All done."));
+        match &events[3] {
+            AgentEvent::Done { text, usage } => {
+                assert_eq!(text, "All done.");
+                assert_eq!((usage.input_tokens, usage.output_tokens), (30, 8));
+            }
+            other => panic!("Expected Done, got {:?}", other),
+        }
+        // Streamed turns are stored like any other
+        assert_eq!(agent.session.message_count(), 4);
+
+        let events: Vec<AgentEvent> = agent.process_message_stream("Again").collect().await;
+        assert!(
+            matches!(events.last(), Some(AgentEvent::Error(e)) if e.contains("No more mock responses"))
+        );
+    }
+
+    struct ScreenTool;
+
+    #[async_trait]
+    impl crate::Tool for ScreenTool {
+        async fn execute(&self, _input: serde_json::Value) -> Result<serde_json::Value> {
+            Ok(serde_json::json!({
+                "image": {"mime": "image/png", "data": "iVBORw0KGgo="},
+                "bytes": 8,
+            }))
+        }
+
+        fn name(&self) -> &str {
+            "capture_screen"
+        }
+    }
+
+    #[tokio::test]
+    async fn test_tool_images_are_attached_for_vision_providers() {
+        let responses = || {
+            vec![
+                GenerateResponse {
+                    content: Content::ToolCall(ToolCall {
+                        id: "shot".into(),
+                        name: "capture_screen".into(),
+                        input: serde_json::json!({}),
+                    }),
+                    stop_reason: StopReason::ToolUse,
+                    usage: Usage::default(),
+                    model: "mock".into(),
+                },
+                GenerateResponse {
+                    content: Content::Text {
+                        text: "The build failed.".into(),
+                    },
+                    stop_reason: StopReason::EndTurn,
+                    usage: Usage::default(),
+                    model: "mock".into(),
+                },
+            ]
+        };
+        let dir = tempfile::tempdir().unwrap();
+        let runtime = Arc::new(
+            Runtime::with_db(
+                dir.path().join("test.db").to_str().unwrap(),
+                false,
+                std::time::Duration::from_secs(30),
+            )
+            .unwrap(),
+        );
+        runtime
+            .register_tool("capture_screen".into(), Arc::new(ScreenTool))
+            .unwrap();
+        let tool_output = |agent: &Agent| match &agent.session.messages[2].content {
+            Content::ToolResult(result) => result.output.clone(),
+            other => panic!("unexpected content: {:?}", other),
+        };
+
+        let vision = Arc::new(MockLLM::new(responses()).with_vision());
+        let mut agent = Agent::new(AgentConfig::default(), vision, runtime.clone());
+        agent.process_message("What's wrong?").await.unwrap();
+        assert_eq!(agent.session.message_count(), 5);
+        assert!(tool_output(&agent).contains("attached below"));
+        match &agent.session.messages[3].content {
+            Content::Mixed { parts } => assert!(matches!(
+                &parts[1],
+                Content::Image { data, mime } if mime == "image/png" && data.starts_with(b"\x89PNG")
+            )),
+            other => panic!("unexpected content: {:?}", other),
+        }
+
+        let blind = Arc::new(MockLLM::new(responses()));
+        let mut agent = Agent::new(AgentConfig::default(), blind, runtime);
+        agent.process_message("What's wrong?").await.unwrap();
+        assert_eq!(agent.session.message_count(), 4);
+        assert!(tool_output(&agent).contains("cannot view images"));
+    }
+
+    #[tokio::test]
+    async fn test_attachments_go_with_the_next_user_message() {
+        let reply = || GenerateResponse {
+            content: Content::Text {
+                text: "A cat.".into(),
+            },
+            stop_reason: StopReason::EndTurn,
+            usage: Usage::default(),
+            model: "mock".into(),
+        };
+        let image = || Content::Image {
+            data: b"\x89PNG\r\n\x1a\n".to_vec(),
+            mime: "image/png".into(),
+        };
+        let (runtime, _dir) = make_runtime();
+
+        let vision = Arc::new(MockLLM::new(vec![reply(), reply()]).with_vision());
+        let mut agent = Agent::new(AgentConfig::default(), vision, runtime.clone());
+        agent.attach(image());
+        assert_eq!(agent.attachments().len(), 1);
+        agent.process_message("What is this?").await.unwrap();
+        assert!(agent.attachments().is_empty());
+        match &agent.session.messages[0].content {
+            Content::Mixed { parts } => {
+                assert_eq!(parts[0].extract_text(), "What is this?");
+                assert!(matches!(&parts[1], Content::Image { mime, .. } if mime == "image/png"));
+            }
+            other => panic!("unexpected content: {:?}", other),
+        }
+        // Sent once only
+        agent.process_message("And now?").await.unwrap();
+        assert!(matches!(
+            agent.session.messages[2].content,
+            Content::Text { .. }
+        ));
+
+        let blind = Arc::new(MockLLM::new(vec![reply()]));
+        let mut agent = Agent::new(AgentConfig::default(), blind, runtime);
+        agent.attach(image());
+        let err = agent
+            .process_message("What is this?")
+            .await
+            .unwrap_err()
+            .to_string();
+        assert!(err.contains("does not accept images"));
+        assert!(agent.session.messages.is_empty());
+        assert!(agent.attachments().is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_tool_schemas_respect_allowlist_and_permission_cap() {
+        let (runtime, _dir) = make_runtime();
+        for (name, level) in [
+            ("read_file", PermissionLevel::Read),
+            ("write_file", PermissionLevel::Write),
+            ("shell", PermissionLevel::Execute),
+            ("capture_screen", PermissionLevel::Admin),
+        ] {
+            runtime
+                .register_tool(name.into(), Arc::new(LeveledTool { name, level }))
+                .unwrap();
+        }
+
+        let llm = Arc::new(MockLLM::new(vec![]));
+        let config = AgentConfig {
+            tools: vec!["read_file".into(), "shell".into()],
+            max_permission: Some(PermissionLevel::Write),
+            ..AgentConfig::default()
+        };
+        let agent = Agent::new(config, llm, runtime);
+
+        let names: Vec<String> = agent
+            .available_tool_schemas()
+            .into_iter()
+            .map(|s| s.name)
+            .collect();
+        assert_eq!(names, vec!["read_file".to_string()]);
+        assert_eq!(agent.caller_permission(), PermissionLevel::Write);
+
+        // Without a cap agents stop at Execute; Admin has to be given
+        let tools = vec!["read_file".into(), "shell".into(), "capture_screen".into()];
+        let default = Agent::new(
+            AgentConfig {
+                tools: tools.clone(),
+                ..AgentConfig::default()
+            },
+            Arc::new(MockLLM::new(vec![])),
+            agent.runtime.clone(),
+        );
+        assert_eq!(default.available_tool_schemas().len(), 2);
+        assert_eq!(default.caller_permission(), PermissionLevel::Execute);
+        let uncapped = Agent::new(
+            AgentConfig {
+                tools,
+                max_permission: Some(PermissionLevel::Admin),
+                ..AgentConfig::default()
+            },
+            Arc::new(MockLLM::new(vec![])),
+            agent.runtime.clone(),
+        );
+        assert_eq!(uncapped.available_tool_schemas().len(), 3);
+    }
+
+    #[tokio::test]
+    async fn test_tool_schemas_come_from_the_tools() {
+        struct Described;
+
+        #[async_trait]
+        impl crate::Tool for Described {
+            async fn execute(&self, _input: serde_json::Value) -> Result<serde_json::Value> {
+                Ok(serde_json::json!({}))
+            }
+
+            fn name(&self) -> &str {
+                "described"
+            }
+
+            fn schema(&self) -> crate::ToolSchemaInfo {
+                crate::ToolSchemaInfo {
+                    name: "described".into(),
+                    description: "Read a file".into(),
+                    parameters: serde_json::json!({
+                        "type": "object",
+                        "properties": { "path": { "type": "string" } },
+                        "required": ["path"]
+                    }),
+                }
+            }
+        }
+
+        let (runtime, _dir) = make_runtime();
+        runtime
+            .register_tool("described".into(), Arc::new(Described))
+            .unwrap();
+        runtime.alias_tool("cat", "described").unwrap();
+        let config = AgentConfig {
+            tools: vec!["cat".into(), "unregistered".into()],
+            ..AgentConfig::default()
+        };
+        let agent = Agent::new(config, Arc::new(MockLLM::new(vec![])), runtime);
+
+        let schemas = agent.available_tool_schemas();
+        assert_eq!(schemas.len(), 1);
+        assert_eq!(schemas[0].name, "cat");
+        assert_eq!(schemas[0].description, "Read a file");
+        assert_eq!(schemas[0].input_schema["required"][0], "path");
+    }
 }
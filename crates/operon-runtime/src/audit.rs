@@ -0,0 +1,149 @@
+//! Persistent audit trail of tool calls: one record per call, with the policy
+//! decision and how the call went, kept in the runtime's storage.
+
+use std::time::Duration;
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use sha2::{Digest, Sha256};
+
+/// What the policy pipeline decided about a call
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum AuditDecision {
+    /// Ran without needing approval (or without a policy pipeline)
+    Allowed,
+    /// Held for approval, then approved
+    Approved,
+    /// Held for approval, then refused or unanswered
+    NotApproved,
+    /// Denied by a policy layer, before or after running
+    Denied,
+}
+
+impl AuditDecision {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            AuditDecision::Allowed => "allowed",
+            AuditDecision::Approved => "approved",
+            AuditDecision::NotApproved => "not_approved",
+            AuditDecision::Denied => "denied",
+        }
+    }
+}
+
+/// How a call ended
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum AuditOutcome {
+    Success,
+    Error,
+}
+
+impl AuditOutcome {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            AuditOutcome::Success => "success",
+            AuditOutcome::Error => "error",
+        }
+    }
+}
+
+/// One tool call in the audit trail
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AuditRecord {
+    /// Sequence number, assigned when the record is stored
+    pub id: u64,
+    pub timestamp: DateTime<Utc>,
+    pub tool: String,
+    /// SHA-256 of the input JSON, to match calls without storing inputs
+    pub input_hash: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub session_id: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub agent: Option<String>,
+    /// Who the session was acting for (the gateway identity)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub principal: Option<String>,
+    pub decision: AuditDecision,
+    /// Why a call was denied, or what was approved
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub reason: Option<String>,
+    pub duration_ms: u64,
+    pub outcome: AuditOutcome,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+impl AuditRecord {
+    /// Record of a call starting now, allowed until the policy says otherwise
+    pub fn new(tool: &str, input: &Value, session_id: Option<&str>) -> Self {
+        Self {
+            id: 0,
+            timestamp: Utc::now(),
+            tool: tool.to_string(),
+            input_hash: input_hash(input),
+            session_id: session_id.map(str::to_string),
+            agent: None,
+            principal: None,
+            decision: AuditDecision::Allowed,
+            reason: None,
+            duration_ms: 0,
+            outcome: AuditOutcome::Success,
+            error: None,
+        }
+    }
+
+    /// Fill in how the call ended
+    pub fn finish<T>(&mut self, result: &anyhow::Result<T>, duration: Duration) {
+        self.duration_ms = duration.as_millis() as u64;
+        if let Err(e) = result {
+            self.outcome = AuditOutcome::Error;
+            self.error = Some(format!("{:#}", e));
+        }
+    }
+}
+
+/// SHA-256 (hex) of `input`; serde_json maps keep keys sorted, so equal
+/// inputs hash the same
+pub fn input_hash(input: &Value) -> String {
+    format!("{:x}", Sha256::digest(input.to_string().as_bytes()))
+}
+
+/// Which audit records to return, newest first. Unset fields match every
+/// record.
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct AuditFilter {
+    #[serde(default)]
+    pub session_id: Option<String>,
+    #[serde(default)]
+    pub tool: Option<String>,
+    #[serde(default)]
+    pub principal: Option<String>,
+    /// Only records at or after this time (RFC 3339)
+    #[serde(default)]
+    pub since: Option<DateTime<Utc>>,
+    /// Only records before this time (RFC 3339)
+    #[serde(default)]
+    pub until: Option<DateTime<Utc>>,
+    /// Most records to return
+    #[serde(default)]
+    pub limit: Option<usize>,
+}
+
+impl AuditFilter {
+    /// Whether `record` passes every condition set
+    pub fn matches(&self, record: &AuditRecord) -> bool {
+        self.session_id
+            .as_ref()
+            .is_none_or(|id| record.session_id.as_ref() == Some(id))
+            && self.tool.as_ref().is_none_or(|tool| &record.tool == tool)
+            && self
+                .principal
+                .as_ref()
+                .is_none_or(|principal| record.principal.as_ref() == Some(principal))
+            && self.since.is_none_or(|since| record.timestamp >= since)
+            && self.until.is_none_or(|until| record.timestamp < until)
+    }
+}
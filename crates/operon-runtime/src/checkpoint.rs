@@ -0,0 +1,60 @@
+//! Per-step completion markers for top-level plan runs, keyed by plan id, so
+//! a run that failed part way can resume without redoing finished steps.
+
+use crate::storage::Storage;
+use anyhow::{Context, Result};
+use serde_json::{json, Value};
+
+fn plan_key(plan_id: &str) -> String {
+    format!("checkpoint/{}", plan_id)
+}
+
+fn step_key(plan_id: &str, step_id: &str) -> String {
+    format!("checkpoint/{}/{}", plan_id, step_id)
+}
+
+/// Plan of the last checkpointed run of `plan_id`
+pub async fn load_plan(storage: &Storage, plan_id: &str) -> Result<Value> {
+    let record = storage
+        .load_state_async(&plan_key(plan_id))
+        .await?
+        .context(format!("No checkpoint for plan '{}'", plan_id))?;
+    Ok(record["plan"].clone())
+}
+
+/// Save the plan a run starts with; a fresh run also drops the markers of
+/// earlier runs, a resumed one keeps them
+pub(crate) async fn start(
+    storage: &Storage,
+    plan_id: &str,
+    plan: &Value,
+    resume: bool,
+) -> Result<()> {
+    if !resume {
+        storage.delete_prefix_async(&step_key(plan_id, "")).await?;
+    }
+    storage
+        .save_state_async(&plan_key(plan_id), json!({"plan": plan}))
+        .await
+}
+
+/// Mark `step_id` completed with `output`
+pub(crate) async fn complete(
+    storage: &Storage,
+    plan_id: &str,
+    step_id: &str,
+    output: &Value,
+) -> Result<()> {
+    storage
+        .save_state_async(&step_key(plan_id, step_id), output.clone())
+        .await
+}
+
+/// Output of `step_id` if an earlier run completed it
+pub(crate) async fn completed(
+    storage: &Storage,
+    plan_id: &str,
+    step_id: &str,
+) -> Result<Option<Value>> {
+    storage.load_state_async(&step_key(plan_id, step_id)).await
+}
@@ -0,0 +1,477 @@
+//! Composite tools: a fixed chain of registered tools exposed as a single
+//! tool, with jq-style paths (`$prev.stdout`, `$steps.fetch.items[0]`)
+//! mapping earlier outputs into later inputs.
+
+use std::future::Future;
+use std::sync::Arc;
+
+use anyhow::{bail, Context, Result};
+use async_trait::async_trait;
+use dashmap::DashMap;
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Map, Value};
+
+use crate::tool::{PermissionLevel, Tool, ToolSchemaInfo};
+
+/// One tool call in a composite chain
+#[derive(Debug, Clone, Deserialize, Serialize, JsonSchema)]
+pub struct CompositeStep {
+    /// Name later steps use in `$steps.<id>` (default: the tool name)
+    #[serde(default)]
+    pub id: Option<String>,
+
+    pub tool: String,
+
+    /// Input template. Strings starting with `$` are paths into `$input`
+    /// (the composite's input), `$prev` (previous output) or `$steps.<id>`;
+    /// `$$` escapes a literal `$`. Default: the previous output.
+    #[serde(default)]
+    pub input: Option<Value>,
+}
+
+impl CompositeStep {
+    pub fn id(&self) -> &str {
+        self.id.as_deref().unwrap_or(&self.tool)
+    }
+}
+
+/// Definition of a composite tool (e.g. `[[tools.composites]]` in config)
+#[derive(Debug, Clone, Deserialize, Serialize, JsonSchema)]
+pub struct CompositeSpec {
+    pub name: String,
+
+    #[serde(default)]
+    pub description: Option<String>,
+
+    /// JSON schema of the composite's input, shown to the LLM
+    #[serde(default)]
+    pub parameters: Option<Value>,
+
+    pub steps: Vec<CompositeStep>,
+
+    /// Output template, same paths as step inputs (default: last step's output)
+    #[serde(default)]
+    pub output: Option<Value>,
+}
+
+impl CompositeSpec {
+    /// Check step ids and that every path refers to an earlier step
+    pub fn validate(&self) -> Result<()> {
+        if self.steps.is_empty() {
+            bail!("Composite tool '{}' has no steps", self.name);
+        }
+        let mut seen: Vec<&str> = Vec::new();
+        for step in &self.steps {
+            if step.tool == self.name {
+                bail!("Composite tool '{}' cannot call itself", self.name);
+            }
+            if let Some(template) = &step.input {
+                check_template(template, &seen).context(format!(
+                    "Composite '{}' step '{}'",
+                    self.name,
+                    step.id()
+                ))?;
+            }
+            if seen.contains(&step.id()) {
+                bail!(
+                    "Composite tool '{}' has duplicate step id '{}'",
+                    self.name,
+                    step.id()
+                );
+            }
+            seen.push(step.id());
+        }
+        if let Some(template) = &self.output {
+            check_template(template, &seen).context(format!("Composite '{}' output", self.name))?;
+        }
+        Ok(())
+    }
+}
+
+/// Registered composite. A [`Runtime`](crate::Runtime) runs each step as a
+/// call of its own (policy, hooks, timeout); executed directly, it calls the
+/// chained tools from the registry.
+pub struct CompositeTool {
+    spec: CompositeSpec,
+    tools: Arc<DashMap<String, Arc<dyn Tool>>>,
+    /// Highest permission among the chained tools, fixed at registration
+    permission: PermissionLevel,
+}
+
+impl CompositeTool {
+    /// Validates the spec; every chained tool must already be registered
+    pub fn new(spec: CompositeSpec, tools: Arc<DashMap<String, Arc<dyn Tool>>>) -> Result<Self> {
+        spec.validate()?;
+        let mut permission = PermissionLevel::Read;
+        for step in &spec.steps {
+            let tool = tools.get(&step.tool).context(format!(
+                "Composite tool '{}' uses unregistered tool '{}'",
+                spec.name, step.tool
+            ))?;
+            permission = permission.max(tool.permission_level());
+        }
+        Ok(Self {
+            spec,
+            tools,
+            permission,
+        })
+    }
+
+    pub fn spec(&self) -> &CompositeSpec {
+        &self.spec
+    }
+}
+
+/// Run `spec`'s steps in order, each through `call(tool, input)`
+pub(crate) async fn run_chain<F, Fut>(
+    spec: &CompositeSpec,
+    input: Value,
+    mut call: F,
+) -> Result<Value>
+where
+    F: FnMut(String, Value) -> Fut,
+    Fut: Future<Output = Result<Value>>,
+{
+    let mut ctx = json!({"input": input, "prev": input, "steps": {}});
+
+    for (i, step) in spec.steps.iter().enumerate() {
+        let step_input = match &step.input {
+            Some(template) => render(template, &ctx)?,
+            None => ctx["prev"].clone(),
+        };
+        tracing::debug!(composite = %spec.name, step = i, tool = %step.tool, "Composite step");
+        let output = call(step.tool.clone(), step_input).await.context(format!(
+            "Composite tool '{}' step {} ('{}') failed",
+            spec.name,
+            i,
+            step.id()
+        ))?;
+        ctx["steps"][step.id()] = output.clone();
+        ctx["prev"] = output;
+    }
+
+    match &spec.output {
+        Some(template) => render(template, &ctx),
+        None => Ok(ctx["prev"].take()),
+    }
+}
+
+#[async_trait]
+impl Tool for CompositeTool {
+    async fn execute(&self, input: Value) -> Result<Value> {
+        run_chain(&self.spec, input, |name, input| {
+            // Clone out of the registry so no lock is held across the await
+            let tool = self.tools.get(&name).map(|t| t.value().clone());
+            async move {
+                tool.context(format!("Tool '{}' not registered", name))?
+                    .execute(input)
+                    .await
+            }
+        })
+        .await
+    }
+
+    fn name(&self) -> &str {
+        &self.spec.name
+    }
+
+    fn schema(&self) -> ToolSchemaInfo {
+        let chain: Vec<&str> = self.spec.steps.iter().map(|s| s.tool.as_str()).collect();
+        ToolSchemaInfo {
+            name: self.spec.name.clone(),
+            description: self
+                .spec
+                .description
+                .clone()
+                .unwrap_or_else(|| format!("Runs {} in sequence", chain.join(" → "))),
+            parameters: self
+                .spec
+                .parameters
+                .clone()
+                .unwrap_or_else(|| json!({"type": "object"})),
+        }
+    }
+
+    fn permission_level(&self) -> PermissionLevel {
+        self.permission.clone()
+    }
+}
+
+#[derive(Debug, PartialEq)]
+enum Segment {
+    Key(String),
+    /// Negative indexes count from the end, as in jq
+    Index(i64),
+}
+
+/// Parse `root.key[0]["odd key"]` (the part after `$`)
+fn parse_path(path: &str) -> Result<Vec<Segment>> {
+    let end = path.find(['.', '[']).unwrap_or(path.len());
+    let root = &path[..end];
+    if !matches!(root, "input" | "prev" | "steps") {
+        bail!(
+            "Unknown path root '${}' (expected $input, $prev or $steps)",
+            root
+        );
+    }
+    let mut segments = vec![Segment::Key(root.to_string())];
+    let mut rest = &path[end..];
+
+    while !rest.is_empty() {
+        if let Some(after) = rest.strip_prefix('.') {
+            let end = after.find(['.', '[']).unwrap_or(after.len());
+            if end == 0 {
+                bail!("Empty key in path '${}'", path);
+            }
+            segments.push(Segment::Key(after[..end].to_string()));
+            rest = &after[end..];
+        } else if let Some(after) = rest.strip_prefix('[') {
+            let end = after
+                .find(']')
+                .context(format!("Unclosed '[' in path '${}'", path))?;
+            let inner = &after[..end];
+            let segment = match inner.strip_prefix('"').and_then(|k| k.strip_suffix('"')) {
+                Some(key) => Segment::Key(key.to_string()),
+                None => Segment::Index(
+                    inner
+                        .parse()
+                        .context(format!("Invalid index '{}' in path '${}'", inner, path))?,
+                ),
+            };
+            segments.push(segment);
+            rest = &after[end + 1..];
+        } else {
+            bail!("Unexpected '{}' in path '${}'", rest, path);
+        }
+    }
+    Ok(segments)
+}
+
+/// Value at `path`; missing keys and indexes give null, like jq
+fn resolve(path: &str, ctx: &Value) -> Result<Value> {
+    let mut current = ctx;
+    for segment in parse_path(path)? {
+        current = match (&segment, current) {
+            (_, Value::Null) => return Ok(Value::Null),
+            (Segment::Key(key), Value::Object(map)) => match map.get(key) {
+                Some(value) => value,
+                None => return Ok(Value::Null),
+            },
+            (Segment::Index(i), Value::Array(items)) => {
+                let idx = if *i < 0 { items.len() as i64 + i } else { *i };
+                match usize::try_from(idx).ok().and_then(|idx| items.get(idx)) {
+                    Some(value) => value,
+                    None => return Ok(Value::Null),
+                }
+            }
+            (segment, other) => bail!(
+                "Cannot index {} with {:?} in path '${}'",
+                type_name(other),
+                segment,
+                path
+            ),
+        };
+    }
+    Ok(current.clone())
+}
+
+fn type_name(value: &Value) -> &'static str {
+    match value {
+        Value::Null => "null",
+        Value::Bool(_) => "boolean",
+        Value::Number(_) => "number",
+        Value::String(_) => "string",
+        Value::Array(_) => "array",
+        Value::Object(_) => "object",
+    }
+}
+
+/// Substitute every `$path` string in `template`
+fn render(template: &Value, ctx: &Value) -> Result<Value> {
+    Ok(match template {
+        Value::String(s) => match s.strip_prefix('$') {
+            Some(rest) if rest.starts_with('$') => Value::String(rest.to_string()),
+            Some(path) => resolve(path, ctx)?,
+            None => template.clone(),
+        },
+        Value::Array(items) => Value::Array(
+            items
+                .iter()
+                .map(|item| render(item, ctx))
+                .collect::<Result<_>>()?,
+        ),
+        Value::Object(map) => Value::Object(
+            map.iter()
+                .map(|(k, v)| Ok((k.clone(), render(v, ctx)?)))
+                .collect::<Result<Map<_, _>>>()?,
+        ),
+        other => other.clone(),
+    })
+}
+
+/// Parse every path in `template`; `$steps.<id>` must name an earlier step
+fn check_template(template: &Value, earlier: &[&str]) -> Result<()> {
+    match template {
+        Value::String(s) => {
+            let Some(path) = s.strip_prefix('$') else {
+                return Ok(());
+            };
+            if path.starts_with('$') {
+                return Ok(());
+            }
+            let segments = parse_path(path)?;
+            if let [Segment::Key(root), rest @ ..] = segments.as_slice() {
+                if root == "steps" {
+                    match rest.first() {
+                        Some(Segment::Key(id)) if earlier.contains(&id.as_str()) => {}
+                        Some(Segment::Key(id)) => {
+                            bail!("'${}' refers to unknown or later step '{}'", path, id)
+                        }
+                        _ => bail!("'${}' must name a step, e.g. $steps.<id>", path),
+                    }
+                }
+            }
+            Ok(())
+        }
+        Value::Array(items) => items.iter().try_for_each(|v| check_template(v, earlier)),
+        Value::Object(map) => map.values().try_for_each(|v| check_template(v, earlier)),
+        _ => Ok(()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Returns its input under "echo" and reports a configurable permission
+    struct Echo {
+        name: &'static str,
+        permission: PermissionLevel,
+    }
+
+    #[async_trait]
+    impl Tool for Echo {
+        async fn execute(&self, input: Value) -> Result<Value> {
+            Ok(json!({"tool": self.name, "echo": input}))
+        }
+
+        fn name(&self) -> &str {
+            self.name
+        }
+
+        fn permission_level(&self) -> PermissionLevel {
+            self.permission.clone()
+        }
+    }
+
+    fn registry() -> Arc<DashMap<String, Arc<dyn Tool>>> {
+        let tools: Arc<DashMap<String, Arc<dyn Tool>>> = Arc::new(DashMap::new());
+        for (name, permission) in [
+            ("read", PermissionLevel::Read),
+            ("fetch", PermissionLevel::Network),
+        ] {
+            tools.insert(name.into(), Arc::new(Echo { name, permission }));
+        }
+        tools
+    }
+
+    fn spec(value: Value) -> CompositeSpec {
+        serde_json::from_value(value).unwrap()
+    }
+
+    #[test]
+    fn test_resolve_paths() {
+        let ctx = json!({"prev": {"items": [1, 2, {"odd key": "x"}]}, "input": null});
+        assert_eq!(resolve("prev.items[1]", &ctx).unwrap(), json!(2));
+        assert_eq!(resolve("prev.items[-1][\"odd key\"]", &ctx).unwrap(), "x");
+        assert_eq!(resolve("prev.missing.deeper", &ctx).unwrap(), Value::Null);
+        assert_eq!(resolve("input.a", &ctx).unwrap(), Value::Null);
+        assert!(resolve("prev.items.key", &ctx).is_err());
+        assert!(resolve("env.HOME", &ctx).is_err());
+        assert!(parse_path("prev.items[0").is_err());
+        assert!(parse_path("prev..x").is_err());
+    }
+
+    #[test]
+    fn test_render_template() {
+        let ctx = json!({"input": {"path": "a.txt"}, "prev": {"n": 3}});
+        let rendered = render(
+            &json!({"file": "$input.path", "count": "$prev.n", "price": "$$5", "fixed": [true]}),
+            &ctx,
+        )
+        .unwrap();
+        assert_eq!(
+            rendered,
+            json!({"file": "a.txt", "count": 3, "price": "$5", "fixed": [true]})
+        );
+    }
+
+    #[tokio::test]
+    async fn test_chains_tools_with_mapping() {
+        let composite = CompositeTool::new(
+            spec(json!({
+                "name": "read_then_fetch",
+                "steps": [
+                    {"id": "first", "tool": "read", "input": {"path": "$input.file"}},
+                    {"tool": "fetch", "input": {"url": "$prev.echo.path", "from": "$steps.first.tool"}}
+                ],
+                "output": {"url": "$steps.fetch.echo.url", "via": "$prev.echo.from"}
+            })),
+            registry(),
+        )
+        .unwrap();
+
+        let output = composite.execute(json!({"file": "x.txt"})).await.unwrap();
+        assert_eq!(output, json!({"url": "x.txt", "via": "read"}));
+        assert_eq!(composite.permission_level(), PermissionLevel::Network);
+        assert_eq!(
+            composite.schema().description,
+            "Runs read → fetch in sequence"
+        );
+    }
+
+    #[tokio::test]
+    async fn test_default_input_is_previous_output() {
+        let composite = CompositeTool::new(
+            spec(json!({
+                "name": "twice",
+                "steps": [{"tool": "read"}, {"id": "again", "tool": "read"}]
+            })),
+            registry(),
+        )
+        .unwrap();
+
+        let output = composite.execute(json!({"n": 1})).await.unwrap();
+        assert_eq!(output["echo"]["echo"], json!({"n": 1}));
+    }
+
+    #[test]
+    fn test_invalid_specs_rejected() {
+        let cases = [
+            (json!({"name": "c", "steps": []}), "no steps"),
+            (json!({"name": "c", "steps": [{"tool": "c"}]}), "itself"),
+            (
+                json!({"name": "c", "steps": [{"tool": "read"}, {"tool": "read"}]}),
+                "duplicate step id",
+            ),
+            (
+                json!({"name": "c", "steps": [{"tool": "read", "input": "$steps.read"}]}),
+                "later step",
+            ),
+            (
+                json!({"name": "c", "steps": [{"tool": "missing"}]}),
+                "unregistered",
+            ),
+        ];
+        for (value, expected) in cases {
+            let err = CompositeTool::new(spec(value), registry()).err().unwrap();
+            assert!(
+                format!("{:#}", err).contains(expected),
+                "{:#} should mention {:?}",
+                err,
+                expected
+            );
+        }
+    }
+}
@@ -1,4 +1,6 @@
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::mpsc::RecvTimeoutError;
 use std::sync::Arc;
 use std::time::Duration;
 
@@ -15,11 +17,16 @@ pub enum ConfigReloadEvent {
     Failure(String),
 }
 
+/// Reads and parses the config file (override to resolve includes, env vars, validation)
+pub type ConfigLoader<C> = Arc<dyn Fn(&Path) -> Result<C> + Send + Sync>;
+
 /// Generic config manager with file watching and hot-reload
 pub struct ConfigManager<C: DeserializeOwned + Send + Sync + 'static> {
     config: Arc<RwLock<C>>,
     config_path: PathBuf,
     reload_tx: broadcast::Sender<ConfigReloadEvent>,
+    loader: ConfigLoader<C>,
+    stop: Arc<AtomicBool>,
 }
 
 impl<C: DeserializeOwned + Send + Sync + 'static> ConfigManager<C> {
@@ -29,6 +36,46 @@ impl<C: DeserializeOwned + Send + Sync + 'static> ConfigManager<C> {
             config: Arc::new(RwLock::new(initial_config)),
             config_path: path,
             reload_tx,
+            loader: Arc::new(|path: &Path| {
+                let content = std::fs::read_to_string(path)
+                    .context(format!("Failed to read config: {:?}", path))?;
+                Ok(toml::from_str::<C>(&content)?)
+            }),
+            stop: Arc::new(AtomicBool::new(false)),
+        }
+    }
+
+    /// Replace the default TOML loader used on reload
+    pub fn with_loader<F>(mut self, loader: F) -> Self
+    where
+        F: Fn(&Path) -> Result<C> + Send + Sync + 'static,
+    {
+        self.loader = Arc::new(loader);
+        self
+    }
+
+    /// End the `watch` loop so its blocking thread doesn't hold up runtime shutdown.
+    /// Also happens when the manager is dropped.
+    pub fn stop_watching(&self) {
+        self.stop.store(true, Ordering::Relaxed);
+    }
+
+    /// Re-read the config file now (e.g. on SIGHUP). The old config is kept on failure.
+    pub async fn reload(&self) -> Result<()> {
+        match (self.loader)(&self.config_path) {
+            Ok(new_config) => {
+                *self.config.write().await = new_config;
+                info!("Config reloaded successfully");
+                let _ = self.reload_tx.send(ConfigReloadEvent::Success);
+                Ok(())
+            }
+            Err(e) => {
+                error!("Config reload failed: {:#}. Preserving old config.", e);
+                let _ = self
+                    .reload_tx
+                    .send(ConfigReloadEvent::Failure(format!("{:#}", e)));
+                Err(e)
+            }
         }
     }
 
@@ -47,6 +94,8 @@ impl<C: DeserializeOwned + Send + Sync + 'static> ConfigManager<C> {
         let config = self.config.clone();
         let config_path = self.config_path.clone();
         let reload_tx = self.reload_tx.clone();
+        let loader = self.loader.clone();
+        let stop = self.stop.clone();
 
         // Use std channel for notify (it's not async)
         let (tx, rx) = std::sync::mpsc::channel();
@@ -69,7 +118,12 @@ impl<C: DeserializeOwned + Send + Sync + 'static> ConfigManager<C> {
             // Keep debouncer alive
             let _debouncer = debouncer;
 
-            for result in rx {
+            loop {
+                let result = match rx.recv_timeout(Duration::from_millis(250)) {
+                    Ok(result) => result,
+                    Err(RecvTimeoutError::Timeout) if !stop.load(Ordering::Relaxed) => continue,
+                    Err(_) => break,
+                };
                 match result {
                     Ok(events) => {
                         let relevant = events
@@ -81,27 +135,21 @@ impl<C: DeserializeOwned + Send + Sync + 'static> ConfigManager<C> {
 
                         info!("Config file changed, reloading...");
 
-                        match std::fs::read_to_string(&config_path) {
-                            Ok(content) => match toml::from_str::<C>(&content) {
-                                Ok(new_config) => {
-                                    // Block on async write
-                                    let config = config.clone();
-                                    let rt = tokio::runtime::Handle::current();
-                                    rt.block_on(async {
-                                        *config.write().await = new_config;
-                                    });
-                                    info!("Config reloaded successfully");
-                                    let _ = reload_tx.send(ConfigReloadEvent::Success);
-                                }
-                                Err(e) => {
-                                    error!("Config parse failed: {}. Preserving old config.", e);
-                                    let _ =
-                                        reload_tx.send(ConfigReloadEvent::Failure(e.to_string()));
-                                }
-                            },
+                        match loader(&config_path) {
+                            Ok(new_config) => {
+                                // Block on async write
+                                let config = config.clone();
+                                let rt = tokio::runtime::Handle::current();
+                                rt.block_on(async {
+                                    *config.write().await = new_config;
+                                });
+                                info!("Config reloaded successfully");
+                                let _ = reload_tx.send(ConfigReloadEvent::Success);
+                            }
                             Err(e) => {
-                                error!("Failed to read config: {}. Preserving old config.", e);
-                                let _ = reload_tx.send(ConfigReloadEvent::Failure(e.to_string()));
+                                error!("Config reload failed: {:#}. Preserving old config.", e);
+                                let _ =
+                                    reload_tx.send(ConfigReloadEvent::Failure(format!("{:#}", e)));
                             }
                         }
                     }
@@ -115,3 +163,54 @@ impl<C: DeserializeOwned + Send + Sync + 'static> ConfigManager<C> {
         Ok(())
     }
 }
+
+impl<C: DeserializeOwned + Send + Sync + 'static> Drop for ConfigManager<C> {
+    fn drop(&mut self) {
+        self.stop_watching();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde::Deserialize;
+
+    #[derive(Debug, Deserialize)]
+    struct TestConfig {
+        value: u32,
+    }
+
+    #[tokio::test]
+    async fn test_reload_updates_config_and_notifies() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("config.toml");
+        std::fs::write(&path, "value = 2\n").unwrap();
+
+        let manager = ConfigManager::new(path.clone(), TestConfig { value: 1 });
+        let mut rx = manager.subscribe_reload();
+
+        manager.reload().await.unwrap();
+        assert_eq!(manager.config().read().await.value, 2);
+        assert!(matches!(
+            rx.recv().await.unwrap(),
+            ConfigReloadEvent::Success
+        ));
+
+        // Broken file keeps the previous config
+        std::fs::write(&path, "value = \"oops\"\n").unwrap();
+        assert!(manager.reload().await.is_err());
+        assert_eq!(manager.config().read().await.value, 2);
+        assert!(matches!(
+            rx.recv().await.unwrap(),
+            ConfigReloadEvent::Failure(_)
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_custom_loader() {
+        let manager = ConfigManager::new(PathBuf::from("unused.toml"), TestConfig { value: 1 })
+            .with_loader(|_| Ok(TestConfig { value: 42 }));
+        manager.reload().await.unwrap();
+        assert_eq!(manager.config().read().await.value, 42);
+    }
+}
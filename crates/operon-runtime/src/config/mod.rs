@@ -1,3 +1,3 @@
 pub mod manager;
 
-pub use manager::{ConfigManager, ConfigReloadEvent};
+pub use manager::{ConfigLoader, ConfigManager, ConfigReloadEvent};
@@ -0,0 +1,203 @@
+//! Priority gate for tool execution: a fixed number of slots shared by
+//! interactive (chat) calls and background plan steps. When slots are
+//! scarce, queued interactive calls are served before background ones.
+
+use std::collections::VecDeque;
+use std::sync::{Arc, Mutex};
+
+use anyhow::Result;
+use serde::Serialize;
+use tokio::sync::oneshot;
+
+/// Who is waiting for an execution slot
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ExecPriority {
+    /// Plan steps and other unattended work
+    Background,
+    /// Tool calls a user is waiting on (chat, gateway sessions)
+    Interactive,
+}
+
+/// Queue-depth metrics
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize)]
+pub struct QueueStats {
+    pub capacity: usize,
+    pub running: usize,
+    pub waiting_interactive: usize,
+    pub waiting_background: usize,
+    /// Most callers ever waiting at once
+    pub peak_waiting: usize,
+    /// Calls that had to wait for a slot
+    pub total_queued: u64,
+}
+
+#[derive(Default)]
+struct QueueState {
+    running: usize,
+    interactive: VecDeque<oneshot::Sender<ExecPermit>>,
+    background: VecDeque<oneshot::Sender<ExecPermit>>,
+    peak_waiting: usize,
+    total_queued: u64,
+}
+
+struct Inner {
+    capacity: usize,
+    state: Mutex<QueueState>,
+}
+
+/// Cheap to clone; clones share slots
+#[derive(Clone)]
+pub struct ExecQueue {
+    inner: Arc<Inner>,
+}
+
+/// Held while a tool runs; dropping it hands the slot to the next waiter
+pub struct ExecPermit {
+    inner: Arc<Inner>,
+    /// False for a permit that never reached its waiter
+    release: bool,
+}
+
+impl ExecQueue {
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            inner: Arc::new(Inner {
+                capacity: capacity.max(1),
+                state: Mutex::new(QueueState::default()),
+            }),
+        }
+    }
+
+    /// Wait for a slot; interactive callers are served first
+    pub async fn acquire(&self, priority: ExecPriority) -> Result<ExecPermit> {
+        let rx = {
+            let mut state = self.inner.lock();
+            if state.running < self.inner.capacity {
+                state.running += 1;
+                return Ok(ExecPermit {
+                    inner: self.inner.clone(),
+                    release: true,
+                });
+            }
+            let (tx, rx) = oneshot::channel();
+            match priority {
+                ExecPriority::Interactive => state.interactive.push_back(tx),
+                ExecPriority::Background => state.background.push_back(tx),
+            }
+            state.total_queued += 1;
+            let waiting = state.interactive.len() + state.background.len();
+            state.peak_waiting = state.peak_waiting.max(waiting);
+            rx
+        };
+        rx.await
+            .map_err(|_| anyhow::anyhow!("Execution queue closed"))
+    }
+
+    pub fn stats(&self) -> QueueStats {
+        let state = self.inner.lock();
+        QueueStats {
+            capacity: self.inner.capacity,
+            running: state.running,
+            waiting_interactive: state.interactive.len(),
+            waiting_background: state.background.len(),
+            peak_waiting: state.peak_waiting,
+            total_queued: state.total_queued,
+        }
+    }
+}
+
+impl Inner {
+    fn lock(&self) -> std::sync::MutexGuard<'_, QueueState> {
+        self.state.lock().unwrap_or_else(|e| e.into_inner())
+    }
+}
+
+impl Drop for ExecPermit {
+    fn drop(&mut self) {
+        if !self.release {
+            return;
+        }
+        let mut state = self.inner.lock();
+        loop {
+            let Some(tx) = state
+                .interactive
+                .pop_front()
+                .or_else(|| state.background.pop_front())
+            else {
+                state.running -= 1;
+                return;
+            };
+            let permit = ExecPermit {
+                inner: self.inner.clone(),
+                release: true,
+            };
+            match tx.send(permit) {
+                // Slot handed over; `running` is unchanged
+                Ok(()) => return,
+                // Waiter gave up; disarm the undelivered permit and try the next
+                Err(mut permit) => permit.release = false,
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+
+    #[tokio::test]
+    async fn test_interactive_served_before_background() {
+        let queue = ExecQueue::new(1);
+        let held = queue.acquire(ExecPriority::Background).await.unwrap();
+        let order = Arc::new(Mutex::new(Vec::new()));
+
+        let mut tasks = Vec::new();
+        for (name, priority) in [
+            ("background", ExecPriority::Background),
+            ("interactive", ExecPriority::Interactive),
+        ] {
+            let queue = queue.clone();
+            let order = order.clone();
+            tasks.push(tokio::spawn(async move {
+                let _permit = queue.acquire(priority).await.unwrap();
+                order.lock().unwrap().push(name);
+            }));
+            // Queue the background waiter first
+            tokio::time::sleep(Duration::from_millis(20)).await;
+        }
+
+        let stats = queue.stats();
+        assert_eq!((stats.running, stats.waiting_background), (1, 1));
+        assert_eq!(stats.waiting_interactive, 1);
+
+        drop(held);
+        for task in tasks {
+            task.await.unwrap();
+        }
+        assert_eq!(*order.lock().unwrap(), ["interactive", "background"]);
+
+        let stats = queue.stats();
+        assert_eq!(stats.running, 0);
+        assert_eq!((stats.peak_waiting, stats.total_queued), (2, 2));
+    }
+
+    #[tokio::test]
+    async fn test_cancelled_waiter_does_not_leak_slot() {
+        let queue = ExecQueue::new(1);
+        let held = queue.acquire(ExecPriority::Interactive).await.unwrap();
+
+        let waiting = tokio::time::timeout(
+            Duration::from_millis(20),
+            queue.acquire(ExecPriority::Interactive),
+        )
+        .await;
+        assert!(waiting.is_err());
+
+        drop(held);
+        assert_eq!(queue.stats().running, 0);
+        let _again = queue.acquire(ExecPriority::Background).await.unwrap();
+        assert_eq!(queue.stats().running, 1);
+    }
+}
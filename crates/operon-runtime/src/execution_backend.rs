@@ -0,0 +1,44 @@
+//! Where registered tools actually run. The runtime resolves, authorizes,
+//! queues and times every call, then hands it to its backend; the default
+//! runs the tool in this process, others may run it elsewhere (e.g. in a
+//! per-session container) and marshal input and output across.
+
+use anyhow::Result;
+use async_trait::async_trait;
+use serde_json::Value;
+
+use crate::tool::Tool;
+use crate::tool_middleware::ToolInvocation;
+
+#[async_trait]
+pub trait ExecutionBackend: Send + Sync {
+    /// Backend name for logging
+    fn name(&self) -> &str;
+
+    /// Run `call`; `tool` is the locally registered implementation
+    async fn execute(&self, call: ToolInvocation, tool: &dyn Tool) -> Result<Value>;
+
+    /// Release what the backend holds for `session` (e.g. its container)
+    async fn end_session(&self, _session: &str) -> Result<()> {
+        Ok(())
+    }
+
+    /// Release everything; called when the runtime stops
+    async fn shutdown(&self) -> Result<()> {
+        Ok(())
+    }
+}
+
+/// Runs tools directly in the runtime's process
+pub struct InProcess;
+
+#[async_trait]
+impl ExecutionBackend for InProcess {
+    fn name(&self) -> &str {
+        "in_process"
+    }
+
+    async fn execute(&self, call: ToolInvocation, tool: &dyn Tool) -> Result<Value> {
+        tool.execute(call.input).await
+    }
+}
@@ -4,14 +4,23 @@ use serde_json::Value;
 /// Hook lifecycle events
 #[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
 pub enum HookEvent {
-    /// Before tool execution
+    /// Before tool execution; data `{tool, input}`, where a changed `input`
+    /// is what runs
     ToolCallBefore,
-    /// After tool execution
+    /// After successful tool execution; data `{tool, input, output}`, where a
+    /// changed `output` is what the caller gets
     ToolCallAfter,
-    /// Session started
+    /// Session started; data `{agent, model, messages}`
     SessionStart,
-    /// Session ended
+    /// Session ended; data `{agent, result, messages, duration_ms, usage}`
     SessionEnd,
+    /// User message received, before the agent answers it; data
+    /// `{agent, message}`, where a changed `message` is what the agent sees
+    MessageReceived,
+    /// Agent finished answering a message; data `{agent, message, response}`
+    ResponseGenerated,
+    /// Top-level plan run finished (successfully or not)
+    PlanEnd,
     /// Config reloaded
     ConfigReload,
 }
@@ -34,3 +43,32 @@ pub struct HookResult {
     /// Hook can abort the operation
     pub abort: bool,
 }
+
+/// Condition on event data a hook only runs for, e.g. `HookFilter::tool("shell")`
+#[derive(Debug, Clone, PartialEq)]
+pub struct HookFilter {
+    /// JSON pointer into the event data (e.g. "/tool", "/input/command")
+    pub field: String,
+    /// Value the field must equal
+    pub value: Value,
+}
+
+impl HookFilter {
+    pub fn new(field: &str, value: impl Into<Value>) -> Self {
+        Self {
+            field: field.to_string(),
+            value: value.into(),
+        }
+    }
+
+    /// Only calls of tool `name` (`ToolCallBefore`, `ToolCallAfter`)
+    pub fn tool(name: &str) -> Self {
+        Self::new("/tool", name)
+    }
+
+    /// Whether the event data holds the value at the field; data without the
+    /// field does not match
+    pub fn matches(&self, ctx: &HookContext) -> bool {
+        ctx.data.pointer(&self.field) == Some(&self.value)
+    }
+}
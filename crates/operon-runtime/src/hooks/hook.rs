@@ -2,7 +2,7 @@ use anyhow::Result;
 use async_trait::async_trait;
 use std::time::Duration;
 
-use super::events::{HookContext, HookEvent, HookResult};
+use super::events::{HookContext, HookEvent, HookFilter, HookResult};
 
 /// Hook trait for intercepting runtime events
 #[async_trait]
@@ -13,6 +13,18 @@ pub trait Hook: Send + Sync {
     /// Events this hook subscribes to
     fn events(&self) -> &[HookEvent];
 
+    /// Conditions on the event data, all of which must hold for the hook to
+    /// run (default: none, runs on every subscribed event)
+    fn filters(&self) -> &[HookFilter] {
+        &[]
+    }
+
+    /// Order among the hooks of an event: lower runs first (default 0); equal
+    /// priorities run in registration order
+    fn priority(&self) -> i32 {
+        0
+    }
+
     /// Handle event, return result (can modify data or abort)
     async fn on_event(&self, ctx: &HookContext) -> Result<HookResult>;
 
@@ -2,6 +2,6 @@ pub mod events;
 pub mod hook;
 pub mod registry;
 
-pub use events::{HookContext, HookEvent, HookResult};
+pub use events::{HookContext, HookEvent, HookFilter, HookResult};
 pub use hook::Hook;
 pub use registry::HookRegistry;
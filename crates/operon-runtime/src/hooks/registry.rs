@@ -20,18 +20,19 @@ impl HookRegistry {
         }
     }
 
-    /// Register a hook for its declared events
+    /// Register a hook for its declared events, in priority order
     pub fn register(&self, hook: Arc<dyn Hook>) {
         for event in hook.events() {
-            self.hooks
-                .entry(event.clone())
-                .or_default()
-                .push(hook.clone());
+            let mut hooks = self.hooks.entry(event.clone()).or_default();
+            hooks.push(hook.clone());
+            // Stable: equal priorities keep registration order
+            hooks.sort_by_key(|hook| hook.priority());
         }
     }
 
     /// Trigger all hooks for an event, return (possibly modified) data
-    /// Hooks execute sequentially; non-critical errors are isolated (logged, not propagated)
+    /// Hooks execute sequentially in priority order, skipping those whose
+    /// filters do not match; non-critical errors are isolated (logged, not propagated)
     pub async fn trigger(&self, ctx: HookContext) -> Result<Value> {
         let hooks = self
             .hooks
@@ -50,6 +51,13 @@ impl HookRegistry {
                 data: data.clone(),
                 ..ctx.clone()
             };
+            if !hook
+                .filters()
+                .iter()
+                .all(|filter| filter.matches(&hook_ctx))
+            {
+                continue;
+            }
 
             let timeout = hook.timeout();
 
@@ -102,7 +110,7 @@ impl Default for HookRegistry {
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::hooks::events::HookResult;
+    use crate::hooks::events::{HookFilter, HookResult};
     use async_trait::async_trait;
     use serde_json::json;
 
@@ -172,6 +180,39 @@ mod tests {
         }
     }
 
+    /// Appends its name to `data["order"]`
+    struct OrderHook {
+        name: &'static str,
+        priority: i32,
+        filters: Vec<HookFilter>,
+    }
+
+    #[async_trait]
+    impl Hook for OrderHook {
+        fn name(&self) -> &str {
+            self.name
+        }
+        fn events(&self) -> &[HookEvent] {
+            &[HookEvent::ToolCallBefore]
+        }
+        fn filters(&self) -> &[HookFilter] {
+            &self.filters
+        }
+        fn priority(&self) -> i32 {
+            self.priority
+        }
+        async fn on_event(&self, ctx: &HookContext) -> Result<HookResult> {
+            let mut data = ctx.data.clone();
+            let mut order = data["order"].as_array().cloned().unwrap_or_default();
+            order.push(json!(self.name));
+            data["order"] = json!(order);
+            Ok(HookResult {
+                modified_data: Some(data),
+                abort: false,
+            })
+        }
+    }
+
     fn make_ctx(event: HookEvent) -> HookContext {
         HookContext {
             event,
@@ -242,4 +283,64 @@ mod tests {
             .unwrap();
         assert_eq!(result["tool"], "shell");
     }
+
+    #[tokio::test]
+    async fn test_hooks_run_in_priority_order() {
+        let registry = HookRegistry::new();
+        for (name, priority) in [
+            ("late", 10),
+            ("first", -5),
+            ("default_a", 0),
+            ("default_b", 0),
+        ] {
+            registry.register(Arc::new(OrderHook {
+                name,
+                priority,
+                filters: Vec::new(),
+            }));
+        }
+
+        let result = registry
+            .trigger(make_ctx(HookEvent::ToolCallBefore))
+            .await
+            .unwrap();
+        assert_eq!(
+            result["order"],
+            json!(["first", "default_a", "default_b", "late"])
+        );
+    }
+
+    #[tokio::test]
+    async fn test_hook_filters_select_events() {
+        let registry = HookRegistry::new();
+        registry.register(Arc::new(OrderHook {
+            name: "shell_only",
+            priority: 0,
+            filters: vec![HookFilter::tool("shell")],
+        }));
+        registry.register(Arc::new(OrderHook {
+            name: "rm_only",
+            priority: 0,
+            filters: vec![
+                HookFilter::tool("shell"),
+                HookFilter::new("/input/command", "rm"),
+            ],
+        }));
+
+        let result = registry
+            .trigger(make_ctx(HookEvent::ToolCallBefore))
+            .await
+            .unwrap();
+        assert_eq!(result["order"], json!(["shell_only"]));
+
+        let mut ctx = make_ctx(HookEvent::ToolCallBefore);
+        ctx.data = json!({"tool": "shell", "input": {"command": "rm"}});
+        let result = registry.trigger(ctx).await.unwrap();
+        assert_eq!(result["order"], json!(["shell_only", "rm_only"]));
+
+        let mut ctx = make_ctx(HookEvent::ToolCallBefore);
+        ctx.data = json!({"tool": "read_file"});
+        let result = registry.trigger(ctx).await.unwrap();
+        assert!(result.get("order").is_none());
+    }
 }
@@ -1,36 +1,66 @@
 pub mod agent_module;
+pub mod audit;
+pub mod checkpoint;
+pub mod composite;
 pub mod config;
+pub mod exec_queue;
+pub mod execution_backend;
 pub mod hooks;
 pub mod llm;
 pub mod memory;
 pub mod plugin;
+pub mod prompt_template;
+pub mod redaction;
 pub mod replay;
 pub mod runtime;
 pub mod scheduler;
+pub mod session_store;
 pub mod storage;
 pub mod tool;
+pub mod tool_middleware;
 pub mod tool_policy;
 
-pub use agent_module::{Agent, AgentConfig, Session, SessionStore};
+pub use agent_module::{
+    Agent, AgentConfig, AgentEvent, ContextConfig, ContextStrategy, ExampleToolCall,
+    FewShotExample, Session, SessionBackend, SessionStore, INTERRUPTED_MARKER,
+};
+pub use audit::{AuditDecision, AuditFilter, AuditOutcome, AuditRecord};
+pub use composite::{CompositeSpec, CompositeStep, CompositeTool};
 pub use config::{ConfigManager, ConfigReloadEvent};
-pub use hooks::{Hook, HookContext, HookEvent, HookRegistry, HookResult};
+pub use exec_queue::{ExecPriority, QueueStats};
+pub use execution_backend::{ExecutionBackend, InProcess};
+pub use hooks::{Hook, HookContext, HookEvent, HookFilter, HookRegistry, HookResult};
 pub use llm::{
-    AnthropicClient, Content, GenerateConfig, GenerateResponse, GeminiClient, LLMProvider, Message,
-    OpenAIClient, ProviderChain, Role, StopReason, ToolCall, ToolResult, ToolSchema, Usage,
+    estimator_for_model, AnthropicClient, Content, GeminiClient, GenerateConfig, GenerateResponse,
+    LLMProvider, Message, MiddlewareProvider, ModelPricing, OpenAIClient, ProviderChain,
+    ProviderHealth, ProviderMiddleware, ProviderRouter, ReasoningEffort, Role, StopReason,
+    TaskKind, TokenBudget, TokenEstimator, ToolCall, ToolResult, ToolSchema, Usage,
 };
 pub use plugin::{Plugin, PluginHandle, PluginLoader, PluginManifest, PluginType};
-pub use replay::{Fixture, StepRecord};
-pub use runtime::{ExecutionContext, Runtime};
+pub use prompt_template::{PromptRegistry, PromptTemplate};
+pub use redaction::Redactor;
+pub use replay::{Fixture, FixtureTool, ScrubStats, StepRecord, FIXTURE_VERSION};
+pub use runtime::{
+    ExecutionContext, NestedStorage, PlanResult, Runtime, StepResult, StepStatus, PLAN_STEP_TOOL,
+};
+pub use scheduler::PlanSchedule;
+pub use session_store::{ImportStats, SessionInfo, SessionQuery, SqliteSessionStore};
 pub use storage::Storage;
 pub use tool::{PermissionLevel, Tool, ToolSchemaInfo};
-pub use tool_policy::{PolicyContext, PolicyDecision, PolicyLayer, ToolPolicyPipeline};
+pub use tool_middleware::{current_session, OutputLimit, ToolInvocation, ToolMiddleware};
+pub use tool_policy::{
+    ApprovalRequest, Approver, AsyncPolicyLayer, PolicyContext, PolicyDecision, PolicyLayer,
+    ToolPolicyPipeline,
+};
 
 /// Initialize structured JSON logging
 pub fn init_logging() {
     use tracing_subscriber::{fmt, EnvFilter};
 
+    // Logs go to stderr so stdout stays clean for command output (e.g. --output json)
     fmt()
         .json()
+        .with_writer(std::io::stderr)
         .with_env_filter(EnvFilter::from_default_env())
         .init();
 }
@@ -1,15 +1,17 @@
-use anyhow::{anyhow, Result};
+use anyhow::Result;
 use async_trait::async_trait;
 use reqwest::{Client, ClientBuilder};
 use serde::Deserialize;
 use serde_json::{json, Value};
 use std::time::Duration;
 
+use super::error::ProviderError;
 use super::provider::LLMProvider;
-use super::streaming::{drive_sse_stream, parse_anthropic_sse};
+use super::streaming::{drive_sse_stream, StreamAssembler};
 use super::types::*;
 
 const ANTHROPIC_API_URL: &str = "https://api.anthropic.com/v1/messages";
+const ANTHROPIC_MODELS_URL: &str = "https://api.anthropic.com/v1/models";
 const ANTHROPIC_VERSION: &str = "2023-06-01";
 const DEFAULT_MODEL: &str = "claude-sonnet-4-20250514";
 
@@ -18,6 +20,10 @@ pub struct AnthropicClient {
     client: Client,
     api_key: String,
     model: String,
+    /// Extended thinking budget in tokens (None = thinking disabled)
+    thinking_budget: Option<u32>,
+    /// Forward reasoning as `StreamChunk::Thinking` while streaming
+    show_thinking: bool,
 }
 
 impl AnthropicClient {
@@ -31,6 +37,8 @@ impl AnthropicClient {
             client,
             api_key: api_key.to_string(),
             model: DEFAULT_MODEL.to_string(),
+            thinking_budget: None,
+            show_thinking: false,
         }
     }
 
@@ -39,6 +47,19 @@ impl AnthropicClient {
         self
     }
 
+    /// Enable extended thinking with `budget_tokens` of reasoning per request.
+    /// The budget is added on top of `GenerateConfig::max_tokens`.
+    pub fn with_thinking(mut self, budget_tokens: u32) -> Self {
+        self.thinking_budget = Some(budget_tokens);
+        self
+    }
+
+    /// Stream reasoning to the consumer instead of only recording it
+    pub fn with_show_thinking(mut self, show: bool) -> Self {
+        self.show_thinking = show;
+        self
+    }
+
     /// Build Anthropic API request body from messages and tools
     fn build_request_body(
         &self,
@@ -59,6 +80,21 @@ impl AnthropicClient {
             "temperature": config.temperature,
         });
 
+        if let Some(budget) = self.thinking_budget {
+            // Thinking counts toward max_tokens and rejects custom sampling
+            body["max_tokens"] = json!(config.max_tokens.saturating_add(budget));
+            body["thinking"] = json!({"type": "enabled", "budget_tokens": budget});
+            body.as_object_mut().unwrap().remove("temperature");
+        } else {
+            // No penalty or seed support in the Messages API
+            if let Some(top_p) = config.top_p {
+                body["top_p"] = json!(top_p);
+            }
+            if let Some(top_k) = config.top_k {
+                body["top_k"] = json!(top_k);
+            }
+        }
+
         if stream {
             body["stream"] = json!(true);
         }
@@ -67,6 +103,10 @@ impl AnthropicClient {
             body["system"] = json!(sys);
         }
 
+        if !config.stop_sequences.is_empty() {
+            body["stop_sequences"] = json!(config.stop_sequences);
+        }
+
         let api_messages: Vec<Value> = messages
             .iter()
             .filter(|m| m.role != Role::System)
@@ -77,6 +117,11 @@ impl AnthropicClient {
         if !tools.is_empty() {
             let api_tools: Vec<Value> = tools.iter().map(|t| self.tool_to_api(t)).collect();
             body["tools"] = json!(api_tools);
+            match config.tool_choice {
+                ToolChoice::Auto => {}
+                ToolChoice::Any => body["tool_choice"] = json!({"type": "any"}),
+                ToolChoice::None => body["tool_choice"] = json!({"type": "none"}),
+            }
         }
 
         body
@@ -89,52 +134,9 @@ impl AnthropicClient {
             Role::System => "user",
         };
 
-        let content = match &msg.content {
-            Content::Text { text } => json!([{"type": "text", "text": text}]),
-            Content::ToolCall(tc) => {
-                json!([{
-                    "type": "tool_use",
-                    "id": tc.id,
-                    "name": tc.name,
-                    "input": tc.input,
-                }])
-            }
-            Content::ToolResult(tr) => {
-                json!([{
-                    "type": "tool_result",
-                    "tool_use_id": tr.tool_use_id,
-                    "content": tr.output,
-                    "is_error": tr.is_error,
-                }])
-            }
-            Content::Mixed { parts } => {
-                let blocks: Vec<Value> = parts
-                    .iter()
-                    .map(|p| match p {
-                        Content::Text { text } => json!({"type": "text", "text": text}),
-                        Content::ToolCall(tc) => json!({
-                            "type": "tool_use",
-                            "id": tc.id,
-                            "name": tc.name,
-                            "input": tc.input,
-                        }),
-                        _ => json!({"type": "text", "text": ""}),
-                    })
-                    .collect();
-                json!(blocks)
-            }
-            Content::Image { data, mime } => {
-                use base64::Engine;
-                let encoded = base64::engine::general_purpose::STANDARD.encode(data);
-                json!([{
-                    "type": "image",
-                    "source": {
-                        "type": "base64",
-                        "media_type": mime,
-                        "data": encoded,
-                    }
-                }])
-            }
+        let content: Vec<Value> = match &msg.content {
+            Content::Mixed { parts } => parts.iter().filter_map(content_block_to_api).collect(),
+            single => content_block_to_api(single).into_iter().collect(),
         };
 
         json!({ "role": role, "content": content })
@@ -149,11 +151,18 @@ impl AnthropicClient {
     }
 
     fn parse_response(&self, body: &ApiResponse) -> Result<GenerateResponse> {
+        let mut thinking_parts = Vec::new();
         let mut text_parts = Vec::new();
         let mut tool_calls = Vec::new();
 
         for block in &body.content {
             match block.block_type.as_str() {
+                "thinking" => {
+                    thinking_parts.push(Content::Thinking {
+                        thinking: block.thinking.clone().unwrap_or_default(),
+                        signature: block.signature.clone().unwrap_or_default(),
+                    });
+                }
                 "text" => {
                     if let Some(ref text) = block.text {
                         text_parts.push(Content::Text { text: text.clone() });
@@ -170,7 +179,8 @@ impl AnthropicClient {
             }
         }
 
-        let mut parts = text_parts;
+        let mut parts = thinking_parts;
+        parts.extend(text_parts);
         parts.extend(tool_calls);
 
         let content = if parts.len() == 1 {
@@ -191,12 +201,55 @@ impl AnthropicClient {
             usage: Usage {
                 input_tokens: body.usage.input_tokens,
                 output_tokens: body.usage.output_tokens,
+                cost_usd: 0.0,
             },
             model: body.model.clone(),
         })
     }
 }
 
+/// One content block in Messages API form (None for nested Mixed)
+fn content_block_to_api(content: &Content) -> Option<Value> {
+    let block = match content {
+        Content::Text { text } => json!({"type": "text", "text": text}),
+        Content::ToolCall(tc) => json!({
+            "type": "tool_use",
+            "id": tc.id,
+            "name": tc.name,
+            "input": tc.input,
+        }),
+        Content::ToolResult(tr) => json!({
+            "type": "tool_result",
+            "tool_use_id": tr.tool_use_id,
+            "content": tr.output,
+            "is_error": tr.is_error,
+        }),
+        Content::Thinking {
+            thinking,
+            signature,
+        } => json!({"type": "thinking", "thinking": thinking, "signature": signature}),
+        Content::Image { data, mime } => {
+            use base64::Engine;
+            let encoded = base64::engine::general_purpose::STANDARD.encode(data);
+            json!({
+                "type": "image",
+                "source": {
+                    "type": "base64",
+                    "media_type": mime,
+                    "data": encoded,
+                }
+            })
+        }
+        // Claude has no audio input; say so rather than send an empty turn
+        Content::Audio { mime, .. } => json!({
+            "type": "text",
+            "text": format!("[{} audio attachment omitted: not supported by this model]", mime),
+        }),
+        Content::Mixed { .. } => return None,
+    };
+    Some(block)
+}
+
 #[async_trait]
 impl LLMProvider for AnthropicClient {
     async fn generate(
@@ -217,10 +270,10 @@ impl LLMProvider for AnthropicClient {
             .send()
             .await?;
 
-        let status = response.status();
-        if !status.is_success() {
-            let error_body = response.text().await.unwrap_or_default();
-            return Err(anyhow!("Anthropic API error ({}): {}", status, error_body));
+        if !response.status().is_success() {
+            return Err(ProviderError::from_response("Anthropic", response)
+                .await
+                .into());
         }
 
         let api_response: ApiResponse = response.json().await?;
@@ -245,44 +298,29 @@ impl LLMProvider for AnthropicClient {
             .send()
             .await?;
 
-        let status = response.status();
-        if !status.is_success() {
-            let error_body = response.text().await.unwrap_or_default();
-            return Err(anyhow!("Anthropic API error ({}): {}", status, error_body));
+        if !response.status().is_success() {
+            return Err(ProviderError::from_response("Anthropic", response)
+                .await
+                .into());
         }
 
         let (tx, rx) = tokio::sync::mpsc::channel(32);
 
         tokio::spawn({
             let byte_stream = response.bytes_stream();
+            let show_thinking = self.show_thinking;
             async move {
-                let mut current_tool_id = String::new();
+                let mut assembler = StreamAssembler::new();
                 drive_sse_stream(
                     byte_stream,
-                    |data: &str| -> Vec<StreamChunk> {
-                        match parse_anthropic_sse(data) {
-                            Some(mut chunk) => {
-                                match &chunk {
-                                    StreamChunk::ToolCallStart { id, .. } => {
-                                        current_tool_id = id.clone();
-                                    }
-                                    StreamChunk::ToolCallDelta { id, .. } if id.is_empty() => {
-                                        chunk = StreamChunk::ToolCallDelta {
-                                            id: current_tool_id.clone(),
-                                            input_delta: match chunk {
-                                                StreamChunk::ToolCallDelta {
-                                                    input_delta, ..
-                                                } => input_delta,
-                                                _ => unreachable!(),
-                                            },
-                                        };
-                                    }
-                                    _ => {}
-                                }
-                                vec![chunk]
-                            }
-                            None => vec![],
+                    |data| {
+                        let mut chunks = assembler.push_anthropic(data);
+                        // Reasoning deltas are display only; ThinkingBlock
+                        // chunks always go through
+                        if !show_thinking {
+                            chunks.retain(|c| !matches!(c, StreamChunk::Thinking(_)));
                         }
+                        chunks
                     },
                     tx,
                 )
@@ -293,6 +331,25 @@ impl LLMProvider for AnthropicClient {
         Ok(rx)
     }
 
+    /// Lists a single model; validates the key without spending tokens
+    async fn health_check(&self) -> Result<()> {
+        let response = self
+            .client
+            .get(ANTHROPIC_MODELS_URL)
+            .query(&[("limit", "1")])
+            .header("x-api-key", &self.api_key)
+            .header("anthropic-version", ANTHROPIC_VERSION)
+            .send()
+            .await?;
+
+        if !response.status().is_success() {
+            return Err(ProviderError::from_response("Anthropic", response)
+                .await
+                .into());
+        }
+        Ok(())
+    }
+
     fn supports_vision(&self) -> bool {
         true
     }
@@ -318,6 +375,8 @@ struct ContentBlock {
     id: Option<String>,
     name: Option<String>,
     input: Option<Value>,
+    thinking: Option<String>,
+    signature: Option<String>,
 }
 
 #[derive(Debug, Deserialize)]
@@ -326,9 +385,7 @@ struct ApiUsage {
     output_tokens: u32,
 }
 
-pub fn is_retryable_status(status: u16) -> bool {
-    status == 429 || status == 529 || (500..600).contains(&status)
-}
+pub use super::error::is_retryable_status;
 
 #[cfg(test)]
 mod tests {
@@ -351,6 +408,70 @@ mod tests {
         assert!(body.get("stream").is_none());
     }
 
+    #[test]
+    fn test_build_request_body_tool_choice() {
+        let client = AnthropicClient::new("test-key");
+        let tools = vec![ToolSchema {
+            name: "shell".into(),
+            description: "Execute shell command".into(),
+            input_schema: json!({"type": "object"}),
+        }];
+        let mut config = GenerateConfig::default();
+
+        let body = client.build_request_body(&[Message::user("Hi")], &tools, &config, false);
+        assert!(body.get("tool_choice").is_none());
+
+        config.tool_choice = ToolChoice::Any;
+        let body = client.build_request_body(&[Message::user("Hi")], &tools, &config, false);
+        assert_eq!(body["tool_choice"]["type"], "any");
+
+        config.tool_choice = ToolChoice::None;
+        let body = client.build_request_body(&[Message::user("Hi")], &tools, &config, false);
+        assert_eq!(body["tool_choice"]["type"], "none");
+    }
+
+    #[test]
+    fn test_build_request_body_stop_sequences() {
+        let client = AnthropicClient::new("test-key");
+        let body = client.build_request_body(
+            &[Message::user("List")],
+            &[],
+            &GenerateConfig::default(),
+            false,
+        );
+        assert!(body.get("stop_sequences").is_none());
+
+        let config = GenerateConfig {
+            stop_sequences: vec!["</plan>".into()],
+            ..Default::default()
+        };
+        let body = client.build_request_body(&[Message::user("List")], &[], &config, false);
+        assert_eq!(body["stop_sequences"], json!(["</plan>"]));
+    }
+
+    #[test]
+    fn test_build_request_body_sampling() {
+        let config = GenerateConfig {
+            top_p: Some(0.9),
+            top_k: Some(40),
+            presence_penalty: Some(0.5),
+            seed: Some(7),
+            ..Default::default()
+        };
+
+        let body = AnthropicClient::new("test-key").build_request_body(
+            &[Message::user("Hi")],
+            &[],
+            &config,
+            false,
+        );
+        assert_eq!(body["top_k"], 40);
+        assert!((body["top_p"].as_f64().unwrap() - 0.9).abs() < 0.001);
+        // Unsupported parameters are left out rather than rejected
+        assert!(body.get("presence_penalty").is_none());
+        assert!(body.get("seed").is_none());
+    }
+
     #[test]
     fn test_build_request_body_streaming() {
         let client = AnthropicClient::new("test-key");
@@ -372,6 +493,8 @@ mod tests {
                 id: None,
                 name: None,
                 input: None,
+                thinking: None,
+                signature: None,
             }],
             stop_reason: Some("end_turn".into()),
             usage: ApiUsage {
@@ -397,6 +520,8 @@ mod tests {
                     id: None,
                     name: None,
                     input: None,
+                    thinking: None,
+                    signature: None,
                 },
                 ContentBlock {
                     block_type: "tool_use".into(),
@@ -404,6 +529,8 @@ mod tests {
                     id: Some("toolu_123".into()),
                     name: Some("shell".into()),
                     input: Some(json!({"cmd": "date"})),
+                    thinking: None,
+                    signature: None,
                 },
             ],
             stop_reason: Some("tool_use".into()),
@@ -419,4 +546,91 @@ mod tests {
         assert_eq!(calls.len(), 1);
         assert_eq!(calls[0].name, "shell");
     }
+
+    #[test]
+    fn test_mixed_text_and_image_message() {
+        let client = AnthropicClient::new("test-key");
+        let msg = Message::user_with_image("What is this?", vec![1, 2, 3], "image/png");
+
+        let api_msg = client.message_to_api(&msg);
+        let content = api_msg["content"].as_array().unwrap();
+        assert_eq!(content.len(), 2);
+        assert_eq!(content[0]["type"], "text");
+        assert_eq!(content[0]["text"], "What is this?");
+        assert_eq!(content[1]["type"], "image");
+        assert_eq!(content[1]["source"]["type"], "base64");
+        assert_eq!(content[1]["source"]["media_type"], "image/png");
+        assert_eq!(content[1]["source"]["data"], "AQID");
+    }
+
+    #[test]
+    fn test_audio_replaced_with_note() {
+        let client = AnthropicClient::new("test-key");
+        let msg = Message::user_with_audio("Transcribe", vec![1, 2, 3], "audio/wav");
+
+        let api_msg = client.message_to_api(&msg);
+        let note = &api_msg["content"][1];
+        assert_eq!(note["type"], "text");
+        assert!(note["text"].as_str().unwrap().contains("audio/wav"));
+        assert!(!client.supports_audio());
+    }
+
+    #[test]
+    fn test_thinking_request_body() {
+        let client = AnthropicClient::new("test-key").with_thinking(2048);
+        let config = GenerateConfig {
+            max_tokens: 1000,
+            ..Default::default()
+        };
+
+        let body = client.build_request_body(&[Message::user("Hello")], &[], &config, false);
+        assert_eq!(body["thinking"]["type"], "enabled");
+        assert_eq!(body["thinking"]["budget_tokens"], 2048);
+        assert_eq!(body["max_tokens"], 3048);
+        assert!(body.get("temperature").is_none());
+    }
+
+    #[test]
+    fn test_thinking_block_round_trip() {
+        let client = AnthropicClient::new("test-key");
+        let api_resp = ApiResponse {
+            model: "claude-sonnet-4-20250514".into(),
+            content: vec![
+                ContentBlock {
+                    block_type: "thinking".into(),
+                    text: None,
+                    id: None,
+                    name: None,
+                    input: None,
+                    thinking: Some("User wants the date.".into()),
+                    signature: Some("sig-abc".into()),
+                },
+                ContentBlock {
+                    block_type: "tool_use".into(),
+                    text: None,
+                    id: Some("toolu_1".into()),
+                    name: Some("shell".into()),
+                    input: Some(json!({"cmd": "date"})),
+                    thinking: None,
+                    signature: None,
+                },
+            ],
+            stop_reason: Some("tool_use".into()),
+            usage: ApiUsage {
+                input_tokens: 20,
+                output_tokens: 40,
+            },
+        };
+
+        let resp = client.parse_response(&api_resp).unwrap();
+        // Reasoning stays out of the visible text
+        assert_eq!(resp.content.extract_text(), "");
+        assert_eq!(resp.content.extract_thinking(), "User wants the date.");
+
+        // Signed thinking is replayed ahead of the tool call
+        let api_msg = client.message_to_api(&Message::assistant(resp.content));
+        assert_eq!(api_msg["content"][0]["type"], "thinking");
+        assert_eq!(api_msg["content"][0]["signature"], "sig-abc");
+        assert_eq!(api_msg["content"][1]["type"], "tool_use");
+    }
 }
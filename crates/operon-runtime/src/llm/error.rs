@@ -0,0 +1,140 @@
+use std::fmt;
+use std::time::Duration;
+
+use reqwest::header::HeaderMap;
+use reqwest::{Response, StatusCode};
+
+/// Non-success HTTP response from an LLM provider API.
+/// Carries the status and server-requested retry delay so failover can act on
+/// them directly; downcast from `anyhow::Error` to inspect.
+#[derive(Debug, Clone)]
+pub struct ProviderError {
+    /// Provider label used in messages ("Anthropic", "OpenAI", "Gemini")
+    pub provider: &'static str,
+    pub status: StatusCode,
+    /// Delay from `Retry-After` / `retry-after-ms` (or the body, for Gemini)
+    pub retry_after: Option<Duration>,
+    pub body: String,
+}
+
+impl ProviderError {
+    /// Consume an error response, capturing status, retry hint and body
+    pub async fn from_response(provider: &'static str, response: Response) -> Self {
+        let status = response.status();
+        let retry_after = parse_retry_after(response.headers());
+        let body = response.text().await.unwrap_or_default();
+        Self {
+            provider,
+            status,
+            retry_after,
+            body,
+        }
+    }
+
+    /// Rate limits, overload and server errors are worth retrying
+    pub fn is_retryable(&self) -> bool {
+        is_retryable_status(self.status.as_u16())
+    }
+}
+
+impl fmt::Display for ProviderError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{} API error ({}): {}",
+            self.provider, self.status, self.body
+        )
+    }
+}
+
+impl std::error::Error for ProviderError {}
+
+pub fn is_retryable_status(status: u16) -> bool {
+    status == 429 || status == 529 || (500..600).contains(&status)
+}
+
+/// Read `retry-after-ms` (OpenAI) or `Retry-After` as seconds or an HTTP date
+pub fn parse_retry_after(headers: &HeaderMap) -> Option<Duration> {
+    if let Some(ms) = headers
+        .get("retry-after-ms")
+        .and_then(|v| v.to_str().ok())
+        .and_then(|v| v.trim().parse::<f64>().ok())
+    {
+        return (ms >= 0.0).then(|| Duration::from_secs_f64(ms / 1000.0));
+    }
+
+    let value = headers
+        .get(reqwest::header::RETRY_AFTER)?
+        .to_str()
+        .ok()?
+        .trim();
+    if let Ok(secs) = value.parse::<u64>() {
+        return Some(Duration::from_secs(secs));
+    }
+    let date = chrono::DateTime::parse_from_rfc2822(value).ok()?;
+    let wait = date.signed_duration_since(chrono::Utc::now());
+    Some(wait.to_std().unwrap_or(Duration::ZERO))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use reqwest::header::HeaderValue;
+
+    fn headers(pairs: &[(&'static str, &str)]) -> HeaderMap {
+        let mut map = HeaderMap::new();
+        for (name, value) in pairs {
+            map.insert(*name, HeaderValue::from_str(value).unwrap());
+        }
+        map
+    }
+
+    #[test]
+    fn test_parse_retry_after_seconds_and_ms() {
+        assert_eq!(
+            parse_retry_after(&headers(&[("retry-after", "7")])),
+            Some(Duration::from_secs(7))
+        );
+        // Millisecond header is more precise and wins
+        assert_eq!(
+            parse_retry_after(&headers(&[
+                ("retry-after", "7"),
+                ("retry-after-ms", "1500")
+            ])),
+            Some(Duration::from_millis(1500))
+        );
+        assert_eq!(
+            parse_retry_after(&headers(&[("retry-after", "soon")])),
+            None
+        );
+        assert_eq!(parse_retry_after(&HeaderMap::new()), None);
+    }
+
+    #[test]
+    fn test_parse_retry_after_http_date() {
+        let past = "Wed, 21 Oct 2015 07:28:00 GMT";
+        assert_eq!(
+            parse_retry_after(&headers(&[("retry-after", past)])),
+            Some(Duration::ZERO)
+        );
+
+        let future = (chrono::Utc::now() + chrono::Duration::seconds(120)).to_rfc2822();
+        let wait = parse_retry_after(&headers(&[("retry-after", &future)])).unwrap();
+        assert!(wait > Duration::from_secs(100) && wait <= Duration::from_secs(120));
+    }
+
+    #[test]
+    fn test_display_matches_legacy_format() {
+        let err = ProviderError {
+            provider: "Anthropic",
+            status: StatusCode::TOO_MANY_REQUESTS,
+            retry_after: None,
+            body: "slow down".into(),
+        };
+        assert_eq!(
+            err.to_string(),
+            "Anthropic API error (429 Too Many Requests): slow down"
+        );
+        assert!(err.is_retryable());
+    }
+}
@@ -1,70 +1,168 @@
 use std::collections::HashMap;
-use std::sync::atomic::{AtomicUsize, Ordering};
-use std::sync::Arc;
-use std::time::Duration;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
 
 use anyhow::{anyhow, Result};
 use async_trait::async_trait;
-use tokio::sync::RwLock;
 
-use super::provider::LLMProvider;
+use super::error::ProviderError;
+use super::provider::{probe_health, LLMProvider, ProviderHealth};
 use super::types::*;
 
 const MAX_RETRIES: usize = 3;
 const BASE_BACKOFF_MS: u64 = 500;
+/// Longest server-requested wait honored before failing over instead
+const MAX_RETRY_AFTER: Duration = Duration::from_secs(30);
+const DEFAULT_FAILURE_DECAY: Duration = Duration::from_secs(60);
+const DEFAULT_HALF_OPEN_AFTER: Duration = Duration::from_secs(30);
+
+/// Per-provider circuit breaker state
+#[derive(Debug, Default)]
+struct ProviderState {
+    failures: usize,
+    last_failure: Option<Instant>,
+    /// Set while a half-open probe request is in flight
+    probe_started: Option<Instant>,
+}
+
+/// Circuit breaker bookkeeping, shared with spliced stream tasks
+#[derive(Clone)]
+struct FailureTracker {
+    states: Arc<Mutex<HashMap<String, ProviderState>>>,
+    max_failures: usize,
+    failure_decay: Duration,
+    half_open_after: Duration,
+}
+
+impl FailureTracker {
+    /// Providers to try, in order: healthy ones, plus excluded ones due a half-open probe
+    fn available(&self, providers: &[Arc<dyn LLMProvider>]) -> Vec<Arc<dyn LLMProvider>> {
+        let mut states = self.states.lock().unwrap();
+        let now = Instant::now();
+        providers
+            .iter()
+            .filter(|p| {
+                let Some(state) = states.get_mut(p.model_name()) else {
+                    return true;
+                };
+
+                if state.failures < self.max_failures {
+                    self.decay(state, now);
+                    return true;
+                }
+
+                // Open: allow one probe once the cooldown has passed. A probe that
+                // never reported back (dropped request) expires after another cooldown.
+                let cooled_down = state
+                    .last_failure
+                    .is_none_or(|t| now.duration_since(t) >= self.half_open_after);
+                let probe_idle = state
+                    .probe_started
+                    .is_none_or(|t| now.duration_since(t) >= self.half_open_after);
+                if cooled_down && probe_idle {
+                    tracing::info!(provider = p.model_name(), "Probing excluded LLM provider");
+                    state.probe_started = Some(now);
+                    return true;
+                }
+                false
+            })
+            .cloned()
+            .collect()
+    }
+
+    fn decay(&self, state: &mut ProviderState, now: Instant) {
+        let (Some(last), false) = (state.last_failure, self.failure_decay.is_zero()) else {
+            return;
+        };
+        let steps = (now.duration_since(last).as_nanos() / self.failure_decay.as_nanos()) as usize;
+        if steps >= state.failures {
+            state.failures = 0;
+            state.last_failure = None;
+        } else if steps > 0 {
+            state.failures -= steps;
+            state.last_failure = Some(last + self.failure_decay * steps as u32);
+        }
+    }
+
+    /// Track a failure for a provider
+    fn track_failure(&self, model_name: &str) {
+        let mut states = self.states.lock().unwrap();
+        let state = states.entry(model_name.to_string()).or_default();
+        state.failures += 1;
+        state.last_failure = Some(Instant::now());
+        state.probe_started = None;
+        if state.failures == self.max_failures {
+            tracing::warn!(
+                provider = model_name,
+                retry_after_secs = self.half_open_after.as_secs(),
+                "LLM provider excluded from chain"
+            );
+        }
+    }
+
+    /// Reset failure count for a provider (on success)
+    fn reset(&self, model_name: &str) {
+        let mut states = self.states.lock().unwrap();
+        if let Some(state) = states.remove(model_name) {
+            if state.failures >= self.max_failures {
+                tracing::info!(
+                    provider = model_name,
+                    "LLM provider recovered, rejoining chain"
+                );
+            }
+        }
+    }
+
+    #[cfg(test)]
+    fn failure_count(&self, model_name: &str) -> usize {
+        self.states
+            .lock()
+            .unwrap()
+            .get(model_name)
+            .map(|s| s.failures)
+            .unwrap_or(0)
+    }
+}
 
 /// Provider chain with failover support
-/// Tries providers in order, tracks failures, retries with exponential backoff
+/// Tries providers in order, tracks failures, retries with exponential backoff.
+/// A provider that reaches `max_failures` is skipped until `half_open_after`
+/// has passed, then gets a single probe request; success rejoins it to the chain.
+/// Below the threshold, one failure is forgotten per `failure_decay` interval.
+/// Streams that die partway are resumed on the next provider (see `generate_stream`).
 pub struct ProviderChain {
     providers: Vec<Arc<dyn LLMProvider>>,
-    failure_counts: Arc<RwLock<HashMap<String, AtomicUsize>>>,
-    max_failures: usize,
+    tracker: FailureTracker,
 }
 
 impl ProviderChain {
     pub fn new(providers: Vec<Arc<dyn LLMProvider>>) -> Self {
         Self {
             providers,
-            failure_counts: Arc::new(RwLock::new(HashMap::new())),
-            max_failures: 5,
+            tracker: FailureTracker {
+                states: Arc::new(Mutex::new(HashMap::new())),
+                max_failures: 5,
+                failure_decay: DEFAULT_FAILURE_DECAY,
+                half_open_after: DEFAULT_HALF_OPEN_AFTER,
+            },
         }
     }
 
     pub fn with_max_failures(mut self, max: usize) -> Self {
-        self.max_failures = max;
+        self.tracker.max_failures = max;
         self
     }
 
-    /// Get available providers (not exceeded max failures)
-    async fn available_providers(&self) -> Vec<Arc<dyn LLMProvider>> {
-        let counts = self.failure_counts.read().await;
-        self.providers
-            .iter()
-            .filter(|p| {
-                counts
-                    .get(p.model_name())
-                    .map(|c| c.load(Ordering::Relaxed) < self.max_failures)
-                    .unwrap_or(true)
-            })
-            .cloned()
-            .collect()
-    }
-
-    /// Track a failure for a provider
-    async fn track_failure(&self, model_name: &str) {
-        let mut counts = self.failure_counts.write().await;
-        counts
-            .entry(model_name.to_string())
-            .or_insert_with(|| AtomicUsize::new(0))
-            .fetch_add(1, Ordering::Relaxed);
+    /// Forget one failure per `decay` interval without new failures
+    pub fn with_failure_decay(mut self, decay: Duration) -> Self {
+        self.tracker.failure_decay = decay;
+        self
     }
 
-    /// Reset failure count for a provider (on success)
-    async fn reset_failures(&self, model_name: &str) {
-        let counts = self.failure_counts.read().await;
-        if let Some(count) = counts.get(model_name) {
-            count.store(0, Ordering::Relaxed);
-        }
+    /// Cooldown before an excluded provider gets a probe request
+    pub fn with_half_open_after(mut self, cooldown: Duration) -> Self {
+        self.tracker.half_open_after = cooldown;
+        self
     }
 }
 
@@ -76,7 +174,7 @@ impl LLMProvider for ProviderChain {
         tools: &[ToolSchema],
         config: &GenerateConfig,
     ) -> Result<GenerateResponse> {
-        let available = self.available_providers().await;
+        let available = self.tracker.available(&self.providers);
 
         if available.is_empty() {
             return Err(anyhow!("All LLM providers have exceeded failure threshold"));
@@ -85,26 +183,21 @@ impl LLMProvider for ProviderChain {
         let mut last_error = None;
 
         for provider in &available {
-            let mut last_error_msg = String::new();
+            let mut backoff: Option<Duration> = None;
             for retry in 0..MAX_RETRIES {
-                if retry > 0 {
-                    let backoff = if !last_error_msg.is_empty() {
-                        parse_retry_delay(&last_error_msg)
-                    } else {
-                        Duration::from_millis(BASE_BACKOFF_MS * 2u64.pow(retry as u32))
-                    };
+                if let Some(delay) = backoff.take() {
                     tracing::info!(
                         provider = provider.model_name(),
                         retry,
-                        backoff_ms = backoff.as_millis() as u64,
+                        backoff_ms = delay.as_millis() as u64,
                         "Retrying LLM request"
                     );
-                    tokio::time::sleep(backoff).await;
+                    tokio::time::sleep(delay).await;
                 }
 
                 match provider.generate(messages, tools, config).await {
                     Ok(response) => {
-                        self.reset_failures(provider.model_name()).await;
+                        self.tracker.reset(provider.model_name());
 
                         if retry > 0 || !std::ptr::eq(provider.as_ref(), available[0].as_ref()) {
                             tracing::info!(
@@ -116,31 +209,39 @@ impl LLMProvider for ProviderChain {
                         return Ok(response);
                     }
                     Err(e) => {
-                        let err_str = e.to_string();
-                        last_error_msg = err_str.clone();
                         tracing::warn!(
                             provider = provider.model_name(),
-                            error = %err_str,
+                            error = %e,
                             retry,
                             "LLM request failed"
                         );
 
                         // Only retry on retryable errors (rate limit, server error)
-                        if is_retryable(&err_str) {
-                            last_error = Some(e);
-                            continue;
-                        } else {
+                        let (retryable, retry_after) = retry_hint(&e);
+                        last_error = Some(e);
+                        if !retryable {
                             // Non-retryable error, try next provider
-                            self.track_failure(provider.model_name()).await;
-                            last_error = Some(e);
                             break;
                         }
+
+                        let delay = retry_after.unwrap_or_else(|| {
+                            Duration::from_millis(BASE_BACKOFF_MS * 2u64.pow(retry as u32 + 1))
+                        });
+                        if delay > MAX_RETRY_AFTER {
+                            tracing::warn!(
+                                provider = provider.model_name(),
+                                retry_after_secs = delay.as_secs(),
+                                "Provider asked for a long wait, failing over"
+                            );
+                            break;
+                        }
+                        backoff = Some(delay);
                     }
                 }
             }
 
             // Exhausted retries for this provider
-            self.track_failure(provider.model_name()).await;
+            self.tracker.track_failure(provider.model_name());
         }
 
         Err(last_error.unwrap_or_else(|| anyhow!("All LLM providers failed")))
@@ -152,39 +253,65 @@ impl LLMProvider for ProviderChain {
         tools: &[ToolSchema],
         config: &GenerateConfig,
     ) -> Result<tokio::sync::mpsc::Receiver<StreamChunk>> {
-        let available = self.available_providers().await;
+        let available = self.tracker.available(&self.providers);
 
         if available.is_empty() {
             return Err(anyhow!("All LLM providers have exceeded failure threshold"));
         }
 
-        // Try each available provider (no retry for streaming - reconnect is complex)
-        let mut last_error = None;
-        for provider in &available {
-            match provider.generate_stream(messages, tools, config).await {
-                Ok(rx) => {
-                    self.reset_failures(provider.model_name()).await;
-                    return Ok(rx);
-                }
-                Err(e) => {
-                    tracing::warn!(
-                        provider = provider.model_name(),
-                        error = %e,
-                        "Streaming request failed, trying next provider"
-                    );
-                    self.track_failure(provider.model_name()).await;
-                    last_error = Some(e);
-                }
-            }
+        // No retry for streaming: open on the first provider that accepts the
+        // request, then resume on the next one if the stream dies partway
+        let (index, first) =
+            open_stream(&self.tracker, &available, 0, messages, tools, config).await?;
+
+        let (tx, rx) = tokio::sync::mpsc::channel(32);
+        tokio::spawn(splice_streams(
+            self.tracker.clone(),
+            available,
+            index,
+            first,
+            messages.to_vec(),
+            tools.to_vec(),
+            config.clone(),
+            tx,
+        ));
+        Ok(rx)
+    }
+
+    /// Healthy if at least one provider in the chain passes its check
+    async fn health_check(&self) -> Result<()> {
+        let report = self.health_report().await;
+        if report.iter().any(|h| h.healthy) {
+            return Ok(());
         }
+        let errors: Vec<String> = report
+            .iter()
+            .map(|h| {
+                format!(
+                    "{}: {}",
+                    h.model,
+                    h.error.as_deref().unwrap_or("unknown error")
+                )
+            })
+            .collect();
+        Err(anyhow!(
+            "All LLM providers failed health check: {}",
+            errors.join("; ")
+        ))
+    }
 
-        Err(last_error.unwrap_or_else(|| anyhow!("All LLM providers failed for streaming")))
+    async fn health_report(&self) -> Vec<ProviderHealth> {
+        futures::future::join_all(self.providers.iter().map(|p| probe_health(p.as_ref()))).await
     }
 
     fn supports_vision(&self) -> bool {
         self.providers.iter().any(|p| p.supports_vision())
     }
 
+    fn supports_audio(&self) -> bool {
+        self.providers.iter().any(|p| p.supports_audio())
+    }
+
     fn model_name(&self) -> &str {
         self.providers
             .first()
@@ -193,20 +320,139 @@ impl LLMProvider for ProviderChain {
     }
 }
 
-/// Parse Retry-After delay from error message
-fn parse_retry_delay(error: &str) -> Duration {
-    // Check for "retry-after: N" in error text
-    if let Some(idx) = error.to_lowercase().find("retry-after") {
-        let rest = &error[idx..];
-        if let Some(secs) = rest.split_whitespace().find_map(|s| {
-            s.trim_matches(|c: char| !c.is_ascii_digit())
-                .parse::<u64>()
-                .ok()
-        }) {
-            return Duration::from_secs(secs.min(300)); // Cap at 5 min
+/// Open a stream on the first provider from `start` that accepts the request
+async fn open_stream(
+    tracker: &FailureTracker,
+    providers: &[Arc<dyn LLMProvider>],
+    start: usize,
+    messages: &[Message],
+    tools: &[ToolSchema],
+    config: &GenerateConfig,
+) -> Result<(usize, tokio::sync::mpsc::Receiver<StreamChunk>)> {
+    let mut last_error = None;
+    for (index, provider) in providers.iter().enumerate().skip(start) {
+        match provider.generate_stream(messages, tools, config).await {
+            Ok(rx) => return Ok((index, rx)),
+            Err(e) => {
+                tracing::warn!(
+                    provider = provider.model_name(),
+                    error = %e,
+                    "Streaming request failed, trying next provider"
+                );
+                tracker.track_failure(provider.model_name());
+                last_error = Some(e);
+            }
+        }
+    }
+
+    Err(last_error.unwrap_or_else(|| anyhow!("All LLM providers failed for streaming")))
+}
+
+/// Instruction appended after the partial answer when resuming on another provider
+const CONTINUE_PROMPT: &str =
+    "Continue your previous response from exactly where it stopped, without repeating any of it.";
+
+/// Forward chunks to the consumer. If a stream ends without `Done`, re-issue the
+/// request to the next provider with the text received so far as an assistant
+/// turn, and keep forwarding from there. A stream cut off inside a tool call
+/// cannot be resumed and ends with `StreamChunk::Error`.
+#[allow(clippy::too_many_arguments)]
+async fn splice_streams(
+    tracker: FailureTracker,
+    providers: Vec<Arc<dyn LLMProvider>>,
+    mut index: usize,
+    mut rx: tokio::sync::mpsc::Receiver<StreamChunk>,
+    messages: Vec<Message>,
+    tools: Vec<ToolSchema>,
+    config: GenerateConfig,
+    tx: tokio::sync::mpsc::Sender<StreamChunk>,
+) {
+    let mut partial_text = String::new();
+    let mut tool_started = false;
+
+    loop {
+        let provider = providers[index].model_name().to_string();
+        let error = loop {
+            match rx.recv().await {
+                Some(StreamChunk::Done { stop_reason, usage }) => {
+                    tracker.reset(&provider);
+                    let _ = tx.send(StreamChunk::Done { stop_reason, usage }).await;
+                    return;
+                }
+                Some(StreamChunk::Error(msg)) => break msg,
+                Some(chunk) => {
+                    match &chunk {
+                        StreamChunk::TextDelta(text) => partial_text.push_str(text),
+                        StreamChunk::ToolCallStart { .. } | StreamChunk::ToolCallDelta { .. } => {
+                            tool_started = true
+                        }
+                        _ => {}
+                    }
+                    if tx.send(chunk).await.is_err() {
+                        // Consumer went away; nothing left to deliver
+                        return;
+                    }
+                }
+                None => break "stream closed without completion".to_string(),
+            }
+        };
+
+        tracing::warn!(
+            provider = %provider,
+            error = %error,
+            received_chars = partial_text.len(),
+            "LLM stream ended prematurely"
+        );
+        tracker.track_failure(&provider);
+
+        if tool_started {
+            let _ = tx
+                .send(StreamChunk::Error(format!(
+                    "{} stream interrupted during a tool call: {}",
+                    provider, error
+                )))
+                .await;
+            return;
+        }
+
+        let mut resumed = messages.clone();
+        if !partial_text.is_empty() {
+            resumed.push(Message::assistant(Content::Text {
+                text: partial_text.clone(),
+            }));
+            resumed.push(Message::user(CONTINUE_PROMPT));
+        }
+
+        match open_stream(&tracker, &providers, index + 1, &resumed, &tools, &config).await {
+            Ok((next, next_rx)) => {
+                tracing::info!(
+                    from = %provider,
+                    to = providers[next].model_name(),
+                    "Resuming stream on next provider"
+                );
+                index = next;
+                rx = next_rx;
+            }
+            Err(e) => {
+                let _ = tx
+                    .send(StreamChunk::Error(format!(
+                        "{} stream interrupted and no provider could resume it: {}",
+                        provider, e
+                    )))
+                    .await;
+                return;
+            }
         }
     }
-    Duration::from_millis(BASE_BACKOFF_MS) // Fallback
+}
+
+/// Retryability and server-requested delay for a failed request
+fn retry_hint(error: &anyhow::Error) -> (bool, Option<Duration>) {
+    match error.downcast_ref::<ProviderError>() {
+        Some(e) => (e.is_retryable(), e.retry_after),
+        // Errors without HTTP details (transport, plugin providers) fall back to the message
+        None => (is_retryable(&error.to_string()), None),
+    }
 }
 
 /// Check if error message indicates a retryable condition
@@ -327,6 +573,295 @@ mod tests {
         assert!(result.is_err());
     }
 
+    /// Provider whose failure mode can be flipped mid-test
+    struct FlakyProvider {
+        name: String,
+        failing: std::sync::atomic::AtomicBool,
+        calls: std::sync::atomic::AtomicUsize,
+    }
+
+    impl FlakyProvider {
+        fn new(name: &str) -> Arc<Self> {
+            Arc::new(Self {
+                name: name.into(),
+                failing: true.into(),
+                calls: 0.into(),
+            })
+        }
+
+        fn calls(&self) -> usize {
+            self.calls.load(std::sync::atomic::Ordering::SeqCst)
+        }
+    }
+
+    #[async_trait]
+    impl LLMProvider for FlakyProvider {
+        async fn generate(
+            &self,
+            _messages: &[Message],
+            _tools: &[ToolSchema],
+            _config: &GenerateConfig,
+        ) -> Result<GenerateResponse> {
+            self.calls.fetch_add(1, std::sync::atomic::Ordering::SeqCst);
+            if self.failing.load(std::sync::atomic::Ordering::SeqCst) {
+                return Err(anyhow!("API error (401): unauthorized"));
+            }
+            Ok(GenerateResponse {
+                content: Content::Text {
+                    text: format!("Response from {}", self.name),
+                },
+                stop_reason: StopReason::EndTurn,
+                usage: Usage::default(),
+                model: self.name.clone(),
+            })
+        }
+
+        fn supports_vision(&self) -> bool {
+            false
+        }
+
+        fn model_name(&self) -> &str {
+            &self.name
+        }
+    }
+
+    async fn ask(chain: &ProviderChain) -> String {
+        chain
+            .generate(&[Message::user("Hi")], &[], &GenerateConfig::default())
+            .await
+            .unwrap()
+            .content
+            .extract_text()
+    }
+
+    #[tokio::test]
+    async fn test_excluded_provider_rejoins_after_half_open_probe() {
+        let flaky = FlakyProvider::new("flaky");
+        let chain = ProviderChain::new(vec![
+            flaky.clone(),
+            Arc::new(MockProvider {
+                name: "backup".into(),
+                should_fail: false,
+                retryable: false,
+            }),
+        ])
+        .with_max_failures(1)
+        .with_half_open_after(Duration::from_millis(50));
+
+        assert_eq!(ask(&chain).await, "Response from backup");
+        let calls = flaky.calls();
+
+        // Excluded during the cooldown
+        assert_eq!(ask(&chain).await, "Response from backup");
+        assert_eq!(flaky.calls(), calls);
+
+        // After the cooldown a probe goes through and the provider rejoins
+        flaky
+            .failing
+            .store(false, std::sync::atomic::Ordering::SeqCst);
+        tokio::time::sleep(Duration::from_millis(60)).await;
+        assert_eq!(ask(&chain).await, "Response from flaky");
+        assert_eq!(chain.tracker.failure_count("flaky"), 0);
+        assert_eq!(ask(&chain).await, "Response from flaky");
+    }
+
+    #[tokio::test]
+    async fn test_failed_probe_restarts_cooldown() {
+        let flaky = FlakyProvider::new("flaky");
+        let chain = ProviderChain::new(vec![
+            flaky.clone(),
+            Arc::new(MockProvider {
+                name: "backup".into(),
+                should_fail: false,
+                retryable: false,
+            }),
+        ])
+        .with_max_failures(1)
+        .with_half_open_after(Duration::from_millis(50));
+
+        ask(&chain).await;
+        tokio::time::sleep(Duration::from_millis(60)).await;
+        let calls = flaky.calls();
+        ask(&chain).await; // probe fails
+        assert!(flaky.calls() > calls);
+
+        let calls = flaky.calls();
+        ask(&chain).await;
+        assert_eq!(
+            flaky.calls(),
+            calls,
+            "cooldown should restart after failed probe"
+        );
+    }
+
+    #[test]
+    fn test_failures_decay_over_time() {
+        let chain = ProviderChain::new(vec![]).with_failure_decay(Duration::from_secs(60));
+        let tracker = &chain.tracker;
+        let start = Instant::now();
+        let mut state = ProviderState {
+            failures: 3,
+            last_failure: Some(start),
+            probe_started: None,
+        };
+
+        tracker.decay(&mut state, start + Duration::from_secs(59));
+        assert_eq!(state.failures, 3);
+
+        tracker.decay(&mut state, start + Duration::from_secs(61));
+        assert_eq!(state.failures, 2);
+
+        // Partial interval carries over: 61s + 59s = two full intervals
+        tracker.decay(&mut state, start + Duration::from_secs(120));
+        assert_eq!(state.failures, 1);
+
+        tracker.decay(&mut state, start + Duration::from_secs(600));
+        assert_eq!(state.failures, 0);
+    }
+
+    /// Fails with a structured 429 carrying `retry_after` until `failures` runs out
+    struct RateLimitedProvider {
+        failures: std::sync::atomic::AtomicUsize,
+        retry_after: Duration,
+    }
+
+    #[async_trait]
+    impl LLMProvider for RateLimitedProvider {
+        async fn generate(
+            &self,
+            _messages: &[Message],
+            _tools: &[ToolSchema],
+            _config: &GenerateConfig,
+        ) -> Result<GenerateResponse> {
+            let remaining = self.failures.load(std::sync::atomic::Ordering::SeqCst);
+            if remaining > 0 {
+                self.failures
+                    .store(remaining - 1, std::sync::atomic::Ordering::SeqCst);
+                return Err(ProviderError {
+                    provider: "Test",
+                    status: reqwest::StatusCode::TOO_MANY_REQUESTS,
+                    retry_after: Some(self.retry_after),
+                    body: "rate limited".into(),
+                }
+                .into());
+            }
+            Ok(GenerateResponse {
+                content: Content::Text {
+                    text: "Response from limited".into(),
+                },
+                stop_reason: StopReason::EndTurn,
+                usage: Usage::default(),
+                model: "limited".into(),
+            })
+        }
+
+        fn supports_vision(&self) -> bool {
+            false
+        }
+
+        fn model_name(&self) -> &str {
+            "limited"
+        }
+    }
+
+    #[tokio::test]
+    async fn test_retry_uses_retry_after_header_value() {
+        let chain = ProviderChain::new(vec![Arc::new(RateLimitedProvider {
+            failures: 1.into(),
+            retry_after: Duration::from_millis(20),
+        })]);
+
+        let started = Instant::now();
+        assert_eq!(ask(&chain).await, "Response from limited");
+        let elapsed = started.elapsed();
+        // Exponential backoff would have slept a full second
+        assert!(elapsed >= Duration::from_millis(20) && elapsed < Duration::from_millis(900));
+    }
+
+    #[tokio::test]
+    async fn test_long_retry_after_fails_over_immediately() {
+        let chain = ProviderChain::new(vec![
+            Arc::new(RateLimitedProvider {
+                failures: 1.into(),
+                retry_after: Duration::from_secs(120),
+            }),
+            Arc::new(MockProvider {
+                name: "backup".into(),
+                should_fail: false,
+                retryable: false,
+            }),
+        ]);
+
+        let started = Instant::now();
+        assert_eq!(ask(&chain).await, "Response from backup");
+        assert!(started.elapsed() < Duration::from_secs(1));
+    }
+
+    #[test]
+    fn test_retry_hint_prefers_structured_error() {
+        let structured: anyhow::Error = ProviderError {
+            provider: "Test",
+            status: reqwest::StatusCode::SERVICE_UNAVAILABLE,
+            retry_after: Some(Duration::from_secs(3)),
+            body: String::new(),
+        }
+        .into();
+        assert_eq!(
+            retry_hint(&structured),
+            (true, Some(Duration::from_secs(3)))
+        );
+
+        let unauthorized: anyhow::Error = ProviderError {
+            provider: "Test",
+            status: reqwest::StatusCode::UNAUTHORIZED,
+            retry_after: None,
+            // A 5xx-looking body must not make a 401 retryable
+            body: "upstream 503".into(),
+        }
+        .into();
+        assert_eq!(retry_hint(&unauthorized), (false, None));
+
+        assert_eq!(retry_hint(&anyhow!("overloaded")), (true, None));
+    }
+
+    #[tokio::test]
+    async fn test_health_report_covers_each_provider() {
+        let chain = ProviderChain::new(vec![
+            Arc::new(MockProvider {
+                name: "primary".into(),
+                should_fail: true,
+                retryable: false,
+            }),
+            Arc::new(MockProvider {
+                name: "fallback".into(),
+                should_fail: false,
+                retryable: false,
+            }),
+        ]);
+
+        let report = chain.health_report().await;
+        assert_eq!(report.len(), 2);
+        assert_eq!(report[0].model, "primary");
+        assert!(!report[0].healthy);
+        assert!(report[0].error.as_deref().unwrap().contains("401"));
+        assert!(report[1].healthy);
+
+        // One healthy provider is enough for the chain
+        assert!(chain.health_check().await.is_ok());
+    }
+
+    #[tokio::test]
+    async fn test_health_check_fails_when_all_unhealthy() {
+        let chain = ProviderChain::new(vec![Arc::new(MockProvider {
+            name: "only".into(),
+            should_fail: true,
+            retryable: false,
+        })]);
+
+        let err = chain.health_check().await.unwrap_err().to_string();
+        assert!(err.contains("only: API error (401)"));
+    }
+
     #[tokio::test]
     async fn test_stream_failover() {
         let chain = ProviderChain::new(vec![Arc::new(MockProvider {
@@ -351,4 +886,144 @@ mod tests {
         assert!(matches!(&chunks[0], StreamChunk::TextDelta(_)));
         assert!(matches!(chunks.last().unwrap(), StreamChunk::Done { .. }));
     }
+
+    /// Streams `chunks` in order, then closes; records the messages it was sent
+    struct ScriptedStreamProvider {
+        name: String,
+        chunks: Vec<StreamChunk>,
+        seen: Mutex<Vec<Vec<Message>>>,
+    }
+
+    impl ScriptedStreamProvider {
+        fn new(name: &str, chunks: Vec<StreamChunk>) -> Arc<Self> {
+            Arc::new(Self {
+                name: name.into(),
+                chunks,
+                seen: Mutex::new(Vec::new()),
+            })
+        }
+    }
+
+    #[async_trait]
+    impl LLMProvider for ScriptedStreamProvider {
+        async fn generate(
+            &self,
+            _messages: &[Message],
+            _tools: &[ToolSchema],
+            _config: &GenerateConfig,
+        ) -> Result<GenerateResponse> {
+            Err(anyhow!("streaming only"))
+        }
+
+        async fn generate_stream(
+            &self,
+            messages: &[Message],
+            _tools: &[ToolSchema],
+            _config: &GenerateConfig,
+        ) -> Result<tokio::sync::mpsc::Receiver<StreamChunk>> {
+            self.seen.lock().unwrap().push(messages.to_vec());
+            let (tx, rx) = tokio::sync::mpsc::channel(self.chunks.len().max(1));
+            for chunk in &self.chunks {
+                tx.send(chunk.clone()).await.unwrap();
+            }
+            Ok(rx)
+        }
+
+        fn supports_vision(&self) -> bool {
+            false
+        }
+
+        fn model_name(&self) -> &str {
+            &self.name
+        }
+    }
+
+    fn done() -> StreamChunk {
+        StreamChunk::Done {
+            stop_reason: StopReason::EndTurn,
+            usage: Usage::default(),
+        }
+    }
+
+    async fn collect_stream(chain: &ProviderChain) -> Vec<StreamChunk> {
+        let mut rx = chain
+            .generate_stream(&[Message::user("Hi")], &[], &GenerateConfig::default())
+            .await
+            .unwrap();
+        let mut chunks = Vec::new();
+        while let Some(chunk) = rx.recv().await {
+            chunks.push(chunk);
+        }
+        chunks
+    }
+
+    #[tokio::test]
+    async fn test_interrupted_stream_resumes_on_next_provider() {
+        let primary = ScriptedStreamProvider::new(
+            "primary",
+            vec![
+                StreamChunk::TextDelta("Hello, ".into()),
+                StreamChunk::Error("SSE read error: connection reset".into()),
+            ],
+        );
+        let backup = ScriptedStreamProvider::new(
+            "backup",
+            vec![StreamChunk::TextDelta("world".into()), done()],
+        );
+        let chain = ProviderChain::new(vec![primary.clone(), backup.clone()]);
+
+        let chunks = collect_stream(&chain).await;
+        let text: String = chunks
+            .iter()
+            .filter_map(|c| match c {
+                StreamChunk::TextDelta(t) => Some(t.as_str()),
+                _ => None,
+            })
+            .collect();
+        assert_eq!(text, "Hello, world");
+        assert_eq!(chunks.len(), 3);
+        assert!(matches!(chunks.last().unwrap(), StreamChunk::Done { .. }));
+
+        // Backup saw the partial answer and a request to continue it
+        let seen = backup.seen.lock().unwrap();
+        let resumed = &seen[0];
+        assert_eq!(resumed.len(), 3);
+        assert!(matches!(resumed[1].role, Role::Assistant));
+        assert_eq!(resumed[1].content.extract_text(), "Hello, ");
+        assert_eq!(resumed[2].content.extract_text(), CONTINUE_PROMPT);
+        assert_eq!(chain.tracker.failure_count("primary"), 1);
+    }
+
+    #[tokio::test]
+    async fn test_stream_closed_without_done_is_resumed() {
+        // Dropped sender with nothing received: resume with the original request
+        let primary = ScriptedStreamProvider::new("primary", vec![]);
+        let backup = ScriptedStreamProvider::new("backup", vec![done()]);
+        let chain = ProviderChain::new(vec![primary, backup.clone()]);
+
+        let chunks = collect_stream(&chain).await;
+        assert_eq!(chunks.len(), 1);
+        assert!(matches!(chunks[0], StreamChunk::Done { .. }));
+        assert_eq!(backup.seen.lock().unwrap()[0].len(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_stream_interrupted_in_tool_call_is_not_resumed() {
+        let primary = ScriptedStreamProvider::new(
+            "primary",
+            vec![
+                StreamChunk::ToolCallStart {
+                    id: "call_1".into(),
+                    name: "shell".into(),
+                },
+                StreamChunk::Error("SSE read error: timeout".into()),
+            ],
+        );
+        let backup = ScriptedStreamProvider::new("backup", vec![done()]);
+        let chain = ProviderChain::new(vec![primary, backup.clone()]);
+
+        let chunks = collect_stream(&chain).await;
+        assert!(matches!(chunks.last().unwrap(), StreamChunk::Error(_)));
+        assert!(backup.seen.lock().unwrap().is_empty());
+    }
 }
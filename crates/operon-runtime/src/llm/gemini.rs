@@ -3,24 +3,54 @@ use async_trait::async_trait;
 use reqwest::{Client, ClientBuilder, Response};
 use serde::Deserialize;
 use serde_json::{json, Value};
-use std::sync::atomic::{AtomicU64, Ordering};
 use std::time::Duration;
 use tracing::{debug, info};
 
+use super::error::ProviderError;
 use super::provider::LLMProvider;
-use super::streaming::{drive_sse_stream, parse_gemini_sse};
+use super::streaming::{drive_sse_stream, StreamAssembler};
 use super::types::*;
 
 const GEMINI_BASE_URL: &str = "https://generativelanguage.googleapis.com/v1beta";
 const DEFAULT_MODEL: &str = "gemini-2.0-flash";
 
-/// Global atomic counter for unique Gemini tool call IDs
-static CALL_COUNTER: AtomicU64 = AtomicU64::new(0);
-
-/// Generate a unique tool call ID for Gemini responses
+/// Generate a tool call ID for Gemini responses, which carry none; random
+/// so IDs stay unique across restarts (agents key idempotent calls on them)
 pub(crate) fn next_call_id(name: &str) -> String {
-    let n = CALL_COUNTER.fetch_add(1, Ordering::Relaxed);
-    format!("gemini_{}_{}", name, n)
+    format!("gemini_{}_{}", name, uuid::Uuid::new_v4().simple())
+}
+
+/// One `parts[]` entry in Gemini form (None for nested Mixed)
+fn part_to_api(content: &Content) -> Option<Value> {
+    let part = match content {
+        Content::Text { text } => json!({"text": text}),
+        Content::ToolCall(tc) => json!({
+            "functionCall": {
+                "name": tc.name,
+                "args": tc.input,
+            }
+        }),
+        Content::ToolResult(tr) => json!({
+            "functionResponse": {
+                "name": tr.name,
+                "response": {"result": tr.output}
+            }
+        }),
+        Content::Image { data, mime } | Content::Audio { data, mime } => {
+            use base64::Engine;
+            let encoded = base64::engine::general_purpose::STANDARD.encode(data);
+            json!({
+                "inlineData": {
+                    "mimeType": mime,
+                    "data": encoded,
+                }
+            })
+        }
+        // Gemini rejects messages without parts
+        Content::Thinking { .. } => json!({"text": ""}),
+        Content::Mixed { .. } => return None,
+    };
+    Some(part)
 }
 
 /// Google Gemini API client
@@ -29,6 +59,9 @@ pub struct GeminiClient {
     api_key: String,
     model: String,
     base_url: Option<String>,
+    /// `safetySettings` entries as (category, threshold), e.g.
+    /// ("HARM_CATEGORY_DANGEROUS_CONTENT", "BLOCK_ONLY_HIGH")
+    safety_settings: Vec<(String, String)>,
 }
 
 impl GeminiClient {
@@ -43,6 +76,7 @@ impl GeminiClient {
             api_key: api_key.to_string(),
             model: DEFAULT_MODEL.to_string(),
             base_url: None,
+            safety_settings: Vec::new(),
         }
     }
 
@@ -56,6 +90,13 @@ impl GeminiClient {
         self
     }
 
+    /// Override the blocking threshold for one harm category
+    pub fn with_safety_setting(mut self, category: &str, threshold: &str) -> Self {
+        self.safety_settings
+            .push((category.to_string(), threshold.to_string()));
+        self
+    }
+
     /// Redact API key from error body to prevent leaking in logs
     fn redact_key(body: &str, key: &str) -> String {
         if key.len() > 4 {
@@ -69,10 +110,7 @@ impl GeminiClient {
     /// NOTE: Gemini API requires the key as a query parameter (Google's design).
     /// Do not log URLs containing the API key.
     fn api_url(&self, stream: bool) -> String {
-        let base = self
-            .base_url
-            .as_deref()
-            .unwrap_or(GEMINI_BASE_URL);
+        let base = self.base_url.as_deref().unwrap_or(GEMINI_BASE_URL);
         if stream {
             format!(
                 "{}/models/{}:streamGenerateContent?alt=sse&key={}",
@@ -106,6 +144,25 @@ impl GeminiClient {
             "temperature": config.temperature,
             "maxOutputTokens": config.max_tokens,
         });
+        let generation = &mut body["generationConfig"];
+        if !config.stop_sequences.is_empty() {
+            generation["stopSequences"] = json!(config.stop_sequences);
+        }
+        if let Some(top_p) = config.top_p {
+            generation["topP"] = json!(top_p);
+        }
+        if let Some(top_k) = config.top_k {
+            generation["topK"] = json!(top_k);
+        }
+        if let Some(penalty) = config.frequency_penalty {
+            generation["frequencyPenalty"] = json!(penalty);
+        }
+        if let Some(penalty) = config.presence_penalty {
+            generation["presencePenalty"] = json!(penalty);
+        }
+        if let Some(seed) = config.seed {
+            generation["seed"] = json!(seed);
+        }
 
         // System instruction (Gemini uses systemInstruction field)
         if let Some(ref sys) = config.system_prompt {
@@ -120,6 +177,25 @@ impl GeminiClient {
             body["tools"] = json!([{
                 "functionDeclarations": declarations
             }]);
+            let mode = match config.tool_choice {
+                ToolChoice::Auto => None,
+                ToolChoice::Any => Some("ANY"),
+                ToolChoice::None => Some("NONE"),
+            };
+            if let Some(mode) = mode {
+                body["toolConfig"] = json!({
+                    "functionCallingConfig": {"mode": mode}
+                });
+            }
+        }
+
+        if !self.safety_settings.is_empty() {
+            let settings: Vec<Value> = self
+                .safety_settings
+                .iter()
+                .map(|(category, threshold)| json!({"category": category, "threshold": threshold}))
+                .collect();
+            body["safetySettings"] = json!(settings);
         }
 
         body
@@ -132,56 +208,14 @@ impl GeminiClient {
             Role::System => "user",
         };
 
-        let parts = match &msg.content {
-            Content::Text { text } => json!([{"text": text}]),
-            Content::ToolCall(tc) => {
-                json!([{
-                    "functionCall": {
-                        "name": tc.name,
-                        "args": tc.input,
-                    }
-                }])
-            }
-            Content::ToolResult(tr) => {
-                json!([{
-                    "functionResponse": {
-                        "name": tr.name,
-                        "response": {"result": tr.output}
-                    }
-                }])
-            }
-            Content::Mixed { parts } => {
-                let api_parts: Vec<Value> = parts
-                    .iter()
-                    .map(|p| match p {
-                        Content::Text { text } => json!({"text": text}),
-                        Content::ToolCall(tc) => json!({
-                            "functionCall": {
-                                "name": tc.name,
-                                "args": tc.input,
-                            }
-                        }),
-                        Content::ToolResult(tr) => json!({
-                            "functionResponse": {
-                                "name": tr.tool_use_id,
-                                "response": {"result": tr.output}
-                            }
-                        }),
-                        _ => json!({"text": ""}),
-                    })
-                    .collect();
-                json!(api_parts)
-            }
-            Content::Image { data, mime } => {
-                use base64::Engine;
-                let encoded = base64::engine::general_purpose::STANDARD.encode(data);
-                json!([{
-                    "inlineData": {
-                        "mimeType": mime,
-                        "data": encoded,
-                    }
-                }])
-            }
+        let parts: Vec<Value> = match &msg.content {
+            // Reasoning from other providers is not replayable here
+            Content::Mixed { parts } => parts
+                .iter()
+                .filter(|p| !matches!(p, Content::Thinking { .. }))
+                .filter_map(part_to_api)
+                .collect(),
+            single => part_to_api(single).into_iter().collect(),
         };
 
         json!({"role": role, "parts": parts})
@@ -189,14 +223,13 @@ impl GeminiClient {
 
     /// Check HTTP response status and return a descriptive error with redacted API key
     async fn check_response(&self, response: Response) -> Result<Response> {
-        let status = response.status();
-        if !status.is_success() {
-            let error_body = response.text().await.unwrap_or_default();
-            return Err(anyhow!(
-                "Gemini API error ({}): {}",
-                status,
-                Self::redact_key(&error_body, &self.api_key)
-            ));
+        if !response.status().is_success() {
+            let mut err = ProviderError::from_response("Gemini", response).await;
+            err.body = Self::redact_key(&err.body, &self.api_key);
+            if err.retry_after.is_none() {
+                err.retry_after = parse_retry_info(&err.body);
+            }
+            return Err(err.into());
         }
         Ok(response)
     }
@@ -267,6 +300,7 @@ impl GeminiClient {
             .map(|u| Usage {
                 input_tokens: u.prompt_token_count.unwrap_or(0),
                 output_tokens: u.candidates_token_count.unwrap_or(0),
+                cost_usd: 0.0,
             })
             .unwrap_or_default();
 
@@ -279,6 +313,20 @@ impl GeminiClient {
     }
 }
 
+/// Gemini reports rate-limit delays in the body (`RetryInfo.retryDelay`, e.g. "30s")
+/// rather than a Retry-After header
+fn parse_retry_info(body: &str) -> Option<Duration> {
+    let value: Value = serde_json::from_str(body).ok()?;
+    value["error"]["details"]
+        .as_array()?
+        .iter()
+        .find_map(|d| d["retryDelay"].as_str())
+        .and_then(|delay| delay.strip_suffix('s'))
+        .and_then(|secs| secs.parse::<f64>().ok())
+        .filter(|secs| *secs >= 0.0)
+        .map(Duration::from_secs_f64)
+}
+
 #[async_trait]
 impl LLMProvider for GeminiClient {
     async fn generate(
@@ -334,17 +382,38 @@ impl LLMProvider for GeminiClient {
         tokio::spawn({
             let byte_stream = response.bytes_stream();
             async move {
-                drive_sse_stream(byte_stream, parse_gemini_sse, tx).await;
+                let mut assembler = StreamAssembler::new();
+                drive_sse_stream(byte_stream, |data| assembler.push_gemini(data), tx).await;
             }
         });
 
         Ok(rx)
     }
 
+    /// Fetches the configured model's metadata; checks both key and model name
+    async fn health_check(&self) -> Result<()> {
+        let base = self.base_url.as_deref().unwrap_or(GEMINI_BASE_URL);
+        let url = format!("{}/models/{}?key={}", base, self.model, self.api_key);
+
+        // Strip the URL from transport errors: it carries the API key
+        let response = self
+            .client
+            .get(&url)
+            .send()
+            .await
+            .map_err(|e| e.without_url())?;
+        self.check_response(response).await?;
+        Ok(())
+    }
+
     fn supports_vision(&self) -> bool {
         true
     }
 
+    fn supports_audio(&self) -> bool {
+        true
+    }
+
     fn model_name(&self) -> &str {
         &self.model
     }
@@ -409,12 +478,38 @@ mod tests {
 
         assert!(body["contents"].is_array());
         assert_eq!(body["contents"][0]["role"], "user");
-        assert_eq!(body["systemInstruction"]["parts"][0]["text"], "You are helpful");
+        assert_eq!(
+            body["systemInstruction"]["parts"][0]["text"],
+            "You are helpful"
+        );
         // f32 -> f64 precision: 0.7f32 becomes ~0.6999999...
         let temp = body["generationConfig"]["temperature"].as_f64().unwrap();
         assert!((temp - 0.7).abs() < 0.001);
     }
 
+    #[test]
+    fn test_mixed_text_and_image_message() {
+        let client = GeminiClient::new("test-key");
+        let msg = Message::user_with_image("What is this?", vec![1, 2, 3], "image/png");
+
+        let api_msg = client.message_to_api(&msg);
+        let parts = api_msg["parts"].as_array().unwrap();
+        assert_eq!(parts.len(), 2);
+        assert_eq!(parts[0]["text"], "What is this?");
+        assert_eq!(parts[1]["inlineData"]["mimeType"], "image/png");
+        assert_eq!(parts[1]["inlineData"]["data"], "AQID");
+    }
+
+    #[test]
+    fn test_audio_message() {
+        let client = GeminiClient::new("test-key");
+        let msg = Message::user_with_audio("Transcribe", vec![1, 2, 3], "audio/wav");
+
+        let api_msg = client.message_to_api(&msg);
+        assert_eq!(api_msg["parts"][1]["inlineData"]["mimeType"], "audio/wav");
+        assert_eq!(api_msg["parts"][1]["inlineData"]["data"], "AQID");
+    }
+
     #[test]
     fn test_build_request_body_with_tools() {
         let client = GeminiClient::new("test-key");
@@ -431,6 +526,68 @@ mod tests {
         let declarations = &body["tools"][0]["functionDeclarations"];
         assert!(declarations.is_array());
         assert_eq!(declarations[0]["name"], "shell");
+        assert!(body.get("toolConfig").is_none());
+        assert!(body.get("safetySettings").is_none());
+    }
+
+    #[test]
+    fn test_build_request_body_stop_sequences() {
+        let client = GeminiClient::new("test-key");
+        let config = GenerateConfig {
+            stop_sequences: vec!["</plan>".into()],
+            ..Default::default()
+        };
+
+        let body = client.build_request_body(&[Message::user("List")], &[], &config);
+        assert_eq!(
+            body["generationConfig"]["stopSequences"],
+            json!(["</plan>"])
+        );
+    }
+
+    #[test]
+    fn test_build_request_body_sampling() {
+        let client = GeminiClient::new("test-key");
+        let config = GenerateConfig {
+            top_k: Some(40),
+            presence_penalty: Some(0.5),
+            seed: Some(7),
+            ..Default::default()
+        };
+
+        let body = client.build_request_body(&[Message::user("Hi")], &[], &config);
+        let generation = &body["generationConfig"];
+        assert_eq!(generation["topK"], 40);
+        assert_eq!(generation["presencePenalty"], 0.5);
+        assert_eq!(generation["seed"], 7);
+        assert!(generation.get("topP").is_none());
+    }
+
+    #[test]
+    fn test_build_request_body_tool_config_and_safety() {
+        let client = GeminiClient::new("test-key")
+            .with_safety_setting("HARM_CATEGORY_DANGEROUS_CONTENT", "BLOCK_ONLY_HIGH");
+        let tools = vec![ToolSchema {
+            name: "shell".into(),
+            description: "Execute shell command".into(),
+            input_schema: json!({"type": "object"}),
+        }];
+        let config = GenerateConfig {
+            tool_choice: ToolChoice::Any,
+            ..Default::default()
+        };
+
+        let body = client.build_request_body(&[Message::user("Run date")], &tools, &config);
+        assert_eq!(body["toolConfig"]["functionCallingConfig"]["mode"], "ANY");
+        assert_eq!(
+            body["safetySettings"][0]["category"],
+            "HARM_CATEGORY_DANGEROUS_CONTENT"
+        );
+        assert_eq!(body["safetySettings"][0]["threshold"], "BLOCK_ONLY_HIGH");
+
+        // Without tools there is nothing to configure
+        let body = client.build_request_body(&[Message::user("Hi")], &[], &config);
+        assert!(body.get("toolConfig").is_none());
     }
 
     #[test]
@@ -514,4 +671,15 @@ mod tests {
         assert_eq!(resp.content.extract_text(), "Let me check.");
         assert_eq!(resp.content.extract_tool_calls().len(), 1);
     }
+
+    #[test]
+    fn test_parse_retry_info() {
+        let body = r#"{"error":{"code":429,"details":[
+            {"@type":"type.googleapis.com/google.rpc.QuotaFailure"},
+            {"@type":"type.googleapis.com/google.rpc.RetryInfo","retryDelay":"22.5s"}
+        ]}}"#;
+        assert_eq!(parse_retry_info(body), Some(Duration::from_millis(22500)));
+        assert_eq!(parse_retry_info("not json"), None);
+        assert_eq!(parse_retry_info(r#"{"error":{"code":500}}"#), None);
+    }
 }
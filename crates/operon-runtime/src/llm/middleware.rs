@@ -0,0 +1,425 @@
+//! Provider middleware: request/response handling stacked around any
+//! `LLMProvider` (logging, caching, prompt rewriting, cost accounting).
+
+use std::sync::Arc;
+
+use anyhow::Result;
+use async_trait::async_trait;
+use tokio::sync::mpsc::Receiver;
+
+use super::provider::{response_to_stream, LLMProvider};
+use super::streaming::StreamAssembler;
+use super::types::*;
+use super::ProviderHealth;
+
+/// A generation request as seen (and rewritable) by middleware
+#[derive(Debug, Clone)]
+pub struct LlmRequest {
+    pub messages: Vec<Message>,
+    pub tools: Vec<ToolSchema>,
+    pub config: GenerateConfig,
+}
+
+/// One layer around a provider. Layers run outermost first on the way in
+/// and innermost first on the way out.
+#[async_trait]
+pub trait ProviderMiddleware: Send + Sync {
+    /// Layer name for logging and error messages
+    fn name(&self) -> &str;
+
+    /// Inspect or rewrite the request. Returning a response skips the provider
+    /// and all inner layers (e.g. a cache hit); outer layers still see it.
+    async fn before_request(&self, _request: &mut LlmRequest) -> Result<Option<GenerateResponse>> {
+        Ok(None)
+    }
+
+    /// Inspect or rewrite the response. For streams the response is assembled
+    /// from the chunks, so changes to it are not delivered.
+    async fn after_response(
+        &self,
+        _request: &LlmRequest,
+        _response: &mut GenerateResponse,
+    ) -> Result<()> {
+        Ok(())
+    }
+}
+
+/// Provider wrapped in a stack of middleware
+pub struct MiddlewareProvider {
+    inner: Arc<dyn LLMProvider>,
+    middleware: Vec<Arc<dyn ProviderMiddleware>>,
+}
+
+impl MiddlewareProvider {
+    pub fn new(inner: Arc<dyn LLMProvider>) -> Self {
+        Self {
+            inner,
+            middleware: Vec::new(),
+        }
+    }
+
+    /// Add a layer inside the ones already added
+    pub fn with_middleware(mut self, middleware: Arc<dyn ProviderMiddleware>) -> Self {
+        self.middleware.push(middleware);
+        self
+    }
+
+    /// Run `before_request` hooks; on a short-circuit returns how many
+    /// layers ran (including the one that answered) and its response
+    async fn run_before(
+        &self,
+        request: &mut LlmRequest,
+    ) -> Result<Option<(usize, GenerateResponse)>> {
+        for (i, layer) in self.middleware.iter().enumerate() {
+            if let Some(response) = layer.before_request(request).await? {
+                tracing::debug!(layer = layer.name(), "LLM request answered by middleware");
+                return Ok(Some((i + 1, response)));
+            }
+        }
+        Ok(None)
+    }
+}
+
+/// Run `after_response` hooks innermost first
+async fn run_after(
+    layers: &[Arc<dyn ProviderMiddleware>],
+    request: &LlmRequest,
+    response: &mut GenerateResponse,
+) -> Result<()> {
+    for layer in layers.iter().rev() {
+        layer.after_response(request, response).await?;
+    }
+    Ok(())
+}
+
+#[async_trait]
+impl LLMProvider for MiddlewareProvider {
+    async fn generate(
+        &self,
+        messages: &[Message],
+        tools: &[ToolSchema],
+        config: &GenerateConfig,
+    ) -> Result<GenerateResponse> {
+        let mut request = LlmRequest {
+            messages: messages.to_vec(),
+            tools: tools.to_vec(),
+            config: config.clone(),
+        };
+        let (depth, mut response) = match self.run_before(&mut request).await? {
+            Some(answered) => answered,
+            None => {
+                let response = self
+                    .inner
+                    .generate(&request.messages, &request.tools, &request.config)
+                    .await?;
+                (self.middleware.len(), response)
+            }
+        };
+        run_after(&self.middleware[..depth], &request, &mut response).await?;
+        Ok(response)
+    }
+
+    async fn generate_stream(
+        &self,
+        messages: &[Message],
+        tools: &[ToolSchema],
+        config: &GenerateConfig,
+    ) -> Result<Receiver<StreamChunk>> {
+        let mut request = LlmRequest {
+            messages: messages.to_vec(),
+            tools: tools.to_vec(),
+            config: config.clone(),
+        };
+        if let Some((depth, mut response)) = self.run_before(&mut request).await? {
+            run_after(&self.middleware[..depth], &request, &mut response).await?;
+            return Ok(response_to_stream(response));
+        }
+
+        let upstream = self
+            .inner
+            .generate_stream(&request.messages, &request.tools, &request.config)
+            .await?;
+        if self.middleware.is_empty() {
+            return Ok(upstream);
+        }
+
+        let (tx, rx) = tokio::sync::mpsc::channel(32);
+        let layers = self.middleware.clone();
+        let mut upstream = StreamAssembler::wrap(upstream, self.inner.model_name());
+        tokio::spawn(async move {
+            let mut failed = false;
+            // Held back so `after_response` finishes before the caller sees the end
+            let mut done = None;
+            while let Some(chunk) = upstream.recv().await {
+                match chunk {
+                    StreamChunk::Done { .. } => done = Some(chunk),
+                    other => {
+                        failed |= matches!(other, StreamChunk::Error(_));
+                        if tx.send(other).await.is_err() {
+                            return;
+                        }
+                    }
+                }
+            }
+            let Some(done) = done else {
+                return;
+            };
+            if !failed {
+                let mut response = upstream.into_response();
+                if let Err(e) = run_after(&layers, &request, &mut response).await {
+                    tracing::warn!(error = %e, "Provider middleware failed after stream");
+                }
+            }
+            let _ = tx.send(done).await;
+        });
+        Ok(rx)
+    }
+
+    async fn health_check(&self) -> Result<()> {
+        self.inner.health_check().await
+    }
+
+    async fn health_report(&self) -> Vec<ProviderHealth> {
+        self.inner.health_report().await
+    }
+
+    fn supports_vision(&self) -> bool {
+        self.inner.supports_vision()
+    }
+
+    fn supports_audio(&self) -> bool {
+        self.inner.supports_audio()
+    }
+
+    fn model_name(&self) -> &str {
+        self.inner.model_name()
+    }
+}
+
+/// Logs each request and the usage of its response
+pub struct LoggingMiddleware;
+
+#[async_trait]
+impl ProviderMiddleware for LoggingMiddleware {
+    fn name(&self) -> &str {
+        "logging"
+    }
+
+    async fn before_request(&self, request: &mut LlmRequest) -> Result<Option<GenerateResponse>> {
+        tracing::debug!(
+            messages = request.messages.len(),
+            tools = request.tools.len(),
+            task = ?request.config.task,
+            "LLM request"
+        );
+        Ok(None)
+    }
+
+    async fn after_response(
+        &self,
+        _request: &LlmRequest,
+        response: &mut GenerateResponse,
+    ) -> Result<()> {
+        tracing::info!(
+            model = %response.model,
+            input_tokens = response.usage.input_tokens,
+            output_tokens = response.usage.output_tokens,
+            stop_reason = ?response.stop_reason,
+            "LLM response"
+        );
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::sync::Mutex;
+
+    /// Answers with the system prompt it received
+    struct EchoSystem {
+        calls: AtomicUsize,
+    }
+
+    #[async_trait]
+    impl LLMProvider for EchoSystem {
+        async fn generate(
+            &self,
+            _messages: &[Message],
+            _tools: &[ToolSchema],
+            config: &GenerateConfig,
+        ) -> Result<GenerateResponse> {
+            self.calls.fetch_add(1, Ordering::SeqCst);
+            Ok(GenerateResponse {
+                content: Content::Text {
+                    text: config.system_prompt.clone().unwrap_or_default(),
+                },
+                stop_reason: StopReason::EndTurn,
+                usage: Usage {
+                    input_tokens: 10,
+                    output_tokens: 5,
+                    cost_usd: 0.0,
+                },
+                model: "echo".into(),
+            })
+        }
+
+        fn supports_vision(&self) -> bool {
+            false
+        }
+
+        fn model_name(&self) -> &str {
+            "echo"
+        }
+    }
+
+    /// Records hook order; optionally rewrites the prompt or answers from "cache"
+    struct Recorder {
+        name: &'static str,
+        log: Arc<Mutex<Vec<String>>>,
+        prompt: Option<&'static str>,
+        cached: Option<&'static str>,
+    }
+
+    impl Recorder {
+        fn new(name: &'static str, log: &Arc<Mutex<Vec<String>>>) -> Self {
+            Self {
+                name,
+                log: log.clone(),
+                prompt: None,
+                cached: None,
+            }
+        }
+    }
+
+    #[async_trait]
+    impl ProviderMiddleware for Recorder {
+        fn name(&self) -> &str {
+            self.name
+        }
+
+        async fn before_request(
+            &self,
+            request: &mut LlmRequest,
+        ) -> Result<Option<GenerateResponse>> {
+            self.log
+                .lock()
+                .unwrap()
+                .push(format!("{}.before", self.name));
+            if let Some(prompt) = self.prompt {
+                request.config.system_prompt = Some(prompt.into());
+            }
+            Ok(self.cached.map(|text| GenerateResponse {
+                content: Content::Text { text: text.into() },
+                stop_reason: StopReason::EndTurn,
+                usage: Usage::default(),
+                model: "cache".into(),
+            }))
+        }
+
+        async fn after_response(
+            &self,
+            _request: &LlmRequest,
+            response: &mut GenerateResponse,
+        ) -> Result<()> {
+            self.log.lock().unwrap().push(format!(
+                "{}.after:{}",
+                self.name,
+                response.usage.total()
+            ));
+            Ok(())
+        }
+    }
+
+    fn echo() -> Arc<EchoSystem> {
+        Arc::new(EchoSystem {
+            calls: AtomicUsize::new(0),
+        })
+    }
+
+    #[tokio::test]
+    async fn test_layers_run_in_onion_order() {
+        let log = Arc::new(Mutex::new(Vec::new()));
+        let provider = MiddlewareProvider::new(echo())
+            .with_middleware(Arc::new(Recorder::new("outer", &log)))
+            .with_middleware(Arc::new(Recorder {
+                prompt: Some("rewritten"),
+                ..Recorder::new("inner", &log)
+            }));
+
+        let response = provider
+            .generate(&[Message::user("Hi")], &[], &GenerateConfig::default())
+            .await
+            .unwrap();
+        assert_eq!(response.content.extract_text(), "rewritten");
+        assert_eq!(
+            log.lock().unwrap().as_slice(),
+            [
+                "outer.before",
+                "inner.before",
+                "inner.after:15",
+                "outer.after:15"
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_short_circuit_skips_provider_and_inner_layers() {
+        let log = Arc::new(Mutex::new(Vec::new()));
+        let inner = echo();
+        let provider = MiddlewareProvider::new(inner.clone())
+            .with_middleware(Arc::new(Recorder::new("outer", &log)))
+            .with_middleware(Arc::new(Recorder {
+                cached: Some("from cache"),
+                ..Recorder::new("cache", &log)
+            }))
+            .with_middleware(Arc::new(Recorder::new("inner", &log)));
+
+        let response = provider
+            .generate(&[Message::user("Hi")], &[], &GenerateConfig::default())
+            .await
+            .unwrap();
+        assert_eq!(response.content.extract_text(), "from cache");
+        assert_eq!(inner.calls.load(Ordering::SeqCst), 0);
+        assert_eq!(
+            log.lock().unwrap().as_slice(),
+            [
+                "outer.before",
+                "cache.before",
+                "cache.after:0",
+                "outer.after:0"
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_stream_runs_after_hooks_before_done() {
+        let log = Arc::new(Mutex::new(Vec::new()));
+        let provider = MiddlewareProvider::new(echo()).with_middleware(Arc::new(Recorder {
+            prompt: Some("streamed"),
+            ..Recorder::new("acct", &log)
+        }));
+
+        let mut rx = provider
+            .generate_stream(&[Message::user("Hi")], &[], &GenerateConfig::default())
+            .await
+            .unwrap();
+        let mut text = String::new();
+        while let Some(chunk) = rx.recv().await {
+            match chunk {
+                StreamChunk::TextDelta(delta) => text.push_str(&delta),
+                StreamChunk::Done { usage, .. } => {
+                    assert_eq!(usage.total(), 15);
+                    // Accounting already ran when the caller sees the end
+                    assert_eq!(
+                        log.lock().unwrap().as_slice(),
+                        ["acct.before", "acct.after:15"]
+                    );
+                }
+                _ => {}
+            }
+        }
+        assert_eq!(text, "streamed");
+    }
+}
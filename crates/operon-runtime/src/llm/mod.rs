@@ -1,18 +1,30 @@
 pub mod anthropic;
+pub mod error;
 pub mod failover;
 pub mod gemini;
+pub mod middleware;
 pub mod openai;
 pub mod provider;
+pub mod router;
 pub mod streaming;
+pub mod tokenizer;
 pub mod types;
 
 pub use anthropic::AnthropicClient;
+pub use error::ProviderError;
 pub use failover::ProviderChain;
 pub use gemini::GeminiClient;
-pub use openai::OpenAIClient;
-pub use provider::LLMProvider;
-pub use streaming::{parse_anthropic_sse, parse_gemini_sse, parse_openai_sse};
+pub use middleware::{LlmRequest, LoggingMiddleware, MiddlewareProvider, ProviderMiddleware};
+pub use openai::{OpenAIClient, ReasoningEffort};
+pub use provider::{probe_health, transcribe, LLMProvider, ProviderHealth};
+pub use router::ProviderRouter;
+pub use streaming::{
+    parse_anthropic_sse, parse_gemini_sse, parse_openai_sse, AssembledStream, StreamAssembler,
+};
+pub use tokenizer::{
+    estimator_for_model, BpeEstimator, HeuristicEstimator, TokenBudget, TokenEstimator,
+};
 pub use types::{
-    Content, GenerateConfig, GenerateResponse, Message, ModelInfo, Role, StopReason, StreamChunk,
-    ToolCall, ToolResult, ToolSchema, Usage,
+    Content, GenerateConfig, GenerateResponse, Message, ModelInfo, ModelPricing, Role, StopReason,
+    StreamChunk, TaskKind, ToolCall, ToolChoice, ToolResult, ToolSchema, Usage,
 };
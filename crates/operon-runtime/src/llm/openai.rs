@@ -1,17 +1,80 @@
 use anyhow::{anyhow, Result};
 use async_trait::async_trait;
 use reqwest::{Client, ClientBuilder};
-use serde::Deserialize;
+use serde::{Deserialize, Serialize};
 use serde_json::{json, Value};
 use std::time::Duration;
 
+use super::error::ProviderError;
 use super::provider::LLMProvider;
-use super::streaming::{drive_sse_stream, parse_openai_sse};
+use super::streaming::{drive_sse_stream, StreamAssembler};
 use super::types::*;
 
 const OPENAI_API_URL: &str = "https://api.openai.com/v1/chat/completions";
 const DEFAULT_MODEL: &str = "gpt-4o";
 
+/// How much hidden reasoning o-series models do before answering
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, schemars::JsonSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum ReasoningEffort {
+    Low,
+    Medium,
+    High,
+}
+
+impl ReasoningEffort {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ReasoningEffort::Low => "low",
+            ReasoningEffort::Medium => "medium",
+            ReasoningEffort::High => "high",
+        }
+    }
+}
+
+/// o-series reasoning models (o1, o3-mini, o4-mini, ...) reject `temperature`
+/// and `max_tokens`; they take `max_completion_tokens` instead
+pub fn is_reasoning_model(model: &str) -> bool {
+    let name = model.rsplit('/').next().unwrap_or(model);
+    let mut chars = name.chars();
+    chars.next() == Some('o') && chars.next().is_some_and(|c| c.is_ascii_digit())
+}
+
+/// Image as a Chat Completions `image_url` content part (inline data URL)
+fn image_url_part(data: &[u8], mime: &str) -> Value {
+    use base64::Engine;
+    let encoded = base64::engine::general_purpose::STANDARD.encode(data);
+    json!({
+        "type": "image_url",
+        "image_url": {
+            "url": format!("data:{};base64,{}", mime, encoded)
+        }
+    })
+}
+
+/// Audio as a Chat Completions `input_audio` content part
+fn input_audio_part(data: &[u8], mime: &str) -> Value {
+    use base64::Engine;
+    let encoded = base64::engine::general_purpose::STANDARD.encode(data);
+    let format = match mime.strip_prefix("audio/").unwrap_or(mime) {
+        "mpeg" | "mp3" => "mp3",
+        "wav" | "wave" | "x-wav" => "wav",
+        other => other,
+    };
+    json!({
+        "type": "input_audio",
+        "input_audio": {
+            "data": encoded,
+            "format": format,
+        }
+    })
+}
+
+/// Audio-input chat models, e.g. "gpt-4o-audio-preview"
+pub fn is_audio_model(model: &str) -> bool {
+    model.contains("audio")
+}
+
 /// OpenAI Chat Completions API client
 pub struct OpenAIClient {
     client: Client,
@@ -19,6 +82,8 @@ pub struct OpenAIClient {
     model: String,
     /// Custom base URL for OpenAI-compatible APIs (e.g., local LLM)
     base_url: Option<String>,
+    /// Sent as `reasoning_effort` to reasoning models only
+    reasoning_effort: Option<ReasoningEffort>,
 }
 
 impl OpenAIClient {
@@ -33,6 +98,7 @@ impl OpenAIClient {
             api_key: api_key.to_string(),
             model: DEFAULT_MODEL.to_string(),
             base_url: None,
+            reasoning_effort: None,
         }
     }
 
@@ -46,10 +112,23 @@ impl OpenAIClient {
         self
     }
 
+    /// Reasoning effort for o-series models (ignored for other models)
+    pub fn with_reasoning_effort(mut self, effort: ReasoningEffort) -> Self {
+        self.reasoning_effort = Some(effort);
+        self
+    }
+
     fn api_url(&self) -> &str {
         self.base_url.as_deref().unwrap_or(OPENAI_API_URL)
     }
 
+    /// Models endpoint next to the chat completions URL, if it follows the usual layout
+    fn models_url(&self) -> Option<String> {
+        self.api_url()
+            .strip_suffix("/chat/completions")
+            .map(|base| format!("{}/models", base))
+    }
+
     /// Build OpenAI API request body
     fn build_request_body(
         &self,
@@ -68,11 +147,36 @@ impl OpenAIClient {
 
         let mut body = json!({
             "model": model,
-            "max_tokens": config.max_tokens,
-            "temperature": config.temperature,
             "messages": api_messages,
         });
 
+        if is_reasoning_model(model) {
+            body["max_completion_tokens"] = json!(config.max_tokens);
+            if let Some(effort) = self.reasoning_effort {
+                body["reasoning_effort"] = json!(effort.as_str());
+            }
+        } else {
+            body["max_tokens"] = json!(config.max_tokens);
+            body["temperature"] = json!(config.temperature);
+            // Reasoning models reject `stop` and sampling controls
+            if !config.stop_sequences.is_empty() {
+                body["stop"] = json!(config.stop_sequences);
+            }
+            if let Some(top_p) = config.top_p {
+                body["top_p"] = json!(top_p);
+            }
+            if let Some(penalty) = config.frequency_penalty {
+                body["frequency_penalty"] = json!(penalty);
+            }
+            if let Some(penalty) = config.presence_penalty {
+                body["presence_penalty"] = json!(penalty);
+            }
+        }
+        // top_k has no Chat Completions equivalent
+        if let Some(seed) = config.seed {
+            body["seed"] = json!(seed);
+        }
+
         if stream {
             body["stream"] = json!(true);
         }
@@ -80,6 +184,11 @@ impl OpenAIClient {
         if !tools.is_empty() {
             let api_tools: Vec<Value> = tools.iter().map(|t| self.tool_to_api(t)).collect();
             body["tools"] = json!(api_tools);
+            match config.tool_choice {
+                ToolChoice::Auto => {}
+                ToolChoice::Any => body["tool_choice"] = json!("required"),
+                ToolChoice::None => body["tool_choice"] = json!("none"),
+            }
         }
 
         body
@@ -103,18 +212,46 @@ impl OpenAIClient {
                     api_msgs.push(json!({"role": "user", "content": text}));
                 }
                 (Role::User, Content::Image { data, mime }) => {
-                    use base64::Engine;
-                    let encoded = base64::engine::general_purpose::STANDARD.encode(data);
                     api_msgs.push(json!({
                         "role": "user",
-                        "content": [{
-                            "type": "image_url",
-                            "image_url": {
-                                "url": format!("data:{};base64,{}", mime, encoded)
-                            }
-                        }]
+                        "content": [image_url_part(data, mime)]
                     }));
                 }
+                (Role::User, Content::Audio { data, mime }) => {
+                    api_msgs.push(json!({
+                        "role": "user",
+                        "content": [input_audio_part(data, mime)]
+                    }));
+                }
+                (Role::User, Content::Mixed { parts }) => {
+                    // Tool results become their own `tool` messages; text and
+                    // images share one multi-part user message
+                    let mut user_parts = Vec::new();
+                    for part in parts {
+                        match part {
+                            Content::Text { text } => {
+                                user_parts.push(json!({"type": "text", "text": text}));
+                            }
+                            Content::Image { data, mime } => {
+                                user_parts.push(image_url_part(data, mime));
+                            }
+                            Content::Audio { data, mime } => {
+                                user_parts.push(input_audio_part(data, mime));
+                            }
+                            Content::ToolResult(tr) => {
+                                api_msgs.push(json!({
+                                    "role": "tool",
+                                    "tool_call_id": tr.tool_use_id,
+                                    "content": tr.output,
+                                }));
+                            }
+                            _ => {}
+                        }
+                    }
+                    if !user_parts.is_empty() {
+                        api_msgs.push(json!({"role": "user", "content": user_parts}));
+                    }
+                }
                 (Role::User, Content::ToolResult(tr)) => {
                     api_msgs.push(json!({
                         "role": "tool",
@@ -239,6 +376,7 @@ impl OpenAIClient {
             .map(|u| Usage {
                 input_tokens: u.prompt_tokens,
                 output_tokens: u.completion_tokens,
+                cost_usd: 0.0,
             })
             .unwrap_or_default();
 
@@ -270,10 +408,10 @@ impl LLMProvider for OpenAIClient {
             .send()
             .await?;
 
-        let status = response.status();
-        if !status.is_success() {
-            let error_body = response.text().await.unwrap_or_default();
-            return Err(anyhow!("OpenAI API error ({}): {}", status, error_body));
+        if !response.status().is_success() {
+            return Err(ProviderError::from_response("OpenAI", response)
+                .await
+                .into());
         }
 
         let api_response: ApiResponse = response.json().await?;
@@ -297,26 +435,64 @@ impl LLMProvider for OpenAIClient {
             .send()
             .await?;
 
-        let status = response.status();
-        if !status.is_success() {
-            let error_body = response.text().await.unwrap_or_default();
-            return Err(anyhow!("OpenAI API error ({}): {}", status, error_body));
+        if !response.status().is_success() {
+            return Err(ProviderError::from_response("OpenAI", response)
+                .await
+                .into());
         }
 
         let (tx, rx) = tokio::sync::mpsc::channel(32);
 
         tokio::spawn(async move {
-            drive_sse_stream(response.bytes_stream(), parse_openai_sse, tx).await;
+            let mut assembler = StreamAssembler::new();
+            drive_sse_stream(
+                response.bytes_stream(),
+                |data| assembler.push_openai(data),
+                tx,
+            )
+            .await;
         });
 
         Ok(rx)
     }
 
+    /// Lists models when the endpoint exposes them; otherwise a one-token completion
+    async fn health_check(&self) -> Result<()> {
+        let Some(url) = self.models_url() else {
+            let config = GenerateConfig {
+                max_tokens: 1,
+                ..Default::default()
+            };
+            return self
+                .generate(&[Message::user("ping")], &[], &config)
+                .await
+                .map(|_| ());
+        };
+
+        let response = self
+            .client
+            .get(url)
+            .header("Authorization", format!("Bearer {}", self.api_key))
+            .send()
+            .await?;
+
+        if !response.status().is_success() {
+            return Err(ProviderError::from_response("OpenAI", response)
+                .await
+                .into());
+        }
+        Ok(())
+    }
+
     fn supports_vision(&self) -> bool {
         // GPT-4o and GPT-4 Vision support images
         self.model.contains("gpt-4")
     }
 
+    fn supports_audio(&self) -> bool {
+        is_audio_model(&self.model)
+    }
+
     fn model_name(&self) -> &str {
         &self.model
     }
@@ -382,6 +558,162 @@ mod tests {
         assert!(body.get("stream").is_none());
     }
 
+    #[test]
+    fn test_mixed_text_and_image_message() {
+        let client = OpenAIClient::new("test-key");
+        let messages = vec![Message::user_with_image(
+            "What is this?",
+            vec![1, 2, 3],
+            "image/png",
+        )];
+
+        let body = client.build_request_body(&messages, &[], &GenerateConfig::default(), false);
+        let user = &body["messages"][0];
+        assert_eq!(user["role"], "user");
+        let parts = user["content"].as_array().unwrap();
+        assert_eq!(parts.len(), 2);
+        assert_eq!(parts[0]["type"], "text");
+        assert_eq!(parts[0]["text"], "What is this?");
+        assert_eq!(parts[1]["type"], "image_url");
+        assert_eq!(parts[1]["image_url"]["url"], "data:image/png;base64,AQID");
+    }
+
+    #[test]
+    fn test_audio_message() {
+        let client = OpenAIClient::new("test-key").with_model("gpt-4o-audio-preview");
+        assert!(client.supports_audio());
+        assert!(!OpenAIClient::new("test-key").supports_audio());
+
+        let messages = vec![Message::user_with_audio(
+            "Summarize this",
+            vec![1, 2, 3],
+            "audio/mpeg",
+        )];
+        let body = client.build_request_body(&messages, &[], &GenerateConfig::default(), false);
+        let parts = body["messages"][0]["content"].as_array().unwrap();
+        assert_eq!(parts[1]["type"], "input_audio");
+        assert_eq!(parts[1]["input_audio"]["data"], "AQID");
+        assert_eq!(parts[1]["input_audio"]["format"], "mp3");
+    }
+
+    #[test]
+    fn test_reasoning_model_request_body() {
+        let client = OpenAIClient::new("test-key")
+            .with_model("o3-mini")
+            .with_reasoning_effort(ReasoningEffort::High);
+        let body = client.build_request_body(
+            &[Message::user("Hi")],
+            &[],
+            &GenerateConfig::default(),
+            false,
+        );
+        assert_eq!(body["max_completion_tokens"], 4096);
+        assert_eq!(body["reasoning_effort"], "high");
+        assert!(body.get("max_tokens").is_none());
+        assert!(body.get("temperature").is_none());
+
+        // Effort is not sent to regular chat models
+        let body = client.build_request_body(
+            &[Message::user("Hi")],
+            &[],
+            &GenerateConfig {
+                model: "gpt-4o".into(),
+                ..Default::default()
+            },
+            false,
+        );
+        assert_eq!(body["max_tokens"], 4096);
+        assert!(body.get("reasoning_effort").is_none());
+    }
+
+    #[test]
+    fn test_is_reasoning_model() {
+        for model in ["o1", "o1-preview", "o3-mini", "o4-mini", "openai/o3"] {
+            assert!(is_reasoning_model(model), "{}", model);
+        }
+        for model in ["gpt-4o", "gpt-4o-mini", "omni-moderation", "llama3"] {
+            assert!(!is_reasoning_model(model), "{}", model);
+        }
+    }
+
+    #[test]
+    fn test_build_request_body_tool_choice() {
+        let client = OpenAIClient::new("test-key");
+        let tools = vec![ToolSchema {
+            name: "shell".into(),
+            description: "Execute shell command".into(),
+            input_schema: json!({"type": "object"}),
+        }];
+        let mut config = GenerateConfig::default();
+
+        let body = client.build_request_body(&[Message::user("Hi")], &tools, &config, false);
+        assert!(body.get("tool_choice").is_none());
+
+        config.tool_choice = ToolChoice::Any;
+        let body = client.build_request_body(&[Message::user("Hi")], &tools, &config, false);
+        assert_eq!(body["tool_choice"], "required");
+
+        config.tool_choice = ToolChoice::None;
+        let body = client.build_request_body(&[Message::user("Hi")], &tools, &config, false);
+        assert_eq!(body["tool_choice"], "none");
+    }
+
+    #[test]
+    fn test_build_request_body_stop_sequences() {
+        let client = OpenAIClient::new("test-key");
+        let body = client.build_request_body(
+            &[Message::user("List")],
+            &[],
+            &GenerateConfig::default(),
+            false,
+        );
+        assert!(body.get("stop").is_none());
+
+        let config = GenerateConfig {
+            stop_sequences: vec!["</plan>".into()],
+            ..Default::default()
+        };
+        let body = client.build_request_body(&[Message::user("List")], &[], &config, false);
+        assert_eq!(body["stop"], json!(["</plan>"]));
+
+        // Not sent to reasoning models, which reject it
+        let body = OpenAIClient::new("test-key")
+            .with_model("o3-mini")
+            .build_request_body(&[Message::user("List")], &[], &config, false);
+        assert!(body.get("stop").is_none());
+    }
+
+    #[test]
+    fn test_build_request_body_sampling() {
+        let config = GenerateConfig {
+            top_p: Some(0.5),
+            top_k: Some(40),
+            frequency_penalty: Some(0.25),
+            presence_penalty: Some(0.5),
+            seed: Some(7),
+            ..Default::default()
+        };
+
+        let body = OpenAIClient::new("test-key").build_request_body(
+            &[Message::user("Hi")],
+            &[],
+            &config,
+            false,
+        );
+        assert_eq!(body["top_p"], 0.5);
+        assert_eq!(body["frequency_penalty"], 0.25);
+        assert_eq!(body["presence_penalty"], 0.5);
+        assert_eq!(body["seed"], 7);
+        assert!(body.get("top_k").is_none());
+
+        // Reasoning models only keep the seed
+        let body = OpenAIClient::new("test-key")
+            .with_model("o1")
+            .build_request_body(&[Message::user("Hi")], &[], &config, false);
+        assert!(body.get("top_p").is_none());
+        assert_eq!(body["seed"], 7);
+    }
+
     #[test]
     fn test_build_request_body_streaming() {
         let client = OpenAIClient::new("test-key");
@@ -455,4 +787,16 @@ mod tests {
             "http://localhost:11434/v1/chat/completions"
         );
     }
+
+    #[test]
+    fn test_models_url() {
+        let client = OpenAIClient::new("key");
+        assert_eq!(
+            client.models_url().as_deref(),
+            Some("https://api.openai.com/v1/models")
+        );
+
+        let custom = OpenAIClient::new("key").with_base_url("http://localhost:8000/generate");
+        assert_eq!(custom.models_url(), None);
+    }
 }
@@ -1,5 +1,8 @@
+use std::time::Instant;
+
 use anyhow::Result;
 use async_trait::async_trait;
+use serde::Serialize;
 
 use super::types::{GenerateConfig, GenerateResponse, Message, StreamChunk, ToolSchema};
 
@@ -26,13 +29,82 @@ pub trait LLMProvider: Send + Sync {
         Ok(response_to_stream(response))
     }
 
+    /// Cheap liveness probe so bad keys or endpoints fail at startup, not on the
+    /// first user message. Default impl sends a one-token completion.
+    async fn health_check(&self) -> Result<()> {
+        let config = GenerateConfig {
+            max_tokens: 1,
+            ..Default::default()
+        };
+        self.generate(&[Message::user("ping")], &[], &config)
+            .await
+            .map(|_| ())
+    }
+
+    /// Health of each underlying provider (one entry unless this is a chain)
+    async fn health_report(&self) -> Vec<ProviderHealth> {
+        vec![probe_health(self).await]
+    }
+
     /// Whether this provider supports vision (image content)
     fn supports_vision(&self) -> bool;
 
+    /// Whether this provider accepts audio content
+    fn supports_audio(&self) -> bool {
+        false
+    }
+
     /// Provider model name for logging/tracking
     fn model_name(&self) -> &str;
 }
 
+/// Instruction sent alongside the clip by `transcribe`
+const TRANSCRIBE_PROMPT: &str =
+    "Transcribe this audio verbatim. Reply with the transcript only, without commentary.";
+
+/// Turn an audio clip into text with an audio-capable provider
+pub async fn transcribe<P: LLMProvider + ?Sized>(
+    provider: &P,
+    data: Vec<u8>,
+    mime: &str,
+) -> Result<String> {
+    if !provider.supports_audio() {
+        anyhow::bail!(
+            "Model '{}' does not accept audio input",
+            provider.model_name()
+        );
+    }
+    let config = GenerateConfig {
+        temperature: 0.0,
+        ..Default::default()
+    };
+    let message = Message::user_with_audio(TRANSCRIBE_PROMPT, data, mime);
+    let response = provider.generate(&[message], &[], &config).await?;
+    Ok(response.content.extract_text().trim().to_string())
+}
+
+/// Result of a provider health check
+#[derive(Debug, Clone, Serialize)]
+pub struct ProviderHealth {
+    pub model: String,
+    pub healthy: bool,
+    pub latency_ms: u64,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+/// Run `health_check` on a provider and time it
+pub async fn probe_health<P: LLMProvider + ?Sized>(provider: &P) -> ProviderHealth {
+    let started = Instant::now();
+    let result = provider.health_check().await;
+    ProviderHealth {
+        model: provider.model_name().to_string(),
+        healthy: result.is_ok(),
+        latency_ms: started.elapsed().as_millis() as u64,
+        error: result.err().map(|e| format!("{:#}", e)),
+    }
+}
+
 /// Build a fallback stream from a GenerateResponse (for non-streaming providers)
 pub fn response_to_stream(response: GenerateResponse) -> tokio::sync::mpsc::Receiver<StreamChunk> {
     let (tx, rx) = tokio::sync::mpsc::channel(8);
@@ -67,3 +139,68 @@ pub fn response_to_stream(response: GenerateResponse) -> tokio::sync::mpsc::Rece
     });
     rx
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::llm::types::{Content, StopReason, Usage};
+
+    /// Echoes the mime type of the audio part it receives
+    struct EchoAudio {
+        audio: bool,
+    }
+
+    #[async_trait]
+    impl LLMProvider for EchoAudio {
+        async fn generate(
+            &self,
+            messages: &[Message],
+            _tools: &[ToolSchema],
+            _config: &GenerateConfig,
+        ) -> Result<GenerateResponse> {
+            let mime = match &messages[0].content {
+                Content::Mixed { parts } => parts
+                    .iter()
+                    .find_map(|p| match p {
+                        Content::Audio { mime, .. } => Some(mime.clone()),
+                        _ => None,
+                    })
+                    .unwrap_or_default(),
+                _ => String::new(),
+            };
+            Ok(GenerateResponse {
+                content: Content::Text {
+                    text: format!("  heard {}\n", mime),
+                },
+                stop_reason: StopReason::EndTurn,
+                usage: Usage::default(),
+                model: "echo".into(),
+            })
+        }
+
+        fn supports_vision(&self) -> bool {
+            false
+        }
+
+        fn supports_audio(&self) -> bool {
+            self.audio
+        }
+
+        fn model_name(&self) -> &str {
+            "echo"
+        }
+    }
+
+    #[tokio::test]
+    async fn test_transcribe() {
+        let text = transcribe(&EchoAudio { audio: true }, vec![0; 4], "audio/wav")
+            .await
+            .unwrap();
+        assert_eq!(text, "heard audio/wav");
+
+        let err = transcribe(&EchoAudio { audio: false }, vec![0; 4], "audio/wav")
+            .await
+            .unwrap_err();
+        assert!(err.to_string().contains("does not accept audio"));
+    }
+}
@@ -0,0 +1,265 @@
+use std::collections::HashMap;
+use std::sync::Arc;
+
+use anyhow::Result;
+use async_trait::async_trait;
+
+use super::provider::LLMProvider;
+use super::types::*;
+use super::ProviderHealth;
+
+/// Routes each request to a provider chosen by its `TaskKind`
+/// (e.g. a cheap model for summaries, a strong one for tool-use turns).
+/// Unrouted task kinds, and routed requests that fail, go to the default provider.
+pub struct ProviderRouter {
+    default: Arc<dyn LLMProvider>,
+    routes: HashMap<TaskKind, Arc<dyn LLMProvider>>,
+}
+
+impl ProviderRouter {
+    pub fn new(default: Arc<dyn LLMProvider>) -> Self {
+        Self {
+            default,
+            routes: HashMap::new(),
+        }
+    }
+
+    /// Send requests of `task` kind to `provider` (which uses its own model)
+    pub fn with_route(mut self, task: TaskKind, provider: Arc<dyn LLMProvider>) -> Self {
+        self.routes.insert(task, provider);
+        self
+    }
+
+    /// Provider and effective config for a request. A routed request drops
+    /// `config.model` so the route's own model applies.
+    fn route(
+        &self,
+        tools: &[ToolSchema],
+        config: &GenerateConfig,
+    ) -> Option<(TaskKind, &Arc<dyn LLMProvider>, GenerateConfig)> {
+        let task = TaskKind::classify(config, tools);
+        self.routes.get(&task).map(|provider| {
+            let routed = GenerateConfig {
+                model: String::new(),
+                ..config.clone()
+            };
+            (task, provider, routed)
+        })
+    }
+}
+
+#[async_trait]
+impl LLMProvider for ProviderRouter {
+    async fn generate(
+        &self,
+        messages: &[Message],
+        tools: &[ToolSchema],
+        config: &GenerateConfig,
+    ) -> Result<GenerateResponse> {
+        if let Some((task, provider, routed)) = self.route(tools, config) {
+            tracing::debug!(
+                ?task,
+                provider = provider.model_name(),
+                "Routing LLM request"
+            );
+            match provider.generate(messages, tools, &routed).await {
+                Ok(response) => return Ok(response),
+                Err(e) => tracing::warn!(
+                    ?task,
+                    provider = provider.model_name(),
+                    error = %e,
+                    "Routed provider failed, using default"
+                ),
+            }
+        }
+        self.default.generate(messages, tools, config).await
+    }
+
+    async fn generate_stream(
+        &self,
+        messages: &[Message],
+        tools: &[ToolSchema],
+        config: &GenerateConfig,
+    ) -> Result<tokio::sync::mpsc::Receiver<StreamChunk>> {
+        if let Some((task, provider, routed)) = self.route(tools, config) {
+            match provider.generate_stream(messages, tools, &routed).await {
+                Ok(rx) => return Ok(rx),
+                Err(e) => tracing::warn!(
+                    ?task,
+                    provider = provider.model_name(),
+                    error = %e,
+                    "Routed provider failed to stream, using default"
+                ),
+            }
+        }
+        self.default.generate_stream(messages, tools, config).await
+    }
+
+    async fn health_check(&self) -> Result<()> {
+        self.default.health_check().await
+    }
+
+    /// Default provider(s) first, then each distinct routed model
+    async fn health_report(&self) -> Vec<ProviderHealth> {
+        let mut report = self.default.health_report().await;
+        for provider in self.routes.values() {
+            for health in provider.health_report().await {
+                if !report.iter().any(|h| h.model == health.model) {
+                    report.push(health);
+                }
+            }
+        }
+        report
+    }
+
+    fn supports_vision(&self) -> bool {
+        self.default.supports_vision()
+    }
+
+    fn supports_audio(&self) -> bool {
+        self.default.supports_audio()
+    }
+
+    fn model_name(&self) -> &str {
+        self.default.model_name()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use anyhow::anyhow;
+    use std::sync::Mutex;
+
+    /// Records the model it was asked for and answers with its own name
+    struct NamedProvider {
+        name: String,
+        fail: bool,
+        seen_models: Mutex<Vec<String>>,
+    }
+
+    impl NamedProvider {
+        fn new(name: &str, fail: bool) -> Arc<Self> {
+            Arc::new(Self {
+                name: name.into(),
+                fail,
+                seen_models: Mutex::new(Vec::new()),
+            })
+        }
+    }
+
+    #[async_trait]
+    impl LLMProvider for NamedProvider {
+        async fn generate(
+            &self,
+            _messages: &[Message],
+            _tools: &[ToolSchema],
+            config: &GenerateConfig,
+        ) -> Result<GenerateResponse> {
+            self.seen_models.lock().unwrap().push(config.model.clone());
+            if self.fail {
+                return Err(anyhow!("API error (503): unavailable"));
+            }
+            Ok(GenerateResponse {
+                content: Content::Text {
+                    text: self.name.clone(),
+                },
+                stop_reason: StopReason::EndTurn,
+                usage: Usage::default(),
+                model: self.name.clone(),
+            })
+        }
+
+        fn supports_vision(&self) -> bool {
+            false
+        }
+
+        fn model_name(&self) -> &str {
+            &self.name
+        }
+    }
+
+    fn tool() -> ToolSchema {
+        ToolSchema {
+            name: "shell".into(),
+            description: "Run a command".into(),
+            input_schema: serde_json::json!({"type": "object"}),
+        }
+    }
+
+    async fn answer(
+        router: &ProviderRouter,
+        tools: &[ToolSchema],
+        config: &GenerateConfig,
+    ) -> String {
+        router
+            .generate(&[Message::user("Hi")], tools, config)
+            .await
+            .unwrap()
+            .content
+            .extract_text()
+    }
+
+    #[tokio::test]
+    async fn test_routes_by_task_kind() {
+        let cheap = NamedProvider::new("cheap", false);
+        let strong = NamedProvider::new("strong", false);
+        let router = ProviderRouter::new(NamedProvider::new("default", false))
+            .with_route(TaskKind::Summarize, cheap.clone())
+            .with_route(TaskKind::ToolUse, strong.clone());
+
+        let summarize = GenerateConfig {
+            task: Some(TaskKind::Summarize),
+            model: "agent-model".into(),
+            ..Default::default()
+        };
+        assert_eq!(answer(&router, &[], &summarize).await, "cheap");
+        // Route's own model wins over the caller's
+        assert_eq!(cheap.seen_models.lock().unwrap().as_slice(), [""]);
+
+        // Offering tools classifies the turn as tool use
+        assert_eq!(
+            answer(&router, &[tool()], &GenerateConfig::default()).await,
+            "strong"
+        );
+        assert_eq!(
+            answer(&router, &[], &GenerateConfig::default()).await,
+            "default"
+        );
+    }
+
+    #[tokio::test]
+    async fn test_failed_route_falls_back_to_default() {
+        let default = NamedProvider::new("default", false);
+        let router = ProviderRouter::new(default.clone())
+            .with_route(TaskKind::Title, NamedProvider::new("broken", true));
+
+        let config = GenerateConfig {
+            task: Some(TaskKind::Title),
+            model: "agent-model".into(),
+            ..Default::default()
+        };
+        assert_eq!(answer(&router, &[], &config).await, "default");
+        assert_eq!(
+            default.seen_models.lock().unwrap().as_slice(),
+            ["agent-model"]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_health_report_includes_routes_once() {
+        let shared = NamedProvider::new("shared", false);
+        let router = ProviderRouter::new(shared.clone())
+            .with_route(TaskKind::Chat, shared)
+            .with_route(TaskKind::Summarize, NamedProvider::new("cheap", false));
+
+        let mut models: Vec<_> = router
+            .health_report()
+            .await
+            .into_iter()
+            .map(|h| h.model)
+            .collect();
+        models.sort();
+        assert_eq!(models, ["cheap", "shared"]);
+    }
+}
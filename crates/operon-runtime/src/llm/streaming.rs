@@ -1,11 +1,15 @@
 //! SSE parsing utilities for LLM streaming responses.
-//! Handles Anthropic and OpenAI server-sent event formats.
+//! Handles Anthropic, OpenAI and Gemini server-sent event formats.
+
+use std::collections::HashMap;
 
 use bytes::Bytes;
 use futures::StreamExt;
 use serde::Deserialize;
+use serde_json::Value;
+use tokio::sync::mpsc;
 
-use super::types::{StopReason, StreamChunk, Usage};
+use super::types::{Content, GenerateResponse, StopReason, StreamChunk, ToolCall, Usage};
 
 /// Max SSE buffer size (1MB) to prevent OOM from malformed streams
 const MAX_BUFFER_SIZE: usize = 1_048_576;
@@ -14,7 +18,8 @@ const MAX_BUFFER_SIZE: usize = 1_048_576;
 /// decode UTF-8 at event boundaries (not chunk boundaries), parse, and send.
 ///
 /// Uses `Vec<u8>` buffer to avoid corrupting multi-byte UTF-8 chars
-/// split across HTTP chunks.
+/// split across HTTP chunks. Transport errors end the stream with
+/// `StreamChunk::Error` rather than a synthetic Done.
 pub async fn drive_sse_stream<S, F>(
     mut byte_stream: S,
     mut parse_event: F,
@@ -31,10 +36,7 @@ pub async fn drive_sse_stream<S, F>(
             Err(e) => {
                 tracing::warn!("SSE read error: {}", e);
                 let _ = tx
-                    .send(StreamChunk::Done {
-                        stop_reason: StopReason::EndTurn,
-                        usage: Usage::default(),
-                    })
+                    .send(StreamChunk::Error(format!("SSE read error: {}", e)))
                     .await;
                 return;
             }
@@ -46,10 +48,10 @@ pub async fn drive_sse_stream<S, F>(
         if buffer.len() > MAX_BUFFER_SIZE {
             tracing::error!("SSE buffer exceeded {}B limit, aborting", MAX_BUFFER_SIZE);
             let _ = tx
-                .send(StreamChunk::Done {
-                    stop_reason: StopReason::EndTurn,
-                    usage: Usage::default(),
-                })
+                .send(StreamChunk::Error(format!(
+                    "SSE buffer exceeded {}B limit",
+                    MAX_BUFFER_SIZE
+                )))
                 .await;
             return;
         }
@@ -92,10 +94,20 @@ fn find_double_newline(buf: &[u8]) -> Option<usize> {
 #[derive(Debug, Deserialize)]
 #[serde(tag = "type")]
 enum AnthropicEvent {
+    #[serde(rename = "message_start")]
+    MessageStart { message: AnthropicMessageStart },
     #[serde(rename = "content_block_start")]
-    ContentBlockStart { content_block: AnthropicBlock },
+    ContentBlockStart {
+        #[serde(default)]
+        index: u32,
+        content_block: AnthropicBlock,
+    },
     #[serde(rename = "content_block_delta")]
-    ContentBlockDelta { delta: AnthropicDelta },
+    ContentBlockDelta {
+        #[serde(default)]
+        index: u32,
+        delta: AnthropicDelta,
+    },
     #[serde(rename = "message_stop")]
     MessageStop,
     #[serde(rename = "message_delta")]
@@ -122,6 +134,12 @@ enum AnthropicDelta {
     TextDelta { text: String },
     #[serde(rename = "input_json_delta")]
     InputJsonDelta { partial_json: String },
+    #[serde(rename = "thinking_delta")]
+    ThinkingDelta { thinking: String },
+    #[serde(rename = "signature_delta")]
+    SignatureDelta { signature: String },
+    #[serde(other)]
+    Unknown,
 }
 
 #[derive(Debug, Deserialize)]
@@ -129,18 +147,28 @@ struct AnthropicMessageDelta {
     stop_reason: Option<String>,
 }
 
+#[derive(Debug, Deserialize)]
+struct AnthropicMessageStart {
+    usage: Option<AnthropicUsage>,
+}
+
 #[derive(Debug, Deserialize)]
 struct AnthropicUsage {
+    input_tokens: Option<u32>,
     output_tokens: Option<u32>,
 }
 
 /// Parse an Anthropic SSE event data string into a StreamChunk.
 /// Returns None for events we don't need to forward (ping, message_start, etc.)
+/// Tool call deltas carry no id; a [`StreamAssembler`] attributes them.
 pub fn parse_anthropic_sse(data: &str) -> Option<StreamChunk> {
     let event: AnthropicEvent = serde_json::from_str(data).ok()?;
+    anthropic_chunk(event)
+}
 
+fn anthropic_chunk(event: AnthropicEvent) -> Option<StreamChunk> {
     match event {
-        AnthropicEvent::ContentBlockStart { content_block } => {
+        AnthropicEvent::ContentBlockStart { content_block, .. } => {
             if content_block.block_type == "tool_use" {
                 Some(StreamChunk::ToolCallStart {
                     id: content_block.id.unwrap_or_default(),
@@ -150,15 +178,19 @@ pub fn parse_anthropic_sse(data: &str) -> Option<StreamChunk> {
                 None // text block start - no data to emit yet
             }
         }
-        AnthropicEvent::ContentBlockDelta { delta } => match delta {
+        AnthropicEvent::ContentBlockDelta { delta, .. } => match delta {
             AnthropicDelta::TextDelta { text } => Some(StreamChunk::TextDelta(text)),
             AnthropicDelta::InputJsonDelta { partial_json } => {
-                // Tool call input delta - caller must track current tool_use id
+                // Tool call input delta - id comes from block tracking (StreamAssembler)
                 Some(StreamChunk::ToolCallDelta {
-                    id: String::new(), // filled by caller from block tracking
+                    id: String::new(),
                     input_delta: partial_json,
                 })
             }
+            AnthropicDelta::ThinkingDelta { thinking } => Some(StreamChunk::Thinking(thinking)),
+            // The signature closes a thinking block; a StreamAssembler turns
+            // it into a ThinkingBlock
+            AnthropicDelta::SignatureDelta { .. } | AnthropicDelta::Unknown => None,
         },
         AnthropicEvent::MessageDelta { delta, usage } => {
             let stop_reason = match delta.stop_reason.as_deref() {
@@ -171,10 +203,12 @@ pub fn parse_anthropic_sse(data: &str) -> Option<StreamChunk> {
                 usage: Usage {
                     input_tokens: 0, // only available in message_start
                     output_tokens: usage.and_then(|u| u.output_tokens).unwrap_or(0),
+                    cost_usd: 0.0,
                 },
             })
         }
-        AnthropicEvent::MessageStop => None, // message_delta already emitted Done
+        AnthropicEvent::MessageStart { .. } => None, // input usage read by StreamAssembler
+        AnthropicEvent::MessageStop => None,         // message_delta already emitted Done
         AnthropicEvent::Unknown => None,
     }
 }
@@ -199,7 +233,6 @@ struct OpenAIMessageDelta {
     tool_calls: Option<Vec<OpenAIToolCallDelta>>,
 }
 
-#[allow(dead_code)] // index used by OpenAI for tool call ordering
 #[derive(Debug, Deserialize)]
 struct OpenAIToolCallDelta {
     index: Option<u32>,
@@ -222,13 +255,26 @@ struct OpenAIUsage {
 /// Parse an OpenAI SSE data line into StreamChunk(s).
 /// Returns empty vec for unparseable data.
 /// May return multiple chunks if both text and tool deltas present.
+/// Argument deltas after a call's first chunk carry no id; a
+/// [`StreamAssembler`] attributes them.
 pub fn parse_openai_sse(data: &str) -> Vec<StreamChunk> {
+    openai_chunks(data)
+        .into_iter()
+        .map(|(_, chunk)| chunk)
+        .collect()
+}
+
+/// OpenAI chunks paired with the `tool_calls[].index` they belong to
+fn openai_chunks(data: &str) -> Vec<(Option<u32>, StreamChunk)> {
     let trimmed = data.trim();
     if trimmed == "[DONE]" {
-        return vec![StreamChunk::Done {
-            stop_reason: StopReason::EndTurn,
-            usage: Usage::default(),
-        }];
+        return vec![(
+            None,
+            StreamChunk::Done {
+                stop_reason: StopReason::EndTurn,
+                usage: Usage::default(),
+            },
+        )];
     }
 
     let delta: OpenAIDelta = match serde_json::from_str(trimmed) {
@@ -256,9 +302,10 @@ pub fn parse_openai_sse(data: &str) -> Vec<StreamChunk> {
                 .map(|u| Usage {
                     input_tokens: u.prompt_tokens.unwrap_or(0),
                     output_tokens: u.completion_tokens.unwrap_or(0),
+                    cost_usd: 0.0,
                 })
                 .unwrap_or_default();
-            chunks.push(StreamChunk::Done { stop_reason, usage });
+            chunks.push((None, StreamChunk::Done { stop_reason, usage }));
             continue;
         }
 
@@ -269,7 +316,7 @@ pub fn parse_openai_sse(data: &str) -> Vec<StreamChunk> {
         // Text content delta
         if let Some(ref content) = msg_delta.content {
             if !content.is_empty() {
-                chunks.push(StreamChunk::TextDelta(content.clone()));
+                chunks.push((None, StreamChunk::TextDelta(content.clone())));
             }
         }
 
@@ -283,18 +330,24 @@ pub fn parse_openai_sse(data: &str) -> Vec<StreamChunk> {
                         .as_ref()
                         .and_then(|f| f.name.clone())
                         .unwrap_or_default();
-                    chunks.push(StreamChunk::ToolCallStart {
-                        id: id.clone(),
-                        name,
-                    });
-                } else if let Some(ref func) = tc.function {
-                    // Argument delta for existing tool call
-                    if let Some(ref args) = func.arguments {
-                        chunks.push(StreamChunk::ToolCallDelta {
-                            id: String::new(), // caller tracks by index
+                    chunks.push((
+                        tc.index,
+                        StreamChunk::ToolCallStart {
+                            id: id.clone(),
+                            name,
+                        },
+                    ));
+                }
+                // Argument delta; the first chunk of a call may already carry some
+                let args = tc.function.as_ref().and_then(|f| f.arguments.as_ref());
+                if let Some(args) = args.filter(|a| !a.is_empty()) {
+                    chunks.push((
+                        tc.index,
+                        StreamChunk::ToolCallDelta {
+                            id: tc.id.clone().unwrap_or_default(),
                             input_delta: args.clone(),
-                        });
-                    }
+                        },
+                    ));
                 }
             }
         }
@@ -417,6 +470,7 @@ pub fn parse_gemini_sse(data: &str) -> Vec<StreamChunk> {
                 .map(|u| Usage {
                     input_tokens: u.prompt_token_count.unwrap_or(0),
                     output_tokens: u.candidates_token_count.unwrap_or(0),
+                    cost_usd: 0.0,
                 })
                 .unwrap_or_default();
 
@@ -427,6 +481,288 @@ pub fn parse_gemini_sse(data: &str) -> Vec<StreamChunk> {
     chunks
 }
 
+// --- Stream assembly ---
+
+/// Tool call being streamed: arguments arrive as JSON fragments
+#[derive(Debug)]
+struct PendingToolCall {
+    id: String,
+    name: String,
+    input: String,
+}
+
+/// Per-stream state on top of the stateless `parse_*_sse` functions.
+/// Attributes every `ToolCallDelta` to its call id (Anthropic tracks content
+/// block indexes, OpenAI tool call indexes), forwards a single `Done`, and
+/// accumulates everything into a final `GenerateResponse`.
+///
+/// Feed raw SSE data with `push_anthropic` / `push_openai` / `push_gemini`,
+/// or already-parsed chunks from a provider channel with `push_chunk`.
+#[derive(Debug, Default)]
+pub struct StreamAssembler {
+    text: String,
+    /// Reasoning of the thinking block still open
+    thinking: String,
+    /// Closed thinking blocks as (thinking, signature)
+    thinking_blocks: Vec<(String, String)>,
+    tool_calls: Vec<PendingToolCall>,
+    /// Provider-side block/call index -> position in `tool_calls`
+    slots: HashMap<u32, usize>,
+    /// Most recently started tool call, for deltas without an index
+    current: Option<usize>,
+    /// Input tokens reported before `Done` (Anthropic `message_start`)
+    input_tokens: u32,
+    done: Option<(StopReason, Usage)>,
+}
+
+impl StreamAssembler {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn push_anthropic(&mut self, data: &str) -> Vec<StreamChunk> {
+        let Ok(event) = serde_json::from_str::<AnthropicEvent>(data) else {
+            return vec![];
+        };
+        let slot = match &event {
+            AnthropicEvent::MessageStart { message } => {
+                if let Some(tokens) = message.usage.as_ref().and_then(|u| u.input_tokens) {
+                    self.input_tokens = tokens;
+                }
+                None
+            }
+            AnthropicEvent::ContentBlockDelta {
+                delta: AnthropicDelta::SignatureDelta { signature },
+                ..
+            } => {
+                // Sent once, right before the thinking block stops
+                let block = StreamChunk::ThinkingBlock {
+                    thinking: self.thinking.clone(),
+                    signature: signature.clone(),
+                };
+                return self.accept(None, block).into_iter().collect();
+            }
+            AnthropicEvent::ContentBlockStart { index, .. }
+            | AnthropicEvent::ContentBlockDelta { index, .. } => Some(*index),
+            _ => None,
+        };
+        anthropic_chunk(event)
+            .and_then(|chunk| self.accept(slot, chunk))
+            .into_iter()
+            .collect()
+    }
+
+    pub fn push_openai(&mut self, data: &str) -> Vec<StreamChunk> {
+        openai_chunks(data)
+            .into_iter()
+            .filter_map(|(slot, chunk)| self.accept(slot, chunk))
+            .collect()
+    }
+
+    pub fn push_gemini(&mut self, data: &str) -> Vec<StreamChunk> {
+        parse_gemini_sse(data)
+            .into_iter()
+            .filter_map(|chunk| self.accept(None, chunk))
+            .collect()
+    }
+
+    /// Track a chunk that was already parsed; returns it attributed,
+    /// or None if it should not be forwarded (e.g. a repeated `Done`)
+    pub fn push_chunk(&mut self, chunk: StreamChunk) -> Option<StreamChunk> {
+        self.accept(None, chunk)
+    }
+
+    /// True once `Done` has been seen
+    pub fn is_done(&self) -> bool {
+        self.done.is_some()
+    }
+
+    /// Wrap a provider's chunk receiver so every chunk read from it is tracked
+    pub fn wrap(rx: mpsc::Receiver<StreamChunk>, model: &str) -> AssembledStream {
+        AssembledStream {
+            rx,
+            assembler: Self::new(),
+            model: model.to_string(),
+        }
+    }
+
+    /// First tool call whose streamed arguments are not valid JSON, as an
+    /// error message
+    pub fn tool_input_error(&self) -> Option<String> {
+        self.tool_calls.iter().find_map(|call| {
+            let input = call.input.trim();
+            if input.is_empty() {
+                return None;
+            }
+            serde_json::from_str::<Value>(input)
+                .err()
+                .map(|e| format!("invalid input for tool call '{}': {}", call.name, e))
+        })
+    }
+
+    fn accept(&mut self, slot: Option<u32>, chunk: StreamChunk) -> Option<StreamChunk> {
+        match chunk {
+            StreamChunk::TextDelta(text) => {
+                self.text.push_str(&text);
+                Some(StreamChunk::TextDelta(text))
+            }
+            StreamChunk::Thinking(text) => {
+                self.thinking.push_str(&text);
+                Some(StreamChunk::Thinking(text))
+            }
+            StreamChunk::ThinkingBlock {
+                thinking,
+                signature,
+            } => {
+                // Replaces the deltas, which may not have been forwarded
+                self.thinking.clear();
+                self.thinking_blocks
+                    .push((thinking.clone(), signature.clone()));
+                Some(StreamChunk::ThinkingBlock {
+                    thinking,
+                    signature,
+                })
+            }
+            StreamChunk::ToolCallStart { id, name } => {
+                let pos = self.tool_calls.len();
+                self.tool_calls.push(PendingToolCall {
+                    id: id.clone(),
+                    name: name.clone(),
+                    input: String::new(),
+                });
+                if let Some(slot) = slot {
+                    self.slots.insert(slot, pos);
+                }
+                self.current = Some(pos);
+                Some(StreamChunk::ToolCallStart { id, name })
+            }
+            StreamChunk::ToolCallDelta { id, input_delta } => {
+                let pos = if id.is_empty() {
+                    slot.and_then(|s| self.slots.get(&s).copied())
+                        .or(self.current)
+                } else {
+                    self.tool_calls.iter().position(|c| c.id == id)
+                };
+                let Some(call) = pos.map(|p| &mut self.tool_calls[p]) else {
+                    tracing::warn!(id = %id, "Dropping tool call delta without a started call");
+                    return None;
+                };
+                call.input.push_str(&input_delta);
+                Some(StreamChunk::ToolCallDelta {
+                    id: call.id.clone(),
+                    input_delta,
+                })
+            }
+            StreamChunk::Done {
+                stop_reason,
+                mut usage,
+            } => {
+                // OpenAI sends finish_reason and then [DONE]; forward the first
+                if self.done.is_some() {
+                    return None;
+                }
+                if usage.input_tokens == 0 {
+                    usage.input_tokens = self.input_tokens;
+                }
+                self.done = Some((stop_reason.clone(), usage.clone()));
+                Some(StreamChunk::Done { stop_reason, usage })
+            }
+            StreamChunk::Error(msg) => Some(StreamChunk::Error(msg)),
+        }
+    }
+
+    /// Everything received so far as a response. A stream that never
+    /// reached `Done` reports `EndTurn` with no output usage.
+    pub fn into_response(self, model: &str) -> GenerateResponse {
+        let mut parts = Vec::new();
+        for (thinking, signature) in self.thinking_blocks {
+            parts.push(Content::Thinking {
+                thinking,
+                signature,
+            });
+        }
+        // Unsigned reasoning from a stream cut off mid-block
+        if !self.thinking.is_empty() {
+            parts.push(Content::Thinking {
+                thinking: self.thinking,
+                signature: String::new(),
+            });
+        }
+        if !self.text.is_empty() {
+            parts.push(Content::Text { text: self.text });
+        }
+        for call in self.tool_calls {
+            // Tools without arguments stream no input at all
+            let input = if call.input.trim().is_empty() {
+                Value::Object(Default::default())
+            } else {
+                serde_json::from_str(&call.input).unwrap_or(Value::Null)
+            };
+            parts.push(Content::ToolCall(ToolCall {
+                id: call.id,
+                name: call.name,
+                input,
+            }));
+        }
+
+        let content = if parts.len() == 1 {
+            parts.into_iter().next().unwrap()
+        } else if parts.is_empty() {
+            Content::Text {
+                text: String::new(),
+            }
+        } else {
+            Content::Mixed { parts }
+        };
+
+        let (stop_reason, usage) = self.done.unwrap_or_else(|| {
+            let usage = Usage {
+                input_tokens: self.input_tokens,
+                output_tokens: 0,
+                cost_usd: 0.0,
+            };
+            (StopReason::EndTurn, usage)
+        });
+
+        GenerateResponse {
+            content,
+            stop_reason,
+            usage,
+            model: model.to_string(),
+        }
+    }
+}
+
+/// Chunk receiver read through a [`StreamAssembler`]: `recv` yields chunks
+/// with every tool call delta attributed to its call id and a single `Done`,
+/// and `into_response` assembles what was received
+pub struct AssembledStream {
+    rx: mpsc::Receiver<StreamChunk>,
+    assembler: StreamAssembler,
+    model: String,
+}
+
+impl AssembledStream {
+    /// Next chunk to forward; None once the provider closed the stream
+    pub async fn recv(&mut self) -> Option<StreamChunk> {
+        loop {
+            let chunk = self.rx.recv().await?;
+            if let Some(chunk) = self.assembler.push_chunk(chunk) {
+                return Some(chunk);
+            }
+        }
+    }
+
+    pub fn assembler(&self) -> &StreamAssembler {
+        &self.assembler
+    }
+
+    /// Response built from the chunks received so far
+    pub fn into_response(self) -> GenerateResponse {
+        self.assembler.into_response(&self.model)
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -613,4 +949,194 @@ mod tests {
         let chunks = parse_gemini_sse(data);
         assert!(chunks.is_empty());
     }
+
+    // --- StreamAssembler tests ---
+
+    #[test]
+    fn test_assembler_anthropic_attributes_tool_deltas() {
+        let events = [
+            r#"{"type":"message_start","message":{"usage":{"input_tokens":12,"output_tokens":1}}}"#,
+            r#"{"type":"content_block_start","index":0,"content_block":{"type":"text","text":""}}"#,
+            r#"{"type":"content_block_delta","index":0,"delta":{"type":"text_delta","text":"Checking"}}"#,
+            r#"{"type":"content_block_start","index":1,"content_block":{"type":"tool_use","id":"toolu_1","name":"shell"}}"#,
+            r#"{"type":"content_block_delta","index":1,"delta":{"type":"input_json_delta","partial_json":"{\"cmd\":"}}"#,
+            r#"{"type":"content_block_delta","index":1,"delta":{"type":"input_json_delta","partial_json":"\"date\"}"}}"#,
+            r#"{"type":"message_delta","delta":{"stop_reason":"tool_use"},"usage":{"output_tokens":30}}"#,
+            r#"{"type":"message_stop"}"#,
+        ];
+        let mut assembler = StreamAssembler::new();
+        let chunks: Vec<_> = events
+            .iter()
+            .flat_map(|e| assembler.push_anthropic(e))
+            .collect();
+
+        assert_eq!(chunks.len(), 5);
+        for chunk in &chunks[2..4] {
+            match chunk {
+                StreamChunk::ToolCallDelta { id, .. } => assert_eq!(id, "toolu_1"),
+                other => panic!("Expected ToolCallDelta, got {:?}", other),
+            }
+        }
+        assert!(assembler.is_done());
+
+        let response = assembler.into_response("claude-test");
+        assert_eq!(response.stop_reason, StopReason::ToolUse);
+        assert_eq!(response.usage.input_tokens, 12);
+        assert_eq!(response.usage.output_tokens, 30);
+        assert_eq!(response.content.extract_text(), "Checking");
+        let calls = response.content.extract_tool_calls();
+        assert_eq!(calls.len(), 1);
+        assert_eq!(calls[0].input["cmd"], "date");
+    }
+
+    #[test]
+    fn test_assembler_openai_parallel_calls_by_index() {
+        let events = [
+            r#"{"choices":[{"index":0,"delta":{"tool_calls":[{"index":0,"id":"call_a","function":{"name":"read","arguments":""}}]}}]}"#,
+            r#"{"choices":[{"index":0,"delta":{"tool_calls":[{"index":1,"id":"call_b","function":{"name":"write","arguments":"{\"path\":"}}]}}]}"#,
+            r#"{"choices":[{"index":0,"delta":{"tool_calls":[{"index":0,"function":{"arguments":"{\"path\":\"a\"}"}}]}}]}"#,
+            r#"{"choices":[{"index":0,"delta":{"tool_calls":[{"index":1,"function":{"arguments":"\"b\"}"}}]}}]}"#,
+            r#"{"choices":[{"index":0,"delta":{},"finish_reason":"tool_calls"}]}"#,
+            "[DONE]",
+        ];
+        let mut assembler = StreamAssembler::new();
+        let chunks: Vec<_> = events
+            .iter()
+            .flat_map(|e| assembler.push_openai(e))
+            .collect();
+
+        let delta_ids: Vec<_> = chunks
+            .iter()
+            .filter_map(|c| match c {
+                StreamChunk::ToolCallDelta { id, .. } => Some(id.as_str()),
+                _ => None,
+            })
+            .collect();
+        assert_eq!(delta_ids, ["call_b", "call_a", "call_b"]);
+        // [DONE] after finish_reason is not forwarded twice
+        let dones = chunks
+            .iter()
+            .filter(|c| matches!(c, StreamChunk::Done { .. }))
+            .count();
+        assert_eq!(dones, 1);
+
+        let response = assembler.into_response("gpt-test");
+        let calls = response.content.extract_tool_calls();
+        assert_eq!(calls[0].input["path"], "a");
+        assert_eq!(calls[1].input["path"], "b");
+    }
+
+    #[tokio::test]
+    async fn test_wrapped_receiver_attributes_parsed_chunks() {
+        // The stateless parser leaves the delta's id empty
+        let (tx, rx) = mpsc::channel(8);
+        let events = [
+            r#"{"type":"content_block_start","index":0,"content_block":{"type":"tool_use","id":"toolu_1","name":"shell"}}"#,
+            r#"{"type":"content_block_delta","index":0,"delta":{"type":"input_json_delta","partial_json":"{\"cmd\":\"ls\"}"}}"#,
+            r#"{"type":"message_delta","delta":{"stop_reason":"tool_use"},"usage":{"output_tokens":7}}"#,
+        ];
+        for event in events {
+            tx.send(parse_anthropic_sse(event).unwrap()).await.unwrap();
+        }
+        tx.send(StreamChunk::ToolCallDelta {
+            id: String::new(),
+            input_delta: "}".into(),
+        })
+        .await
+        .unwrap();
+        drop(tx);
+
+        let mut stream = StreamAssembler::wrap(rx, "claude-test");
+        let mut chunks = Vec::new();
+        while let Some(chunk) = stream.recv().await {
+            chunks.push(chunk);
+        }
+        assert!(matches!(
+            &chunks[1],
+            StreamChunk::ToolCallDelta { id, .. } if id == "toolu_1"
+        ));
+        // The stray brace after `Done` makes the arguments invalid
+        assert!(stream
+            .assembler()
+            .tool_input_error()
+            .unwrap()
+            .contains("'shell'"));
+        let response = stream.into_response();
+        assert_eq!(response.model, "claude-test");
+        assert_eq!(response.stop_reason, StopReason::ToolUse);
+        assert_eq!(response.usage.output_tokens, 7);
+    }
+
+    #[test]
+    fn test_assembler_push_chunk_without_done() {
+        let mut assembler = StreamAssembler::new();
+        assembler.push_chunk(StreamChunk::ToolCallStart {
+            id: "call_1".into(),
+            name: "list".into(),
+        });
+        let delta = assembler.push_chunk(StreamChunk::ToolCallDelta {
+            id: String::new(),
+            input_delta: String::new(),
+        });
+        assert!(matches!(delta, Some(StreamChunk::ToolCallDelta { ref id, .. }) if id == "call_1"));
+        assert!(!assembler.is_done());
+
+        let response = assembler.into_response("m");
+        assert_eq!(response.stop_reason, StopReason::EndTurn);
+        // No arguments streamed: empty object rather than null
+        assert_eq!(
+            response.content.extract_tool_calls()[0].input,
+            serde_json::json!({})
+        );
+    }
+
+    #[test]
+    fn test_assembler_anthropic_thinking() {
+        let events = [
+            r#"{"type":"content_block_start","index":0,"content_block":{"type":"thinking","thinking":""}}"#,
+            r#"{"type":"content_block_delta","index":0,"delta":{"type":"thinking_delta","thinking":"Let me think"}}"#,
+            r#"{"type":"content_block_delta","index":0,"delta":{"type":"signature_delta","signature":"sig-1"}}"#,
+            r#"{"type":"content_block_delta","index":1,"delta":{"type":"text_delta","text":"42"}}"#,
+            r#"{"type":"message_delta","delta":{"stop_reason":"end_turn"},"usage":{"output_tokens":9}}"#,
+        ];
+        let mut assembler = StreamAssembler::new();
+        let chunks: Vec<_> = events
+            .iter()
+            .flat_map(|e| assembler.push_anthropic(e))
+            .collect();
+
+        assert_eq!(chunks.len(), 4);
+        assert!(matches!(&chunks[0], StreamChunk::Thinking(t) if t == "Let me think"));
+        assert!(matches!(
+            &chunks[1],
+            StreamChunk::ThinkingBlock { signature, .. } if signature == "sig-1"
+        ));
+
+        // A consumer that only sees the forwarded chunks, without the
+        // thinking deltas (show_thinking off), assembles the same block
+        let mut downstream = StreamAssembler::new();
+        for chunk in chunks.into_iter().skip(1) {
+            downstream.push_chunk(chunk);
+        }
+        let downstream = downstream.into_response("claude-test").content;
+        let response = assembler.into_response("claude-test");
+        assert_eq!(
+            serde_json::to_value(&downstream).unwrap(),
+            serde_json::to_value(&response.content).unwrap()
+        );
+        assert_eq!(response.content.extract_text(), "42");
+        match &response.content {
+            Content::Mixed { parts } => match &parts[0] {
+                Content::Thinking {
+                    thinking,
+                    signature,
+                } => {
+                    assert_eq!(thinking, "Let me think");
+                    assert_eq!(signature, "sig-1");
+                }
+                other => panic!("Expected Thinking, got {:?}", other),
+            },
+            other => panic!("Expected Mixed, got {:?}", other),
+        }
+    }
 }
@@ -0,0 +1,207 @@
+//! Prompt token estimates, before the provider reports usage.
+//! OpenAI models are counted with their BPE encoding, others with a
+//! characters-per-token heuristic.
+
+use std::sync::Arc;
+
+use anyhow::{bail, Result};
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use tiktoken_rs::tokenizer::{get_tokenizer, Tokenizer};
+use tiktoken_rs::CoreBPE;
+
+use super::types::{Content, Message, ToolSchema};
+
+/// Tokens added per message for its role and framing
+const MESSAGE_OVERHEAD: usize = 4;
+
+/// Flat estimate for an image or audio part
+const MEDIA_TOKENS: usize = 1_500;
+
+/// Counts tokens the way a model's tokenizer would, or close to it
+pub trait TokenEstimator: Send + Sync {
+    fn count(&self, text: &str) -> usize;
+
+    fn count_content(&self, content: &Content) -> usize {
+        match content {
+            Content::Text { text } => self.count(text),
+            Content::Thinking { thinking, .. } => self.count(thinking),
+            Content::Image { .. } | Content::Audio { .. } => MEDIA_TOKENS,
+            Content::ToolCall(call) => self.count(&call.name) + self.count(&call.input.to_string()),
+            Content::ToolResult(result) => self.count(&result.output),
+            Content::Mixed { parts } => parts.iter().map(|part| self.count_content(part)).sum(),
+        }
+    }
+
+    /// Prompt tokens of a request: system prompt, tool schemas and messages
+    fn count_request(
+        &self,
+        system_prompt: Option<&str>,
+        messages: &[Message],
+        tools: &[ToolSchema],
+    ) -> usize {
+        let system = system_prompt.map_or(0, |prompt| self.count(prompt));
+        let tools: usize = tools
+            .iter()
+            .map(|tool| {
+                self.count(&tool.name)
+                    + self.count(&tool.description)
+                    + self.count(&tool.input_schema.to_string())
+            })
+            .sum();
+        let messages: usize = messages
+            .iter()
+            .map(|message| MESSAGE_OVERHEAD + self.count_content(&message.content))
+            .sum();
+        system + tools + messages
+    }
+}
+
+/// Estimate for models without a local tokenizer
+#[derive(Debug, Clone, Copy)]
+pub struct HeuristicEstimator {
+    chars_per_token: f32,
+}
+
+impl HeuristicEstimator {
+    pub fn new(chars_per_token: f32) -> Self {
+        Self { chars_per_token }
+    }
+}
+
+impl TokenEstimator for HeuristicEstimator {
+    fn count(&self, text: &str) -> usize {
+        (text.chars().count() as f32 / self.chars_per_token).ceil() as usize
+    }
+}
+
+/// Exact counts with a tiktoken encoding (OpenAI models)
+pub struct BpeEstimator {
+    bpe: &'static CoreBPE,
+}
+
+impl BpeEstimator {
+    pub fn cl100k() -> Self {
+        Self {
+            bpe: tiktoken_rs::cl100k_base_singleton(),
+        }
+    }
+
+    pub fn o200k() -> Self {
+        Self {
+            bpe: tiktoken_rs::o200k_base_singleton(),
+        }
+    }
+}
+
+impl TokenEstimator for BpeEstimator {
+    fn count(&self, text: &str) -> usize {
+        self.bpe.encode_ordinary(text).len()
+    }
+}
+
+/// Estimator for `model`: its tiktoken encoding for OpenAI models (o200k for
+/// ones tiktoken does not know yet), 3.5 characters per token for Claude and
+/// 4 for anything else
+pub fn estimator_for_model(model: &str) -> Arc<dyn TokenEstimator> {
+    match get_tokenizer(model) {
+        Some(Tokenizer::Cl100kBase) => return Arc::new(BpeEstimator::cl100k()),
+        Some(Tokenizer::O200kBase) => return Arc::new(BpeEstimator::o200k()),
+        _ => {}
+    }
+    let openai = ["gpt-", "chatgpt-", "ft:gpt", "o1", "o3", "o4"]
+        .iter()
+        .any(|prefix| model.starts_with(prefix));
+    if openai {
+        Arc::new(BpeEstimator::o200k())
+    } else if model.starts_with("claude") {
+        Arc::new(HeuristicEstimator::new(3.5))
+    } else {
+        Arc::new(HeuristicEstimator::new(4.0))
+    }
+}
+
+/// Context window limits the agent checks before each LLM call
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+pub struct TokenBudget {
+    /// Model context window: prompt plus response
+    pub context_window: usize,
+    /// Tokens kept free for the response
+    pub reserve_output: usize,
+}
+
+impl TokenBudget {
+    pub fn new(context_window: usize, reserve_output: usize) -> Self {
+        Self {
+            context_window,
+            reserve_output,
+        }
+    }
+
+    /// Tokens a prompt may use
+    pub fn prompt_limit(&self) -> usize {
+        self.context_window.saturating_sub(self.reserve_output)
+    }
+
+    /// Tokens still free after `used` prompt tokens
+    pub fn remaining(&self, used: usize) -> usize {
+        self.prompt_limit().saturating_sub(used)
+    }
+
+    /// Fails if a prompt of `prompt_tokens` does not fit
+    pub fn check(&self, prompt_tokens: usize) -> Result<()> {
+        if prompt_tokens > self.prompt_limit() {
+            bail!(
+                "Prompt of ~{} tokens exceeds the token budget of {} ({} context window, {} kept for the response)",
+                prompt_tokens,
+                self.prompt_limit(),
+                self.context_window,
+                self.reserve_output
+            );
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_openai_models_use_bpe() {
+        let text = "The quick brown fox jumps over the lazy dog.";
+        // 10 tokens in both cl100k and o200k
+        assert_eq!(estimator_for_model("gpt-4o").count(text), 10);
+        assert_eq!(estimator_for_model("gpt-4").count(text), 10);
+        assert_eq!(estimator_for_model("o4-mini").count(text), 10);
+        // 44 characters at 3.5 and 4 per token
+        assert_eq!(estimator_for_model("claude-sonnet-4").count(text), 13);
+        assert_eq!(estimator_for_model("gemini-2.0-flash").count(text), 11);
+    }
+
+    #[test]
+    fn test_request_counts_system_tools_and_messages() {
+        let estimator = HeuristicEstimator::new(4.0);
+        let tools = [ToolSchema {
+            name: "read".into(),
+            description: "Read file".into(),
+            input_schema: serde_json::json!({}),
+        }];
+        let messages = [Message::user("12345678")];
+        // system 2 + tool (1 + 3 + 1) + message (4 + 2)
+        assert_eq!(
+            estimator.count_request(Some("abcdefgh"), &messages, &tools),
+            13
+        );
+    }
+
+    #[test]
+    fn test_budget_check() {
+        let budget = TokenBudget::new(1_000, 200);
+        assert_eq!(budget.prompt_limit(), 800);
+        assert_eq!(budget.remaining(500), 300);
+        assert!(budget.check(800).is_ok());
+        let err = budget.check(801).unwrap_err().to_string();
+        assert!(err.contains("~801 tokens exceeds the token budget of 800"));
+    }
+}
@@ -10,7 +10,7 @@ pub enum Role {
     Assistant,
 }
 
-/// Content within a message - text, image, tool call, or tool result
+/// Content within a message - text, image, audio, tool call, or tool result
 #[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(tag = "type", rename_all = "snake_case")]
 pub enum Content {
@@ -21,12 +21,24 @@ pub enum Content {
         data: Vec<u8>,
         mime: String,
     },
+    /// Audio clip (e.g. a voice note); `mime` like "audio/wav" or "audio/mpeg"
+    Audio {
+        data: Vec<u8>,
+        mime: String,
+    },
     ToolCall(ToolCall),
     ToolResult(ToolResult),
     /// Mixed content blocks (assistant can return text + tool calls)
     Mixed {
         parts: Vec<Content>,
     },
+    /// Model reasoning (Anthropic extended thinking). Not part of `extract_text`;
+    /// sent back verbatim so tool-use turns keep their signed reasoning.
+    Thinking {
+        thinking: String,
+        #[serde(default, skip_serializing_if = "String::is_empty")]
+        signature: String,
+    },
 }
 
 /// Tool call request from LLM
@@ -74,6 +86,42 @@ impl Message {
         }
     }
 
+    /// User turn with text followed by an image (e.g. a screenshot and a question)
+    pub fn user_with_image(text: &str, data: Vec<u8>, mime: &str) -> Self {
+        Self {
+            role: Role::User,
+            content: Content::Mixed {
+                parts: vec![
+                    Content::Text {
+                        text: text.to_string(),
+                    },
+                    Content::Image {
+                        data,
+                        mime: mime.to_string(),
+                    },
+                ],
+            },
+        }
+    }
+
+    /// User turn with text followed by an audio clip
+    pub fn user_with_audio(text: &str, data: Vec<u8>, mime: &str) -> Self {
+        Self {
+            role: Role::User,
+            content: Content::Mixed {
+                parts: vec![
+                    Content::Text {
+                        text: text.to_string(),
+                    },
+                    Content::Audio {
+                        data,
+                        mime: mime.to_string(),
+                    },
+                ],
+            },
+        }
+    }
+
     pub fn assistant(content: Content) -> Self {
         Self {
             role: Role::Assistant,
@@ -117,12 +165,16 @@ pub enum StopReason {
 pub struct Usage {
     pub input_tokens: u32,
     pub output_tokens: u32,
+    /// Estimated spend in US dollars (0 for models without known pricing)
+    #[serde(default)]
+    pub cost_usd: f64,
 }
 
 impl std::ops::AddAssign for Usage {
     fn add_assign(&mut self, rhs: Self) {
         self.input_tokens += rhs.input_tokens;
         self.output_tokens += rhs.output_tokens;
+        self.cost_usd += rhs.cost_usd;
     }
 }
 
@@ -132,6 +184,62 @@ impl Usage {
     }
 }
 
+/// Price of a model in US dollars per million tokens
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ModelPricing {
+    pub input_per_mtok: f64,
+    pub output_per_mtok: f64,
+}
+
+/// List prices by model name prefix; the longest matching prefix wins
+const MODEL_PRICING: &[(&str, ModelPricing)] = &[
+    ("claude-opus-4", ModelPricing::new(15.0, 75.0)),
+    ("claude-sonnet-4", ModelPricing::new(3.0, 15.0)),
+    ("claude-haiku-4", ModelPricing::new(1.0, 5.0)),
+    ("claude-3-7-sonnet", ModelPricing::new(3.0, 15.0)),
+    ("claude-3-5-sonnet", ModelPricing::new(3.0, 15.0)),
+    ("claude-3-5-haiku", ModelPricing::new(0.8, 4.0)),
+    ("gpt-4o", ModelPricing::new(2.5, 10.0)),
+    ("gpt-4o-mini", ModelPricing::new(0.15, 0.6)),
+    ("gpt-4.1", ModelPricing::new(2.0, 8.0)),
+    ("gpt-4.1-mini", ModelPricing::new(0.4, 1.6)),
+    ("gpt-4.1-nano", ModelPricing::new(0.1, 0.4)),
+    ("o1", ModelPricing::new(15.0, 60.0)),
+    ("o3", ModelPricing::new(2.0, 8.0)),
+    ("o3-mini", ModelPricing::new(1.1, 4.4)),
+    ("o4-mini", ModelPricing::new(1.1, 4.4)),
+    ("gemini-2.5-pro", ModelPricing::new(1.25, 10.0)),
+    ("gemini-2.5-flash", ModelPricing::new(0.3, 2.5)),
+    ("gemini-2.0-flash", ModelPricing::new(0.1, 0.4)),
+    ("gemini-1.5-pro", ModelPricing::new(1.25, 5.0)),
+    ("gemini-1.5-flash", ModelPricing::new(0.075, 0.3)),
+];
+
+impl ModelPricing {
+    pub const fn new(input_per_mtok: f64, output_per_mtok: f64) -> Self {
+        Self {
+            input_per_mtok,
+            output_per_mtok,
+        }
+    }
+
+    /// Pricing for `model`, if it is in the table
+    pub fn for_model(model: &str) -> Option<Self> {
+        MODEL_PRICING
+            .iter()
+            .filter(|(prefix, _)| model.starts_with(prefix))
+            .max_by_key(|(prefix, _)| prefix.len())
+            .map(|(_, pricing)| *pricing)
+    }
+
+    /// Cost in US dollars of the tokens in `usage`
+    pub fn cost(&self, usage: &Usage) -> f64 {
+        (usage.input_tokens as f64 * self.input_per_mtok
+            + usage.output_tokens as f64 * self.output_per_mtok)
+            / 1_000_000.0
+    }
+}
+
 /// LLM generation response
 #[derive(Debug, Clone)]
 pub struct GenerateResponse {
@@ -141,6 +249,53 @@ pub struct GenerateResponse {
     pub model: String,
 }
 
+/// Request class used for model routing
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize, schemars::JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum TaskKind {
+    /// Plain conversational turn
+    Chat,
+    /// Turn where tools are offered to the model
+    ToolUse,
+    /// Condensing history or documents
+    Summarize,
+    /// Short session title generation
+    Title,
+}
+
+impl TaskKind {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            TaskKind::Chat => "chat",
+            TaskKind::ToolUse => "tool_use",
+            TaskKind::Summarize => "summarize",
+            TaskKind::Title => "title",
+        }
+    }
+
+    /// Explicit `config.task`, else ToolUse when tools are offered, else Chat
+    pub fn classify(config: &GenerateConfig, tools: &[ToolSchema]) -> Self {
+        match config.task {
+            Some(task) => task,
+            None if !tools.is_empty() => TaskKind::ToolUse,
+            None => TaskKind::Chat,
+        }
+    }
+}
+
+/// Whether the model may, must, or must not call tools this turn
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ToolChoice {
+    /// Model decides (provider default)
+    #[default]
+    Auto,
+    /// Model must call one of the offered tools
+    Any,
+    /// Tools stay declared but may not be called
+    None,
+}
+
 /// Config for LLM generation request
 #[derive(Debug, Clone)]
 pub struct GenerateConfig {
@@ -148,6 +303,22 @@ pub struct GenerateConfig {
     pub max_tokens: u32,
     pub temperature: f32,
     pub system_prompt: Option<String>,
+    /// Request class for routing (None = inferred from the request)
+    pub task: Option<TaskKind>,
+    /// Tool calling mode; only sent when tools are offered
+    pub tool_choice: ToolChoice,
+    /// Generation stops before emitting any of these (reported as EndTurn)
+    pub stop_sequences: Vec<String>,
+    /// Nucleus sampling cutoff
+    pub top_p: Option<f32>,
+    /// Sample from the k most likely tokens (Anthropic, Gemini)
+    pub top_k: Option<u32>,
+    /// Penalize tokens by how often they already appeared (OpenAI, Gemini)
+    pub frequency_penalty: Option<f32>,
+    /// Penalize tokens that already appeared at all (OpenAI, Gemini)
+    pub presence_penalty: Option<f32>,
+    /// Best-effort deterministic sampling (OpenAI, Gemini)
+    pub seed: Option<u64>,
 }
 
 impl Default for GenerateConfig {
@@ -157,6 +328,14 @@ impl Default for GenerateConfig {
             max_tokens: 4096,
             temperature: 0.7,
             system_prompt: None,
+            task: None,
+            tool_choice: ToolChoice::Auto,
+            stop_sequences: Vec::new(),
+            top_p: None,
+            top_k: None,
+            frequency_penalty: None,
+            presence_penalty: None,
+            seed: None,
         }
     }
 }
@@ -166,6 +345,11 @@ impl Default for GenerateConfig {
 pub enum StreamChunk {
     /// Text delta
     TextDelta(String),
+    /// Reasoning delta (only emitted when the provider is set to show thinking)
+    Thinking(String),
+    /// A finished reasoning block with its signature, always emitted: the
+    /// provider needs it back verbatim in tool-use follow-ups
+    ThinkingBlock { thinking: String, signature: String },
     /// Tool call start
     ToolCallStart { id: String, name: String },
     /// Tool call input delta (partial JSON)
@@ -175,6 +359,8 @@ pub enum StreamChunk {
         stop_reason: StopReason,
         usage: Usage,
     },
+    /// Stream aborted before completion (no Done follows)
+    Error(String),
 }
 
 /// Model capability metadata
@@ -239,6 +425,22 @@ impl Content {
         }
     }
 
+    /// Extract reasoning text from thinking blocks
+    pub fn extract_thinking(&self) -> String {
+        match self {
+            Content::Thinking { thinking, .. } => thinking.clone(),
+            Content::Mixed { parts } => parts
+                .iter()
+                .filter_map(|p| match p {
+                    Content::Thinking { thinking, .. } => Some(thinking.as_str()),
+                    _ => None,
+                })
+                .collect::<Vec<_>>()
+                .join(""),
+            _ => String::new(),
+        }
+    }
+
     /// Extract text from content
     pub fn extract_text(&self) -> String {
         match self {
@@ -255,3 +457,22 @@ impl Content {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_pricing_uses_longest_prefix() {
+        let mini = ModelPricing::for_model("gpt-4o-mini-2024-07-18").unwrap();
+        assert_eq!(mini, ModelPricing::new(0.15, 0.6));
+        let sonnet = ModelPricing::for_model("claude-sonnet-4-20250514").unwrap();
+        let usage = Usage {
+            input_tokens: 1_000_000,
+            output_tokens: 100_000,
+            cost_usd: 0.0,
+        };
+        assert!((sonnet.cost(&usage) - 4.5).abs() < 1e-9);
+        assert!(ModelPricing::for_model("llama3").is_none());
+    }
+}
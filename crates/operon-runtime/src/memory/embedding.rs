@@ -1,15 +1,31 @@
-use anyhow::{Context, Result};
+use anyhow::{bail, Context, Result};
 use async_trait::async_trait;
 use serde::{Deserialize, Serialize};
 use std::time::Duration;
 use tracing::warn;
 
+/// Max inputs per OpenAI embeddings request (API limit is 2048)
+const OPENAI_MAX_BATCH: usize = 2048;
+
 /// Abstraction for text → vector embedding providers.
 #[async_trait]
 pub trait EmbeddingProvider: Send + Sync {
     async fn embed(&self, text: &str) -> Result<Vec<f32>>;
-    async fn embed_batch(&self, texts: &[String]) -> Result<Vec<Vec<f32>>>;
+
+    /// One vector per input, in input order. The default embeds one text at a
+    /// time; providers with a multi-input endpoint should override it.
+    async fn embed_batch(&self, texts: &[&str]) -> Result<Vec<Vec<f32>>> {
+        let mut results = Vec::with_capacity(texts.len());
+        for text in texts {
+            results.push(self.embed(text).await?);
+        }
+        Ok(results)
+    }
+
     fn dimensions(&self) -> usize;
+
+    /// Model name, recorded with the vectors to detect model changes
+    fn model(&self) -> &str;
 }
 
 /// OpenAI embedding provider using text-embedding-3-small (1536 dims).
@@ -38,9 +54,9 @@ impl OpenAIEmbedding {
 }
 
 #[derive(Serialize)]
-struct EmbeddingRequest {
-    model: String,
-    input: Vec<String>,
+struct EmbeddingRequest<'a> {
+    model: &'a str,
+    input: &'a [&'a str],
 }
 
 #[derive(Deserialize)]
@@ -50,27 +66,63 @@ struct EmbeddingResponse {
 
 #[derive(Deserialize)]
 struct EmbeddingData {
+    #[serde(default)]
+    index: usize,
     embedding: Vec<f32>,
 }
 
+impl EmbeddingResponse {
+    /// Vectors in request order; the API tags each with its input index
+    fn into_vectors(mut self, expected: usize) -> Result<Vec<Vec<f32>>> {
+        if self.data.len() != expected {
+            bail!(
+                "Embedding API returned {} vectors for {} inputs",
+                self.data.len(),
+                expected
+            );
+        }
+        self.data.sort_by_key(|d| d.index);
+        Ok(self.data.into_iter().map(|d| d.embedding).collect())
+    }
+}
+
 #[async_trait]
 impl EmbeddingProvider for OpenAIEmbedding {
     async fn embed(&self, text: &str) -> Result<Vec<f32>> {
-        let results = self.embed_batch(&[text.to_string()]).await?;
+        let results = self.embed_batch(&[text]).await?;
         results
             .into_iter()
             .next()
             .context("Empty embedding response")
     }
 
-    async fn embed_batch(&self, texts: &[String]) -> Result<Vec<Vec<f32>>> {
+    async fn embed_batch(&self, texts: &[&str]) -> Result<Vec<Vec<f32>>> {
+        let mut results = Vec::with_capacity(texts.len());
+        for chunk in texts.chunks(OPENAI_MAX_BATCH) {
+            results.extend(self.request_embeddings(chunk).await?);
+        }
+        Ok(results)
+    }
+
+    fn dimensions(&self) -> usize {
+        self.dims
+    }
+
+    fn model(&self) -> &str {
+        &self.model
+    }
+}
+
+impl OpenAIEmbedding {
+    /// One embeddings API call for up to `OPENAI_MAX_BATCH` inputs, with retries
+    async fn request_embeddings(&self, texts: &[&str]) -> Result<Vec<Vec<f32>>> {
         let max_retries = 3u32;
         let mut attempt = 0;
 
         loop {
             let body = EmbeddingRequest {
-                model: self.model.clone(),
-                input: texts.to_vec(),
+                model: &self.model,
+                input: texts,
             };
 
             let resp = self
@@ -83,14 +135,17 @@ impl EmbeddingProvider for OpenAIEmbedding {
 
             match resp {
                 Ok(r) if r.status().is_success() => {
-                    let data: EmbeddingResponse =
-                        r.json().await.context("Failed to parse embedding response")?;
-                    return Ok(data.data.into_iter().map(|d| d.embedding).collect());
+                    let data: EmbeddingResponse = r
+                        .json()
+                        .await
+                        .context("Failed to parse embedding response")?;
+                    return data.into_vectors(texts.len());
                 }
                 Ok(r) => {
                     let status = r.status();
                     let text = r.text().await.unwrap_or_default();
-                    if attempt < max_retries && (status.is_server_error() || status.as_u16() == 429) {
+                    if attempt < max_retries && (status.is_server_error() || status.as_u16() == 429)
+                    {
                         let delay = Duration::from_secs(2u64.pow(attempt));
                         warn!(attempt, %status, "Embedding API error, retrying in {:?}", delay);
                         tokio::time::sleep(delay).await;
@@ -112,10 +167,6 @@ impl EmbeddingProvider for OpenAIEmbedding {
             }
         }
     }
-
-    fn dimensions(&self) -> usize {
-        self.dims
-    }
 }
 
 /// Mock embedding provider for testing — returns deterministic vectors.
@@ -147,15 +198,39 @@ impl EmbeddingProvider for MockEmbedding {
         Ok(vec)
     }
 
-    async fn embed_batch(&self, texts: &[String]) -> Result<Vec<Vec<f32>>> {
-        let mut results = Vec::with_capacity(texts.len());
-        for text in texts {
-            results.push(self.embed(text).await?);
-        }
-        Ok(results)
-    }
-
     fn dimensions(&self) -> usize {
         self.dims
     }
+
+    fn model(&self) -> &str {
+        "mock"
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_response_vectors_follow_input_order() {
+        let response: EmbeddingResponse = serde_json::from_str(
+            r#"{"data":[{"index":1,"embedding":[1.0]},{"index":0,"embedding":[0.0]}]}"#,
+        )
+        .unwrap();
+        assert_eq!(
+            response.into_vectors(2).unwrap(),
+            vec![vec![0.0], vec![1.0]]
+        );
+
+        let response: EmbeddingResponse =
+            serde_json::from_str(r#"{"data":[{"index":0,"embedding":[0.0]}]}"#).unwrap();
+        assert!(response.into_vectors(2).is_err());
+    }
+
+    #[tokio::test]
+    async fn test_default_embed_batch_matches_embed() {
+        let mock = MockEmbedding::new(8);
+        let batch = mock.embed_batch(&["a", "b"]).await.unwrap();
+        assert_eq!(batch[1], mock.embed("b").await.unwrap());
+    }
 }
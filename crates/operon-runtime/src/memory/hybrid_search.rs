@@ -1,5 +1,41 @@
+use crate::memory::types::MergeStrategy;
 use std::collections::HashMap;
 
+/// RRF constant used by hybrid search
+pub const RRF_K: u32 = 60;
+
+/// Merge vector and FTS results with the query's strategy
+pub fn merge(
+    strategy: &MergeStrategy,
+    vector_results: &[(String, f32)],
+    fts_results: &[(String, f64)],
+    limit: usize,
+) -> Vec<(String, f64)> {
+    match *strategy {
+        MergeStrategy::Rrf => rrf_merge(vector_results, fts_results, RRF_K, limit),
+        MergeStrategy::MinMax {
+            vector_weight,
+            fts_weight,
+        } => weighted_merge(
+            vector_results,
+            fts_results,
+            min_max,
+            (vector_weight, fts_weight),
+            limit,
+        ),
+        MergeStrategy::ZScore {
+            vector_weight,
+            fts_weight,
+        } => weighted_merge(
+            vector_results,
+            fts_results,
+            z_score,
+            (vector_weight, fts_weight),
+            limit,
+        ),
+    }
+}
+
 /// Reciprocal Rank Fusion (RRF) merge algorithm.
 /// Combines ranked results from vector and FTS searches.
 /// Score = Σ 1/(k + rank) where k=60 (standard RRF constant).
@@ -25,6 +61,96 @@ pub fn rrf_merge(
     merged
 }
 
+/// Normalize each source with `normalize` and sum the weighted scores. BM25
+/// is negated first (SQLite ranks lower as better); a document missing from
+/// one source gets that source's worst normalized score.
+fn weighted_merge(
+    vector_results: &[(String, f32)],
+    fts_results: &[(String, f64)],
+    normalize: fn(&[f64]) -> Vec<f64>,
+    (vector_weight, fts_weight): (f64, f64),
+    limit: usize,
+) -> Vec<(String, f64)> {
+    let vector: Vec<f64> = vector_results.iter().map(|(_, s)| *s as f64).collect();
+    let fts: Vec<f64> = fts_results.iter().map(|(_, s)| -s).collect();
+    let sources = [
+        (
+            vector_results
+                .iter()
+                .map(|(id, _)| id)
+                .zip(normalize(&vector))
+                .collect::<Vec<_>>(),
+            vector_weight,
+        ),
+        (
+            fts_results
+                .iter()
+                .map(|(id, _)| id)
+                .zip(normalize(&fts))
+                .collect(),
+            fts_weight,
+        ),
+    ];
+
+    let mut scores: HashMap<String, f64> = HashMap::new();
+    for (normalized, _) in &sources {
+        for (id, _) in normalized {
+            scores.entry((*id).clone()).or_default();
+        }
+    }
+    for (normalized, weight) in &sources {
+        let worst = normalized
+            .iter()
+            .map(|(_, s)| *s)
+            .fold(f64::INFINITY, f64::min);
+        let by_id: HashMap<&String, f64> = normalized.iter().copied().collect();
+        for (id, score) in scores.iter_mut() {
+            let value = by_id.get(id).copied().unwrap_or(worst);
+            if value.is_finite() {
+                *score += weight * value;
+            }
+        }
+    }
+
+    let mut merged: Vec<(String, f64)> = scores.into_iter().collect();
+    merged.sort_by(|a, b| b.1.partial_cmp(&a.1).unwrap_or(std::cmp::Ordering::Equal));
+    merged.truncate(limit);
+    merged
+}
+
+/// Scale to 0..1; all-equal scores map to 1
+fn min_max(scores: &[f64]) -> Vec<f64> {
+    let min = scores.iter().copied().fold(f64::INFINITY, f64::min);
+    let max = scores.iter().copied().fold(f64::NEG_INFINITY, f64::max);
+    scores
+        .iter()
+        .map(|s| {
+            if max > min {
+                (s - min) / (max - min)
+            } else {
+                1.0
+            }
+        })
+        .collect()
+}
+
+/// Standard scores; all-equal scores map to 0
+fn z_score(scores: &[f64]) -> Vec<f64> {
+    let n = scores.len() as f64;
+    let mean = scores.iter().sum::<f64>() / n;
+    let std_dev = (scores.iter().map(|s| (s - mean).powi(2)).sum::<f64>() / n).sqrt();
+    scores
+        .iter()
+        .map(|s| {
+            if std_dev > 0.0 {
+                (s - mean) / std_dev
+            } else {
+                0.0
+            }
+        })
+        .collect()
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -58,6 +184,42 @@ mod tests {
         assert!(results.is_empty());
     }
 
+    #[test]
+    fn test_weighted_merges_normalize_scales() {
+        // Vector scores are close together, BM25 scores far apart
+        let vector = vec![("a".into(), 0.91f32), ("b".into(), 0.90)];
+        let fts = vec![("b".into(), -12.0f64), ("c".into(), -2.0)];
+
+        let min_max = MergeStrategy::MinMax {
+            vector_weight: 0.5,
+            fts_weight: 0.6,
+        };
+        let results = merge(&min_max, &vector, &fts, 10);
+        let score = |id: &str| results.iter().find(|(i, _)| i == id).unwrap().1;
+        assert_eq!(results[0].0, "b");
+        assert!((score("b") - 0.6).abs() < 1e-9);
+        assert!((score("a") - 0.5).abs() < 1e-9);
+        assert!(score("c").abs() < 1e-9);
+
+        // Weights shift the ranking toward one source
+        let vector_heavy = MergeStrategy::ZScore {
+            vector_weight: 1.0,
+            fts_weight: 0.0,
+        };
+        let results = merge(&vector_heavy, &vector, &fts, 10);
+        assert_eq!(results[0].0, "a");
+
+        let fts_heavy = MergeStrategy::ZScore {
+            vector_weight: 0.0,
+            fts_weight: 1.0,
+        };
+        let results = merge(&fts_heavy, &vector, &fts, 2);
+        assert_eq!(results.len(), 2);
+        assert_eq!(results[0].0, "b");
+
+        assert!(merge(&min_max, &[], &[], 10).is_empty());
+    }
+
     #[test]
     fn test_rrf_merge_limit() {
         let vector = vec![("a".into(), 1.0f32), ("b".into(), 0.5)];
@@ -2,6 +2,7 @@ use crate::memory::embedding::EmbeddingProvider;
 use crate::memory::text_search::TextSearchIndex;
 use crate::memory::types::{Document, IndexStats};
 use crate::memory::vector_store::VectorStore;
+use crate::storage::blocking;
 use anyhow::{Context, Result};
 use notify::{Event, EventKind, RecursiveMode, Watcher};
 use sha2::{Digest, Sha256};
@@ -11,7 +12,11 @@ use std::sync::Arc;
 use tokio::sync::mpsc;
 use tracing::{debug, info, warn};
 
-/// Indexes workspace files into text search and vector stores.
+/// Documents embedded per provider call during a full workspace index
+pub(crate) const EMBED_BATCH_SIZE: usize = 32;
+
+/// Indexes workspace files into text search and vector stores. Database
+/// writes run on the blocking pool so large files do not stall other tasks.
 pub struct DocumentIndexer {
     workspace: PathBuf,
     text_index: Arc<TextSearchIndex>,
@@ -42,6 +47,9 @@ impl DocumentIndexer {
         let files = collect_text_files(&self.workspace)?;
         info!(count = files.len(), "Indexing workspace files");
 
+        // Embeddings are requested in batches to amortize HTTP round trips
+        let mut pending = Vec::new();
+
         for path in &files {
             let rel_path = match safe_rel_path(path, &self.workspace) {
                 Some(r) => r,
@@ -54,22 +62,30 @@ impl DocumentIndexer {
             let doc_id = rel_path.clone();
             seen_ids.insert(doc_id.clone());
 
-            match self.index_file(&doc_id, path).await {
-                Ok(true) => stats.files_indexed += 1,
-                Ok(false) => stats.files_skipped += 1,
+            match self.index_text(&doc_id, path).await {
+                Ok(Some(doc)) => {
+                    stats.files_indexed += 1;
+                    pending.push(doc);
+                    if pending.len() >= EMBED_BATCH_SIZE {
+                        self.embed_documents(&pending).await;
+                        pending.clear();
+                    }
+                }
+                Ok(None) => stats.files_skipped += 1,
                 Err(e) => {
                     warn!(path = %rel_path, error = %e, "Failed to index file");
                     stats.errors += 1;
                 }
             }
         }
+        self.embed_documents(&pending).await;
 
         // Remove stale documents (files deleted from workspace)
-        if let Ok(existing_ids) = self.text_index.list_document_ids() {
+        let text_index = self.text_index.clone();
+        if let Ok(existing_ids) = blocking(move || text_index.list_document_ids()).await {
             for id in existing_ids {
                 if !seen_ids.contains(&id) {
-                    let _ = self.text_index.remove_document(&id);
-                    let _ = self.vector_store.remove(&id);
+                    self.remove_document(&id).await;
                     stats.files_removed += 1;
                     debug!(id = %id, "Removed stale document");
                 }
@@ -82,12 +98,26 @@ impl DocumentIndexer {
 
     /// Index a single file. Returns true if indexed, false if skipped (unchanged).
     async fn index_file(&self, doc_id: &str, path: &Path) -> Result<bool> {
+        match self.index_text(doc_id, path).await? {
+            Some(doc) => {
+                self.embed_documents(std::slice::from_ref(&doc)).await;
+                Ok(true)
+            }
+            None => Ok(false),
+        }
+    }
+
+    /// Add a file to the full-text index. Returns the document still to be
+    /// embedded, or None if the file was skipped (unchanged, binary, too large).
+    async fn index_text(&self, doc_id: &str, path: &Path) -> Result<Option<Document>> {
         // Skip files larger than 10MB to avoid OOM and embedding API limits
         const MAX_FILE_SIZE: u64 = 10 * 1024 * 1024;
-        let metadata = tokio::fs::metadata(path).await.context("Failed to read metadata")?;
+        let metadata = tokio::fs::metadata(path)
+            .await
+            .context("Failed to read metadata")?;
         if metadata.len() > MAX_FILE_SIZE {
             warn!(path = %path.display(), size = metadata.len(), "Skipping large file");
-            return Ok(false);
+            return Ok(None);
         }
 
         let bytes = tokio::fs::read(path).await.context("Failed to read file")?;
@@ -95,43 +125,79 @@ impl DocumentIndexer {
         // Skip binary files (null byte heuristic)
         let check_len = bytes.len().min(8192);
         if bytes[..check_len].contains(&0) {
-            return Ok(false);
+            return Ok(None);
         }
 
         let content = String::from_utf8(bytes).context("File is not valid UTF-8")?;
         let hash = compute_hash(&content);
 
-        // Skip if content unchanged
-        if let Ok(Some(existing_hash)) = self.text_index.get_content_hash(doc_id) {
-            if existing_hash == hash {
-                return Ok(false);
-            }
-        }
-
-        let rel_path = safe_rel_path(path, &self.workspace)
-            .unwrap_or_else(|| doc_id.to_string());
-
-        // Index into FTS
+        let rel_path = safe_rel_path(path, &self.workspace).unwrap_or_else(|| doc_id.to_string());
         let doc = Document {
             id: doc_id.to_string(),
             path: rel_path,
-            content: content.clone(),
+            content,
             content_hash: hash,
             metadata: None,
         };
-        self.text_index.index_document(&doc)?;
 
-        // Get embedding and store vector
-        match self.embedder.embed(&content).await {
-            Ok(embedding) => {
-                self.vector_store.upsert(doc_id, &embedding)?;
+        let text_index = self.text_index.clone();
+        blocking(move || {
+            // Skip if content unchanged
+            if let Ok(Some(existing_hash)) = text_index.get_content_hash(&doc.id) {
+                if existing_hash == doc.content_hash {
+                    return Ok(None);
+                }
+            }
+            // Index into FTS
+            text_index.index_document(&doc)?;
+            Ok(Some(doc))
+        })
+        .await
+    }
+
+    /// Drop a document from both indexes, ignoring errors
+    async fn remove_document(&self, id: &str) {
+        let (text_index, vector_store, id) = (
+            self.text_index.clone(),
+            self.vector_store.clone(),
+            id.to_string(),
+        );
+        let _ = blocking(move || {
+            let _ = text_index.remove_document(&id);
+            vector_store.remove(&id)
+        })
+        .await;
+    }
+
+    /// Embed documents in one provider call and store their vectors.
+    /// Failures leave the documents FTS-only.
+    async fn embed_documents(&self, docs: &[Document]) {
+        if docs.is_empty() {
+            return;
+        }
+        let texts: Vec<&str> = docs.iter().map(|d| d.content.as_str()).collect();
+        match self.embedder.embed_batch(&texts).await {
+            Ok(embeddings) => {
+                let vector_store = self.vector_store.clone();
+                let vectors: Vec<(String, Vec<f32>)> =
+                    docs.iter().map(|d| d.id.clone()).zip(embeddings).collect();
+                let stored = blocking(move || {
+                    for (id, embedding) in &vectors {
+                        if let Err(e) = vector_store.upsert(id, embedding) {
+                            warn!(doc_id = %id, error = %e, "Failed to store embedding");
+                        }
+                    }
+                    Ok(())
+                })
+                .await;
+                if let Err(e) = stored {
+                    warn!(count = docs.len(), error = %e, "Failed to store embeddings");
+                }
             }
             Err(e) => {
-                warn!(doc_id = %doc_id, error = %e, "Embedding failed, FTS-only index");
+                warn!(count = docs.len(), error = %e, "Embedding failed, FTS-only index");
             }
         }
-
-        Ok(true)
     }
 
     /// Watch workspace for file changes and auto-reindex.
@@ -177,8 +243,7 @@ impl DocumentIndexer {
                     }
                 } else {
                     // File deleted
-                    let _ = self.text_index.remove_document(&rel_path);
-                    let _ = self.vector_store.remove(&rel_path);
+                    self.remove_document(&rel_path).await;
                     debug!(path = %rel_path, "Removed deleted file from index");
                 }
             }
@@ -206,7 +271,11 @@ fn collect_text_files(dir: &Path) -> Result<Vec<PathBuf>> {
     Ok(files)
 }
 
-fn collect_recursive(dir: &Path, out: &mut Vec<PathBuf>, visited: &mut HashSet<PathBuf>) -> Result<()> {
+fn collect_recursive(
+    dir: &Path,
+    out: &mut Vec<PathBuf>,
+    visited: &mut HashSet<PathBuf>,
+) -> Result<()> {
     // Symlink loop protection
     if let Ok(canonical) = dir.canonicalize() {
         if !visited.insert(canonical) {
@@ -221,7 +290,10 @@ fn collect_recursive(dir: &Path, out: &mut Vec<PathBuf>, visited: &mut HashSet<P
         let name = entry.file_name().to_string_lossy().to_string();
 
         // Skip hidden files/dirs and common non-text directories
-        if name.starts_with('.') || name == "node_modules" || name == "target" || name == "__pycache__"
+        if name.starts_with('.')
+            || name == "node_modules"
+            || name == "target"
+            || name == "__pycache__"
         {
             continue;
         }
@@ -237,16 +309,44 @@ fn collect_recursive(dir: &Path, out: &mut Vec<PathBuf>, visited: &mut HashSet<P
 
 /// Simple heuristic: check file extension for known text types.
 fn is_text_path(path: &Path) -> bool {
-    let ext = path
-        .extension()
-        .and_then(|e| e.to_str())
-        .unwrap_or("");
+    let ext = path.extension().and_then(|e| e.to_str()).unwrap_or("");
     matches!(
         ext,
-        "rs" | "py" | "js" | "ts" | "tsx" | "jsx" | "json" | "toml" | "yaml" | "yml"
-            | "md" | "txt" | "html" | "css" | "scss" | "sql" | "sh" | "bash" | "zsh"
-            | "go" | "java" | "kt" | "swift" | "c" | "cpp" | "h" | "hpp" | "rb"
-            | "lua" | "vim" | "conf" | "cfg" | "ini" | "env" | "xml" | "csv"
+        "rs" | "py"
+            | "js"
+            | "ts"
+            | "tsx"
+            | "jsx"
+            | "json"
+            | "toml"
+            | "yaml"
+            | "yml"
+            | "md"
+            | "txt"
+            | "html"
+            | "css"
+            | "scss"
+            | "sql"
+            | "sh"
+            | "bash"
+            | "zsh"
+            | "go"
+            | "java"
+            | "kt"
+            | "swift"
+            | "c"
+            | "cpp"
+            | "h"
+            | "hpp"
+            | "rb"
+            | "lua"
+            | "vim"
+            | "conf"
+            | "cfg"
+            | "ini"
+            | "env"
+            | "xml"
+            | "csv"
     )
 }
 
@@ -255,3 +355,67 @@ fn compute_hash(content: &str) -> String {
     hasher.update(content.as_bytes());
     format!("{:x}", hasher.finalize())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::memory::embedding::MockEmbedding;
+    use async_trait::async_trait;
+    use std::sync::Mutex;
+
+    /// Records the size of every embed_batch call
+    struct CountingEmbedding {
+        inner: MockEmbedding,
+        batches: Mutex<Vec<usize>>,
+    }
+
+    #[async_trait]
+    impl EmbeddingProvider for CountingEmbedding {
+        async fn embed(&self, text: &str) -> Result<Vec<f32>> {
+            self.inner.embed(text).await
+        }
+
+        async fn embed_batch(&self, texts: &[&str]) -> Result<Vec<Vec<f32>>> {
+            self.batches.lock().unwrap().push(texts.len());
+            self.inner.embed_batch(texts).await
+        }
+
+        fn dimensions(&self) -> usize {
+            self.inner.dimensions()
+        }
+
+        fn model(&self) -> &str {
+            self.inner.model()
+        }
+    }
+
+    #[tokio::test]
+    async fn test_index_workspace_embeds_in_batches() {
+        let dir = tempfile::tempdir().unwrap();
+        let workspace = dir.path().join("ws");
+        std::fs::create_dir(&workspace).unwrap();
+        for i in 0..EMBED_BATCH_SIZE + 3 {
+            std::fs::write(workspace.join(format!("f{}.md", i)), format!("note {}", i)).unwrap();
+        }
+
+        let db = dir.path().join("memory.db");
+        let embedder = Arc::new(CountingEmbedding {
+            inner: MockEmbedding::new(8),
+            batches: Mutex::new(Vec::new()),
+        });
+        let indexer = DocumentIndexer::new(
+            workspace,
+            Arc::new(TextSearchIndex::new(&db).unwrap()),
+            Arc::new(VectorStore::new(&db, 8).unwrap()),
+            embedder.clone(),
+        );
+
+        let stats = indexer.index_workspace().await.unwrap();
+        assert_eq!(stats.files_indexed, EMBED_BATCH_SIZE + 3);
+        assert_eq!(*embedder.batches.lock().unwrap(), vec![EMBED_BATCH_SIZE, 3]);
+
+        // Unchanged files are skipped and not re-embedded
+        indexer.index_workspace().await.unwrap();
+        assert_eq!(embedder.batches.lock().unwrap().len(), 2);
+    }
+}
@@ -1,29 +1,35 @@
 pub mod embedding;
 pub mod hybrid_search;
 pub mod indexer;
+pub mod sqlite;
 pub mod text_search;
 pub mod types;
 pub mod vector_store;
 
 use crate::memory::embedding::EmbeddingProvider;
-use crate::memory::hybrid_search::rrf_merge;
-use crate::memory::indexer::DocumentIndexer;
+use crate::memory::indexer::{DocumentIndexer, EMBED_BATCH_SIZE};
 use crate::memory::text_search::TextSearchIndex;
-use crate::memory::types::{SearchQuery, SearchResult, SearchSource};
+use crate::memory::types::{
+    EmbeddingInfo, MigrationStats, SearchQuery, SearchResult, SearchSource,
+};
 use crate::memory::vector_store::VectorStore;
-use anyhow::Result;
+use crate::storage::blocking;
+use anyhow::{Context, Result};
 use std::path::{Path, PathBuf};
 use std::sync::Arc;
+use tracing::info;
 
 /// Orchestrates text search, vector search, and hybrid search.
+/// Index reads run on the blocking pool, off the async workers.
 pub struct MemoryManager {
-    text_index: Arc<TextSearchIndex>,
-    vector_store: Arc<VectorStore>,
+    indexes: Indexes,
     embedder: Arc<dyn EmbeddingProvider>,
     indexer: Arc<DocumentIndexer>,
 }
 
 impl MemoryManager {
+    /// Fails if the index was embedded with another model or dimension; see
+    /// [`MemoryManager::migrate`]
     pub fn new(
         db_path: &Path,
         workspace: PathBuf,
@@ -32,6 +38,7 @@ impl MemoryManager {
         let dims = embedder.dimensions();
         let text_index = Arc::new(TextSearchIndex::new(db_path)?);
         let vector_store = Arc::new(VectorStore::new(db_path, dims)?);
+        vector_store.check_embedding(embedder.model())?;
         let indexer = Arc::new(DocumentIndexer::new(
             workspace,
             text_index.clone(),
@@ -40,13 +47,61 @@ impl MemoryManager {
         ));
 
         Ok(Self {
-            text_index,
-            vector_store,
+            indexes: Indexes {
+                text_index,
+                vector_store,
+            },
             embedder,
             indexer,
         })
     }
 
+    /// Re-embed every indexed document with `embedder`. All embeddings are
+    /// computed first and then swapped in with one transaction, so a failure
+    /// leaves the index as it was.
+    pub async fn migrate(
+        db_path: &Path,
+        embedder: Arc<dyn EmbeddingProvider>,
+    ) -> Result<MigrationStats> {
+        let text_index = TextSearchIndex::new(db_path)?;
+        let vector_store = VectorStore::new(db_path, embedder.dimensions())?;
+        let from = vector_store.embedding_info()?;
+
+        let mut documents = Vec::new();
+        for id in text_index.list_document_ids()? {
+            if let Some(content) = text_index.get_document_content(&id)? {
+                documents.push((id, content));
+            }
+        }
+        let mut vectors = Vec::with_capacity(documents.len());
+        for batch in documents.chunks(EMBED_BATCH_SIZE) {
+            let texts: Vec<&str> = batch.iter().map(|(_, c)| c.as_str()).collect();
+            let embeddings = embedder.embed_batch(&texts).await?;
+            if embeddings.len() != batch.len() {
+                anyhow::bail!(
+                    "Embedding provider returned {} vectors for {} documents",
+                    embeddings.len(),
+                    batch.len()
+                );
+            }
+            vectors.extend(batch.iter().map(|(id, _)| id.clone()).zip(embeddings));
+        }
+        let documents = vectors.len();
+        let model = embedder.model().to_string();
+        blocking(move || vector_store.replace_all(&model, &vectors)).await?;
+
+        let stats = MigrationStats {
+            documents,
+            from,
+            to: EmbeddingInfo {
+                model: embedder.model().to_string(),
+                dimensions: embedder.dimensions(),
+            },
+        };
+        info!(documents = stats.documents, model = %stats.to.model, dimensions = stats.to.dimensions, "Memory index re-embedded");
+        Ok(stats)
+    }
+
     /// Run initial workspace indexing and start file watcher.
     pub async fn start_indexing(&self) -> Result<tokio::task::JoinHandle<()>> {
         // Initial full index
@@ -57,39 +112,104 @@ impl MemoryManager {
 
     /// Search memory using the specified source (vector, FTS, or hybrid).
     pub async fn search(&self, query: SearchQuery) -> Result<Vec<SearchResult>> {
-        match query.source {
-            SearchSource::FullText => self.search_fts(&query.query, query.limit),
-            SearchSource::Vector => self.search_vector(&query.query, query.limit).await,
-            SearchSource::Hybrid => self.search_hybrid(&query.query, query.limit).await,
+        let embedding = match query.source {
+            SearchSource::FullText => None,
+            SearchSource::Vector | SearchSource::Hybrid => {
+                Some(self.embedder.embed(&query.query).await?)
+            }
+        };
+        let indexes = self.indexes.clone();
+        blocking(move || indexes.search(&query, embedding.as_deref())).await
+    }
+
+    /// Run several searches, embedding every vector/hybrid query in a single
+    /// provider call. Results are returned in query order.
+    pub async fn search_batch(&self, queries: &[SearchQuery]) -> Result<Vec<Vec<SearchResult>>> {
+        let texts: Vec<&str> = queries
+            .iter()
+            .filter(|q| q.source != SearchSource::FullText)
+            .map(|q| q.query.as_str())
+            .collect();
+        let mut embeddings = if texts.is_empty() {
+            Vec::new()
+        } else {
+            self.embedder.embed_batch(&texts).await?
+        }
+        .into_iter();
+
+        let mut jobs = Vec::with_capacity(queries.len());
+        for query in queries {
+            let embedding = match query.source {
+                SearchSource::FullText => None,
+                SearchSource::Vector | SearchSource::Hybrid => Some(
+                    embeddings
+                        .next()
+                        .context("Embedding provider returned too few vectors")?,
+                ),
+            };
+            jobs.push((query.clone(), embedding));
+        }
+        let indexes = self.indexes.clone();
+        blocking(move || {
+            jobs.iter()
+                .map(|(query, embedding)| indexes.search(query, embedding.as_deref()))
+                .collect()
+        })
+        .await
+    }
+}
+
+/// Handles on the search indexes, cloned into blocking tasks
+#[derive(Clone)]
+struct Indexes {
+    text_index: Arc<TextSearchIndex>,
+    vector_store: Arc<VectorStore>,
+}
+
+impl Indexes {
+    /// Run one query; `embedding` is required for vector and hybrid sources
+    fn search(&self, query: &SearchQuery, embedding: Option<&[f32]>) -> Result<Vec<SearchResult>> {
+        match (&query.source, embedding) {
+            (SearchSource::FullText, _) => self.search_fts(query),
+            (SearchSource::Vector, Some(embedding)) => self.vector_results(query, embedding),
+            (SearchSource::Hybrid, Some(embedding)) => self.hybrid_results(query, embedding),
+            (_, None) => anyhow::bail!("Missing query embedding"),
         }
     }
 
-    fn search_fts(&self, query: &str, limit: usize) -> Result<Vec<SearchResult>> {
-        let results = self.text_index.search(query, limit)?;
+    fn search_fts(&self, query: &SearchQuery) -> Result<Vec<SearchResult>> {
+        let results = self
+            .text_index
+            .search_matching(&query.query, query.limit, |id| query.matches_path(id))?;
         results
             .into_iter()
             .map(|(id, score)| self.build_result(&id, score, SearchSource::FullText))
             .collect()
     }
 
-    async fn search_vector(&self, query: &str, limit: usize) -> Result<Vec<SearchResult>> {
-        let query_emb = self.embedder.embed(query).await?;
-        let results = self.vector_store.search(&query_emb, limit)?;
+    fn vector_results(&self, query: &SearchQuery, query_emb: &[f32]) -> Result<Vec<SearchResult>> {
+        let results = self
+            .vector_store
+            .search_matching(query_emb, query.limit, |id| query.matches_path(id))?;
         results
             .into_iter()
             .map(|(id, score)| self.build_result(&id, score as f64, SearchSource::Vector))
             .collect()
     }
 
-    async fn search_hybrid(&self, query: &str, limit: usize) -> Result<Vec<SearchResult>> {
+    fn hybrid_results(&self, query: &SearchQuery, query_emb: &[f32]) -> Result<Vec<SearchResult>> {
         // Fetch more results from each source for better RRF merging
-        let fetch_limit = limit * 3;
+        let fetch_limit = query.limit * 3;
+        let keep = |id: &str| query.matches_path(id);
 
-        let fts_results = self.text_index.search(query, fetch_limit)?;
-        let query_emb = self.embedder.embed(query).await?;
-        let vector_results = self.vector_store.search(&query_emb, fetch_limit)?;
+        let fts_results = self
+            .text_index
+            .search_matching(&query.query, fetch_limit, keep)?;
+        let vector_results = self
+            .vector_store
+            .search_matching(query_emb, fetch_limit, keep)?;
 
-        let merged = rrf_merge(&vector_results, &fts_results, 60, limit);
+        let merged = hybrid_search::merge(&query.merge, &vector_results, &fts_results, query.limit);
 
         merged
             .into_iter()
@@ -119,3 +239,176 @@ impl MemoryManager {
         })
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::memory::embedding::MockEmbedding;
+
+    #[tokio::test]
+    async fn test_migrate_re_embeds_into_new_dimensions() {
+        let dir = tempfile::tempdir().unwrap();
+        let workspace = dir.path().join("ws");
+        std::fs::create_dir(&workspace).unwrap();
+        std::fs::write(workspace.join("a.md"), "alpha notes").unwrap();
+        std::fs::write(workspace.join("b.md"), "beta notes").unwrap();
+        let db = dir.path().join("memory.db");
+
+        let manager =
+            MemoryManager::new(&db, workspace.clone(), Arc::new(MockEmbedding::new(8))).unwrap();
+        manager.indexer.index_workspace().await.unwrap();
+        drop(manager);
+
+        let err = MemoryManager::new(&db, workspace.clone(), Arc::new(MockEmbedding::new(16)))
+            .err()
+            .unwrap();
+        assert!(err.to_string().contains("mock (8 dims)"), "{}", err);
+
+        let stats = MemoryManager::migrate(&db, Arc::new(MockEmbedding::new(16)))
+            .await
+            .unwrap();
+        assert_eq!(stats.documents, 2);
+        assert_eq!(stats.from.unwrap().dimensions, 8);
+        assert_eq!(stats.to.dimensions, 16);
+
+        let manager = MemoryManager::new(&db, workspace, Arc::new(MockEmbedding::new(16))).unwrap();
+        let results = manager
+            .search(SearchQuery {
+                query: "alpha notes".to_string(),
+                limit: 1,
+                source: SearchSource::Vector,
+                path_glob: None,
+                extensions: Vec::new(),
+                merge: types::MergeStrategy::Rrf,
+            })
+            .await
+            .unwrap();
+        assert_eq!(results[0].document_id, "a.md");
+    }
+
+    #[tokio::test]
+    async fn test_search_filters_by_path_glob_and_extension() {
+        let dir = tempfile::tempdir().unwrap();
+        let workspace = dir.path().join("ws");
+        std::fs::create_dir_all(workspace.join("src/net")).unwrap();
+        std::fs::create_dir(workspace.join("docs")).unwrap();
+        for file in [
+            "src/lib.rs",
+            "src/net/tcp.rs",
+            "docs/notes.md",
+            "docs/old.md",
+        ] {
+            std::fs::write(workspace.join(file), format!("needle in {}", file)).unwrap();
+        }
+        let manager = MemoryManager::new(
+            &dir.path().join("memory.db"),
+            workspace,
+            Arc::new(MockEmbedding::new(8)),
+        )
+        .unwrap();
+        manager.indexer.index_workspace().await.unwrap();
+
+        let search = |source: SearchSource, path_glob: Option<&str>, extensions: &[&str]| {
+            let query = SearchQuery {
+                query: "needle".to_string(),
+                limit: 10,
+                source,
+                path_glob: path_glob.map(str::to_string),
+                extensions: extensions.iter().map(|e| e.to_string()).collect(),
+                merge: types::MergeStrategy::default(),
+            };
+            let manager = &manager;
+            async move {
+                let mut paths: Vec<String> = manager
+                    .search(query)
+                    .await
+                    .unwrap()
+                    .into_iter()
+                    .map(|r| r.path)
+                    .collect();
+                paths.sort();
+                paths
+            }
+        };
+
+        let fts = SearchSource::FullText;
+        assert_eq!(search(fts.clone(), None, &[]).await.len(), 4);
+        assert_eq!(
+            search(fts.clone(), None, &["rs"]).await,
+            ["src/lib.rs", "src/net/tcp.rs"]
+        );
+        assert_eq!(
+            search(fts.clone(), Some("src/**/*.rs"), &[]).await,
+            ["src/lib.rs", "src/net/tcp.rs"]
+        );
+        assert_eq!(
+            search(fts.clone(), Some("src/*.rs"), &[]).await,
+            ["src/lib.rs"]
+        );
+        // A pattern without `/` matches file names; extensions ignore case and dots
+        assert_eq!(search(fts, Some("no?es.*"), &[]).await, ["docs/notes.md"]);
+        assert_eq!(
+            search(SearchSource::Vector, None, &[".MD"]).await,
+            ["docs/notes.md", "docs/old.md"]
+        );
+        assert_eq!(
+            search(SearchSource::Hybrid, Some("docs/**"), &["rs"]).await,
+            Vec::<String>::new()
+        );
+    }
+
+    #[test]
+    fn test_replace_all_keeps_old_vectors_on_bad_input() {
+        let dir = tempfile::tempdir().unwrap();
+        let store = VectorStore::new(&dir.path().join("memory.db"), 2).unwrap();
+        store.check_embedding("m1").unwrap();
+        store.upsert("a", &[1.0, 0.0]).unwrap();
+
+        let bad = vec![("a".to_string(), vec![1.0, 0.0, 0.0])];
+        assert!(store.replace_all("m2", &bad).is_err());
+        assert_eq!(store.embedding_info().unwrap().unwrap().model, "m1");
+        assert_eq!(store.search(&[1.0, 0.0], 5).unwrap().len(), 1);
+
+        // Same dimensions but another model is also a mismatch
+        let err = store.check_embedding("m2").unwrap_err();
+        assert!(err.to_string().contains("built with m1"), "{}", err);
+    }
+
+    #[test]
+    fn test_verify_and_compact_vectors() {
+        let dir = tempfile::tempdir().unwrap();
+        let db = dir.path().join("memory.db");
+        let text = TextSearchIndex::new(&db).unwrap();
+        let store = VectorStore::new(&db, 2).unwrap();
+        for id in ["a", "b", "c"] {
+            text.index_document(&types::Document {
+                id: id.to_string(),
+                path: id.to_string(),
+                content: format!("note {}", id),
+                content_hash: id.to_string(),
+                metadata: None,
+            })
+            .unwrap();
+        }
+        store.upsert("a", &[1.0, 0.0]).unwrap();
+        store.upsert("b", &[1.0]).unwrap();
+        store.upsert("gone", &[0.0, 1.0]).unwrap();
+
+        let report = store.verify().unwrap();
+        assert!(!report.is_ok());
+        assert_eq!(report.vectors, 3);
+        assert_eq!(report.orphaned, vec!["gone"]);
+        assert_eq!(report.corrupted, vec!["b"]);
+        assert_eq!(report.missing, vec!["c"]);
+        assert!(report.database_errors.is_empty());
+
+        let stats = store.compact().unwrap();
+        assert_eq!((stats.orphaned_removed, stats.corrupted_removed), (1, 1));
+        text.rebuild().unwrap();
+        let report = store.verify().unwrap();
+        assert_eq!(report.vectors, 1);
+        assert!(report.orphaned.is_empty() && report.corrupted.is_empty());
+        assert_eq!(report.missing, vec!["b", "c"]);
+        assert_eq!(text.search("note", 5).unwrap().len(), 3);
+    }
+}
@@ -0,0 +1,141 @@
+use anyhow::{anyhow, Context, Result};
+use rusqlite::Connection;
+use std::ops::Deref;
+use std::path::{Path, PathBuf};
+use std::sync::{Mutex, MutexGuard};
+use std::time::Duration;
+
+/// How long a statement waits on another connection's lock before failing
+/// with "database is locked"
+pub const BUSY_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Idle read connections kept per pool
+const MAX_IDLE_READERS: usize = 4;
+
+/// Open a connection tuned for concurrent use: WAL journal (readers never
+/// block the writer), NORMAL sync and a busy timeout.
+pub fn open_connection(path: &Path) -> Result<Connection> {
+    let conn = Connection::open(path)
+        .with_context(|| format!("Failed to open database {}", path.display()))?;
+    conn.busy_timeout(BUSY_TIMEOUT)?;
+    conn.pragma_update(None, "journal_mode", "WAL")
+        .context("Failed to enable WAL mode")?;
+    conn.pragma_update(None, "synchronous", "NORMAL")?;
+    Ok(conn)
+}
+
+/// One writer connection plus a pool of readers on the same database, so
+/// searches run while the indexer writes.
+pub struct ConnectionPool {
+    path: PathBuf,
+    writer: Mutex<Connection>,
+    readers: Mutex<Vec<Connection>>,
+}
+
+impl ConnectionPool {
+    pub fn open(path: &Path) -> Result<Self> {
+        Ok(Self {
+            path: path.to_path_buf(),
+            writer: Mutex::new(open_connection(path)?),
+            readers: Mutex::new(Vec::new()),
+        })
+    }
+
+    /// The single write connection; writers queue here instead of on
+    /// SQLite's file lock.
+    pub fn writer(&self) -> Result<MutexGuard<'_, Connection>> {
+        self.writer
+            .lock()
+            .map_err(|e| anyhow!("DB lock poisoned: {}", e))
+    }
+
+    /// An idle read connection, or a new one if all are busy.
+    pub fn reader(&self) -> Result<PooledReader<'_>> {
+        let idle = self
+            .readers
+            .lock()
+            .map_err(|e| anyhow!("DB lock poisoned: {}", e))?
+            .pop();
+        let conn = match idle {
+            Some(conn) => conn,
+            None => open_connection(&self.path)?,
+        };
+        Ok(PooledReader {
+            pool: self,
+            conn: Some(conn),
+        })
+    }
+}
+
+/// Read connection that goes back to its pool when dropped
+pub struct PooledReader<'a> {
+    pool: &'a ConnectionPool,
+    conn: Option<Connection>,
+}
+
+impl Deref for PooledReader<'_> {
+    type Target = Connection;
+
+    fn deref(&self) -> &Connection {
+        self.conn
+            .as_ref()
+            .expect("connection is present until drop")
+    }
+}
+
+impl Drop for PooledReader<'_> {
+    fn drop(&mut self) {
+        if let (Some(conn), Ok(mut idle)) = (self.conn.take(), self.pool.readers.lock()) {
+            if idle.len() < MAX_IDLE_READERS {
+                idle.push(conn);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::memory::text_search::TextSearchIndex;
+    use crate::memory::types::Document;
+    use crate::memory::vector_store::VectorStore;
+    use std::sync::Arc;
+
+    #[test]
+    fn test_concurrent_writers_and_readers_do_not_lock() {
+        let dir = tempfile::tempdir().unwrap();
+        let db = dir.path().join("memory.db");
+        let text = Arc::new(TextSearchIndex::new(&db).unwrap());
+        let vectors = Arc::new(VectorStore::new(&db, 4).unwrap());
+
+        let handles: Vec<_> = (0..4)
+            .map(|t| {
+                let text = text.clone();
+                let vectors = vectors.clone();
+                std::thread::spawn(move || {
+                    for i in 0..25 {
+                        let id = format!("doc-{}-{}", t, i);
+                        text.index_document(&Document {
+                            id: id.clone(),
+                            path: id.clone(),
+                            content: format!("shared words {}", id),
+                            content_hash: id.clone(),
+                            metadata: None,
+                        })
+                        .unwrap();
+                        vectors
+                            .upsert(&id, &[t as f32, i as f32, 1.0, 0.0])
+                            .unwrap();
+                        text.search("shared", 5).unwrap();
+                        vectors.search(&[1.0, 0.0, 0.0, 0.0], 5).unwrap();
+                    }
+                })
+            })
+            .collect();
+        for handle in handles {
+            handle.join().unwrap();
+        }
+
+        assert_eq!(text.document_count().unwrap(), 100);
+        assert_eq!(vectors.count().unwrap(), 100);
+    }
+}
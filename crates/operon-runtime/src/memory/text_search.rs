@@ -1,26 +1,22 @@
+use crate::memory::sqlite::ConnectionPool;
 use crate::memory::types::Document;
-use anyhow::{anyhow, Context, Result};
-use rusqlite::{params, Connection};
+use anyhow::{Context, Result};
+use rusqlite::params;
 use std::path::Path;
-use std::sync::Mutex;
 
 /// Full-text search index backed by SQLite FTS5.
 pub struct TextSearchIndex {
-    conn: Mutex<Connection>,
+    pool: ConnectionPool,
 }
 
 impl TextSearchIndex {
     /// Open or create the SQLite database with FTS5 tables.
     pub fn new(db_path: &Path) -> Result<Self> {
-        let conn = Connection::open(db_path).context("Failed to open memory database")?;
+        let pool = ConnectionPool::open(db_path)?;
 
-        // Enable WAL mode for better concurrent read/write performance
-        conn.pragma_update(None, "journal_mode", "WAL")
-            .context("Failed to enable WAL mode")?;
-        conn.pragma_update(None, "synchronous", "NORMAL")?;
-
-        conn.execute_batch(
-            "CREATE TABLE IF NOT EXISTS documents (
+        pool.writer()?
+            .execute_batch(
+                "CREATE TABLE IF NOT EXISTS documents (
                 id TEXT PRIMARY KEY,
                 path TEXT NOT NULL,
                 content TEXT NOT NULL,
@@ -51,17 +47,15 @@ impl TextSearchIndex {
                 INSERT INTO documents_fts(rowid, content, path)
                 VALUES (new.rowid, new.content, new.path);
             END;",
-        )
-        .context("Failed to initialize FTS5 tables")?;
+            )
+            .context("Failed to initialize FTS5 tables")?;
 
-        Ok(Self {
-            conn: Mutex::new(conn),
-        })
+        Ok(Self { pool })
     }
 
     /// Index a document (upsert into documents + FTS5 via triggers).
     pub fn index_document(&self, doc: &Document) -> Result<()> {
-        let conn = self.conn.lock().map_err(|e| anyhow!("DB lock poisoned: {}", e))?;
+        let conn = self.pool.writer()?;
         conn.execute(
             "INSERT INTO documents (id, path, content, content_hash, metadata)
              VALUES (?1, ?2, ?3, ?4, ?5)
@@ -71,7 +65,13 @@ impl TextSearchIndex {
                 content_hash = excluded.content_hash,
                 updated_at = datetime('now'),
                 metadata = excluded.metadata",
-            params![doc.id, doc.path, doc.content, doc.content_hash, doc.metadata],
+            params![
+                doc.id,
+                doc.path,
+                doc.content,
+                doc.content_hash,
+                doc.metadata
+            ],
         )
         .context("Failed to index document")?;
         Ok(())
@@ -79,7 +79,7 @@ impl TextSearchIndex {
 
     /// Remove a document from both tables.
     pub fn remove_document(&self, id: &str) -> Result<()> {
-        let conn = self.conn.lock().map_err(|e| anyhow!("DB lock poisoned: {}", e))?;
+        let conn = self.pool.writer()?;
         conn.execute("DELETE FROM documents WHERE id = ?1", params![id])
             .context("Failed to remove document")?;
         Ok(())
@@ -87,29 +87,44 @@ impl TextSearchIndex {
 
     /// BM25-ranked full-text search. Returns (doc_id, bm25_score) pairs.
     pub fn search(&self, query: &str, limit: usize) -> Result<Vec<(String, f64)>> {
-        let conn = self.conn.lock().map_err(|e| anyhow!("DB lock poisoned: {}", e))?;
+        self.search_matching(query, limit, |_| true)
+    }
+
+    /// `search` over only the documents whose id passes `keep`; the limit
+    /// applies after filtering.
+    pub fn search_matching(
+        &self,
+        query: &str,
+        limit: usize,
+        keep: impl Fn(&str) -> bool,
+    ) -> Result<Vec<(String, f64)>> {
+        let conn = self.pool.reader()?;
         let mut stmt = conn.prepare(
             "SELECT d.id, bm25(documents_fts) AS score
              FROM documents_fts f
              JOIN documents d ON d.rowid = f.rowid
              WHERE documents_fts MATCH ?1
-             ORDER BY score
-             LIMIT ?2",
+             ORDER BY score",
         )?;
 
-        let results = stmt
-            .query_map(params![query, limit as i64], |row| {
-                Ok((row.get::<_, String>(0)?, row.get::<_, f64>(1)?))
-            })?
-            .collect::<Result<Vec<_>, _>>()
-            .context("Failed to collect FTS results")?;
+        let mut results = Vec::new();
+        let mut rows = stmt.query(params![query])?;
+        while results.len() < limit {
+            let Some(row) = rows.next().context("Failed to collect FTS results")? else {
+                break;
+            };
+            let id: String = row.get(0)?;
+            if keep(&id) {
+                results.push((id, row.get::<_, f64>(1)?));
+            }
+        }
 
         Ok(results)
     }
 
     /// Check if a document exists by id.
     pub fn has_document(&self, id: &str) -> Result<bool> {
-        let conn = self.conn.lock().map_err(|e| anyhow!("DB lock poisoned: {}", e))?;
+        let conn = self.pool.reader()?;
         let count: i64 = conn.query_row(
             "SELECT COUNT(*) FROM documents WHERE id = ?1",
             params![id],
@@ -120,7 +135,7 @@ impl TextSearchIndex {
 
     /// Get the content hash for a document (for cache-based skip).
     pub fn get_content_hash(&self, id: &str) -> Result<Option<String>> {
-        let conn = self.conn.lock().map_err(|e| anyhow!("DB lock poisoned: {}", e))?;
+        let conn = self.pool.reader()?;
         let mut stmt = conn.prepare("SELECT content_hash FROM documents WHERE id = ?1")?;
         let result = stmt
             .query_row(params![id], |row| row.get::<_, String>(0))
@@ -130,7 +145,7 @@ impl TextSearchIndex {
 
     /// Get document content by id.
     pub fn get_document_content(&self, id: &str) -> Result<Option<String>> {
-        let conn = self.conn.lock().map_err(|e| anyhow!("DB lock poisoned: {}", e))?;
+        let conn = self.pool.reader()?;
         let mut stmt = conn.prepare("SELECT content FROM documents WHERE id = ?1")?;
         let result = stmt
             .query_row(params![id], |row| row.get::<_, String>(0))
@@ -138,9 +153,27 @@ impl TextSearchIndex {
         Ok(result)
     }
 
+    /// Number of indexed documents.
+    pub fn document_count(&self) -> Result<usize> {
+        let conn = self.pool.reader()?;
+        let count: i64 = conn.query_row("SELECT COUNT(*) FROM documents", [], |row| row.get(0))?;
+        Ok(count as usize)
+    }
+
+    /// Rebuild the FTS5 index from the documents table.
+    pub fn rebuild(&self) -> Result<()> {
+        let conn = self.pool.writer()?;
+        conn.execute_batch(
+            "INSERT INTO documents_fts(documents_fts) VALUES ('rebuild');
+             REINDEX documents;",
+        )
+        .context("Failed to rebuild full-text index")?;
+        Ok(())
+    }
+
     /// List all document IDs in the index.
     pub fn list_document_ids(&self) -> Result<Vec<String>> {
-        let conn = self.conn.lock().map_err(|e| anyhow!("DB lock poisoned: {}", e))?;
+        let conn = self.pool.reader()?;
         let mut stmt = conn.prepare("SELECT id FROM documents")?;
         let ids = stmt
             .query_map([], |row| row.get::<_, String>(0))?
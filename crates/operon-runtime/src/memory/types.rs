@@ -33,12 +33,106 @@ pub struct SearchQuery {
     pub limit: usize,
     #[serde(default)]
     pub source: SearchSource,
+    /// Only paths matching this glob (`*`, `?`, `**`); a pattern without `/`
+    /// is matched against the file name
+    #[serde(default)]
+    pub path_glob: Option<String>,
+    /// Only files with one of these extensions (`rs` or `.rs`)
+    #[serde(default)]
+    pub extensions: Vec<String>,
+    /// How hybrid searches combine the two result lists
+    #[serde(default)]
+    pub merge: MergeStrategy,
 }
 
 fn default_limit() -> usize {
     10
 }
 
+/// Hybrid merge strategy. The weighted variants normalize each source's
+/// scores onto a common scale and sum them with the given weights.
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq)]
+#[serde(tag = "strategy", rename_all = "snake_case")]
+pub enum MergeStrategy {
+    /// Reciprocal Rank Fusion over ranks only
+    #[default]
+    Rrf,
+    /// Scale each source to 0..1 by its best and worst score
+    MinMax {
+        #[serde(default = "default_weight")]
+        vector_weight: f64,
+        #[serde(default = "default_weight")]
+        fts_weight: f64,
+    },
+    /// Standard score per source: distance from the mean in standard deviations
+    ZScore {
+        #[serde(default = "default_weight")]
+        vector_weight: f64,
+        #[serde(default = "default_weight")]
+        fts_weight: f64,
+    },
+}
+
+fn default_weight() -> f64 {
+    0.5
+}
+
+impl SearchQuery {
+    /// Whether a document path passes the query's path and extension filters
+    pub fn matches_path(&self, path: &str) -> bool {
+        if let Some(pattern) = &self.path_glob {
+            let target = if pattern.contains('/') {
+                path
+            } else {
+                path.rsplit('/').next().unwrap_or(path)
+            };
+            let pattern: Vec<char> = pattern.chars().collect();
+            let target: Vec<char> = target.chars().collect();
+            if !glob_match(&pattern, &target) {
+                return false;
+            }
+        }
+        if self.extensions.is_empty() {
+            return true;
+        }
+        let Some((_, ext)) = path.rsplit('/').next().unwrap_or(path).rsplit_once('.') else {
+            return false;
+        };
+        self.extensions
+            .iter()
+            .any(|want| want.trim_start_matches('.').eq_ignore_ascii_case(ext))
+    }
+}
+
+/// `*` and `?` stay within one path segment; `**` spans segments and `**/`
+/// also matches no directory at all
+fn glob_match(pattern: &[char], path: &[char]) -> bool {
+    match pattern {
+        [] => path.is_empty(),
+        ['*', '*', rest @ ..] => {
+            if let ['/', after @ ..] = rest {
+                if glob_match(after, path) {
+                    return true;
+                }
+            }
+            (0..=path.len()).any(|i| glob_match(rest, &path[i..]))
+        }
+        ['*', rest @ ..] => {
+            for i in 0..=path.len() {
+                if glob_match(rest, &path[i..]) {
+                    return true;
+                }
+                if path.get(i) == Some(&'/') {
+                    break;
+                }
+            }
+            false
+        }
+        ['?', rest @ ..] => matches!(path, [c, tail @ ..] if *c != '/' && glob_match(rest, tail)),
+        [c, rest @ ..] => matches!(path, [p, tail @ ..] if p == c && glob_match(rest, tail)),
+    }
+}
+
 /// Which search backend(s) to use.
 #[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq)]
 #[serde(rename_all = "lowercase")]
@@ -50,6 +144,52 @@ pub enum SearchSource {
     Hybrid,
 }
 
+/// Embedding model and vector size a vector store was built with
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct EmbeddingInfo {
+    /// Empty for stores built before the model was recorded
+    pub model: String,
+    pub dimensions: usize,
+}
+
+/// Result of re-embedding the memory index with another model
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MigrationStats {
+    pub documents: usize,
+    pub from: Option<EmbeddingInfo>,
+    pub to: EmbeddingInfo,
+}
+
+/// Findings of [`VectorStore::verify`](crate::memory::vector_store::VectorStore::verify)
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct IntegrityReport {
+    pub vectors: usize,
+    /// Vectors whose document is no longer indexed
+    pub orphaned: Vec<String>,
+    /// Vectors of the wrong size or holding NaN/infinite values
+    pub corrupted: Vec<String>,
+    /// Indexed documents without a vector
+    pub missing: Vec<String>,
+    /// Problems reported by SQLite's integrity check
+    pub database_errors: Vec<String>,
+}
+
+impl IntegrityReport {
+    pub fn is_ok(&self) -> bool {
+        self.orphaned.is_empty()
+            && self.corrupted.is_empty()
+            && self.missing.is_empty()
+            && self.database_errors.is_empty()
+    }
+}
+
+/// What [`VectorStore::compact`](crate::memory::vector_store::VectorStore::compact) removed
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct CompactStats {
+    pub orphaned_removed: usize,
+    pub corrupted_removed: usize,
+}
+
 /// Statistics returned after an indexing operation.
 #[derive(Debug, Clone, Default)]
 pub struct IndexStats {
@@ -1,43 +1,43 @@
-use anyhow::{anyhow, Context, Result};
-use rusqlite::{params, Connection};
+use crate::memory::sqlite::ConnectionPool;
+use crate::memory::types::{CompactStats, EmbeddingInfo, IntegrityReport};
+use anyhow::{bail, Context, Result};
+use rusqlite::{params, Connection, OptionalExtension};
 use std::path::Path;
-use std::sync::Mutex;
 use tracing::warn;
 
 /// Vector storage with brute-force cosine similarity search.
 /// Uses SQLite to persist embeddings as BLOBs.
 /// Sufficient for workspace-scale datasets (<10K docs). Swap to HNSW if needed.
 pub struct VectorStore {
-    conn: Mutex<Connection>,
+    pool: ConnectionPool,
     dimensions: usize,
 }
 
 impl VectorStore {
     pub fn new(db_path: &Path, dimensions: usize) -> Result<Self> {
-        let conn = Connection::open(db_path).context("Failed to open vector database")?;
+        let pool = ConnectionPool::open(db_path)?;
 
-        conn.pragma_update(None, "journal_mode", "WAL")
-            .context("Failed to enable WAL mode")?;
-        conn.pragma_update(None, "synchronous", "NORMAL")?;
-
-        conn.execute_batch(
-            "CREATE TABLE IF NOT EXISTS vectors (
+        pool.writer()?
+            .execute_batch(
+                "CREATE TABLE IF NOT EXISTS vectors (
                 id TEXT PRIMARY KEY,
                 embedding BLOB NOT NULL
+            );
+
+            CREATE TABLE IF NOT EXISTS vector_meta (
+                key TEXT PRIMARY KEY,
+                value TEXT NOT NULL
             );",
-        )
-        .context("Failed to initialize vector table")?;
+            )
+            .context("Failed to initialize vector table")?;
 
-        Ok(Self {
-            conn: Mutex::new(conn),
-            dimensions,
-        })
+        Ok(Self { pool, dimensions })
     }
 
     /// Insert or update an embedding for a document.
     pub fn upsert(&self, id: &str, embedding: &[f32]) -> Result<()> {
         let bytes = embedding_to_bytes(embedding);
-        let conn = self.conn.lock().map_err(|e| anyhow!("DB lock poisoned: {}", e))?;
+        let conn = self.pool.writer()?;
         conn.execute(
             "INSERT INTO vectors (id, embedding) VALUES (?1, ?2)
              ON CONFLICT(id) DO UPDATE SET embedding = excluded.embedding",
@@ -49,14 +49,184 @@ impl VectorStore {
 
     /// Remove an embedding by document id.
     pub fn remove(&self, id: &str) -> Result<()> {
-        let conn = self.conn.lock().map_err(|e| anyhow!("DB lock poisoned: {}", e))?;
+        let conn = self.pool.writer()?;
         conn.execute("DELETE FROM vectors WHERE id = ?1", params![id])?;
         Ok(())
     }
 
+    /// Model and dimensions of the stored vectors: as recorded, or inferred
+    /// from the stored vectors for stores built before they were recorded.
+    /// None for an empty store.
+    pub fn embedding_info(&self) -> Result<Option<EmbeddingInfo>> {
+        let conn = self.pool.reader()?;
+        let meta = |key: &str| -> Result<Option<String>> {
+            Ok(conn
+                .query_row(
+                    "SELECT value FROM vector_meta WHERE key = ?1",
+                    params![key],
+                    |row| row.get(0),
+                )
+                .optional()?)
+        };
+        if let Some(dimensions) = meta("dimensions")? {
+            return Ok(Some(EmbeddingInfo {
+                model: meta("model")?.unwrap_or_default(),
+                dimensions: dimensions.parse().context("Corrupted vector dimensions")?,
+            }));
+        }
+        let bytes: Option<i64> = conn
+            .query_row("SELECT length(embedding) FROM vectors LIMIT 1", [], |row| {
+                row.get(0)
+            })
+            .optional()?;
+        Ok(bytes.map(|bytes| EmbeddingInfo {
+            model: String::new(),
+            dimensions: bytes as usize / 4,
+        }))
+    }
+
+    /// Fail if the stored vectors came from another model or have another
+    /// size than `model` produces; records `model` on a new store.
+    pub fn check_embedding(&self, model: &str) -> Result<()> {
+        match self.embedding_info()? {
+            Some(info)
+                if info.dimensions != self.dimensions
+                    || (!info.model.is_empty() && info.model != model) =>
+            {
+                let built_with = if info.model.is_empty() {
+                    "an unrecorded model"
+                } else {
+                    &info.model
+                };
+                bail!(
+                    "Memory index was built with {} ({} dims) but the embedding model is {} ({} dims); re-embed the index before searching it",
+                    built_with,
+                    info.dimensions,
+                    model,
+                    self.dimensions
+                )
+            }
+            Some(info) if !info.model.is_empty() => Ok(()),
+            _ => {
+                let conn = self.pool.writer()?;
+                write_meta(&conn, model, self.dimensions)
+            }
+        }
+    }
+
+    /// Replace every stored vector with `vectors` from `model` in one
+    /// transaction; on error the old vectors are kept.
+    pub fn replace_all(&self, model: &str, vectors: &[(String, Vec<f32>)]) -> Result<()> {
+        if let Some((id, v)) = vectors.iter().find(|(_, v)| v.len() != self.dimensions) {
+            bail!(
+                "Embedding for {} has {} dims, expected {}",
+                id,
+                v.len(),
+                self.dimensions
+            );
+        }
+        let mut conn = self.pool.writer()?;
+        let tx = conn.transaction()?;
+        tx.execute("DELETE FROM vectors", [])?;
+        {
+            let mut insert = tx.prepare("INSERT INTO vectors (id, embedding) VALUES (?1, ?2)")?;
+            for (id, embedding) in vectors {
+                insert.execute(params![id, embedding_to_bytes(embedding)])?;
+            }
+        }
+        write_meta(&tx, model, self.dimensions)?;
+        tx.commit().context("Failed to replace vectors")?;
+        Ok(())
+    }
+
+    /// Number of stored vectors.
+    pub fn count(&self) -> Result<usize> {
+        let conn = self.pool.reader()?;
+        let count: i64 = conn.query_row("SELECT COUNT(*) FROM vectors", [], |row| row.get(0))?;
+        Ok(count as usize)
+    }
+
+    /// Check the database and every stored vector. Orphans and missing
+    /// vectors are found against the `documents` table of the memory index
+    /// in the same database, when there is one.
+    pub fn verify(&self) -> Result<IntegrityReport> {
+        let dimensions = self
+            .embedding_info()?
+            .map_or(self.dimensions, |info| info.dimensions);
+        let conn = self.pool.reader()?;
+        let mut report = IntegrityReport::default();
+
+        let mut stmt = conn.prepare("PRAGMA integrity_check")?;
+        report.database_errors = stmt
+            .query_map([], |row| row.get::<_, String>(0))?
+            .collect::<Result<Vec<_>, _>>()?
+            .into_iter()
+            .filter(|msg| msg != "ok")
+            .collect();
+
+        let mut stmt = conn.prepare("SELECT id, embedding FROM vectors")?;
+        let rows = stmt.query_map([], |row| {
+            Ok((row.get::<_, String>(0)?, row.get::<_, Vec<u8>>(1)?))
+        })?;
+        for row in rows {
+            let (id, blob) = row?;
+            report.vectors += 1;
+            let valid = bytes_to_embedding(&blob, dimensions)
+                .is_ok_and(|emb| emb.iter().all(|x| x.is_finite()));
+            if !valid {
+                report.corrupted.push(id);
+            }
+        }
+
+        if has_documents_table(&conn)? {
+            report.orphaned = query_ids(
+                &conn,
+                "SELECT v.id FROM vectors v
+                 WHERE NOT EXISTS (SELECT 1 FROM documents d WHERE d.id = v.id)",
+            )?;
+            report.missing = query_ids(
+                &conn,
+                "SELECT d.id FROM documents d
+                 WHERE NOT EXISTS (SELECT 1 FROM vectors v WHERE v.id = d.id)",
+            )?;
+        }
+        Ok(report)
+    }
+
+    /// Drop orphaned and corrupted vectors, then rebuild the table's indexes
+    /// and reclaim free space. Documents left without a vector are re-embedded
+    /// by a migration.
+    pub fn compact(&self) -> Result<CompactStats> {
+        let report = self.verify()?;
+        let conn = self.pool.writer()?;
+        for id in report.orphaned.iter().chain(&report.corrupted) {
+            conn.execute("DELETE FROM vectors WHERE id = ?1", params![id])?;
+        }
+        conn.execute_batch("REINDEX vectors; VACUUM;")
+            .context("Failed to rebuild vector indexes")?;
+        Ok(CompactStats {
+            orphaned_removed: report.orphaned.len(),
+            corrupted_removed: report
+                .corrupted
+                .iter()
+                .filter(|id| !report.orphaned.contains(id))
+                .count(),
+        })
+    }
+
     /// Cosine similarity search. Returns (doc_id, similarity_score) sorted descending.
     pub fn search(&self, query_embedding: &[f32], limit: usize) -> Result<Vec<(String, f32)>> {
-        let conn = self.conn.lock().map_err(|e| anyhow!("DB lock poisoned: {}", e))?;
+        self.search_matching(query_embedding, limit, |_| true)
+    }
+
+    /// `search` over only the vectors whose id passes `keep`
+    pub fn search_matching(
+        &self,
+        query_embedding: &[f32],
+        limit: usize,
+        keep: impl Fn(&str) -> bool,
+    ) -> Result<Vec<(String, f32)>> {
+        let conn = self.pool.reader()?;
         let mut stmt = conn.prepare("SELECT id, embedding FROM vectors")?;
 
         let mut scored: Vec<(String, f32)> = stmt
@@ -66,15 +236,16 @@ impl VectorStore {
                 Ok((id, blob))
             })?
             .filter_map(|r| r.ok())
-            .filter_map(|(id, blob)| {
-                match bytes_to_embedding(&blob, self.dimensions) {
+            .filter(|(id, _)| keep(id))
+            .filter_map(
+                |(id, blob)| match bytes_to_embedding(&blob, self.dimensions) {
                     Ok(emb) => Some((id, cosine_similarity(query_embedding, &emb))),
                     Err(e) => {
                         warn!(doc_id = %id, error = %e, "Skipping corrupted embedding");
                         None
                     }
-                }
-            })
+                },
+            )
             .collect();
 
         scored.sort_by(|a, b| b.1.partial_cmp(&a.1).unwrap_or(std::cmp::Ordering::Equal));
@@ -83,6 +254,32 @@ impl VectorStore {
     }
 }
 
+fn has_documents_table(conn: &Connection) -> Result<bool> {
+    let count: i64 = conn.query_row(
+        "SELECT COUNT(*) FROM sqlite_master WHERE type = 'table' AND name = 'documents'",
+        [],
+        |row| row.get(0),
+    )?;
+    Ok(count > 0)
+}
+
+fn query_ids(conn: &Connection, sql: &str) -> Result<Vec<String>> {
+    let mut stmt = conn.prepare(sql)?;
+    let ids = stmt
+        .query_map([], |row| row.get::<_, String>(0))?
+        .collect::<Result<Vec<_>, _>>()?;
+    Ok(ids)
+}
+
+fn write_meta(conn: &Connection, model: &str, dimensions: usize) -> Result<()> {
+    conn.execute(
+        "INSERT OR REPLACE INTO vector_meta (key, value) VALUES ('model', ?1), ('dimensions', ?2)",
+        params![model, dimensions.to_string()],
+    )
+    .context("Failed to record embedding model")?;
+    Ok(())
+}
+
 fn cosine_similarity(a: &[f32], b: &[f32]) -> f32 {
     let dot: f32 = a.iter().zip(b.iter()).map(|(x, y)| x * y).sum();
     let norm_a: f32 = a.iter().map(|x| x * x).sum::<f32>().sqrt();
@@ -91,7 +288,11 @@ fn cosine_similarity(a: &[f32], b: &[f32]) -> f32 {
         return 0.0;
     }
     let sim = dot / (norm_a * norm_b);
-    if sim.is_finite() { sim } else { 0.0 }
+    if sim.is_finite() {
+        sim
+    } else {
+        0.0
+    }
 }
 
 fn embedding_to_bytes(embedding: &[f32]) -> Vec<u8> {
@@ -103,13 +304,20 @@ fn bytes_to_embedding(bytes: &[u8], dimensions: usize) -> Result<Vec<f32>> {
     if bytes.len() != expected_len {
         anyhow::bail!(
             "Dimension mismatch: expected {} bytes ({} dims), got {} bytes",
-            expected_len, dimensions, bytes.len()
+            expected_len,
+            dimensions,
+            bytes.len()
         );
     }
     Ok((0..dimensions)
         .map(|i| {
             let start = i * 4;
-            f32::from_le_bytes([bytes[start], bytes[start + 1], bytes[start + 2], bytes[start + 3]])
+            f32::from_le_bytes([
+                bytes[start],
+                bytes[start + 1],
+                bytes[start + 2],
+                bytes[start + 3],
+            ])
         })
         .collect())
 }
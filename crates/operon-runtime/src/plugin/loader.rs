@@ -22,6 +22,8 @@ pub struct LoadedPlugin {
     pub plugin_dir: std::path::PathBuf,
     /// FFI handle — present when the .so/.dylib was successfully loaded
     pub handle: Option<PluginHandle>,
+    /// Names this plugin's tools are registered under
+    pub tools: Vec<String>,
 }
 
 /// Plugin loader: discovers, validates, loads, and registers plugins
@@ -81,6 +83,7 @@ impl PluginLoader {
         }
 
         let mut ffi_handle: Option<PluginHandle> = None;
+        let mut tool_names = Vec::new();
 
         // Validate and load plugin
         match manifest.plugin_type {
@@ -102,13 +105,26 @@ impl PluginLoader {
 
                         match init_result {
                             Ok(Ok(())) => {
-                                // Register tools
-                                for tool in handle.plugin().tools() {
-                                    let name = tool.name().to_string();
-                                    if let Err(e) =
-                                        self.runtime.register_tool(name.clone(), Arc::from(tool))
+                                // Register tools under the plugin's namespace
+                                let tools = handle.plugin().tools();
+                                let raw: Vec<&str> = tools.iter().map(|t| t.name()).collect();
+                                let names =
+                                    match namespaced_tool_names(&self.runtime, manifest, &raw) {
+                                        Ok(names) => names,
+                                        Err(e) => {
+                                            // Tools hold plugin code; drop them before unloading
+                                            drop(tools);
+                                            handle.shutdown_and_drop();
+                                            return Err(e);
+                                        }
+                                    };
+                                for (name, tool) in names.into_iter().zip(tools) {
+                                    match self.runtime.register_tool(name.clone(), Arc::from(tool))
                                     {
-                                        warn!(tool = %name, error = %e, "Failed to register plugin tool");
+                                        Ok(()) => tool_names.push(name),
+                                        Err(e) => {
+                                            warn!(tool = %name, error = %e, "Failed to register plugin tool")
+                                        }
                                     }
                                 }
 
@@ -161,14 +177,22 @@ impl PluginLoader {
                 manifest: manifest.clone(),
                 plugin_dir: plugin_dir.to_path_buf(),
                 handle: ffi_handle,
+                tools: tool_names,
             },
         );
 
         Ok(())
     }
 
-    /// Unload a plugin by name. Calls shutdown on FFI-loaded plugins.
+    /// Unload a plugin by name, unregistering its tools first. Calls shutdown
+    /// on FFI-loaded plugins.
     pub async fn unload_plugin(&self, name: &str) -> Result<()> {
+        if self.runtime.is_executing_plan() {
+            return Err(anyhow!(
+                "Cannot unload plugin '{}' while a plan is running",
+                name
+            ));
+        }
         let loaded = self
             .plugins
             .write()
@@ -176,9 +200,27 @@ impl PluginLoader {
             .remove(name)
             .ok_or_else(|| anyhow!("Plugin '{}' not found", name))?;
 
+        let mut in_use = false;
+        for tool_name in &loaded.tools {
+            match self.runtime.unregister_tool(tool_name) {
+                // Only our reference left means no call is running its code
+                Ok(tool) => in_use |= Arc::strong_count(&tool) > 1,
+                Err(e) => warn!(tool = %tool_name, error = %e, "Failed to unregister plugin tool"),
+            }
+        }
+
         // Shutdown FFI handle if present
         if let Some(handle) = loaded.handle {
-            handle.shutdown_and_drop();
+            if in_use {
+                // Unloading the library under a running call would crash; keep it mapped
+                warn!(
+                    plugin = name,
+                    "Plugin tool still in use; leaving library loaded"
+                );
+                std::mem::forget(handle);
+            } else {
+                handle.shutdown_and_drop();
+            }
         }
 
         info!(plugin = name, "Plugin unloaded");
@@ -206,6 +248,42 @@ impl PluginLoader {
     }
 }
 
+/// Registered names for a plugin's tools. A clash with an existing tool or
+/// alias, or between two of the plugin's own tools, is an error rather than
+/// silently replacing the earlier tool; so is a name LLM providers won't
+/// accept (anything but letters, digits, `_` and `-`).
+fn namespaced_tool_names(
+    runtime: &Runtime,
+    manifest: &PluginManifest,
+    tools: &[&str],
+) -> Result<Vec<String>> {
+    let mut names: Vec<String> = Vec::with_capacity(tools.len());
+    for tool in tools {
+        let name = manifest.tool_name(tool);
+        let valid = name
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '-');
+        if name.is_empty() || !valid {
+            return Err(anyhow!(
+                "Plugin '{}' tool '{}' gets the name '{}'; tool names may only use letters, digits, '_' and '-'",
+                manifest.name,
+                tool,
+                name
+            ));
+        }
+        if runtime.has_tool(&name) || names.contains(&name) {
+            return Err(anyhow!(
+                "Plugin '{}' tool '{}' collides with existing tool '{}'",
+                manifest.name,
+                tool,
+                name
+            ));
+        }
+        names.push(name);
+    }
+    Ok(names)
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -237,6 +315,7 @@ mod tests {
             entry_point: "./libtest.so".into(),
             dependencies: vec![],
             config: serde_json::Value::Null,
+            tool_prefix: None,
         };
 
         let dir = tempfile::tempdir().unwrap();
@@ -265,6 +344,7 @@ mod tests {
             entry_point: "./libtest.so".into(),
             dependencies: vec![],
             config: serde_json::Value::Null,
+            tool_prefix: None,
         };
 
         loader.load_plugin(&manifest, dir.path()).await.unwrap();
@@ -292,6 +372,7 @@ mod tests {
             entry_point: "./libtest.so".into(),
             dependencies: vec![],
             config: serde_json::Value::Null,
+            tool_prefix: None,
         };
 
         loader.load_plugin(&manifest, dir.path()).await.unwrap();
@@ -305,6 +386,46 @@ mod tests {
         assert!(loader.list_plugins().await.is_empty());
     }
 
+    struct NoopTool;
+
+    #[async_trait::async_trait]
+    impl crate::Tool for NoopTool {
+        async fn execute(&self, _input: serde_json::Value) -> Result<serde_json::Value> {
+            Ok(serde_json::Value::Null)
+        }
+
+        fn name(&self) -> &str {
+            "noop"
+        }
+    }
+
+    #[test]
+    fn test_namespaced_tool_names_detect_collisions() {
+        let (runtime, _dir) = make_test_runtime();
+        runtime
+            .register_tool("search".into(), Arc::new(NoopTool))
+            .unwrap();
+        let mut manifest: PluginManifest = toml::from_str(
+            "name = \"web\"\nversion = \"1.0.0\"\napi_version = 1\nentry_point = \"./libweb.so\"\n",
+        )
+        .unwrap();
+
+        let names = namespaced_tool_names(&runtime, &manifest, &["search", "fetch"]).unwrap();
+        assert_eq!(names, ["web__search", "web__fetch"]);
+
+        // Raw names would shadow the built-in
+        manifest.tool_prefix = Some(String::new());
+        let err = namespaced_tool_names(&runtime, &manifest, &["search"]).unwrap_err();
+        assert!(err.to_string().contains("collides"));
+
+        manifest.tool_prefix = None;
+        assert!(namespaced_tool_names(&runtime, &manifest, &["fetch", "fetch"]).is_err());
+
+        manifest.tool_prefix = Some("web.".into());
+        let err = namespaced_tool_names(&runtime, &manifest, &["fetch"]).unwrap_err();
+        assert!(err.to_string().contains("'web.fetch'"));
+    }
+
     #[tokio::test]
     async fn test_load_all_empty_dir() {
         let (runtime, _dir) = make_test_runtime();
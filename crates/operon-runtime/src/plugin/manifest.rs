@@ -28,6 +28,9 @@ pub struct PluginManifest {
     /// Optional plugin configuration passed to `Plugin::init()`
     #[serde(default)]
     pub config: serde_json::Value,
+    /// Prefix for this plugin's tool names (default "<name>__"; "" keeps raw names)
+    #[serde(default)]
+    pub tool_prefix: Option<String>,
 }
 
 fn default_plugin_type() -> PluginType {
@@ -44,6 +47,14 @@ impl PluginManifest {
         Ok(manifest)
     }
 
+    /// Name a tool provided by this plugin is registered under
+    pub fn tool_name(&self, tool: &str) -> String {
+        match &self.tool_prefix {
+            Some(prefix) => format!("{}{}", prefix, tool),
+            None => format!("{}__{}", self.name, tool),
+        }
+    }
+
     /// Resolve entry point path relative to manifest directory
     pub fn resolve_entry_point(&self, manifest_dir: &Path) -> std::path::PathBuf {
         manifest_dir.join(&self.entry_point)
@@ -104,6 +115,25 @@ entry_point = "./libtest.dylib"
         assert_eq!(manifest.name, "test-plugin");
         assert_eq!(manifest.api_version, 1);
         assert_eq!(manifest.plugin_type, PluginType::Native);
+        assert_eq!(manifest.tool_name("search"), "test-plugin__search");
+    }
+
+    #[test]
+    fn test_tool_prefix_override() {
+        let mut manifest: PluginManifest = toml::from_str(
+            r#"
+name = "web"
+version = "1.0.0"
+api_version = 1
+entry_point = "./libweb.so"
+tool_prefix = "web_"
+"#,
+        )
+        .unwrap();
+        assert_eq!(manifest.tool_name("search"), "web_search");
+
+        manifest.tool_prefix = Some(String::new());
+        assert_eq!(manifest.tool_name("search"), "search");
     }
 
     #[test]
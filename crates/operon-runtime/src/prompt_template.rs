@@ -0,0 +1,191 @@
+//! Named, versioned prompt templates stored as `<dir>/<name>/<version>.md`
+//! and referenced from system prompts as `template:<name>[@<version>]`.
+
+use anyhow::{anyhow, bail, Context, Result};
+use std::collections::HashMap;
+use std::path::PathBuf;
+
+/// Prefix marking a system prompt as a template reference
+pub const TEMPLATE_PREFIX: &str = "template:";
+
+/// One version of a prompt template; `{{name}}` marks a variable
+#[derive(Debug, Clone, PartialEq)]
+pub struct PromptTemplate {
+    pub name: String,
+    pub version: u32,
+    pub body: String,
+}
+
+impl PromptTemplate {
+    /// Substitute every `{{var}}`; a variable missing from `vars` is an error
+    pub fn render(&self, vars: &HashMap<String, String>) -> Result<String> {
+        let mut out = String::with_capacity(self.body.len());
+        let mut rest = self.body.as_str();
+        while let Some(start) = rest.find("{{") {
+            out.push_str(&rest[..start]);
+            let after = &rest[start + 2..];
+            let end = after.find("}}").ok_or_else(|| {
+                anyhow!(
+                    "Template {}@{} has an unclosed '{{{{'",
+                    self.name,
+                    self.version
+                )
+            })?;
+            let var = after[..end].trim();
+            let value = vars.get(var).ok_or_else(|| {
+                anyhow!(
+                    "Template {}@{} uses undefined variable '{}'",
+                    self.name,
+                    self.version,
+                    var
+                )
+            })?;
+            out.push_str(value);
+            rest = &after[end + 2..];
+        }
+        out.push_str(rest);
+        Ok(out)
+    }
+}
+
+/// Registry of prompt templates in a directory (default `~/.silentclaw/prompts`)
+pub struct PromptRegistry {
+    dir: PathBuf,
+}
+
+impl PromptRegistry {
+    pub fn new(dir: impl Into<PathBuf>) -> Self {
+        Self { dir: dir.into() }
+    }
+
+    /// Versions of template `name`, oldest first
+    pub fn versions(&self, name: &str) -> Result<Vec<u32>> {
+        check_name(name)?;
+        let dir = self.dir.join(name);
+        let entries = std::fs::read_dir(&dir)
+            .with_context(|| format!("No prompt template '{}' in {}", name, self.dir.display()))?;
+        let mut versions: Vec<u32> = entries
+            .filter_map(|entry| {
+                let path = entry.ok()?.path();
+                if path.extension()? != "md" {
+                    return None;
+                }
+                path.file_stem()?.to_str()?.parse().ok()
+            })
+            .collect();
+        versions.sort_unstable();
+        Ok(versions)
+    }
+
+    /// Template `name` at `version` (None = latest)
+    pub fn load(&self, name: &str, version: Option<u32>) -> Result<PromptTemplate> {
+        let version = match version {
+            Some(version) => version,
+            None => *self
+                .versions(name)?
+                .last()
+                .ok_or_else(|| anyhow!("Prompt template '{}' has no versions", name))?,
+        };
+        check_name(name)?;
+        let path = self.dir.join(name).join(format!("{}.md", version));
+        let body = std::fs::read_to_string(&path)
+            .with_context(|| format!("Failed to read prompt template {}", path.display()))?;
+        Ok(PromptTemplate {
+            name: name.to_string(),
+            version,
+            body,
+        })
+    }
+
+    /// `prompt` unchanged, or rendered with `vars` if it is a
+    /// `template:<name>[@<version>]` reference
+    pub fn resolve(&self, prompt: &str, vars: &HashMap<String, String>) -> Result<String> {
+        let Some(reference) = prompt.trim().strip_prefix(TEMPLATE_PREFIX) else {
+            return Ok(prompt.to_string());
+        };
+        let (name, version) = match reference.split_once('@') {
+            Some((name, version)) => {
+                let version = version
+                    .parse()
+                    .with_context(|| format!("Invalid template version in '{}'", prompt))?;
+                (name, Some(version))
+            }
+            None => (reference, None),
+        };
+        self.load(name, version)?.render(vars)
+    }
+}
+
+/// Template names are single path components
+fn check_name(name: &str) -> Result<()> {
+    if name.is_empty()
+        || !name
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_')
+    {
+        bail!(
+            "Invalid prompt template name '{}' (use letters, digits, '-' and '_')",
+            name
+        );
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn registry() -> (PromptRegistry, tempfile::TempDir) {
+        let dir = tempfile::tempdir().unwrap();
+        let reviewer = dir.path().join("code-reviewer");
+        std::fs::create_dir_all(&reviewer).unwrap();
+        std::fs::write(reviewer.join("1.md"), "Review code.").unwrap();
+        std::fs::write(
+            reviewer.join("2.md"),
+            "You are {{ agent }}. Review {{language}} code.",
+        )
+        .unwrap();
+        std::fs::write(reviewer.join("notes.txt"), "ignored").unwrap();
+        (PromptRegistry::new(dir.path()), dir)
+    }
+
+    #[test]
+    fn test_resolve_references_by_version() {
+        let (registry, _dir) = registry();
+        let vars = HashMap::from([
+            ("agent".to_string(), "reviewer".to_string()),
+            ("language".to_string(), "Rust".to_string()),
+        ]);
+
+        assert_eq!(registry.versions("code-reviewer").unwrap(), vec![1, 2]);
+        assert_eq!(
+            registry.resolve("template:code-reviewer@1", &vars).unwrap(),
+            "Review code."
+        );
+        assert_eq!(
+            registry.resolve("template:code-reviewer", &vars).unwrap(),
+            "You are reviewer. Review Rust code."
+        );
+        assert_eq!(
+            registry.resolve("Plain prompt", &vars).unwrap(),
+            "Plain prompt"
+        );
+    }
+
+    #[test]
+    fn test_resolve_errors() {
+        let (registry, _dir) = registry();
+        let err = registry
+            .resolve("template:code-reviewer@2", &HashMap::new())
+            .unwrap_err();
+        assert!(err.to_string().contains("undefined variable 'agent'"));
+        assert!(registry
+            .resolve("template:code-reviewer@9", &HashMap::new())
+            .is_err());
+        assert!(registry
+            .resolve("template:../etc@1", &HashMap::new())
+            .unwrap_err()
+            .to_string()
+            .contains("Invalid prompt template name"));
+    }
+}
@@ -1,10 +1,22 @@
-use anyhow::{Context, Result};
+use anyhow::{anyhow, Context, Result};
+use async_trait::async_trait;
 use serde::{Deserialize, Serialize};
-use serde_json::Value;
+use serde_json::{json, Value};
+use std::collections::HashMap;
 use std::path::Path;
+use tracing::{info, warn};
+
+use crate::tool::{PermissionLevel, Tool};
+
+/// Current fixture format. Files without a `version` field predate
+/// versioning and count as version 0; version 2 added step errors.
+pub const FIXTURE_VERSION: u32 = 2;
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Fixture {
+    /// Format version the fixture was written with
+    #[serde(default)]
+    pub version: u32,
     pub plan_id: String,
     pub recorded_at: String,
     pub steps: Vec<StepRecord>,
@@ -16,12 +28,36 @@ pub struct StepRecord {
     pub tool: String,
     pub input: Value,
     pub output: Value,
+    #[serde(default)]
     pub duration_ms: u64,
+    /// Replay this step as a failure with this message instead of `output`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+impl StepRecord {
+    /// The recorded output, or the recorded error as a failure
+    pub fn result(&self) -> Result<Value> {
+        match &self.error {
+            Some(error) => Err(anyhow!("{}", error)),
+            None => Ok(self.output.clone()),
+        }
+    }
+}
+
+/// What [`Fixture::scrub`] replaced
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize)]
+pub struct ScrubStats {
+    /// Strings replaced with a placeholder
+    pub values: usize,
+    /// Bytes those strings held
+    pub bytes: usize,
 }
 
 impl Fixture {
     pub fn new(plan_id: String) -> Self {
         Self {
+            version: FIXTURE_VERSION,
             plan_id,
             recorded_at: timestamp_now(),
             steps: Vec::new(),
@@ -37,14 +73,170 @@ impl Fixture {
         Ok(())
     }
 
-    /// Load fixture from JSON file
+    /// Load fixture from JSON file, migrating older formats in memory
     pub fn load(dir: &Path) -> Result<Self> {
         let path = dir.join("fixture.json");
-        let content = std::fs::read_to_string(&path)
-            .context(format!("Failed to read fixture: {:?}", path))?;
-        let fixture: Self =
-            serde_json::from_str(&content).context("Failed to parse fixture JSON")?;
-        Ok(fixture)
+        let value = read_json(&path)?;
+        Self::from_json(value).context(format!("Invalid fixture: {:?}", path))
+    }
+
+    /// Parse fixture JSON of any version. Older versions are migrated; newer
+    /// ones load as long as the fields this release knows still parse.
+    pub fn from_json(mut value: Value) -> Result<Self> {
+        let version = value.get("version").and_then(Value::as_u64).unwrap_or(0) as u32;
+        if version > FIXTURE_VERSION {
+            warn!(
+                version,
+                supported = FIXTURE_VERSION,
+                "Fixture was written by a newer release"
+            );
+            return serde_json::from_value(value).context(format!(
+                "Fixture version {} is newer than supported version {}; upgrade to replay it",
+                version, FIXTURE_VERSION
+            ));
+        }
+        if version < FIXTURE_VERSION {
+            migrate(&mut value, version)?;
+            info!(from = version, to = FIXTURE_VERSION, "Migrated fixture");
+        }
+        serde_json::from_value(value).context("Failed to parse fixture JSON")
+    }
+
+    /// Rewrite an older fixture file in the current format. Returns the
+    /// version it had, or None if it was already current (or newer).
+    pub fn upgrade(dir: &Path) -> Result<Option<u32>> {
+        let value = read_json(&dir.join("fixture.json"))?;
+        let version = value.get("version").and_then(Value::as_u64).unwrap_or(0) as u32;
+        if version >= FIXTURE_VERSION {
+            return Ok(None);
+        }
+        Self::from_json(value)?.save(dir)?;
+        Ok(Some(version))
+    }
+
+    /// The record of step `index`
+    pub fn step_mut(&mut self, index: usize) -> Result<&mut StepRecord> {
+        self.steps
+            .iter_mut()
+            .find(|r| r.index == index)
+            .context(format!("No step {} in fixture '{}'", index, self.plan_id))
+    }
+
+    /// Replace every string longer than `max_bytes` in step outputs with a
+    /// short placeholder. Inputs are kept: replay matches steps by them.
+    pub fn scrub(&mut self, max_bytes: usize) -> ScrubStats {
+        let mut stats = ScrubStats::default();
+        for record in &mut self.steps {
+            scrub_value(&mut record.output, max_bytes, &mut stats);
+        }
+        stats
+    }
+
+    /// Sequential plan of the recorded steps, for when the original plan is unavailable
+    pub fn to_plan(&self) -> Value {
+        let mut steps: Vec<&StepRecord> = self.steps.iter().collect();
+        steps.sort_by_key(|r| r.index);
+        let steps: Vec<Value> = steps
+            .iter()
+            .map(|r| serde_json::json!({"tool": r.tool, "input": r.input}))
+            .collect();
+        serde_json::json!({"id": self.plan_id, "steps": steps})
+    }
+}
+
+fn read_json(path: &Path) -> Result<Value> {
+    let content =
+        std::fs::read_to_string(path).context(format!("Failed to read fixture: {:?}", path))?;
+    serde_json::from_str(&content).context("Failed to parse fixture JSON")
+}
+
+/// Upgrade fixture JSON from `from` to [`FIXTURE_VERSION`], one version at a time
+fn migrate(value: &mut Value, from: u32) -> Result<()> {
+    let fixture = value
+        .as_object_mut()
+        .context("Fixture must be a JSON object")?;
+    if from < 1 {
+        // Version 0 steps could lack their index (list position) and duration
+        if let Some(Value::Array(steps)) = fixture.get_mut("steps") {
+            for (position, step) in steps.iter_mut().enumerate() {
+                if let Some(step) = step.as_object_mut() {
+                    step.entry("index").or_insert(json!(position));
+                    step.entry("duration_ms").or_insert(json!(0));
+                }
+            }
+        }
+        fixture.entry("recorded_at").or_insert(json!(""));
+    }
+    // Version 2 only added the optional step `error`
+    fixture.insert("version".to_string(), json!(FIXTURE_VERSION));
+    Ok(())
+}
+
+fn scrub_value(value: &mut Value, max_bytes: usize, stats: &mut ScrubStats) {
+    match value {
+        Value::String(s) if s.len() > max_bytes => {
+            stats.values += 1;
+            stats.bytes += s.len();
+            *s = format!("<scrubbed {} bytes>", s.len());
+        }
+        Value::Array(items) => {
+            for item in items {
+                scrub_value(item, max_bytes, stats);
+            }
+        }
+        Value::Object(map) => {
+            for item in map.values_mut() {
+                scrub_value(item, max_bytes, stats);
+            }
+        }
+        _ => {}
+    }
+}
+
+/// Stand-in for a recorded tool: answers each call with the output recorded
+/// for the same input, doing no work itself (for measuring runtime overhead)
+pub struct FixtureTool {
+    name: String,
+    /// Canonical input JSON → recorded step
+    outputs: HashMap<String, StepRecord>,
+}
+
+impl FixtureTool {
+    /// One stand-in per tool recorded in `fixture`
+    pub fn from_fixture(fixture: &Fixture) -> Vec<FixtureTool> {
+        let mut tools: HashMap<&str, FixtureTool> = HashMap::new();
+        for record in &fixture.steps {
+            tools
+                .entry(&record.tool)
+                .or_insert_with(|| FixtureTool {
+                    name: record.tool.clone(),
+                    outputs: HashMap::new(),
+                })
+                .outputs
+                .insert(record.input.to_string(), record.clone());
+        }
+        tools.into_values().collect()
+    }
+}
+
+#[async_trait]
+impl Tool for FixtureTool {
+    async fn execute(&self, input: Value) -> Result<Value> {
+        self.outputs
+            .get(&input.to_string())
+            .context(format!(
+                "No recorded output of '{}' for input {}",
+                self.name, input
+            ))?
+            .result()
+    }
+
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn permission_level(&self) -> PermissionLevel {
+        PermissionLevel::Read
     }
 }
 
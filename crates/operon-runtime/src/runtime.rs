@@ -1,22 +1,49 @@
+use crate::audit::{AuditDecision, AuditRecord};
+use crate::checkpoint;
+use crate::composite::{self, CompositeSpec, CompositeTool};
+use crate::exec_queue::{ExecPriority, ExecQueue, QueueStats};
+use crate::execution_backend::{ExecutionBackend, InProcess};
+use crate::hooks::{HookContext, HookEvent, HookRegistry};
+use crate::llm::Message;
 use crate::replay::{self, Fixture, StepRecord};
-use crate::scheduler::{self, ScheduledStep};
-use crate::tool::PermissionLevel;
-use crate::tool_policy::{PolicyContext, ToolPolicyPipeline};
+use crate::scheduler::{self, OnError, ScheduledStep};
+use crate::tool::{PermissionLevel, ToolSchemaInfo};
+use crate::tool_middleware::{self, ToolInvocation, ToolMiddleware};
+use crate::tool_policy::{ApprovalRequest, Approver, PolicyContext, ToolPolicyPipeline};
 use crate::{Storage, Tool};
 use anyhow::{Context, Result};
 use dashmap::DashMap;
+use serde::{Deserialize, Serialize};
 use serde_json::Value;
+use std::collections::{HashMap, HashSet};
+use std::future::Future;
 use std::path::PathBuf;
+use std::pin::Pin;
 use std::sync::atomic::{AtomicU8, Ordering};
 use std::sync::Arc;
 use std::time::Duration;
 use tokio::sync::Semaphore;
 use tokio::task::JoinSet;
-use tracing::{info, warn};
+use tracing::{debug, info, warn};
 
 const STATE_IDLE: u8 = 0;
 const STATE_RUNNING: u8 = 1;
 
+/// Tool name of the built-in step that runs another plan
+pub const PLAN_STEP_TOOL: &str = "plan";
+
+/// Guards against plans that (indirectly) include themselves
+const MAX_PLAN_DEPTH: usize = 8;
+
+/// Past this many cached tool results, expired ones are pruned on insert
+const MAX_CACHED_RESULTS: usize = 256;
+
+/// Storage key prefix for results of calls made with an idempotency key
+const IDEMPOTENCY_KEY_PREFIX: &str = "idempotency/";
+
+/// How long a stored idempotent result is returned by default
+const DEFAULT_IDEMPOTENCY_TTL: Duration = Duration::from_secs(24 * 60 * 60);
+
 /// Controls how the runtime handles tool execution
 #[derive(Debug, Clone)]
 pub enum ExecutionContext {
@@ -28,17 +55,222 @@ pub enum ExecutionContext {
     Replay(PathBuf),
 }
 
+/// Where a nested plan's step outputs are stored
+#[derive(
+    Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize, schemars::JsonSchema,
+)]
+#[serde(rename_all = "snake_case")]
+pub enum NestedStorage {
+    /// Child steps store under their own ids, visible to the parent plan
+    #[default]
+    Shared,
+    /// Child steps store under "<parent step id>/<child step id>"
+    Isolated,
+}
+
+/// Storage namespace and nesting depth of a (possibly nested) plan run
+#[derive(Debug, Clone, Default)]
+struct RunScope {
+    prefix: String,
+    depth: usize,
+    /// Plan id a top-level run checkpoints its completed steps under
+    checkpoint: Option<String>,
+    /// Take steps completed by an earlier run from the checkpoint
+    resume: bool,
+    /// Directory the run records its own fixture to (`--record`); a nested
+    /// plan's is the parent's joined with the `plan` step id
+    fixture: Option<PathBuf>,
+}
+
+impl RunScope {
+    fn is_nested(&self) -> bool {
+        self.depth > 0
+    }
+
+    /// Storage key for a step's output
+    fn key(&self, step_id: &str) -> String {
+        format!("{}{}", self.prefix, step_id)
+    }
+
+    fn child(&self, step_id: &str, storage: NestedStorage) -> Self {
+        let prefix = match storage {
+            NestedStorage::Shared => self.prefix.clone(),
+            NestedStorage::Isolated => format!("{}{}/", self.prefix, step_id),
+        };
+        Self {
+            prefix,
+            depth: self.depth + 1,
+            checkpoint: None,
+            resume: false,
+            fixture: self.fixture.as_ref().map(|dir| dir.join(step_id)),
+        }
+    }
+}
+
+/// How a plan step ended
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum StepStatus {
+    Completed,
+    /// Not executed because of dry-run (plan-wide or `force_dry_run`) or a
+    /// false `when` condition
+    Skipped,
+    /// Output taken from a replay fixture
+    Replayed,
+    /// Completed by an earlier run; output taken from the plan's checkpoint
+    Resumed,
+    /// Failed under a `continue` or `skip_dependents` policy
+    Failed,
+}
+
+impl StepStatus {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            StepStatus::Completed => "completed",
+            StepStatus::Skipped => "skipped",
+            StepStatus::Replayed => "replayed",
+            StepStatus::Resumed => "resumed",
+            StepStatus::Failed => "failed",
+        }
+    }
+}
+
+/// Outcome of one plan step
+#[derive(Debug, Clone, Serialize)]
+pub struct StepResult {
+    pub index: usize,
+    pub id: String,
+    pub tool: String,
+    pub status: StepStatus,
+    pub output: Option<Value>,
+    pub duration_ms: u64,
+    /// Same duration at microsecond resolution (runtime overhead is often sub-ms)
+    pub duration_us: u64,
+    /// Why the step failed
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+impl StepResult {
+    fn new(
+        step: &ScheduledStep,
+        status: StepStatus,
+        output: Option<Value>,
+        duration: Duration,
+    ) -> Self {
+        Self {
+            index: step.index,
+            id: step.id.clone(),
+            tool: step.tool.clone(),
+            status,
+            output,
+            duration_ms: duration.as_millis() as u64,
+            duration_us: duration.as_micros() as u64,
+            error: None,
+        }
+    }
+
+    fn failed(step: &ScheduledStep, error: String, duration: Duration) -> Self {
+        Self {
+            error: Some(error),
+            ..Self::new(step, StepStatus::Failed, None, duration)
+        }
+    }
+}
+
+/// Outcome of a plan run, with steps in plan order
+#[derive(Debug, Clone, Serialize)]
+pub struct PlanResult {
+    pub plan_id: String,
+    pub dry_run: bool,
+    pub steps: Vec<StepResult>,
+    /// Output of the last step to finish that produced one
+    pub output: Option<Value>,
+    /// Ids of the steps that failed without aborting the plan
+    pub failed: Vec<String>,
+    pub duration_ms: u64,
+}
+
+impl PlanResult {
+    /// `steps` in completion order; stored in plan order
+    fn new(plan_id: String, dry_run: bool, mut steps: Vec<StepResult>, duration_ms: u64) -> Self {
+        let output = steps.iter().rev().find_map(|s| s.output.clone());
+        steps.sort_by_key(|s| s.index);
+        let failed = steps
+            .iter()
+            .filter(|s| s.status == StepStatus::Failed)
+            .map(|s| s.id.clone())
+            .collect();
+        Self {
+            plan_id,
+            dry_run,
+            steps,
+            output,
+            failed,
+            duration_ms,
+        }
+    }
+
+    /// Result of the step with `id`
+    pub fn step(&self, id: &str) -> Option<&StepResult> {
+        self.steps.iter().find(|s| s.id == id)
+    }
+
+    /// Number of steps that ended with `status`
+    pub fn count(&self, status: StepStatus) -> usize {
+        self.steps.iter().filter(|s| s.status == status).count()
+    }
+}
+
 pub struct Runtime {
     tools: Arc<DashMap<String, Arc<dyn Tool>>>,
     storage: Storage,
     dry_run: bool,
     default_timeout: Duration,
     tool_timeouts: DashMap<String, Duration>,
+    /// Extra names for registered tools (alias → registered name)
+    aliases: DashMap<String, String>,
+    /// Result cache TTL for tools that opted in
+    cache_ttls: DashMap<String, Duration>,
+    /// Cached results keyed by (tool, canonical input JSON)
+    result_cache: DashMap<(String, String), (std::time::Instant, Value)>,
+    /// Held while a keyed call runs so concurrent duplicates wait for its result
+    idempotency_locks: DashMap<String, Arc<tokio::sync::Mutex<()>>>,
+    /// How long a stored idempotent result is returned before the call runs again
+    idempotency_ttl: Duration,
+    /// Tokens left in each agent session's context window, for the policy
+    /// pipeline to check tool results against
+    context_tokens_left: DashMap<String, usize>,
+    /// Agent running each session, for policy layers scoped by agent
+    session_agents: DashMap<String, String>,
+    /// Latest messages of each agent session, for policy layers that weigh
+    /// the conversation
+    session_messages: DashMap<String, Vec<Message>>,
+    /// Gateway identity each session is serving, for per-user policy limits
+    session_principals: DashMap<String, String>,
     state: AtomicU8,
     execution_context: ExecutionContext,
     max_parallel: usize,
+    /// Execution slots shared by chat calls and plan steps (`max_parallel` of them)
+    exec_queue: ExecQueue,
+    /// Per-resource-class concurrency caps, applied on top of `max_parallel`
+    resource_limits: HashMap<String, usize>,
+    /// Default storage for `plan` steps without a `storage` input
+    nested_storage: NestedStorage,
     /// Optional policy pipeline evaluated before every tool execution
-    policy: Option<ToolPolicyPipeline>,
+    policy: std::sync::RwLock<Option<Arc<ToolPolicyPipeline>>>,
+    /// Asked about calls the policy pipeline holds for approval
+    approver: Option<Arc<dyn Approver>>,
+    /// Layers wrapped around every tool call, outermost first
+    tool_middleware: Arc<Vec<Arc<dyn ToolMiddleware>>>,
+    /// Where tool calls run once they pass the middleware
+    execution_backend: Arc<dyn ExecutionBackend>,
+    /// Hooks around tool calls and lifecycle events (plan and session end)
+    hooks: Option<Arc<HookRegistry>>,
+    /// Registered composites, whose steps run as calls of their own
+    composites: DashMap<String, Arc<CompositeSpec>>,
+    /// Whether every tool call is recorded in the storage's audit trail
+    audit_trail: bool,
 }
 
 impl Runtime {
@@ -57,10 +289,28 @@ impl Runtime {
             dry_run,
             default_timeout,
             tool_timeouts: DashMap::new(),
+            aliases: DashMap::new(),
+            cache_ttls: DashMap::new(),
+            result_cache: DashMap::new(),
+            idempotency_locks: DashMap::new(),
+            idempotency_ttl: DEFAULT_IDEMPOTENCY_TTL,
+            context_tokens_left: DashMap::new(),
+            session_agents: DashMap::new(),
+            session_messages: DashMap::new(),
+            session_principals: DashMap::new(),
             state: AtomicU8::new(STATE_IDLE),
             execution_context: ExecutionContext::Normal,
             max_parallel: 4,
-            policy: None,
+            exec_queue: ExecQueue::new(4),
+            resource_limits: HashMap::new(),
+            nested_storage: NestedStorage::default(),
+            policy: std::sync::RwLock::new(None),
+            approver: None,
+            tool_middleware: Arc::new(Vec::new()),
+            execution_backend: Arc::new(InProcess),
+            hooks: None,
+            composites: DashMap::new(),
+            audit_trail: false,
         })
     }
 
@@ -73,44 +323,354 @@ impl Runtime {
     /// Set max parallel concurrency
     pub fn with_max_parallel(mut self, max: usize) -> Self {
         self.max_parallel = max.max(1);
+        self.exec_queue = ExecQueue::new(self.max_parallel);
+        self
+    }
+
+    /// Cap how many steps of a resource class (e.g. "shell") run at once
+    pub fn with_resource_limit(mut self, class: &str, limit: usize) -> Self {
+        self.resource_limits.insert(class.to_string(), limit.max(1));
+        self
+    }
+
+    /// Default storage for nested `plan` steps (overridable per step)
+    pub fn with_nested_storage(mut self, storage: NestedStorage) -> Self {
+        self.nested_storage = storage;
+        self
+    }
+
+    /// Wrap every tool call in `middleware`, inside the layers already added
+    pub fn with_tool_middleware(mut self, middleware: Arc<dyn ToolMiddleware>) -> Self {
+        Arc::make_mut(&mut self.tool_middleware).push(middleware);
+        self
+    }
+
+    /// Run tool calls on `backend` instead of in this process
+    pub fn with_execution_backend(mut self, backend: Arc<dyn ExecutionBackend>) -> Self {
+        info!(backend = backend.name(), "Tool execution backend set");
+        self.execution_backend = backend;
+        self
+    }
+
+    /// Trigger tool call (`ToolCallBefore`, `ToolCallAfter`), session
+    /// (`SessionStart`, `MessageReceived`, `ResponseGenerated`, `SessionEnd`)
+    /// and `PlanEnd` events on `registry`
+    pub fn with_hooks(mut self, registry: Arc<HookRegistry>) -> Self {
+        self.hooks = Some(registry);
+        self
+    }
+
+    /// Run the hooks registered for `event`, for events hooks can only
+    /// observe. Hook failures are logged, never returned.
+    pub async fn trigger_hook(&self, event: HookEvent, data: Value, session_id: Option<&str>) {
+        let result = self
+            .trigger_hook_checked(event.clone(), || data, session_id)
+            .await;
+        if let Err(e) = result {
+            warn!(event = ?event, error = %e, "Lifecycle hook failed");
+        }
+    }
+
+    /// Run the hooks registered for `event` on the payload from `data`,
+    /// returning it as the hooks left it (None: no hooks registered). Fails if
+    /// a hook aborts the operation or a critical hook fails. Hooks see the
+    /// agent recorded for `session_id` (see [`Runtime::set_session_agent`]).
+    pub async fn trigger_hook_checked(
+        &self,
+        event: HookEvent,
+        data: impl FnOnce() -> Value,
+        session_id: Option<&str>,
+    ) -> Result<Option<Value>> {
+        let Some(hooks) = &self.hooks else {
+            return Ok(None);
+        };
+        if !hooks.has_hooks(&event) {
+            return Ok(None);
+        }
+        let ctx = HookContext {
+            event,
+            data: data(),
+            agent_id: session_id
+                .and_then(|id| self.session_agents.get(id).map(|agent| agent.clone())),
+            session_id: session_id.map(str::to_string),
+        };
+        hooks.trigger(ctx).await.map(Some)
+    }
+
+    /// How long the result of a call made with an idempotency key is
+    /// returned for repeats of it (default 24 hours)
+    pub fn with_idempotency_ttl(mut self, ttl: Duration) -> Self {
+        self.idempotency_ttl = ttl;
+        self
+    }
+
+    /// Record every tool call (policy decision, duration, outcome) in the
+    /// storage's audit trail
+    pub fn with_audit_trail(mut self) -> Self {
+        self.audit_trail = true;
         self
     }
 
     /// Set tool policy pipeline (builder pattern)
     pub fn with_policy(mut self, pipeline: ToolPolicyPipeline) -> Self {
-        self.policy = Some(pipeline);
+        self.set_policy(pipeline);
         self
     }
 
     /// Set tool policy pipeline (mutable reference, call before Arc wrapping)
     pub fn set_policy(&mut self, pipeline: ToolPolicyPipeline) {
-        self.policy = Some(pipeline);
+        *self.policy.get_mut().unwrap_or_else(|e| e.into_inner()) = Some(Arc::new(pipeline));
+    }
+
+    /// Swap the policy pipeline of a running runtime (e.g. on config reload);
+    /// calls already being checked finish under the old one
+    pub fn replace_policy(&self, pipeline: Option<ToolPolicyPipeline>) {
+        *self.policy.write().unwrap_or_else(|e| e.into_inner()) = pipeline.map(Arc::new);
+    }
+
+    /// Ask `approver` about calls the policy pipeline holds for approval;
+    /// without one, those calls are denied
+    pub fn with_approver(mut self, approver: Arc<dyn Approver>) -> Self {
+        self.approver = Some(approver);
+        self
     }
 
-    /// Register a tool. Fails if runtime is currently executing a plan.
+    /// Register a tool. Fails if runtime is currently executing a plan; safe
+    /// at any other time, including between turns of a running chat session.
     pub fn register_tool(&self, name: String, tool: Arc<dyn Tool>) -> Result<()> {
-        if self.state.load(Ordering::SeqCst) != STATE_IDLE {
+        if self.is_executing_plan() {
             anyhow::bail!("Cannot register tools while runtime is executing a plan");
         }
+        self.composites.remove(&name);
         self.tools.insert(name, tool);
         Ok(())
     }
 
+    /// Remove a registered tool with its timeout, cache and aliases, returning it.
+    /// Calls already in flight keep their own reference and finish normally.
+    pub fn unregister_tool(&self, name: &str) -> Result<Arc<dyn Tool>> {
+        if self.is_executing_plan() {
+            anyhow::bail!("Cannot unregister tools while runtime is executing a plan");
+        }
+        if let Some(target) = self.aliases.get(name) {
+            anyhow::bail!("'{}' is an alias of '{}'; use remove_alias", name, *target);
+        }
+        let (_, tool) = self
+            .tools
+            .remove(name)
+            .context(format!("Tool '{}' not registered", name))?;
+        self.tool_timeouts.remove(name);
+        self.cache_ttls.remove(name);
+        self.composites.remove(name);
+        self.result_cache.retain(|(tool, _), _| tool != name);
+        self.aliases.retain(|_, target| target != name);
+        info!(tool = name, "Tool unregistered");
+        Ok(tool)
+    }
+
+    /// Remove an alias; returns whether it existed
+    pub fn remove_alias(&self, alias: &str) -> bool {
+        self.aliases.remove(alias).is_some()
+    }
+
+    /// Whether a plan is running (tool registration is locked meanwhile)
+    pub fn is_executing_plan(&self) -> bool {
+        self.state.load(Ordering::SeqCst) != STATE_IDLE
+    }
+
+    /// Registered tool for `name` (following aliases), cloned out of the
+    /// registry so no lock is held while it runs
+    fn get_tool(&self, name: &str) -> Option<Arc<dyn Tool>> {
+        self.tools
+            .get(&self.resolve_tool_name(name))
+            .map(|t| t.value().clone())
+    }
+
+    /// Expose a registered tool under another name as well (e.g. "bash" for "shell")
+    pub fn alias_tool(&self, alias: &str, target: &str) -> Result<()> {
+        if self.tools.contains_key(alias) {
+            anyhow::bail!("Alias '{}' collides with a registered tool", alias);
+        }
+        // Aliases of aliases point straight at the tool
+        let target = self.resolve_tool_name(target);
+        if !self.tools.contains_key(&target) {
+            anyhow::bail!("Alias '{}' targets unregistered tool '{}'", alias, target);
+        }
+        self.aliases.insert(alias.to_string(), target);
+        Ok(())
+    }
+
+    /// Move a registered tool to a new name (e.g. to avoid a collision);
+    /// its timeout, cache TTL and aliases follow it
+    pub fn rename_tool(&self, from: &str, to: &str) -> Result<()> {
+        if self.is_executing_plan() {
+            anyhow::bail!("Cannot rename tools while runtime is executing a plan");
+        }
+        if self.tools.contains_key(to) || self.aliases.contains_key(to) {
+            anyhow::bail!("Cannot rename '{}': '{}' is already in use", from, to);
+        }
+        let (_, tool) = self
+            .tools
+            .remove(from)
+            .context(format!("Cannot rename unregistered tool '{}'", from))?;
+        self.tools.insert(to.to_string(), tool);
+        if let Some((_, timeout)) = self.tool_timeouts.remove(from) {
+            self.tool_timeouts.insert(to.to_string(), timeout);
+        }
+        if let Some((_, ttl)) = self.cache_ttls.remove(from) {
+            self.cache_ttls.insert(to.to_string(), ttl);
+        }
+        if let Some((_, spec)) = self.composites.remove(from) {
+            self.composites.insert(to.to_string(), spec);
+        }
+        self.result_cache.retain(|(tool, _), _| tool != from);
+        for mut alias in self.aliases.iter_mut() {
+            if alias.value() == from {
+                *alias.value_mut() = to.to_string();
+            }
+        }
+        Ok(())
+    }
+
+    /// Registered name for `name`, following aliases; registered tools win
+    pub fn resolve_tool_name(&self, name: &str) -> String {
+        if self.tools.contains_key(name) {
+            return name.to_string();
+        }
+        self.aliases
+            .get(name)
+            .map(|target| target.clone())
+            .unwrap_or_else(|| name.to_string())
+    }
+
+    /// Register a composite tool chaining already-registered tools. Each
+    /// step is a tool call of its own, checked by the policy pipeline and
+    /// hooks and run with its tool's timeout.
+    pub fn register_composite(&self, spec: CompositeSpec) -> Result<()> {
+        let name = spec.name.clone();
+        let tool = CompositeTool::new(spec, self.tools.clone())?;
+        let spec = Arc::new(tool.spec().clone());
+        self.register_tool(name.clone(), Arc::new(tool))?;
+        self.composites.insert(name, spec);
+        Ok(())
+    }
+
+    /// Composite registered as `name` (following aliases)
+    fn composite(&self, name: &str) -> Option<Arc<CompositeSpec>> {
+        self.composites
+            .get(&self.resolve_tool_name(name))
+            .map(|spec| spec.value().clone())
+    }
+
+    /// Run a composite's steps as calls made by the composite's caller, all
+    /// within the composite's own timeout. Boxed because the steps recurse
+    /// back into `execute_call`.
+    fn run_composite<'a>(
+        &'a self,
+        name: &'a str,
+        spec: Arc<CompositeSpec>,
+        input: Value,
+        caller_permission: PermissionLevel,
+        session: Option<&'a str>,
+    ) -> Pin<Box<dyn Future<Output = Result<Value>> + Send + 'a>> {
+        Box::pin(async move {
+            let timeout = self.get_timeout(name);
+            let chain = composite::run_chain(&spec, input, |tool, input| {
+                let permission = caller_permission.clone();
+                async move {
+                    self.execute_call(&tool, input, permission, None, session)
+                        .await
+                }
+            });
+            tokio::time::timeout(timeout, chain).await.map_err(|_| {
+                anyhow::anyhow!(
+                    "Tool '{}' timed out after {:.1}s",
+                    name,
+                    timeout.as_secs_f64()
+                )
+            })?
+        })
+    }
+
     /// Configure timeout for specific tool
     pub fn configure_timeout(&self, tool_name: String, timeout: Duration) {
         self.tool_timeouts.insert(tool_name, timeout);
     }
 
+    /// Cache results of an idempotent tool (e.g. read_file) for `ttl`; calls
+    /// with the same input within the TTL return the cached result
+    pub fn configure_cache_ttl(&self, tool_name: &str, ttl: Duration) {
+        self.cache_ttls
+            .insert(self.resolve_tool_name(tool_name), ttl);
+    }
+
+    /// Drop all cached tool results
+    pub fn clear_tool_cache(&self) {
+        self.result_cache.clear();
+    }
+
+    /// Cached result for a call, if the tool caches and the entry is fresh
+    fn cached_result(&self, tool_name: &str, input: &Value) -> Option<Value> {
+        let ttl = *self.cache_ttls.get(tool_name)?;
+        // serde_json maps keep keys sorted, so equal inputs serialize identically
+        let key = (tool_name.to_string(), input.to_string());
+        let (stored_at, output) = self.result_cache.get(&key).map(|e| e.value().clone())?;
+        if stored_at.elapsed() < ttl {
+            return Some(output);
+        }
+        self.result_cache.remove(&key);
+        None
+    }
+
+    /// Remember a result for tools that cache; a successful call to a tool
+    /// that can change state (above Read) invalidates everything cached
+    fn store_result(&self, tool_name: &str, tool: &dyn Tool, input: &Value, output: &Value) {
+        if let Some(ttl) = self.cache_ttls.get(tool_name).map(|t| *t) {
+            if self.result_cache.len() >= MAX_CACHED_RESULTS {
+                self.result_cache
+                    .retain(|_, (stored_at, _)| stored_at.elapsed() < ttl);
+            }
+            self.result_cache.insert(
+                (tool_name.to_string(), input.to_string()),
+                (std::time::Instant::now(), output.clone()),
+            );
+        } else if tool.permission_level() > PermissionLevel::Read && !self.result_cache.is_empty() {
+            self.result_cache.clear();
+        }
+    }
+
     /// Get timeout for tool (custom or default)
     pub fn get_timeout(&self, tool_name: &str) -> Duration {
         self.tool_timeouts
-            .get(tool_name)
+            .get(&self.resolve_tool_name(tool_name))
             .map(|t| *t)
             .unwrap_or(self.default_timeout)
     }
 
-    /// Run plan JSON with state machine guard
-    pub async fn run_plan(&self, plan: Value) -> Result<()> {
+    /// Run plan JSON with state machine guard. Plans with an `id` checkpoint
+    /// each completed step so a failed run can be resumed.
+    pub async fn run_plan(&self, plan: Value) -> Result<PlanResult> {
+        self.run_top_level(plan, false).await
+    }
+
+    /// Run a plan again, taking the steps its last run completed from the
+    /// checkpoint instead of executing them
+    pub async fn resume_plan(&self, plan: Value) -> Result<PlanResult> {
+        self.run_top_level(plan, true).await
+    }
+
+    async fn run_top_level(&self, plan: Value, resume: bool) -> Result<PlanResult> {
+        // Dry and replayed runs complete nothing worth keeping
+        let checkpoint = plan["id"]
+            .as_str()
+            .filter(|_| {
+                !self.dry_run && !matches!(self.execution_context, ExecutionContext::Replay(_))
+            })
+            .map(str::to_string);
+        if resume && checkpoint.is_none() {
+            anyhow::bail!("Only plans with an 'id' can resume, and not in dry-run or replay mode");
+        }
+
         // Transition Idle → Running (CAS prevents concurrent runs)
         if self
             .state
@@ -125,232 +685,893 @@ impl Runtime {
             anyhow::bail!("Runtime is already executing a plan");
         }
 
-        let result = self.run_plan_inner(plan).await;
+        let plan_id = plan["id"].as_str().unwrap_or("unknown").to_string();
+        let start = std::time::Instant::now();
+        let fixture = match &self.execution_context {
+            ExecutionContext::Record(dir) => Some(dir.clone()),
+            _ => None,
+        };
+        let scope = RunScope {
+            checkpoint,
+            resume,
+            fixture,
+            ..RunScope::default()
+        };
+        let result = self.run_plan_inner(plan, None, &scope).await;
 
         // Transition Running → Idle (always, even on error)
         self.state.store(STATE_IDLE, Ordering::SeqCst);
 
+        let summary = match &result {
+            Ok(result) => serde_json::json!({
+                "plan_id": result.plan_id,
+                "status": "succeeded",
+                "duration_ms": result.duration_ms,
+                "steps": result.steps.len(),
+                "failed": result.failed,
+                "dry_run": result.dry_run,
+            }),
+            Err(e) => serde_json::json!({
+                "plan_id": plan_id,
+                "status": "failed",
+                "error": format!("{:#}", e),
+                "duration_ms": start.elapsed().as_millis() as u64,
+                "dry_run": self.dry_run,
+            }),
+        };
+        self.trigger_hook(HookEvent::PlanEnd, summary, None).await;
+
         result
     }
 
     /// Core plan execution: routes to sequential or parallel based on dependencies
-    async fn run_plan_inner(&self, plan: Value) -> Result<()> {
+    /// `params` overrides the plan's parameter defaults
+    async fn run_plan_inner(
+        &self,
+        plan: Value,
+        params: Option<&Value>,
+        scope: &RunScope,
+    ) -> Result<PlanResult> {
+        let plan = scheduler::apply_params(&plan, params)?;
         let steps = scheduler::parse_steps(&plan)?;
+        scheduler::check_output_references(&steps)?;
         let plan_id = plan["id"].as_str().unwrap_or("unknown").to_string();
+        if let Some(checkpoint_id) = &scope.checkpoint {
+            checkpoint::start(&self.storage, checkpoint_id, &plan, scope.resume).await?;
+        }
+        let start = std::time::Instant::now();
 
         // If no dependencies declared, fall back to sequential for backward compat
-        if !scheduler::has_dependencies(&steps) {
-            return self.run_sequential(&steps, &plan_id).await;
+        let results = if scheduler::has_dependencies(&steps) {
+            self.run_dag(&steps, &plan_id, scope).await?
+        } else {
+            self.run_sequential(&steps, &plan_id, scope).await?
+        };
+
+        Ok(PlanResult::new(
+            plan_id,
+            self.dry_run,
+            results,
+            start.elapsed().as_millis() as u64,
+        ))
+    }
+
+    /// Replay fixture for a top-level run; nested plans replay as a single step
+    fn replay_fixture(&self, scope: &RunScope) -> Result<Option<Fixture>> {
+        match &self.execution_context {
+            ExecutionContext::Replay(dir) if !scope.is_nested() => Ok(Some(Fixture::load(dir)?)),
+            _ => Ok(None),
+        }
+    }
+
+    /// Save a recording run's fixture
+    fn save_recordings(
+        &self,
+        plan_id: &str,
+        mut recordings: Vec<StepRecord>,
+        scope: &RunScope,
+    ) -> Result<()> {
+        if let Some(dir) = &scope.fixture {
+            recordings.sort_by_key(|r| r.index);
+            let fixture = Fixture {
+                version: replay::FIXTURE_VERSION,
+                plan_id: plan_id.to_string(),
+                recorded_at: replay::timestamp_now(),
+                steps: recordings,
+            };
+            fixture.save(dir)?;
+            info!(dir = ?dir, "Fixture recorded");
+        }
+        Ok(())
+    }
+
+    /// `step` ready to run, or `None` if its `when` condition is false. The
+    /// `{{steps.<id>.output...}}` placeholders in its input are filled in
+    /// from the stored outputs of steps that ran earlier in this run.
+    async fn prepare_step(
+        &self,
+        step: &ScheduledStep,
+        scope: &RunScope,
+        results: &[StepResult],
+    ) -> Result<Option<ScheduledStep>> {
+        let mut refs = scheduler::output_references(step)?;
+        if let Some(id) = step.foreach.as_ref().and_then(|foreach| foreach.step()) {
+            if !refs.iter().any(|r| r == id) {
+                refs.push(id.to_string());
+            }
+        }
+        let condition_ref = step.when.as_ref().map(|condition| condition.step());
+        let mut outputs = HashMap::with_capacity(refs.len() + 1);
+        for id in refs.iter().map(String::as_str).chain(condition_ref) {
+            let ran = results.iter().any(|r| {
+                r.id == id
+                    && matches!(
+                        r.status,
+                        StepStatus::Completed | StepStatus::Replayed | StepStatus::Resumed
+                    )
+            });
+            if !ran || outputs.contains_key(id) {
+                continue;
+            }
+            let output = self
+                .storage
+                .load_state_async(&scope.key(id))
+                .await?
+                .context(format!("No stored output for step '{}'", id))?;
+            outputs.insert(id.to_string(), output);
+        }
+
+        if let Some(condition) = &step.when {
+            if !condition.evaluate(&outputs) {
+                info!(step = step.index, when = %condition, "Condition is false, skipping");
+                return Ok(None);
+            }
+        }
+        if refs.is_empty() {
+            return Ok(Some(step.clone()));
+        }
+        if let Some(id) = refs.iter().find(|id| !outputs.contains_key(*id)) {
+            anyhow::bail!(
+                "Step '{}' uses the output of '{}', which did not run",
+                step.id,
+                id
+            );
+        }
+        let mut resolved = step.clone();
+        resolved.input = scheduler::resolve_input(step, &outputs)
+            .context(format!("Failed to resolve the input of step '{}'", step.id))?;
+        if let Some(foreach) = &step.foreach {
+            resolved.foreach = Some(
+                foreach
+                    .resolve(&outputs)
+                    .context(format!("Failed to resolve the items of step '{}'", step.id))?,
+            );
+        }
+        Ok(Some(resolved))
+    }
+
+    /// Run a step in the calling task: a nested plan, the items of a
+    /// `foreach` step, or a tool call, with retries
+    async fn run_step(&self, step: &ScheduledStep, scope: &RunScope) -> Result<Value> {
+        if step.foreach.is_some() {
+            self.run_foreach(step).await
+        } else if step.tool == PLAN_STEP_TOOL {
+            with_retries(step, || self.run_plan_step(step, scope)).await
+        } else {
+            with_retries(step, || self.execute_step(step)).await
+        }
+    }
+
+    /// Run a `foreach` step's items concurrently, at most `max_parallel` (and
+    /// its resource class limit) at a time. The output is the items' outputs
+    /// in order; the first failed item fails the step.
+    async fn run_foreach(&self, step: &ScheduledStep) -> Result<Value> {
+        let items = step.expand()?;
+        let limit = self
+            .resource_limits
+            .get(&step.resource_class)
+            .map_or(self.max_parallel, |&limit| limit.min(self.max_parallel))
+            .max(1);
+        info!(step = step.index, tool = %step.tool, items = items.len(), limit, "Running foreach step");
+        let semaphore = Semaphore::new(limit);
+        let runs = items.iter().map(|item| async {
+            let _permit = semaphore
+                .acquire()
+                .await
+                .map_err(|e| anyhow::anyhow!("Semaphore closed: {}", e))?;
+            with_retries(item, || self.execute_step(item)).await
+        });
+        let outputs = futures::future::try_join_all(runs).await?;
+        Ok(Value::Array(outputs))
+    }
+
+    /// Store a finished step's output and note it for the fixture and result
+    async fn complete_step(
+        &self,
+        scope: &RunScope,
+        step: &ScheduledStep,
+        output: Value,
+        duration: Duration,
+        recordings: &mut Vec<StepRecord>,
+        results: &mut Vec<StepResult>,
+    ) -> Result<()> {
+        let duration_ms = duration.as_millis() as u64;
+        info!(step = step.index, tool = %step.tool, duration_ms, "Step completed");
+        self.storage
+            .save_state_async(&scope.key(&step.id), output.clone())
+            .await?;
+        if let Some(plan_id) = &scope.checkpoint {
+            checkpoint::complete(&self.storage, plan_id, &step.id, &output).await?;
+        }
+
+        if scope.fixture.is_some() {
+            recordings.push(StepRecord {
+                index: step.index,
+                tool: step.tool.clone(),
+                input: step.input.clone(),
+                output: output.clone(),
+                duration_ms,
+                error: None,
+            });
+        }
+        results.push(StepResult::new(
+            step,
+            StepStatus::Completed,
+            Some(output),
+            duration,
+        ));
+        Ok(())
+    }
+
+    /// Result of a step an earlier run of this plan completed, with its
+    /// checkpointed output restored to storage
+    async fn resumed_step(
+        &self,
+        scope: &RunScope,
+        step: &ScheduledStep,
+    ) -> Result<Option<StepResult>> {
+        let Some(plan_id) = scope.checkpoint.as_ref().filter(|_| scope.resume) else {
+            return Ok(None);
+        };
+        let Some(output) = checkpoint::completed(&self.storage, plan_id, &step.id).await? else {
+            return Ok(None);
+        };
+        info!(step = step.index, tool = %step.tool, "RESUME: Completed by an earlier run");
+        self.storage
+            .save_state_async(&scope.key(&step.id), output.clone())
+            .await?;
+        Ok(Some(StepResult::new(
+            step,
+            StepStatus::Resumed,
+            Some(output),
+            Duration::ZERO,
+        )))
+    }
+
+    /// Record a failed step under its `on_error` policy; returns the error
+    /// when the policy aborts the plan
+    fn fail_step(
+        &self,
+        scope: &RunScope,
+        step: &ScheduledStep,
+        error: anyhow::Error,
+        duration: Duration,
+        recordings: &mut Vec<StepRecord>,
+        results: &mut Vec<StepResult>,
+    ) -> Result<()> {
+        if step.on_error == OnError::Abort {
+            return Err(error);
         }
+        let message = format!("{:#}", error);
+        warn!(step = step.index, tool = %step.tool, on_error = ?step.on_error, error = %message, "Step failed");
+
+        if scope.fixture.is_some() {
+            recordings.push(StepRecord {
+                index: step.index,
+                tool: step.tool.clone(),
+                input: step.input.clone(),
+                output: Value::Null,
+                duration_ms: duration.as_millis() as u64,
+                error: Some(message.clone()),
+            });
+        }
+        results.push(StepResult::failed(step, message, duration));
+        Ok(())
+    }
+
+    /// Run the plan given by a `plan` step's input (`file` or inline `plan`)
+    /// as a child run with the step's `params` and `timeout_secs` (else the
+    /// `plan` tool timeout, if configured); its output is the child's
+    /// `PlanResult`. Boxed because the child run recurses back into this
+    /// function.
+    fn run_plan_step<'a>(
+        &'a self,
+        step: &'a ScheduledStep,
+        scope: &'a RunScope,
+    ) -> Pin<Box<dyn Future<Output = Result<Value>> + Send + 'a>> {
+        Box::pin(async move {
+            if scope.depth >= MAX_PLAN_DEPTH {
+                anyhow::bail!(
+                    "Nested plans exceed max depth {} (step '{}')",
+                    MAX_PLAN_DEPTH,
+                    step.id
+                );
+            }
+            let child = if let Some(file) = step.input["file"].as_str() {
+                let content = std::fs::read_to_string(file)
+                    .context(format!("Failed to read nested plan file: {:?}", file))?;
+                serde_json::from_str(&content).context("Failed to parse nested plan JSON")?
+            } else if step.input["plan"].is_object() {
+                step.input["plan"].clone()
+            } else {
+                anyhow::bail!("Step '{}' needs a 'file' or inline 'plan' input", step.id);
+            };
+            let storage = match step.input.get("storage") {
+                Some(value) => serde_json::from_value(value.clone())
+                    .context(format!("Invalid 'storage' for step '{}'", step.id))?,
+                None => self.nested_storage,
+            };
+
+            let timeout = match step.input.get("timeout_secs") {
+                Some(secs) => Some(Duration::from_secs(
+                    secs.as_u64()
+                        .context(format!("Invalid 'timeout_secs' for step '{}'", step.id))?,
+                )),
+                None => self.tool_timeouts.get(PLAN_STEP_TOOL).map(|t| *t),
+            };
+
+            info!(step = step.index, ?storage, ?timeout, "Running nested plan");
+            let child_scope = scope.child(&step.id, storage);
+            let run = self.run_plan_inner(child, step.input.get("params"), &child_scope);
+            let result = match timeout {
+                Some(timeout) => tokio::time::timeout(timeout, run).await.map_err(|_| {
+                    anyhow::anyhow!(
+                        "Nested plan timed out after {:.1}s (step '{}')",
+                        timeout.as_secs_f64(),
+                        step.id
+                    )
+                })?,
+                None => run.await,
+            }
+            .context(format!("Nested plan failed (step '{}')", step.id))?;
+            Ok(serde_json::to_value(result)?)
+        })
+    }
 
-        // Compute execution levels (DAG)
-        let levels = scheduler::compute_levels(&steps)?;
+    /// DAG execution: each level's steps run concurrently
+    async fn run_dag(
+        &self,
+        steps: &[ScheduledStep],
+        plan_id: &str,
+        scope: &RunScope,
+    ) -> Result<Vec<StepResult>> {
+        let levels = scheduler::compute_levels(steps)?;
         info!(levels = levels.len(), "Executing plan with DAG scheduling");
 
         let semaphore = Arc::new(Semaphore::new(self.max_parallel));
+        let class_semaphores: HashMap<String, Arc<Semaphore>> = self
+            .resource_limits
+            .iter()
+            .map(|(class, &limit)| (class.clone(), Arc::new(Semaphore::new(limit))))
+            .collect();
         let mut recordings: Vec<StepRecord> = Vec::new();
+        let mut results: Vec<StepResult> = Vec::new();
+        // Failed `skip_dependents` steps and the steps skipped because of them
+        let mut blocked: HashSet<String> = HashSet::new();
 
         // Load replay fixture if needed
-        let replay_fixture = match &self.execution_context {
-            ExecutionContext::Replay(dir) => Some(Fixture::load(dir)?),
-            _ => None,
-        };
+        let replay_fixture = self.replay_fixture(scope)?;
 
         for (level_idx, level) in levels.iter().enumerate() {
             info!(level = level_idx, steps = level.len(), "Executing level");
 
-            if self.dry_run {
-                for &step_idx in level {
-                    let step = &steps[step_idx];
+            blocked.extend(
+                results
+                    .iter()
+                    .filter(|r| {
+                        r.status == StepStatus::Failed
+                            && steps[r.index].on_error == OnError::SkipDependents
+                    })
+                    .map(|r| r.id.clone()),
+            );
+            let mut live = Vec::with_capacity(level.len());
+            for &step_idx in level {
+                let step = &steps[step_idx];
+                if let Some(result) = self.resumed_step(scope, step).await? {
+                    results.push(result);
+                    continue;
+                }
+                if let Some(dep) = step.depends_on.iter().find(|dep| blocked.contains(*dep)) {
+                    warn!(step = step.index, tool = %step.tool, dependency = %dep, "Skipping: dependency failed");
+                    blocked.insert(step.id.clone());
+                } else if step.dry_run(self.dry_run) {
                     warn!(step = step.index, tool = %step.tool, "DRY-RUN: Skipping");
+                } else {
+                    live.push(step_idx);
+                    continue;
+                }
+                results.push(StepResult::new(
+                    step,
+                    StepStatus::Skipped,
+                    None,
+                    Duration::ZERO,
+                ));
+            }
+            // Steps whose `when` is false are skipped like dry-run ones
+            let mut prepared = Vec::with_capacity(live.len());
+            for &step_idx in &live {
+                let step = &steps[step_idx];
+                match self.prepare_step(step, scope, &results).await? {
+                    Some(step) => prepared.push(step),
+                    None => results.push(StepResult::new(
+                        step,
+                        StepStatus::Skipped,
+                        None,
+                        Duration::ZERO,
+                    )),
                 }
+            }
+            if prepared.is_empty() {
                 continue;
             }
 
             // Replay: return recorded outputs
             if let Some(ref fixture) = replay_fixture {
-                for &step_idx in level {
-                    let step = &steps[step_idx];
+                for step in &prepared {
                     let record = fixture
                         .steps
                         .iter()
                         .find(|r| r.index == step.index)
                         .context(format!("No fixture for step {}", step.index))?;
                     info!(step = step.index, tool = %step.tool, "REPLAY");
-                    self.storage.save_state(&step.id, &record.output)?;
+                    let output = match replayed_output(step, record) {
+                        Ok(output) => output,
+                        Err(e) => {
+                            let duration = Duration::from_millis(record.duration_ms);
+                            self.fail_step(
+                                scope,
+                                step,
+                                e,
+                                duration,
+                                &mut recordings,
+                                &mut results,
+                            )?;
+                            continue;
+                        }
+                    };
+                    self.storage
+                        .save_state_async(&scope.key(&step.id), output.clone())
+                        .await?;
+                    results.push(StepResult::new(
+                        step,
+                        StepStatus::Replayed,
+                        Some(output),
+                        Duration::ZERO,
+                    ));
                 }
                 continue;
             }
 
+            // Nested plans, foreach steps and composites need the runtime
+            // itself, so they run after the level's tools
+            let (inline, live): (Vec<ScheduledStep>, Vec<ScheduledStep>) =
+                prepared.into_iter().partition(|step| {
+                    step.tool == PLAN_STEP_TOOL
+                        || step.foreach.is_some()
+                        || self.composite(&step.tool).is_some()
+                });
+
             // Execute level in parallel via JoinSet
             let mut join_set = JoinSet::new();
 
-            for &step_idx in level {
-                let step = steps[step_idx].clone();
+            for step in live {
                 let tools = self.tools.clone();
+                let tool_name = self.resolve_tool_name(&step.tool);
                 let sem = semaphore.clone();
+                let exec_queue = self.exec_queue.clone();
+                let class_sem = class_semaphores.get(&step.resource_class).cloned();
                 let timeout = self.get_timeout(&step.tool);
+                let middleware = self.tool_middleware.clone();
+                let backend = self.execution_backend.clone();
 
                 join_set.spawn(async move {
+                    // Class permit first so waiting steps don't hold a global slot
+                    let _class_permit = match class_sem {
+                        Some(class_sem) => Some(
+                            class_sem
+                                .acquire_owned()
+                                .await
+                                .map_err(|e| anyhow::anyhow!("Semaphore closed: {}", e))?,
+                        ),
+                        None => None,
+                    };
                     let _permit = sem
                         .acquire()
                         .await
                         .map_err(|e| anyhow::anyhow!("Semaphore closed: {}", e))?;
-
-                    let tool = tools
-                        .get(&step.tool)
-                        .context(format!("Tool '{}' not registered", step.tool))?;
+                    // Shared with chat calls, which go first when slots are scarce
+                    let _slot = exec_queue.acquire(ExecPriority::Background).await?;
 
                     let start = std::time::Instant::now();
-
-                    let result =
-                        match tokio::time::timeout(timeout, tool.execute(step.input.clone())).await
-                        {
-                            Err(_) => anyhow::bail!(
-                                "Tool '{}' timed out after {:.1}s (step '{}')",
-                                step.tool,
-                                timeout.as_secs_f64(),
-                                step.id
-                            ),
-                            Ok(Err(e)) => {
-                                return Err(e).context(format!(
+                    let result = async {
+                        let tool = tools
+                            .get(&tool_name)
+                            .map(|t| t.value().clone())
+                            .context(format!("Tool '{}' not registered", step.tool))?;
+                        with_retries(&step, || async {
+                            let call = ToolInvocation {
+                                tool: tool_name.clone(),
+                                input: step.input.clone(),
+                                session: None,
+                            };
+                            let execution = tool_middleware::execute_on(
+                                &middleware,
+                                backend.as_ref(),
+                                tool.as_ref(),
+                                call,
+                            );
+                            match tokio::time::timeout(timeout, execution).await {
+                                Err(_) => anyhow::bail!(
+                                    "Tool '{}' timed out after {:.1}s (step '{}')",
+                                    step.tool,
+                                    timeout.as_secs_f64(),
+                                    step.id
+                                ),
+                                Ok(Err(e)) => Err(e).context(format!(
                                     "Tool '{}' failed (step '{}')",
                                     step.tool, step.id
-                                ))
+                                )),
+                                Ok(Ok(r)) => Ok(r),
                             }
-                            Ok(Ok(r)) => r,
-                        };
+                        })
+                        .await
+                    }
+                    .await;
 
-                    let duration_ms = start.elapsed().as_millis() as u64;
-                    Ok((step, result, duration_ms))
+                    Ok((step, result, start.elapsed()))
                 });
             }
 
-            // Collect results, fail fast on first error (abort remaining on failure)
+            // Collect results; a failure that aborts the plan cancels the rest of the level
             while let Some(task_result) = join_set.join_next().await {
                 let joined = task_result.context("Task panicked");
-                let (step, result, duration_ms) = match joined.and_then(|r| r) {
-                    Ok(v) => v,
-                    Err(e) => {
-                        join_set.abort_all();
-                        return Err(e).context("Step execution failed");
+                let failed = match joined.and_then(|r| r) {
+                    Ok((step, Ok(output), duration)) => {
+                        self.complete_step(
+                            scope,
+                            &step,
+                            output,
+                            duration,
+                            &mut recordings,
+                            &mut results,
+                        )
+                        .await?;
+                        Ok(())
                     }
+                    Ok((step, Err(e), duration)) => {
+                        self.fail_step(scope, &step, e, duration, &mut recordings, &mut results)
+                    }
+                    Err(e) => Err(e),
                 };
-
-                info!(step = step.index, tool = %step.tool, duration_ms, "Step completed");
-                self.storage.save_state(&step.id, &result)?;
-
-                if matches!(self.execution_context, ExecutionContext::Record(_)) {
-                    recordings.push(StepRecord {
-                        index: step.index,
-                        tool: step.tool.clone(),
-                        input: step.input.clone(),
-                        output: result,
-                        duration_ms,
-                    });
+                if let Err(e) = failed {
+                    join_set.abort_all();
+                    return Err(e).context("Step execution failed");
                 }
             }
-        }
 
-        // Save recordings
-        if let ExecutionContext::Record(ref dir) = self.execution_context {
-            recordings.sort_by_key(|r| r.index);
-            let fixture = Fixture {
-                plan_id,
-                recorded_at: replay::timestamp_now(),
-                steps: recordings,
-            };
-            fixture.save(dir)?;
-            info!(dir = ?dir, "Fixture recorded");
+            for step in inline {
+                let start = std::time::Instant::now();
+                match self.run_step(&step, scope).await {
+                    Ok(output) => {
+                        self.complete_step(
+                            scope,
+                            &step,
+                            output,
+                            start.elapsed(),
+                            &mut recordings,
+                            &mut results,
+                        )
+                        .await?
+                    }
+                    Err(e) => self
+                        .fail_step(
+                            scope,
+                            &step,
+                            e,
+                            start.elapsed(),
+                            &mut recordings,
+                            &mut results,
+                        )
+                        .context("Step execution failed")?,
+                }
+            }
         }
 
-        Ok(())
+        self.save_recordings(plan_id, recordings, scope)?;
+        Ok(results)
     }
 
     /// Sequential execution for plans without dependencies (backward compat)
-    async fn run_sequential(&self, steps: &[ScheduledStep], plan_id: &str) -> Result<()> {
+    async fn run_sequential(
+        &self,
+        steps: &[ScheduledStep],
+        plan_id: &str,
+        scope: &RunScope,
+    ) -> Result<Vec<StepResult>> {
         let mut recordings: Vec<StepRecord> = Vec::new();
+        let mut results: Vec<StepResult> = Vec::new();
 
-        let replay_fixture = match &self.execution_context {
-            ExecutionContext::Replay(dir) => Some(Fixture::load(dir)?),
-            _ => None,
-        };
+        let replay_fixture = self.replay_fixture(scope)?;
 
         for step in steps {
-            if self.dry_run {
+            if let Some(result) = self.resumed_step(scope, step).await? {
+                results.push(result);
+                continue;
+            }
+            if step.dry_run(self.dry_run) {
                 warn!(step = step.index, tool = %step.tool, "DRY-RUN: Skipping tool execution");
+                results.push(StepResult::new(
+                    step,
+                    StepStatus::Skipped,
+                    None,
+                    Duration::ZERO,
+                ));
                 continue;
             }
 
+            let Some(step) = self.prepare_step(step, scope, &results).await? else {
+                results.push(StepResult::new(
+                    step,
+                    StepStatus::Skipped,
+                    None,
+                    Duration::ZERO,
+                ));
+                continue;
+            };
+            let step = &step;
+
             // Replay mode
             if let Some(ref fixture) = replay_fixture {
                 if let Some(record) = fixture.steps.iter().find(|r| r.index == step.index) {
                     info!(step = step.index, tool = %step.tool, "REPLAY");
-                    self.storage.save_state(&step.id, &record.output)?;
+                    match replayed_output(step, record) {
+                        Ok(output) => {
+                            self.storage
+                                .save_state_async(&scope.key(&step.id), output.clone())
+                                .await?;
+                            results.push(StepResult::new(
+                                step,
+                                StepStatus::Replayed,
+                                Some(output),
+                                Duration::ZERO,
+                            ));
+                        }
+                        Err(e) => {
+                            let duration = Duration::from_millis(record.duration_ms);
+                            self.fail_step(
+                                scope,
+                                step,
+                                e,
+                                duration,
+                                &mut recordings,
+                                &mut results,
+                            )?;
+                        }
+                    }
                     continue;
                 }
             }
 
-            let tool = self
-                .tools
-                .get(&step.tool)
-                .context(format!("Tool '{}' not registered", step.tool))?;
-
-            let timeout = self.get_timeout(&step.tool);
-            info!(step = step.index, tool = %step.tool, timeout_ms = timeout.as_millis(), "Executing tool");
-
             let start = std::time::Instant::now();
-
-            let result = match tokio::time::timeout(timeout, tool.execute(step.input.clone())).await
-            {
-                Err(_elapsed) => {
-                    anyhow::bail!(
-                        "Tool '{}' timed out after {:.1}s (step '{}')",
-                        step.tool,
-                        timeout.as_secs_f64(),
-                        step.id
-                    );
+            match self.run_step(step, scope).await {
+                Ok(output) => {
+                    self.complete_step(
+                        scope,
+                        step,
+                        output,
+                        start.elapsed(),
+                        &mut recordings,
+                        &mut results,
+                    )
+                    .await?
                 }
-                Ok(Err(e)) => {
-                    return Err(e).context(format!(
-                        "Tool '{}' execution failed (step '{}')",
-                        step.tool, step.id
-                    ));
-                }
-                Ok(Ok(result)) => result,
-            };
-
-            let duration_ms = start.elapsed().as_millis() as u64;
-            info!(step = step.index, tool = %step.tool, duration_ms, "Tool completed");
-            self.storage.save_state(&step.id, &result)?;
-
-            if matches!(self.execution_context, ExecutionContext::Record(_)) {
-                recordings.push(StepRecord {
-                    index: step.index,
-                    tool: step.tool.clone(),
-                    input: step.input.clone(),
-                    output: result,
-                    duration_ms,
-                });
+                Err(e) => self.fail_step(
+                    scope,
+                    step,
+                    e,
+                    start.elapsed(),
+                    &mut recordings,
+                    &mut results,
+                )?,
             }
         }
 
-        // Save recordings
-        if let ExecutionContext::Record(ref dir) = self.execution_context {
-            let fixture = Fixture {
-                plan_id: plan_id.to_string(),
-                recorded_at: replay::timestamp_now(),
-                steps: recordings,
-            };
-            fixture.save(dir)?;
-            info!(dir = ?dir, "Fixture recorded");
+        self.save_recordings(plan_id, recordings, scope)?;
+        Ok(results)
+    }
+
+    /// Run a tool step with its timeout
+    async fn execute_step(&self, step: &ScheduledStep) -> Result<Value> {
+        if let Some(spec) = self.composite(&step.tool) {
+            return self
+                .run_composite(
+                    &step.tool,
+                    spec,
+                    step.input.clone(),
+                    PermissionLevel::Execute,
+                    None,
+                )
+                .await
+                .context(format!("Step '{}' failed", step.id));
         }
+        let tool = self
+            .get_tool(&step.tool)
+            .context(format!("Tool '{}' not registered", step.tool))?;
 
-        Ok(())
+        let timeout = self.get_timeout(&step.tool);
+        let _slot = self.exec_queue.acquire(ExecPriority::Background).await?;
+        info!(step = step.index, tool = %step.tool, timeout_ms = timeout.as_millis(), "Executing tool");
+
+        let call = ToolInvocation {
+            tool: self.resolve_tool_name(&step.tool),
+            input: step.input.clone(),
+            session: None,
+        };
+        let execution = tool_middleware::execute_on(
+            &self.tool_middleware,
+            self.execution_backend.as_ref(),
+            tool.as_ref(),
+            call,
+        );
+        match tokio::time::timeout(timeout, execution).await {
+            Err(_elapsed) => {
+                anyhow::bail!(
+                    "Tool '{}' timed out after {:.1}s (step '{}')",
+                    step.tool,
+                    timeout.as_secs_f64(),
+                    step.id
+                );
+            }
+            Ok(Err(e)) => Err(e).context(format!(
+                "Tool '{}' execution failed (step '{}')",
+                step.tool, step.id
+            )),
+            Ok(Ok(result)) => Ok(result),
+        }
     }
 
     /// Execute a single tool by name (used by Agent loop)
     pub async fn execute_tool(&self, tool_name: &str, input: Value) -> Result<Value> {
+        self.execute_tool_as(tool_name, input, PermissionLevel::Execute)
+            .await
+    }
+
+    /// Execute a single tool on behalf of a caller capped at `caller_permission`
+    pub async fn execute_tool_as(
+        &self,
+        tool_name: &str,
+        input: Value,
+        caller_permission: PermissionLevel,
+    ) -> Result<Value> {
+        self.execute_tool_with_key(tool_name, input, caller_permission, None)
+            .await
+    }
+
+    /// Like `execute_tool_as`; with an idempotency key, the first successful
+    /// result is stored and returned for later calls with the same key and
+    /// input instead of running the tool again, until it is older than the
+    /// idempotency TTL. Failed calls are not stored.
+    pub async fn execute_tool_with_key(
+        &self,
+        tool_name: &str,
+        input: Value,
+        caller_permission: PermissionLevel,
+        idempotency_key: Option<&str>,
+    ) -> Result<Value> {
+        self.execute_call(tool_name, input, caller_permission, idempotency_key, None)
+            .await
+    }
+
+    /// Like `execute_tool_as`, for a call made by agent session `session_id`;
+    /// the policy pipeline and execution backend see the session
+    pub async fn execute_tool_in_session(
+        &self,
+        tool_name: &str,
+        input: Value,
+        caller_permission: PermissionLevel,
+        session_id: &str,
+    ) -> Result<Value> {
+        self.execute_call(tool_name, input, caller_permission, None, Some(session_id))
+            .await
+    }
+
+    /// `execute_tool_in_session` with an idempotency key (see
+    /// `execute_tool_with_key`)
+    pub async fn execute_tool_in_session_with_key(
+        &self,
+        tool_name: &str,
+        input: Value,
+        caller_permission: PermissionLevel,
+        session_id: &str,
+        idempotency_key: &str,
+    ) -> Result<Value> {
+        self.execute_call(
+            tool_name,
+            input,
+            caller_permission,
+            Some(idempotency_key),
+            Some(session_id),
+        )
+        .await
+    }
+
+    /// Record the tokens left in session `session_id`'s context window (None:
+    /// unknown), seen by the policy pipeline as `context_tokens_left`
+    pub fn set_context_tokens_left(&self, session_id: &str, tokens: Option<usize>) {
+        match tokens {
+            Some(tokens) => {
+                self.context_tokens_left
+                    .insert(session_id.to_string(), tokens);
+            }
+            None => {
+                self.context_tokens_left.remove(session_id);
+            }
+        }
+    }
+
+    /// Record that agent `agent_name` runs session `session_id`, seen by the
+    /// policy pipeline as `agent_name` and by hooks as `agent_id`
+    pub fn set_session_agent(&self, session_id: &str, agent_name: &str) {
+        self.session_agents
+            .insert(session_id.to_string(), agent_name.to_string());
+    }
+
+    /// Record the latest messages of session `session_id`, seen by the
+    /// policy pipeline as `recent_messages`
+    pub fn set_recent_messages(&self, session_id: &str, messages: Vec<Message>) {
+        self.session_messages
+            .insert(session_id.to_string(), messages);
+    }
+
+    /// Record the gateway identity session `session_id` is serving (None:
+    /// unauthenticated), seen by the policy pipeline as `principal`
+    pub fn set_session_principal(&self, session_id: &str, principal: Option<&str>) {
+        match principal {
+            Some(principal) => {
+                self.session_principals
+                    .insert(session_id.to_string(), principal.to_string());
+            }
+            None => {
+                self.session_principals.remove(session_id);
+            }
+        }
+    }
+
+    /// Release what the tools and the execution backend hold for an ended
+    /// session
+    pub async fn end_session(&self, session_id: &str) -> Result<()> {
+        self.context_tokens_left.remove(session_id);
+        self.session_agents.remove(session_id);
+        self.session_messages.remove(session_id);
+        self.session_principals.remove(session_id);
+        for tool in self.tools.iter() {
+            tool.end_session(session_id);
+        }
+        self.execution_backend.end_session(session_id).await
+    }
+
+    async fn execute_call(
+        &self,
+        tool_name: &str,
+        input: Value,
+        caller_permission: PermissionLevel,
+        idempotency_key: Option<&str>,
+        session: Option<&str>,
+    ) -> Result<Value> {
+        // Policy layers, hooks and the audit trail all see the canonical
+        // name, so an alias is held, limited and recorded like its target
+        let tool_name = &self.resolve_tool_name(tool_name);
+
         // Dry-run check BEFORE policy evaluation to avoid incrementing rate-limit counters
         if self.dry_run {
             warn!(tool = tool_name, "DRY-RUN: Skipping tool execution");
@@ -361,25 +1582,298 @@ impl Runtime {
             }));
         }
 
+        let record = self
+            .audit_trail
+            .then(|| AuditRecord::new(tool_name, &input, session));
+        let started = std::time::Instant::now();
+        let mut verdict = (AuditDecision::Allowed, None);
+        let result = self
+            .execute_checked(
+                tool_name,
+                input,
+                caller_permission,
+                idempotency_key,
+                session,
+                &mut verdict,
+            )
+            .await;
+        if let Some(mut record) = record {
+            record.agent =
+                session.and_then(|id| self.session_agents.get(id).map(|agent| agent.clone()));
+            record.principal = session.and_then(|id| {
+                self.session_principals
+                    .get(id)
+                    .map(|principal| principal.clone())
+            });
+            (record.decision, record.reason) = verdict;
+            record.finish(&result, started.elapsed());
+            if let Err(e) = self.storage.append_audit_async(record).await {
+                warn!(tool = tool_name, error = %e, "Failed to store audit record");
+            }
+        }
+        result
+    }
+
+    /// Run a call through the policy pipeline, noting what it decided and why
+    /// in `verdict`
+    async fn execute_checked(
+        &self,
+        tool_name: &str,
+        input: Value,
+        caller_permission: PermissionLevel,
+        idempotency_key: Option<&str>,
+        session: Option<&str>,
+        verdict: &mut (AuditDecision, Option<String>),
+    ) -> Result<Value> {
+        // Composite steps are called with the composite caller's permission
+        let step_permission = caller_permission.clone();
+        // ToolCallBefore hooks may rewrite the input (checked by the policy
+        // below) or abort the call
+        let payload = || serde_json::json!({"tool": tool_name, "input": input.clone()});
+        let input = match self
+            .trigger_hook_checked(HookEvent::ToolCallBefore, payload, session)
+            .await
+            .with_context(|| format!("Tool call '{}' stopped by hook", tool_name))?
+        {
+            Some(mut data) => data.get_mut("input").map(Value::take).unwrap_or(input),
+            None => input,
+        };
+
         // Policy pipeline evaluation (if configured)
-        if let Some(ref policy) = self.policy {
+        let policy = self
+            .policy
+            .read()
+            .unwrap_or_else(|e| e.into_inner())
+            .clone();
+        let policy = policy.map(|policy| {
             let ctx = PolicyContext {
                 tool_name: tool_name.to_string(),
                 input: input.clone(),
-                caller_permission: PermissionLevel::Execute,
+                caller_permission,
                 dry_run: self.dry_run,
-                session_id: None,
+                session_id: session.map(str::to_string),
+                registered_permission: self.tool_permission(tool_name),
+                context_tokens_left: session
+                    .and_then(|id| self.context_tokens_left.get(id).map(|left| *left)),
+                agent_name: session
+                    .and_then(|id| self.session_agents.get(id).map(|agent| agent.clone())),
+                principal: session.and_then(|id| {
+                    self.session_principals
+                        .get(id)
+                        .map(|principal| principal.clone())
+                }),
+                recent_messages: session
+                    .and_then(|id| {
+                        self.session_messages
+                            .get(id)
+                            .map(|messages| messages.clone())
+                    })
+                    .unwrap_or_default(),
+                redacted_input: policy.redact_input(&input),
             };
-            policy.evaluate(&ctx)?;
+            (policy, ctx)
+        });
+        if let Some((policy, ctx)) = &policy {
+            let approval = policy
+                .evaluate(ctx)
+                .await
+                .inspect_err(|e| *verdict = (AuditDecision::Denied, Some(e.to_string())))?;
+            if let Some(description) = approval {
+                let approved = self
+                    .await_approval(tool_name, ctx.display_input(), session, description.clone())
+                    .await;
+                *verdict = match approved {
+                    Ok(()) => (AuditDecision::Approved, Some(description)),
+                    Err(_) => (AuditDecision::NotApproved, Some(description)),
+                };
+                approved?;
+            }
+        }
+
+        let hook_input = self
+            .hooks
+            .as_ref()
+            .is_some_and(|hooks| hooks.has_hooks(&HookEvent::ToolCallAfter))
+            .then(|| input.clone());
+        let mut output = match self.composite(tool_name) {
+            Some(spec) => {
+                self.run_composite(tool_name, spec, input, step_permission, session)
+                    .await?
+            }
+            None => {
+                self.execute_resolved(tool_name, input, idempotency_key, session)
+                    .await?
+            }
+        };
+        if let Some((policy, ctx)) = &policy {
+            policy
+                .evaluate_result(ctx, &output)
+                .await
+                .inspect_err(|e| *verdict = (AuditDecision::Denied, Some(e.to_string())))?;
+            policy.process_result(ctx, &mut output).await;
+        }
+
+        // ToolCallAfter hooks see the result and may rewrite or withhold it
+        if let Some(input) = hook_input {
+            let payload =
+                || serde_json::json!({"tool": tool_name, "input": input, "output": output});
+            if let Some(mut data) = self
+                .trigger_hook_checked(HookEvent::ToolCallAfter, payload, session)
+                .await
+                .with_context(|| format!("Result of tool '{}' stopped by hook", tool_name))?
+            {
+                if let Some(modified) = data.get_mut("output") {
+                    output = modified.take();
+                }
+            }
+        }
+        Ok(output)
+    }
+
+    /// Wait for the approver to let a held call run; fails if it says no or
+    /// there is nobody to ask
+    async fn await_approval(
+        &self,
+        tool_name: &str,
+        input: &Value,
+        session: Option<&str>,
+        description: String,
+    ) -> Result<()> {
+        let Some(approver) = &self.approver else {
+            anyhow::bail!(
+                "Tool call needs approval ({}), but there is nobody to ask here",
+                description
+            );
+        };
+        let request = ApprovalRequest {
+            call_id: uuid::Uuid::new_v4().to_string(),
+            tool_name: tool_name.to_string(),
+            input: input.clone(),
+            session_id: session.map(str::to_string),
+            description,
+        };
+        info!(tool = tool_name, call_id = %request.call_id, "Waiting for tool call approval");
+        if !approver.approve(&request).await? {
+            anyhow::bail!("Tool call was not approved ({})", request.description);
+        }
+        info!(tool = tool_name, call_id = %request.call_id, "Tool call approved");
+        Ok(())
+    }
+
+    /// Run a call that passed the policy pipeline, through its idempotency
+    /// key if it has one
+    async fn execute_resolved(
+        &self,
+        tool_name: &str,
+        input: Value,
+        idempotency_key: Option<&str>,
+        session: Option<&str>,
+    ) -> Result<Value> {
+        let resolved = self.resolve_tool_name(tool_name);
+        let Some(key) = idempotency_key else {
+            return self.run_tool(tool_name, &resolved, input, session).await;
+        };
+
+        // The same key with another input is another call
+        let storage_key = format!(
+            "{}{}/{}",
+            IDEMPOTENCY_KEY_PREFIX,
+            key,
+            crate::audit::input_hash(&input)
+        );
+        let lock = self
+            .idempotency_locks
+            .entry(storage_key.clone())
+            .or_default()
+            .clone();
+        let guard = lock.lock().await;
+        let result = self
+            .run_idempotent(tool_name, &resolved, input, key, &storage_key, session)
+            .await;
+        drop(guard);
+        drop(lock);
+        self.idempotency_locks
+            .remove_if(&storage_key, |_, lock| Arc::strong_count(lock) == 1);
+        result
+    }
+
+    /// Stored result for `key`, or run the tool and store its result
+    async fn run_idempotent(
+        &self,
+        tool_name: &str,
+        resolved: &str,
+        input: Value,
+        key: &str,
+        storage_key: &str,
+        session: Option<&str>,
+    ) -> Result<Value> {
+        let now = chrono::Utc::now().timestamp();
+        let fresh = |stored: &Value| {
+            stored["stored_at"]
+                .as_i64()
+                .is_some_and(|at| now.saturating_sub(at) < self.idempotency_ttl.as_secs() as i64)
+        };
+        if let Some(stored) = self
+            .storage
+            .load_state_async(storage_key)
+            .await?
+            .filter(fresh)
+        {
+            if stored["tool"] != resolved {
+                anyhow::bail!(
+                    "Idempotency key '{}' was already used for tool '{}'",
+                    key,
+                    stored["tool"].as_str().unwrap_or_default()
+                );
+            }
+            info!(
+                tool = tool_name,
+                key, "Returning stored result for idempotency key"
+            );
+            return Ok(stored["output"].clone());
+        }
+
+        let output = self.run_tool(tool_name, resolved, input, session).await?;
+        self.storage
+            .save_state_async(
+                storage_key,
+                serde_json::json!({"tool": resolved, "output": output, "stored_at": now}),
+            )
+            .await?;
+        Ok(output)
+    }
+
+    /// Run a tool past the policy check: result cache, queue slot and timeout
+    async fn run_tool(
+        &self,
+        tool_name: &str,
+        resolved: &str,
+        input: Value,
+        session: Option<&str>,
+    ) -> Result<Value> {
+        if let Some(cached) = self.cached_result(resolved, &input) {
+            debug!(tool = tool_name, "Tool result served from cache");
+            return Ok(cached);
         }
 
         let tool = self
-            .tools
-            .get(tool_name)
+            .get_tool(tool_name)
             .ok_or_else(|| anyhow::anyhow!("Tool '{}' not registered", tool_name))?;
 
         let timeout = self.get_timeout(tool_name);
-        let result = match tokio::time::timeout(timeout, tool.execute(input)).await {
+        let _slot = self.exec_queue.acquire(ExecPriority::Interactive).await?;
+        let call = ToolInvocation {
+            tool: resolved.to_string(),
+            input: input.clone(),
+            session: session.map(str::to_string),
+        };
+        let execution = tool_middleware::execute_on(
+            &self.tool_middleware,
+            self.execution_backend.as_ref(),
+            tool.as_ref(),
+            call,
+        );
+        let result = match tokio::time::timeout(timeout, execution).await {
             Err(_) => anyhow::bail!(
                 "Tool '{}' timed out after {:.1}s",
                 tool_name,
@@ -389,12 +1883,80 @@ impl Runtime {
             Ok(Ok(r)) => r,
         };
 
+        self.store_result(resolved, tool.as_ref(), &input, &result);
         Ok(result)
     }
 
-    /// Get list of registered tool names
+    /// Execution levels, ordering and parallelism for `plan`, without running it
+    pub fn plan_schedule(&self, plan: &Value) -> Result<scheduler::PlanSchedule> {
+        scheduler::plan_schedule(plan, self.max_parallel)
+    }
+
+    /// State store for step outputs and plan checkpoints
+    pub fn storage(&self) -> &Storage {
+        &self.storage
+    }
+
+    /// Depth and throughput of the shared tool execution queue
+    pub fn queue_stats(&self) -> QueueStats {
+        self.exec_queue.stats()
+    }
+
+    /// Whether `name` is a registered tool or alias
+    pub fn has_tool(&self, name: &str) -> bool {
+        self.tools.contains_key(name) || self.aliases.contains_key(name)
+    }
+
+    /// Get list of registered tool names, including aliases
     pub fn tool_names(&self) -> Vec<String> {
-        self.tools.iter().map(|r| r.key().clone()).collect()
+        self.tools
+            .iter()
+            .map(|r| r.key().clone())
+            .chain(self.aliases.iter().map(|a| a.key().clone()))
+            .collect()
+    }
+
+    /// Schema a registered tool (or alias) declares, named as it was asked
+    /// for so renamed and aliased tools are called by their registry name
+    pub fn tool_schema(&self, name: &str) -> Option<ToolSchemaInfo> {
+        let mut schema = self.get_tool(name)?.schema();
+        schema.name = name.to_string();
+        Some(schema)
+    }
+
+    /// Schemas of all registered tools and aliases (see [`Runtime::tool_schema`])
+    pub fn tool_schemas(&self) -> Vec<ToolSchemaInfo> {
+        self.tool_names()
+            .iter()
+            .filter_map(|name| self.tool_schema(name))
+            .collect()
+    }
+
+    /// Stored output of a completed plan step
+    pub fn step_output(&self, step_id: &str) -> Result<Option<Value>> {
+        self.storage.load_state(step_id)
+    }
+
+    /// Permission level declared by a registered tool
+    pub fn tool_permission(&self, tool_name: &str) -> Option<PermissionLevel> {
+        self.tools
+            .get(&self.resolve_tool_name(tool_name))
+            .map(|t| t.permission_level())
+    }
+
+    /// Declared permission levels of all registered tools (for PermissionCheckLayer)
+    pub fn tool_permissions(&self) -> HashMap<String, PermissionLevel> {
+        let mut permissions: HashMap<String, PermissionLevel> = self
+            .tools
+            .iter()
+            .map(|r| (r.key().clone(), r.value().permission_level()))
+            .collect();
+        for alias in self.aliases.iter() {
+            if let Some(level) = permissions.get(alias.value()).cloned() {
+                permissions.insert(alias.key().clone(), level);
+            }
+        }
+        permissions
     }
 
     /// Start runtime
@@ -405,7 +1967,51 @@ impl Runtime {
 
     /// Stop runtime
     pub async fn stop(&self) -> Result<()> {
+        self.execution_backend.shutdown().await?;
         info!("Runtime stopped");
         Ok(())
     }
 }
+
+/// Runs `attempt` until it succeeds or the step's `retry` policy runs out,
+/// doubling the backoff after each failure
+async fn with_retries<F, Fut>(step: &ScheduledStep, mut attempt: F) -> Result<Value>
+where
+    F: FnMut() -> Fut,
+    Fut: Future<Output = Result<Value>>,
+{
+    let Some(retry) = step.retry else {
+        return attempt().await;
+    };
+    let mut backoff = Duration::from_millis(retry.backoff_ms);
+    for n in 1..=retry.max {
+        match attempt().await {
+            Ok(output) => return Ok(output),
+            Err(e) => {
+                warn!(
+                    step = %step.id,
+                    attempt = n,
+                    backoff_ms = backoff.as_millis() as u64,
+                    error = %e,
+                    "Step failed, retrying"
+                );
+                tokio::time::sleep(backoff).await;
+                backoff = backoff.saturating_mul(2);
+            }
+        }
+    }
+    attempt().await.context(format!(
+        "Step '{}' failed after {} attempts",
+        step.id,
+        retry.max + 1
+    ))
+}
+
+/// Output recorded for a replayed step; a recorded error fails the step as
+/// the tool would have
+fn replayed_output(step: &ScheduledStep, record: &StepRecord) -> Result<Value> {
+    record
+        .result()
+        .context(format!("Tool '{}' failed (step '{}')", step.tool, step.id))
+        .context("Step execution failed")
+}
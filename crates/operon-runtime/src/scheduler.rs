@@ -1,6 +1,10 @@
-use anyhow::{Context, Result};
-use serde_json::Value;
-use std::collections::{HashMap, VecDeque};
+use anyhow::{bail, Context, Result};
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+use std::collections::{HashMap, HashSet, VecDeque};
+use std::ops::Range;
+
+use crate::runtime::PLAN_STEP_TOOL;
 
 /// Parsed step with dependency info
 #[derive(Debug, Clone)]
@@ -10,6 +14,138 @@ pub struct ScheduledStep {
     pub tool: String,
     pub input: Value,
     pub depends_on: Vec<String>,
+    /// Concurrency class for per-class limits (`resource_class`, else the tool name)
+    pub resource_class: String,
+    /// Run even when the plan is in dry-run mode (e.g. read-only checks)
+    pub always_execute: bool,
+    /// Skip even when the plan is in execute mode (e.g. risky steps)
+    pub force_dry_run: bool,
+    /// Run the step again when it fails
+    pub retry: Option<RetryPolicy>,
+    /// Run the step only if this holds for earlier outputs
+    pub when: Option<StepCondition>,
+    /// What a failure does to the rest of the plan (step `on_error`, else the plan's)
+    pub on_error: OnError,
+    /// Run the tool once per item instead of once
+    pub foreach: Option<Foreach>,
+}
+
+/// What happens to a plan when one of its steps fails
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum OnError {
+    /// Stop the plan and return the error
+    #[default]
+    Abort,
+    /// Mark the step failed and run the rest of the plan
+    Continue,
+    /// Mark the step failed and skip the steps that depend on it, directly or not
+    SkipDependents,
+}
+
+/// How often a failed step is tried again
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RetryPolicy {
+    /// Retries after the first attempt
+    pub max: u32,
+    /// Wait before the first retry; doubled before each further one
+    #[serde(default)]
+    pub backoff_ms: u64,
+}
+
+impl ScheduledStep {
+    /// Whether this step is skipped, given the plan-wide dry-run setting.
+    /// `force_dry_run` wins over `always_execute`.
+    pub fn dry_run(&self, plan_dry_run: bool) -> bool {
+        self.force_dry_run || (plan_dry_run && !self.always_execute)
+    }
+
+    /// One step per `foreach` item, with `{{item}}` and `{{item.<path>}}` in
+    /// the input replaced by the item and ids `<id>[<n>]`. Items taken from
+    /// an output must have been resolved with `Foreach::resolve` first.
+    pub fn expand(&self) -> Result<Vec<ScheduledStep>> {
+        let Some(foreach) = &self.foreach else {
+            return Ok(vec![self.clone()]);
+        };
+        let ForeachSource::Items(items) = &foreach.source else {
+            bail!("Items of step '{}' have not been resolved", self.id);
+        };
+        items
+            .iter()
+            .enumerate()
+            .map(|(n, item)| {
+                let input = render(
+                    &self.input,
+                    &|inner| VarRef::parse("item", inner),
+                    &|reference: &VarRef| reference.lookup(item).cloned(),
+                )
+                .with_context(|| format!("Failed to render item {} of step '{}'", n, self.id))?;
+                Ok(ScheduledStep {
+                    id: format!("{}[{}]", self.id, n),
+                    input,
+                    when: None,
+                    foreach: None,
+                    ..self.clone()
+                })
+            })
+            .collect()
+    }
+}
+
+/// Items a `foreach` step runs its tool for: a list in the plan, or an array
+/// in an earlier step's output (`steps.<id>.output.<path>`)
+#[derive(Debug, Clone, PartialEq)]
+pub struct Foreach {
+    source: ForeachSource,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum ForeachSource {
+    Items(Vec<Value>),
+    Output(OutputRef),
+}
+
+impl Foreach {
+    pub fn items(items: Vec<Value>) -> Self {
+        Self {
+            source: ForeachSource::Items(items),
+        }
+    }
+
+    pub fn parse(path: &str) -> Result<Self> {
+        let reference = OutputRef::parse(path.trim())?
+            .with_context(|| format!("'{}' is not steps.<id>.output.<path>", path))?;
+        Ok(Self {
+            source: ForeachSource::Output(reference),
+        })
+    }
+
+    /// Id of the step whose output holds the items, if any
+    pub fn step(&self) -> Option<&str> {
+        match &self.source {
+            ForeachSource::Items(_) => None,
+            ForeachSource::Output(reference) => Some(&reference.step),
+        }
+    }
+
+    /// The items, given `outputs` (step id to output)
+    pub fn resolve(&self, outputs: &HashMap<String, Value>) -> Result<Self> {
+        let ForeachSource::Output(reference) = &self.source else {
+            return Ok(self.clone());
+        };
+        let value = outputs
+            .get(&reference.step)
+            .with_context(|| format!("No output of step '{}'", reference.step))
+            .and_then(|output| reference.lookup(output))?;
+        let Value::Array(items) = value else {
+            bail!(
+                "steps.{}.output.{} is not an array",
+                reference.step,
+                reference.path.join(".")
+            );
+        };
+        Ok(Self::items(items.clone()))
+    }
 }
 
 /// Parse plan steps and extract dependency info.
@@ -18,6 +154,12 @@ pub fn parse_steps(plan: &Value) -> Result<Vec<ScheduledStep>> {
         .as_array()
         .context("Plan missing 'steps' array")?;
 
+    let plan_on_error: OnError = match plan.get("on_error") {
+        Some(value) => serde_json::from_value(value.clone())
+            .context("Invalid plan 'on_error' (expected abort, continue or skip_dependents)")?,
+        None => OnError::default(),
+    };
+
     let mut result = Vec::with_capacity(steps.len());
 
     for (i, step) in steps.iter().enumerate() {
@@ -42,12 +184,60 @@ pub fn parse_steps(plan: &Value) -> Result<Vec<ScheduledStep>> {
             })
             .unwrap_or_default();
 
+        let resource_class = step["resource_class"]
+            .as_str()
+            .map(|s| s.to_string())
+            .unwrap_or_else(|| tool.clone());
+
+        let retry = match step.get("retry") {
+            Some(retry) => Some(
+                serde_json::from_value(retry.clone())
+                    .context(format!("Invalid 'retry' for step '{}'", id))?,
+            ),
+            None => None,
+        };
+        let when = match step.get("when") {
+            Some(Value::String(expr)) => Some(
+                StepCondition::parse(expr).context(format!("Invalid 'when' for step '{}'", id))?,
+            ),
+            Some(_) => bail!("Step '{}' has a non-string 'when'", id),
+            None => None,
+        };
+        let on_error = match step.get("on_error") {
+            Some(value) => serde_json::from_value(value.clone()).context(format!(
+                "Invalid 'on_error' for step '{}' (expected abort, continue or skip_dependents)",
+                id
+            ))?,
+            None => plan_on_error,
+        };
+        let foreach = match step.get("foreach") {
+            Some(Value::Array(items)) => Some(Foreach::items(items.clone())),
+            Some(Value::String(path)) => {
+                Some(Foreach::parse(path).context(format!("Invalid 'foreach' for step '{}'", id))?)
+            }
+            Some(_) => bail!(
+                "Step '{}' has a 'foreach' that is neither an array nor steps.<id>.output.<path>",
+                id
+            ),
+            None => None,
+        };
+        if foreach.is_some() && tool == PLAN_STEP_TOOL {
+            bail!("Step '{}' cannot use 'foreach' with nested plans", id);
+        }
+
         result.push(ScheduledStep {
             index: i,
             id,
             tool,
             input,
             depends_on,
+            resource_class,
+            always_execute: step["always_execute"].as_bool().unwrap_or(false),
+            force_dry_run: step["force_dry_run"].as_bool().unwrap_or(false),
+            retry,
+            when,
+            on_error,
+            foreach,
         });
     }
 
@@ -125,7 +315,551 @@ pub fn compute_levels(steps: &[ScheduledStep]) -> Result<Vec<Vec<usize>>> {
     Ok(levels)
 }
 
+/// A `{{steps.<id>.output.<json-path>}}` placeholder in a step input
+#[derive(Debug, Clone, PartialEq)]
+struct OutputRef {
+    step: String,
+    /// Keys, or indexes into arrays, below the step's output
+    path: Vec<String>,
+}
+
+impl OutputRef {
+    /// Parse the inside of a `{{...}}`; None if it is not a step reference
+    fn parse(inner: &str) -> Result<Option<Self>> {
+        let Some(rest) = inner.strip_prefix("steps.") else {
+            return Ok(None);
+        };
+        let mut parts = rest.split('.');
+        let step = parts.next().unwrap_or_default();
+        if step.is_empty() || parts.next() != Some("output") {
+            bail!(
+                "Invalid placeholder '{{{{{}}}}}' (expected steps.<id>.output.<path>)",
+                inner
+            );
+        }
+        let path: Vec<String> = parts.map(str::to_string).collect();
+        if path.iter().any(String::is_empty) {
+            bail!("Empty key in placeholder '{{{{{}}}}}'", inner);
+        }
+        Ok(Some(Self {
+            step: step.to_string(),
+            path,
+        }))
+    }
+
+    /// Value at this reference's path in the step's `output`
+    fn lookup<'a>(&self, output: &'a Value) -> Result<&'a Value> {
+        let mut current = output;
+        for key in &self.path {
+            current = child(current, key).with_context(|| {
+                format!(
+                    "Output of step '{}' has no '{}' (in '{}')",
+                    self.step,
+                    key,
+                    self.path.join(".")
+                )
+            })?;
+        }
+        Ok(current)
+    }
+}
+
+/// `{{<root>}}` or `{{<root>.<path>}}`: a `foreach` step's `item` or a
+/// plan's `params`
+struct VarRef {
+    root: &'static str,
+    path: Vec<String>,
+}
+
+impl VarRef {
+    /// Parse the inside of a `{{...}}`; None if it does not start with `root`
+    fn parse(root: &'static str, inner: &str) -> Result<Option<Self>> {
+        let path: Vec<String> = match inner.strip_prefix(root) {
+            Some("") => Vec::new(),
+            Some(rest) => match rest.strip_prefix('.') {
+                Some(rest) => rest.split('.').map(str::to_string).collect(),
+                None => return Ok(None),
+            },
+            None => return Ok(None),
+        };
+        if path.iter().any(String::is_empty) {
+            bail!("Empty key in placeholder '{{{{{}}}}}'", inner);
+        }
+        Ok(Some(Self { root, path }))
+    }
+
+    fn lookup<'a>(&self, value: &'a Value) -> Result<&'a Value> {
+        let mut current = value;
+        for key in &self.path {
+            current = child(current, key).with_context(|| {
+                format!(
+                    "'{}' has no '{}' (in '{}.{}')",
+                    self.root,
+                    key,
+                    self.root,
+                    self.path.join(".")
+                )
+            })?;
+        }
+        Ok(current)
+    }
+}
+
+/// `value[key]` for objects, `value[index]` for arrays
+fn child<'a>(value: &'a Value, key: &str) -> Option<&'a Value> {
+    match value {
+        Value::Object(map) => map.get(key),
+        Value::Array(items) => key.parse::<usize>().ok().and_then(|i| items.get(i)),
+        _ => None,
+    }
+}
+
+/// Comparison in a `when` condition
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum CompareOp {
+    Eq,
+    Ne,
+    Ge,
+    Le,
+    Gt,
+    Lt,
+}
+
+impl CompareOp {
+    /// Longer operators first so `>=` is not read as `>`
+    const ALL: [(&'static str, CompareOp); 6] = [
+        ("==", CompareOp::Eq),
+        ("!=", CompareOp::Ne),
+        (">=", CompareOp::Ge),
+        ("<=", CompareOp::Le),
+        (">", CompareOp::Gt),
+        ("<", CompareOp::Lt),
+    ];
+
+    fn apply(self, left: &Value, right: &Value) -> bool {
+        let ordering = match (left, right) {
+            (Value::Number(a), Value::Number(b)) => a.as_f64().partial_cmp(&b.as_f64()),
+            (Value::String(a), Value::String(b)) => Some(a.cmp(b)),
+            _ => None,
+        };
+        match self {
+            CompareOp::Eq => ordering.map_or(left == right, |o| o.is_eq()),
+            CompareOp::Ne => ordering.map_or(left != right, |o| o.is_ne()),
+            CompareOp::Ge => ordering.is_some_and(|o| o.is_ge()),
+            CompareOp::Le => ordering.is_some_and(|o| o.is_le()),
+            CompareOp::Gt => ordering.is_some_and(|o| o.is_gt()),
+            CompareOp::Lt => ordering.is_some_and(|o| o.is_lt()),
+        }
+    }
+}
+
+/// A step's `when` condition: `steps.<id>.output.<path>`, optionally
+/// negated with `!` or compared with a JSON literal (`== 0`, `!= "ok"`,
+/// `> 2.5`). Outputs of steps that did not run, and missing keys, are null.
+#[derive(Debug, Clone, PartialEq)]
+pub struct StepCondition {
+    expr: String,
+    reference: OutputRef,
+    negated: bool,
+    comparison: Option<(CompareOp, Value)>,
+}
+
+impl StepCondition {
+    pub fn parse(expr: &str) -> Result<Self> {
+        let trimmed = expr.trim();
+        let (negated, rest) = match trimmed.strip_prefix('!') {
+            Some(rest) => (true, rest.trim_start()),
+            None => (false, trimmed),
+        };
+        let end = rest
+            .find(|c: char| c.is_whitespace() || "=!<>".contains(c))
+            .unwrap_or(rest.len());
+        let reference = OutputRef::parse(&rest[..end])?.with_context(|| {
+            format!(
+                "Condition '{}' must start with steps.<id>.output.<path>",
+                expr
+            )
+        })?;
+        let rest = rest[end..].trim();
+        let comparison = if rest.is_empty() {
+            None
+        } else {
+            let (op, literal) = CompareOp::ALL
+                .iter()
+                .find_map(|(token, op)| rest.strip_prefix(token).map(|lit| (*op, lit)))
+                .with_context(|| format!("Unknown operator in condition '{}'", expr))?;
+            if negated {
+                bail!("Condition '{}' cannot both negate and compare", expr);
+            }
+            let literal = serde_json::from_str(literal.trim()).with_context(|| {
+                format!(
+                    "Condition '{}' must compare with a JSON literal (e.g. 0, \"ok\", true)",
+                    expr
+                )
+            })?;
+            Some((op, literal))
+        };
+        Ok(Self {
+            expr: expr.to_string(),
+            reference,
+            negated,
+            comparison,
+        })
+    }
+
+    /// Id of the step whose output the condition reads
+    pub fn step(&self) -> &str {
+        &self.reference.step
+    }
+
+    /// Whether the condition holds given `outputs` (step id to output)
+    pub fn evaluate(&self, outputs: &HashMap<String, Value>) -> bool {
+        let value = outputs
+            .get(&self.reference.step)
+            .and_then(|output| self.reference.lookup(output).ok())
+            .unwrap_or(&Value::Null);
+        match &self.comparison {
+            Some((op, literal)) => op.apply(value, literal),
+            None => is_truthy(value) != self.negated,
+        }
+    }
+}
+
+impl std::fmt::Display for StepCondition {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(&self.expr)
+    }
+}
+
+/// false, null, 0, "" and empty arrays and objects are false
+fn is_truthy(value: &Value) -> bool {
+    match value {
+        Value::Null => false,
+        Value::Bool(b) => *b,
+        Value::Number(n) => n.as_f64() != Some(0.0),
+        Value::String(s) => !s.is_empty(),
+        Value::Array(items) => !items.is_empty(),
+        Value::Object(map) => !map.is_empty(),
+    }
+}
+
+/// Step output placeholders in `s` with their byte ranges
+fn output_placeholders(s: &str) -> Result<Vec<(Range<usize>, OutputRef)>> {
+    placeholders(s, &OutputRef::parse)
+}
+
+/// Placeholders in `s` that `parse` recognises, with their byte ranges
+fn placeholders<R>(s: &str, parse: &Parse<R>) -> Result<Vec<(Range<usize>, R)>> {
+    let mut found = Vec::new();
+    let mut offset = 0;
+    while let Some(start) = s[offset..].find("{{") {
+        let start = offset + start;
+        let Some(len) = s[start + 2..].find("}}") else {
+            break;
+        };
+        let end = start + len + 4;
+        if let Some(reference) = parse(s[start + 2..end - 2].trim())? {
+            found.push((start..end, reference));
+        }
+        offset = end;
+    }
+    Ok(found)
+}
+
+/// A nested plan's own steps are resolved when that plan runs, so the child
+/// plan of a `plan` step is not searched for placeholders
+fn is_child_plan(step: &ScheduledStep, key: &str) -> bool {
+    step.tool == PLAN_STEP_TOOL && key == "plan"
+}
+
+fn collect_references(value: &Value, refs: &mut Vec<String>) -> Result<()> {
+    match value {
+        Value::String(s) => {
+            for (_, reference) in output_placeholders(s)? {
+                if !refs.contains(&reference.step) {
+                    refs.push(reference.step);
+                }
+            }
+        }
+        Value::Array(items) => {
+            for item in items {
+                collect_references(item, refs)?;
+            }
+        }
+        Value::Object(map) => {
+            for item in map.values() {
+                collect_references(item, refs)?;
+            }
+        }
+        _ => {}
+    }
+    Ok(())
+}
+
+/// Ids of the steps whose outputs this step's input refers to
+pub fn output_references(step: &ScheduledStep) -> Result<Vec<String>> {
+    let mut refs = Vec::new();
+    let result = match &step.input {
+        Value::Object(map) => map
+            .iter()
+            .filter(|(key, _)| !is_child_plan(step, key))
+            .try_for_each(|(_, value)| collect_references(value, &mut refs)),
+        input => collect_references(input, &mut refs),
+    };
+    result.with_context(|| format!("Invalid input of step '{}'", step.id))?;
+    Ok(refs)
+}
+
+/// Parses the inside of a `{{...}}`; None if it is another kind of placeholder
+type Parse<R> = dyn Fn(&str) -> Result<Option<R>>;
+
+/// `value` with the placeholders `parse` recognises replaced by `lookup`
+fn render<R>(
+    value: &Value,
+    parse: &Parse<R>,
+    lookup: &dyn Fn(&R) -> Result<Value>,
+) -> Result<Value> {
+    Ok(match value {
+        Value::String(s) => {
+            let placeholders = placeholders(s, parse)?;
+            match placeholders.as_slice() {
+                [] => value.clone(),
+                // A lone placeholder keeps the value's JSON type
+                [(range, reference)] if range.len() == s.len() => lookup(reference)?,
+                _ => {
+                    let mut rendered = String::with_capacity(s.len());
+                    let mut last = 0;
+                    for (range, reference) in &placeholders {
+                        rendered.push_str(&s[last..range.start]);
+                        match lookup(reference)? {
+                            Value::String(text) => rendered.push_str(&text),
+                            other => rendered.push_str(&other.to_string()),
+                        }
+                        last = range.end;
+                    }
+                    rendered.push_str(&s[last..]);
+                    Value::String(rendered)
+                }
+            }
+        }
+        Value::Array(items) => Value::Array(
+            items
+                .iter()
+                .map(|item| render(item, parse, lookup))
+                .collect::<Result<_>>()?,
+        ),
+        Value::Object(map) => Value::Object(
+            map.iter()
+                .map(|(key, item)| Ok((key.clone(), render(item, parse, lookup)?)))
+                .collect::<Result<Map<_, _>>>()?,
+        ),
+        other => other.clone(),
+    })
+}
+
+/// This step's input with its placeholders replaced from `outputs` (step id
+/// to output). A string that is just one placeholder becomes the referenced
+/// value; elsewhere values are spliced into the text, strings unquoted.
+pub fn resolve_input(step: &ScheduledStep, outputs: &HashMap<String, Value>) -> Result<Value> {
+    let output = |reference: &OutputRef| {
+        outputs
+            .get(&reference.step)
+            .with_context(|| format!("No output of step '{}'", reference.step))
+            .and_then(|output| reference.lookup(output))
+            .cloned()
+    };
+    match &step.input {
+        Value::Object(map) => map
+            .iter()
+            .map(|(key, value)| {
+                let value = if is_child_plan(step, key) {
+                    value.clone()
+                } else {
+                    render(value, &OutputRef::parse, &output)?
+                };
+                Ok((key.clone(), value))
+            })
+            .collect::<Result<Map<_, _>>>()
+            .map(Value::Object),
+        input => render(input, &OutputRef::parse, &output),
+    }
+}
+
+/// `plan` with the `{{params.<name>}}` placeholders in its step inputs
+/// filled in. Values come from `overrides` (a `plan` step's `params`), else
+/// from the plan's own `params` defaults.
+pub fn apply_params(plan: &Value, overrides: Option<&Value>) -> Result<Value> {
+    let mut params = match plan.get("params") {
+        Some(Value::Object(defaults)) => defaults.clone(),
+        Some(_) => bail!("Plan 'params' must be an object"),
+        None => Map::new(),
+    };
+    match overrides {
+        Some(Value::Object(values)) => params.extend(values.clone()),
+        Some(_) => bail!("'params' must be an object"),
+        None => {}
+    }
+    let params = Value::Object(params);
+    let parse = |inner: &str| VarRef::parse("params", inner);
+    let lookup = |reference: &VarRef| reference.lookup(&params).cloned();
+
+    let mut plan = plan.clone();
+    let Some(steps) = plan.get_mut("steps").and_then(Value::as_array_mut) else {
+        return Ok(plan);
+    };
+    for step in steps {
+        let nested = step["tool"] == PLAN_STEP_TOOL;
+        let Some(input) = step.get_mut("input") else {
+            continue;
+        };
+        *input = match &*input {
+            // A nested plan's own placeholders are filled in when it runs
+            Value::Object(map) => Value::Object(
+                map.iter()
+                    .map(|(key, value)| {
+                        let value = if nested && key == "plan" {
+                            value.clone()
+                        } else {
+                            render(value, &parse, &lookup)?
+                        };
+                        Ok((key.clone(), value))
+                    })
+                    .collect::<Result<_>>()?,
+            ),
+            other => render(other, &parse, &lookup)?,
+        };
+    }
+    Ok(plan)
+}
+
+/// Check that each step only uses outputs of steps that finish before it:
+/// earlier steps when the plan runs in order, otherwise its (transitive)
+/// dependencies
+pub fn check_output_references(steps: &[ScheduledStep]) -> Result<()> {
+    let id_to_idx: HashMap<&str, usize> = steps
+        .iter()
+        .enumerate()
+        .map(|(i, s)| (s.id.as_str(), i))
+        .collect();
+    let sequential = !has_dependencies(steps);
+    for (i, step) in steps.iter().enumerate() {
+        let mut refs = output_references(step)?;
+        let extra = step.when.as_ref().map(StepCondition::step);
+        for id in extra
+            .into_iter()
+            .chain(step.foreach.as_ref().and_then(Foreach::step))
+        {
+            if !refs.iter().any(|r| r == id) {
+                refs.push(id.to_string());
+            }
+        }
+        if refs.is_empty() {
+            continue;
+        }
+        let ancestors = if sequential {
+            HashSet::new()
+        } else {
+            ancestors(steps, &id_to_idx, i)
+        };
+        for id in refs {
+            let Some(&idx) = id_to_idx.get(id.as_str()) else {
+                bail!(
+                    "Step '{}' uses the output of '{}' which does not exist",
+                    step.id,
+                    id
+                );
+            };
+            let runs_before = if sequential {
+                idx < i
+            } else {
+                ancestors.contains(&idx)
+            };
+            if !runs_before {
+                bail!(
+                    "Step '{}' uses the output of '{}' but does not run after it{}",
+                    step.id,
+                    id,
+                    if sequential {
+                        ""
+                    } else {
+                        " (add it to depends_on)"
+                    }
+                );
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Indexes of the steps `steps[idx]` depends on, directly or not
+fn ancestors(
+    steps: &[ScheduledStep],
+    id_to_idx: &HashMap<&str, usize>,
+    idx: usize,
+) -> HashSet<usize> {
+    let mut seen = HashSet::new();
+    let mut stack = vec![idx];
+    while let Some(current) = stack.pop() {
+        for dep in &steps[current].depends_on {
+            if let Some(&dep_idx) = id_to_idx.get(dep.as_str()) {
+                if seen.insert(dep_idx) {
+                    stack.push(dep_idx);
+                }
+            }
+        }
+    }
+    seen
+}
+
 /// Check if plan has any dependencies declared
 pub fn has_dependencies(steps: &[ScheduledStep]) -> bool {
     steps.iter().any(|s| !s.depends_on.is_empty())
 }
+
+/// How a plan will be executed, computed without running it
+#[derive(Debug, Clone, Serialize)]
+pub struct PlanSchedule {
+    pub plan_id: String,
+    /// Step ids per level; steps within a level may run concurrently
+    pub levels: Vec<Vec<String>>,
+    /// Step ids in execution order (level by level)
+    pub order: Vec<String>,
+    /// True when no `depends_on` is declared and steps run strictly in order
+    pub sequential: bool,
+    /// Most steps running at once (widest level, capped by `max_parallel`)
+    pub max_concurrency: usize,
+    /// Average steps per level, i.e. the speed-up over running one at a time
+    pub estimated_parallelism: f64,
+}
+
+/// Validate a plan and compute its schedule for a runtime with `max_parallel` slots
+pub fn plan_schedule(plan: &Value, max_parallel: usize) -> Result<PlanSchedule> {
+    let steps = parse_steps(plan)?;
+    let sequential = !has_dependencies(&steps);
+    let levels = if sequential {
+        (0..steps.len()).map(|i| vec![i]).collect()
+    } else {
+        compute_levels(&steps)?
+    };
+    check_output_references(&steps)?;
+
+    let levels: Vec<Vec<String>> = levels
+        .iter()
+        .map(|level| level.iter().map(|&i| steps[i].id.clone()).collect())
+        .collect();
+    let widest = levels.iter().map(Vec::len).max().unwrap_or(0);
+    let estimated_parallelism = if levels.is_empty() {
+        0.0
+    } else {
+        steps.len() as f64 / levels.len() as f64
+    };
+
+    Ok(PlanSchedule {
+        plan_id: plan["id"].as_str().unwrap_or("unknown").to_string(),
+        order: levels.iter().flatten().cloned().collect(),
+        levels,
+        sequential,
+        max_concurrency: widest.min(max_parallel.max(1)),
+        estimated_parallelism,
+    })
+}
@@ -0,0 +1,495 @@
+use anyhow::{Context, Result};
+use async_trait::async_trait;
+use chrono::{DateTime, SecondsFormat, Utc};
+use rusqlite::{params, params_from_iter, OptionalExtension};
+use serde::Serialize;
+use std::path::Path;
+use std::sync::Arc;
+use tracing::{info, warn};
+
+use crate::agent_module::{Session, SessionBackend, SessionStore};
+use crate::llm::types::Usage;
+use crate::memory::sqlite::ConnectionPool;
+use crate::redaction::Redactor;
+use crate::storage::blocking;
+
+/// Session store in one SQLite database: the session itself is kept as JSON,
+/// next to indexed columns for listing and pruning without loading it
+pub struct SqliteSessionStore {
+    pool: Arc<ConnectionPool>,
+    redactor: Option<Arc<Redactor>>,
+}
+
+/// Which sessions [`SqliteSessionStore::query`] returns (unset fields match
+/// all), most recently updated first
+#[derive(Debug, Clone, Default)]
+pub struct SessionQuery {
+    pub agent: Option<String>,
+    pub updated_after: Option<DateTime<Utc>>,
+    pub updated_before: Option<DateTime<Utc>>,
+    /// Least input plus output tokens used
+    pub min_tokens: Option<u64>,
+    pub limit: Option<usize>,
+}
+
+/// Indexed summary of a stored session
+#[derive(Debug, Clone, Serialize)]
+pub struct SessionInfo {
+    pub id: String,
+    pub agent_name: String,
+    pub message_count: usize,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+    pub usage: Usage,
+}
+
+/// Outcome of [`SqliteSessionStore::import_json`]
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+pub struct ImportStats {
+    pub imported: usize,
+    /// Already stored with the same or a later update
+    pub skipped: usize,
+    /// Unreadable files, left in place
+    pub failed: usize,
+}
+
+/// Timestamps are stored in one fixed format so they sort as text
+fn timestamp(time: &DateTime<Utc>) -> String {
+    time.to_rfc3339_opts(SecondsFormat::Micros, true)
+}
+
+/// Bring a table created by an older version up to date: `cost_usd` came
+/// later, filled in from the stored sessions
+fn migrate(conn: &rusqlite::Connection) -> rusqlite::Result<()> {
+    let has_cost: bool = conn.query_row(
+        "SELECT COUNT(*) > 0 FROM pragma_table_info('sessions') WHERE name = 'cost_usd'",
+        [],
+        |row| row.get(0),
+    )?;
+    if !has_cost {
+        conn.execute_batch(
+            "ALTER TABLE sessions ADD COLUMN cost_usd REAL NOT NULL DEFAULT 0;
+             UPDATE sessions
+                SET cost_usd = COALESCE(json_extract(data, '$.cumulative_usage.cost_usd'), 0);",
+        )?;
+    }
+    Ok(())
+}
+
+fn parse_timestamp(text: &str) -> rusqlite::Result<DateTime<Utc>> {
+    DateTime::parse_from_rfc3339(text)
+        .map(|time| time.with_timezone(&Utc))
+        .map_err(|e| rusqlite::Error::ToSqlConversionFailure(Box::new(e)))
+}
+
+impl SqliteSessionStore {
+    pub fn open(path: &Path) -> Result<Self> {
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent)
+                .with_context(|| format!("Failed to create session dir: {:?}", parent))?;
+        }
+        let pool = ConnectionPool::open(path)?;
+        let conn = pool.writer()?;
+        conn.execute_batch(
+            "CREATE TABLE IF NOT EXISTS sessions (
+                id TEXT PRIMARY KEY,
+                agent_name TEXT NOT NULL,
+                created_at TEXT NOT NULL,
+                updated_at TEXT NOT NULL,
+                message_count INTEGER NOT NULL,
+                input_tokens INTEGER NOT NULL,
+                output_tokens INTEGER NOT NULL,
+                total_tokens INTEGER NOT NULL,
+                cost_usd REAL NOT NULL DEFAULT 0,
+                data TEXT NOT NULL
+            );
+            CREATE INDEX IF NOT EXISTS idx_sessions_agent ON sessions(agent_name, updated_at);
+            CREATE INDEX IF NOT EXISTS idx_sessions_updated ON sessions(updated_at);
+            CREATE INDEX IF NOT EXISTS idx_sessions_tokens ON sessions(total_tokens);",
+        )
+        .context("Failed to initialize session table")?;
+        migrate(&conn).context("Failed to migrate session table")?;
+        drop(conn);
+        Ok(Self {
+            pool: Arc::new(pool),
+            redactor: None,
+        })
+    }
+
+    /// Redact sensitive values in what `save` writes (the session in memory
+    /// is left as is)
+    pub fn with_redactor(mut self, redactor: Arc<Redactor>) -> Self {
+        self.redactor = Some(redactor);
+        self
+    }
+
+    /// Insert or replace a session
+    pub async fn save(&self, session: &Session) -> Result<()> {
+        let mut session = session.clone();
+        if let Some(redactor) = &self.redactor {
+            redactor.redact_session(&mut session);
+        }
+        let pool = self.pool.clone();
+        blocking(move || save_session(&pool, &session, false).map(|_| ())).await
+    }
+
+    pub async fn load(&self, session_id: &str) -> Result<Session> {
+        let (pool, id) = (self.pool.clone(), session_id.to_string());
+        blocking(move || {
+            let data: String = pool
+                .reader()?
+                .query_row(
+                    "SELECT data FROM sessions WHERE id = ?1",
+                    params![id],
+                    |row| row.get(0),
+                )
+                .optional()?
+                .with_context(|| format!("Session not found: {}", id))?;
+            serde_json::from_str(&data).with_context(|| format!("Corrupted session: {}", id))
+        })
+        .await
+    }
+
+    /// All session IDs
+    pub async fn list_sessions(&self) -> Result<Vec<String>> {
+        let pool = self.pool.clone();
+        blocking(move || {
+            let conn = pool.reader()?;
+            let mut stmt = conn.prepare("SELECT id FROM sessions ORDER BY id")?;
+            let ids = stmt
+                .query_map([], |row| row.get(0))?
+                .collect::<rusqlite::Result<Vec<String>>>()?;
+            Ok(ids)
+        })
+        .await
+    }
+
+    /// Sessions matching `query`, most recently updated first
+    pub async fn query(&self, query: &SessionQuery) -> Result<Vec<SessionInfo>> {
+        let mut sql = String::from(
+            "SELECT id, agent_name, message_count, created_at, updated_at, input_tokens, \
+             output_tokens, cost_usd FROM sessions WHERE 1 = 1",
+        );
+        let mut args: Vec<rusqlite::types::Value> = Vec::new();
+        if let Some(agent) = &query.agent {
+            sql.push_str(" AND agent_name = ?");
+            args.push(agent.clone().into());
+        }
+        if let Some(after) = &query.updated_after {
+            sql.push_str(" AND updated_at >= ?");
+            args.push(timestamp(after).into());
+        }
+        if let Some(before) = &query.updated_before {
+            sql.push_str(" AND updated_at < ?");
+            args.push(timestamp(before).into());
+        }
+        if let Some(min_tokens) = query.min_tokens {
+            sql.push_str(" AND total_tokens >= ?");
+            args.push((min_tokens.min(i64::MAX as u64) as i64).into());
+        }
+        sql.push_str(" ORDER BY updated_at DESC");
+        if let Some(limit) = query.limit {
+            sql.push_str(&format!(" LIMIT {}", limit));
+        }
+
+        let pool = self.pool.clone();
+        blocking(move || {
+            let conn = pool.reader()?;
+            let mut stmt = conn.prepare(&sql)?;
+            let sessions = stmt
+                .query_map(params_from_iter(args), |row| {
+                    Ok(SessionInfo {
+                        id: row.get(0)?,
+                        agent_name: row.get(1)?,
+                        message_count: row.get(2)?,
+                        created_at: parse_timestamp(&row.get::<_, String>(3)?)?,
+                        updated_at: parse_timestamp(&row.get::<_, String>(4)?)?,
+                        usage: Usage {
+                            input_tokens: row.get(5)?,
+                            output_tokens: row.get(6)?,
+                            cost_usd: row.get(7)?,
+                        },
+                    })
+                })?
+                .collect::<rusqlite::Result<Vec<_>>>()?;
+            Ok(sessions)
+        })
+        .await
+    }
+
+    /// Remove a session; false if there was none with this ID
+    pub async fn delete(&self, session_id: &str) -> Result<bool> {
+        let (pool, id) = (self.pool.clone(), session_id.to_string());
+        blocking(move || {
+            let deleted = pool
+                .writer()?
+                .execute("DELETE FROM sessions WHERE id = ?1", params![id])?;
+            Ok(deleted > 0)
+        })
+        .await
+    }
+
+    /// Remove sessions last updated before `cutoff`; returns how many
+    pub async fn prune(&self, cutoff: DateTime<Utc>) -> Result<usize> {
+        let pool = self.pool.clone();
+        blocking(move || {
+            let pruned = pool.writer()?.execute(
+                "DELETE FROM sessions WHERE updated_at < ?1",
+                params![timestamp(&cutoff)],
+            )?;
+            Ok(pruned)
+        })
+        .await
+    }
+
+    /// Import the sessions of a JSON [`SessionStore`] directory. Sessions
+    /// already stored are only replaced by a more recently updated file, so
+    /// running the import again is harmless; the files are left in place.
+    pub async fn import_json(&self, dir: &Path) -> Result<ImportStats> {
+        let json_store = SessionStore::new(dir.to_path_buf())?;
+        let mut sessions = Vec::new();
+        let mut stats = ImportStats::default();
+        for id in json_store.list_sessions()? {
+            match json_store.load(&id).await {
+                Ok(session) => sessions.push(session),
+                Err(e) => {
+                    warn!(session_id = %id, error = %e, "Skipping unreadable session file");
+                    stats.failed += 1;
+                }
+            }
+        }
+
+        let pool = self.pool.clone();
+        let stats = blocking(move || {
+            for session in &sessions {
+                if save_session(&pool, session, true)? {
+                    stats.imported += 1;
+                } else {
+                    stats.skipped += 1;
+                }
+            }
+            Ok(stats)
+        })
+        .await?;
+        info!(
+            dir = %dir.display(),
+            imported = stats.imported,
+            skipped = stats.skipped,
+            failed = stats.failed,
+            "Imported JSON sessions"
+        );
+        Ok(stats)
+    }
+}
+
+#[async_trait]
+impl SessionBackend for SqliteSessionStore {
+    async fn save(&self, session: &Session) -> Result<()> {
+        SqliteSessionStore::save(self, session).await
+    }
+
+    async fn load(&self, session_id: &str) -> Result<Session> {
+        SqliteSessionStore::load(self, session_id).await
+    }
+
+    async fn list_sessions(&self) -> Result<Vec<String>> {
+        SqliteSessionStore::list_sessions(self).await
+    }
+}
+
+/// Write a session row; with `only_newer`, an existing row updated at the same
+/// time or later is kept. Returns whether the row was written.
+fn save_session(pool: &ConnectionPool, session: &Session, only_newer: bool) -> Result<bool> {
+    let data = serde_json::to_string(session)?;
+    let usage = &session.cumulative_usage;
+    let mut sql = String::from(
+        "INSERT INTO sessions (id, agent_name, created_at, updated_at, message_count,
+             input_tokens, output_tokens, total_tokens, cost_usd, data)
+         VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10)
+         ON CONFLICT(id) DO UPDATE SET
+             agent_name = excluded.agent_name,
+             created_at = excluded.created_at,
+             updated_at = excluded.updated_at,
+             message_count = excluded.message_count,
+             input_tokens = excluded.input_tokens,
+             output_tokens = excluded.output_tokens,
+             total_tokens = excluded.total_tokens,
+             cost_usd = excluded.cost_usd,
+             data = excluded.data",
+    );
+    if only_newer {
+        sql.push_str(" WHERE excluded.updated_at > sessions.updated_at");
+    }
+    let written = pool
+        .writer()?
+        .execute(
+            &sql,
+            params![
+                session.id,
+                session.agent_name,
+                timestamp(&session.created_at),
+                timestamp(&session.updated_at),
+                session.messages.len() as i64,
+                usage.input_tokens,
+                usage.output_tokens,
+                usage.total(),
+                usage.cost_usd,
+                data,
+            ],
+        )
+        .with_context(|| format!("Failed to save session: {}", session.id))?;
+    Ok(written > 0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::llm::types::Message;
+
+    fn session(agent: &str, tokens: u32, updated_at: &str) -> Session {
+        let mut session = Session::new(agent);
+        session.add_message(Message::user("hello"));
+        session.cumulative_usage = Usage {
+            input_tokens: tokens,
+            output_tokens: 0,
+            cost_usd: tokens as f64 / 1000.0,
+        };
+        session.updated_at = updated_at.parse().unwrap();
+        session
+    }
+
+    #[tokio::test]
+    async fn test_save_load_and_query() {
+        let dir = tempfile::tempdir().unwrap();
+        let store = SqliteSessionStore::open(&dir.path().join("sessions.db")).unwrap();
+        let old = session("coder", 50, "2026-01-01T00:00:00Z");
+        let recent = session("coder", 500, "2026-03-01T00:00:00Z");
+        let other = session("reviewer", 1000, "2026-02-01T00:00:00Z");
+        for s in [&old, &recent, &other] {
+            store.save(s).await.unwrap();
+        }
+
+        let loaded = store.load(&recent.id).await.unwrap();
+        assert_eq!(loaded.messages.len(), 1);
+        assert_eq!(loaded.updated_at, recent.updated_at);
+        assert_eq!(store.list_sessions().await.unwrap().len(), 3);
+
+        let ids = |infos: Vec<SessionInfo>| infos.into_iter().map(|i| i.id).collect::<Vec<_>>();
+        let all = store.query(&SessionQuery::default()).await.unwrap();
+        assert_eq!(
+            ids(all),
+            vec![recent.id.clone(), other.id.clone(), old.id.clone()]
+        );
+        let coder = SessionQuery {
+            agent: Some("coder".into()),
+            ..SessionQuery::default()
+        };
+        assert_eq!(
+            ids(store.query(&coder).await.unwrap()),
+            vec![recent.id.clone(), old.id.clone()]
+        );
+        let heavy_since_jan = SessionQuery {
+            updated_after: Some("2026-01-15T00:00:00Z".parse().unwrap()),
+            min_tokens: Some(600),
+            ..SessionQuery::default()
+        };
+        let heavy = store.query(&heavy_since_jan).await.unwrap();
+        assert_eq!(ids(heavy.clone()), vec![other.id.clone()]);
+        assert_eq!(heavy[0].usage.input_tokens, 1000);
+        assert_eq!(heavy[0].usage.cost_usd, 1.0);
+        assert_eq!(heavy[0].message_count, 1);
+
+        let pruned = store
+            .prune("2026-02-15T00:00:00Z".parse().unwrap())
+            .await
+            .unwrap();
+        assert_eq!(pruned, 2);
+        assert!(store.load(&old.id).await.is_err());
+        assert!(store.delete(&recent.id).await.unwrap());
+        assert!(!store.delete(&recent.id).await.unwrap());
+    }
+
+    #[tokio::test]
+    async fn test_open_adds_cost_to_older_tables() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("sessions.db");
+        let old = session("coder", 50, "2026-01-01T00:00:00Z");
+        {
+            let conn = rusqlite::Connection::open(&path).unwrap();
+            conn.execute_batch(
+                "CREATE TABLE sessions (
+                    id TEXT PRIMARY KEY,
+                    agent_name TEXT NOT NULL,
+                    created_at TEXT NOT NULL,
+                    updated_at TEXT NOT NULL,
+                    message_count INTEGER NOT NULL,
+                    input_tokens INTEGER NOT NULL,
+                    output_tokens INTEGER NOT NULL,
+                    total_tokens INTEGER NOT NULL,
+                    data TEXT NOT NULL
+                );",
+            )
+            .unwrap();
+            conn.execute(
+                "INSERT INTO sessions VALUES (?1, 'coder', ?2, ?2, 1, 50, 0, 50, ?3)",
+                rusqlite::params![
+                    old.id,
+                    timestamp(&old.updated_at),
+                    serde_json::to_string(&old).unwrap()
+                ],
+            )
+            .unwrap();
+        }
+
+        let store = SqliteSessionStore::open(&path).unwrap();
+        let all = store.query(&SessionQuery::default()).await.unwrap();
+        assert_eq!(all[0].usage.cost_usd, old.cumulative_usage.cost_usd);
+        store
+            .save(&session("coder", 10, "2026-02-01T00:00:00Z"))
+            .await
+            .unwrap();
+        // Reopening a migrated table is a no-op
+        drop(store);
+        let store = SqliteSessionStore::open(&path).unwrap();
+        assert_eq!(store.list_sessions().await.unwrap().len(), 2);
+    }
+
+    #[tokio::test]
+    async fn test_import_json_keeps_newer_rows() {
+        let dir = tempfile::tempdir().unwrap();
+        let json = SessionStore::new(dir.path().join("sessions")).unwrap();
+        let first = session("coder", 10, "2026-01-01T00:00:00Z");
+        let second = session("coder", 20, "2026-01-02T00:00:00Z");
+        json.save(&first).await.unwrap();
+        json.save(&second).await.unwrap();
+        std::fs::write(dir.path().join("sessions/broken.json"), "{").unwrap();
+
+        let store = SqliteSessionStore::open(&dir.path().join("sessions.db")).unwrap();
+        // A later update already in the database wins over the file
+        let mut updated = second.clone();
+        updated.add_message(Message::user("more"));
+        store.save(&updated).await.unwrap();
+
+        let stats = store
+            .import_json(&dir.path().join("sessions"))
+            .await
+            .unwrap();
+        assert_eq!(
+            stats,
+            ImportStats {
+                imported: 1,
+                skipped: 1,
+                failed: 1,
+            }
+        );
+        assert_eq!(store.load(&first.id).await.unwrap().messages.len(), 1);
+        assert_eq!(store.load(&second.id).await.unwrap().messages.len(), 2);
+
+        let again = store
+            .import_json(&dir.path().join("sessions"))
+            .await
+            .unwrap();
+        assert_eq!(again.imported, 0);
+        assert_eq!(again.skipped, 2);
+    }
+}
@@ -1,11 +1,30 @@
+use crate::audit::{AuditFilter, AuditRecord};
 use anyhow::{Context, Result};
 use redb::{Database, ReadableTable, TableDefinition};
 use serde_json::Value;
+use std::sync::Arc;
 
 const STATE_TABLE: TableDefinition<&str, &str> = TableDefinition::new("state");
 
+/// Tool call audit records (JSON) by sequence number
+const AUDIT_TABLE: TableDefinition<u64, &str> = TableDefinition::new("audit");
+
+/// Key/value state store. Clones share the database; the `*_async` methods
+/// run on tokio's blocking pool so large writes do not stall async tasks.
+#[derive(Clone)]
 pub struct Storage {
-    db: Database,
+    db: Arc<Database>,
+}
+
+/// Run synchronous database work on tokio's blocking thread pool
+pub async fn blocking<T, F>(f: F) -> Result<T>
+where
+    F: FnOnce() -> Result<T> + Send + 'static,
+    T: Send + 'static,
+{
+    tokio::task::spawn_blocking(f)
+        .await
+        .context("Database task panicked")?
 }
 
 impl Storage {
@@ -17,10 +36,47 @@ impl Storage {
         let write_txn = db.begin_write()?;
         {
             let _ = write_txn.open_table(STATE_TABLE)?;
+            let _ = write_txn.open_table(AUDIT_TABLE)?;
         }
         write_txn.commit()?;
 
-        Ok(Self { db })
+        Ok(Self { db: Arc::new(db) })
+    }
+
+    /// `save_state` without blocking the async runtime
+    pub async fn save_state_async(&self, key: &str, value: Value) -> Result<()> {
+        let (storage, key) = (self.clone(), key.to_string());
+        blocking(move || storage.save_state(&key, &value)).await
+    }
+
+    /// `load_state` without blocking the async runtime
+    pub async fn load_state_async(&self, key: &str) -> Result<Option<Value>> {
+        let (storage, key) = (self.clone(), key.to_string());
+        blocking(move || storage.load_state(&key)).await
+    }
+
+    /// `list_keys` without blocking the async runtime
+    pub async fn list_keys_async(&self) -> Result<Vec<String>> {
+        let storage = self.clone();
+        blocking(move || storage.list_keys()).await
+    }
+
+    /// `delete_prefix` without blocking the async runtime
+    pub async fn delete_prefix_async(&self, prefix: &str) -> Result<usize> {
+        let (storage, prefix) = (self.clone(), prefix.to_string());
+        blocking(move || storage.delete_prefix(&prefix)).await
+    }
+
+    /// `append_audit` without blocking the async runtime
+    pub async fn append_audit_async(&self, record: AuditRecord) -> Result<u64> {
+        let storage = self.clone();
+        blocking(move || storage.append_audit(record)).await
+    }
+
+    /// `audit_records` without blocking the async runtime
+    pub async fn audit_records_async(&self, filter: AuditFilter) -> Result<Vec<AuditRecord>> {
+        let storage = self.clone();
+        blocking(move || storage.audit_records(&filter)).await
     }
 
     /// Save state to database
@@ -61,4 +117,70 @@ impl Storage {
         }
         Ok(keys)
     }
+
+    /// Delete every key starting with `prefix`; returns how many were removed
+    pub fn delete_prefix(&self, prefix: &str) -> Result<usize> {
+        let write_txn = self.db.begin_write()?;
+        let removed = {
+            let mut table = write_txn.open_table(STATE_TABLE)?;
+            let mut keys = Vec::new();
+            for entry in table.range(prefix..)? {
+                let (key, _) = entry?;
+                if !key.value().starts_with(prefix) {
+                    break;
+                }
+                keys.push(key.value().to_string());
+            }
+            for key in &keys {
+                table.remove(key.as_str())?;
+            }
+            keys.len()
+        };
+        write_txn.commit()?;
+        Ok(removed)
+    }
+
+    /// Store `record` under the next sequence number; returns that number
+    pub fn append_audit(&self, mut record: AuditRecord) -> Result<u64> {
+        let write_txn = self.db.begin_write()?;
+        {
+            let mut table = write_txn.open_table(AUDIT_TABLE)?;
+            record.id = match table.last()? {
+                Some((last, _)) => last.value() + 1,
+                None => 1,
+            };
+            let record_str = serde_json::to_string(&record)?;
+            table.insert(record.id, record_str.as_str())?;
+        }
+        write_txn.commit()?;
+        Ok(record.id)
+    }
+
+    /// Audit records passing `filter`, newest first
+    pub fn audit_records(&self, filter: &AuditFilter) -> Result<Vec<AuditRecord>> {
+        let read_txn = self.db.begin_read()?;
+        let table = read_txn.open_table(AUDIT_TABLE)?;
+        let mut records = Vec::new();
+        for entry in table.iter()?.rev() {
+            if filter.limit.is_some_and(|limit| records.len() >= limit) {
+                break;
+            }
+            let (_, value) = entry?;
+            let record: AuditRecord = serde_json::from_str(value.value())?;
+            if filter.matches(&record) {
+                records.push(record);
+            }
+        }
+        Ok(records)
+    }
+
+    /// Audit record `id`, if stored
+    pub fn audit_record(&self, id: u64) -> Result<Option<AuditRecord>> {
+        let read_txn = self.db.begin_read()?;
+        let table = read_txn.open_table(AUDIT_TABLE)?;
+        match table.get(id)? {
+            Some(value) => Ok(Some(serde_json::from_str(value.value())?)),
+            None => Ok(None),
+        }
+    }
 }
@@ -1,9 +1,15 @@
 use anyhow::Result;
 use async_trait::async_trait;
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
 use serde_json::Value;
 
-/// Permission level for tool execution
-#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+/// Permission level for tool execution.
+/// Variants are ordered by privilege: Read < Write < Execute < Network < Admin
+#[derive(
+    Debug, Clone, PartialEq, Eq, Hash, PartialOrd, Ord, Serialize, Deserialize, JsonSchema,
+)]
+#[serde(rename_all = "lowercase")]
 pub enum PermissionLevel {
     Read,
     Write,
@@ -48,4 +54,7 @@ pub trait Tool: Send + Sync {
     fn permission_level(&self) -> PermissionLevel {
         PermissionLevel::Execute
     }
+
+    /// Forget what the tool keeps for `session`, which has ended
+    fn end_session(&self, _session: &str) {}
 }
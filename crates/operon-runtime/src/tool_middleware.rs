@@ -0,0 +1,328 @@
+//! Tool middleware: behaviour stacked around every tool the runtime executes
+//! (output truncation, metrics, redaction, caching), so each concern doesn't
+//! need its own wrapper `Tool`.
+
+use std::sync::Arc;
+
+use anyhow::Result;
+use async_trait::async_trait;
+use serde_json::Value;
+
+use crate::execution_backend::{ExecutionBackend, InProcess};
+use crate::tool::Tool;
+
+tokio::task_local! {
+    /// Session of the tool call being run
+    static CURRENT_SESSION: Option<String>;
+}
+
+/// Session the running tool call belongs to, for tools that keep per-session
+/// state (None outside a session or outside a call)
+pub fn current_session() -> Option<String> {
+    CURRENT_SESSION.try_with(Clone::clone).ok().flatten()
+}
+
+/// A tool call as seen (and rewritable) by middleware
+#[derive(Debug, Clone)]
+pub struct ToolInvocation {
+    /// Registered name of the tool being run (informational; the tool is
+    /// already chosen when middleware runs)
+    pub tool: String,
+    pub input: Value,
+    /// Agent session the call belongs to, if any
+    pub session: Option<String>,
+}
+
+/// One layer around tool execution. Layers run outermost first on the way
+/// in and innermost first on the way out.
+#[async_trait]
+pub trait ToolMiddleware: Send + Sync {
+    /// Layer name for logging and error messages
+    fn name(&self) -> &str;
+
+    /// Inspect or rewrite the input. Returning an output skips the tool and
+    /// all inner layers (e.g. a cache hit); outer layers still see it.
+    async fn before_execute(&self, _call: &mut ToolInvocation) -> Result<Option<Value>> {
+        Ok(None)
+    }
+
+    /// Inspect or rewrite the output of a successful call
+    async fn after_execute(&self, _call: &ToolInvocation, _output: &mut Value) -> Result<()> {
+        Ok(())
+    }
+
+    /// Wrap the rest of the chain. The default runs `before_execute`, the
+    /// inner layers and the tool, then `after_execute`; override it to time,
+    /// retry or otherwise surround the call.
+    async fn around(&self, mut call: ToolInvocation, next: Next<'_>) -> Result<Value> {
+        let mut output = match self.before_execute(&mut call).await? {
+            Some(output) => {
+                tracing::debug!(layer = self.name(), tool = %call.tool, "Tool call answered by middleware");
+                output
+            }
+            None => next.run(call.clone()).await?,
+        };
+        self.after_execute(&call, &mut output).await?;
+        Ok(output)
+    }
+}
+
+/// The layers inside the current one, ending at the tool itself
+pub struct Next<'a> {
+    layers: &'a [Arc<dyn ToolMiddleware>],
+    backend: &'a dyn ExecutionBackend,
+    tool: &'a dyn Tool,
+}
+
+impl Next<'_> {
+    /// Run the remaining layers and the tool
+    pub async fn run(self, call: ToolInvocation) -> Result<Value> {
+        match self.layers.split_first() {
+            Some((layer, rest)) => {
+                let next = Next {
+                    layers: rest,
+                    backend: self.backend,
+                    tool: self.tool,
+                };
+                layer.around(call, next).await
+            }
+            None => {
+                CURRENT_SESSION
+                    .scope(call.session.clone(), self.backend.execute(call, self.tool))
+                    .await
+            }
+        }
+    }
+}
+
+/// Run `tool` in this process through `layers` (outermost first)
+pub async fn execute_with(
+    layers: &[Arc<dyn ToolMiddleware>],
+    tool: &dyn Tool,
+    call: ToolInvocation,
+) -> Result<Value> {
+    execute_on(layers, &InProcess, tool, call).await
+}
+
+/// Run `tool` through `layers`, handing the call to `backend` after the innermost
+pub async fn execute_on(
+    layers: &[Arc<dyn ToolMiddleware>],
+    backend: &dyn ExecutionBackend,
+    tool: &dyn Tool,
+    call: ToolInvocation,
+) -> Result<Value> {
+    Next {
+        layers,
+        backend,
+        tool,
+    }
+    .run(call)
+    .await
+}
+
+/// Cuts string values in tool output down to `max_bytes`, noting how much
+/// was dropped, so one huge result can't flood an agent's context
+pub struct OutputLimit {
+    max_bytes: usize,
+}
+
+impl OutputLimit {
+    pub fn new(max_bytes: usize) -> Self {
+        Self { max_bytes }
+    }
+
+    fn truncate(&self, value: &mut Value) {
+        match value {
+            Value::String(text) if text.len() > self.max_bytes => {
+                let mut end = self.max_bytes;
+                while !text.is_char_boundary(end) {
+                    end -= 1;
+                }
+                let omitted = text.len() - end;
+                text.truncate(end);
+                text.push_str(&format!("\n[... {} bytes truncated ...]", omitted));
+            }
+            Value::Array(items) => items.iter_mut().for_each(|v| self.truncate(v)),
+            Value::Object(map) => map.values_mut().for_each(|v| self.truncate(v)),
+            _ => {}
+        }
+    }
+}
+
+#[async_trait]
+impl ToolMiddleware for OutputLimit {
+    fn name(&self) -> &str {
+        "output_limit"
+    }
+
+    async fn after_execute(&self, _call: &ToolInvocation, output: &mut Value) -> Result<()> {
+        self.truncate(output);
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::sync::Mutex;
+
+    /// Returns its input, counting calls
+    struct Echo {
+        calls: AtomicUsize,
+    }
+
+    #[async_trait]
+    impl Tool for Echo {
+        async fn execute(&self, input: Value) -> Result<Value> {
+            self.calls.fetch_add(1, Ordering::SeqCst);
+            Ok(input)
+        }
+
+        fn name(&self) -> &str {
+            "echo"
+        }
+    }
+
+    /// Records hook order; optionally tags the input or answers from "cache"
+    struct Recorder {
+        name: &'static str,
+        log: Arc<Mutex<Vec<String>>>,
+        cached: Option<Value>,
+    }
+
+    impl Recorder {
+        fn new(name: &'static str, log: &Arc<Mutex<Vec<String>>>) -> Self {
+            Self {
+                name,
+                log: log.clone(),
+                cached: None,
+            }
+        }
+    }
+
+    #[async_trait]
+    impl ToolMiddleware for Recorder {
+        fn name(&self) -> &str {
+            self.name
+        }
+
+        async fn before_execute(&self, call: &mut ToolInvocation) -> Result<Option<Value>> {
+            self.log
+                .lock()
+                .unwrap()
+                .push(format!("{}.before", self.name));
+            call.input[self.name] = json!(true);
+            Ok(self.cached.clone())
+        }
+
+        async fn after_execute(&self, _call: &ToolInvocation, _output: &mut Value) -> Result<()> {
+            self.log
+                .lock()
+                .unwrap()
+                .push(format!("{}.after", self.name));
+            Ok(())
+        }
+    }
+
+    /// Overrides `around` to surround the whole inner chain
+    struct Around {
+        log: Arc<Mutex<Vec<String>>>,
+    }
+
+    #[async_trait]
+    impl ToolMiddleware for Around {
+        fn name(&self) -> &str {
+            "around"
+        }
+
+        async fn around(&self, call: ToolInvocation, next: Next<'_>) -> Result<Value> {
+            self.log.lock().unwrap().push("around.enter".into());
+            let output = next.run(call).await;
+            self.log.lock().unwrap().push("around.exit".into());
+            output
+        }
+    }
+
+    fn call(input: Value) -> ToolInvocation {
+        ToolInvocation {
+            tool: "echo".into(),
+            input,
+            session: None,
+        }
+    }
+
+    #[tokio::test]
+    async fn test_layers_run_in_onion_order() {
+        let log = Arc::new(Mutex::new(Vec::new()));
+        let layers: Vec<Arc<dyn ToolMiddleware>> = vec![
+            Arc::new(Around { log: log.clone() }),
+            Arc::new(Recorder::new("outer", &log)),
+            Arc::new(Recorder::new("inner", &log)),
+        ];
+        let tool = Echo {
+            calls: AtomicUsize::new(0),
+        };
+
+        let output = execute_with(&layers, &tool, call(json!({}))).await.unwrap();
+        // Both layers rewrote the input the tool received
+        assert_eq!(output, json!({"outer": true, "inner": true}));
+        assert_eq!(
+            log.lock().unwrap().as_slice(),
+            [
+                "around.enter",
+                "outer.before",
+                "inner.before",
+                "inner.after",
+                "outer.after",
+                "around.exit"
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_short_circuit_skips_tool_and_inner_layers() {
+        let log = Arc::new(Mutex::new(Vec::new()));
+        let layers: Vec<Arc<dyn ToolMiddleware>> = vec![
+            Arc::new(Recorder::new("outer", &log)),
+            Arc::new(Recorder {
+                cached: Some(json!("from cache")),
+                ..Recorder::new("cache", &log)
+            }),
+            Arc::new(Recorder::new("inner", &log)),
+        ];
+        let tool = Echo {
+            calls: AtomicUsize::new(0),
+        };
+
+        let output = execute_with(&layers, &tool, call(json!({}))).await.unwrap();
+        assert_eq!(output, json!("from cache"));
+        assert_eq!(tool.calls.load(Ordering::SeqCst), 0);
+        assert_eq!(
+            log.lock().unwrap().as_slice(),
+            ["outer.before", "cache.before", "cache.after", "outer.after"]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_output_limit_truncates_nested_strings() {
+        let layers: Vec<Arc<dyn ToolMiddleware>> = vec![Arc::new(OutputLimit::new(2))];
+        let tool = Echo {
+            calls: AtomicUsize::new(0),
+        };
+
+        let output = execute_with(
+            &layers,
+            &tool,
+            call(json!({"stdout": "héllo world", "lines": ["ok", "truncated"], "code": 0})),
+        )
+        .await
+        .unwrap();
+        // Never cuts inside a character: "hé" would be 3 bytes
+        assert_eq!(output["stdout"], "h\n[... 11 bytes truncated ...]");
+        assert_eq!(output["lines"][0], "ok");
+        assert_eq!(output["lines"][1], "tr\n[... 7 bytes truncated ...]");
+        assert_eq!(output["code"], 0);
+    }
+}
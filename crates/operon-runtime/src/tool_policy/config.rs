@@ -1,10 +1,15 @@
 //! Configuration for the tool policy pipeline layers.
 
+use std::collections::BTreeMap;
+
+use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
 
+use crate::tool::PermissionLevel;
+
 /// Configuration for the 7-layer tool policy pipeline.
 /// Each layer can be individually enabled/disabled via TOML config.
-#[derive(Debug, Clone, Deserialize, Serialize)]
+#[derive(Debug, Clone, Deserialize, Serialize, JsonSchema)]
 pub struct ToolPolicyConfig {
     /// Master switch: if false, no policy layers are evaluated
     #[serde(default)]
@@ -22,10 +27,19 @@ pub struct ToolPolicyConfig {
     #[serde(default)]
     pub rate_limit_enabled: bool,
 
-    /// Max tool calls per tool per minute
+    /// Max tool calls per tool per minute, across all callers
     #[serde(default = "default_max_calls")]
     pub max_calls_per_minute: u32,
 
+    /// Max calls per tool per minute from any one session
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub session_calls_per_minute: Option<u32>,
+
+    /// Max calls per tool per minute on behalf of any one authenticated
+    /// gateway identity (`gateway.api_keys` name)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub principal_calls_per_minute: Option<u32>,
+
     /// Layer 4: Input validation against tool schemas
     #[serde(default = "default_true")]
     pub input_validation_enabled: bool,
@@ -38,9 +52,87 @@ pub struct ToolPolicyConfig {
     #[serde(default)]
     pub dry_run_bypass_tools: Vec<String>,
 
-    /// Layer 6: Audit logging
+    /// Layer 6: Audit logging, and a record of every call in the runtime's
+    /// audit trail (`warden audit list`, GET /api/v1/audit)
     #[serde(default = "default_true")]
     pub audit_enabled: bool,
+
+    /// Layer 8: Deny tool results that would overflow the agent's context
+    /// window (needs `llm.context_window`)
+    #[serde(default = "default_true")]
+    pub context_window_enabled: bool,
+
+    /// Layer 9: Deny agent calls to tools outside the agent's `tools` list
+    #[serde(default = "default_true")]
+    pub agent_scope_enabled: bool,
+
+    /// Layer 10: Tools whose calls wait for a person to approve them
+    /// (`warden chat` asks on the terminal; the gateway asks WebSocket clients)
+    #[serde(default)]
+    pub approval_tools: Vec<String>,
+
+    /// Layer 11: Declarative rules (`[[tool_policy.rules]]`), checked in
+    /// order; the first rule a call matches decides it
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub rules: Vec<PolicyRule>,
+
+    /// Layer 12: Secrets scrubbed from tool inputs and outputs
+    /// (`[tool_policy.redaction]`)
+    #[serde(default)]
+    pub redaction: SecretRedactionConfig,
+}
+
+/// Secrets redaction: API keys, bearer tokens, AWS keys and configured
+/// patterns are replaced in tool inputs before they are logged or shown for
+/// approval, and in tool outputs before the model sees them
+#[derive(Debug, Clone, Deserialize, Serialize, JsonSchema)]
+pub struct SecretRedactionConfig {
+    #[serde(default = "default_true")]
+    pub enabled: bool,
+
+    /// Extra patterns (regex) by kind, e.g. internal_token = 'itk_[0-9a-f]{32}'
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub patterns: BTreeMap<String, String>,
+}
+
+/// A declarative policy rule. A call matches when every condition given
+/// matches; a rule without conditions matches every call.
+#[derive(Debug, Clone, Deserialize, Serialize, JsonSchema)]
+pub struct PolicyRule {
+    /// Name shown when the rule denies or holds a call
+    pub name: String,
+
+    /// What a matching call gets: "allow" (skip the rules after this one),
+    /// "deny" or "approve" (wait for a person, see `approval_tools`)
+    pub action: RuleAction,
+
+    /// Tool names the rule covers; a trailing `*` matches a prefix, e.g.
+    /// "ssh_*" (empty = every tool)
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub tools: Vec<String>,
+
+    /// Regexes over input fields by dotted path, e.g. `cmd = '^rm\s'` or
+    /// `"options.force" = "true"`; non-string values are matched as JSON, and
+    /// a missing field does not match
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub input: BTreeMap<String, String>,
+
+    /// Caller permission levels the rule covers (empty = any)
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub permissions: Vec<PermissionLevel>,
+
+    /// Why, told to the model on deny and to the person asked on approve
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub reason: Option<String>,
+}
+
+/// What a policy rule does with the calls it matches
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize, Serialize, JsonSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum RuleAction {
+    Allow,
+    Deny,
+    Approve,
 }
 
 fn default_true() -> bool {
@@ -63,10 +155,26 @@ impl Default for ToolPolicyConfig {
             default_permission: default_permission(),
             rate_limit_enabled: false,
             max_calls_per_minute: default_max_calls(),
+            session_calls_per_minute: None,
+            principal_calls_per_minute: None,
             input_validation_enabled: default_true(),
             dry_run_guard_enabled: default_true(),
             dry_run_bypass_tools: vec![],
             audit_enabled: default_true(),
+            context_window_enabled: default_true(),
+            agent_scope_enabled: default_true(),
+            approval_tools: vec![],
+            rules: vec![],
+            redaction: SecretRedactionConfig::default(),
+        }
+    }
+}
+
+impl Default for SecretRedactionConfig {
+    fn default() -> Self {
+        Self {
+            enabled: default_true(),
+            patterns: BTreeMap::new(),
         }
     }
 }
@@ -3,7 +3,11 @@
 pub mod config;
 pub mod layers;
 
+use crate::llm::Message;
 use crate::tool::PermissionLevel;
+use anyhow::Result;
+use async_trait::async_trait;
+use serde::Serialize;
 use serde_json::Value;
 
 /// Result of a single policy layer evaluation
@@ -12,6 +16,29 @@ pub enum PolicyDecision {
     Allow,
     /// Deny the tool call with a reason
     Deny(String),
+    /// Run the tool call only once a person approves it; the text describes
+    /// the call to them
+    RequireApproval(String),
+}
+
+/// A tool call waiting for a person's approval
+#[derive(Debug, Clone, Serialize)]
+pub struct ApprovalRequest {
+    /// Unique ID to answer the request by
+    pub call_id: String,
+    pub tool_name: String,
+    pub input: Value,
+    pub session_id: Option<String>,
+    /// What the call does, e.g. "shell: rm -rf build"
+    pub description: String,
+}
+
+/// Asks a person whether a tool call may run (a terminal prompt, a gateway
+/// client, ...)
+#[async_trait]
+pub trait Approver: Send + Sync {
+    /// Whether the call may run; waits for the answer
+    async fn approve(&self, request: &ApprovalRequest) -> Result<bool>;
 }
 
 /// Context passed to each policy layer for evaluation
@@ -21,6 +48,30 @@ pub struct PolicyContext {
     pub caller_permission: PermissionLevel,
     pub dry_run: bool,
     pub session_id: Option<String>,
+    /// Declared permission of the tool as registered right now (None if it
+    /// isn't); lets layers see tools registered after they were built
+    pub registered_permission: Option<PermissionLevel>,
+    /// Tokens left in the calling agent's context window (None if it has no
+    /// token budget)
+    pub context_tokens_left: Option<usize>,
+    /// Name of the agent whose session made the call (None outside agent
+    /// sessions, e.g. plan steps)
+    pub agent_name: Option<String>,
+    /// Authenticated gateway identity the session is serving (None outside
+    /// the gateway, or without gateway auth)
+    pub principal: Option<String>,
+    /// Latest messages of the calling session, oldest first, ending with the
+    /// reply that made the call (empty outside agent sessions)
+    pub recent_messages: Vec<Message>,
+    /// `input` with secrets redacted (None when there was nothing to redact)
+    pub redacted_input: Option<Value>,
+}
+
+impl PolicyContext {
+    /// Input as it may be logged or shown to a person: secrets redacted
+    pub fn display_input(&self) -> &Value {
+        self.redacted_input.as_ref().unwrap_or(&self.input)
+    }
 }
 
 /// Individual policy layer trait.
@@ -32,40 +83,118 @@ pub trait PolicyLayer: Send + Sync {
     /// Evaluate whether the tool call should proceed
     fn evaluate(&self, ctx: &PolicyContext) -> PolicyDecision;
 
+    /// Evaluate whether a call's output may be returned to the caller
+    fn evaluate_result(&self, _ctx: &PolicyContext, _output: &Value) -> PolicyDecision {
+        PolicyDecision::Allow
+    }
+
+    /// Scrub a copy of a call's input meant for logs and approval prompts
+    fn redact_input(&self, _input: &mut Value) {}
+
+    /// Rewrite a call's output before it is returned to the caller
+    fn process_result(&self, _ctx: &PolicyContext, _output: &mut Value) {}
+
     /// Whether this layer is active (disabled layers are skipped)
     fn enabled(&self) -> bool {
         true
     }
 }
 
+/// Policy layer that may wait on I/O before deciding, e.g. to run a memory
+/// search or ask an external policy service.
+#[async_trait]
+pub trait AsyncPolicyLayer: Send + Sync {
+    /// Layer name for logging and error messages
+    fn name(&self) -> &str;
+
+    /// Evaluate whether the tool call should proceed
+    async fn evaluate(&self, ctx: &PolicyContext) -> PolicyDecision;
+
+    /// Evaluate whether a call's output may be returned to the caller
+    async fn evaluate_result(&self, _ctx: &PolicyContext, _output: &Value) -> PolicyDecision {
+        PolicyDecision::Allow
+    }
+
+    /// Scrub a copy of a call's input meant for logs and approval prompts
+    fn redact_input(&self, _input: &mut Value) {}
+
+    /// Rewrite a call's output before it is returned to the caller
+    async fn process_result(&self, _ctx: &PolicyContext, _output: &mut Value) {}
+
+    /// Whether this layer is active (disabled layers are skipped)
+    fn enabled(&self) -> bool {
+        true
+    }
+}
+
+/// Runs a synchronous layer in the pipeline
+struct SyncLayer(Box<dyn PolicyLayer>);
+
+#[async_trait]
+impl AsyncPolicyLayer for SyncLayer {
+    fn name(&self) -> &str {
+        self.0.name()
+    }
+
+    async fn evaluate(&self, ctx: &PolicyContext) -> PolicyDecision {
+        self.0.evaluate(ctx)
+    }
+
+    async fn evaluate_result(&self, ctx: &PolicyContext, output: &Value) -> PolicyDecision {
+        self.0.evaluate_result(ctx, output)
+    }
+
+    fn redact_input(&self, input: &mut Value) {
+        self.0.redact_input(input)
+    }
+
+    async fn process_result(&self, ctx: &PolicyContext, output: &mut Value) {
+        self.0.process_result(ctx, output)
+    }
+
+    fn enabled(&self) -> bool {
+        self.0.enabled()
+    }
+}
+
 /// Pipeline that evaluates policy layers in order.
 /// Short-circuits on first Deny.
 pub struct ToolPolicyPipeline {
-    layers: Vec<Box<dyn PolicyLayer>>,
+    layers: Vec<Box<dyn AsyncPolicyLayer>>,
 }
 
 impl ToolPolicyPipeline {
     pub fn new() -> Self {
-        Self {
-            layers: Vec::new(),
-        }
+        Self { layers: Vec::new() }
     }
 
     /// Add a policy layer to the pipeline
     pub fn add_layer(mut self, layer: Box<dyn PolicyLayer>) -> Self {
+        self.layers.push(Box::new(SyncLayer(layer)));
+        self
+    }
+
+    /// Add a policy layer that decides asynchronously; it runs in the order
+    /// added, like any other layer
+    pub fn add_async_layer(mut self, layer: Box<dyn AsyncPolicyLayer>) -> Self {
         self.layers.push(layer);
         self
     }
 
     /// Evaluate all enabled layers in order.
-    /// Returns Ok(()) if all layers Allow, Err with reason on first Deny.
-    pub fn evaluate(&self, ctx: &PolicyContext) -> anyhow::Result<()> {
+    /// Returns Err with reason on first Deny; otherwise Ok with the first
+    /// approval a layer asked for, if any.
+    pub async fn evaluate(&self, ctx: &PolicyContext) -> anyhow::Result<Option<String>> {
+        let mut approval = None;
         for layer in &self.layers {
             if !layer.enabled() {
                 continue;
             }
-            match layer.evaluate(ctx) {
+            match layer.evaluate(ctx).await {
                 PolicyDecision::Allow => continue,
+                PolicyDecision::RequireApproval(description) => {
+                    approval.get_or_insert(description);
+                }
                 PolicyDecision::Deny(reason) => {
                     tracing::warn!(
                         layer = layer.name(),
@@ -77,8 +206,48 @@ impl ToolPolicyPipeline {
                 }
             }
         }
+        Ok(approval)
+    }
+
+    /// Evaluate all enabled layers on a call's output, in order.
+    /// Returns Err with reason on first Deny; the call has already run, only
+    /// its output is withheld.
+    pub async fn evaluate_result(&self, ctx: &PolicyContext, output: &Value) -> anyhow::Result<()> {
+        for layer in self.layers.iter().filter(|layer| layer.enabled()) {
+            if let PolicyDecision::Deny(reason) = layer.evaluate_result(ctx, output).await {
+                tracing::warn!(
+                    layer = layer.name(),
+                    tool = %ctx.tool_name,
+                    reason = %reason,
+                    "Tool output dropped by policy"
+                );
+                anyhow::bail!(
+                    "Output of '{}' dropped by {} (the call already ran): {}",
+                    ctx.tool_name,
+                    layer.name(),
+                    reason
+                );
+            }
+        }
         Ok(())
     }
+
+    /// `input` as redacted by the enabled layers, for
+    /// [`PolicyContext::redacted_input`]; None if no layer changed it
+    pub fn redact_input(&self, input: &Value) -> Option<Value> {
+        let mut redacted = input.clone();
+        for layer in self.layers.iter().filter(|layer| layer.enabled()) {
+            layer.redact_input(&mut redacted);
+        }
+        (redacted != *input).then_some(redacted)
+    }
+
+    /// Pass a call's output through every enabled layer, in order
+    pub async fn process_result(&self, ctx: &PolicyContext, output: &mut Value) {
+        for layer in self.layers.iter().filter(|layer| layer.enabled()) {
+            layer.process_result(ctx, output).await;
+        }
+    }
 }
 
 impl Default for ToolPolicyPipeline {
@@ -127,6 +296,33 @@ mod tests {
         }
     }
 
+    /// Helper: layer asking for approval
+    struct ApproveLayer;
+    impl PolicyLayer for ApproveLayer {
+        fn name(&self) -> &str {
+            "approve"
+        }
+        fn evaluate(&self, _ctx: &PolicyContext) -> PolicyDecision {
+            PolicyDecision::RequireApproval("shell: echo hi".into())
+        }
+    }
+
+    /// Helper: async layer denying tools named in the input's `deny` list
+    struct DenyListedLayer;
+    #[async_trait]
+    impl AsyncPolicyLayer for DenyListedLayer {
+        fn name(&self) -> &str {
+            "deny_listed"
+        }
+        async fn evaluate(&self, ctx: &PolicyContext) -> PolicyDecision {
+            tokio::task::yield_now().await;
+            match ctx.input["deny"].as_str() {
+                Some(tool) if tool == ctx.tool_name => PolicyDecision::Deny("listed".into()),
+                _ => PolicyDecision::Allow,
+            }
+        }
+    }
+
     fn test_ctx() -> PolicyContext {
         PolicyContext {
             tool_name: "shell".into(),
@@ -134,33 +330,70 @@ mod tests {
             caller_permission: PermissionLevel::Execute,
             dry_run: false,
             session_id: None,
+            registered_permission: None,
+            context_tokens_left: None,
+            agent_name: None,
+            principal: None,
+            recent_messages: Vec::new(),
+            redacted_input: None,
         }
     }
 
-    #[test]
-    fn test_pipeline_all_allow() {
+    #[tokio::test]
+    async fn test_pipeline_all_allow() {
         let pipeline = ToolPolicyPipeline::new()
             .add_layer(Box::new(AllowLayer))
             .add_layer(Box::new(AllowLayer));
-        assert!(pipeline.evaluate(&test_ctx()).is_ok());
+        assert!(pipeline.evaluate(&test_ctx()).await.is_ok());
     }
 
-    #[test]
-    fn test_pipeline_deny_stops_execution() {
+    #[tokio::test]
+    async fn test_pipeline_deny_stops_execution() {
         let pipeline = ToolPolicyPipeline::new()
             .add_layer(Box::new(AllowLayer))
             .add_layer(Box::new(DenyLayer("blocked".into())))
             .add_layer(Box::new(AllowLayer));
-        let err = pipeline.evaluate(&test_ctx()).unwrap_err();
+        let err = pipeline.evaluate(&test_ctx()).await.unwrap_err();
         assert!(err.to_string().contains("blocked"));
     }
 
-    #[test]
-    fn test_pipeline_disabled_layer_skipped() {
+    #[tokio::test]
+    async fn test_pipeline_disabled_layer_skipped() {
         let pipeline = ToolPolicyPipeline::new()
             .add_layer(Box::new(AllowLayer))
             .add_layer(Box::new(DisabledDenyLayer))
             .add_layer(Box::new(AllowLayer));
-        assert!(pipeline.evaluate(&test_ctx()).is_ok());
+        assert!(pipeline.evaluate(&test_ctx()).await.is_ok());
+    }
+
+    #[tokio::test]
+    async fn test_pipeline_approval_unless_denied() {
+        let pipeline = ToolPolicyPipeline::new()
+            .add_layer(Box::new(ApproveLayer))
+            .add_layer(Box::new(AllowLayer));
+        let approval = pipeline.evaluate(&test_ctx()).await.unwrap();
+        assert_eq!(approval.as_deref(), Some("shell: echo hi"));
+
+        let pipeline = ToolPolicyPipeline::new()
+            .add_layer(Box::new(ApproveLayer))
+            .add_layer(Box::new(DenyLayer("blocked".into())));
+        assert!(pipeline.evaluate(&test_ctx()).await.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_pipeline_async_layers_run_in_order() {
+        let pipeline = ToolPolicyPipeline::new()
+            .add_async_layer(Box::new(DenyListedLayer))
+            .add_layer(Box::new(DenyLayer("blocked".into())));
+        let mut ctx = test_ctx();
+        ctx.input = serde_json::json!({"deny": "shell"});
+        let err = pipeline.evaluate(&ctx).await.unwrap_err();
+        assert!(err
+            .to_string()
+            .contains("Policy denied by deny_listed: listed"));
+
+        ctx.input = serde_json::json!({});
+        let err = pipeline.evaluate(&ctx).await.unwrap_err();
+        assert!(err.to_string().contains("Policy denied by deny: blocked"));
     }
 }
@@ -1,6 +1,16 @@
 use anyhow::Result;
 use async_trait::async_trait;
-use operon_runtime::{ExecutionContext, Fixture, Runtime, Tool};
+use operon_runtime::tool_policy::config::PolicyRule;
+use operon_runtime::tool_policy::layers::{
+    ApprovalLayer, PermissionCheckLayer, RateLimitLayer, RedactionLayer, RuleEngineLayer,
+    ToolExistenceLayer,
+};
+use operon_runtime::{
+    ApprovalRequest, Approver, AuditDecision, AuditFilter, AuditOutcome, CompositeSpec,
+    ExecutionBackend, ExecutionContext, Fixture, FixtureTool, Hook, HookContext, HookEvent,
+    HookRegistry, HookResult, NestedStorage, OutputLimit, PermissionLevel, Runtime, StepRecord,
+    StepStatus, Storage, Tool, ToolInvocation, ToolMiddleware, ToolPolicyPipeline, FIXTURE_VERSION,
+};
 use serde_json::{json, Value};
 use std::sync::atomic::{AtomicU32, Ordering};
 use std::sync::Arc;
@@ -84,6 +94,45 @@ async fn test_runtime_dry_run_mode() {
     let _ = std::fs::remove_file(&db_path);
 }
 
+#[tokio::test]
+async fn test_per_step_dry_run_overrides() {
+    let db_path = get_test_db_path();
+    let tool = Arc::new(MockTool::new("mock"));
+
+    // Dry-run plan still runs always_execute steps
+    let runtime = Runtime::with_db(&db_path, true, Duration::from_secs(60)).unwrap();
+    runtime
+        .register_tool("mock".to_string(), tool.clone())
+        .unwrap();
+    let plan = json!({
+        "id": "test-overrides-dry",
+        "steps": [
+            {"id": "check", "tool": "mock", "input": {"n": 1}, "always_execute": true},
+            {"id": "apply", "tool": "mock", "input": {"n": 2}}
+        ]
+    });
+    runtime.run_plan(plan).await.unwrap();
+    assert!(runtime.step_output("check").unwrap().is_some());
+    assert!(runtime.step_output("apply").unwrap().is_none());
+    drop(runtime);
+
+    // Execute-mode DAG plan skips force_dry_run steps
+    let runtime = Runtime::with_db(&db_path, false, Duration::from_secs(60)).unwrap();
+    runtime.register_tool("mock".to_string(), tool).unwrap();
+    let plan = json!({
+        "id": "test-overrides-exec",
+        "steps": [
+            {"id": "build", "tool": "mock", "input": {}, "depends_on": []},
+            {"id": "deploy", "tool": "mock", "input": {}, "depends_on": ["build"], "force_dry_run": true}
+        ]
+    });
+    runtime.run_plan(plan).await.unwrap();
+    assert!(runtime.step_output("build").unwrap().is_some());
+    assert!(runtime.step_output("deploy").unwrap().is_none());
+
+    let _ = std::fs::remove_file(&db_path);
+}
+
 #[tokio::test]
 async fn test_runtime_missing_tool() {
     let db_path = get_test_db_path();
@@ -213,6 +262,110 @@ async fn test_runtime_replay_step_count_mismatch() {
     let _ = std::fs::remove_dir_all(&fixture_dir);
 }
 
+#[tokio::test]
+async fn test_unversioned_fixture_is_migrated_and_replayed() {
+    let fixture_dir = tempfile::tempdir().unwrap();
+    // Written before fixtures were versioned: no version, index or duration
+    let legacy = json!({
+        "plan_id": "legacy",
+        "steps": [{"tool": "mock", "input": {}, "output": {"recorded": true}}]
+    });
+    std::fs::write(
+        fixture_dir.path().join("fixture.json"),
+        serde_json::to_string(&legacy).unwrap(),
+    )
+    .unwrap();
+
+    let fixture = Fixture::load(fixture_dir.path()).unwrap();
+    assert_eq!(fixture.version, FIXTURE_VERSION);
+    assert_eq!(fixture.steps[0].index, 0);
+
+    let db_path = get_test_db_path();
+    let runtime = Runtime::with_db(&db_path, false, Duration::from_secs(60))
+        .unwrap()
+        .with_execution_context(ExecutionContext::Replay(fixture_dir.path().to_path_buf()));
+    let plan = json!({"id": "legacy", "steps": [{"tool": "mock", "input": {}}]});
+    let result = runtime.run_plan(plan).await.unwrap();
+    assert_eq!(result.steps[0].status, StepStatus::Replayed);
+    assert_eq!(result.steps[0].output, Some(json!({"recorded": true})));
+
+    // Upgrading rewrites the file once
+    assert_eq!(Fixture::upgrade(fixture_dir.path()).unwrap(), Some(0));
+    assert_eq!(Fixture::upgrade(fixture_dir.path()).unwrap(), None);
+    let _ = std::fs::remove_file(&db_path);
+}
+
+#[tokio::test]
+async fn test_edited_fixture_replays_errors_and_scrubbed_outputs() {
+    let fixture_dir = tempfile::tempdir().unwrap();
+    let mut fixture = Fixture::new("edited".to_string());
+    for (index, output) in [json!({"log": "x".repeat(10_000), "code": 0}), json!("ok")]
+        .into_iter()
+        .enumerate()
+    {
+        fixture.steps.push(StepRecord {
+            index,
+            tool: "mock".to_string(),
+            input: json!({"n": index}),
+            output,
+            duration_ms: 1,
+            error: None,
+        });
+    }
+    let stats = fixture.scrub(1024);
+    assert_eq!((stats.values, stats.bytes), (1, 10_000));
+    assert_eq!(
+        fixture.steps[0].output,
+        json!({"log": "<scrubbed 10000 bytes>", "code": 0})
+    );
+    fixture.step_mut(1).unwrap().error = Some("connection refused".to_string());
+    assert!(fixture.step_mut(7).is_err());
+    fixture.save(fixture_dir.path()).unwrap();
+
+    let db_path = get_test_db_path();
+    let runtime = Runtime::with_db(&db_path, false, Duration::from_secs(60))
+        .unwrap()
+        .with_execution_context(ExecutionContext::Replay(fixture_dir.path().to_path_buf()));
+    let plan = json!({"id": "edited", "steps": [
+        {"id": "a", "tool": "mock", "input": {"n": 0}},
+        {"id": "b", "tool": "mock", "input": {"n": 1}, "depends_on": ["a"]}
+    ]});
+    let err = runtime.run_plan(plan).await.unwrap_err();
+    let message = format!("{:#}", err);
+    assert!(
+        message.contains("Tool 'mock' failed (step 'b')") && message.contains("connection refused"),
+        "{}",
+        message
+    );
+
+    // Stand-in tools fail the same way
+    let tools = FixtureTool::from_fixture(&Fixture::load(fixture_dir.path()).unwrap());
+    let err = tools[0].execute(json!({"n": 1})).await.unwrap_err();
+    assert_eq!(err.to_string(), "connection refused");
+    let _ = std::fs::remove_file(&db_path);
+}
+
+#[test]
+fn test_newer_fixture_loads_known_fields() {
+    let fixture = Fixture::from_json(json!({
+        "version": FIXTURE_VERSION + 1,
+        "plan_id": "future",
+        "recorded_at": "0s",
+        "checksum": "abc",
+        "steps": [{"index": 0, "tool": "mock", "input": {}, "output": 1, "duration_ms": 1, "attempts": 2}]
+    }))
+    .unwrap();
+    assert_eq!(fixture.steps.len(), 1);
+
+    let err = Fixture::from_json(json!({"version": FIXTURE_VERSION + 1, "steps": "changed"}))
+        .unwrap_err();
+    assert!(
+        format!("{:#}", err).contains("newer than supported"),
+        "{:#}",
+        err
+    );
+}
+
 // Phase 7: Parallel independent steps
 #[tokio::test]
 async fn test_parallel_independent_steps() {
@@ -239,6 +392,526 @@ async fn test_parallel_independent_steps() {
     let _ = std::fs::remove_file(&db_path);
 }
 
+/// Tracks the highest number of concurrent executions
+struct ConcurrencyTool {
+    active: AtomicU32,
+    peak: AtomicU32,
+}
+
+#[async_trait]
+impl Tool for ConcurrencyTool {
+    async fn execute(&self, _input: Value) -> Result<Value> {
+        let now = self.active.fetch_add(1, Ordering::SeqCst) + 1;
+        self.peak.fetch_max(now, Ordering::SeqCst);
+        tokio::time::sleep(Duration::from_millis(20)).await;
+        self.active.fetch_sub(1, Ordering::SeqCst);
+        Ok(json!({}))
+    }
+
+    fn name(&self) -> &str {
+        "slow"
+    }
+}
+
+#[tokio::test]
+async fn test_resource_class_limits_concurrency() {
+    let db_path = get_test_db_path();
+    let runtime = Runtime::with_db(&db_path, false, Duration::from_secs(60))
+        .unwrap()
+        .with_max_parallel(8)
+        .with_resource_limit("slow", 2)
+        .with_resource_limit("network", 1);
+
+    let slow = Arc::new(ConcurrencyTool {
+        active: AtomicU32::new(0),
+        peak: AtomicU32::new(0),
+    });
+    runtime
+        .register_tool("slow".to_string(), slow.clone())
+        .unwrap();
+    let tagged = Arc::new(ConcurrencyTool {
+        active: AtomicU32::new(0),
+        peak: AtomicU32::new(0),
+    });
+    runtime
+        .register_tool("fetch".to_string(), tagged.clone())
+        .unwrap();
+
+    // Classes default to the tool name; `resource_class` overrides it
+    let mut steps: Vec<Value> = (0..6)
+        .map(|i| json!({"id": format!("s{}", i), "tool": "slow", "input": {}, "depends_on": []}))
+        .collect();
+    steps.extend((0..3).map(|i| {
+        json!({"id": format!("f{}", i), "tool": "fetch", "resource_class": "network", "input": {}})
+    }));
+    steps.push(json!({"id": "last", "tool": "slow", "input": {}, "depends_on": ["s0"]}));
+
+    runtime
+        .run_plan(json!({"id": "test-classes", "steps": steps}))
+        .await
+        .unwrap();
+    assert_eq!(slow.peak.load(Ordering::SeqCst), 2);
+    assert_eq!(tagged.peak.load(Ordering::SeqCst), 1);
+
+    let _ = std::fs::remove_file(&db_path);
+}
+
+#[tokio::test]
+async fn test_plan_result_collects_step_outputs() {
+    let db_path = get_test_db_path();
+    let runtime = Runtime::with_db(&db_path, false, Duration::from_secs(60)).unwrap();
+    runtime
+        .register_tool("mock".to_string(), Arc::new(MockTool::new("mock")))
+        .unwrap();
+
+    let plan = json!({
+        "id": "test-result",
+        "steps": [
+            {"id": "b", "tool": "mock", "input": {"n": 2}, "depends_on": ["a"]},
+            {"id": "a", "tool": "mock", "input": {"n": 1}, "depends_on": []},
+            {"id": "c", "tool": "mock", "input": {"n": 3}, "depends_on": ["b"], "force_dry_run": true}
+        ]
+    });
+    let result = runtime.run_plan(plan).await.unwrap();
+
+    assert_eq!(result.plan_id, "test-result");
+    // Plan order, not execution order
+    let ids: Vec<_> = result.steps.iter().map(|s| s.id.as_str()).collect();
+    assert_eq!(ids, ["b", "a", "c"]);
+    assert_eq!(result.step("a").unwrap().status, StepStatus::Completed);
+    assert_eq!(
+        result.step("a").unwrap().output.as_ref().unwrap()["input"]["n"],
+        1
+    );
+    assert_eq!(result.step("c").unwrap().status, StepStatus::Skipped);
+    assert!(result.step("c").unwrap().output.is_none());
+    assert_eq!(result.count(StepStatus::Completed), 2);
+    // Final output comes from the last step that ran, "b"
+    assert_eq!(result.output.as_ref().unwrap()["input"]["n"], 2);
+
+    let _ = std::fs::remove_file(&db_path);
+}
+
+#[tokio::test]
+async fn test_step_inputs_use_earlier_outputs() {
+    let db_path = get_test_db_path();
+    let runtime = Runtime::with_db(&db_path, false, Duration::from_secs(60)).unwrap();
+    runtime
+        .register_tool("mock".to_string(), Arc::new(MockTool::new("mock")))
+        .unwrap();
+
+    let plan = json!({
+        "id": "test-outputs",
+        "steps": [
+            {"id": "a", "tool": "mock", "input": {"text": "hello", "items": [1, 2]}, "depends_on": []},
+            {"id": "b", "tool": "mock", "depends_on": ["a"], "input": {
+                "copy": "{{steps.a.output.input}}",
+                "second": "{{ steps.a.output.input.items.1 }}",
+                "message": "{{steps.a.output.input.text}} x{{steps.a.output.input.items.1}}",
+                "other": "{{name}}"
+            }},
+            {"id": "c", "tool": "mock", "depends_on": ["b"], "input": {"first": "{{steps.a.output.input.items.0}}"}}
+        ]
+    });
+    let result = runtime.run_plan(plan).await.unwrap();
+    let b = &result.step("b").unwrap().output.as_ref().unwrap()["input"];
+    assert_eq!(b["copy"], json!({"text": "hello", "items": [1, 2]}));
+    assert_eq!(b["second"], 2);
+    assert_eq!(b["message"], "hello x2");
+    // Other placeholders are left alone
+    assert_eq!(b["other"], "{{name}}");
+    // Transitive dependencies count
+    assert_eq!(
+        result.step("c").unwrap().output.as_ref().unwrap()["input"]["first"],
+        1
+    );
+
+    // Sequential plans may use any earlier step
+    let plan = json!({
+        "steps": [
+            {"id": "a", "tool": "mock", "input": {"n": 1}},
+            {"id": "b", "tool": "mock", "input": {"n": "{{steps.a.output.input.n}}"}}
+        ]
+    });
+    let result = runtime.run_plan(plan).await.unwrap();
+    assert_eq!(result.output.unwrap()["input"]["n"], 1);
+
+    let err = |plan: Value| {
+        let runtime = &runtime;
+        async move { format!("{:#}", runtime.run_plan(plan).await.unwrap_err()) }
+    };
+    let unordered = json!({"steps": [
+        {"id": "a", "tool": "mock", "depends_on": []},
+        {"id": "b", "tool": "mock", "depends_on": []},
+        {"id": "c", "tool": "mock", "depends_on": ["a"], "input": {"x": "{{steps.b.output}}"}}
+    ]});
+    assert!(err(unordered.clone())
+        .await
+        .contains("uses the output of 'b' but does not run after it (add it to depends_on)"));
+    assert!(runtime.plan_schedule(&unordered).is_err());
+    let later = json!({"steps": [
+        {"id": "a", "tool": "mock", "input": {"x": "{{steps.b.output}}"}},
+        {"id": "b", "tool": "mock"}
+    ]});
+    assert!(err(later).await.contains("does not run after it"));
+    let skipped = json!({"steps": [
+        {"id": "a", "tool": "mock", "force_dry_run": true},
+        {"id": "b", "tool": "mock", "input": {"x": "{{steps.a.output}}"}}
+    ]});
+    assert!(err(skipped)
+        .await
+        .contains("uses the output of 'a', which did not run"));
+    let missing_key = json!({"steps": [
+        {"id": "a", "tool": "mock"},
+        {"id": "b", "tool": "mock", "input": {"x": "{{steps.a.output.nope}}"}}
+    ]});
+    assert!(err(missing_key)
+        .await
+        .contains("Output of step 'a' has no 'nope'"));
+    let malformed = json!({"steps": [{"id": "a", "tool": "mock", "input": "{{steps.a.stdout}}"}]});
+    assert!(err(malformed)
+        .await
+        .contains("expected steps.<id>.output.<path>"));
+
+    let _ = std::fs::remove_file(&db_path);
+}
+
+/// Fails until its `succeed_on`-th call
+struct FlakyTool {
+    calls: AtomicU32,
+}
+
+#[async_trait]
+impl Tool for FlakyTool {
+    async fn execute(&self, input: Value) -> Result<Value> {
+        let n = self.calls.fetch_add(1, Ordering::SeqCst) + 1;
+        if u64::from(n) < input["succeed_on"].as_u64().unwrap_or(u64::MAX) {
+            anyhow::bail!("flaky failure {}", n);
+        }
+        Ok(json!({"call": n}))
+    }
+
+    fn name(&self) -> &str {
+        "flaky"
+    }
+}
+
+#[tokio::test]
+async fn test_step_retry_and_when() {
+    let db_path = get_test_db_path();
+    let runtime = Runtime::with_db(&db_path, false, Duration::from_secs(60)).unwrap();
+    runtime
+        .register_tool("mock".to_string(), Arc::new(MockTool::new("mock")))
+        .unwrap();
+    let flaky = Arc::new(FlakyTool {
+        calls: AtomicU32::new(0),
+    });
+    runtime
+        .register_tool("flaky".to_string(), flaky.clone())
+        .unwrap();
+
+    // Third attempt succeeds within two retries
+    let plan = json!({"steps": [{
+        "id": "a", "tool": "flaky", "input": {"succeed_on": 3},
+        "retry": {"max": 2, "backoff_ms": 1}
+    }]});
+    let result = runtime.run_plan(plan).await.unwrap();
+    assert_eq!(result.output.unwrap()["call"], 3);
+
+    flaky.calls.store(0, Ordering::SeqCst);
+    let plan = json!({"steps": [{
+        "id": "a", "tool": "flaky", "input": {"succeed_on": 5},
+        "retry": {"max": 1, "backoff_ms": 1}, "depends_on": []
+    }]});
+    let err = format!("{:#}", runtime.run_plan(plan).await.unwrap_err());
+    assert!(err.contains("Step 'a' failed after 2 attempts"), "{}", err);
+    assert_eq!(flaky.calls.load(Ordering::SeqCst), 2);
+
+    let plan = json!({"steps": [
+        {"id": "a", "tool": "mock", "input": {"count": 2, "mode": "fast"}, "depends_on": []},
+        {"id": "big", "tool": "mock", "depends_on": ["a"], "when": "steps.a.output.input.count > 1"},
+        {"id": "slow", "tool": "mock", "depends_on": ["a"], "when": "steps.a.output.input.mode == \"slow\""},
+        {"id": "fast", "tool": "mock", "depends_on": ["a"], "when": "steps.a.output.input.mode"},
+        {"id": "none", "tool": "mock", "depends_on": ["a"], "when": "!steps.a.output.input.missing"}
+    ]});
+    let result = runtime.run_plan(plan).await.unwrap();
+    let status = |id: &str| result.step(id).unwrap().status;
+    assert_eq!(status("big"), StepStatus::Completed);
+    assert_eq!(status("slow"), StepStatus::Skipped);
+    assert_eq!(status("fast"), StepStatus::Completed);
+    assert_eq!(status("none"), StepStatus::Completed);
+
+    // Sequential plans skip the same way
+    let plan = json!({"steps": [
+        {"id": "a", "tool": "mock", "input": {"ok": false}},
+        {"id": "b", "tool": "mock", "when": "steps.a.output.input.ok"}
+    ]});
+    let result = runtime.run_plan(plan).await.unwrap();
+    assert_eq!(result.step("b").unwrap().status, StepStatus::Skipped);
+
+    let invalid = json!({"steps": [{"id": "a", "tool": "mock", "when": "steps.a.stdout"}]});
+    let err = format!("{:#}", runtime.run_plan(invalid).await.unwrap_err());
+    assert!(err.contains("Invalid 'when' for step 'a'"), "{}", err);
+    let unordered = json!({"steps": [
+        {"id": "a", "tool": "mock", "depends_on": []},
+        {"id": "b", "tool": "mock", "depends_on": []},
+        {"id": "c", "tool": "mock", "depends_on": ["a"], "when": "steps.b.output"}
+    ]});
+    let err = format!("{:#}", runtime.run_plan(unordered).await.unwrap_err());
+    assert!(err.contains("uses the output of 'b' but does not run after it"));
+
+    let _ = std::fs::remove_file(&db_path);
+}
+
+#[tokio::test]
+async fn test_on_error_policies() {
+    let db_path = get_test_db_path();
+    let runtime = Runtime::with_db(&db_path, false, Duration::from_secs(60)).unwrap();
+    runtime
+        .register_tool("mock".to_string(), Arc::new(MockTool::new("mock")))
+        .unwrap();
+    runtime
+        .register_tool(
+            "flaky".to_string(),
+            Arc::new(FlakyTool {
+                calls: AtomicU32::new(0),
+            }),
+        )
+        .unwrap();
+
+    let plan = json!({
+        "on_error": "continue",
+        "steps": [
+            {"id": "a", "tool": "flaky", "depends_on": [], "on_error": "skip_dependents"},
+            {"id": "b", "tool": "mock", "depends_on": ["a"]},
+            {"id": "c", "tool": "mock", "depends_on": ["b"]},
+            {"id": "d", "tool": "flaky", "depends_on": []},
+            {"id": "e", "tool": "mock", "depends_on": ["d"]},
+            {"id": "cleanup", "tool": "mock", "depends_on": ["c", "e"]}
+        ]
+    });
+    let result = runtime.run_plan(plan).await.unwrap();
+    assert_eq!(result.failed, ["a", "d"]);
+    let a = result.step("a").unwrap();
+    assert_eq!(a.status, StepStatus::Failed);
+    assert!(a.error.as_ref().unwrap().contains("flaky failure"));
+    assert_eq!(result.step("b").unwrap().status, StepStatus::Skipped);
+    assert_eq!(result.step("c").unwrap().status, StepStatus::Skipped);
+    assert_eq!(result.step("e").unwrap().status, StepStatus::Completed);
+    // Blocked through c
+    assert_eq!(result.step("cleanup").unwrap().status, StepStatus::Skipped);
+
+    let plan = json!({"on_error": "continue", "steps": [
+        {"id": "a", "tool": "flaky"},
+        {"id": "b", "tool": "mock"}
+    ]});
+    let result = runtime.run_plan(plan).await.unwrap();
+    assert_eq!(result.failed, ["a"]);
+    assert_eq!(result.step("b").unwrap().status, StepStatus::Completed);
+
+    // Abort is the default
+    let plan = json!({"steps": [{"id": "a", "tool": "flaky"}, {"id": "b", "tool": "mock"}]});
+    assert!(runtime.run_plan(plan).await.is_err());
+
+    let err = |plan: Value| {
+        let runtime = &runtime;
+        async move { format!("{:#}", runtime.run_plan(plan).await.unwrap_err()) }
+    };
+    let uses_failed = json!({"steps": [
+        {"id": "a", "tool": "flaky", "on_error": "continue"},
+        {"id": "b", "tool": "mock", "input": {"x": "{{steps.a.output}}"}}
+    ]});
+    assert!(err(uses_failed)
+        .await
+        .contains("uses the output of 'a', which did not run"));
+    let invalid = json!({"on_error": "retry", "steps": [{"tool": "mock"}]});
+    assert!(err(invalid).await.contains("Invalid plan 'on_error'"));
+
+    let _ = std::fs::remove_file(&db_path);
+}
+
+#[tokio::test]
+async fn test_resume_from_checkpoint() {
+    let db_path = get_test_db_path();
+    let runtime = Runtime::with_db(&db_path, false, Duration::from_secs(60)).unwrap();
+    let counting = Arc::new(CountingTool {
+        calls: AtomicU32::new(0),
+    });
+    runtime
+        .register_tool("counting".to_string(), counting.clone())
+        .unwrap();
+    runtime
+        .register_tool(
+            "flaky".to_string(),
+            Arc::new(FlakyTool {
+                calls: AtomicU32::new(0),
+            }),
+        )
+        .unwrap();
+
+    let plan = json!({"id": "long", "steps": [
+        {"id": "a", "tool": "counting"},
+        {"id": "b", "tool": "flaky", "input": {"succeed_on": 2}},
+        {"id": "c", "tool": "counting", "input": {"from_a": "{{steps.a.output.call}}"}}
+    ]});
+    assert!(runtime.run_plan(plan.clone()).await.is_err());
+    assert_eq!(counting.calls.load(Ordering::SeqCst), 1);
+
+    let stored = operon_runtime::checkpoint::load_plan(runtime.storage(), "long")
+        .await
+        .unwrap();
+    assert_eq!(stored, plan);
+    let result = runtime.resume_plan(stored).await.unwrap();
+    assert_eq!(result.step("a").unwrap().status, StepStatus::Resumed);
+    assert_eq!(result.step("b").unwrap().status, StepStatus::Completed);
+    // Resumed outputs feed later steps
+    assert_eq!(result.output.unwrap()["input"]["from_a"], 1);
+    assert_eq!(counting.calls.load(Ordering::SeqCst), 2);
+
+    // A fresh run starts over and drops the old markers
+    let result = runtime.run_plan(plan.clone()).await.unwrap();
+    assert_eq!(result.count(StepStatus::Completed), 3);
+    let mut failing = plan.clone();
+    failing["steps"][1]["input"]["succeed_on"] = json!(100);
+    assert!(runtime.run_plan(failing).await.is_err());
+    let result = runtime.resume_plan(plan).await.unwrap();
+    assert_eq!(result.step("a").unwrap().status, StepStatus::Resumed);
+    assert_eq!(result.step("c").unwrap().status, StepStatus::Completed);
+
+    let err = runtime
+        .resume_plan(json!({"steps": [{"tool": "counting"}]}))
+        .await
+        .unwrap_err();
+    assert!(err
+        .to_string()
+        .contains("Only plans with an 'id' can resume"));
+    assert!(
+        operon_runtime::checkpoint::load_plan(runtime.storage(), "other")
+            .await
+            .is_err()
+    );
+
+    let _ = std::fs::remove_file(&db_path);
+}
+
+#[tokio::test]
+async fn test_foreach_steps() {
+    let db_path = get_test_db_path();
+    let runtime = Runtime::with_db(&db_path, false, Duration::from_secs(60))
+        .unwrap()
+        .with_max_parallel(3);
+    runtime
+        .register_tool("mock".to_string(), Arc::new(MockTool::new("mock")))
+        .unwrap();
+    let slow = Arc::new(ConcurrencyTool {
+        active: AtomicU32::new(0),
+        peak: AtomicU32::new(0),
+    });
+    runtime
+        .register_tool("slow".to_string(), slow.clone())
+        .unwrap();
+
+    let plan = json!({"steps": [
+        {"id": "list", "tool": "mock", "depends_on": [],
+         "input": {"files": [{"path": "a"}, {"path": "b"}, {"path": "c"}]}},
+        {"id": "read", "tool": "mock", "depends_on": ["list"],
+         "foreach": "steps.list.output.input.files",
+         "input": {"path": "{{item.path}}", "label": "file {{item.path}}"}},
+        {"id": "use", "tool": "mock", "depends_on": ["read"],
+         "input": {"second": "{{steps.read.output.1.input.path}}"}},
+        {"id": "wide", "tool": "slow", "depends_on": [], "foreach": [1, 2, 3, 4, 5, 6, 7]}
+    ]});
+    let result = runtime.run_plan(plan).await.unwrap();
+    let read = result.step("read").unwrap().output.as_ref().unwrap();
+    assert_eq!(read.as_array().unwrap().len(), 3);
+    assert_eq!(read[0]["input"], json!({"path": "a", "label": "file a"}));
+    assert_eq!(
+        result.step("use").unwrap().output.as_ref().unwrap()["input"]["second"],
+        "b"
+    );
+    assert_eq!(
+        result
+            .step("wide")
+            .unwrap()
+            .output
+            .as_ref()
+            .unwrap()
+            .as_array()
+            .unwrap()
+            .len(),
+        7
+    );
+    // Items share max_parallel
+    assert_eq!(slow.peak.load(Ordering::SeqCst), 3);
+
+    let plan = json!({"steps": [{"id": "x", "tool": "mock", "foreach": ["p", "q"], "input": {"v": "{{item}}"}}]});
+    let result = runtime.run_plan(plan).await.unwrap();
+    assert_eq!(
+        result.output.unwrap(),
+        json!([
+            {"tool": "mock", "input": {"v": "p"}},
+            {"tool": "mock", "input": {"v": "q"}}
+        ])
+    );
+
+    let err = |plan: Value| {
+        let runtime = &runtime;
+        async move { format!("{:#}", runtime.run_plan(plan).await.unwrap_err()) }
+    };
+    let not_array = json!({"steps": [
+        {"id": "a", "tool": "mock", "input": {"n": 1}},
+        {"id": "b", "tool": "mock", "foreach": "steps.a.output.input.n"}
+    ]});
+    assert!(err(not_array)
+        .await
+        .contains("steps.a.output.input.n is not an array"));
+    let number = json!({"steps": [{"id": "a", "tool": "mock", "foreach": 5}]});
+    assert!(err(number).await.contains("neither an array"));
+    let missing_key = json!({"steps": [{"id": "a", "tool": "mock", "foreach": [1], "input": {"x": "{{item.path}}"}}]});
+    assert!(err(missing_key).await.contains("'item' has no 'path'"));
+    let b_first = json!({"steps": [
+        {"id": "b", "tool": "mock", "foreach": "steps.a.output"},
+        {"id": "a", "tool": "mock"}
+    ]});
+    assert!(err(b_first).await.contains("does not run after it"));
+
+    let _ = std::fs::remove_file(&db_path);
+}
+
+#[tokio::test]
+async fn test_plan_schedule() {
+    let db_path = get_test_db_path();
+    let runtime = Runtime::with_db(&db_path, true, Duration::from_secs(60))
+        .unwrap()
+        .with_max_parallel(2);
+
+    let plan = json!({
+        "id": "test-schedule",
+        "steps": [
+            {"id": "a", "tool": "mock", "depends_on": []},
+            {"id": "b", "tool": "mock", "depends_on": []},
+            {"id": "c", "tool": "mock", "depends_on": []},
+            {"id": "d", "tool": "mock", "depends_on": ["a", "b", "c"]}
+        ]
+    });
+    let schedule = runtime.plan_schedule(&plan).unwrap();
+    assert_eq!(schedule.levels, vec![vec!["a", "b", "c"], vec!["d"]]);
+    assert_eq!(schedule.order, ["a", "b", "c", "d"]);
+    assert!(!schedule.sequential);
+    // Widest level is capped by max_parallel
+    assert_eq!(schedule.max_concurrency, 2);
+    assert_eq!(schedule.estimated_parallelism, 2.0);
+
+    let sequential = runtime
+        .plan_schedule(&json!({"steps": [{"tool": "mock"}, {"tool": "mock"}]}))
+        .unwrap();
+    assert!(sequential.sequential);
+    assert_eq!(sequential.levels, vec![vec!["step_0"], vec!["step_1"]]);
+    assert_eq!(sequential.max_concurrency, 1);
+
+    let _ = std::fs::remove_file(&db_path);
+}
+
 // Phase 7: Cycle detection
 #[tokio::test]
 async fn test_cycle_detection() {
@@ -305,3 +978,1119 @@ async fn test_sequential_backward_compat() {
 
     let _ = std::fs::remove_file(&db_path);
 }
+
+#[tokio::test]
+async fn test_execute_tool_as_enforces_caller_permission() {
+    let db_path = get_test_db_path();
+    let runtime = Runtime::with_db(&db_path, false, Duration::from_secs(60)).unwrap();
+    runtime
+        .register_tool("mock".to_string(), Arc::new(MockTool::new("mock")))
+        .unwrap();
+
+    let pipeline = ToolPolicyPipeline::new().add_layer(Box::new(PermissionCheckLayer::new(
+        runtime.tool_permissions(),
+        PermissionLevel::Read,
+    )));
+    let runtime = runtime.with_policy(pipeline);
+
+    // MockTool uses the default Execute permission
+    assert_eq!(
+        runtime.tool_permission("mock"),
+        Some(PermissionLevel::Execute)
+    );
+
+    let denied = runtime
+        .execute_tool_as("mock", json!({}), PermissionLevel::Write)
+        .await;
+    assert!(denied
+        .unwrap_err()
+        .to_string()
+        .contains("insufficient permission"));
+
+    let allowed = runtime
+        .execute_tool_as("mock", json!({}), PermissionLevel::Admin)
+        .await;
+    assert!(allowed.is_ok());
+
+    let _ = std::fs::remove_file(&db_path);
+}
+
+/// Approves calls whose input says so, remembering what it was asked
+struct InputApprover {
+    asked: std::sync::Mutex<Vec<ApprovalRequest>>,
+}
+
+#[async_trait]
+impl Approver for InputApprover {
+    async fn approve(&self, request: &ApprovalRequest) -> Result<bool> {
+        self.asked.lock().unwrap().push(request.clone());
+        Ok(request.input["approve"] == true)
+    }
+}
+
+#[tokio::test]
+async fn test_tool_calls_held_for_approval() {
+    let db_path = get_test_db_path();
+    let pipeline =
+        || ToolPolicyPipeline::new().add_layer(Box::new(ApprovalLayer::new(vec!["mock".into()])));
+
+    // Nobody to ask: held calls are denied
+    let runtime = Runtime::with_db(&db_path, false, Duration::from_secs(60))
+        .unwrap()
+        .with_policy(pipeline());
+    runtime
+        .register_tool("mock".to_string(), Arc::new(MockTool::new("mock")))
+        .unwrap();
+    let err = runtime.execute_tool("mock", json!({})).await.unwrap_err();
+    assert!(err.to_string().contains("needs approval (mock: {})"));
+    drop(runtime);
+
+    let approver = Arc::new(InputApprover {
+        asked: std::sync::Mutex::new(Vec::new()),
+    });
+    let runtime = Runtime::with_db(&db_path, false, Duration::from_secs(60))
+        .unwrap()
+        .with_policy(pipeline())
+        .with_approver(approver.clone());
+    runtime
+        .register_tool("mock".to_string(), Arc::new(MockTool::new("mock")))
+        .unwrap();
+    runtime
+        .register_tool("other".to_string(), Arc::new(MockTool::new("other")))
+        .unwrap();
+
+    let output = runtime
+        .execute_tool_in_session(
+            "mock",
+            json!({"approve": true}),
+            PermissionLevel::Execute,
+            "s1",
+        )
+        .await
+        .unwrap();
+    assert_eq!(output["input"]["approve"], true);
+    let err = runtime
+        .execute_tool("mock", json!({"approve": false}))
+        .await
+        .unwrap_err();
+    assert!(err.to_string().contains("was not approved"));
+    // Tools the layer does not list run without asking
+    runtime.execute_tool("other", json!({})).await.unwrap();
+
+    let asked = approver.asked.lock().unwrap();
+    assert_eq!(asked.len(), 2);
+    assert_eq!(asked[0].tool_name, "mock");
+    assert_eq!(asked[0].session_id.as_deref(), Some("s1"));
+    assert_eq!(asked[0].description, "mock: {\"approve\":true}");
+    assert_ne!(asked[0].call_id, asked[1].call_id);
+
+    let _ = std::fs::remove_file(&db_path);
+}
+
+#[tokio::test]
+async fn test_policy_redaction_scrubs_secrets() {
+    let db_path = get_test_db_path();
+    let approver = Arc::new(InputApprover {
+        asked: std::sync::Mutex::new(Vec::new()),
+    });
+    let runtime = Runtime::with_db(&db_path, false, Duration::from_secs(60))
+        .unwrap()
+        .with_policy(
+            ToolPolicyPipeline::new()
+                .add_layer(Box::new(RedactionLayer::new(&Default::default()).unwrap()))
+                .add_layer(Box::new(ApprovalLayer::new(vec!["mock".into()]))),
+        )
+        .with_approver(approver.clone());
+    runtime
+        .register_tool("mock".to_string(), Arc::new(MockTool::new("mock")))
+        .unwrap();
+
+    let key = "sk-abcdefghijklmnopqrstuvwx";
+    let output = runtime
+        .execute_tool(
+            "mock",
+            json!({"approve": true, "command": format!("export KEY={}", key)}),
+        )
+        .await
+        .unwrap();
+    // The tool echoes the key back; the caller gets the output without it
+    assert_eq!(output["input"]["command"], "export KEY=[REDACTED:token]");
+
+    let asked = approver.asked.lock().unwrap();
+    assert_eq!(asked[0].input["command"], "export KEY=[REDACTED:token]");
+    assert_eq!(asked[0].description, "mock: export KEY=[REDACTED:token]");
+
+    let _ = std::fs::remove_file(&db_path);
+}
+
+#[tokio::test]
+async fn test_audit_trail_records_decisions_and_outcomes() {
+    let dir = tempfile::tempdir().unwrap();
+    let db_path = dir.path().join("audit.db");
+    let approver = Arc::new(InputApprover {
+        asked: std::sync::Mutex::new(Vec::new()),
+    });
+    let runtime = Runtime::with_db(db_path.to_str().unwrap(), false, Duration::from_secs(60))
+        .unwrap()
+        .with_policy(
+            ToolPolicyPipeline::new()
+                .add_layer(Box::new(ToolExistenceLayer::new(vec!["mock".into()])))
+                .add_layer(Box::new(ApprovalLayer::new(vec!["mock".into()]))),
+        )
+        .with_approver(approver)
+        .with_audit_trail();
+    runtime
+        .register_tool("mock".to_string(), Arc::new(MockTool::new("mock")))
+        .unwrap();
+
+    runtime
+        .execute_tool_in_session(
+            "mock",
+            json!({"approve": true}),
+            PermissionLevel::Execute,
+            "s1",
+        )
+        .await
+        .unwrap();
+    runtime
+        .execute_tool("mock", json!({"approve": false}))
+        .await
+        .unwrap_err();
+    runtime
+        .execute_tool("missing", json!({}))
+        .await
+        .unwrap_err();
+
+    let storage = runtime.storage();
+    let records = storage.audit_records(&AuditFilter::default()).unwrap();
+    let decisions: Vec<_> = records.iter().map(|r| (r.id, r.decision)).collect();
+    assert_eq!(
+        decisions,
+        [
+            (3, AuditDecision::Denied),
+            (2, AuditDecision::NotApproved),
+            (1, AuditDecision::Approved),
+        ]
+    );
+    assert_eq!(records[0].outcome, AuditOutcome::Error);
+    assert!(records[0]
+        .reason
+        .as_deref()
+        .unwrap()
+        .contains("tool not found: missing"));
+    assert_eq!(records[2].outcome, AuditOutcome::Success);
+    assert_eq!(
+        records[2].input_hash,
+        operon_runtime::audit::input_hash(&json!({"approve": true}))
+    );
+
+    let in_session = AuditFilter {
+        session_id: Some("s1".into()),
+        ..Default::default()
+    };
+    assert_eq!(storage.audit_records(&in_session).unwrap().len(), 1);
+    let mock_calls = AuditFilter {
+        tool: Some("mock".into()),
+        limit: Some(1),
+        ..Default::default()
+    };
+    assert_eq!(storage.audit_records(&mock_calls).unwrap()[0].id, 2);
+    let later = AuditFilter {
+        since: Some(chrono::Utc::now()),
+        ..Default::default()
+    };
+    assert!(storage.audit_records(&later).unwrap().is_empty());
+    assert_eq!(storage.audit_record(2).unwrap().unwrap().tool, "mock");
+    assert!(storage.audit_record(9).unwrap().is_none());
+}
+
+#[tokio::test]
+async fn test_nested_plan_storage_modes() {
+    let db_path = get_test_db_path();
+    let runtime = Runtime::with_db(&db_path, false, Duration::from_secs(60)).unwrap();
+    runtime
+        .register_tool("mock".to_string(), Arc::new(MockTool::new("mock")))
+        .unwrap();
+
+    let child = json!({
+        "id": "child-plan",
+        "steps": [{"id": "child", "tool": "mock", "input": {"n": 7}}]
+    });
+    let plan = json!({
+        "id": "parent",
+        "steps": [
+            {"id": "shared", "tool": "plan", "input": {"plan": child}, "depends_on": []},
+            {"id": "sub", "tool": "plan", "input": {"plan": child, "storage": "isolated"}, "depends_on": []},
+            {"id": "after", "tool": "mock", "input": {"n": 8}, "depends_on": ["shared", "sub"]}
+        ]
+    });
+    let result = runtime.run_plan(plan).await.unwrap();
+    assert_eq!(result.count(StepStatus::Completed), 3);
+
+    // Step output is the child's PlanResult
+    let nested = result.step("sub").unwrap().output.as_ref().unwrap();
+    assert_eq!(nested["plan_id"], "child-plan");
+    assert_eq!(nested["output"]["input"]["n"], 7);
+
+    assert_eq!(
+        runtime.step_output("child").unwrap().unwrap()["input"]["n"],
+        7
+    );
+    assert_eq!(
+        runtime.step_output("sub/child").unwrap().unwrap()["input"]["n"],
+        7
+    );
+
+    let _ = std::fs::remove_file(&db_path);
+}
+
+#[tokio::test]
+async fn test_nested_plan_from_file_and_depth_limit() {
+    let db_path = get_test_db_path();
+    let runtime = Runtime::with_db(&db_path, false, Duration::from_secs(60))
+        .unwrap()
+        .with_nested_storage(NestedStorage::Isolated);
+    runtime
+        .register_tool("mock".to_string(), Arc::new(MockTool::new("mock")))
+        .unwrap();
+
+    let dir = tempfile::tempdir().unwrap();
+    let file = dir.path().join("child.json");
+    std::fs::write(
+        &file,
+        json!({"id": "from-file", "steps": [{"id": "x", "tool": "mock", "input": {}}]}).to_string(),
+    )
+    .unwrap();
+    let plan = json!({
+        "id": "parent",
+        "steps": [{"id": "inc", "tool": "plan", "input": {"file": file}}]
+    });
+    runtime.run_plan(plan).await.unwrap();
+    // Runtime default applies when the step sets no storage
+    assert!(runtime.step_output("inc/x").unwrap().is_some());
+    assert!(runtime.step_output("x").unwrap().is_none());
+
+    // A plan that includes itself stops at the depth limit
+    let looping = dir.path().join("loop.json");
+    std::fs::write(
+        &looping,
+        json!({"id": "loop", "steps": [{"id": "again", "tool": "plan", "input": {"file": looping}}]})
+            .to_string(),
+    )
+    .unwrap();
+    let err = runtime
+        .run_plan(json!({
+            "id": "parent",
+            "steps": [{"id": "start", "tool": "plan", "input": {"file": looping}}]
+        }))
+        .await
+        .unwrap_err();
+    assert!(format!("{:#}", err).contains("max depth"));
+
+    let _ = std::fs::remove_file(&db_path);
+}
+
+#[tokio::test]
+async fn test_nested_plan_params_timeout_and_fixture() {
+    let db_path = get_test_db_path();
+    let dir = tempfile::tempdir().unwrap();
+    let fixture_dir = dir.path().join("fixture");
+    let runtime = Runtime::with_db(&db_path, false, Duration::from_secs(60))
+        .unwrap()
+        .with_execution_context(ExecutionContext::Record(fixture_dir.clone()));
+    runtime
+        .register_tool("mock".to_string(), Arc::new(MockTool::new("mock")))
+        .unwrap();
+    runtime
+        .register_tool(
+            "slow".to_string(),
+            Arc::new(ConcurrencyTool {
+                active: AtomicU32::new(0),
+                peak: AtomicU32::new(0),
+            }),
+        )
+        .unwrap();
+
+    let file = dir.path().join("deploy.json");
+    std::fs::write(
+        &file,
+        json!({
+            "id": "deploy",
+            "params": {"env": "staging", "replicas": 1},
+            "steps": [{"id": "x", "tool": "mock", "input": {
+                "env": "{{params.env}}",
+                "label": "deploy to {{params.env}}",
+                "replicas": "{{params.replicas}}"
+            }}]
+        })
+        .to_string(),
+    )
+    .unwrap();
+    let plan = json!({"id": "parent", "steps": [
+        {"id": "prod", "tool": "plan", "input": {"file": file, "params": {"env": "prod"}}},
+        {"id": "default", "tool": "plan", "input": {"file": file}}
+    ]});
+    let result = runtime.run_plan(plan).await.unwrap();
+    let child_input =
+        |id: &str| result.step(id).unwrap().output.as_ref().unwrap()["output"]["input"].clone();
+    assert_eq!(
+        child_input("prod"),
+        json!({"env": "prod", "label": "deploy to prod", "replicas": 1})
+    );
+    assert_eq!(child_input("default")["env"], "staging");
+
+    // Each nested plan records its own fixture next to the parent's
+    assert_eq!(Fixture::load(&fixture_dir).unwrap().steps.len(), 2);
+    let child = Fixture::load(&fixture_dir.join("prod")).unwrap();
+    assert_eq!(child.plan_id, "deploy");
+    assert_eq!(child.steps[0].input["env"], "prod");
+
+    let err = |plan: Value| {
+        let runtime = &runtime;
+        async move { format!("{:#}", runtime.run_plan(plan).await.unwrap_err()) }
+    };
+    let slow = json!({"steps": [{"id": "wait", "tool": "plan", "input": {
+        "plan": {"steps": [{"tool": "slow"}]},
+        "timeout_secs": 0
+    }}]});
+    assert!(err(slow)
+        .await
+        .contains("Nested plan timed out after 0.0s (step 'wait')"));
+    let missing = json!({"steps": [{"id": "p", "tool": "plan", "input": {
+        "plan": {"steps": [{"tool": "mock", "input": {"x": "{{params.missing}}"}}]}
+    }}]});
+    assert!(err(missing).await.contains("'params' has no 'missing'"));
+
+    let _ = std::fs::remove_file(&db_path);
+}
+
+#[tokio::test]
+async fn test_composite_tool_registered_as_one_call() {
+    let db_path = get_test_db_path();
+    let runtime = Runtime::with_db(&db_path, false, Duration::from_secs(60)).unwrap();
+    runtime
+        .register_tool("mock".to_string(), Arc::new(MockTool::new("mock")))
+        .unwrap();
+
+    let spec: CompositeSpec = serde_json::from_value(json!({
+        "name": "mock_twice",
+        "steps": [
+            {"id": "one", "tool": "mock", "input": {"q": "$input.query"}},
+            {"id": "two", "tool": "mock", "input": {"from": "$steps.one.input.q"}}
+        ]
+    }))
+    .unwrap();
+    runtime.register_composite(spec).unwrap();
+    assert!(runtime.tool_names().contains(&"mock_twice".to_string()));
+
+    let output = runtime
+        .execute_tool("mock_twice", json!({"query": "hi"}))
+        .await
+        .unwrap();
+    assert_eq!(output, json!({"tool": "mock", "input": {"from": "hi"}}));
+
+    // Composites only chain tools that already exist
+    let missing: CompositeSpec =
+        serde_json::from_value(json!({"name": "bad", "steps": [{"tool": "nope"}]})).unwrap();
+    assert!(runtime.register_composite(missing).is_err());
+
+    // Each step is checked by the policy on its own: the composite may run,
+    // its "mock" steps still need approval
+    let guarded_db = get_test_db_path();
+    let guarded = Runtime::with_db(&guarded_db, false, Duration::from_secs(60))
+        .unwrap()
+        .with_policy(
+            ToolPolicyPipeline::new().add_layer(Box::new(ApprovalLayer::new(vec!["mock".into()]))),
+        );
+    guarded
+        .register_tool("mock".to_string(), Arc::new(MockTool::new("mock")))
+        .unwrap();
+    guarded
+        .register_composite(
+            serde_json::from_value(json!({"name": "mock_once", "steps": [{"tool": "mock"}]}))
+                .unwrap(),
+        )
+        .unwrap();
+    let err = guarded
+        .execute_tool("mock_once", json!({}))
+        .await
+        .unwrap_err();
+    assert!(format!("{:#}", err).contains("needs approval"), "{:#}", err);
+
+    // Plan steps with dependencies run the composite's steps the same way
+    let plan = json!({"id": "composite-dag", "steps": [
+        {"id": "first", "tool": "mock", "input": {}, "depends_on": []},
+        {"id": "then", "tool": "mock_once", "input": {}, "depends_on": ["first"]}
+    ]});
+    let err = guarded.run_plan(plan).await.unwrap_err();
+    assert!(format!("{:#}", err).contains("needs approval"), "{:#}", err);
+
+    let _ = std::fs::remove_file(&db_path);
+    let _ = std::fs::remove_file(&guarded_db);
+}
+
+#[tokio::test]
+async fn test_tool_aliases_and_renames() {
+    let db_path = get_test_db_path();
+    let runtime = Runtime::with_db(&db_path, false, Duration::from_secs(60)).unwrap();
+    runtime
+        .register_tool("mock".to_string(), Arc::new(MockTool::new("mock")))
+        .unwrap();
+    runtime.configure_timeout("mock".to_string(), Duration::from_secs(5));
+
+    runtime.alias_tool("bash", "mock").unwrap();
+    // Alias of an alias resolves to the tool itself
+    runtime.alias_tool("sh", "bash").unwrap();
+    assert_eq!(runtime.resolve_tool_name("sh"), "mock");
+    assert!(runtime.alias_tool("mock", "bash").is_err());
+    assert!(runtime.alias_tool("zsh", "missing").is_err());
+
+    let output = runtime.execute_tool("sh", json!({"n": 1})).await.unwrap();
+    assert_eq!(output["tool"], "mock");
+    assert_eq!(runtime.get_timeout("bash"), Duration::from_secs(5));
+    assert_eq!(
+        runtime.tool_permissions().get("bash"),
+        Some(&PermissionLevel::Execute)
+    );
+
+    runtime.rename_tool("mock", "renamed").unwrap();
+    let mut names = runtime.tool_names();
+    names.sort();
+    assert_eq!(names, ["bash", "renamed", "sh"]);
+    // Schemas carry the name the LLM should call
+    assert_eq!(runtime.tool_schema("sh").unwrap().name, "sh");
+    assert!(runtime.tool_schema("mock").is_none());
+    let mut schema_names: Vec<String> =
+        runtime.tool_schemas().into_iter().map(|s| s.name).collect();
+    schema_names.sort();
+    assert_eq!(schema_names, names);
+    assert!(runtime.execute_tool("mock", json!({})).await.is_err());
+    // Aliases and timeouts follow the rename
+    assert!(runtime.execute_tool("bash", json!({})).await.is_ok());
+    assert_eq!(runtime.get_timeout("renamed"), Duration::from_secs(5));
+
+    // Plans may use aliases as step tools
+    let result = runtime
+        .run_plan(json!({"id": "alias-plan", "steps": [{"id": "s", "tool": "bash", "input": {}}]}))
+        .await
+        .unwrap();
+    assert_eq!(result.count(StepStatus::Completed), 1);
+
+    let _ = std::fs::remove_file(&db_path);
+}
+
+#[tokio::test]
+async fn test_aliased_calls_checked_as_their_target() {
+    let db_path = get_test_db_path();
+    let deny_rule: PolicyRule = serde_json::from_value(json!({
+        "name": "no-force",
+        "action": "deny",
+        "tools": ["mock"],
+        "input": {"force": "true"}
+    }))
+    .unwrap();
+    let pipeline = ToolPolicyPipeline::new()
+        .add_layer(Box::new(RuleEngineLayer::new(vec![deny_rule]).unwrap()))
+        .add_layer(Box::new(RateLimitLayer::new(1)))
+        .add_layer(Box::new(ApprovalLayer::new(vec!["mock".into()])));
+    let approver = Arc::new(InputApprover {
+        asked: std::sync::Mutex::new(Vec::new()),
+    });
+    let runtime = Runtime::with_db(&db_path, false, Duration::from_secs(60))
+        .unwrap()
+        .with_policy(pipeline)
+        .with_approver(approver.clone());
+    runtime
+        .register_tool("mock".to_string(), Arc::new(MockTool::new("mock")))
+        .unwrap();
+    runtime.alias_tool("bash", "mock").unwrap();
+
+    // Deny rules written for the tool match its alias
+    let err = runtime
+        .execute_tool("bash", json!({"force": true}))
+        .await
+        .unwrap_err();
+    assert!(err.to_string().contains("no-force"));
+
+    // Approval lists hold the alias, and the prompt names the tool
+    runtime
+        .execute_tool("bash", json!({"approve": true}))
+        .await
+        .unwrap();
+    let asked = approver.asked.lock().unwrap().clone();
+    assert_eq!(asked.len(), 1);
+    assert_eq!(asked[0].tool_name, "mock");
+
+    // The alias and the tool draw from one rate-limit bucket
+    let err = runtime
+        .execute_tool("mock", json!({"approve": true}))
+        .await
+        .unwrap_err();
+    assert!(err.to_string().contains("rate limit exceeded"));
+
+    let _ = std::fs::remove_file(&db_path);
+}
+
+#[tokio::test]
+async fn test_policy_replaced_on_shared_runtime() {
+    let db_path = get_test_db_path();
+    let runtime = Arc::new(Runtime::with_db(&db_path, false, Duration::from_secs(60)).unwrap());
+    runtime
+        .register_tool("mock".to_string(), Arc::new(MockTool::new("mock")))
+        .unwrap();
+    runtime.execute_tool("mock", json!({})).await.unwrap();
+
+    // As a config reload does for `warden serve`
+    let deny_rule: PolicyRule = serde_json::from_value(json!({
+        "name": "no-mock",
+        "action": "deny",
+        "tools": ["mock"]
+    }))
+    .unwrap();
+    runtime.replace_policy(Some(
+        ToolPolicyPipeline::new()
+            .add_layer(Box::new(RuleEngineLayer::new(vec![deny_rule]).unwrap())),
+    ));
+    let err = runtime.execute_tool("mock", json!({})).await.unwrap_err();
+    assert!(err.to_string().contains("no-mock"));
+
+    runtime.replace_policy(None);
+    runtime.execute_tool("mock", json!({})).await.unwrap();
+
+    let _ = std::fs::remove_file(&db_path);
+}
+
+#[tokio::test]
+async fn test_register_and_unregister_between_calls() {
+    let db_path = get_test_db_path();
+    let runtime = Runtime::with_db(&db_path, false, Duration::from_secs(60)).unwrap();
+    runtime
+        .register_tool("mock".to_string(), Arc::new(MockTool::new("mock")))
+        .unwrap();
+    // Policy built from the tools known at startup, as `warden chat` does
+    let pipeline = ToolPolicyPipeline::new()
+        .add_layer(Box::new(ToolExistenceLayer::new(runtime.tool_names())))
+        .add_layer(Box::new(PermissionCheckLayer::new(
+            runtime.tool_permissions(),
+            PermissionLevel::Read,
+        )));
+    let runtime = runtime.with_policy(pipeline);
+
+    // A tool that appears mid-session is usable right away
+    runtime
+        .register_tool("late".to_string(), Arc::new(MockTool::new("late")))
+        .unwrap();
+    runtime.alias_tool("later", "late").unwrap();
+    let output = runtime.execute_tool("later", json!({})).await.unwrap();
+    assert_eq!(output["tool"], "late");
+
+    assert!(runtime.unregister_tool("later").is_err());
+    runtime.unregister_tool("late").unwrap();
+    assert!(!runtime.has_tool("late"));
+    assert!(!runtime.has_tool("later"));
+    assert!(runtime.execute_tool("late", json!({})).await.is_err());
+    assert!(runtime.unregister_tool("late").is_err());
+
+    let _ = std::fs::remove_file(&db_path);
+}
+
+/// Read-only tool that counts how often it actually runs
+struct CountingTool {
+    calls: AtomicU32,
+}
+
+#[async_trait]
+impl Tool for CountingTool {
+    async fn execute(&self, input: Value) -> Result<Value> {
+        let n = self.calls.fetch_add(1, Ordering::SeqCst) + 1;
+        Ok(json!({"call": n, "input": input}))
+    }
+
+    fn name(&self) -> &str {
+        "counting"
+    }
+
+    fn permission_level(&self) -> PermissionLevel {
+        PermissionLevel::Read
+    }
+}
+
+#[tokio::test]
+async fn test_tool_result_cache_ttl() {
+    let db_path = get_test_db_path();
+    let runtime = Runtime::with_db(&db_path, false, Duration::from_secs(60)).unwrap();
+    let counting = Arc::new(CountingTool {
+        calls: AtomicU32::new(0),
+    });
+    runtime
+        .register_tool("read".to_string(), counting.clone())
+        .unwrap();
+    runtime
+        .register_tool("mock".to_string(), Arc::new(MockTool::new("mock")))
+        .unwrap();
+    runtime.configure_cache_ttl("read", Duration::from_millis(200));
+
+    // Key order doesn't matter: inputs are compared canonically
+    let first = runtime
+        .execute_tool("read", json!({"path": "a", "limit": 1}))
+        .await
+        .unwrap();
+    let second = runtime
+        .execute_tool("read", json!({"limit": 1, "path": "a"}))
+        .await
+        .unwrap();
+    assert_eq!(first, second);
+    assert_eq!(counting.calls.load(Ordering::SeqCst), 1);
+
+    runtime
+        .execute_tool("read", json!({"path": "b"}))
+        .await
+        .unwrap();
+    assert_eq!(counting.calls.load(Ordering::SeqCst), 2);
+
+    // A call that may change state invalidates cached reads
+    runtime.execute_tool("mock", json!({})).await.unwrap();
+    runtime
+        .execute_tool("read", json!({"path": "b"}))
+        .await
+        .unwrap();
+    assert_eq!(counting.calls.load(Ordering::SeqCst), 3);
+
+    tokio::time::sleep(Duration::from_millis(250)).await;
+    runtime
+        .execute_tool("read", json!({"path": "b"}))
+        .await
+        .unwrap();
+    assert_eq!(counting.calls.load(Ordering::SeqCst), 4);
+
+    let _ = std::fs::remove_file(&db_path);
+}
+
+#[tokio::test]
+async fn test_idempotency_key_returns_stored_result() {
+    let db_path = get_test_db_path();
+    let counting = Arc::new(CountingTool {
+        calls: AtomicU32::new(0),
+    });
+    {
+        let runtime = Arc::new(Runtime::with_db(&db_path, false, Duration::from_secs(60)).unwrap());
+        runtime
+            .register_tool("count".to_string(), counting.clone())
+            .unwrap();
+        runtime
+            .register_tool("mock".to_string(), Arc::new(MockTool::new("mock")))
+            .unwrap();
+
+        // Concurrent retries with the same key run the tool once
+        let calls = (0..3).map(|_| {
+            let runtime = runtime.clone();
+            tokio::spawn(async move {
+                runtime
+                    .execute_tool_with_key(
+                        "count",
+                        json!({"n": 1}),
+                        PermissionLevel::Execute,
+                        Some("order-42"),
+                    )
+                    .await
+                    .unwrap()
+            })
+        });
+        for call in calls.collect::<Vec<_>>() {
+            assert_eq!(call.await.unwrap()["call"], 1);
+        }
+        assert_eq!(counting.calls.load(Ordering::SeqCst), 1);
+
+        // Without a key, with another key, or with the same key and another
+        // input, the tool runs again
+        runtime
+            .execute_tool("count", json!({"n": 1}))
+            .await
+            .unwrap();
+        runtime
+            .execute_tool_with_key("count", json!({}), PermissionLevel::Execute, Some("other"))
+            .await
+            .unwrap();
+        runtime
+            .execute_tool_with_key(
+                "count",
+                json!({"n": 2}),
+                PermissionLevel::Execute,
+                Some("order-42"),
+            )
+            .await
+            .unwrap();
+        assert_eq!(counting.calls.load(Ordering::SeqCst), 4);
+
+        let err = runtime
+            .execute_tool_with_key(
+                "mock",
+                json!({"n": 1}),
+                PermissionLevel::Execute,
+                Some("order-42"),
+            )
+            .await
+            .unwrap_err();
+        assert!(err.to_string().contains("already used for tool 'count'"));
+    }
+
+    // Stored results survive a restart
+    let runtime = Runtime::with_db(&db_path, false, Duration::from_secs(60)).unwrap();
+    runtime
+        .register_tool("count".to_string(), counting.clone())
+        .unwrap();
+    let replayed = runtime
+        .execute_tool_with_key(
+            "count",
+            json!({"n": 1}),
+            PermissionLevel::Execute,
+            Some("order-42"),
+        )
+        .await
+        .unwrap();
+    assert_eq!(replayed["call"], 1);
+    assert_eq!(counting.calls.load(Ordering::SeqCst), 4);
+
+    // Past the TTL the stored result is no longer used
+    let runtime = runtime.with_idempotency_ttl(Duration::ZERO);
+    runtime
+        .execute_tool_with_key(
+            "count",
+            json!({"n": 1}),
+            PermissionLevel::Execute,
+            Some("order-42"),
+        )
+        .await
+        .unwrap();
+    assert_eq!(counting.calls.load(Ordering::SeqCst), 5);
+
+    let _ = std::fs::remove_file(&db_path);
+}
+
+/// Stamps the tool name into the input, to show which calls pass through
+struct TagInput;
+
+#[async_trait]
+impl ToolMiddleware for TagInput {
+    fn name(&self) -> &str {
+        "tag"
+    }
+
+    async fn before_execute(&self, call: &mut ToolInvocation) -> Result<Option<Value>> {
+        call.input["seen_by"] = json!(call.tool);
+        Ok(None)
+    }
+}
+
+#[tokio::test]
+async fn test_tool_middleware_wraps_calls_and_plan_steps() {
+    let db_path = get_test_db_path();
+    let runtime = Runtime::with_db(&db_path, false, Duration::from_secs(60))
+        .unwrap()
+        .with_tool_middleware(Arc::new(OutputLimit::new(8)))
+        .with_tool_middleware(Arc::new(TagInput));
+    runtime
+        .register_tool("mock".to_string(), Arc::new(MockTool::new("mock")))
+        .unwrap();
+    runtime.alias_tool("m", "mock").unwrap();
+
+    // Layers see the registered name, and the outer limit applies to the tagged output
+    let output = runtime
+        .execute_tool("m", json!({"text": "0123456789"}))
+        .await
+        .unwrap();
+    assert_eq!(output["input"]["seen_by"], "mock");
+    assert_eq!(
+        output["input"]["text"],
+        "01234567\n[... 2 bytes truncated ...]"
+    );
+
+    // Without dependencies steps run sequentially; with them, as a DAG
+    for (plan_id, depends_on) in [("sequential", json!([])), ("dag", json!(["a"]))] {
+        let plan = json!({"id": plan_id, "steps": [
+            {"id": "a", "tool": "mock", "input": {}},
+            {"id": "b", "tool": "mock", "input": {}, "depends_on": depends_on}
+        ]});
+        let result = runtime.run_plan(plan).await.unwrap();
+        for step in &result.steps {
+            let output = step.output.as_ref().unwrap();
+            assert_eq!(output["input"]["seen_by"], "mock", "{}", plan_id);
+        }
+    }
+
+    let _ = std::fs::remove_file(&db_path);
+}
+
+/// Runs "remote_*" tools "elsewhere", recording each call's session
+#[derive(Default)]
+struct RecordingBackend {
+    calls: std::sync::Mutex<Vec<(String, Option<String>)>>,
+    ended: std::sync::Mutex<Vec<String>>,
+}
+
+#[async_trait]
+impl ExecutionBackend for RecordingBackend {
+    fn name(&self) -> &str {
+        "recording"
+    }
+
+    async fn execute(&self, call: ToolInvocation, tool: &dyn Tool) -> Result<Value> {
+        self.calls
+            .lock()
+            .unwrap()
+            .push((call.tool.clone(), call.session.clone()));
+        if call.tool.starts_with("remote_") {
+            return Ok(json!({"remote": call.input}));
+        }
+        tool.execute(call.input).await
+    }
+
+    async fn end_session(&self, session: &str) -> Result<()> {
+        self.ended.lock().unwrap().push(session.to_string());
+        Ok(())
+    }
+}
+
+#[tokio::test]
+async fn test_execution_backend_runs_calls_with_session() {
+    let db_path = get_test_db_path();
+    let backend = Arc::new(RecordingBackend::default());
+    let runtime = Runtime::with_db(&db_path, false, Duration::from_secs(60))
+        .unwrap()
+        .with_tool_middleware(Arc::new(TagInput))
+        .with_execution_backend(backend.clone());
+    for name in ["mock", "remote_mock"] {
+        runtime
+            .register_tool(name.to_string(), Arc::new(MockTool::new(name)))
+            .unwrap();
+    }
+
+    // The backend sees the call after middleware rewrote it
+    let output = runtime
+        .execute_tool_in_session("remote_mock", json!({}), PermissionLevel::Execute, "s1")
+        .await
+        .unwrap();
+    assert_eq!(output, json!({"remote": {"seen_by": "remote_mock"}}));
+    let output = runtime.execute_tool("mock", json!({})).await.unwrap();
+    assert_eq!(output["tool"], "mock");
+
+    let plan = json!({"id": "p", "steps": [{"id": "a", "tool": "remote_mock", "input": {}}]});
+    let result = runtime.run_plan(plan).await.unwrap();
+    assert_eq!(
+        result.steps[0].output.as_ref().unwrap()["remote"]["seen_by"],
+        "remote_mock"
+    );
+
+    assert_eq!(
+        backend.calls.lock().unwrap().as_slice(),
+        [
+            ("remote_mock".to_string(), Some("s1".to_string())),
+            ("mock".to_string(), None),
+            ("remote_mock".to_string(), None),
+        ]
+    );
+    runtime.end_session("s1").await.unwrap();
+    assert_eq!(backend.ended.lock().unwrap().as_slice(), ["s1"]);
+
+    let _ = std::fs::remove_file(&db_path);
+}
+
+/// Keeps the data of every event it sees
+#[derive(Default)]
+struct CapturingHook {
+    seen: std::sync::Mutex<Vec<Value>>,
+}
+
+#[async_trait]
+impl Hook for CapturingHook {
+    fn name(&self) -> &str {
+        "capturing"
+    }
+
+    fn events(&self) -> &[HookEvent] {
+        &[HookEvent::PlanEnd]
+    }
+
+    async fn on_event(&self, ctx: &HookContext) -> Result<HookResult> {
+        self.seen.lock().unwrap().push(ctx.data.clone());
+        Ok(HookResult::default())
+    }
+}
+
+#[tokio::test]
+async fn test_plan_end_hook_reports_success_and_failure() {
+    let db_path = get_test_db_path();
+    let hook = Arc::new(CapturingHook::default());
+    let hooks = Arc::new(HookRegistry::new());
+    hooks.register(hook.clone());
+    let runtime = Runtime::with_db(&db_path, false, Duration::from_secs(60))
+        .unwrap()
+        .with_hooks(hooks);
+    runtime
+        .register_tool("mock".to_string(), Arc::new(MockTool::new("mock")))
+        .unwrap();
+
+    let plan = json!({"id": "nightly", "steps": [{"id": "a", "tool": "mock", "input": {}}]});
+    runtime.run_plan(plan).await.unwrap();
+    let plan = json!({"id": "broken", "steps": [{"id": "a", "tool": "missing", "input": {}}]});
+    assert!(runtime.run_plan(plan).await.is_err());
+
+    let seen = hook.seen.lock().unwrap();
+    assert_eq!(seen.len(), 2);
+    assert_eq!(seen[0]["plan_id"], "nightly");
+    assert_eq!(seen[0]["status"], "succeeded");
+    assert_eq!(seen[0]["steps"], 1);
+    assert_eq!(seen[1]["plan_id"], "broken");
+    assert_eq!(seen[1]["status"], "failed");
+    assert!(seen[1]["error"].as_str().unwrap().contains("missing"));
+
+    let _ = std::fs::remove_file(&db_path);
+}
+
+/// Blocks `rm` calls, tags every input and marks every output as checked
+struct GuardHook {
+    agents: std::sync::Mutex<Vec<Option<String>>>,
+}
+
+#[async_trait]
+impl Hook for GuardHook {
+    fn name(&self) -> &str {
+        "guard"
+    }
+
+    fn events(&self) -> &[HookEvent] {
+        &[HookEvent::ToolCallBefore, HookEvent::ToolCallAfter]
+    }
+
+    async fn on_event(&self, ctx: &HookContext) -> Result<HookResult> {
+        let mut data = ctx.data.clone();
+        match ctx.event {
+            HookEvent::ToolCallBefore if data["input"]["command"] == "rm" => {
+                return Ok(HookResult {
+                    modified_data: None,
+                    abort: true,
+                });
+            }
+            HookEvent::ToolCallBefore => {
+                self.agents.lock().unwrap().push(ctx.agent_id.clone());
+                data["input"]["tagged"] = json!(true);
+            }
+            _ => data["output"] = json!({"checked": true, "result": data["output"]}),
+        }
+        Ok(HookResult {
+            modified_data: Some(data),
+            abort: false,
+        })
+    }
+}
+
+#[tokio::test]
+async fn test_tool_call_hooks_rewrite_and_abort_calls() {
+    let dir = tempfile::tempdir().unwrap();
+    let db_path = dir.path().join("test.db");
+    let hook = Arc::new(GuardHook {
+        agents: std::sync::Mutex::new(Vec::new()),
+    });
+    let hooks = Arc::new(HookRegistry::new());
+    hooks.register(hook.clone());
+    let runtime = Runtime::with_db(db_path.to_str().unwrap(), false, Duration::from_secs(60))
+        .unwrap()
+        .with_hooks(hooks)
+        .with_audit_trail();
+    runtime
+        .register_tool("mock".to_string(), Arc::new(MockTool::new("mock")))
+        .unwrap();
+    runtime.set_session_agent("s1", "coder");
+
+    let output = runtime
+        .execute_tool_in_session(
+            "mock",
+            json!({"command": "ls"}),
+            PermissionLevel::Execute,
+            "s1",
+        )
+        .await
+        .unwrap();
+    assert_eq!(output["checked"], true);
+    assert_eq!(output["result"]["input"]["command"], "ls");
+    assert_eq!(output["result"]["input"]["tagged"], true);
+    assert_eq!(
+        *hook.agents.lock().unwrap(),
+        vec![Some("coder".to_string())]
+    );
+
+    let err = runtime
+        .execute_tool("mock", json!({"command": "rm"}))
+        .await
+        .unwrap_err();
+    assert!(format!("{:#}", err).contains("Hook 'guard' aborted operation"));
+    let records = runtime
+        .storage()
+        .audit_records(&AuditFilter::default())
+        .unwrap();
+    assert_eq!(records[0].outcome, AuditOutcome::Error);
+}
+
+#[tokio::test]
+async fn test_fixture_tools_replay_outputs_through_execution_path() {
+    let db_path = get_test_db_path();
+    let runtime = Runtime::with_db(&db_path, false, Duration::from_secs(60)).unwrap();
+    let fixture: Fixture = serde_json::from_value(json!({
+        "plan_id": "recorded",
+        "recorded_at": "0s",
+        "steps": [
+            {"index": 1, "tool": "shell", "input": {"cmd": "date"}, "output": {"stdout": "today"}, "duration_ms": 3},
+            {"index": 0, "tool": "shell", "input": {"cmd": "pwd"}, "output": {"stdout": "/work"}, "duration_ms": 2}
+        ]
+    }))
+    .unwrap();
+    for tool in FixtureTool::from_fixture(&fixture) {
+        runtime
+            .register_tool(tool.name().to_string(), Arc::new(tool))
+            .unwrap();
+    }
+
+    let result = runtime.run_plan(fixture.to_plan()).await.unwrap();
+    assert_eq!(result.plan_id, "recorded");
+    let outputs: Vec<_> = result.steps.iter().map(|s| s.output.clone()).collect();
+    assert_eq!(
+        outputs,
+        [
+            Some(json!({"stdout": "/work"})),
+            Some(json!({"stdout": "today"}))
+        ]
+    );
+    assert!(result
+        .steps
+        .iter()
+        .all(|s| s.status == StepStatus::Completed && s.duration_us >= s.duration_ms * 1000));
+
+    let err = runtime
+        .execute_tool("shell", json!({"cmd": "ls"}))
+        .await
+        .unwrap_err();
+    assert!(format!("{:#}", err).contains("No recorded output of 'shell'"));
+
+    let _ = std::fs::remove_file(&db_path);
+}
+
+#[tokio::test(flavor = "current_thread")]
+async fn test_storage_async_facade_shares_database_between_clones() {
+    let db_path = get_test_db_path();
+    let storage = Storage::open(&db_path).unwrap();
+    let clone = storage.clone();
+
+    // A large write on one handle runs off the runtime thread while the other reads
+    let big = json!({"blob": "x".repeat(1 << 20)});
+    let (saved, missing) = tokio::join!(
+        storage.save_state_async("big", big.clone()),
+        clone.load_state_async("absent")
+    );
+    saved.unwrap();
+    assert_eq!(missing.unwrap(), None);
+
+    assert_eq!(clone.load_state_async("big").await.unwrap(), Some(big));
+    assert_eq!(clone.list_keys_async().await.unwrap(), ["big"]);
+
+    let _ = std::fs::remove_file(&db_path);
+}
@@ -1,4 +1,6 @@
 use clap::{Parser, Subcommand, ValueEnum};
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
 use std::path::PathBuf;
 
 #[derive(Subcommand)]
@@ -17,7 +19,214 @@ pub enum PluginCommands {
     },
 }
 
-#[derive(ValueEnum, Clone, Debug, PartialEq)]
+#[derive(Subcommand)]
+pub enum ConfigCommands {
+    /// Validate a config file (syntax, types, and value ranges)
+    Validate {
+        /// Path to config file (defaults to --config)
+        path: Option<PathBuf>,
+    },
+    /// Print the JSON Schema of the config file format
+    Schema,
+}
+
+#[derive(Subcommand)]
+pub enum PlanCommands {
+    /// Check a plan's steps and dependencies and show its execution levels
+    Validate {
+        /// Path to plan JSON file
+        file: PathBuf,
+    },
+    /// Print a plan's dependency graph in Graphviz DOT format
+    Graph {
+        /// Path to plan JSON file
+        file: PathBuf,
+    },
+}
+
+#[derive(Subcommand)]
+pub enum SessionCommands {
+    /// List saved chat sessions
+    List,
+    /// Copy a session into a new one to try another direction
+    Fork {
+        /// Session to fork
+        id: String,
+        /// Keep only the first N messages (default: all)
+        #[arg(long)]
+        at: Option<usize>,
+    },
+    /// Copy JSON session files into the SQLite session database
+    Import {
+        /// Directory of session files (default: ~/.silentclaw/sessions)
+        #[arg(long)]
+        dir: Option<String>,
+    },
+    /// Search the SQLite session database (sessions.store = "sqlite")
+    Query {
+        /// Only sessions of this agent
+        #[arg(long)]
+        agent: Option<String>,
+        /// Only sessions updated at or after this time (RFC 3339)
+        #[arg(long)]
+        since: Option<chrono::DateTime<chrono::Utc>>,
+        /// Only sessions updated before this time (RFC 3339)
+        #[arg(long)]
+        until: Option<chrono::DateTime<chrono::Utc>>,
+        /// Only sessions that used at least this many tokens
+        #[arg(long)]
+        min_tokens: Option<u64>,
+        /// Most sessions to list
+        #[arg(long, default_value_t = 50)]
+        limit: usize,
+    },
+    /// Delete sessions from the SQLite session database that have been idle
+    /// for a while
+    Prune {
+        /// Delete sessions not updated in this many days
+        #[arg(long)]
+        older_than_days: u32,
+    },
+}
+
+#[derive(Subcommand)]
+pub enum MemoryCommands {
+    /// Show document/vector counts and the embedding model of the index
+    Stats {
+        /// Check the database and every vector for orphans and corruption
+        #[arg(long)]
+        verify: bool,
+        /// Drop orphaned/corrupted vectors and rebuild indexes (implies --verify)
+        #[arg(long)]
+        compact: bool,
+    },
+    /// Re-embed the memory index with another embedding model or dimension
+    Migrate {
+        /// Embedding model (default: [memory] embedding_model)
+        #[arg(long)]
+        model: Option<String>,
+        /// Vector size of the model (default: [memory] embedding_dimensions)
+        #[arg(long)]
+        dimensions: Option<usize>,
+    },
+}
+
+#[derive(Subcommand)]
+pub enum FixtureCommands {
+    /// List the recorded steps, or print one step's input and output
+    Show {
+        /// Fixture directory written by --record
+        dir: PathBuf,
+        /// Step index to print in full
+        #[arg(long)]
+        step: Option<usize>,
+    },
+    /// Change what a step replays: a new output, or an error
+    Edit {
+        /// Fixture directory written by --record
+        dir: PathBuf,
+        /// Step index to change
+        #[arg(long)]
+        step: usize,
+        /// New output as JSON (clears a recorded error)
+        #[arg(long, conflicts_with = "error", required_unless_present = "error")]
+        set_output: Option<String>,
+        /// Make the step fail on replay with this message
+        #[arg(long)]
+        error: Option<String>,
+    },
+    /// Replace long strings in step outputs with a short placeholder
+    Scrub {
+        /// Fixture directory written by --record
+        dir: PathBuf,
+        /// Strings longer than this many bytes are replaced
+        #[arg(long, default_value_t = 4096)]
+        max_bytes: usize,
+    },
+    /// Rewrite a fixture recorded by an older release in the current format
+    Migrate {
+        /// Fixture directory written by --record
+        dir: PathBuf,
+    },
+}
+
+#[derive(Subcommand)]
+pub enum AgentCommands {
+    /// List the agents defined in [agents.<name>] sections and agents_dir
+    List,
+}
+
+#[derive(Subcommand)]
+pub enum PolicyCommands {
+    /// Show which [[tool_policy.rules]] rule decides each sample tool call
+    Test {
+        /// JSON file with a sample call, or an array of them: {"tool": "shell",
+        /// "input": {...}, "permission": "execute", "expect": "deny"}
+        #[arg(long, required_unless_present = "tool", conflicts_with = "tool")]
+        file: Option<PathBuf>,
+        /// Tool of a single sample call
+        #[arg(long)]
+        tool: Option<String>,
+        /// Input JSON of the --tool call
+        #[arg(long, default_value = "{}", requires = "tool")]
+        input: String,
+        /// Caller permission of the --tool call (default: execute)
+        #[arg(long, requires = "tool")]
+        permission: Option<String>,
+    },
+}
+
+#[derive(Subcommand)]
+pub enum AuditCommands {
+    /// List recorded tool calls, newest first
+    List {
+        /// Only calls made in this session
+        #[arg(long)]
+        session: Option<String>,
+        /// Only calls to this tool
+        #[arg(long)]
+        tool: Option<String>,
+        /// Only calls made for this gateway identity
+        #[arg(long)]
+        principal: Option<String>,
+        /// Only calls at or after this time (RFC 3339, e.g. 2026-10-17T09:00:00Z)
+        #[arg(long)]
+        since: Option<chrono::DateTime<chrono::Utc>>,
+        /// Only calls before this time (RFC 3339)
+        #[arg(long)]
+        until: Option<chrono::DateTime<chrono::Utc>>,
+        /// Most calls to list
+        #[arg(long, default_value_t = 50)]
+        limit: usize,
+    },
+    /// Show one recorded tool call
+    Show {
+        /// Record ID (from `warden audit list`)
+        id: u64,
+    },
+}
+
+#[derive(Subcommand)]
+pub enum WorkspaceCommands {
+    /// List workspace snapshots that can be restored
+    List,
+    /// Revert every file the filesystem tools changed during a session
+    Restore {
+        /// Snapshot ID (default: the most recent)
+        id: Option<String>,
+    },
+}
+
+#[derive(ValueEnum, Clone, Copy, Debug, PartialEq)]
+pub enum OutputFormat {
+    /// Human-readable text (default)
+    Text,
+    /// Machine-readable JSON on stdout
+    Json,
+}
+
+#[derive(ValueEnum, Clone, Debug, PartialEq, Deserialize, Serialize, JsonSchema)]
+#[serde(rename_all = "kebab-case")]
 pub enum ExecutionMode {
     /// Use config.runtime.dry_run setting (default)
     Auto,
@@ -47,6 +256,10 @@ pub struct Cli {
     #[arg(long)]
     pub config: Option<PathBuf>,
 
+    /// Output format for command results
+    #[arg(long, global = true, default_value = "text", value_enum)]
+    pub output: OutputFormat,
+
     /// Record tool outputs to fixture directory for replay testing
     #[arg(long, conflicts_with = "replay")]
     pub record: Option<PathBuf>,
@@ -78,8 +291,46 @@ pub enum Commands {
     /// Run a plan from JSON file
     RunPlan {
         /// Path to plan JSON file
+        #[arg(long, required_unless_present = "resume")]
+        file: Option<PathBuf>,
+        /// Resume the last run of this plan id, skipping the steps it completed
+        /// (runs the checkpointed plan unless --file is given)
+        #[arg(long, value_name = "PLAN_ID", conflicts_with = "watch")]
+        resume: Option<String>,
+        /// Re-run whenever the plan file (or a --watch-path) changes
         #[arg(long)]
-        file: PathBuf,
+        watch: bool,
+        /// Also re-run when files under this path change (repeatable)
+        #[arg(long = "watch-path", value_name = "PATH", requires = "watch")]
+        watch_paths: Vec<PathBuf>,
+        /// Milliseconds of quiet before changes trigger a re-run
+        #[arg(long, default_value_t = 500, requires = "watch")]
+        debounce_ms: u64,
+    },
+    /// Replay a recorded fixture repeatedly and report per-step runtime overhead
+    Bench {
+        /// Fixture directory written by --record
+        #[arg(long)]
+        fixture: PathBuf,
+        /// Plan the fixture was recorded from (default: the recorded steps, in order)
+        #[arg(long)]
+        file: Option<PathBuf>,
+        /// Measured runs
+        #[arg(long, default_value_t = 20)]
+        iterations: usize,
+        /// Unmeasured runs before measuring
+        #[arg(long, default_value_t = 2)]
+        warmup: usize,
+    },
+    /// Maintain recorded replay fixtures
+    Fixture {
+        #[command(subcommand)]
+        action: FixtureCommands,
+    },
+    /// Inspect plans without running them
+    Plan {
+        #[command(subcommand)]
+        action: PlanCommands,
     },
     /// Interactive chat with an agent
     Chat {
@@ -89,19 +340,96 @@ pub enum Commands {
         /// Resume existing session by ID
         #[arg(long)]
         session: Option<String>,
+        /// Switch to another provider and model, e.g. "openai/gpt-4o" (also
+        /// for a resumed session; the REPL accepts `/model <provider>[/<model>]`)
+        #[arg(long)]
+        model: Option<String>,
+        /// Full-screen terminal UI with tool activity and session switching
+        #[arg(long, conflicts_with = "prompt")]
+        tui: bool,
+        /// Answer a single prompt and exit; piped stdin is attached as context
+        #[arg(short, long)]
+        prompt: Option<String>,
     },
     /// Manage plugins
     Plugin {
         #[command(subcommand)]
         action: PluginCommands,
     },
+    /// Inspect configured agent profiles
+    Agent {
+        #[command(subcommand)]
+        action: AgentCommands,
+    },
+    /// Check tool policy rules
+    Policy {
+        #[command(subcommand)]
+        action: PolicyCommands,
+    },
+    /// Inspect the audit trail of tool calls (recorded with
+    /// tool_policy.enabled and audit_enabled)
+    Audit {
+        #[command(subcommand)]
+        action: AuditCommands,
+    },
+    /// Manage saved chat sessions
+    Session {
+        #[command(subcommand)]
+        action: SessionCommands,
+    },
+    /// Maintain the memory search index
+    Memory {
+        #[command(subcommand)]
+        action: MemoryCommands,
+    },
+    /// Restore workspace snapshots taken with `tools.filesystem.snapshot`
+    Workspace {
+        #[command(subcommand)]
+        action: WorkspaceCommands,
+    },
+    /// Validate config files or dump the config JSON Schema
+    Config {
+        #[command(subcommand)]
+        action: ConfigCommands,
+    },
+    /// Check config and LLM provider connectivity
+    Doctor,
+    /// Run one built-in tool with JSON input on stdin and print the result
+    /// (the container side of `[runtime.container]`)
+    #[command(hide = true)]
+    ExecTool {
+        /// Tool name
+        tool: String,
+        /// Stop the call after this many seconds
+        #[arg(long)]
+        timeout_secs: Option<u64>,
+    },
     /// Start the HTTP/WebSocket gateway server
     Serve {
+        #[command(subcommand)]
+        action: Option<ServeCommands>,
         /// Host to bind to
         #[arg(long, default_value = "127.0.0.1")]
         host: String,
         /// Port to listen on
         #[arg(long, default_value = "8080")]
         port: u16,
+        /// Run in the background (Unix only); SIGHUP reloads config, SIGTERM stops
+        #[arg(long)]
+        daemon: bool,
+        /// PID file for --daemon, status and stop [default: ~/.silentclaw/warden-serve.pid]
+        #[arg(long, global = true)]
+        pid_file: Option<PathBuf>,
+        /// Log file for --daemon [default: ~/.silentclaw/warden-serve.log]
+        #[arg(long, requires = "daemon")]
+        log_file: Option<PathBuf>,
     },
 }
+
+#[derive(Subcommand)]
+pub enum ServeCommands {
+    /// Show whether a background server is running
+    Status,
+    /// Gracefully stop a background server
+    Stop,
+}
@@ -0,0 +1,69 @@
+use crate::cli::OutputFormat;
+use crate::config::Config;
+use anyhow::Result;
+use operon_runtime::PermissionLevel;
+
+/// List the configured agents with their resolved settings, by name
+pub fn list(config: &Config, output: OutputFormat) -> Result<()> {
+    let mut names: Vec<_> = config.agents.keys().collect();
+    names.sort();
+
+    let mut agents = Vec::with_capacity(names.len());
+    for name in names {
+        let agent = super::agent_config(config, name)?;
+        agents.push((config.agents[name].description.clone(), agent));
+    }
+
+    if output == OutputFormat::Json {
+        let list: Vec<_> = agents
+            .iter()
+            .map(|(description, agent)| {
+                serde_json::json!({
+                    "name": agent.name,
+                    "description": description,
+                    "model": agent.model,
+                    "tools": agent.tools,
+                    "max_permission": agent.max_permission,
+                    "temperature": (f64::from(agent.temperature) * 100.0).round() / 100.0,
+                    "max_tokens": agent.max_tokens,
+                    "max_iterations": agent.max_iterations,
+                    "max_cost_usd": agent.max_cost_usd,
+                })
+            })
+            .collect();
+        return super::print_json(&list);
+    }
+
+    if agents.is_empty() {
+        println!("No agents defined; add [agents.<name>] sections or set agents_dir.");
+        return Ok(());
+    }
+    for (description, agent) in &agents {
+        let model = if agent.model.is_empty() {
+            "provider default"
+        } else {
+            &agent.model
+        };
+        let tools = if agent.tools.is_empty() {
+            "all tools".to_string()
+        } else {
+            agent.tools.join(", ")
+        };
+        let permission = format!(
+            "{:?}",
+            agent
+                .max_permission
+                .clone()
+                .unwrap_or(PermissionLevel::Execute)
+        )
+        .to_lowercase();
+        println!(
+            "{:<16} {}  (up to {}; {})",
+            agent.name, model, permission, tools
+        );
+        if let Some(description) = description {
+            println!("{:<16} {}", "", description);
+        }
+    }
+    Ok(())
+}
@@ -0,0 +1,82 @@
+use crate::cli::OutputFormat;
+use anyhow::{Context, Result};
+use operon_runtime::{AuditFilter, AuditRecord, Storage};
+
+use super::run_plan::DB_PATH;
+
+fn open_storage() -> Result<Storage> {
+    Storage::open(DB_PATH).with_context(|| {
+        format!(
+            "Failed to open {} (is `warden serve` or `warden chat` running from here?)",
+            DB_PATH
+        )
+    })
+}
+
+/// List recorded tool calls passing `filter`, newest first
+pub fn list(filter: AuditFilter, output: OutputFormat) -> Result<()> {
+    let records = open_storage()?.audit_records(&filter)?;
+
+    if output == OutputFormat::Json {
+        return super::print_json(&records);
+    }
+    if records.is_empty() {
+        println!("No tool calls recorded; they are with tool_policy.enabled and audit_enabled.");
+        return Ok(());
+    }
+    for record in &records {
+        println!(
+            "{:>6}  {}  {:<12} {:<7} {:>7}ms  {}{}",
+            record.id,
+            record.timestamp.format("%Y-%m-%d %H:%M:%S"),
+            record.decision.as_str(),
+            record.outcome.as_str(),
+            record.duration_ms,
+            record.tool,
+            record
+                .session_id
+                .as_ref()
+                .map(|id| format!("  (session {})", id))
+                .unwrap_or_default()
+        );
+    }
+    Ok(())
+}
+
+/// Show every field of audit record `id`
+pub fn show(id: u64, output: OutputFormat) -> Result<()> {
+    let record = open_storage()?
+        .audit_record(id)?
+        .with_context(|| format!("No audit record {}", id))?;
+
+    if output == OutputFormat::Json {
+        return super::print_json(&record);
+    }
+    print_record(&record);
+    Ok(())
+}
+
+fn print_record(record: &AuditRecord) {
+    println!("Record:     {}", record.id);
+    println!("Time:       {}", record.timestamp.to_rfc3339());
+    println!("Tool:       {}", record.tool);
+    println!("Input hash: {}", record.input_hash);
+    if let Some(session_id) = &record.session_id {
+        println!("Session:    {}", session_id);
+    }
+    if let Some(agent) = &record.agent {
+        println!("Agent:      {}", agent);
+    }
+    if let Some(principal) = &record.principal {
+        println!("Principal:  {}", principal);
+    }
+    println!("Decision:   {}", record.decision.as_str());
+    if let Some(reason) = &record.reason {
+        println!("Reason:     {}", reason);
+    }
+    println!("Duration:   {}ms", record.duration_ms);
+    println!("Outcome:    {}", record.outcome.as_str());
+    if let Some(error) = &record.error {
+        println!("Error:      {}", error);
+    }
+}
@@ -0,0 +1,222 @@
+use crate::cli::OutputFormat;
+use crate::config::Config;
+use anyhow::{Context, Result};
+use operon_runtime::{Fixture, FixtureTool, PlanResult, Runtime, Tool};
+use serde::Serialize;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use tracing::info;
+
+/// `warden bench` inputs
+pub struct BenchOptions {
+    /// Fixture directory written by `--record`
+    pub fixture: PathBuf,
+    /// Plan the fixture was recorded from; defaults to the recorded steps in order
+    pub plan: Option<PathBuf>,
+    pub iterations: usize,
+    pub warmup: usize,
+}
+
+/// Latency distribution in microseconds
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+pub struct Latency {
+    pub min_us: u64,
+    pub p50_us: u64,
+    pub p95_us: u64,
+    pub max_us: u64,
+    pub mean_us: u64,
+}
+
+impl Latency {
+    fn from_samples(samples: &[u64]) -> Self {
+        if samples.is_empty() {
+            return Self::default();
+        }
+        let mut sorted = samples.to_vec();
+        sorted.sort_unstable();
+        // Nearest-rank percentile
+        let percentile = |p: usize| sorted[(p * sorted.len()).div_ceil(100).max(1) - 1];
+        Self {
+            min_us: sorted[0],
+            p50_us: percentile(50),
+            p95_us: percentile(95),
+            max_us: sorted[sorted.len() - 1],
+            mean_us: sorted.iter().sum::<u64>() / sorted.len() as u64,
+        }
+    }
+}
+
+#[derive(Debug, Serialize)]
+pub struct StepLatency {
+    pub id: String,
+    pub tool: String,
+    #[serde(flatten)]
+    pub latency: Latency,
+}
+
+#[derive(Debug, Serialize)]
+pub struct BenchReport {
+    pub plan_id: String,
+    pub iterations: usize,
+    pub warmup: usize,
+    /// Whole-plan wall time, including scheduling between steps
+    pub plan: Latency,
+    pub steps: Vec<StepLatency>,
+}
+
+/// Replay a fixture's outputs through the real execution path (scheduler,
+/// queue, timeouts, middleware, state storage) with tools that do no work,
+/// so the timings are the runtime's own overhead
+pub async fn execute(options: BenchOptions, config: &Config, output: OutputFormat) -> Result<()> {
+    if options.iterations == 0 {
+        anyhow::bail!("--iterations must be at least 1");
+    }
+    let fixture = Fixture::load(&options.fixture)?;
+    let plan = match &options.plan {
+        Some(file) => {
+            let content = std::fs::read_to_string(file)
+                .context(format!("Failed to read plan file: {:?}", file))?;
+            serde_json::from_str(&content).context("Failed to parse plan JSON")?
+        }
+        None => fixture.to_plan(),
+    };
+
+    // Scratch state DB so benchmark runs don't touch the real one
+    let db_path = std::env::temp_dir().join(format!("warden-bench-{}.db", std::process::id()));
+    let report = run(&plan, &fixture, &options, config, &db_path).await;
+    let _ = std::fs::remove_file(&db_path);
+    let report = report?;
+
+    if output == OutputFormat::Json {
+        return super::print_json(&report);
+    }
+    print_report(&report);
+    Ok(())
+}
+
+async fn run(
+    plan: &serde_json::Value,
+    fixture: &Fixture,
+    options: &BenchOptions,
+    config: &Config,
+    db_path: &Path,
+) -> Result<BenchReport> {
+    let db_path = db_path.to_str().context("Non-UTF-8 temp directory")?;
+    let timeout = Duration::from_secs(config.runtime.timeout_secs);
+    let mut runtime =
+        Runtime::with_db(db_path, false, timeout)?.with_max_parallel(config.runtime.max_parallel);
+    for (class, &limit) in &config.runtime.resource_limits {
+        runtime = runtime.with_resource_limit(class, limit);
+    }
+    for tool in FixtureTool::from_fixture(fixture) {
+        runtime.register_tool(tool.name().to_string(), Arc::new(tool))?;
+    }
+
+    runtime.start().await?;
+    let mut plan_samples = Vec::with_capacity(options.iterations);
+    let mut step_samples: Vec<(String, String, Vec<u64>)> = Vec::new();
+    for i in 0..options.warmup + options.iterations {
+        let start = Instant::now();
+        let result = runtime.run_plan(plan.clone()).await?;
+        let elapsed = start.elapsed();
+        if i < options.warmup {
+            continue;
+        }
+        plan_samples.push(elapsed.as_micros() as u64);
+        record_steps(&mut step_samples, &result);
+    }
+    runtime.stop().await?;
+    info!(iterations = options.iterations, "Benchmark completed");
+
+    Ok(BenchReport {
+        plan_id: plan["id"].as_str().unwrap_or("unknown").to_string(),
+        iterations: options.iterations,
+        warmup: options.warmup,
+        plan: Latency::from_samples(&plan_samples),
+        steps: step_samples
+            .into_iter()
+            .map(|(id, tool, samples)| StepLatency {
+                id,
+                tool,
+                latency: Latency::from_samples(&samples),
+            })
+            .collect(),
+    })
+}
+
+/// Add one run's step timings, keeping steps in plan order
+fn record_steps(samples: &mut Vec<(String, String, Vec<u64>)>, result: &PlanResult) {
+    for step in &result.steps {
+        match samples.iter_mut().find(|(id, _, _)| *id == step.id) {
+            Some((_, _, durations)) => durations.push(step.duration_us),
+            None => samples.push((step.id.clone(), step.tool.clone(), vec![step.duration_us])),
+        }
+    }
+}
+
+fn print_report(report: &BenchReport) {
+    println!(
+        "Plan {}: {} iterations ({} warmup), times in µs",
+        report.plan_id, report.iterations, report.warmup
+    );
+    let id_width = report
+        .steps
+        .iter()
+        .map(|s| s.id.len())
+        .max()
+        .unwrap_or(0)
+        .max("(plan)".len());
+    let tool_width = report
+        .steps
+        .iter()
+        .map(|s| s.tool.len())
+        .max()
+        .unwrap_or(0)
+        .max("TOOL".len());
+
+    println!(
+        "{:<id_width$}  {:<tool_width$}  {:>8}  {:>8}  {:>8}  {:>8}  {:>8}",
+        "STEP", "TOOL", "MIN", "P50", "P95", "MAX", "MEAN"
+    );
+    let rows = report
+        .steps
+        .iter()
+        .map(|s| (s.id.as_str(), s.tool.as_str(), &s.latency))
+        .chain(std::iter::once(("(plan)", "", &report.plan)));
+    for (id, tool, latency) in rows {
+        println!(
+            "{:<id_width$}  {:<tool_width$}  {:>8}  {:>8}  {:>8}  {:>8}  {:>8}",
+            id,
+            tool,
+            latency.min_us,
+            latency.p50_us,
+            latency.p95_us,
+            latency.max_us,
+            latency.mean_us
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_latency_percentiles() {
+        let samples: Vec<u64> = (1..=100).rev().collect();
+        let latency = Latency::from_samples(&samples);
+        assert_eq!(
+            latency,
+            Latency {
+                min_us: 1,
+                p50_us: 50,
+                p95_us: 95,
+                max_us: 100,
+                mean_us: 50,
+            }
+        );
+        assert_eq!(Latency::from_samples(&[7]).p95_us, 7);
+        assert_eq!(Latency::from_samples(&[]), Latency::default());
+    }
+}
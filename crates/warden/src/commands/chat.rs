@@ -1,36 +1,134 @@
-use crate::cli::ExecutionMode;
-use crate::config::Config;
-use anyhow::{anyhow, Result};
-use operon_adapters::{register_filesystem_tools, register_shell_tool, MemorySearchTool};
+use crate::cli::{ExecutionMode, OutputFormat};
+use crate::config::{Config, LlmConfig};
+use anyhow::{anyhow, Context, Result};
+use async_trait::async_trait;
+use futures::StreamExt;
+use operon_adapters::{load_image, register_filesystem_tools, MemorySearchTool, WorkspaceGuard};
 use operon_runtime::{
-    Agent, AgentConfig, AnthropicClient, ConfigManager, ConfigReloadEvent, GeminiClient,
-    LLMProvider, OpenAIClient, PermissionLevel, ProviderChain, Runtime, SessionStore,
-    ToolPolicyPipeline,
+    Agent, AgentEvent, AnthropicClient, ApprovalRequest, Approver, ConfigManager,
+    ConfigReloadEvent, GeminiClient, LLMProvider, OpenAIClient, ProviderChain, ProviderRouter,
+    Runtime, Session,
 };
-use operon_runtime::tool_policy::layers::{
-    AuditLogLayer, DryRunGuardLayer, InputValidationLayer, PermissionCheckLayer, RateLimitLayer,
-    TimeoutEnforceLayer, ToolExistenceLayer,
-};
-use std::collections::HashMap;
-use std::io::{self, BufRead, Write};
+use std::io::{self, BufRead, IsTerminal, Read, Write};
 use std::path::PathBuf;
 use std::sync::Arc;
 use std::time::Duration;
 use tracing::info;
 
+/// Max bytes of piped stdin attached to a one-shot prompt (head and tail are kept)
+const MAX_STDIN_CONTEXT_BYTES: usize = 64 * 1024;
+
+/// How the chat session interacts with the user
+pub enum ChatMode {
+    /// Line-based REPL
+    Repl,
+    /// Full-screen terminal UI
+    Tui,
+    /// Answer one message and exit (prompt and/or piped stdin)
+    OneShot {
+        message: String,
+        output: OutputFormat,
+    },
+}
+
+impl ChatMode {
+    /// Pick the mode from CLI flags; piped stdin without a TTY forces one-shot mode
+    pub fn resolve(tui: bool, prompt: Option<String>, output: OutputFormat) -> Result<Self> {
+        let piped = if io::stdin().is_terminal() {
+            None
+        } else {
+            let mut buf = String::new();
+            io::stdin().read_to_string(&mut buf)?;
+            Some(buf)
+        };
+
+        if tui && piped.is_some() {
+            return Err(anyhow!("--tui requires an interactive terminal on stdin"));
+        }
+
+        Ok(match compose_prompt(prompt.as_deref(), piped.as_deref()) {
+            Some(message) => ChatMode::OneShot { message, output },
+            None if tui => ChatMode::Tui,
+            None => ChatMode::Repl,
+        })
+    }
+}
+
+/// Combine an explicit prompt with piped stdin (attached as truncated context)
+fn compose_prompt(prompt: Option<&str>, piped: Option<&str>) -> Option<String> {
+    let piped = piped.map(str::trim_end).filter(|s| !s.is_empty());
+    match (prompt, piped) {
+        (Some(prompt), Some(context)) => Some(format!(
+            "{}\n\n<stdin>\n{}\n</stdin>",
+            prompt,
+            truncate_context(context, MAX_STDIN_CONTEXT_BYTES)
+        )),
+        (Some(prompt), None) => Some(prompt.to_string()),
+        (None, Some(context)) => Some(truncate_context(context, MAX_STDIN_CONTEXT_BYTES)),
+        (None, None) => None,
+    }
+}
+
+/// Keep the head and tail of oversized input, noting how much was omitted
+fn truncate_context(text: &str, max_bytes: usize) -> String {
+    if text.len() <= max_bytes {
+        return text.to_string();
+    }
+    let half = max_bytes / 2;
+    let mut head_end = half;
+    while !text.is_char_boundary(head_end) {
+        head_end -= 1;
+    }
+    let mut tail_start = text.len() - half;
+    while !text.is_char_boundary(tail_start) {
+        tail_start += 1;
+    }
+    format!(
+        "{}\n[... {} bytes omitted ...]\n{}",
+        &text[..head_end],
+        tail_start - head_end,
+        &text[tail_start..]
+    )
+}
+
 /// Execute chat command with optional config file path for hot-reload
 pub async fn execute(
     agent_name: String,
     session_id: Option<String>,
+    model: Option<String>,
+    mode: ChatMode,
     execution_mode: ExecutionMode,
     config: &Config,
     config_path: Option<PathBuf>,
 ) -> Result<()> {
     info!(agent = %agent_name, "Starting chat session");
 
+    // Once agents are defined, a mistyped --agent fails instead of chatting
+    // with the defaults under that name
+    if agent_name != "default"
+        && !config.agents.is_empty()
+        && !config.agents.contains_key(&agent_name)
+    {
+        let mut known: Vec<_> = config.agents.keys().map(String::as_str).collect();
+        known.sort_unstable();
+        return Err(anyhow!(
+            "Unknown agent: {} (available: {})",
+            agent_name,
+            known.join(", ")
+        ));
+    }
+
     // Build LLM provider from config
     let provider = build_provider(config)?;
 
+    // Interactive sessions probe the provider up front; a one-shot prompt fails fast anyway
+    if config.llm.startup_health_check && !matches!(mode, ChatMode::OneShot { .. }) {
+        provider
+            .health_check()
+            .await
+            .context("LLM provider health check failed; run `warden doctor` for details")?;
+    }
+
     // Resolve dry-run
     let dry_run = match execution_mode {
         ExecutionMode::Auto => config.runtime.dry_run,
@@ -40,50 +138,62 @@ pub async fn execute(
 
     // Create runtime and register tools (build fully before Arc wrapping)
     let default_timeout = Duration::from_secs(config.runtime.timeout_secs);
-    let mut runtime = Runtime::new(dry_run, default_timeout)?;
+    let mut runtime =
+        Runtime::new(dry_run, default_timeout)?.with_max_parallel(config.runtime.max_parallel);
+    if let Some(backend) = super::execution_backend(config)? {
+        runtime = runtime.with_execution_backend(backend);
+    }
+    runtime = super::attach_notifications(runtime, config)?;
+    let redactor = super::redactor(config)?;
+    if let Some(redactor) = &redactor {
+        runtime = runtime.with_tool_middleware(redactor.clone());
+    }
 
     if config.tools.shell.enabled {
-        register_shell_tool(
+        super::register_shell(
             &runtime,
+            config,
             dry_run,
-            config.tools.shell.blocklist.clone(),
-            config.tools.shell.allowlist.clone(),
+            super::tool_timeout(config, "shell"),
         )?;
     }
 
+    super::register_ssh_tools(&runtime, config, dry_run)?;
+    super::register_kubernetes_tool(&runtime, config)?;
+    super::register_github_tool(&runtime, config)?;
+    super::register_email_tool(&runtime, config)?;
+    super::register_screenshot_tools(&runtime, config)?;
+
     if config.tools.filesystem.enabled {
         register_filesystem_tools(
             &runtime,
             PathBuf::from(&config.tools.filesystem.workspace),
             config.tools.filesystem.max_file_size_mb,
+            config.tools.filesystem.write_limits(),
+            super::workspace::start_snapshot(config, dry_run)?,
         )?;
     }
 
     // Initialize memory search if enabled
     if config.memory.enabled {
-        let db_path = shellexpand::tilde(&config.memory.db_path).to_string();
-        let db_path = PathBuf::from(&db_path);
+        let db_path = super::memory::db_path(config);
         if let Some(parent) = db_path.parent() {
             tokio::fs::create_dir_all(parent).await?;
         }
 
-        let embedding_key = std::env::var("OPENAI_API_KEY")
-            .or_else(|_| std::env::var("EMBEDDING_API_KEY"))
-            .unwrap_or_default();
-
-        if !embedding_key.is_empty() {
-            let embedder = Arc::new(
-                operon_runtime::memory::embedding::OpenAIEmbedding::new(&embedding_key),
-            );
+        if let Some(embedder) = super::memory::embedder(config, None, None) {
             let workspace = PathBuf::from(&config.tools.filesystem.workspace);
             let manager = Arc::new(
-                operon_runtime::memory::MemoryManager::new(&db_path, workspace, embedder)?,
+                operon_runtime::memory::MemoryManager::new(&db_path, workspace, Arc::new(embedder))
+                    .context("Memory index does not match [memory] settings; run `warden memory migrate`")?,
             );
 
             if config.memory.auto_reindex {
                 let watcher_handle = manager.start_indexing().await?;
                 // Keep watcher alive for the duration of the process
-                tokio::spawn(async move { let _ = watcher_handle.await; });
+                tokio::spawn(async move {
+                    let _ = watcher_handle.await;
+                });
             }
 
             runtime.register_tool(
@@ -96,60 +206,28 @@ pub async fn execute(
         }
     }
 
-    // Build tool policy pipeline if enabled (before Arc wrapping)
-    if config.tool_policy.enabled {
-        let tool_names = runtime.tool_names();
-        let mut pipeline = ToolPolicyPipeline::new()
-            .add_layer(Box::new(ToolExistenceLayer::new(tool_names)));
-
-        if config.tool_policy.permission_enabled {
-            let default_perm = parse_permission_level(&config.tool_policy.default_permission);
-            pipeline = pipeline.add_layer(Box::new(PermissionCheckLayer::new(
-                HashMap::new(),
-                default_perm,
-            )));
-        }
-
-        if config.tool_policy.rate_limit_enabled {
-            pipeline = pipeline.add_layer(Box::new(RateLimitLayer::new(
-                config.tool_policy.max_calls_per_minute,
-            )));
-        }
-
-        if config.tool_policy.input_validation_enabled {
-            pipeline = pipeline.add_layer(Box::new(InputValidationLayer::new(
-                HashMap::new(), // TODO: populate from runtime tool schemas
-            )));
-        }
-
-        if config.tool_policy.dry_run_guard_enabled {
-            pipeline = pipeline.add_layer(Box::new(DryRunGuardLayer::new(
-                config.tool_policy.dry_run_bypass_tools.clone(),
-            )));
-        }
-
-        if config.tool_policy.audit_enabled {
-            pipeline = pipeline.add_layer(Box::new(AuditLogLayer::new()));
-        }
-
-        pipeline = pipeline.add_layer(Box::new(TimeoutEnforceLayer::new()));
+    super::apply_tool_config(&runtime, config)?;
 
+    // Build tool policy pipeline if enabled (before Arc wrapping)
+    if let Some(pipeline) = super::policy_pipeline(&runtime, config)? {
         runtime.set_policy(pipeline);
         info!("Tool policy pipeline enabled");
     }
+    if super::audit_trail_enabled(config) {
+        runtime = runtime.with_audit_trail();
+    }
+    // Only the line REPL can stop to ask; elsewhere held calls are denied
+    if matches!(mode, ChatMode::Repl) {
+        runtime = runtime.with_approver(Arc::new(TerminalApprover::default()));
+    }
 
     // All setup done — now wrap in Arc
     let runtime = Arc::new(runtime);
 
-    // Build agent config
-    let agent_config = AgentConfig {
-        name: agent_name.clone(),
-        model: config.llm.model.clone(),
-        ..AgentConfig::default()
-    };
+    let agent_config = super::agent_config(config, &agent_name)?;
 
     // Create or resume agent
-    let session_store = SessionStore::new(dirs_home().join(".silentclaw").join("sessions"))?;
+    let session_store = super::session_store(config)?;
 
     let mut agent = if let Some(ref sid) = session_id {
         let session = session_store.load(sid).await?;
@@ -163,14 +241,26 @@ pub async fn execute(
         Agent::new(agent_config, provider, runtime)
     };
 
-    // Start config hot-reload watcher if config path is provided
-    if let Some(ref path) = config_path {
-        let config_manager = ConfigManager::<Config>::new(path.clone(), Config::default_config());
+    // An explicit --model wins over the model a resumed session was handed to
+    if let Some(spec) = model.or_else(|| saved_model(&agent.session)) {
+        hand_off(&mut agent, config, &spec)?;
+    }
+    agent.start_session().await;
+
+    // Start config hot-reload watcher if config path is provided; it stops when
+    // `config_manager` is dropped at the end of the chat
+    let config_manager = config_path.as_ref().map(|path| {
+        Arc::new(
+            ConfigManager::<Config>::new(path.clone(), Config::default_config())
+                .with_loader(|path| crate::config::load_config(Some(path))),
+        )
+    });
+    if let Some(ref config_manager) = config_manager {
         let mut reload_rx = config_manager.subscribe_reload();
 
         // Spawn watcher
         let watcher_handle = tokio::spawn({
-            let cm = config_manager;
+            let cm = config_manager.clone();
             async move {
                 if let Err(e) = cm.watch().await {
                     tracing::error!("Config watcher failed: {}", e);
@@ -194,10 +284,35 @@ pub async fn execute(
         });
     }
 
+    match mode {
+        ChatMode::Tui => return super::chat_tui::run(agent, session_store).await,
+        ChatMode::OneShot { message, output } => {
+            let response = agent.process_message(&message).await;
+            session_store.save(&agent.session).await?;
+            info!(session_id = %agent.session.id, "Session saved");
+            agent.end_session().await?;
+            let response = response?;
+            match output {
+                OutputFormat::Json => super::print_json(&serde_json::json!({
+                    "session_id": agent.session.id,
+                    "agent": agent_name,
+                    "response": response,
+                    "usage": agent.session.cumulative_usage,
+                }))?,
+                OutputFormat::Text => println!("{}", response),
+            }
+            return Ok(());
+        }
+        ChatMode::Repl => {}
+    }
+
     println!("SilentClaw Agent [{}] - Type 'exit' to quit", agent_name);
     println!("Session: {}", agent.session.id);
     println!("---");
 
+    // `/image <path>` reads from the filesystem tools' workspace
+    let attachments = super::workspace::attachment_guard(config)?;
+
     // Interactive REPL
     let stdin = io::stdin();
     let mut stdout = io::stdout();
@@ -207,20 +322,47 @@ pub async fn execute(
         stdout.flush()?;
 
         let mut input = String::new();
-        stdin.lock().read_line(&mut input)?;
+        let bytes_read = stdin.lock().read_line(&mut input)?;
         let input = input.trim();
 
-        if input.is_empty() {
+        if input.is_empty() && bytes_read > 0 {
             continue;
         }
 
-        if input == "exit" || input == "quit" {
+        // EOF (Ctrl-D) ends the session like "exit"
+        if bytes_read == 0 || input == "exit" || input == "quit" {
             // Save session before exit
             session_store.save(&agent.session).await?;
             println!("Session saved: {}", agent.session.id);
+            agent.end_session().await?;
             break;
         }
 
+        if let Some(spec) = input.strip_prefix("/model ") {
+            match hand_off(&mut agent, config, spec.trim()) {
+                Ok(()) => println!("Switched to {}\n", spec.trim()),
+                Err(e) => eprintln!("\nError: {}\n", e),
+            }
+            continue;
+        }
+
+        if let Some(path) = input.strip_prefix("/image ") {
+            match attach_image(&mut agent, attachments.as_deref(), path.trim()).await {
+                Ok(()) => println!("Attached {}; it goes with your next message\n", path.trim()),
+                Err(e) => eprintln!("\nError: {}\n", e),
+            }
+            continue;
+        }
+
+        if agent.config.stream {
+            print!("\nAssistant: ");
+            stdout.flush()?;
+            if let Err(e) = print_streamed_reply(&mut agent, input).await {
+                eprintln!("\nError: {}\n", e);
+            }
+            continue;
+        }
+
         match agent.process_message(input).await {
             Ok(response) => {
                 println!("\nAssistant: {}\n", response);
@@ -234,33 +376,50 @@ pub async fn execute(
     Ok(())
 }
 
-/// Build LLM provider from config (supports env vars as fallback)
-pub fn build_provider(config: &Config) -> Result<Arc<dyn LLMProvider>> {
-    let anthropic_key = if config.llm.anthropic_api_key.is_empty() {
-        std::env::var("ANTHROPIC_API_KEY").ok()
-    } else {
-        Some(config.llm.anthropic_api_key.clone())
-    };
+/// Load an image from the workspace to send with the next message
+async fn attach_image(agent: &mut Agent, guard: Option<&WorkspaceGuard>, path: &str) -> Result<()> {
+    let guard = guard.context("Attaching images needs [tools.filesystem] enabled")?;
+    agent.attach(load_image(guard, path).await?);
+    Ok(())
+}
 
-    let openai_key = if config.llm.openai_api_key.is_empty() {
-        std::env::var("OPENAI_API_KEY").ok()
-    } else {
-        Some(config.llm.openai_api_key.clone())
-    };
+/// Print a reply as it streams in, with a line per tool call
+async fn print_streamed_reply(agent: &mut Agent, input: &str) -> Result<()> {
+    let mut stdout = io::stdout();
+    let mut events = agent.process_message_stream(input);
+    while let Some(event) = events.next().await {
+        match event {
+            AgentEvent::TextDelta(text) => {
+                print!("{}", text);
+                stdout.flush()?;
+            }
+            AgentEvent::ToolCallStarted { name, .. } => println!("\n  [{}]", name),
+            AgentEvent::ToolCallFinished {
+                name,
+                output,
+                is_error: true,
+                ..
+            } => println!("  [{} failed: {}]", name, output),
+            AgentEvent::Done { .. } => println!("\n"),
+            AgentEvent::Error(e) => return Err(anyhow!(e)),
+            AgentEvent::Thinking(_) | AgentEvent::ToolCallFinished { .. } => {}
+        }
+    }
+    Ok(())
+}
 
-    let gemini_key = if config.llm.gemini_api_key.is_empty() {
-        std::env::var("GOOGLE_API_KEY").ok()
-    } else {
-        Some(config.llm.gemini_api_key.clone())
-    };
+/// Build LLM provider from config (supports env vars as fallback)
+pub fn build_provider(config: &Config) -> Result<Arc<dyn LLMProvider>> {
+    let anthropic_key = api_key(&config.llm, "anthropic");
+    let openai_key = api_key(&config.llm, "openai");
+    let gemini_key = api_key(&config.llm, "gemini");
 
     let mut providers: Vec<Arc<dyn LLMProvider>> = Vec::new();
 
     // Helper: push Gemini as fallback provider
-    let push_gemini_fallback = |providers: &mut Vec<Arc<dyn LLMProvider>>,
-                                 key: &Option<String>| {
+    let push_gemini_fallback = |providers: &mut Vec<Arc<dyn LLMProvider>>, key: &Option<String>| {
         if let Some(key) = key {
-            providers.push(Arc::new(GeminiClient::new(key)));
+            providers.push(Arc::new(gemini_client(key, &config.llm)));
         }
     };
 
@@ -268,7 +427,7 @@ pub fn build_provider(config: &Config) -> Result<Arc<dyn LLMProvider>> {
     match config.llm.provider.as_str() {
         "gemini" => {
             if let Some(key) = &gemini_key {
-                let mut client = GeminiClient::new(key);
+                let mut client = gemini_client(key, &config.llm);
                 if !config.llm.model.is_empty() {
                     client = client.with_model(&config.llm.model);
                 }
@@ -276,36 +435,36 @@ pub fn build_provider(config: &Config) -> Result<Arc<dyn LLMProvider>> {
             }
             // Fallbacks: anthropic, then openai
             if let Some(key) = &anthropic_key {
-                providers.push(Arc::new(AnthropicClient::new(key)));
+                providers.push(Arc::new(anthropic_client(key, &config.llm)));
             }
             if let Some(key) = &openai_key {
-                providers.push(Arc::new(OpenAIClient::new(key)));
+                providers.push(Arc::new(openai_client(key, &config.llm)));
             }
         }
         "openai" => {
             if let Some(key) = &openai_key {
-                let mut client = OpenAIClient::new(key);
+                let mut client = openai_client(key, &config.llm);
                 if !config.llm.model.is_empty() {
                     client = client.with_model(&config.llm.model);
                 }
                 providers.push(Arc::new(client));
             }
             if let Some(key) = &anthropic_key {
-                providers.push(Arc::new(AnthropicClient::new(key)));
+                providers.push(Arc::new(anthropic_client(key, &config.llm)));
             }
             push_gemini_fallback(&mut providers, &gemini_key);
         }
         _ => {
             // Default: anthropic first
             if let Some(key) = &anthropic_key {
-                let mut client = AnthropicClient::new(key);
+                let mut client = anthropic_client(key, &config.llm);
                 if !config.llm.model.is_empty() {
                     client = client.with_model(&config.llm.model);
                 }
                 providers.push(Arc::new(client));
             }
             if let Some(key) = &openai_key {
-                providers.push(Arc::new(OpenAIClient::new(key)));
+                providers.push(Arc::new(openai_client(key, &config.llm)));
             }
             push_gemini_fallback(&mut providers, &gemini_key);
         }
@@ -317,13 +476,148 @@ pub fn build_provider(config: &Config) -> Result<Arc<dyn LLMProvider>> {
         ));
     }
 
-    if providers.len() == 1 {
-        Ok(providers.into_iter().next().unwrap())
+    let default: Arc<dyn LLMProvider> = if providers.len() == 1 {
+        providers.into_iter().next().unwrap()
+    } else {
+        Arc::new(ProviderChain::new(providers))
+    };
+
+    if config.llm.routes.is_empty() {
+        return Ok(default);
+    }
+
+    // Task routes get a dedicated client so each can pin its own model
+    let mut router = ProviderRouter::new(default);
+    for (task, route) in &config.llm.routes {
+        let client =
+            provider_client(&config.llm, &route.provider, &route.model).with_context(|| {
+                format!(
+                    "llm.routes.{} cannot use provider '{}'",
+                    task.as_str(),
+                    route.provider
+                )
+            })?;
+        router = router.with_route(*task, client);
+    }
+    Ok(Arc::new(router))
+}
+
+/// API key for `provider` from config, falling back to its env var
+fn api_key(llm: &LlmConfig, provider: &str) -> Option<String> {
+    let (key, var) = match provider {
+        "anthropic" => (&llm.anthropic_api_key, "ANTHROPIC_API_KEY"),
+        "openai" => (&llm.openai_api_key, "OPENAI_API_KEY"),
+        _ => (&llm.gemini_api_key, "GOOGLE_API_KEY"),
+    };
+    if key.is_empty() {
+        std::env::var(var).ok()
     } else {
-        Ok(Arc::new(ProviderChain::new(providers)))
+        Some(key.clone())
     }
 }
 
+/// Single client for `provider` pinned to `model` (empty: the provider's
+/// default), without fallbacks
+pub fn provider_client(
+    llm: &LlmConfig,
+    provider: &str,
+    model: &str,
+) -> Result<Arc<dyn LLMProvider>> {
+    if !["anthropic", "openai", "gemini"].contains(&provider) {
+        return Err(anyhow!(
+            "Unknown provider '{}' (expected anthropic, openai or gemini)",
+            provider
+        ));
+    }
+    let Some(key) = api_key(llm, provider) else {
+        return Err(anyhow!("Provider '{}' has no API key configured", provider));
+    };
+    let client: Arc<dyn LLMProvider> = match provider {
+        "anthropic" => {
+            let mut client = anthropic_client(&key, llm);
+            if !model.is_empty() {
+                client = client.with_model(model);
+            }
+            Arc::new(client)
+        }
+        "openai" => {
+            let mut client = openai_client(&key, llm);
+            if !model.is_empty() {
+                client = client.with_model(model);
+            }
+            Arc::new(client)
+        }
+        _ => {
+            let mut client = gemini_client(&key, llm);
+            if !model.is_empty() {
+                client = client.with_model(model);
+            }
+            Arc::new(client)
+        }
+    };
+    Ok(client)
+}
+
+/// Move `agent` to `spec` ("provider" or "provider/model"), keeping its history
+fn hand_off(agent: &mut Agent, config: &Config, spec: &str) -> Result<()> {
+    let (provider, model) = spec.split_once('/').unwrap_or((spec, ""));
+    agent.hand_off(
+        provider,
+        provider_client(&config.llm, provider, model)?,
+        model,
+    );
+    Ok(())
+}
+
+/// "provider/model" a saved session was last handed off to
+fn saved_model(session: &Session) -> Option<String> {
+    let provider = session.metadata.get("provider")?.as_str()?;
+    let model = session.metadata.get("model").and_then(|m| m.as_str());
+    Some(format!("{}/{}", provider, model.unwrap_or_default()))
+}
+
+/// Anthropic client with the configured extended thinking settings
+fn anthropic_client(key: &str, llm: &LlmConfig) -> AnthropicClient {
+    let client = AnthropicClient::new(key).with_show_thinking(llm.show_thinking);
+    match llm.thinking_budget {
+        Some(budget) => client.with_thinking(budget),
+        None => client,
+    }
+}
+
+/// OpenAI client with the configured reasoning effort
+fn openai_client(key: &str, llm: &LlmConfig) -> OpenAIClient {
+    let client = OpenAIClient::new(key);
+    match llm.reasoning_effort {
+        Some(effort) => client.with_reasoning_effort(effort),
+        None => client,
+    }
+}
+
+/// Gemini client with the configured safety thresholds
+fn gemini_client(key: &str, llm: &LlmConfig) -> GeminiClient {
+    llm.gemini_safety
+        .iter()
+        .fold(GeminiClient::new(key), |client, (category, threshold)| {
+            client.with_safety_setting(category, threshold)
+        })
+}
+
+/// Directory where chat sessions are stored
+pub fn sessions_dir() -> PathBuf {
+    dirs_home().join(".silentclaw").join("sessions")
+}
+
+/// SQLite session database (see `warden session import`)
+pub fn sessions_db_path() -> PathBuf {
+    dirs_home().join(".silentclaw").join("sessions.db")
+}
+
+/// Directory of prompt templates referenced as `template:<name>[@<version>]`
+pub fn prompts_dir() -> PathBuf {
+    dirs_home().join(".silentclaw").join("prompts")
+}
+
 /// Get home directory
 fn dirs_home() -> std::path::PathBuf {
     std::env::var("HOME")
@@ -331,14 +625,70 @@ fn dirs_home() -> std::path::PathBuf {
         .unwrap_or_else(|_| std::path::PathBuf::from("."))
 }
 
-/// Parse permission level string from config to enum (defaults to Read for safety)
-fn parse_permission_level(s: &str) -> PermissionLevel {
-    match s.to_lowercase().as_str() {
-        "read" => PermissionLevel::Read,
-        "write" => PermissionLevel::Write,
-        "execute" => PermissionLevel::Execute,
-        "network" => PermissionLevel::Network,
-        "admin" => PermissionLevel::Admin,
-        _ => PermissionLevel::Read,
+/// Asks on the terminal whether a held tool call may run
+#[derive(Default)]
+struct TerminalApprover {
+    /// One question at a time when tool calls run in parallel
+    asking: tokio::sync::Mutex<()>,
+}
+
+#[async_trait]
+impl Approver for TerminalApprover {
+    async fn approve(&self, request: &ApprovalRequest) -> Result<bool> {
+        let _asking = self.asking.lock().await;
+        print!("\nAllow {}? [y/N] ", request.description);
+        io::stdout().flush()?;
+        let answer = tokio::task::spawn_blocking(|| {
+            let mut line = String::new();
+            io::stdin().lock().read_line(&mut line).map(|_| line)
+        })
+        .await??;
+        Ok(matches!(answer.trim().to_lowercase().as_str(), "y" | "yes"))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_compose_prompt_attaches_stdin() {
+        let msg = compose_prompt(Some("explain this"), Some("panic at line 3\n")).unwrap();
+        assert_eq!(msg, "explain this\n\n<stdin>\npanic at line 3\n</stdin>");
+        assert_eq!(
+            compose_prompt(None, Some("just stdin")).unwrap(),
+            "just stdin"
+        );
+        assert_eq!(compose_prompt(Some("hi"), Some("  \n")).unwrap(), "hi");
+        assert!(compose_prompt(None, None).is_none());
+    }
+
+    #[test]
+    fn test_truncate_context_keeps_head_and_tail() {
+        let text = format!("{}{}", "a".repeat(100), "b".repeat(100));
+        let out = truncate_context(&text, 40);
+        assert!(out.starts_with(&"a".repeat(20)));
+        assert!(out.ends_with(&"b".repeat(20)));
+        assert!(out.contains("[... 160 bytes omitted ...]"));
+        assert_eq!(truncate_context("short", 40), "short");
+    }
+
+    #[test]
+    fn test_hand_off_spec_and_saved_model() {
+        let llm = LlmConfig {
+            openai_api_key: "sk-test".to_string(),
+            ..LlmConfig::default()
+        };
+        assert!(provider_client(&llm, "openai", "gpt-4o").is_ok());
+        let err = provider_client(&llm, "mistral", "").err().unwrap();
+        assert!(err.to_string().contains("Unknown provider 'mistral'"));
+
+        let mut session = Session::new("default");
+        assert_eq!(saved_model(&session), None);
+        session
+            .metadata
+            .insert("provider".to_string(), "openai".into());
+        session.metadata.insert("model".to_string(), "".into());
+        assert_eq!(saved_model(&session).as_deref(), Some("openai/"));
     }
 }
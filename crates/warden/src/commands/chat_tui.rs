@@ -0,0 +1,622 @@
+//! Full-screen terminal UI for `warden chat --tui`.
+//!
+//! The agent runs in a background task that owns the `Agent` and `SessionStore`;
+//! the UI loop only renders state and forwards commands, so the screen stays
+//! responsive while the LLM and tools are working.
+
+use anyhow::Result;
+use operon_runtime::{Agent, AgentEvent, Content, Message, Role, Session, SessionBackend};
+use ratatui::crossterm::event::{self, Event, KeyCode, KeyEvent, KeyEventKind, KeyModifiers};
+use ratatui::layout::{Constraint, Layout, Position};
+use ratatui::style::{Color, Modifier, Style};
+use ratatui::text::{Line, Span};
+use ratatui::widgets::{Block, List, ListItem, ListState, Paragraph};
+use ratatui::Frame;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::mpsc;
+
+/// Max tool activity entries kept in the side panel
+const MAX_ACTIVITY: usize = 200;
+
+/// Commands sent from the UI to the agent worker
+enum Command {
+    Send(String),
+    /// Switch to a stored session (None = start a new one)
+    Switch(Option<String>),
+    Quit,
+}
+
+/// Results sent from the agent worker back to the UI
+enum Update {
+    Reply(std::result::Result<String, String>),
+    Loaded {
+        id: String,
+        messages: Vec<Message>,
+        sessions: Vec<String>,
+    },
+    Failed(String),
+    Closed,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Speaker {
+    User,
+    Assistant,
+    Error,
+}
+
+struct Entry {
+    speaker: Speaker,
+    text: String,
+}
+
+enum Activity {
+    Started {
+        name: String,
+        input: String,
+    },
+    Finished {
+        name: String,
+        summary: String,
+        is_error: bool,
+    },
+}
+
+struct App {
+    agent_name: String,
+    session_id: String,
+    sessions: Vec<String>,
+    transcript: Vec<Entry>,
+    activity: Vec<Activity>,
+    input: String,
+    status: String,
+    busy: bool,
+    /// Whether the last assistant entry is still receiving text for this turn
+    streaming: bool,
+    /// Lines scrolled up from the bottom of the conversation (0 = follow output)
+    scroll: usize,
+    quitting: bool,
+}
+
+/// Run the TUI until the user quits; the current session is saved on exit
+pub async fn run(agent: Agent, store: Arc<dyn SessionBackend>) -> Result<()> {
+    let (event_tx, mut event_rx) = mpsc::unbounded_channel();
+    let agent = agent.with_event_sender(event_tx);
+
+    let mut app = App {
+        agent_name: agent.config.name.clone(),
+        session_id: agent.session.id.clone(),
+        sessions: sorted_sessions(store.as_ref()).await,
+        transcript: transcript_from_messages(&agent.session.messages),
+        activity: Vec::new(),
+        input: String::new(),
+        status: "Ready".to_string(),
+        busy: false,
+        streaming: false,
+        scroll: 0,
+        quitting: false,
+    };
+
+    let (cmd_tx, cmd_rx) = mpsc::unbounded_channel();
+    let (update_tx, mut update_rx) = mpsc::unbounded_channel();
+    tokio::spawn(run_worker(agent, store, cmd_rx, update_tx));
+
+    // Terminal input is read on a plain thread so a pending read never blocks shutdown
+    let (key_tx, mut key_rx) = mpsc::unbounded_channel();
+    let stop = Arc::new(AtomicBool::new(false));
+    let reader_stop = stop.clone();
+    std::thread::spawn(move || {
+        while !reader_stop.load(Ordering::Relaxed) {
+            if !event::poll(Duration::from_millis(100)).unwrap_or(false) {
+                continue;
+            }
+            if let Ok(Event::Key(key)) = event::read() {
+                if key.kind == KeyEventKind::Press && key_tx.send(key).is_err() {
+                    break;
+                }
+            }
+        }
+    });
+
+    let mut terminal = ratatui::init();
+    let result = loop {
+        if let Err(e) = terminal.draw(|frame| app.render(frame)) {
+            break Err(e.into());
+        }
+
+        tokio::select! {
+            Some(key) = key_rx.recv() => {
+                if app.handle_key(key, &cmd_tx) {
+                    break Ok(());
+                }
+            }
+            Some(event) = event_rx.recv() => app.on_agent_event(event),
+            Some(update) = update_rx.recv() => {
+                if app.on_update(update) {
+                    break Ok(());
+                }
+            }
+            else => break Ok(()),
+        }
+    };
+
+    stop.store(true, Ordering::Relaxed);
+    ratatui::restore();
+    println!("Session saved: {}", app.session_id);
+    result
+}
+
+/// Owns the agent; processes UI commands one at a time
+async fn run_worker(
+    mut agent: Agent,
+    store: Arc<dyn SessionBackend>,
+    mut commands: mpsc::UnboundedReceiver<Command>,
+    updates: mpsc::UnboundedSender<Update>,
+) {
+    while let Some(command) = commands.recv().await {
+        match command {
+            Command::Send(text) => {
+                let reply = agent
+                    .process_message(&text)
+                    .await
+                    .map_err(|e| format!("{:#}", e));
+                let _ = updates.send(Update::Reply(reply));
+            }
+            Command::Switch(target) => {
+                if let Err(e) = store.save(&agent.session).await {
+                    let _ = updates.send(Update::Failed(format!("Save failed: {:#}", e)));
+                    continue;
+                }
+                let session = match target {
+                    Some(id) => match store.load(&id).await {
+                        Ok(session) => session,
+                        Err(e) => {
+                            let _ = updates.send(Update::Failed(format!("{:#}", e)));
+                            continue;
+                        }
+                    },
+                    None => Session::new(&agent.config.name),
+                };
+                agent.session = session;
+                // Persist immediately so new sessions show up in the list
+                let _ = store.save(&agent.session).await;
+                let _ = updates.send(Update::Loaded {
+                    id: agent.session.id.clone(),
+                    messages: agent.session.messages.clone(),
+                    sessions: sorted_sessions(store.as_ref()).await,
+                });
+            }
+            Command::Quit => {
+                if let Err(e) = store.save(&agent.session).await {
+                    tracing::error!("Failed to save session: {}", e);
+                }
+                if let Err(e) = agent.end_session().await {
+                    tracing::error!("Failed to end session: {}", e);
+                }
+                let _ = updates.send(Update::Closed);
+                break;
+            }
+        }
+    }
+}
+
+impl App {
+    /// Handle a key press; returns true when the UI should exit immediately
+    fn handle_key(&mut self, key: KeyEvent, commands: &mpsc::UnboundedSender<Command>) -> bool {
+        let ctrl = key.modifiers.contains(KeyModifiers::CONTROL);
+        match key.code {
+            KeyCode::Esc => return self.request_quit(commands),
+            KeyCode::Char('c') if ctrl => return self.request_quit(commands),
+            KeyCode::Char('n') if ctrl && !self.busy => {
+                self.status = "Starting new session...".to_string();
+                let _ = commands.send(Command::Switch(None));
+            }
+            KeyCode::Tab => self.switch_relative(1, commands),
+            KeyCode::BackTab => self.switch_relative(-1, commands),
+            KeyCode::PageUp => self.scroll = self.scroll.saturating_add(10),
+            KeyCode::PageDown => self.scroll = self.scroll.saturating_sub(10),
+            KeyCode::Up => self.scroll = self.scroll.saturating_add(1),
+            KeyCode::Down => self.scroll = self.scroll.saturating_sub(1),
+            KeyCode::Backspace => {
+                self.input.pop();
+            }
+            KeyCode::Enter => {
+                let text = self.input.trim().to_string();
+                if text.is_empty() || self.busy || self.quitting {
+                    return false;
+                }
+                self.input.clear();
+                if text == "exit" || text == "quit" {
+                    return self.request_quit(commands);
+                }
+                self.transcript.push(Entry {
+                    speaker: Speaker::User,
+                    text: text.clone(),
+                });
+                self.busy = true;
+                self.streaming = false;
+                self.scroll = 0;
+                self.status = "Thinking...".to_string();
+                let _ = commands.send(Command::Send(text));
+            }
+            KeyCode::Char(c) if !ctrl => self.input.push(c),
+            _ => {}
+        }
+        false
+    }
+
+    /// Ask the worker to save and stop; a second request exits without waiting
+    /// (e.g. while a long turn is still running)
+    fn request_quit(&mut self, commands: &mpsc::UnboundedSender<Command>) -> bool {
+        if self.quitting {
+            return true;
+        }
+        self.quitting = true;
+        self.status = "Saving session...".to_string();
+        let _ = commands.send(Command::Quit);
+        false
+    }
+
+    /// Load the session `offset` positions away from the current one in the list
+    fn switch_relative(&mut self, offset: isize, commands: &mpsc::UnboundedSender<Command>) {
+        if self.busy || self.sessions.is_empty() {
+            return;
+        }
+        let len = self.sessions.len() as isize;
+        let current = self
+            .sessions
+            .iter()
+            .position(|id| *id == self.session_id)
+            .map(|i| i as isize)
+            .unwrap_or(-1);
+        let next = (current + offset).rem_euclid(len) as usize;
+        let target = self.sessions[next].clone();
+        if target != self.session_id {
+            self.status = format!("Loading session {}...", short_id(&target));
+            let _ = commands.send(Command::Switch(Some(target)));
+        }
+    }
+
+    fn on_agent_event(&mut self, event: AgentEvent) {
+        match event {
+            AgentEvent::TextDelta(text) => {
+                match self.transcript.last_mut() {
+                    Some(entry) if self.streaming && entry.speaker == Speaker::Assistant => {
+                        entry.text.push_str(&text);
+                    }
+                    _ => self.transcript.push(Entry {
+                        speaker: Speaker::Assistant,
+                        text,
+                    }),
+                }
+                self.streaming = true;
+            }
+            AgentEvent::ToolCallStarted { name, input, .. } => {
+                self.streaming = false;
+                self.status = format!("Running {}...", name);
+                self.push_activity(Activity::Started {
+                    name,
+                    input: truncate(&input.to_string(), 120),
+                });
+            }
+            AgentEvent::ToolCallFinished {
+                name,
+                output,
+                is_error,
+                ..
+            } => {
+                self.status = "Thinking...".to_string();
+                let summary = if is_error {
+                    truncate(&output, 120)
+                } else {
+                    format!("{} bytes", output.len())
+                };
+                self.push_activity(Activity::Finished {
+                    name,
+                    summary,
+                    is_error,
+                });
+            }
+            // The reply and errors come back through `Update::Reply`
+            AgentEvent::Thinking(_) | AgentEvent::Done { .. } | AgentEvent::Error(_) => {}
+        }
+    }
+
+    /// Apply a worker update; returns true when the worker has shut down
+    fn on_update(&mut self, update: Update) -> bool {
+        match update {
+            Update::Reply(Ok(text)) => {
+                // The final text normally already arrived as a TextDelta
+                let shown = matches!(self.transcript.last(), Some(e) if e.speaker == Speaker::Assistant && self.streaming);
+                if !shown && !text.is_empty() {
+                    self.transcript.push(Entry {
+                        speaker: Speaker::Assistant,
+                        text,
+                    });
+                }
+                self.busy = false;
+                self.streaming = false;
+                self.status = "Ready".to_string();
+            }
+            Update::Reply(Err(err)) => {
+                self.transcript.push(Entry {
+                    speaker: Speaker::Error,
+                    text: err,
+                });
+                self.busy = false;
+                self.streaming = false;
+                self.status = "Ready".to_string();
+            }
+            Update::Loaded {
+                id,
+                messages,
+                sessions,
+            } => {
+                self.status = format!("Loaded session {}", short_id(&id));
+                self.session_id = id;
+                self.sessions = sessions;
+                self.transcript = transcript_from_messages(&messages);
+                self.activity.clear();
+                self.scroll = 0;
+            }
+            Update::Failed(err) => self.status = err,
+            Update::Closed => return true,
+        }
+        false
+    }
+
+    fn push_activity(&mut self, activity: Activity) {
+        self.activity.push(activity);
+        if self.activity.len() > MAX_ACTIVITY {
+            self.activity.remove(0);
+        }
+    }
+
+    fn render(&self, frame: &mut Frame) {
+        let [main, input_area, status_area] = Layout::vertical([
+            Constraint::Min(5),
+            Constraint::Length(3),
+            Constraint::Length(1),
+        ])
+        .areas(frame.area());
+        let [sessions_area, chat_area, activity_area] = Layout::horizontal([
+            Constraint::Length(22),
+            Constraint::Min(30),
+            Constraint::Length(40),
+        ])
+        .areas(main);
+
+        // Sessions
+        let items: Vec<ListItem> = self
+            .sessions
+            .iter()
+            .map(|id| ListItem::new(short_id(id)))
+            .collect();
+        let mut list_state = ListState::default()
+            .with_selected(self.sessions.iter().position(|id| *id == self.session_id));
+        frame.render_stateful_widget(
+            List::new(items)
+                .block(Block::bordered().title(" Sessions "))
+                .highlight_style(Style::default().add_modifier(Modifier::REVERSED)),
+            sessions_area,
+            &mut list_state,
+        );
+
+        // Conversation with manual wrapping so scrolling is line-accurate
+        let width = chat_area.width.saturating_sub(2) as usize;
+        let height = chat_area.height.saturating_sub(2) as usize;
+        let lines = self.transcript_lines(width);
+        let max_scroll = lines.len().saturating_sub(height);
+        let scroll = self.scroll.min(max_scroll);
+        let start = max_scroll - scroll;
+        let visible: Vec<Line> = lines.into_iter().skip(start).take(height).collect();
+        let title = if scroll > 0 {
+            format!(" {} (scrolled {} lines) ", self.agent_name, scroll)
+        } else {
+            format!(" {} ", self.agent_name)
+        };
+        frame.render_widget(
+            Paragraph::new(visible).block(Block::bordered().title(title)),
+            chat_area,
+        );
+
+        // Tool activity (newest at the bottom)
+        let activity_height = activity_area.height.saturating_sub(2) as usize;
+        let activity: Vec<ListItem> = self
+            .activity
+            .iter()
+            .skip(self.activity.len().saturating_sub(activity_height))
+            .map(|a| match a {
+                Activity::Started { name, input } => ListItem::new(Line::from(vec![
+                    Span::styled("▶ ", Style::default().fg(Color::Yellow)),
+                    Span::styled(name.clone(), Style::default().add_modifier(Modifier::BOLD)),
+                    Span::raw(format!(" {}", input)),
+                ])),
+                Activity::Finished {
+                    name,
+                    summary,
+                    is_error,
+                } => {
+                    let (mark, color) = if *is_error {
+                        ("✗ ", Color::Red)
+                    } else {
+                        ("✓ ", Color::Green)
+                    };
+                    ListItem::new(Line::from(vec![
+                        Span::styled(mark, Style::default().fg(color)),
+                        Span::styled(name.clone(), Style::default().add_modifier(Modifier::BOLD)),
+                        Span::raw(format!(" {}", summary)),
+                    ]))
+                }
+            })
+            .collect();
+        frame.render_widget(
+            List::new(activity).block(Block::bordered().title(" Tool activity ")),
+            activity_area,
+        );
+
+        // Input box
+        let input_title = if self.busy {
+            " Waiting for agent... "
+        } else {
+            " Message (Enter to send) "
+        };
+        let input_width = input_area.width.saturating_sub(2) as usize;
+        let input_chars = self.input.chars().count();
+        let visible_input: String = self
+            .input
+            .chars()
+            .skip(input_chars.saturating_sub(input_width.saturating_sub(1)))
+            .collect();
+        let cursor_x = visible_input.chars().count() as u16;
+        frame.render_widget(
+            Paragraph::new(visible_input).block(Block::bordered().title(input_title)),
+            input_area,
+        );
+        if !self.busy {
+            frame.set_cursor_position(Position::new(input_area.x + 1 + cursor_x, input_area.y + 1));
+        }
+
+        // Status line
+        let status = Line::from(vec![
+            Span::styled(
+                format!(" session {} ", short_id(&self.session_id)),
+                Style::default().add_modifier(Modifier::REVERSED),
+            ),
+            Span::raw(format!(" {} ", self.status)),
+            Span::styled(
+                "│ PgUp/PgDn scroll · Tab switch session · Ctrl-N new · Esc quit",
+                Style::default().fg(Color::DarkGray),
+            ),
+        ]);
+        frame.render_widget(Paragraph::new(status), status_area);
+    }
+
+    fn transcript_lines(&self, width: usize) -> Vec<Line<'static>> {
+        let mut lines = Vec::new();
+        for entry in &self.transcript {
+            let (label, color) = match entry.speaker {
+                Speaker::User => ("You", Color::Cyan),
+                Speaker::Assistant => ("Assistant", Color::Green),
+                Speaker::Error => ("Error", Color::Red),
+            };
+            lines.push(Line::from(Span::styled(
+                label,
+                Style::default().fg(color).add_modifier(Modifier::BOLD),
+            )));
+            for line in wrap_text(&entry.text, width) {
+                lines.push(Line::from(line));
+            }
+            lines.push(Line::default());
+        }
+        lines
+    }
+}
+
+/// Rebuild the visible conversation from stored session messages
+fn transcript_from_messages(messages: &[Message]) -> Vec<Entry> {
+    messages
+        .iter()
+        .filter_map(|msg| {
+            let speaker = match msg.role {
+                Role::User => Speaker::User,
+                Role::Assistant => Speaker::Assistant,
+                Role::System => return None,
+            };
+            // Tool results are stored as user messages; keep them out of the transcript
+            if matches!(msg.content, Content::ToolResult(_)) {
+                return None;
+            }
+            let text = msg.content.extract_text();
+            (!text.is_empty()).then_some(Entry { speaker, text })
+        })
+        .collect()
+}
+
+async fn sorted_sessions(store: &dyn SessionBackend) -> Vec<String> {
+    let mut sessions = store.list_sessions().await.unwrap_or_default();
+    sessions.sort();
+    sessions
+}
+
+fn short_id(id: &str) -> String {
+    id.chars().take(8).collect()
+}
+
+fn truncate(s: &str, max: usize) -> String {
+    if s.chars().count() <= max {
+        s.to_string()
+    } else {
+        let cut: String = s.chars().take(max).collect();
+        format!("{}…", cut)
+    }
+}
+
+/// Greedy word wrap by character count; words longer than `width` are split
+fn wrap_text(text: &str, width: usize) -> Vec<String> {
+    let width = width.max(1);
+    let mut out = Vec::new();
+    for raw in text.split('\n') {
+        let mut line = String::new();
+        let mut len = 0;
+        for word in raw.split_inclusive(' ') {
+            let word_len = word.chars().count();
+            if len > 0 && len + word_len > width {
+                out.push(std::mem::take(&mut line));
+                len = 0;
+            }
+            if word_len > width {
+                for ch in word.chars() {
+                    if len == width {
+                        out.push(std::mem::take(&mut line));
+                        len = 0;
+                    }
+                    line.push(ch);
+                    len += 1;
+                }
+            } else {
+                line.push_str(word);
+                len += word_len;
+            }
+        }
+        out.push(line);
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_wrap_text() {
+        assert_eq!(wrap_text("hello world", 6), vec!["hello ", "world"]);
+        assert_eq!(wrap_text("abcdefgh", 3), vec!["abc", "def", "gh"]);
+        assert_eq!(wrap_text("a\n\nb", 10), vec!["a", "", "b"]);
+    }
+
+    #[test]
+    fn test_transcript_skips_tool_results() {
+        let messages = vec![
+            Message::user("hi"),
+            Message {
+                role: Role::User,
+                content: Content::ToolResult(operon_runtime::ToolResult {
+                    tool_use_id: "t1".into(),
+                    name: "shell".into(),
+                    output: "ok".into(),
+                    is_error: false,
+                }),
+            },
+            Message::assistant(Content::Text {
+                text: "hello".into(),
+            }),
+        ];
+        let transcript = transcript_from_messages(&messages);
+        assert_eq!(transcript.len(), 2);
+        assert_eq!(transcript[0].speaker, Speaker::User);
+        assert_eq!(transcript[1].text, "hello");
+    }
+}
@@ -0,0 +1,54 @@
+use crate::cli::OutputFormat;
+use crate::config::{config_schema, load_toml, parse_config, unknown_keys};
+use anyhow::Result;
+use std::path::{Path, PathBuf};
+
+/// Config subcommand actions
+pub enum ConfigAction {
+    Validate(PathBuf),
+    Schema,
+}
+
+pub fn execute(action: ConfigAction, output: OutputFormat) -> Result<()> {
+    match action {
+        ConfigAction::Validate(path) => validate(&path, output),
+        ConfigAction::Schema => super::print_json(&config_schema()),
+    }
+}
+
+/// Validate a config file as written (environment overrides are not applied)
+fn validate(path: &Path, output: OutputFormat) -> Result<()> {
+    let value = load_toml(path)?;
+    let warnings: Vec<String> = unknown_keys(value.clone())
+        .into_iter()
+        .map(|key| format!("unknown key '{}'", key))
+        .collect();
+    let errors = match parse_config(value) {
+        Ok(config) => config.validation_errors(),
+        Err(e) => vec![e.to_string()],
+    };
+
+    if output == OutputFormat::Json {
+        super::print_json(&serde_json::json!({
+            "path": path,
+            "valid": errors.is_empty(),
+            "errors": errors,
+            "warnings": warnings,
+        }))?;
+    } else {
+        for warning in &warnings {
+            eprintln!("warning: {}", warning);
+        }
+        for err in &errors {
+            eprintln!("error: {}", err);
+        }
+    }
+
+    if !errors.is_empty() {
+        anyhow::bail!("{} config error(s) in {:?}", errors.len(), path);
+    }
+    if output == OutputFormat::Text {
+        println!("Config OK: {:?}", path);
+    }
+    Ok(())
+}
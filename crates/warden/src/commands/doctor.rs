@@ -0,0 +1,44 @@
+use crate::cli::OutputFormat;
+use crate::commands::chat::build_provider;
+use crate::config::Config;
+use anyhow::{bail, Result};
+use std::path::Path;
+
+/// Report config source and probe every configured LLM provider
+pub async fn execute(
+    config: &Config,
+    config_path: Option<&Path>,
+    output: OutputFormat,
+) -> Result<()> {
+    let provider = build_provider(config)?;
+    let report = provider.health_report().await;
+    let failed = report.iter().filter(|h| !h.healthy).count();
+
+    if output == OutputFormat::Json {
+        super::print_json(&serde_json::json!({
+            "config": config_path,
+            "healthy": failed == 0,
+            "providers": report,
+        }))?;
+    } else {
+        match config_path {
+            Some(path) => println!("Config: {} (OK)", path.display()),
+            None => println!("Config: built-in defaults"),
+        }
+        for health in &report {
+            match &health.error {
+                None => println!("  ok    {} ({} ms)", health.model, health.latency_ms),
+                Some(err) => println!("  FAIL  {}: {}", health.model, err),
+            }
+        }
+    }
+
+    if failed > 0 {
+        bail!(
+            "{} of {} LLM provider(s) failed health check",
+            failed,
+            report.len()
+        );
+    }
+    Ok(())
+}
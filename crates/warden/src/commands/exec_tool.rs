@@ -0,0 +1,69 @@
+use crate::config::Config;
+use anyhow::{Context, Result};
+use operon_adapters::register_filesystem_tools;
+use operon_runtime::Runtime;
+use serde_json::{json, Value};
+use std::io::Read;
+use std::path::PathBuf;
+use std::time::Duration;
+
+/// Container side of `ContainerBackend`: run one built-in tool with JSON
+/// input read from stdin and print `{"output": ...}` or `{"error": "..."}`.
+/// Always executes; the calling warden already applied dry-run and policy.
+/// `timeout_secs` is the caller's timeout for `tool`, enforced here so a
+/// timed-out call stops inside the container too.
+pub async fn execute(tool: &str, timeout_secs: Option<u64>, config: &Config) -> Result<()> {
+    let mut input = String::new();
+    std::io::stdin()
+        .read_to_string(&mut input)
+        .context("Failed to read tool input")?;
+
+    // Scratch state DB so calls leave nothing in the mounted workspace
+    let db_path = std::env::temp_dir().join(format!("warden-exec-tool-{}.db", std::process::id()));
+    let timeout = timeout_secs.map(Duration::from_secs);
+    let result = run(tool, &input, timeout, config, &db_path).await;
+    let _ = std::fs::remove_file(&db_path);
+
+    let response = match result {
+        Ok(output) => json!({ "output": output }),
+        Err(e) => json!({ "error": format!("{:#}", e) }),
+    };
+    println!("{}", response);
+    Ok(())
+}
+
+async fn run(
+    tool: &str,
+    input: &str,
+    timeout: Option<Duration>,
+    config: &Config,
+    db_path: &std::path::Path,
+) -> Result<Value> {
+    let input: Value = serde_json::from_str(input).context("Tool input is not valid JSON")?;
+    let db_path = db_path.to_str().context("Non-UTF-8 temp directory")?;
+    let runtime = Runtime::with_db(
+        db_path,
+        false,
+        Duration::from_secs(config.runtime.timeout_secs),
+    )?;
+    if config.tools.shell.enabled {
+        let shell_timeout = match timeout {
+            Some(timeout) if tool == "shell" => timeout,
+            _ => super::tool_timeout(config, "shell"),
+        };
+        super::register_shell(&runtime, config, false, shell_timeout)?;
+    }
+    if config.tools.filesystem.enabled {
+        register_filesystem_tools(
+            &runtime,
+            PathBuf::from(&config.tools.filesystem.workspace),
+            config.tools.filesystem.max_file_size_mb,
+            config.tools.filesystem.write_limits(),
+            None,
+        )?;
+    }
+    if let Some(timeout) = timeout.filter(|_| tool != "shell") {
+        runtime.configure_timeout(tool.to_string(), timeout);
+    }
+    runtime.execute_tool(tool, input).await
+}
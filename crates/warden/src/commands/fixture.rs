@@ -0,0 +1,234 @@
+use crate::cli::OutputFormat;
+use anyhow::{bail, Context, Result};
+use operon_runtime::{Fixture, StepRecord, FIXTURE_VERSION};
+use serde_json::{json, Value};
+use std::path::Path;
+
+/// Change made by `warden fixture edit`
+pub enum StepEdit {
+    /// Replace the recorded output (JSON text) and clear any error
+    Output(String),
+    /// Make the step fail on replay with this message
+    Error(String),
+}
+
+/// Load a fixture that will be written back; newer formats are refused since
+/// saving would drop fields this release does not know
+fn load_editable(dir: &Path) -> Result<Fixture> {
+    let fixture = Fixture::load(dir)?;
+    if fixture.version > FIXTURE_VERSION {
+        bail!(
+            "Fixture version {} was written by a newer release; edit it with that release",
+            fixture.version
+        );
+    }
+    Ok(fixture)
+}
+
+fn step_summary(record: &StepRecord) -> String {
+    match &record.error {
+        Some(error) => format!("error: {}", error),
+        None => format!("output {} bytes", record.output.to_string().len()),
+    }
+}
+
+/// `warden fixture show`: list the recorded steps, or one step in full
+pub fn show(dir: &Path, step: Option<usize>, output: OutputFormat) -> Result<()> {
+    let mut fixture = Fixture::load(dir)?;
+    if let Some(index) = step {
+        let record = fixture.step_mut(index)?;
+        if output == OutputFormat::Json {
+            return super::print_json(record);
+        }
+        println!(
+            "Step {} ({}, {} ms)",
+            record.index, record.tool, record.duration_ms
+        );
+        println!("Input:\n{}", serde_json::to_string_pretty(&record.input)?);
+        println!("Output:\n{}", serde_json::to_string_pretty(&record.output)?);
+        if let Some(error) = &record.error {
+            println!("Error (replayed instead of the output): {}", error);
+        }
+        return Ok(());
+    }
+
+    if output == OutputFormat::Json {
+        return super::print_json(&fixture);
+    }
+    println!(
+        "Fixture '{}' (version {}, recorded {}): {} steps",
+        fixture.plan_id,
+        fixture.version,
+        fixture.recorded_at,
+        fixture.steps.len()
+    );
+    fixture.steps.sort_by_key(|r| r.index);
+    for record in &fixture.steps {
+        println!(
+            "  #{:<3} {:<20} {:>6} ms  {}",
+            record.index,
+            record.tool,
+            record.duration_ms,
+            step_summary(record)
+        );
+    }
+    Ok(())
+}
+
+/// `warden fixture edit`: change what one step replays
+pub fn edit(dir: &Path, step: usize, change: StepEdit, output: OutputFormat) -> Result<()> {
+    let mut fixture = load_editable(dir)?;
+    let record = fixture.step_mut(step)?;
+    match change {
+        StepEdit::Output(text) => {
+            record.output =
+                serde_json::from_str::<Value>(&text).context("--set-output must be valid JSON")?;
+            record.error = None;
+        }
+        StepEdit::Error(message) => record.error = Some(message),
+    }
+    let summary = step_summary(record);
+    let record = json!(record);
+    fixture.save(dir)?;
+
+    if output == OutputFormat::Json {
+        return super::print_json(&record);
+    }
+    println!("Step {} now replays {}", step, summary);
+    Ok(())
+}
+
+/// `warden fixture scrub`: shrink strings over `max_bytes` in step outputs
+pub fn scrub(dir: &Path, max_bytes: usize, output: OutputFormat) -> Result<()> {
+    let mut fixture = load_editable(dir)?;
+    let stats = fixture.scrub(max_bytes);
+    if stats.values > 0 {
+        fixture.save(dir)?;
+    }
+
+    if output == OutputFormat::Json {
+        return super::print_json(&stats);
+    }
+    println!(
+        "Scrubbed {} values ({} KiB) longer than {} bytes",
+        stats.values,
+        stats.bytes / 1024,
+        max_bytes
+    );
+    Ok(())
+}
+
+/// `warden fixture migrate`: rewrite an older fixture in the current format
+pub fn migrate(dir: &Path, output: OutputFormat) -> Result<()> {
+    let from = Fixture::upgrade(dir)?;
+    if output == OutputFormat::Json {
+        return super::print_json(&json!({
+            "dir": dir,
+            "migrated_from": from,
+            "version": FIXTURE_VERSION,
+        }));
+    }
+    match from {
+        Some(from) => println!(
+            "Migrated {} from fixture version {} to {}",
+            dir.display(),
+            from,
+            FIXTURE_VERSION
+        ),
+        None => println!(
+            "{} is already at fixture version {} or newer",
+            dir.display(),
+            FIXTURE_VERSION
+        ),
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn recorded(dir: &Path) {
+        let mut fixture = Fixture::new("plan".to_string());
+        fixture.steps.push(StepRecord {
+            index: 0,
+            tool: "http".to_string(),
+            input: json!({"url": "https://example.com"}),
+            output: json!({"body": "x".repeat(5000)}),
+            duration_ms: 12,
+            error: None,
+        });
+        fixture.save(dir).unwrap();
+    }
+
+    #[test]
+    fn test_edit_and_scrub_rewrite_the_fixture() {
+        let dir = tempfile::tempdir().unwrap();
+        recorded(dir.path());
+
+        scrub(dir.path(), 100, OutputFormat::Json).unwrap();
+        let fixture = Fixture::load(dir.path()).unwrap();
+        assert_eq!(
+            fixture.steps[0].output,
+            json!({"body": "<scrubbed 5000 bytes>"})
+        );
+
+        edit(
+            dir.path(),
+            0,
+            StepEdit::Error("HTTP 503".to_string()),
+            OutputFormat::Json,
+        )
+        .unwrap();
+        assert_eq!(
+            Fixture::load(dir.path()).unwrap().steps[0].error.as_deref(),
+            Some("HTTP 503")
+        );
+
+        edit(
+            dir.path(),
+            0,
+            StepEdit::Output(r#"{"status": 200}"#.to_string()),
+            OutputFormat::Json,
+        )
+        .unwrap();
+        let step = &Fixture::load(dir.path()).unwrap().steps[0];
+        assert_eq!(
+            (step.output.clone(), step.error.clone()),
+            (json!({"status": 200}), None)
+        );
+
+        assert!(edit(
+            dir.path(),
+            0,
+            StepEdit::Output("{".to_string()),
+            OutputFormat::Json
+        )
+        .is_err());
+        assert!(edit(
+            dir.path(),
+            3,
+            StepEdit::Error("x".to_string()),
+            OutputFormat::Json
+        )
+        .is_err());
+    }
+
+    #[test]
+    fn test_newer_fixtures_are_not_rewritten() {
+        let dir = tempfile::tempdir().unwrap();
+        let newer = json!({
+            "version": FIXTURE_VERSION + 1,
+            "plan_id": "plan",
+            "recorded_at": "0s",
+            "steps": [],
+            "signature": "keep-me"
+        });
+        std::fs::write(dir.path().join("fixture.json"), newer.to_string()).unwrap();
+
+        let err = scrub(dir.path(), 100, OutputFormat::Json).unwrap_err();
+        assert!(err.to_string().contains("newer release"), "{}", err);
+        let content = std::fs::read_to_string(dir.path().join("fixture.json")).unwrap();
+        assert!(content.contains("keep-me"));
+    }
+}
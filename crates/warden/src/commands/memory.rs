@@ -0,0 +1,158 @@
+use crate::cli::OutputFormat;
+use crate::config::Config;
+use anyhow::{bail, Result};
+use operon_runtime::memory::embedding::OpenAIEmbedding;
+use operon_runtime::memory::text_search::TextSearchIndex;
+use operon_runtime::memory::vector_store::VectorStore;
+use operon_runtime::memory::MemoryManager;
+use serde_json::json;
+use std::path::PathBuf;
+use std::sync::Arc;
+
+/// `[memory] db_path` with `~` expanded
+pub fn db_path(config: &Config) -> PathBuf {
+    PathBuf::from(shellexpand::tilde(&config.memory.db_path).to_string())
+}
+
+/// Embedding client for `[memory]` (model and dimensions overridable), or
+/// None without OPENAI_API_KEY / EMBEDDING_API_KEY
+pub fn embedder(
+    config: &Config,
+    model: Option<&str>,
+    dimensions: Option<usize>,
+) -> Option<OpenAIEmbedding> {
+    let key = std::env::var("OPENAI_API_KEY")
+        .or_else(|_| std::env::var("EMBEDDING_API_KEY"))
+        .ok()
+        .filter(|key| !key.is_empty())?;
+    Some(OpenAIEmbedding::new(&key).with_model(
+        model.unwrap_or(&config.memory.embedding_model),
+        dimensions.unwrap_or(config.memory.embedding_dimensions),
+    ))
+}
+
+/// Document and vector counts of the memory index. `verify` checks the
+/// database and every vector; `compact` also drops orphaned and corrupted
+/// vectors and rebuilds the indexes. Fails if problems remain.
+pub fn stats(config: &Config, verify: bool, compact: bool, output: OutputFormat) -> Result<()> {
+    let path = db_path(config);
+    if !path.exists() {
+        bail!("No memory index at {}", path.display());
+    }
+    let text_index = TextSearchIndex::new(&path)?;
+    let vector_store = VectorStore::new(&path, config.memory.embedding_dimensions)?;
+
+    let compacted = if compact {
+        let stats = vector_store.compact()?;
+        text_index.rebuild()?;
+        Some(stats)
+    } else {
+        None
+    };
+    let report = if verify || compact {
+        Some(vector_store.verify()?)
+    } else {
+        None
+    };
+    let documents = text_index.document_count()?;
+    let vectors = vector_store.count()?;
+    let embedding = vector_store.embedding_info()?;
+    let size = std::fs::metadata(&path).map(|m| m.len()).unwrap_or(0);
+
+    if output == OutputFormat::Json {
+        super::print_json(&json!({
+            "path": path,
+            "documents": documents,
+            "vectors": vectors,
+            "embedding": embedding,
+            "size_bytes": size,
+            "compacted": compacted,
+            "verify": report,
+        }))?;
+    } else {
+        println!("Memory index: {} ({} KiB)", path.display(), size / 1024);
+        println!("  documents: {}", documents);
+        println!("  vectors:   {}", vectors);
+        if let Some(info) = &embedding {
+            let model = if info.model.is_empty() {
+                "unrecorded model"
+            } else {
+                &info.model
+            };
+            println!("  embedding: {} ({} dims)", model, info.dimensions);
+        }
+        if let Some(stats) = &compacted {
+            println!(
+                "Compacted: removed {} orphaned and {} corrupted vectors, rebuilt indexes",
+                stats.orphaned_removed, stats.corrupted_removed
+            );
+        }
+        if let Some(report) = &report {
+            let lists = [
+                ("orphaned vectors", &report.orphaned),
+                ("corrupted vectors", &report.corrupted),
+                ("documents without vectors", &report.missing),
+                ("database errors", &report.database_errors),
+            ];
+            for (label, items) in lists.iter().filter(|(_, items)| !items.is_empty()) {
+                println!("  {}: {}", label, items.len());
+                for item in items.iter().take(10) {
+                    println!("    {}", item);
+                }
+            }
+            if report.is_ok() {
+                println!("Verify: ok");
+            }
+        }
+    }
+
+    if let Some(report) = report.filter(|r| !r.is_ok()) {
+        if !report.orphaned.is_empty() || !report.corrupted.is_empty() {
+            bail!("Memory index failed verification; `warden memory stats --compact` removes bad vectors");
+        }
+        bail!("Memory index failed verification; `warden memory migrate` re-embeds every document");
+    }
+    Ok(())
+}
+
+/// Re-embed every indexed document with `model`/`dimensions` (default: the
+/// configured ones) and swap the new vectors in atomically
+pub async fn migrate(
+    config: &Config,
+    model: Option<String>,
+    dimensions: Option<usize>,
+    output: OutputFormat,
+) -> Result<()> {
+    let path = db_path(config);
+    if !path.exists() {
+        bail!("No memory index at {}", path.display());
+    }
+    let Some(embedder) = embedder(config, model.as_deref(), dimensions) else {
+        bail!("No embedding API key found (OPENAI_API_KEY or EMBEDDING_API_KEY)");
+    };
+    let stats = MemoryManager::migrate(&path, Arc::new(embedder)).await?;
+
+    if output == OutputFormat::Json {
+        return super::print_json(&stats);
+    }
+    let from = match &stats.from {
+        Some(from) if !from.model.is_empty() => {
+            format!("{} ({} dims)", from.model, from.dimensions)
+        }
+        Some(from) => format!("{} dims", from.dimensions),
+        None => "an empty index".to_string(),
+    };
+    println!(
+        "Re-embedded {} documents from {} into {} ({} dims)",
+        stats.documents, from, stats.to.model, stats.to.dimensions
+    );
+    if stats.to.model != config.memory.embedding_model
+        || stats.to.dimensions != config.memory.embedding_dimensions
+    {
+        println!(
+            "Set embedding_model = \"{}\" and embedding_dimensions = {} under [memory] to use it",
+            stats.to.model, stats.to.dimensions
+        );
+    }
+    Ok(())
+}
@@ -1,5 +1,512 @@
+pub mod agent;
+pub mod audit;
+pub mod bench;
 pub mod chat;
+pub mod chat_tui;
+pub mod config;
+pub mod doctor;
+pub mod exec_tool;
+pub mod fixture;
 pub mod init;
+pub mod memory;
+pub mod plan;
 pub mod plugin;
+pub mod policy;
 pub mod run_plan;
 pub mod serve;
+pub mod session;
+pub mod workspace;
+
+use crate::config::Config;
+use anyhow::{Context, Result};
+use operon_adapters::{
+    CaptureTarget, ContainerBackend, EmailTool, GitHubTool, KubernetesJobTool, NotificationHook,
+    Notifier, NotifyTool, ScreenshotTool, ShellSandbox, SshTarget,
+};
+use operon_runtime::tool_policy::layers::{
+    AgentToolScopeLayer, ApprovalLayer, AuditLogLayer, ContextWindowLayer, DryRunGuardLayer,
+    InputValidationLayer, PermissionCheckLayer, RateLimitLayer, RedactionLayer, RuleEngineLayer,
+    TimeoutEnforceLayer, ToolExistenceLayer,
+};
+use operon_runtime::{
+    AgentConfig, ExecutionBackend, HookEvent, HookRegistry, PermissionLevel, PromptRegistry,
+    Redactor, Runtime, SessionBackend, SessionStore, SqliteSessionStore, ToolPolicyPipeline,
+};
+use serde::Serialize;
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::time::Duration;
+
+/// Print a command result as pretty JSON on stdout (for `--output json`)
+pub fn print_json<T: Serialize>(value: &T) -> Result<()> {
+    println!("{}", serde_json::to_string_pretty(value)?);
+    Ok(())
+}
+
+/// Settings of agent `name`: its `[agents.<name>]` definition, if any, over
+/// the defaults, with a `template:` system prompt rendered
+pub fn agent_config(config: &Config, name: &str) -> Result<AgentConfig> {
+    let mut agent = AgentConfig {
+        name: name.to_string(),
+        model: config.llm.model.clone(),
+        summarize_every: config.llm.summarize_every,
+        stream: config.llm.stream,
+        context: config.llm.context.clone(),
+        context_window: config.llm.context_window,
+        max_cost_usd: config.llm.max_cost_usd,
+        ..AgentConfig::default()
+    };
+    if let Some(profile) = config.agents.get(name) {
+        agent.tools = profile.tools.clone();
+        agent.max_permission = profile.max_permission.clone();
+        if let Some(model) = &profile.model {
+            agent.model = model.clone();
+        }
+        if let Some(system_prompt) = &profile.system_prompt {
+            let mut vars: HashMap<_, _> = profile.prompt_vars.clone().into_iter().collect();
+            vars.entry("agent".to_string())
+                .or_insert_with(|| name.to_string());
+            agent.system_prompt = PromptRegistry::new(chat::prompts_dir())
+                .resolve(system_prompt, &vars)
+                .with_context(|| format!("agents.{}.system_prompt", name))?;
+        }
+        if let Some(path) = &profile.examples {
+            let path = shellexpand::tilde(path).to_string();
+            let examples = std::fs::read_to_string(&path)
+                .with_context(|| format!("Failed to read examples file {}", path))?;
+            agent.examples = serde_json::from_str(&examples)
+                .with_context(|| format!("Invalid examples file {}", path))?;
+        }
+        if let Some(max_tokens) = profile.examples_max_tokens {
+            agent.examples_max_tokens = max_tokens;
+        }
+        if let Some(temperature) = profile.temperature {
+            agent.temperature = temperature;
+        }
+        if let Some(max_tokens) = profile.max_tokens {
+            agent.max_tokens = max_tokens;
+        }
+        if let Some(max_iterations) = profile.max_iterations {
+            agent.max_iterations = max_iterations;
+        }
+        if profile.max_cost_usd.is_some() {
+            agent.max_cost_usd = profile.max_cost_usd;
+        }
+    }
+    Ok(agent)
+}
+
+/// Redactor for `[redaction]`, if enabled
+pub fn redactor(config: &Config) -> Result<Option<Arc<Redactor>>> {
+    if !config.redaction.enabled {
+        return Ok(None);
+    }
+    let mut redactor = Redactor::new();
+    for (kind, pattern) in &config.redaction.patterns {
+        redactor = redactor.with_pattern(kind, pattern)?;
+    }
+    Ok(Some(Arc::new(redactor)))
+}
+
+/// Session store picked by `[sessions] store`, redacting what it saves when
+/// `[redaction]` is enabled
+pub fn session_store(config: &Config) -> Result<Arc<dyn SessionBackend>> {
+    let redactor = redactor(config)?;
+    Ok(match config.sessions.store.as_str() {
+        "sqlite" => {
+            let mut store = SqliteSessionStore::open(&chat::sessions_db_path())?;
+            if let Some(redactor) = redactor {
+                store = store.with_redactor(redactor);
+            }
+            Arc::new(store)
+        }
+        _ => {
+            let mut store = SessionStore::new(chat::sessions_dir())?;
+            if let Some(redactor) = redactor {
+                store = store.with_redactor(redactor);
+            }
+            Arc::new(store)
+        }
+    })
+}
+
+/// Build the shell sandbox from `[tools.shell.sandbox]`; none in dry-run mode,
+/// where no command runs
+pub fn shell_sandbox(config: &Config, dry_run: bool) -> Result<Option<ShellSandbox>> {
+    let sandbox = &config.tools.shell.sandbox;
+    if !sandbox.enabled || dry_run {
+        return Ok(None);
+    }
+    let workspace = sandbox
+        .workspace
+        .as_deref()
+        .unwrap_or(&config.tools.filesystem.workspace);
+    let shell_sandbox =
+        ShellSandbox::new(sandbox.program.parse()?, std::path::Path::new(workspace))?
+            .with_level(sandbox.level.clone());
+    shell_sandbox.check_available()?;
+    tracing::info!(
+        program = %sandbox.program,
+        level = ?sandbox.level,
+        workspace = %shell_sandbox.workspace().display(),
+        "Shell commands sandboxed"
+    );
+    Ok(Some(shell_sandbox))
+}
+
+/// `tool`'s timeout from `[tools.timeouts]`, else `runtime.timeout_secs`
+pub fn tool_timeout(config: &Config, tool: &str) -> Duration {
+    let secs = config
+        .tools
+        .timeouts
+        .get(tool)
+        .copied()
+        .unwrap_or(config.runtime.timeout_secs);
+    Duration::from_secs(secs)
+}
+
+/// Register the shell tool from `[tools.shell]`: validation lists, sandbox,
+/// and `timeout` after which a command is stopped (SIGTERM, then SIGKILL
+/// after the grace period)
+pub fn register_shell(
+    runtime: &Runtime,
+    config: &Config,
+    dry_run: bool,
+    timeout: Duration,
+) -> Result<()> {
+    operon_adapters::register_shell_tool(
+        runtime,
+        dry_run,
+        config.tools.shell.blocklist.clone(),
+        config.tools.shell.allowlist.clone(),
+        shell_sandbox(config, dry_run)?,
+        timeout,
+        Duration::from_secs(config.tools.shell.grace_period_secs),
+    )
+}
+
+/// Register the remote ssh tools from `[tools.ssh]`, if enabled. Remote
+/// commands share `[tools.shell]`'s blocklist and allowlist.
+pub fn register_ssh_tools(runtime: &Runtime, config: &Config, dry_run: bool) -> Result<()> {
+    let ssh = &config.tools.ssh;
+    if !ssh.enabled {
+        return Ok(());
+    }
+    let mut target = SshTarget::new(&ssh.host)
+        .with_port(ssh.port)
+        .with_remote_root(&ssh.remote_root)
+        .with_max_output_bytes(ssh.max_output_bytes);
+    if let Some(user) = &ssh.user {
+        target = target.with_user(user);
+    }
+    if let Some(identity_file) = &ssh.identity_file {
+        let identity_file = shellexpand::tilde(identity_file).to_string();
+        target = target.with_identity_file(std::path::Path::new(&identity_file));
+    }
+    operon_adapters::register_ssh_tools(
+        runtime,
+        target,
+        dry_run,
+        config.tools.shell.blocklist.clone(),
+        config.tools.shell.allowlist.clone(),
+    )?;
+    tracing::info!(host = %ssh.host, port = ssh.port, "Registered ssh tools");
+    Ok(())
+}
+
+/// Register the kubernetes_job tool from `[tools.kubernetes]`, if enabled
+pub fn register_kubernetes_tool(runtime: &Runtime, config: &Config) -> Result<()> {
+    let kubernetes = &config.tools.kubernetes;
+    if !kubernetes.enabled {
+        return Ok(());
+    }
+    let mut tool = KubernetesJobTool::new(&kubernetes.namespace)
+        .with_kubectl(&kubernetes.kubectl)
+        .with_resources(&kubernetes.cpu, &kubernetes.memory)
+        .with_timeout(std::time::Duration::from_secs(kubernetes.timeout_secs))
+        .with_max_log_bytes(kubernetes.max_log_bytes);
+    if let Some(context) = &kubernetes.context {
+        tool = tool.with_context(context);
+    }
+    if let Some(template) = &kubernetes.template {
+        let path = shellexpand::tilde(template).to_string();
+        let template = std::fs::read_to_string(&path)
+            .with_context(|| format!("Failed to read Kubernetes job template {}", path))?;
+        tool = tool.with_template(template);
+    }
+    // The tool waits out the job itself; the runtime timeout is only a backstop
+    runtime.configure_timeout("kubernetes_job".to_string(), tool.max_duration());
+    runtime.register_tool("kubernetes_job".to_string(), Arc::new(tool))?;
+    tracing::info!(namespace = %kubernetes.namespace, "Registered kubernetes_job tool");
+    Ok(())
+}
+
+/// Register the github tool from `[tools.github]`, if enabled. Pull requests
+/// are opened from the current branch of `tools.filesystem.workspace`.
+pub fn register_github_tool(runtime: &Runtime, config: &Config) -> Result<()> {
+    let github = &config.tools.github;
+    if !github.enabled {
+        return Ok(());
+    }
+    let mut tool = GitHubTool::new(&github.repo, &github.token)?
+        .with_workspace(std::path::Path::new(&config.tools.filesystem.workspace));
+    if let Some(api_url) = &github.api_url {
+        tool = tool.with_api_url(api_url);
+    }
+    if let Some(git_url) = &github.git_url {
+        tool = tool.with_git_url(git_url);
+    }
+    if let Some(base_branch) = &github.base_branch {
+        tool = tool.with_base_branch(base_branch);
+    }
+    if !github.actions.is_empty() {
+        tool = tool.with_actions(github.actions.clone())?;
+    }
+    runtime.register_tool("github".to_string(), Arc::new(tool))?;
+    tracing::info!(repo = %github.repo, "Registered github tool");
+    Ok(())
+}
+
+/// Register the email tool from `[tools.email]`, if enabled
+pub fn register_email_tool(runtime: &Runtime, config: &Config) -> Result<()> {
+    let email = &config.tools.email;
+    if !email.enabled {
+        return Ok(());
+    }
+    let mut tool = EmailTool::new(&email.host, &email.from)?
+        .with_security(email.security.parse()?)
+        .with_allowed_recipients(email.allowed_recipients.clone());
+    if let Some(port) = email.port {
+        tool = tool.with_port(port);
+    }
+    if let Some(username) = &email.username {
+        tool = tool.with_credentials(username, &email.password);
+    }
+    runtime.register_tool("email".to_string(), Arc::new(tool))?;
+    tracing::info!(relay = %email.host, "Registered email tool");
+    Ok(())
+}
+
+/// Register capture_screen and capture_window from `[tools.screenshot]`, if
+/// enabled. Both need Admin permission, so agents must allow it explicitly.
+pub fn register_screenshot_tools(runtime: &Runtime, config: &Config) -> Result<()> {
+    let screenshot = &config.tools.screenshot;
+    if !screenshot.enabled {
+        return Ok(());
+    }
+    for (target, command) in [
+        (CaptureTarget::Screen, &screenshot.screen_command),
+        (CaptureTarget::Window, &screenshot.window_command),
+    ] {
+        let mut tool = ScreenshotTool::new(target).with_max_bytes(screenshot.max_bytes);
+        if !command.is_empty() {
+            tool = tool.with_command(command.clone());
+        }
+        runtime.register_tool(tool.tool_name().to_string(), Arc::new(tool))?;
+    }
+    tracing::info!("Registered capture_screen and capture_window tools");
+    Ok(())
+}
+
+/// Wire `[notifications]` into `runtime`: the end-of-run summary hooks and
+/// the `notify` tool
+pub fn attach_notifications(mut runtime: Runtime, config: &Config) -> Result<Runtime> {
+    let notifications = &config.notifications;
+    if !notifications.enabled {
+        return Ok(runtime);
+    }
+    let notifier = Arc::new(Notifier::new(
+        notifications.kind.parse()?,
+        &notifications.webhook_url,
+    ));
+    let mut events = Vec::new();
+    if notifications.on_session_end {
+        events.push(HookEvent::SessionEnd);
+    }
+    if notifications.on_plan_end {
+        events.push(HookEvent::PlanEnd);
+    }
+    if !events.is_empty() {
+        let hooks = Arc::new(HookRegistry::new());
+        hooks.register(Arc::new(NotificationHook::new(notifier.clone(), events)));
+        runtime = runtime.with_hooks(hooks);
+    }
+    if notifications.tool {
+        runtime.register_tool("notify".to_string(), Arc::new(NotifyTool::new(notifier)))?;
+    }
+    tracing::info!(kind = %notifications.kind, "Notifications enabled");
+    Ok(runtime)
+}
+
+/// Container backend from `[runtime.container]`, if enabled
+pub fn execution_backend(config: &Config) -> Result<Option<Arc<dyn ExecutionBackend>>> {
+    let container = &config.runtime.container;
+    if !container.enabled {
+        return Ok(None);
+    }
+    let backend = ContainerBackend::new(
+        &container.engine,
+        &container.image,
+        container.tools.iter().cloned(),
+    )
+    .with_workspace(std::path::Path::new(&config.tools.filesystem.workspace))?
+    .with_entrypoint(container.entrypoint.clone())
+    .with_network(container.network)
+    .with_shell_validation(
+        config.tools.shell.blocklist.clone(),
+        config.tools.shell.allowlist.clone(),
+    )
+    .with_timeouts(
+        container
+            .tools
+            .iter()
+            .map(|tool| (tool.clone(), tool_timeout(config, tool)))
+            .collect(),
+    );
+    tracing::info!(
+        engine = %container.engine,
+        image = %container.image,
+        tools = ?container.tools,
+        "Tools run in per-session containers"
+    );
+    Ok(Some(Arc::new(backend)))
+}
+
+/// The `[tool_policy]` pipeline for `runtime`'s tools, or None when the
+/// policy is disabled; call after all tools are registered
+pub fn policy_pipeline(runtime: &Runtime, config: &Config) -> Result<Option<ToolPolicyPipeline>> {
+    if !config.tool_policy.enabled {
+        return Ok(None);
+    }
+    let tool_names = runtime.tool_names();
+    let mut pipeline =
+        ToolPolicyPipeline::new().add_layer(Box::new(ToolExistenceLayer::new(tool_names)));
+
+    if config.tool_policy.permission_enabled {
+        let default_perm = parse_permission_level(&config.tool_policy.default_permission);
+        pipeline = pipeline.add_layer(Box::new(PermissionCheckLayer::new(
+            runtime.tool_permissions(),
+            default_perm,
+        )));
+    }
+
+    if config.tool_policy.rate_limit_enabled {
+        let mut layer = RateLimitLayer::new(config.tool_policy.max_calls_per_minute);
+        if let Some(limit) = config.tool_policy.session_calls_per_minute {
+            layer = layer.with_session_limit(limit);
+        }
+        if let Some(limit) = config.tool_policy.principal_calls_per_minute {
+            layer = layer.with_principal_limit(limit);
+        }
+        pipeline = pipeline.add_layer(Box::new(layer));
+    }
+
+    if config.tool_policy.input_validation_enabled {
+        pipeline = pipeline.add_layer(Box::new(InputValidationLayer::new(
+            HashMap::new(), // TODO: populate from runtime tool schemas
+        )));
+    }
+
+    if config.tool_policy.dry_run_guard_enabled {
+        pipeline = pipeline.add_layer(Box::new(DryRunGuardLayer::new(
+            config.tool_policy.dry_run_bypass_tools.clone(),
+        )));
+    }
+
+    if config.tool_policy.audit_enabled {
+        pipeline = pipeline.add_layer(Box::new(AuditLogLayer::new()));
+    }
+
+    pipeline = pipeline.add_layer(Box::new(TimeoutEnforceLayer::new()));
+
+    if config.tool_policy.context_window_enabled {
+        pipeline = pipeline.add_layer(Box::new(ContextWindowLayer::new(
+            operon_runtime::estimator_for_model(&config.llm.model),
+        )));
+    }
+
+    if config.tool_policy.agent_scope_enabled {
+        let scopes = config
+            .agents
+            .iter()
+            .map(|(name, profile)| (name.clone(), profile.tools.clone()))
+            .collect();
+        pipeline = pipeline.add_layer(Box::new(AgentToolScopeLayer::new(scopes)));
+    }
+
+    if !config.tool_policy.rules.is_empty() {
+        let rules = RuleEngineLayer::new(config.tool_policy.rules.clone())
+            .context("Invalid tool_policy.rules")?;
+        for (rule, path) in rules.undeclared_input_paths(&runtime.tool_schemas()) {
+            tracing::warn!(
+                rule = %rule,
+                path = %path,
+                "Policy rule input path is not a parameter of any tool it covers; the rule never matches"
+            );
+        }
+        pipeline = pipeline.add_layer(Box::new(rules));
+    }
+
+    if !config.tool_policy.approval_tools.is_empty() {
+        pipeline = pipeline.add_layer(Box::new(ApprovalLayer::new(
+            config.tool_policy.approval_tools.clone(),
+        )));
+    }
+
+    let redaction = &config.tool_policy.redaction;
+    if redaction.enabled {
+        pipeline = pipeline.add_layer(Box::new(
+            RedactionLayer::new(&redaction.patterns)
+                .context("Invalid tool_policy.redaction.patterns")?,
+        ));
+    }
+
+    Ok(Some(pipeline))
+}
+
+/// Whether tool calls are recorded in the audit trail (`warden audit list`)
+pub fn audit_trail_enabled(config: &Config) -> bool {
+    config.tool_policy.enabled && config.tool_policy.audit_enabled
+}
+
+/// Parse permission level string from config to enum (defaults to Read for safety)
+fn parse_permission_level(s: &str) -> PermissionLevel {
+    match s.to_lowercase().as_str() {
+        "read" => PermissionLevel::Read,
+        "write" => PermissionLevel::Write,
+        "execute" => PermissionLevel::Execute,
+        "network" => PermissionLevel::Network,
+        "admin" => PermissionLevel::Admin,
+        _ => PermissionLevel::Read,
+    }
+}
+
+/// Apply `[tools.renames]`, `[[tools.composites]]`, `[tools.aliases]` and
+/// `[tools.cache_ttl]`, in that order; call after all other tools are registered
+pub fn apply_tool_config(runtime: &Runtime, config: &Config) -> Result<()> {
+    // Tools may be disabled in this config; skip their renames and aliases
+    for (from, to) in &config.tools.renames {
+        if !runtime.has_tool(from) {
+            tracing::warn!(tool = %from, "Rename of unregistered tool skipped");
+            continue;
+        }
+        runtime.rename_tool(from, to)?;
+        tracing::info!(from = %from, to = %to, "Renamed tool");
+    }
+    for spec in &config.tools.composites {
+        let name = spec.name.clone();
+        runtime.register_composite(spec.clone())?;
+        tracing::info!(tool = %name, "Registered composite tool");
+    }
+    for (alias, target) in &config.tools.aliases {
+        if !runtime.has_tool(target) {
+            tracing::warn!(alias = %alias, tool = %target, "Alias of unregistered tool skipped");
+            continue;
+        }
+        runtime.alias_tool(alias, target)?;
+    }
+    for (tool, &secs) in &config.tools.cache_ttl {
+        runtime.configure_cache_ttl(tool, std::time::Duration::from_secs(secs));
+    }
+    Ok(())
+}
@@ -0,0 +1,123 @@
+use crate::cli::OutputFormat;
+use crate::config::Config;
+use anyhow::{Context, Result};
+use operon_runtime::scheduler::{self, PlanSchedule, ScheduledStep};
+use std::path::{Path, PathBuf};
+
+/// Plan subcommand actions
+pub enum PlanAction {
+    Validate(PathBuf),
+    Graph(PathBuf),
+}
+
+pub fn execute(action: PlanAction, config: &Config, output: OutputFormat) -> Result<()> {
+    match action {
+        PlanAction::Validate(file) => validate(&file, config, output),
+        PlanAction::Graph(file) => graph(&file, config, output),
+    }
+}
+
+fn load_plan(file: &Path) -> Result<serde_json::Value> {
+    let content =
+        std::fs::read_to_string(file).context(format!("Failed to read plan file: {:?}", file))?;
+    serde_json::from_str(&content).context("Failed to parse plan JSON")
+}
+
+/// Same schedule `Runtime::plan_schedule` reports, without opening the state DB
+fn schedule(plan: &serde_json::Value, config: &Config) -> Result<PlanSchedule> {
+    scheduler::plan_schedule(plan, config.runtime.max_parallel)
+}
+
+/// Check steps and dependencies (missing ids, cycles) and summarize the schedule
+fn validate(file: &Path, config: &Config, output: OutputFormat) -> Result<()> {
+    let plan = load_plan(file)?;
+    let schedule = schedule(&plan, config)?;
+
+    if output == OutputFormat::Json {
+        return super::print_json(&schedule);
+    }
+
+    println!(
+        "Plan OK: {} ({} steps in {} levels, up to {} concurrent)",
+        schedule.plan_id,
+        schedule.order.len(),
+        schedule.levels.len(),
+        schedule.max_concurrency
+    );
+    if !schedule.sequential {
+        for (i, level) in schedule.levels.iter().enumerate() {
+            println!("  level {}: {}", i, level.join(", "));
+        }
+    }
+    Ok(())
+}
+
+/// Print the dependency graph as Graphviz DOT, one rank per level
+fn graph(file: &Path, config: &Config, output: OutputFormat) -> Result<()> {
+    let plan = load_plan(file)?;
+    let schedule = schedule(&plan, config)?;
+
+    if output == OutputFormat::Json {
+        return super::print_json(&schedule);
+    }
+
+    let steps = scheduler::parse_steps(&plan)?;
+    print!("{}", to_dot(&schedule, &steps));
+    Ok(())
+}
+
+fn to_dot(schedule: &PlanSchedule, steps: &[ScheduledStep]) -> String {
+    let mut dot = format!("digraph {:?} {{\n    rankdir=LR;\n", schedule.plan_id);
+    for step in steps {
+        dot.push_str(&format!(
+            "    {:?} [label={:?}];\n",
+            step.id,
+            format!("{}\n{}", step.id, step.tool)
+        ));
+    }
+    if schedule.sequential {
+        // No declared dependencies: steps run in file order
+        for pair in steps.windows(2) {
+            dot.push_str(&format!(
+                "    {:?} -> {:?} [style=dashed];\n",
+                pair[0].id, pair[1].id
+            ));
+        }
+    } else {
+        for step in steps {
+            for dep in &step.depends_on {
+                dot.push_str(&format!("    {:?} -> {:?};\n", dep, step.id));
+            }
+        }
+        for level in &schedule.levels {
+            let ids: Vec<String> = level.iter().map(|id| format!("{:?};", id)).collect();
+            dot.push_str(&format!("    {{ rank=same; {} }}\n", ids.join(" ")));
+        }
+    }
+    dot.push_str("}\n");
+    dot
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_to_dot_groups_levels() {
+        let plan = serde_json::json!({
+            "id": "deploy",
+            "steps": [
+                {"id": "a", "tool": "shell", "depends_on": []},
+                {"id": "b", "tool": "shell", "depends_on": []},
+                {"id": "c", "tool": "http", "depends_on": ["a", "b"]}
+            ]
+        });
+        let schedule = scheduler::plan_schedule(&plan, 4).unwrap();
+        let dot = to_dot(&schedule, &scheduler::parse_steps(&plan).unwrap());
+
+        assert!(dot.starts_with("digraph \"deploy\" {"));
+        assert!(dot.contains("\"c\" [label=\"c\\nhttp\"];"));
+        assert!(dot.contains("\"a\" -> \"c\";"));
+        assert!(dot.contains("{ rank=same; \"a\"; \"b\"; }"));
+    }
+}
@@ -1,3 +1,4 @@
+use crate::cli::OutputFormat;
 use anyhow::Result;
 use operon_runtime::{HookRegistry, PluginLoader, Runtime};
 use std::path::PathBuf;
@@ -12,7 +13,7 @@ pub enum PluginAction {
     Unload(String),
 }
 
-pub async fn execute(action: PluginAction) -> Result<()> {
+pub async fn execute(action: PluginAction, output: OutputFormat) -> Result<()> {
     let plugin_dir = dirs_home().join(".silentclaw").join("plugins");
     let runtime = Arc::new(Runtime::new(true, Duration::from_secs(60))?);
     let hook_registry = Arc::new(HookRegistry::new());
@@ -24,7 +25,13 @@ pub async fn execute(action: PluginAction) -> Result<()> {
             let _ = loader.load_all(&plugin_dir).await?;
             let plugins = loader.list_plugins().await;
 
-            if plugins.is_empty() {
+            if output == OutputFormat::Json {
+                let list: Vec<_> = plugins
+                    .iter()
+                    .map(|(name, version)| serde_json::json!({"name": name, "version": version}))
+                    .collect();
+                super::print_json(&list)?;
+            } else if plugins.is_empty() {
                 println!("No plugins installed.");
                 println!("Plugin directory: {:?}", plugin_dir);
             } else {
@@ -38,13 +45,25 @@ pub async fn execute(action: PluginAction) -> Result<()> {
             let manifest = operon_runtime::PluginManifest::load(&path.join("plugin.toml"))?;
             loader.load_plugin(&manifest, &path).await?;
             info!(plugin = %manifest.name, "Plugin loaded successfully");
-            println!("Plugin '{}' loaded.", manifest.name);
+            if output == OutputFormat::Json {
+                super::print_json(&serde_json::json!({
+                    "name": manifest.name,
+                    "version": manifest.version,
+                    "loaded": true,
+                }))?;
+            } else {
+                println!("Plugin '{}' loaded.", manifest.name);
+            }
         }
         PluginAction::Unload(name) => {
             // First load to populate
             let _ = loader.load_all(&plugin_dir).await?;
             loader.unload_plugin(&name).await?;
-            println!("Plugin '{}' unloaded.", name);
+            if output == OutputFormat::Json {
+                super::print_json(&serde_json::json!({"name": name, "unloaded": true}))?;
+            } else {
+                println!("Plugin '{}' unloaded.", name);
+            }
         }
     }
 
@@ -0,0 +1,144 @@
+use crate::cli::OutputFormat;
+use crate::config::Config;
+use anyhow::{bail, Context, Result};
+use operon_runtime::tool_policy::config::RuleAction;
+use operon_runtime::tool_policy::layers::RuleEngineLayer;
+use operon_runtime::{PermissionLevel, PolicyContext};
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+
+/// A call to check the rules against
+#[derive(Debug, Deserialize)]
+pub struct SampleCall {
+    pub tool: String,
+    #[serde(default)]
+    pub input: serde_json::Value,
+    /// Caller permission (default: execute, what agents without a
+    /// max_permission call with)
+    #[serde(default)]
+    pub permission: Option<PermissionLevel>,
+    /// Action the rules should reach; a different outcome fails the test
+    #[serde(default)]
+    pub expect: Option<RuleAction>,
+}
+
+#[derive(Debug, Serialize)]
+struct Outcome {
+    tool: String,
+    input: serde_json::Value,
+    action: RuleAction,
+    /// Rule that decided the call (None: no rule matched, so it is allowed)
+    rule: Option<String>,
+    reason: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    expected: Option<RuleAction>,
+    passed: bool,
+}
+
+/// Samples from a JSON file holding one call or an array of them
+pub fn load_samples(file: &Path) -> Result<Vec<SampleCall>> {
+    let content = std::fs::read_to_string(file)
+        .with_context(|| format!("Failed to read sample calls: {:?}", file))?;
+    let value: serde_json::Value =
+        serde_json::from_str(&content).context("Failed to parse sample calls JSON")?;
+    let samples = match value {
+        serde_json::Value::Array(_) => serde_json::from_value(value),
+        single => serde_json::from_value(single).map(|sample| vec![sample]),
+    };
+    samples.context(
+        "Sample calls need a \"tool\", and optionally \"input\", \"permission\" and \"expect\"",
+    )
+}
+
+/// Sample from command-line flags: a tool, its input JSON and a permission
+pub fn sample_call(tool: String, input: &str, permission: Option<&str>) -> Result<SampleCall> {
+    let input = serde_json::from_str(input).context("--input must be JSON")?;
+    let permission = permission
+        .map(|level| serde_json::from_value(serde_json::Value::String(level.to_lowercase())))
+        .transpose()
+        .context("--permission must be read, write, execute, network or admin")?;
+    Ok(SampleCall {
+        tool,
+        input,
+        permission,
+        expect: None,
+    })
+}
+
+/// Evaluate `[[tool_policy.rules]]` against sample calls and report which
+/// rule decides each; fails if any call misses its expected action
+pub fn test(config: &Config, samples: Vec<SampleCall>, output: OutputFormat) -> Result<()> {
+    let rules = &config.tool_policy.rules;
+    let layer = RuleEngineLayer::new(rules.clone()).context("Invalid tool_policy.rules")?;
+
+    let outcomes: Vec<Outcome> = samples
+        .into_iter()
+        .map(|sample| {
+            let ctx = PolicyContext {
+                tool_name: sample.tool.clone(),
+                input: sample.input.clone(),
+                caller_permission: sample.permission.unwrap_or(PermissionLevel::Execute),
+                dry_run: false,
+                session_id: None,
+                registered_permission: None,
+                context_tokens_left: None,
+                agent_name: None,
+                principal: None,
+                recent_messages: Vec::new(),
+                redacted_input: None,
+            };
+            let rule = layer.matching_rule(&ctx);
+            let action = rule.map_or(RuleAction::Allow, |rule| rule.action);
+            Outcome {
+                tool: sample.tool,
+                input: sample.input,
+                action,
+                rule: rule.map(|rule| rule.name.clone()),
+                reason: rule.and_then(|rule| rule.reason.clone()),
+                expected: sample.expect,
+                passed: sample.expect.is_none_or(|expected| expected == action),
+            }
+        })
+        .collect();
+    let failed = outcomes.iter().filter(|outcome| !outcome.passed).count();
+
+    if output == OutputFormat::Json {
+        super::print_json(&outcomes)?;
+    } else {
+        if rules.is_empty() {
+            println!("No rules defined; add [[tool_policy.rules]] sections.");
+        }
+        for outcome in &outcomes {
+            let decided_by = match (&outcome.rule, &outcome.reason) {
+                (Some(rule), Some(reason)) => format!("rule '{}': {}", rule, reason),
+                (Some(rule), None) => format!("rule '{}'", rule),
+                (None, _) => "no rule matched".to_string(),
+            };
+            let verdict = match outcome.expected {
+                Some(_) if outcome.passed => "ok  ",
+                Some(_) => "FAIL",
+                None => "    ",
+            };
+            println!(
+                "{} {:<7} {} {}  ({})",
+                verdict,
+                format!("{:?}", outcome.action).to_lowercase(),
+                outcome.tool,
+                outcome.input,
+                decided_by
+            );
+            if let (false, Some(expected)) = (outcome.passed, outcome.expected) {
+                println!("     expected {}", format!("{:?}", expected).to_lowercase());
+            }
+        }
+    }
+
+    if failed > 0 {
+        bail!(
+            "{} of {} sample calls did not get the expected action",
+            failed,
+            outcomes.len()
+        );
+    }
+    Ok(())
+}
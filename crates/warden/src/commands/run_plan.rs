@@ -1,12 +1,25 @@
-use crate::cli::ExecutionMode;
+use crate::cli::{ExecutionMode, OutputFormat};
 use crate::config::Config;
 use anyhow::{Context, Result};
-use operon_adapters::ShellTool;
-use operon_runtime::{ExecutionContext, Runtime};
-use std::path::PathBuf;
-use std::sync::Arc;
+use notify_debouncer_mini::notify::RecursiveMode;
+use notify_debouncer_mini::{new_debouncer, DebounceEventResult};
+use operon_runtime::{ExecutionContext, PlanResult, Runtime, StepStatus, Storage, PLAN_STEP_TOOL};
+use std::path::{Path, PathBuf};
 use std::time::Duration;
-use tracing::info;
+use tokio::sync::mpsc::UnboundedReceiver;
+use tracing::{info, warn};
+
+/// State database of plan runs (and of chat and gateway tool calls),
+/// relative to the working directory
+pub(crate) const DB_PATH: &str = "./silentclaw.db";
+
+/// `--watch`: what besides the plan file triggers a re-run
+pub struct WatchOptions {
+    /// Files or directories watched recursively
+    pub paths: Vec<PathBuf>,
+    /// Quiet time before a burst of changes triggers a run
+    pub debounce: Duration,
+}
 
 pub async fn execute(
     plan_file: PathBuf,
@@ -14,16 +27,197 @@ pub async fn execute(
     config: &Config,
     record: Option<PathBuf>,
     replay: Option<PathBuf>,
+    output: OutputFormat,
+    watch_options: Option<WatchOptions>,
 ) -> Result<()> {
-    info!(?plan_file, ?execution_mode, "Running plan");
+    let Some(options) = watch_options else {
+        return run_once(&plan_file, &execution_mode, config, record, replay, output).await;
+    };
+    watch(
+        plan_file,
+        options,
+        &execution_mode,
+        config,
+        record,
+        replay,
+        output,
+    )
+    .await
+}
+
+/// Run the plan, then again after every relevant change until Ctrl-C.
+/// Failed runs are reported without ending the watch.
+async fn watch(
+    plan_file: PathBuf,
+    options: WatchOptions,
+    execution_mode: &ExecutionMode,
+    config: &Config,
+    record: Option<PathBuf>,
+    replay: Option<PathBuf>,
+    output: OutputFormat,
+) -> Result<()> {
+    let cwd = std::env::current_dir()?;
+    let filter = WatchFilter {
+        plan_file: plan_file
+            .canonicalize()
+            .context(format!("Failed to read plan file: {:?}", plan_file))?,
+        paths: options
+            .paths
+            .iter()
+            .map(|p| {
+                p.canonicalize()
+                    .context(format!("Failed to resolve watch path: {:?}", p))
+            })
+            .collect::<Result<_>>()?,
+        // Written by every run; watching them would re-trigger forever
+        ignored: std::iter::once(cwd.join("silentclaw.db"))
+            .chain(record.iter().map(|dir| cwd.join(dir)))
+            .collect(),
+    };
+
+    let (tx, mut rx) = tokio::sync::mpsc::unbounded_channel();
+    let mut debouncer = new_debouncer(options.debounce, move |result: DebounceEventResult| {
+        let _ = tx.send(result);
+    })
+    .context("Failed to create file watcher")?;
+    let plan_dir = filter.plan_file.parent().unwrap_or(&filter.plan_file);
+    debouncer
+        .watcher()
+        .watch(plan_dir, RecursiveMode::NonRecursive)
+        .context("Failed to watch plan directory")?;
+    for path in &filter.paths {
+        debouncer
+            .watcher()
+            .watch(path, RecursiveMode::Recursive)
+            .context(format!("Failed to watch {:?}", path))?;
+    }
+
+    loop {
+        if let Err(e) = run_once(
+            &plan_file,
+            execution_mode,
+            config,
+            record.clone(),
+            replay.clone(),
+            output,
+        )
+        .await
+        {
+            eprintln!("Plan run failed: {:#}", e);
+        }
+
+        // Changes made during the run (including by the plan itself) don't count
+        tokio::time::sleep(options.debounce).await;
+        while rx.try_recv().is_ok() {}
+
+        eprintln!("Watching for changes (Ctrl-C to stop)...");
+        let changed = tokio::select! {
+            _ = tokio::signal::ctrl_c() => return Ok(()),
+            changed = next_change(&mut rx, &filter) => changed?,
+        };
+        info!(path = ?changed, "Change detected, re-running plan");
+    }
+}
+
+/// Wait for a debounced batch that touches a watched path
+async fn next_change(
+    rx: &mut UnboundedReceiver<DebounceEventResult>,
+    filter: &WatchFilter,
+) -> Result<PathBuf> {
+    loop {
+        match rx.recv().await.context("File watcher stopped")? {
+            Ok(events) => {
+                if let Some(event) = events.into_iter().find(|e| filter.matches(&e.path)) {
+                    return Ok(event.path);
+                }
+            }
+            Err(e) => warn!(error = ?e, "File watcher error"),
+        }
+    }
+}
 
-    // Read plan JSON
-    let plan_content = std::fs::read_to_string(&plan_file)
+/// Which changed paths trigger a re-run (all paths canonical)
+struct WatchFilter {
+    plan_file: PathBuf,
+    paths: Vec<PathBuf>,
+    ignored: Vec<PathBuf>,
+}
+
+impl WatchFilter {
+    fn matches(&self, path: &Path) -> bool {
+        if path == self.plan_file {
+            return true;
+        }
+        if self.ignored.iter().any(|ignored| path.starts_with(ignored)) {
+            return false;
+        }
+        self.paths.iter().any(|root| {
+            path.strip_prefix(root).is_ok_and(|rel| {
+                // Skip .git and other hidden files/dirs
+                !rel.components()
+                    .any(|c| c.as_os_str().to_string_lossy().starts_with('.'))
+            })
+        })
+    }
+}
+
+/// `--resume`: run `plan_id` again, skipping the steps its last run
+/// completed. `plan_file` replaces the checkpointed plan, e.g. with a fix.
+pub async fn resume(
+    plan_id: String,
+    plan_file: Option<PathBuf>,
+    execution_mode: ExecutionMode,
+    config: &Config,
+    record: Option<PathBuf>,
+    replay: Option<PathBuf>,
+    output: OutputFormat,
+) -> Result<()> {
+    if replay.is_some() {
+        anyhow::bail!("--resume cannot be combined with --replay");
+    }
+    let plan = match plan_file {
+        Some(plan_file) => {
+            let plan = read_plan(&plan_file)?;
+            if plan["id"].as_str() != Some(plan_id.as_str()) {
+                anyhow::bail!("Plan file {:?} is not plan '{}'", plan_file, plan_id);
+            }
+            plan
+        }
+        // Closed again before the runtime opens it
+        None => operon_runtime::checkpoint::load_plan(&Storage::open(DB_PATH)?, &plan_id).await?,
+    };
+    info!(plan_id, ?execution_mode, "Resuming plan");
+    run(plan, &execution_mode, config, record, None, output, true).await
+}
+
+fn read_plan(plan_file: &Path) -> Result<serde_json::Value> {
+    let plan_content = std::fs::read_to_string(plan_file)
         .context(format!("Failed to read plan file: {:?}", plan_file))?;
+    serde_json::from_str(&plan_content).context("Failed to parse plan JSON")
+}
 
-    let plan: serde_json::Value =
-        serde_json::from_str(&plan_content).context("Failed to parse plan JSON")?;
+async fn run_once(
+    plan_file: &Path,
+    execution_mode: &ExecutionMode,
+    config: &Config,
+    record: Option<PathBuf>,
+    replay: Option<PathBuf>,
+    output: OutputFormat,
+) -> Result<()> {
+    info!(?plan_file, ?execution_mode, "Running plan");
+    let plan = read_plan(plan_file)?;
+    run(plan, execution_mode, config, record, replay, output, false).await
+}
 
+async fn run(
+    plan: serde_json::Value,
+    execution_mode: &ExecutionMode,
+    config: &Config,
+    record: Option<PathBuf>,
+    replay: Option<PathBuf>,
+    output: OutputFormat,
+    resume: bool,
+) -> Result<()> {
     // Resolve dry-run from execution mode
     let dry_run = match execution_mode {
         ExecutionMode::Auto => config.runtime.dry_run,
@@ -40,29 +234,105 @@ pub async fn execute(
         ExecutionContext::Normal
     };
 
+    // always_execute steps need a shell that really runs; the runtime still
+    // skips every other step in dry-run mode
+    let steps = operon_runtime::scheduler::parse_steps(&plan)?;
+    let shell_dry_run = dry_run && !steps.iter().any(|s| s.always_execute);
+
+    let runtime = build_runtime(config, dry_run, shell_dry_run, execution_context, DB_PATH)?;
+
+    // Start runtime
+    runtime.start().await?;
+
+    // Run plan
+    let result = if resume {
+        runtime.resume_plan(plan).await?
+    } else {
+        runtime.run_plan(plan).await?
+    };
+
+    // Stop runtime
+    runtime.stop().await?;
+
+    info!("Plan execution completed");
+
+    if output == OutputFormat::Json {
+        let steps: Vec<_> = result
+            .steps
+            .iter()
+            .map(|step| {
+                serde_json::json!({
+                    "id": step.id,
+                    "tool": step.tool,
+                    "status": step.status,
+                    "skipped": step.status == StepStatus::Skipped,
+                    "duration_ms": step.duration_ms,
+                    "output": step.output,
+                    "error": step.error,
+                })
+            })
+            .collect();
+        super::print_json(&serde_json::json!({
+            "plan_id": result.plan_id,
+            "dry_run": result.dry_run,
+            "status": if result.failed.is_empty() { "completed" } else { "partial" },
+            "duration_ms": result.duration_ms,
+            "output": result.output,
+            "failed": result.failed,
+            "steps": steps,
+        }))?;
+    } else {
+        print_summary(&result);
+    }
+
+    Ok(())
+}
+
+/// Runtime for one plan run, with every tool enabled in `config` registered.
+/// `shell_dry_run` may be false in a dry run for plans with `always_execute` steps.
+pub fn build_runtime(
+    config: &Config,
+    dry_run: bool,
+    shell_dry_run: bool,
+    execution_context: ExecutionContext,
+    db_path: &str,
+) -> Result<Runtime> {
     // Create runtime (single timeout source)
     let default_timeout = Duration::from_secs(config.runtime.timeout_secs);
-    let runtime = Runtime::new(dry_run, default_timeout)?
+    let mut runtime = Runtime::with_db(db_path, dry_run, default_timeout)?
         .with_execution_context(execution_context)
-        .with_max_parallel(config.runtime.max_parallel);
+        .with_max_parallel(config.runtime.max_parallel)
+        .with_nested_storage(config.runtime.nested_storage);
+    for (class, &limit) in &config.runtime.resource_limits {
+        runtime = runtime.with_resource_limit(class, limit);
+    }
+    if let Some(backend) = super::execution_backend(config)? {
+        runtime = runtime.with_execution_backend(backend);
+    }
+    let runtime = super::attach_notifications(runtime, config)?;
+
+    // Caps whole nested plans; a step's own `timeout_secs` wins
+    if let Some(&secs) = config.tools.timeouts.get(PLAN_STEP_TOOL) {
+        runtime.configure_timeout(PLAN_STEP_TOOL.to_string(), Duration::from_secs(secs));
+    }
 
     // Register shell tool if enabled
     if config.tools.shell.enabled {
-        let shell_tool = ShellTool::new(dry_run).with_validation(
-            config.tools.shell.blocklist.clone(),
-            config.tools.shell.allowlist.clone(),
-        );
-
-        runtime.register_tool("shell".to_string(), Arc::new(shell_tool))?;
-
-        // Configure per-tool timeout (single location)
-        if let Some(&timeout_secs) = config.tools.timeouts.get("shell") {
-            runtime.configure_timeout("shell".to_string(), Duration::from_secs(timeout_secs));
-        }
-
+        super::register_shell(
+            &runtime,
+            config,
+            shell_dry_run,
+            super::tool_timeout(config, "shell"),
+        )?;
         info!("Registered shell tool");
     }
 
+    super::register_ssh_tools(&runtime, config, shell_dry_run)?;
+    super::register_kubernetes_tool(&runtime, config)?;
+    super::register_github_tool(&runtime, config)?;
+    super::register_email_tool(&runtime, config)?;
+    super::register_screenshot_tools(&runtime, config)?;
+
     // Register Python tools if enabled (auto-discovery)
     if config.tools.python.enabled {
         info!(
@@ -73,16 +343,77 @@ pub async fn execute(
         // Tools are registered individually when discovered
     }
 
-    // Start runtime
-    runtime.start().await?;
+    super::apply_tool_config(&runtime, config)?;
 
-    // Run plan
-    runtime.run_plan(plan).await?;
+    Ok(runtime)
+}
 
-    // Stop runtime
-    runtime.stop().await?;
+/// Per-step status table followed by a one-line total
+fn print_summary(result: &PlanResult) {
+    let id_width = result
+        .steps
+        .iter()
+        .map(|s| s.id.len())
+        .max()
+        .unwrap_or(0)
+        .max("STEP".len());
+    let tool_width = result
+        .steps
+        .iter()
+        .map(|s| s.tool.len())
+        .max()
+        .unwrap_or(0)
+        .max("TOOL".len());
 
-    info!("Plan execution completed");
+    println!(
+        "{:<id_width$}  {:<tool_width$}  {:<9}  {:>8}",
+        "STEP", "TOOL", "STATUS", "DURATION"
+    );
+    for step in &result.steps {
+        println!(
+            "{:<id_width$}  {:<tool_width$}  {:<9}  {:>6}ms",
+            step.id,
+            step.tool,
+            step.status.as_str(),
+            step.duration_ms
+        );
+        if let Some(error) = &step.error {
+            println!("  {}", error);
+        }
+    }
+    println!(
+        "Plan {} completed in {}ms: {} completed, {} skipped, {} replayed, {} resumed, {} failed",
+        result.plan_id,
+        result.duration_ms,
+        result.count(StepStatus::Completed),
+        result.count(StepStatus::Skipped),
+        result.count(StepStatus::Replayed),
+        result.count(StepStatus::Resumed),
+        result.count(StepStatus::Failed)
+    );
+}
 
-    Ok(())
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_watch_filter_matches_plan_and_watched_paths() {
+        let filter = WatchFilter {
+            plan_file: PathBuf::from("/work/plans/deploy.json"),
+            paths: vec![PathBuf::from("/work/src")],
+            ignored: vec![
+                PathBuf::from("/work/src/silentclaw.db"),
+                PathBuf::from("/work/src/fixtures"),
+            ],
+        };
+
+        assert!(filter.matches(Path::new("/work/plans/deploy.json")));
+        assert!(filter.matches(Path::new("/work/src/lib/mod.rs")));
+        // Siblings of the plan file aren't watched
+        assert!(!filter.matches(Path::new("/work/plans/other.json")));
+        assert!(!filter.matches(Path::new("/work/src/.git/index")));
+        assert!(!filter.matches(Path::new("/work/src/silentclaw.db")));
+        assert!(!filter.matches(Path::new("/work/src/fixtures/step_0.json")));
+    }
 }
@@ -1,14 +1,25 @@
-use crate::cli::ExecutionMode;
-use crate::commands::chat::build_provider;
-use crate::config::Config;
-use anyhow::Result;
-use operon_adapters::{register_filesystem_tools, register_shell_tool};
-use operon_gateway::{start_server, AppState, AuthConfig, RateLimiter, SessionManager};
-use operon_runtime::{ConfigManager, ConfigReloadEvent, Runtime};
-use std::path::PathBuf;
+use crate::cli::{ExecutionMode, OutputFormat};
+use crate::commands::chat::{build_provider, provider_client};
+use crate::config::{self, Config};
+use crate::daemon;
+use crate::schedules::Scheduler;
+use anyhow::{bail, Result};
+use operon_adapters::register_filesystem_tools;
+use operon_gateway::{
+    start_server, AppState, ApprovalBroker, AuthConfig, QuotaTracker, RateLimiter, Reloadable,
+    SessionManager,
+};
+use operon_runtime::{ConfigManager, ConfigReloadEvent, Runtime, Storage};
+use std::path::{Path, PathBuf};
 use std::sync::Arc;
 use std::time::Duration;
-use tracing::info;
+use tracing::{info, warn};
+
+/// How long `serve stop` waits; covers the gateway's 10s connection drain
+const STOP_TIMEOUT: Duration = Duration::from_secs(20);
+
+/// How often the provider health served by /health is re-probed
+const HEALTH_REFRESH_INTERVAL: Duration = Duration::from_secs(60);
 
 /// Execute serve command with optional config file path for hot-reload
 pub async fn execute(
@@ -29,33 +40,133 @@ pub async fn execute(
     };
 
     let default_timeout = Duration::from_secs(config.runtime.timeout_secs);
-    let runtime = Arc::new(Runtime::new(dry_run, default_timeout)?);
+    let mut runtime =
+        Runtime::new(dry_run, default_timeout)?.with_max_parallel(config.runtime.max_parallel);
+    if let Some(backend) = super::execution_backend(config)? {
+        runtime = runtime.with_execution_backend(backend);
+    }
+    runtime = super::attach_notifications(runtime, config)?;
+    if let Some(redactor) = super::redactor(config)? {
+        runtime = runtime.with_tool_middleware(redactor);
+    }
 
     if config.tools.shell.enabled {
-        register_shell_tool(
+        super::register_shell(
             &runtime,
+            config,
             dry_run,
-            config.tools.shell.blocklist.clone(),
-            config.tools.shell.allowlist.clone(),
+            super::tool_timeout(config, "shell"),
         )?;
     }
 
+    super::register_ssh_tools(&runtime, config, dry_run)?;
+    super::register_kubernetes_tool(&runtime, config)?;
+    super::register_github_tool(&runtime, config)?;
+    super::register_email_tool(&runtime, config)?;
+    super::register_screenshot_tools(&runtime, config)?;
+
     if config.tools.filesystem.enabled {
         register_filesystem_tools(
             &runtime,
             std::path::PathBuf::from(&config.tools.filesystem.workspace),
             config.tools.filesystem.max_file_size_mb,
+            config.tools.filesystem.write_limits(),
+            super::workspace::start_snapshot(config, dry_run)?,
         )?;
     }
 
+    super::apply_tool_config(&runtime, config)?;
+
+    if let Some(pipeline) = super::policy_pipeline(&runtime, config)? {
+        runtime.set_policy(pipeline);
+        info!("Tool policy pipeline enabled");
+    }
+    if super::audit_trail_enabled(config) {
+        runtime = runtime.with_audit_trail();
+    }
+    // Held tool calls are put to the session's WebSocket clients
+    let approvals = Arc::new(ApprovalBroker::new());
+    let runtime = Arc::new(runtime.with_approver(approvals.clone()));
+
+    // Sessions pick an [agents.<name>] definition by agent_id
+    let agents = config
+        .agents
+        .keys()
+        .map(|name| Ok((name.clone(), super::agent_config(config, name)?)))
+        .collect::<Result<_>>()?;
+    let mut session_manager = SessionManager::new(provider, runtime.clone())
+        .with_default_agent(super::agent_config(config, "default")?)
+        .with_agents(agents)
+        .with_approvals(approvals)
+        .with_provider_factory({
+            let llm = config.llm.clone();
+            Arc::new(move |provider, model| provider_client(&llm, provider, model))
+        });
+    if let Some(quota) = quota_tracker(config)? {
+        session_manager = session_manager.with_quota(Arc::new(quota));
+    }
+    if let Some(guard) = super::workspace::attachment_guard(config)? {
+        session_manager = session_manager.with_workspace(guard);
+    }
+    if config.sessions.save_gateway_sessions {
+        session_manager = session_manager.with_session_store(super::session_store(config)?);
+    }
+    let session_manager = Arc::new(session_manager);
+
+    if config.llm.startup_health_check {
+        let report = session_manager.refresh_provider_health().await;
+        for health in &report {
+            match &health.error {
+                None => {
+                    info!(model = %health.model, latency_ms = health.latency_ms, "LLM provider healthy")
+                }
+                Some(err) => {
+                    warn!(model = %health.model, error = %err, "LLM provider failed health check")
+                }
+            }
+        }
+        if !report.iter().any(|h| h.healthy) {
+            bail!("No LLM provider passed its health check; run `warden doctor` for details");
+        }
+    }
+
+    // Keep /health current; the startup check, when on, is the first probe
+    tokio::spawn({
+        let session_manager = session_manager.clone();
+        let probed = config.llm.startup_health_check;
+        async move {
+            let mut refresh = tokio::time::interval(HEALTH_REFRESH_INTERVAL);
+            if probed {
+                refresh.tick().await;
+            }
+            loop {
+                refresh.tick().await;
+                session_manager.refresh_provider_health().await;
+            }
+        }
+    });
+
+    let state = AppState {
+        session_manager,
+        auth_config: Reloadable::new(auth_config(config)),
+        rate_limiter: Arc::new(RateLimiter::new(120)),
+        allowed_origins: Reloadable::new(config.gateway.allowed_origins.clone()),
+        trusted_proxy_headers: config.gateway.trusted_proxy_headers.clone(),
+    };
+
     // Start config hot-reload watcher if config path is provided
-    if let Some(ref path) = config_path {
-        let config_manager = ConfigManager::<Config>::new(path.clone(), Config::default_config());
+    let config_manager = config_path.as_ref().map(|path| {
+        Arc::new(
+            ConfigManager::<Config>::new(path.clone(), Config::default_config())
+                .with_loader(|path| config::load_config(Some(path))),
+        )
+    });
+    if let Some(ref config_manager) = config_manager {
         let mut reload_rx = config_manager.subscribe_reload();
 
         // Spawn watcher
         let watcher_handle = tokio::spawn({
-            let cm = config_manager;
+            let cm = config_manager.clone();
             async move {
                 if let Err(e) = cm.watch().await {
                     tracing::error!("Config watcher failed: {}", e);
@@ -64,11 +175,28 @@ pub async fn execute(
         });
 
         // Spawn reload listener
+        let cm = config_manager.clone();
+        let state = state.clone();
         tokio::spawn(async move {
             while let Ok(event) = reload_rx.recv().await {
                 match event {
                     ConfigReloadEvent::Success => {
-                        info!("Config file reloaded successfully (note: runtime provider swap not yet implemented)");
+                        let config = cm.config();
+                        let applied =
+                            apply_reloaded_config(&*config.read().await, &runtime, &state).await;
+                        match applied {
+                            Ok(()) => {
+                                info!(
+                                    "Config reloaded: LLM provider, tool policy and gateway auth/CORS updated; other settings apply on restart"
+                                );
+                                // /health reports the new provider, not the old one
+                                state.session_manager.refresh_provider_health().await;
+                            }
+                            Err(e) => tracing::warn!(
+                                "Reloaded config not applied: {:#}. Old settings preserved.",
+                                e
+                            ),
+                        }
                     }
                     ConfigReloadEvent::Failure(err) => {
                         tracing::warn!("Config reload failed: {}. Old config preserved.", err);
@@ -79,16 +207,134 @@ pub async fn execute(
         });
     }
 
-    let session_manager = Arc::new(SessionManager::new(provider, runtime));
+    #[cfg(unix)]
+    reload_on_sighup(config_manager.clone())?;
 
-    let state = AppState {
-        session_manager,
-        auth_config: Arc::new(AuthConfig::new(None)),
-        rate_limiter: Arc::new(RateLimiter::new(120)),
-        allowed_origins: vec![],
-    };
+    // Scheduled plans run alongside the gateway and stop with it
+    let scheduler = Scheduler::new(config, &daemon::default_schedule_dir())?;
+    tokio::select! {
+        result = start_server(state, &host, port) => result?,
+        result = scheduler.run() => result?,
+    }
 
-    start_server(state, &host, port).await?;
+    if let Some(cm) = config_manager {
+        cm.stop_watching();
+    }
+
+    Ok(())
+}
+
+/// Gateway authentication from `[gateway]`
+fn auth_config(config: &Config) -> AuthConfig {
+    AuthConfig::new(None)
+        .with_api_keys(config.gateway.api_keys.clone().into_iter().collect())
+        .with_exempt_paths(config.gateway.auth_exempt_paths.clone())
+        .with_admins(config.gateway.admin_identities.clone())
+}
+
+/// Put a reloaded config into effect: the LLM provider new sessions start
+/// on, the tool policy, and gateway auth and CORS origins. Nothing changes
+/// unless all of them build.
+async fn apply_reloaded_config(config: &Config, runtime: &Runtime, state: &AppState) -> Result<()> {
+    let provider = build_provider(config)?;
+    let policy = super::policy_pipeline(runtime, config)?;
+
+    state.session_manager.set_provider(provider).await;
+    runtime.replace_policy(policy);
+    state.auth_config.set(auth_config(config));
+    state
+        .allowed_origins
+        .set(config.gateway.allowed_origins.clone());
+    Ok(())
+}
+
+/// Quota tracker for `[gateway.quotas]`, counting in ~/.silentclaw/quotas.db
+fn quota_tracker(config: &Config) -> Result<Option<QuotaTracker>> {
+    let quotas = &config.gateway.quotas;
+    if !quotas.enabled {
+        return Ok(None);
+    }
+    let path = daemon::default_quota_db();
+    if let Some(parent) = path.parent() {
+        std::fs::create_dir_all(parent)?;
+    }
+    let storage = Storage::open(&path.to_string_lossy())?;
+    let tracker = quotas.identities.iter().fold(
+        QuotaTracker::new(storage, (&quotas.default).into()),
+        |tracker, (identity, limits)| tracker.with_limits(identity, limits.into()),
+    );
+    info!(path = %path.display(), identities = quotas.identities.len(), "Gateway quotas enabled");
+    Ok(Some(tracker))
+}
+
+/// Re-read the config file whenever SIGHUP arrives
+#[cfg(unix)]
+fn reload_on_sighup(config_manager: Option<Arc<ConfigManager<Config>>>) -> Result<()> {
+    use tokio::signal::unix::{signal, SignalKind};
 
+    let mut hangup = signal(SignalKind::hangup())?;
+    tokio::spawn(async move {
+        while hangup.recv().await.is_some() {
+            match config_manager {
+                Some(ref cm) => {
+                    info!("SIGHUP received, reloading config");
+                    // Outcome is reported through the reload listener
+                    let _ = cm.reload().await;
+                }
+                None => warn!("SIGHUP received but no --config file to reload"),
+            }
+        }
+    });
+    Ok(())
+}
+
+/// Report whether a background server is running (exit code 3 if not, as in LSB init scripts)
+pub fn status(pid_file: &Path, output: OutputFormat) -> Result<()> {
+    let pid = daemon::running_pid(pid_file);
+
+    if output == OutputFormat::Json {
+        super::print_json(&serde_json::json!({
+            "running": pid.is_some(),
+            "pid": pid,
+            "pid_file": pid_file,
+        }))?;
+    } else {
+        match pid {
+            Some(pid) => println!("warden serve is running (pid {})", pid),
+            None => println!("warden serve is not running"),
+        }
+    }
+
+    if pid.is_none() {
+        std::process::exit(3);
+    }
     Ok(())
 }
+
+/// Send SIGTERM to the background server and wait for it to drain and exit
+pub async fn stop(pid_file: &Path, output: OutputFormat) -> Result<()> {
+    let Some(pid) = daemon::running_pid(pid_file) else {
+        if output == OutputFormat::Json {
+            return super::print_json(&serde_json::json!({"stopped": false, "pid": null}));
+        }
+        println!("warden serve is not running");
+        return Ok(());
+    };
+
+    daemon::terminate(pid)?;
+    if !daemon::wait_for_exit(pid, STOP_TIMEOUT).await {
+        bail!(
+            "warden serve (pid {}) did not exit within {}s",
+            pid,
+            STOP_TIMEOUT.as_secs()
+        );
+    }
+    let _ = std::fs::remove_file(pid_file);
+
+    if output == OutputFormat::Json {
+        super::print_json(&serde_json::json!({"stopped": true, "pid": pid}))
+    } else {
+        println!("warden serve stopped (pid {})", pid);
+        Ok(())
+    }
+}
@@ -0,0 +1,164 @@
+use crate::cli::OutputFormat;
+use crate::commands::chat::{sessions_db_path, sessions_dir};
+use crate::config::Config;
+use anyhow::{bail, Result};
+use chrono::{DateTime, Utc};
+use operon_runtime::{SessionQuery, SqliteSessionStore};
+use std::path::PathBuf;
+
+/// Save a copy of session `id` holding its first `at` messages (default: all)
+/// under a new id; resume it with `warden chat --session <new id>`
+pub async fn fork(
+    config: &Config,
+    id: &str,
+    at: Option<usize>,
+    output: OutputFormat,
+) -> Result<()> {
+    let store = super::session_store(config)?;
+    let session = store.load(id).await?;
+    let fork = session.fork(at)?;
+    store.save(&fork).await?;
+
+    if output == OutputFormat::Json {
+        return super::print_json(&serde_json::json!({
+            "id": fork.id,
+            "forked_from": session.id,
+            "messages": fork.message_count(),
+        }));
+    }
+    println!(
+        "Forked {} at message {} into {}",
+        session.id,
+        fork.message_count(),
+        fork.id
+    );
+    Ok(())
+}
+
+/// Import the JSON session files in `dir` (default: the chat sessions
+/// directory) into the SQLite session database; safe to run again
+pub async fn import(dir: Option<&str>, output: OutputFormat) -> Result<()> {
+    let dir = dir
+        .map(|dir| PathBuf::from(shellexpand::tilde(dir).as_ref()))
+        .unwrap_or_else(sessions_dir);
+    let db_path = sessions_db_path();
+    let store = SqliteSessionStore::open(&db_path)?;
+    let stats = store.import_json(&dir).await?;
+
+    if output == OutputFormat::Json {
+        return super::print_json(&serde_json::json!({
+            "database": db_path,
+            "imported": stats.imported,
+            "skipped": stats.skipped,
+            "failed": stats.failed,
+        }));
+    }
+    println!(
+        "Imported {} sessions into {} ({} already up to date, {} unreadable)",
+        stats.imported,
+        db_path.display(),
+        stats.skipped,
+        stats.failed
+    );
+    Ok(())
+}
+
+/// List saved chat sessions, most recently updated first
+pub async fn list(config: &Config, output: OutputFormat) -> Result<()> {
+    let store = super::session_store(config)?;
+
+    let mut sessions = Vec::new();
+    for id in store.list_sessions().await? {
+        match store.load(&id).await {
+            Ok(session) => sessions.push(session),
+            Err(e) => tracing::warn!(session_id = %id, error = %e, "Skipping unreadable session"),
+        }
+    }
+    sessions.sort_by_key(|s| std::cmp::Reverse(s.updated_at));
+
+    if output == OutputFormat::Json {
+        let list: Vec<_> = sessions
+            .iter()
+            .map(|s| {
+                serde_json::json!({
+                    "id": s.id,
+                    "agent": s.agent_name,
+                    "messages": s.message_count(),
+                    "created_at": s.created_at.to_rfc3339(),
+                    "updated_at": s.updated_at.to_rfc3339(),
+                    "usage": s.cumulative_usage,
+                })
+            })
+            .collect();
+        return super::print_json(&list);
+    }
+
+    if sessions.is_empty() {
+        println!("No saved sessions.");
+        return Ok(());
+    }
+    for s in &sessions {
+        println!(
+            "{}  {:<12} {:>4} messages  updated {}",
+            s.id,
+            s.agent_name,
+            s.message_count(),
+            s.updated_at.format("%Y-%m-%d %H:%M")
+        );
+    }
+    Ok(())
+}
+
+/// The SQLite session database, which `query` and `prune` need
+fn sqlite_store(config: &Config, command: &str) -> Result<SqliteSessionStore> {
+    if config.sessions.store != "sqlite" {
+        bail!(
+            "`warden session {}` needs sessions.store = \"sqlite\" (copy existing sessions over with `warden session import`)",
+            command
+        );
+    }
+    SqliteSessionStore::open(&sessions_db_path())
+}
+
+/// List stored sessions matching `query`, most recently updated first
+pub async fn query(config: &Config, query: SessionQuery, output: OutputFormat) -> Result<()> {
+    let sessions = sqlite_store(config, "query")?.query(&query).await?;
+
+    if output == OutputFormat::Json {
+        return super::print_json(&sessions);
+    }
+    if sessions.is_empty() {
+        println!("No matching sessions.");
+        return Ok(());
+    }
+    for s in &sessions {
+        println!(
+            "{}  {:<12} {:>4} messages {:>8} tokens  ${:.4}  updated {}",
+            s.id,
+            s.agent_name,
+            s.message_count,
+            s.usage.total(),
+            s.usage.cost_usd,
+            s.updated_at.format("%Y-%m-%d %H:%M")
+        );
+    }
+    Ok(())
+}
+
+/// Delete stored sessions last updated before `cutoff`
+pub async fn prune(config: &Config, cutoff: DateTime<Utc>, output: OutputFormat) -> Result<()> {
+    let pruned = sqlite_store(config, "prune")?.prune(cutoff).await?;
+
+    if output == OutputFormat::Json {
+        return super::print_json(&serde_json::json!({
+            "pruned": pruned,
+            "cutoff": cutoff.to_rfc3339(),
+        }));
+    }
+    println!(
+        "Deleted {} sessions last updated before {}",
+        pruned,
+        cutoff.format("%Y-%m-%d %H:%M")
+    );
+    Ok(())
+}
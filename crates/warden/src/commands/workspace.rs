@@ -0,0 +1,92 @@
+use crate::cli::OutputFormat;
+use crate::config::Config;
+use anyhow::{Context, Result};
+use operon_adapters::{WorkspaceGuard, WorkspaceSnapshot};
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use tracing::info;
+
+/// Where workspace snapshots are kept
+pub fn snapshots_dir() -> PathBuf {
+    std::env::var("HOME")
+        .map(PathBuf::from)
+        .unwrap_or_else(|_| PathBuf::from("."))
+        .join(".silentclaw")
+        .join("snapshots")
+}
+
+/// Start a snapshot for this session if `tools.filesystem.snapshot` is on
+/// and tools will really run
+pub fn start_snapshot(config: &Config, dry_run: bool) -> Result<Option<Arc<WorkspaceSnapshot>>> {
+    if !config.tools.filesystem.snapshot || dry_run {
+        return Ok(None);
+    }
+    let secs = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_secs();
+    let id = format!("{}-{}", secs, std::process::id());
+    let snapshot = WorkspaceSnapshot::new(
+        &snapshots_dir(),
+        &id,
+        Path::new(&config.tools.filesystem.workspace),
+    )?;
+    info!(snapshot = %id, "Workspace snapshot started; undo with `warden workspace restore`");
+    Ok(Some(Arc::new(snapshot)))
+}
+
+/// Guard for files users attach to messages: the filesystem tools'
+/// workspace, if they are enabled
+pub fn attachment_guard(config: &Config) -> Result<Option<Arc<WorkspaceGuard>>> {
+    let fs = &config.tools.filesystem;
+    if !fs.enabled {
+        return Ok(None);
+    }
+    let guard = WorkspaceGuard::new(PathBuf::from(&fs.workspace), fs.max_file_size_mb)?;
+    Ok(Some(Arc::new(guard)))
+}
+
+/// List snapshots, most recent first
+pub fn list(output: OutputFormat) -> Result<()> {
+    let mut snapshots = WorkspaceSnapshot::list(&snapshots_dir())?;
+    snapshots.reverse();
+
+    if output == OutputFormat::Json {
+        return super::print_json(&snapshots);
+    }
+    if snapshots.is_empty() {
+        println!("No workspace snapshots.");
+        return Ok(());
+    }
+    for s in &snapshots {
+        println!(
+            "{}  {:>4} paths  {}",
+            s.id,
+            s.entries.len(),
+            s.workspace.display()
+        );
+    }
+    Ok(())
+}
+
+/// Restore snapshot `id`, or the most recent one
+pub fn restore(id: Option<String>, output: OutputFormat) -> Result<()> {
+    let dir = snapshots_dir();
+    let id = match id {
+        Some(id) => id,
+        None => WorkspaceSnapshot::list(&dir)?
+            .pop()
+            .map(|s| s.id)
+            .context("No workspace snapshots to restore")?,
+    };
+    let summary = WorkspaceSnapshot::restore(&dir, &id)?;
+
+    if output == OutputFormat::Json {
+        return super::print_json(&summary);
+    }
+    println!(
+        "Restored snapshot {}: {} files restored, {} files removed",
+        summary.id, summary.restored, summary.removed
+    );
+    Ok(())
+}
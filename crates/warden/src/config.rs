@@ -1,11 +1,19 @@
+use crate::cli::ExecutionMode;
 use anyhow::{Context, Result};
+use operon_runtime::{
+    CompositeSpec, ContextConfig, NestedStorage, PermissionLevel, ReasoningEffort, TaskKind,
+};
+use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
-use std::collections::HashMap;
+use std::collections::{BTreeMap, HashMap};
 use std::fs;
-use std::path::Path;
+use std::path::{Path, PathBuf};
 
-#[derive(Debug, Deserialize, Serialize)]
+#[derive(Debug, Deserialize, Serialize, JsonSchema)]
 pub struct Config {
+    /// Additional config files merged into this one (paths relative to this file, globs allowed)
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub include: Vec<String>,
     /// Config schema version
     #[serde(default = "default_config_version")]
     pub version: u32,
@@ -17,13 +25,37 @@ pub struct Config {
     pub memory: MemoryConfig,
     #[serde(default)]
     pub tool_policy: operon_runtime::tool_policy::config::ToolPolicyConfig,
+    /// Agent definitions (tools, permission cap, model, system prompt), keyed
+    /// by agent name (`[agents.<name>]`)
+    #[serde(default)]
+    pub agents: HashMap<String, AgentProfileConfig>,
+    /// Directory of agent files, one `<name>.toml` per agent with the body of
+    /// its `[agents.<name>]` section (relative to the config file); agents defined
+    /// in the config itself take precedence
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub agents_dir: Option<String>,
+    /// Slack/Discord webhook for run summaries and the notify tool
+    #[serde(default)]
+    pub notifications: NotificationsConfig,
+    /// Plans `warden serve` runs on a cron schedule (`[[schedules]]`)
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub schedules: Vec<ScheduleConfig>,
+    /// PII redaction of tool output and saved chat sessions
+    #[serde(default)]
+    pub redaction: RedactionConfig,
+    /// API keys and usage quotas for `warden serve`
+    #[serde(default)]
+    pub gateway: GatewayConfig,
+    /// Where chat (and optionally gateway) sessions are saved
+    #[serde(default)]
+    pub sessions: SessionsConfig,
 }
 
 fn default_config_version() -> u32 {
     1
 }
 
-#[derive(Debug, Deserialize, Serialize)]
+#[derive(Debug, Clone, Deserialize, Serialize, JsonSchema)]
 pub struct LlmConfig {
     /// Anthropic API key (or set ANTHROPIC_API_KEY env)
     #[serde(default)]
@@ -40,6 +72,55 @@ pub struct LlmConfig {
     /// Default model (empty = provider default)
     #[serde(default)]
     pub model: String,
+    /// Probe providers at startup (serve, interactive chat) so bad keys fail fast
+    #[serde(default = "default_enabled")]
+    pub startup_health_check: bool,
+    /// Per-task provider/model overrides: chat, tool_use, summarize, title
+    #[serde(default, skip_serializing_if = "HashMap::is_empty")]
+    pub routes: HashMap<TaskKind, LlmRouteConfig>,
+    /// Anthropic extended thinking budget in tokens, min 1024 (unset = disabled)
+    #[serde(default)]
+    pub thinking_budget: Option<u32>,
+    /// Stream model reasoning to the output instead of keeping it hidden
+    #[serde(default)]
+    pub show_thinking: bool,
+    /// OpenAI o-series reasoning effort: "low", "medium", "high" (unset = model default)
+    #[serde(default)]
+    pub reasoning_effort: Option<ReasoningEffort>,
+    /// Gemini safety thresholds by harm category,
+    /// e.g. HARM_CATEGORY_DANGEROUS_CONTENT = "BLOCK_ONLY_HIGH"
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub gemini_safety: BTreeMap<String, String>,
+    /// Summarize chat history in the background every N new messages into
+    /// the session metadata (0 = off); uses the `summarize` route if set
+    #[serde(default)]
+    pub summarize_every: usize,
+    /// Stream responses as they are generated; an interrupted reply stays in
+    /// the session, marked as interrupted
+    #[serde(default)]
+    pub stream: bool,
+    /// Keep long chats within the context window: strategy "truncate_oldest",
+    /// "sliding_window" or "summarize" (default: send the whole history)
+    #[serde(default)]
+    pub context: ContextConfig,
+    /// Model context window in tokens: prompts are checked against it before
+    /// they are sent (unset = not checked)
+    #[serde(default)]
+    pub context_window: Option<usize>,
+    /// Spend limit per session in US dollars, estimated from the model's list
+    /// prices (unset = no limit)
+    #[serde(default)]
+    pub max_cost_usd: Option<f64>,
+}
+
+/// Provider and model used for one task kind
+#[derive(Debug, Clone, Deserialize, Serialize, JsonSchema)]
+pub struct LlmRouteConfig {
+    /// "anthropic", "openai", or "gemini"
+    pub provider: String,
+    /// Model for this route (empty = provider default)
+    #[serde(default)]
+    pub model: String,
 }
 
 fn default_provider() -> String {
@@ -54,11 +135,105 @@ impl Default for LlmConfig {
             gemini_api_key: String::new(),
             provider: default_provider(),
             model: String::new(),
+            startup_health_check: true,
+            routes: HashMap::new(),
+            thinking_budget: None,
+            show_thinking: false,
+            reasoning_effort: None,
+            gemini_safety: BTreeMap::new(),
+            summarize_every: 0,
+            stream: false,
+            context: ContextConfig::default(),
+            context_window: None,
+            max_cost_usd: None,
         }
     }
 }
 
-#[derive(Debug, Deserialize, Serialize)]
+#[derive(Debug, Default, Deserialize, Serialize, JsonSchema)]
+pub struct AgentProfileConfig {
+    /// What the agent is for, shown by `warden agent list`
+    #[serde(default)]
+    pub description: Option<String>,
+
+    /// Tools exposed to this agent (empty = all registered)
+    #[serde(default)]
+    pub tools: Vec<String>,
+
+    /// Highest tool permission this agent may use: "read", "write", "execute"
+    /// (default), "network", "admin" (no cap)
+    #[serde(default)]
+    pub max_permission: Option<PermissionLevel>,
+
+    /// Model for this agent (default: llm.model)
+    #[serde(default)]
+    pub model: Option<String>,
+
+    /// System prompt replacing the built-in one, or a template reference
+    /// like "template:code-reviewer@2" (from ~/.silentclaw/prompts)
+    #[serde(default)]
+    pub system_prompt: Option<String>,
+
+    /// Values for `{{var}}` in the prompt template (`agent` defaults to the name)
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub prompt_vars: BTreeMap<String, String>,
+
+    /// JSON file of example conversations sent ahead of each session:
+    /// `[{"user": ..., "tool_calls": [{"name", "input", "output"}], "assistant": ...}]`
+    #[serde(default)]
+    pub examples: Option<String>,
+
+    /// Estimated tokens the examples may use (default 2000)
+    #[serde(default)]
+    pub examples_max_tokens: Option<usize>,
+
+    /// Sampling temperature, 0-2 (default 0.7)
+    #[serde(default)]
+    pub temperature: Option<f32>,
+
+    /// Max tokens per response (default 4096)
+    #[serde(default)]
+    pub max_tokens: Option<u32>,
+
+    /// Max LLM calls per user turn (default 10)
+    #[serde(default)]
+    pub max_iterations: Option<usize>,
+
+    /// Spend limit per session in US dollars (default: llm.max_cost_usd)
+    #[serde(default)]
+    pub max_cost_usd: Option<f64>,
+}
+
+#[derive(Debug, Deserialize, Serialize, JsonSchema)]
+pub struct ScheduleConfig {
+    /// Unique name (letters, digits, `-`, `_`); keys the run history
+    pub name: String,
+
+    /// Cron expression, "minute hour day month weekday" (a leading seconds
+    /// field is allowed), in local time
+    pub cron: String,
+
+    /// Plan file; relative paths resolve against serve's working directory
+    pub plan: String,
+
+    /// Agent profile (`[agents.<name>]`) whose tools and max_permission the
+    /// plan must stay within
+    #[serde(default)]
+    pub agent: Option<String>,
+
+    /// "auto" (runtime.dry_run), "dry-run" or "execute"
+    #[serde(default = "default_schedule_mode")]
+    pub mode: ExecutionMode,
+
+    #[serde(default = "default_enabled")]
+    pub enabled: bool,
+}
+
+fn default_schedule_mode() -> ExecutionMode {
+    ExecutionMode::Auto
+}
+
+#[derive(Debug, Deserialize, Serialize, JsonSchema)]
 pub struct RuntimeConfig {
     #[serde(default = "default_dry_run")]
     pub dry_run: bool,
@@ -68,9 +243,72 @@ pub struct RuntimeConfig {
 
     #[serde(default = "default_max_parallel")]
     pub max_parallel: usize,
+
+    /// Per-resource-class concurrency caps, e.g. `{ shell = 2 }`. A step's class
+    /// is its `resource_class` field, else its tool name.
+    #[serde(default)]
+    pub resource_limits: HashMap<String, usize>,
+
+    /// Where nested `plan` steps store their outputs: "shared" (parent's keys)
+    /// or "isolated" (prefixed with the plan step's id)
+    #[serde(default)]
+    pub nested_storage: NestedStorage,
+
+    /// Confine warden and its tools to the workspace and state paths (Linux)
+    #[serde(default)]
+    pub landlock: LandlockConfig,
+
+    /// Run selected tools in a per-session container (`[runtime.container]`)
+    #[serde(default)]
+    pub container: ContainerConfig,
+}
+
+#[derive(Debug, Deserialize, Serialize, JsonSchema)]
+pub struct ContainerConfig {
+    #[serde(default)]
+    pub enabled: bool,
+
+    /// "docker" or "podman"
+    #[serde(default = "default_container_engine")]
+    pub engine: String,
+
+    /// Image with the tool entrypoint (by default `warden exec-tool`) on PATH
+    #[serde(default)]
+    pub image: String,
+
+    /// Tools run in the container; the rest run in-process
+    #[serde(default = "default_container_tools")]
+    pub tools: Vec<String>,
+
+    /// Command run in the container for each call, followed by the tool name
+    #[serde(default = "default_container_entrypoint")]
+    pub entrypoint: Vec<String>,
+
+    /// Give containers network access
+    #[serde(default)]
+    pub network: bool,
+}
+
+#[derive(Debug, Default, Deserialize, Serialize, JsonSchema)]
+pub struct LandlockConfig {
+    /// Apply Landlock rules before run-plan, chat and serve start any tool
+    #[serde(default)]
+    pub enabled: bool,
+
+    /// Refuse to start when the kernel can't enforce the rules
+    #[serde(default)]
+    pub required: bool,
+
+    /// Extra paths tools may read and execute from
+    #[serde(default)]
+    pub read_paths: Vec<String>,
+
+    /// Extra paths tools may write to
+    #[serde(default)]
+    pub write_paths: Vec<String>,
 }
 
-#[derive(Debug, Deserialize, Serialize)]
+#[derive(Debug, Deserialize, Serialize, JsonSchema)]
 pub struct ToolsConfig {
     #[serde(default)]
     pub shell: ShellConfig,
@@ -81,11 +319,47 @@ pub struct ToolsConfig {
     #[serde(default)]
     pub filesystem: FilesystemConfig,
 
+    /// Remote host for the ssh_shell / ssh_read_file / ssh_write_file tools
+    #[serde(default)]
+    pub ssh: SshConfig,
+
+    /// Cluster and limits for the kubernetes_job tool
+    #[serde(default)]
+    pub kubernetes: KubernetesConfig,
+
+    /// Repository and token for the github tool
+    #[serde(default)]
+    pub github: GitHubConfig,
+
+    /// SMTP relay and recipient allowlist for the email tool
+    #[serde(default)]
+    pub email: EmailConfig,
+
+    /// capture_screen / capture_window tools (Admin permission)
+    #[serde(default)]
+    pub screenshot: ScreenshotConfig,
+
     #[serde(default)]
     pub timeouts: HashMap<String, u64>,
+
+    /// Seconds to reuse a tool's result for identical input, e.g. `{ read_file = 30 }`
+    #[serde(default)]
+    pub cache_ttl: HashMap<String, u64>,
+
+    /// Chains of tools exposed as one tool (`[[tools.composites]]`)
+    #[serde(default)]
+    pub composites: Vec<CompositeSpec>,
+
+    /// Extra names for tools, e.g. `{ bash = "shell", sh = "shell" }`
+    #[serde(default)]
+    pub aliases: BTreeMap<String, String>,
+
+    /// Registered name → new name, e.g. to move a plugin tool off a built-in's name
+    #[serde(default)]
+    pub renames: BTreeMap<String, String>,
 }
 
-#[derive(Debug, Deserialize, Serialize)]
+#[derive(Debug, Deserialize, Serialize, JsonSchema)]
 pub struct FilesystemConfig {
     #[serde(default = "default_enabled")]
     pub enabled: bool,
@@ -97,6 +371,20 @@ pub struct FilesystemConfig {
     /// Max file size in MB for read operations
     #[serde(default = "default_max_file_size_mb")]
     pub max_file_size_mb: u64,
+
+    /// Max size in MB of a file the tools write (defaults to max_file_size_mb)
+    #[serde(default)]
+    pub max_write_size_mb: Option<u64>,
+
+    /// Total MB the tools may write per agent session, so a runaway agent
+    /// cannot fill the disk (unlimited when unset)
+    #[serde(default)]
+    pub write_quota_mb: Option<u64>,
+
+    /// Save files before the filesystem tools first change them in execute
+    /// mode, so `warden workspace restore` can revert a session
+    #[serde(default)]
+    pub snapshot: bool,
 }
 
 fn default_workspace() -> String {
@@ -113,11 +401,24 @@ impl Default for FilesystemConfig {
             enabled: default_enabled(),
             workspace: default_workspace(),
             max_file_size_mb: default_max_file_size_mb(),
+            max_write_size_mb: None,
+            write_quota_mb: None,
+            snapshot: false,
+        }
+    }
+}
+
+impl FilesystemConfig {
+    pub fn write_limits(&self) -> operon_adapters::WriteLimits {
+        const MB: u64 = 1024 * 1024;
+        operon_adapters::WriteLimits {
+            max_file_size: self.max_write_size_mb.map(|mb| mb * MB),
+            quota: self.write_quota_mb.map(|mb| mb * MB),
         }
     }
 }
 
-#[derive(Debug, Deserialize, Serialize)]
+#[derive(Debug, Deserialize, Serialize, JsonSchema)]
 pub struct ShellConfig {
     #[serde(default = "default_enabled")]
     pub enabled: bool,
@@ -129,9 +430,187 @@ pub struct ShellConfig {
     /// If non-empty, only allow commands starting with these executables
     #[serde(default)]
     pub allowlist: Vec<String>,
+
+    /// Seconds a timed-out command gets to exit after SIGTERM before SIGKILL
+    #[serde(default = "default_grace_period")]
+    pub grace_period_secs: u64,
+
+    /// Run commands inside bubblewrap/firejail (`[tools.shell.sandbox]`)
+    #[serde(default)]
+    pub sandbox: ShellSandboxConfig,
+}
+
+#[derive(Debug, Deserialize, Serialize, JsonSchema)]
+pub struct ShellSandboxConfig {
+    #[serde(default)]
+    pub enabled: bool,
+
+    /// "bubblewrap" or "firejail"
+    #[serde(default = "default_sandbox_program")]
+    pub program: String,
+
+    /// What commands may do: "read" mounts the workspace read-only, "write" and
+    /// "execute" make it writable, "network" and "admin" also allow network access
+    #[serde(default = "default_sandbox_level")]
+    pub level: PermissionLevel,
+
+    /// Directory bind-mounted into the sandbox (default: `tools.filesystem.workspace`)
+    #[serde(default)]
+    pub workspace: Option<String>,
+}
+
+#[derive(Debug, Deserialize, Serialize, JsonSchema)]
+pub struct SshConfig {
+    #[serde(default)]
+    pub enabled: bool,
+
+    #[serde(default)]
+    pub host: String,
+
+    /// Login user (default: the ssh client's)
+    #[serde(default)]
+    pub user: Option<String>,
+
+    #[serde(default = "default_ssh_port")]
+    pub port: u16,
+
+    /// Private key for authentication (default: the ssh client's keys/agent);
+    /// passwords are never prompted for
+    #[serde(default)]
+    pub identity_file: Option<String>,
+
+    /// Remote directory commands run in; file tool paths are relative to it
+    #[serde(default = "default_workspace")]
+    pub remote_root: String,
+
+    /// Bytes of stdout and of stderr kept per call
+    #[serde(default = "default_ssh_max_output_bytes")]
+    pub max_output_bytes: usize,
+}
+
+#[derive(Debug, Deserialize, Serialize, JsonSchema)]
+pub struct KubernetesConfig {
+    #[serde(default)]
+    pub enabled: bool,
+
+    /// kubeconfig context (default: the current one)
+    #[serde(default)]
+    pub context: Option<String>,
+
+    #[serde(default = "default_kubernetes_namespace")]
+    pub namespace: String,
+
+    #[serde(default = "default_kubectl")]
+    pub kubectl: String,
+
+    /// Job manifest file (JSON or YAML) with `{{name}}`, `{{image}}`, `{{command}}`,
+    /// ... placeholders (default: a single-container Job)
+    #[serde(default)]
+    pub template: Option<String>,
+
+    /// CPU request/limit of job containers
+    #[serde(default = "default_kubernetes_cpu")]
+    pub cpu: String,
+
+    /// Memory request/limit of job containers
+    #[serde(default = "default_kubernetes_memory")]
+    pub memory: String,
+
+    /// Longest a job may run before it is deleted
+    #[serde(default = "default_kubernetes_timeout")]
+    pub timeout_secs: u64,
+
+    /// Log bytes returned per job (the tail is kept)
+    #[serde(default = "default_kubernetes_max_log_bytes")]
+    pub max_log_bytes: usize,
+}
+
+#[derive(Debug, Default, Deserialize, Serialize, JsonSchema)]
+pub struct GitHubConfig {
+    #[serde(default)]
+    pub enabled: bool,
+
+    /// Repository the tool works on, as "owner/name"
+    #[serde(default)]
+    pub repo: String,
+
+    /// Token scoped to `repo` (or set GITHUB_TOKEN env)
+    #[serde(default)]
+    pub token: String,
+
+    /// API root for GitHub Enterprise (default: https://api.github.com)
+    #[serde(default)]
+    pub api_url: Option<String>,
+
+    /// Git host pull request branches are pushed to, the only host the token
+    /// is sent to by git (default: https://github.com)
+    #[serde(default)]
+    pub git_url: Option<String>,
+
+    /// Branch pull requests target (default: "main")
+    #[serde(default)]
+    pub base_branch: Option<String>,
+
+    /// Allowed actions: list_issues, create_issue, get_pr_diff, review_pr,
+    /// open_pr (default: all)
+    #[serde(default)]
+    pub actions: Vec<String>,
+}
+
+#[derive(Debug, Deserialize, Serialize, JsonSchema)]
+pub struct EmailConfig {
+    #[serde(default)]
+    pub enabled: bool,
+
+    /// SMTP relay host
+    #[serde(default)]
+    pub host: String,
+
+    /// Relay port (default: 587 for starttls, 465 for tls, 25 for none)
+    #[serde(default)]
+    pub port: Option<u16>,
+
+    /// "starttls", "tls" or "none"
+    #[serde(default = "default_smtp_security")]
+    pub security: String,
+
+    /// Relay login; no authentication when unset
+    #[serde(default)]
+    pub username: Option<String>,
+
+    /// Relay password (or set SILENTCLAW_SMTP_PASSWORD env)
+    #[serde(default)]
+    pub password: String,
+
+    /// Sender, e.g. "SilentClaw <bot@example.com>"
+    #[serde(default)]
+    pub from: String,
+
+    /// Addresses mail may go to: exact addresses, or "@domain" for a whole domain
+    #[serde(default)]
+    pub allowed_recipients: Vec<String>,
+}
+
+#[derive(Debug, Deserialize, Serialize, JsonSchema)]
+pub struct ScreenshotConfig {
+    #[serde(default)]
+    pub enabled: bool,
+
+    /// Command for capture_screen, with an `{output}` placeholder
+    /// (default: `screencapture` on macOS, ImageMagick `import` elsewhere)
+    #[serde(default)]
+    pub screen_command: Vec<String>,
+
+    /// Command for capture_window, with `{output}` and `{window}` placeholders
+    #[serde(default)]
+    pub window_command: Vec<String>,
+
+    /// Largest image returned to the model
+    #[serde(default = "default_screenshot_max_bytes")]
+    pub max_bytes: usize,
 }
 
-#[derive(Debug, Deserialize, Serialize)]
+#[derive(Debug, Deserialize, Serialize, JsonSchema)]
 pub struct PythonConfig {
     #[serde(default = "default_enabled")]
     pub enabled: bool,
@@ -148,6 +627,70 @@ fn default_timeout() -> u64 {
     60
 }
 
+fn default_grace_period() -> u64 {
+    5
+}
+
+fn default_container_engine() -> String {
+    "docker".to_string()
+}
+
+fn default_container_tools() -> Vec<String> {
+    vec!["shell".to_string()]
+}
+
+fn default_container_entrypoint() -> Vec<String> {
+    vec!["warden".to_string(), "exec-tool".to_string()]
+}
+
+fn default_sandbox_program() -> String {
+    "bubblewrap".to_string()
+}
+
+fn default_sandbox_level() -> PermissionLevel {
+    PermissionLevel::Execute
+}
+
+fn default_ssh_port() -> u16 {
+    22
+}
+
+fn default_ssh_max_output_bytes() -> usize {
+    operon_adapters::ssh_tool::DEFAULT_MAX_OUTPUT_BYTES
+}
+
+fn default_screenshot_max_bytes() -> usize {
+    operon_adapters::screenshot_tool::DEFAULT_MAX_IMAGE_BYTES
+}
+
+fn default_kubernetes_namespace() -> String {
+    "default".to_string()
+}
+
+fn default_kubectl() -> String {
+    "kubectl".to_string()
+}
+
+fn default_smtp_security() -> String {
+    "starttls".to_string()
+}
+
+fn default_kubernetes_cpu() -> String {
+    "500m".to_string()
+}
+
+fn default_kubernetes_memory() -> String {
+    "512Mi".to_string()
+}
+
+fn default_kubernetes_timeout() -> u64 {
+    600
+}
+
+fn default_kubernetes_max_log_bytes() -> usize {
+    1024 * 1024
+}
+
 fn default_max_parallel() -> usize {
     4
 }
@@ -166,43 +709,296 @@ impl Default for ShellConfig {
             enabled: default_enabled(),
             blocklist: Vec::new(),
             allowlist: Vec::new(),
+            grace_period_secs: default_grace_period(),
+            sandbox: ShellSandboxConfig::default(),
         }
     }
 }
 
-impl Default for PythonConfig {
+impl Default for ContainerConfig {
     fn default() -> Self {
         Self {
-            enabled: default_enabled(),
-            scripts_dir: default_scripts_dir(),
+            enabled: false,
+            engine: default_container_engine(),
+            image: String::new(),
+            tools: default_container_tools(),
+            entrypoint: default_container_entrypoint(),
+            network: false,
         }
     }
 }
 
-#[derive(Debug, Deserialize, Serialize)]
-pub struct MemoryConfig {
-    #[serde(default)]
-    pub enabled: bool,
-
-    /// Path to SQLite database for memory index
-    #[serde(default = "default_memory_db_path")]
-    pub db_path: String,
-
-    /// Embedding provider: "openai" or "voyage"
-    #[serde(default = "default_embedding_provider")]
-    pub embedding_provider: String,
-
-    /// Embedding model name
-    #[serde(default = "default_embedding_model")]
-    pub embedding_model: String,
-
-    /// Auto-reindex on file changes
-    #[serde(default = "default_auto_reindex")]
-    pub auto_reindex: bool,
+impl Default for ShellSandboxConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            program: default_sandbox_program(),
+            level: default_sandbox_level(),
+            workspace: None,
+        }
+    }
 }
 
-fn default_memory_db_path() -> String {
-    "~/.silentclaw/memory.db".to_string()
+impl Default for SshConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            host: String::new(),
+            user: None,
+            port: default_ssh_port(),
+            identity_file: None,
+            remote_root: default_workspace(),
+            max_output_bytes: default_ssh_max_output_bytes(),
+        }
+    }
+}
+
+impl Default for KubernetesConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            context: None,
+            namespace: default_kubernetes_namespace(),
+            kubectl: default_kubectl(),
+            template: None,
+            cpu: default_kubernetes_cpu(),
+            memory: default_kubernetes_memory(),
+            timeout_secs: default_kubernetes_timeout(),
+            max_log_bytes: default_kubernetes_max_log_bytes(),
+        }
+    }
+}
+
+impl Default for EmailConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            host: String::new(),
+            port: None,
+            security: default_smtp_security(),
+            username: None,
+            password: String::new(),
+            from: String::new(),
+            allowed_recipients: Vec::new(),
+        }
+    }
+}
+
+impl Default for ScreenshotConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            screen_command: Vec::new(),
+            window_command: Vec::new(),
+            max_bytes: default_screenshot_max_bytes(),
+        }
+    }
+}
+
+impl Default for PythonConfig {
+    fn default() -> Self {
+        Self {
+            enabled: default_enabled(),
+            scripts_dir: default_scripts_dir(),
+        }
+    }
+}
+
+#[derive(Debug, Deserialize, Serialize, JsonSchema)]
+pub struct NotificationsConfig {
+    #[serde(default)]
+    pub enabled: bool,
+
+    /// "slack" or "discord"
+    #[serde(default = "default_webhook_kind")]
+    pub kind: String,
+
+    /// Incoming webhook URL (or set SILENTCLAW_WEBHOOK_URL env)
+    #[serde(default)]
+    pub webhook_url: String,
+
+    /// Register the `notify` tool so agents and plans can post messages
+    #[serde(default = "default_enabled")]
+    pub tool: bool,
+
+    /// Post a summary (last reply, duration, tokens) when a chat session ends
+    #[serde(default = "default_enabled")]
+    pub on_session_end: bool,
+
+    /// Post a summary (status, duration, steps) when a plan run ends
+    #[serde(default = "default_enabled")]
+    pub on_plan_end: bool,
+}
+
+#[derive(Debug, Deserialize, Serialize, JsonSchema)]
+pub struct SessionsConfig {
+    /// "json" (a file per session in ~/.silentclaw/sessions) or "sqlite"
+    /// (~/.silentclaw/sessions.db, needed by `warden session query`/`prune`)
+    #[serde(default = "default_session_store")]
+    pub store: String,
+    /// Also save `warden serve` sessions to the store after each message
+    #[serde(default)]
+    pub save_gateway_sessions: bool,
+}
+
+fn default_session_store() -> String {
+    "json".to_string()
+}
+
+impl Default for SessionsConfig {
+    fn default() -> Self {
+        Self {
+            store: default_session_store(),
+            save_gateway_sessions: false,
+        }
+    }
+}
+
+#[derive(Debug, Default, Deserialize, Serialize, JsonSchema)]
+pub struct RedactionConfig {
+    /// Replace emails, API tokens and card numbers in tool output before the
+    /// LLM sees it, and in chat sessions before they are saved
+    #[serde(default)]
+    pub enabled: bool,
+
+    /// Extra patterns (regex) by kind, e.g. employee_id = 'EMP-\d{6}'
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub patterns: BTreeMap<String, String>,
+}
+
+#[derive(Debug, Deserialize, Serialize, JsonSchema)]
+pub struct GatewayConfig {
+    /// Bearer tokens by name (`name = "token"`); the name identifies the
+    /// caller for quotas. Empty = no authentication.
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub api_keys: BTreeMap<String, String>,
+
+    /// Identities (`api_keys` names, or "default" for the shared token) that
+    /// see every caller's audit records; others only see their own
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub admin_identities: Vec<String>,
+
+    #[serde(default)]
+    pub quotas: QuotaConfig,
+
+    /// CORS origins, e.g. "https://app.example.com". Empty = allow any
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub allowed_origins: Vec<String>,
+
+    /// Paths served without an API key; a trailing `*` matches any suffix
+    #[serde(default = "default_auth_exempt_paths")]
+    pub auth_exempt_paths: Vec<String>,
+
+    /// Headers set by a reverse proxy (e.g. "X-Forwarded-For") that carry
+    /// the client address for rate limits and quotas. Only list headers
+    /// your proxy overwrites; clients can send them too.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub trusted_proxy_headers: Vec<String>,
+}
+
+fn default_auth_exempt_paths() -> Vec<String> {
+    vec!["/health".to_string()]
+}
+
+impl Default for GatewayConfig {
+    fn default() -> Self {
+        Self {
+            api_keys: BTreeMap::new(),
+            admin_identities: Vec::new(),
+            quotas: QuotaConfig::default(),
+            allowed_origins: Vec::new(),
+            auth_exempt_paths: default_auth_exempt_paths(),
+            trusted_proxy_headers: Vec::new(),
+        }
+    }
+}
+
+#[derive(Debug, Default, Deserialize, Serialize, JsonSchema)]
+pub struct QuotaConfig {
+    /// Count requests and LLM tokens per API key (per client IP without
+    /// keys) and answer 429 once a limit is reached
+    #[serde(default)]
+    pub enabled: bool,
+
+    /// Limits for every identity without its own entry
+    #[serde(default, flatten)]
+    pub default: QuotaLimitsConfig,
+
+    /// Limits by API key name, replacing the defaults
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub identities: BTreeMap<String, QuotaLimitsConfig>,
+}
+
+/// Quota limits; unset = unlimited. Days and months are UTC.
+#[derive(Debug, Clone, Default, Deserialize, Serialize, JsonSchema)]
+pub struct QuotaLimitsConfig {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub daily_requests: Option<u64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub daily_tokens: Option<u64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub monthly_requests: Option<u64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub monthly_tokens: Option<u64>,
+}
+
+impl From<&QuotaLimitsConfig> for operon_gateway::QuotaLimits {
+    fn from(limits: &QuotaLimitsConfig) -> Self {
+        Self {
+            daily_requests: limits.daily_requests,
+            daily_tokens: limits.daily_tokens,
+            monthly_requests: limits.monthly_requests,
+            monthly_tokens: limits.monthly_tokens,
+        }
+    }
+}
+
+fn default_webhook_kind() -> String {
+    "slack".to_string()
+}
+
+impl Default for NotificationsConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            kind: default_webhook_kind(),
+            webhook_url: String::new(),
+            tool: default_enabled(),
+            on_session_end: default_enabled(),
+            on_plan_end: default_enabled(),
+        }
+    }
+}
+
+#[derive(Debug, Deserialize, Serialize, JsonSchema)]
+pub struct MemoryConfig {
+    #[serde(default)]
+    pub enabled: bool,
+
+    /// Path to SQLite database for memory index
+    #[serde(default = "default_memory_db_path")]
+    pub db_path: String,
+
+    /// Embedding provider: "openai" or "voyage"
+    #[serde(default = "default_embedding_provider")]
+    pub embedding_provider: String,
+
+    /// Embedding model name
+    #[serde(default = "default_embedding_model")]
+    pub embedding_model: String,
+
+    /// Vector size the embedding model returns. Changing this or the model
+    /// needs `warden memory migrate`.
+    #[serde(default = "default_embedding_dimensions", alias = "vector_dimension")]
+    pub embedding_dimensions: usize,
+
+    /// Auto-reindex on file changes
+    #[serde(default = "default_auto_reindex")]
+    pub auto_reindex: bool,
+}
+
+fn default_memory_db_path() -> String {
+    "~/.silentclaw/memory.db".to_string()
 }
 
 fn default_embedding_provider() -> String {
@@ -213,6 +1009,10 @@ fn default_embedding_model() -> String {
     "text-embedding-3-small".to_string()
 }
 
+fn default_embedding_dimensions() -> usize {
+    1536
+}
+
 fn default_auto_reindex() -> bool {
     true
 }
@@ -224,6 +1024,7 @@ impl Default for MemoryConfig {
             db_path: default_memory_db_path(),
             embedding_provider: default_embedding_provider(),
             embedding_model: default_embedding_model(),
+            embedding_dimensions: default_embedding_dimensions(),
             auto_reindex: default_auto_reindex(),
         }
     }
@@ -233,28 +1034,50 @@ impl Config {
     /// Create a default config (used as initial value for ConfigManager)
     pub fn default_config() -> Self {
         Self {
+            include: Vec::new(),
             version: default_config_version(),
             runtime: RuntimeConfig {
                 dry_run: default_dry_run(),
                 timeout_secs: default_timeout(),
                 max_parallel: default_max_parallel(),
+                resource_limits: HashMap::new(),
+                nested_storage: NestedStorage::default(),
+                landlock: LandlockConfig::default(),
+                container: ContainerConfig::default(),
             },
             tools: ToolsConfig {
                 shell: ShellConfig::default(),
                 python: PythonConfig::default(),
                 filesystem: FilesystemConfig::default(),
+                ssh: SshConfig::default(),
+                kubernetes: KubernetesConfig::default(),
+                github: GitHubConfig::default(),
+                email: EmailConfig::default(),
+                screenshot: ScreenshotConfig::default(),
                 timeouts: HashMap::new(),
+                cache_ttl: HashMap::new(),
+                composites: Vec::new(),
+                aliases: BTreeMap::new(),
+                renames: BTreeMap::new(),
             },
             llm: LlmConfig::default(),
             memory: MemoryConfig::default(),
             tool_policy: operon_runtime::tool_policy::config::ToolPolicyConfig::default(),
+            agents: HashMap::new(),
+            agents_dir: None,
+            notifications: NotificationsConfig::default(),
+            redaction: RedactionConfig::default(),
+            gateway: GatewayConfig::default(),
+            sessions: SessionsConfig::default(),
+            schedules: Vec::new(),
         }
     }
 
     /// Validate configuration values
     pub fn validate(&self) -> Result<()> {
-        if self.runtime.timeout_secs == 0 {
-            anyhow::bail!("runtime.timeout_secs must be > 0");
+        let errors = self.validation_errors();
+        if !errors.is_empty() {
+            anyhow::bail!("Invalid config: {}", errors.join("; "));
         }
         if self.runtime.timeout_secs > 3600 {
             tracing::warn!(
@@ -262,10 +1085,388 @@ impl Config {
                 self.runtime.timeout_secs
             );
         }
+        Ok(())
+    }
+
+    /// Collect every semantic validation error, each prefixed with its key path
+    pub fn validation_errors(&self) -> Vec<String> {
+        let mut errors = Vec::new();
+
+        if self.runtime.timeout_secs == 0 {
+            errors.push("runtime.timeout_secs must be > 0".to_string());
+        }
         if self.runtime.max_parallel == 0 || self.runtime.max_parallel > 100 {
-            anyhow::bail!("runtime.max_parallel must be between 1-100");
+            errors.push("runtime.max_parallel must be between 1-100".to_string());
         }
-        Ok(())
+        for (class, &limit) in &self.runtime.resource_limits {
+            if limit == 0 {
+                errors.push(format!("runtime.resource_limits.{} must be > 0", class));
+            }
+        }
+        if !LLM_PROVIDERS.contains(&self.llm.provider.as_str()) {
+            errors.push(format!(
+                "llm.provider must be one of {} (got '{}')",
+                LLM_PROVIDERS.join(", "),
+                self.llm.provider
+            ));
+        }
+        if !SESSION_STORES.contains(&self.sessions.store.as_str()) {
+            errors.push(format!(
+                "sessions.store must be one of {} (got '{}')",
+                SESSION_STORES.join(", "),
+                self.sessions.store
+            ));
+        }
+        for (task, route) in &self.llm.routes {
+            if !LLM_PROVIDERS.contains(&route.provider.as_str()) {
+                errors.push(format!(
+                    "llm.routes.{}.provider must be one of {} (got '{}')",
+                    task.as_str(),
+                    LLM_PROVIDERS.join(", "),
+                    route.provider
+                ));
+            }
+        }
+        if self
+            .llm
+            .thinking_budget
+            .is_some_and(|b| b < MIN_THINKING_BUDGET)
+        {
+            errors.push(format!(
+                "llm.thinking_budget must be >= {}",
+                MIN_THINKING_BUDGET
+            ));
+        }
+        if self.llm.context.max_tokens == 0 {
+            errors.push("llm.context.max_tokens must be > 0".to_string());
+        }
+        if self.llm.context.keep_recent == 0 {
+            errors.push("llm.context.keep_recent must be > 0".to_string());
+        }
+        if self.llm.context_window == Some(0) {
+            errors.push("llm.context_window must be > 0".to_string());
+        }
+        if let Some(limit) = self.llm.max_cost_usd {
+            if !limit.is_finite() || limit <= 0.0 {
+                errors.push(format!("llm.max_cost_usd must be > 0 (got {})", limit));
+            }
+        }
+        for (category, threshold) in &self.llm.gemini_safety {
+            if !category.starts_with("HARM_CATEGORY_") {
+                errors.push(format!(
+                    "llm.gemini_safety key must be a HARM_CATEGORY_* name (got '{}')",
+                    category
+                ));
+            }
+            if !GEMINI_SAFETY_THRESHOLDS.contains(&threshold.as_str()) {
+                errors.push(format!(
+                    "llm.gemini_safety.{} must be one of {} (got '{}')",
+                    category,
+                    GEMINI_SAFETY_THRESHOLDS.join(", "),
+                    threshold
+                ));
+            }
+        }
+        if let Err(e) = self
+            .tools
+            .shell
+            .sandbox
+            .program
+            .parse::<operon_adapters::SandboxProgram>()
+        {
+            errors.push(format!("tools.shell.sandbox.program: {}", e));
+        }
+        let container = &self.runtime.container;
+        if container.enabled {
+            if !CONTAINER_ENGINES.contains(&container.engine.as_str()) {
+                errors.push(format!(
+                    "runtime.container.engine must be one of {} (got '{}')",
+                    CONTAINER_ENGINES.join(", "),
+                    container.engine
+                ));
+            }
+            if container.image.trim().is_empty() {
+                errors.push("runtime.container.image is required when enabled".to_string());
+            }
+            if container.entrypoint.is_empty() {
+                errors.push("runtime.container.entrypoint must not be empty".to_string());
+            }
+            // The container cannot nest the host's sandbox program
+            if container.tools.iter().any(|t| t == "shell") && self.tools.shell.sandbox.enabled {
+                errors.push(
+                    "runtime.container.tools cannot include \"shell\" while tools.shell.sandbox is enabled"
+                        .to_string(),
+                );
+            }
+        }
+        let ssh = &self.tools.ssh;
+        if ssh.enabled {
+            if ssh.host.trim().is_empty() {
+                errors.push("tools.ssh.host is required when enabled".to_string());
+            }
+            if ssh.max_output_bytes == 0 {
+                errors.push("tools.ssh.max_output_bytes must be > 0".to_string());
+            }
+        }
+        let mut schedule_names = std::collections::HashSet::new();
+        for schedule in &self.schedules {
+            let valid_name = !schedule.name.is_empty()
+                && schedule
+                    .name
+                    .chars()
+                    .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_');
+            if !valid_name {
+                errors.push(format!(
+                    "schedules: name '{}' may only contain letters, digits, '-' and '_'",
+                    schedule.name
+                ));
+            } else if !schedule_names.insert(schedule.name.as_str()) {
+                errors.push(format!("schedules: duplicate name '{}'", schedule.name));
+            }
+            if let Err(e) = crate::schedules::parse_cron(&schedule.cron) {
+                errors.push(format!("schedules.{}.cron: {}", schedule.name, e));
+            }
+            if let Some(agent) = &schedule.agent {
+                if !self.agents.contains_key(agent) {
+                    errors.push(format!(
+                        "schedules.{}.agent: no [agents.{}] profile",
+                        schedule.name, agent
+                    ));
+                }
+            }
+        }
+        let mut agent_names: Vec<_> = self.agents.keys().collect();
+        agent_names.sort();
+        for name in agent_names {
+            let agent = &self.agents[name];
+            if agent.temperature.is_some_and(|t| !(0.0..=2.0).contains(&t)) {
+                errors.push(format!("agents.{}.temperature must be between 0-2", name));
+            }
+            if agent.max_tokens == Some(0) {
+                errors.push(format!("agents.{}.max_tokens must be > 0", name));
+            }
+            if agent.max_iterations == Some(0) {
+                errors.push(format!("agents.{}.max_iterations must be > 0", name));
+            }
+        }
+        let notifications = &self.notifications;
+        if notifications.enabled {
+            if let Err(e) = notifications.kind.parse::<operon_adapters::WebhookKind>() {
+                errors.push(format!("notifications.kind: {}", e));
+            }
+            if !notifications.webhook_url.starts_with("https://")
+                && !notifications.webhook_url.starts_with("http://")
+            {
+                errors.push(
+                    "notifications.webhook_url must be an http(s) URL when enabled".to_string(),
+                );
+            }
+        }
+        for (kind, pattern) in &self.redaction.patterns {
+            if let Err(e) = operon_runtime::Redactor::new().with_pattern(kind, pattern) {
+                errors.push(format!("redaction.patterns.{}: {}", kind, e));
+            }
+        }
+        for (name, key) in &self.gateway.api_keys {
+            if key.trim().is_empty() {
+                errors.push(format!("gateway.api_keys.{} must not be empty", name));
+            }
+        }
+        for origin in &self.gateway.allowed_origins {
+            let valid = origin
+                .strip_prefix("https://")
+                .or_else(|| origin.strip_prefix("http://"))
+                .is_some_and(|host| !host.is_empty() && !host.contains('/'));
+            if !valid {
+                errors.push(format!(
+                    "gateway.allowed_origins: '{}' must be scheme://host[:port]",
+                    origin
+                ));
+            }
+        }
+        for path in &self.gateway.auth_exempt_paths {
+            if !path.starts_with('/') {
+                errors.push(format!(
+                    "gateway.auth_exempt_paths: '{}' must start with '/'",
+                    path
+                ));
+            }
+        }
+        for header in &self.gateway.trusted_proxy_headers {
+            if header.is_empty()
+                || !header
+                    .chars()
+                    .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_')
+            {
+                errors.push(format!(
+                    "gateway.trusted_proxy_headers: '{}' is not a valid header name",
+                    header
+                ));
+            }
+        }
+        let kubernetes = &self.tools.kubernetes;
+        if kubernetes.enabled {
+            if kubernetes.namespace.trim().is_empty() {
+                errors.push("tools.kubernetes.namespace must not be empty".to_string());
+            }
+            if kubernetes.timeout_secs == 0 {
+                errors.push("tools.kubernetes.timeout_secs must be > 0".to_string());
+            }
+        }
+        let screenshot = &self.tools.screenshot;
+        if screenshot.enabled {
+            for (field, command, placeholders) in [
+                (
+                    "screen_command",
+                    &screenshot.screen_command,
+                    &["{output}"][..],
+                ),
+                (
+                    "window_command",
+                    &screenshot.window_command,
+                    &["{output}", "{window}"][..],
+                ),
+            ] {
+                for placeholder in placeholders {
+                    if !command.is_empty() && !command.iter().any(|arg| arg.contains(placeholder)) {
+                        errors.push(format!(
+                            "tools.screenshot.{} must contain {}",
+                            field, placeholder
+                        ));
+                    }
+                }
+            }
+        }
+        let github = &self.tools.github;
+        if github.enabled {
+            if github
+                .repo
+                .split('/')
+                .filter(|part| !part.is_empty())
+                .count()
+                != 2
+            {
+                errors.push("tools.github.repo must be \"owner/name\"".to_string());
+            }
+            if github.token.is_empty() {
+                errors.push(
+                    "tools.github.token is required when enabled (or set GITHUB_TOKEN)".to_string(),
+                );
+            }
+            for action in &github.actions {
+                if !operon_adapters::github_tool::GITHUB_ACTIONS.contains(&action.as_str()) {
+                    errors.push(format!("tools.github.actions: unknown action '{}'", action));
+                }
+            }
+        }
+        let email = &self.tools.email;
+        if email.enabled {
+            if email.host.trim().is_empty() {
+                errors.push("tools.email.host is required when enabled".to_string());
+            }
+            if let Err(e) = operon_adapters::EmailTool::new(&email.host, &email.from) {
+                errors.push(format!("tools.email.from: {}", e));
+            }
+            if let Err(e) = email.security.parse::<operon_adapters::SmtpSecurity>() {
+                errors.push(format!("tools.email.security: {}", e));
+            }
+            if email.allowed_recipients.is_empty() {
+                errors.push("tools.email.allowed_recipients must not be empty".to_string());
+            }
+        }
+        if self.tools.filesystem.max_file_size_mb == 0 {
+            errors.push("tools.filesystem.max_file_size_mb must be > 0".to_string());
+        }
+        if self.tools.filesystem.max_write_size_mb == Some(0) {
+            errors.push("tools.filesystem.max_write_size_mb must be > 0".to_string());
+        }
+        if self.tools.filesystem.write_quota_mb == Some(0) {
+            errors.push("tools.filesystem.write_quota_mb must be > 0".to_string());
+        }
+        for (tool, secs) in &self.tools.timeouts {
+            if *secs == 0 {
+                errors.push(format!("tools.timeouts.{} must be > 0", tool));
+            }
+        }
+        for (tool, secs) in &self.tools.cache_ttl {
+            if *secs == 0 {
+                errors.push(format!("tools.cache_ttl.{} must be > 0", tool));
+            }
+        }
+        for (alias, target) in &self.tools.aliases {
+            if alias == target {
+                errors.push(format!("tools.aliases.{} cannot point to itself", alias));
+            }
+        }
+        for (from, to) in &self.tools.renames {
+            if to.is_empty() || from == to {
+                errors.push(format!(
+                    "tools.renames.{} must be a new, non-empty name",
+                    from
+                ));
+            }
+        }
+        for composite in &self.tools.composites {
+            if let Err(e) = composite.validate() {
+                errors.push(format!("tools.composites: {:#}", e));
+            }
+        }
+
+        let policy = &self.tool_policy;
+        if !PERMISSION_LEVELS.contains(&policy.default_permission.to_lowercase().as_str()) {
+            errors.push(format!(
+                "tool_policy.default_permission must be one of {} (got '{}')",
+                PERMISSION_LEVELS.join(", "),
+                policy.default_permission
+            ));
+        }
+        if policy.rate_limit_enabled && policy.max_calls_per_minute == 0 {
+            errors.push(
+                "tool_policy.max_calls_per_minute must be > 0 when rate_limit_enabled".to_string(),
+            );
+        }
+        for (name, limit) in [
+            ("session_calls_per_minute", policy.session_calls_per_minute),
+            (
+                "principal_calls_per_minute",
+                policy.principal_calls_per_minute,
+            ),
+        ] {
+            if limit == Some(0) {
+                errors.push(format!("tool_policy.{} must be > 0", name));
+            }
+        }
+        if let Err(e) =
+            operon_runtime::tool_policy::layers::RuleEngineLayer::new(policy.rules.clone())
+        {
+            errors.push(format!("tool_policy.rules: {:#}", e));
+        }
+        for (kind, pattern) in &policy.redaction.patterns {
+            if let Err(e) = operon_runtime::Redactor::secrets().with_pattern(kind, pattern) {
+                errors.push(format!("tool_policy.redaction.patterns.{}: {}", kind, e));
+            }
+        }
+
+        let memory = &self.memory;
+        if memory.enabled {
+            if memory.db_path.trim().is_empty() {
+                errors.push("memory.db_path must not be empty".to_string());
+            }
+            if !["openai", "voyage"].contains(&memory.embedding_provider.as_str()) {
+                errors.push(format!(
+                    "memory.embedding_provider must be one of openai, voyage (got '{}')",
+                    memory.embedding_provider
+                ));
+            }
+            if memory.embedding_model.trim().is_empty() {
+                errors.push("memory.embedding_model must not be empty".to_string());
+            }
+            if memory.embedding_dimensions == 0 {
+                errors.push("memory.embedding_dimensions must be > 0".to_string());
+            }
+        }
+
+        errors
     }
 
     /// Apply environment variable overrides
@@ -302,16 +1503,55 @@ impl Config {
                 self.llm.gemini_api_key = key;
             }
         }
+        if let Ok(token) = std::env::var("GITHUB_TOKEN") {
+            if self.tools.github.token.is_empty() {
+                self.tools.github.token = token;
+            }
+        }
+        if let Ok(password) = std::env::var("SILENTCLAW_SMTP_PASSWORD") {
+            if self.tools.email.password.is_empty() {
+                self.tools.email.password = password;
+            }
+        }
+        if let Ok(url) = std::env::var("SILENTCLAW_WEBHOOK_URL") {
+            if self.notifications.webhook_url.is_empty() {
+                self.notifications.webhook_url = url;
+            }
+        }
     }
 }
 
+/// Accepted values for `llm.provider` and `llm.routes.*.provider`
+const LLM_PROVIDERS: &[&str] = &["anthropic", "openai", "gemini"];
+
+/// Smallest extended thinking budget the Anthropic API accepts
+const MIN_THINKING_BUDGET: u32 = 1024;
+
+/// Accepted values for `llm.gemini_safety.*`
+const GEMINI_SAFETY_THRESHOLDS: &[&str] = &[
+    "BLOCK_NONE",
+    "BLOCK_ONLY_HIGH",
+    "BLOCK_MEDIUM_AND_ABOVE",
+    "BLOCK_LOW_AND_ABOVE",
+    "OFF",
+];
+
+/// Accepted values for `runtime.container.engine`
+const CONTAINER_ENGINES: &[&str] = &["docker", "podman"];
+
+/// Accepted values for `sessions.store`
+const SESSION_STORES: &[&str] = &["json", "sqlite"];
+
+/// Accepted values for `tool_policy.default_permission`
+const PERMISSION_LEVELS: &[&str] = &["read", "write", "execute", "network", "admin"];
+
+/// Maximum nesting depth for `include` directives
+const MAX_INCLUDE_DEPTH: usize = 8;
+
 /// Load config from file or use defaults
 pub fn load_config(path: Option<&Path>) -> Result<Config> {
     let mut config = if let Some(path) = path {
-        let content =
-            fs::read_to_string(path).context(format!("Failed to read config file: {:?}", path))?;
-
-        toml::from_str(&content).context("Failed to parse TOML config")?
+        parse_config(load_toml(path)?)?
     } else {
         Config::default_config()
     };
@@ -324,3 +1564,786 @@ pub fn load_config(path: Option<&Path>) -> Result<Config> {
 
     Ok(config)
 }
+
+/// Read a config file with all `include` directives resolved and the files of
+/// `agents_dir` added, without deserializing it
+pub fn load_toml(path: &Path) -> Result<toml::Value> {
+    let value = load_toml_with_includes(path, 0)?;
+    let Some(dir) = value.get("agents_dir").and_then(toml::Value::as_str) else {
+        return Ok(value);
+    };
+    let base_dir = path.parent().unwrap_or_else(|| Path::new("."));
+    let dir = base_dir.join(shellexpand::tilde(dir).as_ref());
+    let mut merged = toml::Value::Table(toml::map::Map::from_iter([(
+        "agents".to_string(),
+        load_agents_dir(&dir)?,
+    )]));
+    merge_toml(&mut merged, value);
+    Ok(merged)
+}
+
+/// Agent definitions from the `<name>.toml` files in `dir`, keyed by file name
+fn load_agents_dir(dir: &Path) -> Result<toml::Value> {
+    let entries = fs::read_dir(dir).context(format!("Failed to read agents_dir {:?}", dir))?;
+    let mut files: Vec<PathBuf> = entries
+        .filter_map(|entry| entry.ok().map(|e| e.path()))
+        .filter(|p| p.is_file() && p.extension().is_some_and(|ext| ext == "toml"))
+        .collect();
+    files.sort();
+
+    let mut agents = toml::map::Map::new();
+    for file in files {
+        let Some(name) = file.file_stem().and_then(|stem| stem.to_str()) else {
+            continue;
+        };
+        let content =
+            fs::read_to_string(&file).context(format!("Failed to read agent file {:?}", file))?;
+        let agent: toml::Value =
+            toml::from_str(&content).context(format!("Failed to parse agent file {:?}", file))?;
+        agents.insert(name.to_string(), agent);
+    }
+    Ok(toml::Value::Table(agents))
+}
+
+/// Deserialize a merged TOML document into `Config`, reporting the failing key path
+pub fn parse_config(value: toml::Value) -> Result<Config> {
+    serde_path_to_error::deserialize(value).map_err(|e| {
+        let path = e.path().to_string();
+        if path == "." {
+            anyhow::anyhow!("Failed to parse TOML config: {}", e.inner())
+        } else {
+            anyhow::anyhow!("Failed to parse TOML config at '{}': {}", path, e.inner())
+        }
+    })
+}
+
+/// Key paths present in the document that `Config` does not recognize (likely typos)
+pub fn unknown_keys(value: toml::Value) -> Vec<String> {
+    let mut unknown = Vec::new();
+    let _: Result<Config, _> = serde_ignored::deserialize(value, |path| {
+        unknown.push(path.to_string());
+    });
+    unknown
+}
+
+/// JSON Schema describing the full config file, for editor completion
+pub fn config_schema() -> serde_json::Value {
+    serde_json::to_value(schemars::schema_for!(Config)).unwrap_or_default()
+}
+
+/// Read a TOML file and merge in every file listed in its top-level `include` array.
+///
+/// Include patterns are resolved relative to the including file and may contain globs
+/// (`agents/*.toml`). Included files are merged in order, then the including file is
+/// merged on top, so its own keys always take precedence.
+fn load_toml_with_includes(path: &Path, depth: usize) -> Result<toml::Value> {
+    if depth > MAX_INCLUDE_DEPTH {
+        anyhow::bail!(
+            "Config include depth exceeds {} at {:?} (include cycle?)",
+            MAX_INCLUDE_DEPTH,
+            path
+        );
+    }
+
+    let content =
+        fs::read_to_string(path).context(format!("Failed to read config file: {:?}", path))?;
+    let mut value: toml::Value =
+        toml::from_str(&content).context(format!("Failed to parse TOML config: {:?}", path))?;
+
+    let includes = match value.as_table_mut().and_then(|t| t.remove("include")) {
+        Some(toml::Value::Array(items)) => items,
+        Some(_) => anyhow::bail!("'include' must be an array of paths in {:?}", path),
+        None => return Ok(value),
+    };
+
+    let base_dir = path.parent().unwrap_or_else(|| Path::new("."));
+    let mut merged = toml::Value::Table(toml::map::Map::new());
+
+    for item in includes {
+        let pattern = item
+            .as_str()
+            .context(format!("'include' entries must be strings in {:?}", path))?;
+        for file in resolve_include(base_dir, pattern)? {
+            let included = load_toml_with_includes(&file, depth + 1)?;
+            merge_toml(&mut merged, included);
+        }
+    }
+
+    merge_toml(&mut merged, value);
+    Ok(merged)
+}
+
+/// Expand one include pattern into a sorted list of files.
+/// Plain paths must exist; glob patterns may match nothing.
+fn resolve_include(base_dir: &Path, pattern: &str) -> Result<Vec<PathBuf>> {
+    let expanded = shellexpand::tilde(pattern).to_string();
+    let full = if Path::new(&expanded).is_absolute() {
+        PathBuf::from(&expanded)
+    } else {
+        base_dir.join(&expanded)
+    };
+
+    let is_glob = expanded.contains(['*', '?', '[']);
+    if !is_glob {
+        if !full.is_file() {
+            anyhow::bail!("Included config file not found: {:?}", full);
+        }
+        return Ok(vec![full]);
+    }
+
+    let full_str = full
+        .to_str()
+        .context(format!("Include path is not valid UTF-8: {:?}", full))?;
+    let mut files: Vec<PathBuf> = glob::glob(full_str)
+        .context(format!("Invalid include pattern: {}", pattern))?
+        .filter_map(|entry| entry.ok())
+        .filter(|p| p.is_file())
+        .collect();
+    files.sort();
+    Ok(files)
+}
+
+/// Deep-merge `overlay` into `base`: tables merge recursively, everything else is replaced
+fn merge_toml(base: &mut toml::Value, overlay: toml::Value) {
+    match (base, overlay) {
+        (toml::Value::Table(base_table), toml::Value::Table(overlay_table)) => {
+            for (key, value) in overlay_table {
+                match base_table.get_mut(&key) {
+                    Some(existing) => merge_toml(existing, value),
+                    None => {
+                        base_table.insert(key, value);
+                    }
+                }
+            }
+        }
+        (base, overlay) => *base = overlay,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn write(dir: &Path, name: &str, content: &str) -> PathBuf {
+        let path = dir.join(name);
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent).unwrap();
+        }
+        fs::write(&path, content).unwrap();
+        path
+    }
+
+    #[test]
+    fn test_include_merges_files() {
+        let dir = tempfile::tempdir().unwrap();
+        write(
+            dir.path(),
+            "tools.toml",
+            "[tools.shell]\nenabled = false\n\n[tools.timeouts]\nshell = 30\n",
+        );
+        let main = write(
+            dir.path(),
+            "silentclaw.toml",
+            "include = [\"tools.toml\"]\n\n[runtime]\ndry_run = true\n",
+        );
+
+        let config = load_config(Some(&main)).unwrap();
+        assert!(!config.tools.shell.enabled);
+        assert_eq!(config.tools.shell.grace_period_secs, 5);
+        assert!(!config.tools.filesystem.snapshot);
+        assert!(!config.tools.shell.sandbox.enabled);
+        assert!(!config.runtime.landlock.enabled);
+        assert!(!config.runtime.container.enabled);
+        assert_eq!(config.runtime.container.tools, vec!["shell".to_string()]);
+        assert_eq!(config.tools.shell.sandbox.level, PermissionLevel::Execute);
+        assert_eq!(config.tools.timeouts.get("shell"), Some(&30));
+        assert!(config.runtime.dry_run);
+    }
+
+    #[test]
+    fn test_include_glob_and_main_file_precedence() {
+        let dir = tempfile::tempdir().unwrap();
+        write(
+            dir.path(),
+            "conf.d/a.toml",
+            "[runtime]\ntimeout_secs = 10\n",
+        );
+        write(
+            dir.path(),
+            "conf.d/b.toml",
+            "[runtime]\ntimeout_secs = 20\nmax_parallel = 8\n",
+        );
+        let main = write(
+            dir.path(),
+            "silentclaw.toml",
+            "include = [\"conf.d/*.toml\"]\n\n[runtime]\nmax_parallel = 2\n\n[tools]\n",
+        );
+
+        let config = load_config(Some(&main)).unwrap();
+        // b.toml overrides a.toml; the main file overrides both
+        assert_eq!(config.runtime.timeout_secs, 20);
+        assert_eq!(config.runtime.max_parallel, 2);
+    }
+
+    #[test]
+    fn test_missing_include_is_error() {
+        let dir = tempfile::tempdir().unwrap();
+        let main = write(
+            dir.path(),
+            "silentclaw.toml",
+            "include = [\"nope.toml\"]\n\n[runtime]\n\n[tools]\n",
+        );
+        assert!(load_config(Some(&main)).is_err());
+    }
+
+    #[test]
+    fn test_parse_error_reports_key_path() {
+        let value: toml::Value =
+            toml::from_str("[runtime]\ntimeout_secs = \"soon\"\n\n[tools]\n").unwrap();
+        let err = parse_config(value).unwrap_err().to_string();
+        assert!(err.contains("runtime.timeout_secs"), "{}", err);
+    }
+
+    #[test]
+    fn test_rate_limit_scopes_must_be_positive() {
+        let value: toml::Value = toml::from_str(
+            "[runtime]\n[tools]\n[tool_policy]\nrate_limit_enabled = true\n\
+             session_calls_per_minute = 10\nprincipal_calls_per_minute = 0\n",
+        )
+        .unwrap();
+        let config = parse_config(value).unwrap();
+        assert_eq!(config.tool_policy.session_calls_per_minute, Some(10));
+        assert_eq!(
+            config.validation_errors(),
+            ["tool_policy.principal_calls_per_minute must be > 0"]
+        );
+    }
+
+    #[test]
+    fn test_validation_errors_cover_policy_and_memory() {
+        let mut config = Config::default_config();
+        config.tool_policy.default_permission = "root".into();
+        config.memory.enabled = true;
+        config.memory.embedding_provider = "nope".into();
+
+        let errors = config.validation_errors();
+        assert_eq!(errors.len(), 2);
+        assert!(errors[0].starts_with("tool_policy.default_permission"));
+        assert!(errors[1].starts_with("memory.embedding_provider"));
+        assert!(config.validate().is_err());
+    }
+
+    #[test]
+    fn test_shell_sandbox_parses_and_validates() {
+        let value: toml::Value = toml::from_str(
+            "[runtime]\n[tools.shell.sandbox]\nenabled = true\nprogram = \"firejail\"\nlevel = \"read\"\n",
+        )
+        .unwrap();
+        let mut config = parse_config(value).unwrap();
+        let sandbox = &config.tools.shell.sandbox;
+        assert!(sandbox.enabled);
+        assert_eq!(sandbox.level, PermissionLevel::Read);
+        assert!(config.validation_errors().is_empty());
+
+        config.tools.shell.sandbox.program = "docker".into();
+        let errors = config.validation_errors();
+        assert_eq!(errors.len(), 1);
+        assert!(errors[0].starts_with("tools.shell.sandbox.program"));
+    }
+
+    #[test]
+    fn test_enabled_ssh_needs_host() {
+        let value: toml::Value = toml::from_str(
+            "[runtime]\n[tools.ssh]\nenabled = true\nuser = \"deploy\"\nidentity_file = \"~/.ssh/id_ed25519\"\n",
+        )
+        .unwrap();
+        let mut config = parse_config(value).unwrap();
+        assert_eq!(config.tools.ssh.port, 22);
+        assert_eq!(config.tools.ssh.remote_root, ".");
+        assert_eq!(
+            config.validation_errors(),
+            vec!["tools.ssh.host is required when enabled".to_string()]
+        );
+
+        config.tools.ssh.host = "build.example.com".into();
+        assert!(config.validation_errors().is_empty());
+    }
+
+    #[test]
+    fn test_notifications_need_webhook_and_known_kind() {
+        let value: toml::Value = toml::from_str(
+            "[runtime]\n[tools]\n\n[notifications]\nenabled = true\nkind = \"discord\"\n",
+        )
+        .unwrap();
+        let mut config = parse_config(value).unwrap();
+        assert!(config.notifications.tool);
+        assert!(config.notifications.on_plan_end);
+        assert_eq!(
+            config.validation_errors(),
+            vec!["notifications.webhook_url must be an http(s) URL when enabled".to_string()]
+        );
+
+        config.notifications.webhook_url = "https://discord.com/api/webhooks/1/abc".into();
+        assert!(config.validation_errors().is_empty());
+        config.notifications.kind = "teams".into();
+        assert!(config.validation_errors()[0].starts_with("notifications.kind"));
+    }
+
+    #[test]
+    fn test_kubernetes_defaults_and_validation() {
+        let value: toml::Value = toml::from_str(
+            "[runtime]\n[tools.kubernetes]\nenabled = true\ncontext = \"staging\"\nmemory = \"2Gi\"\n",
+        )
+        .unwrap();
+        let mut config = parse_config(value).unwrap();
+        let kubernetes = &config.tools.kubernetes;
+        assert_eq!(kubernetes.namespace, "default");
+        assert_eq!(kubernetes.cpu, "500m");
+        assert_eq!(kubernetes.memory, "2Gi");
+        assert!(config.validation_errors().is_empty());
+
+        config.tools.kubernetes.timeout_secs = 0;
+        assert_eq!(
+            config.validation_errors(),
+            vec!["tools.kubernetes.timeout_secs must be > 0".to_string()]
+        );
+    }
+
+    #[test]
+    fn test_sessions_store_validation() {
+        let value: toml::Value = toml::from_str("[runtime]\n[tools]\n").unwrap();
+        let mut config = parse_config(value).unwrap();
+        assert_eq!(config.sessions.store, "json");
+        assert!(!config.sessions.save_gateway_sessions);
+
+        config.sessions.store = "postgres".into();
+        assert_eq!(
+            config.validation_errors(),
+            vec!["sessions.store must be one of json, sqlite (got 'postgres')".to_string()]
+        );
+    }
+
+    #[test]
+    fn test_screenshot_command_placeholders() {
+        let value: toml::Value = toml::from_str(
+            "[runtime]\n[tools.screenshot]\nenabled = true\nscreen_command = [\"grim\", \"{output}\"]\nwindow_command = [\"grim\", \"{output}\"]\n",
+        )
+        .unwrap();
+        let config = parse_config(value).unwrap();
+        assert_eq!(
+            config.tools.screenshot.max_bytes,
+            operon_adapters::screenshot_tool::DEFAULT_MAX_IMAGE_BYTES
+        );
+        assert_eq!(
+            config.validation_errors(),
+            vec!["tools.screenshot.window_command must contain {window}".to_string()]
+        );
+    }
+
+    #[test]
+    fn test_schedules_validation() {
+        let value: toml::Value = toml::from_str(
+            r#"
+[runtime]
+[tools]
+[[schedules]]
+name = "nightly"
+cron = "30 2 * * *"
+plan = "plans/nightly.json"
+mode = "dry-run"
+[[schedules]]
+name = "nightly"
+cron = "every night"
+plan = "plans/other.json"
+agent = "ghost"
+"#,
+        )
+        .unwrap();
+        let config = parse_config(value).unwrap();
+        assert_eq!(config.schedules[0].mode, ExecutionMode::DryRun);
+        assert_eq!(config.schedules[1].mode, ExecutionMode::Auto);
+        let errors = config.validation_errors();
+        assert_eq!(errors.len(), 3);
+        assert_eq!(errors[0], "schedules: duplicate name 'nightly'");
+        assert!(
+            errors[1].starts_with("schedules.nightly.cron: invalid cron expression 'every night'")
+        );
+        assert_eq!(
+            errors[2],
+            "schedules.nightly.agent: no [agents.ghost] profile"
+        );
+    }
+
+    #[test]
+    fn test_email_needs_relay_sender_and_allowlist() {
+        let value: toml::Value = toml::from_str(
+            "[runtime]\n[tools.email]\nenabled = true\nhost = \"smtp.example.com\"\nfrom = \"bot\"\nsecurity = \"ssl\"\n",
+        )
+        .unwrap();
+        let mut config = parse_config(value).unwrap();
+        assert_eq!(
+            config.validation_errors(),
+            vec![
+                "tools.email.from: Invalid sender address 'bot'".to_string(),
+                "tools.email.security: Unknown SMTP security 'ssl' (expected starttls, tls or none)"
+                    .to_string(),
+                "tools.email.allowed_recipients must not be empty".to_string(),
+            ]
+        );
+
+        config.tools.email.from = "SilentClaw <bot@example.com>".to_string();
+        config.tools.email.security = "tls".to_string();
+        config.tools.email.allowed_recipients = vec!["@example.com".to_string()];
+        assert!(config.validation_errors().is_empty());
+    }
+
+    #[test]
+    fn test_redaction_patterns_must_compile() {
+        let value: toml::Value = toml::from_str(
+            "[runtime]\n[tools]\n[redaction]\nenabled = true\npatterns = { employee_id = 'EMP-\\d{6}', broken = '(' }\n",
+        )
+        .unwrap();
+        let config = parse_config(value).unwrap();
+        let errors = config.validation_errors();
+        assert_eq!(errors.len(), 1);
+        assert!(errors[0].starts_with("redaction.patterns.broken: Invalid redaction pattern"));
+    }
+
+    #[test]
+    fn test_policy_rules_must_compile() {
+        let value: toml::Value = toml::from_str(
+            "[runtime]\n[tools]\n[tool_policy]\nenabled = true\n\
+             [[tool_policy.rules]]\nname = \"no-rm\"\naction = \"deny\"\ntools = [\"shell\"]\n\
+             input = { command = '^rm\\s' }\npermissions = [\"execute\"]\n\
+             [[tool_policy.rules]]\nname = \"broken\"\naction = \"approve\"\ninput = { path = '(' }\n",
+        )
+        .unwrap();
+        let config = parse_config(value).unwrap();
+        assert_eq!(
+            config.tool_policy.rules[0].action,
+            operon_runtime::tool_policy::config::RuleAction::Deny
+        );
+        let errors = config.validation_errors();
+        assert_eq!(errors.len(), 1);
+        assert!(errors[0].starts_with("tool_policy.rules: rule 'broken': invalid pattern for path"));
+    }
+
+    #[test]
+    fn test_policy_redaction_config() {
+        let config = parse_config(toml::from_str("[runtime]\n[tools]\n").unwrap()).unwrap();
+        assert!(config.tool_policy.redaction.enabled);
+
+        let value: toml::Value = toml::from_str(
+            "[runtime]\n[tools]\n[tool_policy.redaction]\n\
+             patterns = { internal = 'itk_[0-9a-f]{32}', broken = '(' }\n",
+        )
+        .unwrap();
+        let config = parse_config(value).unwrap();
+        let errors = config.validation_errors();
+        assert_eq!(errors.len(), 1);
+        assert!(errors[0]
+            .starts_with("tool_policy.redaction.patterns.broken: Invalid redaction pattern"));
+    }
+
+    #[test]
+    fn test_gateway_keys_and_quotas() {
+        let value: toml::Value = toml::from_str(
+            "[runtime]\n[tools]\n[gateway.api_keys]\nci = \"secret\"\nbroken = \" \"\n\
+             [gateway.quotas]\nenabled = true\ndaily_requests = 10\n\
+             identities.ci = { monthly_tokens = 500 }\n",
+        )
+        .unwrap();
+        let config = parse_config(value).unwrap();
+        let quotas = &config.gateway.quotas;
+        assert!(quotas.enabled);
+        assert_eq!(quotas.default.daily_requests, Some(10));
+        assert_eq!(quotas.default.monthly_tokens, None);
+        let ci = operon_gateway::QuotaLimits::from(&quotas.identities["ci"]);
+        assert_eq!((ci.daily_requests, ci.monthly_tokens), (None, Some(500)));
+        assert_eq!(
+            config.validation_errors(),
+            vec!["gateway.api_keys.broken must not be empty".to_string()]
+        );
+    }
+
+    #[test]
+    fn test_gateway_cors_exemptions_and_proxy_headers() {
+        let value: toml::Value = toml::from_str("[runtime]\n[tools]\n").unwrap();
+        let config = parse_config(value).unwrap();
+        assert_eq!(config.gateway.auth_exempt_paths, vec!["/health"]);
+
+        let value: toml::Value = toml::from_str(
+            "[runtime]\n[tools]\n[gateway]\n\
+             allowed_origins = [\"https://app.example.com\", \"app.example.com\"]\n\
+             auth_exempt_paths = [\"/health\", \"metrics\"]\n\
+             trusted_proxy_headers = [\"X-Forwarded-For\", \"Bad Header\"]\n",
+        )
+        .unwrap();
+        let config = parse_config(value).unwrap();
+        assert_eq!(
+            config.validation_errors(),
+            vec![
+                "gateway.allowed_origins: 'app.example.com' must be scheme://host[:port]"
+                    .to_string(),
+                "gateway.auth_exempt_paths: 'metrics' must start with '/'".to_string(),
+                "gateway.trusted_proxy_headers: 'Bad Header' is not a valid header name"
+                    .to_string(),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_github_needs_repo_token_and_known_actions() {
+        let value: toml::Value = toml::from_str(
+            "[runtime]\n[tools.github]\nenabled = true\nrepo = \"acme\"\nactions = [\"open_pr\", \"merge_pr\"]\n",
+        )
+        .unwrap();
+        let mut config = parse_config(value).unwrap();
+        assert_eq!(
+            config.validation_errors(),
+            vec![
+                "tools.github.repo must be \"owner/name\"".to_string(),
+                "tools.github.token is required when enabled (or set GITHUB_TOKEN)".to_string(),
+                "tools.github.actions: unknown action 'merge_pr'".to_string(),
+            ]
+        );
+
+        config.tools.github.repo = "acme/app".to_string();
+        config.tools.github.token = "ghp_test".to_string();
+        config.tools.github.actions = vec!["open_pr".to_string()];
+        assert!(config.validation_errors().is_empty());
+    }
+
+    #[test]
+    fn test_enabled_container_needs_image_and_known_engine() {
+        let mut config = Config::default_config();
+        config.runtime.container.enabled = true;
+        config.runtime.container.engine = "lxc".into();
+
+        let errors = config.validation_errors();
+        assert_eq!(errors.len(), 2);
+        assert!(errors[0].starts_with("runtime.container.engine"));
+        assert!(errors[1].starts_with("runtime.container.image"));
+
+        config.runtime.container.engine = "docker".into();
+        config.runtime.container.image = "warden:latest".into();
+        config.tools.shell.sandbox.enabled = true;
+        assert_eq!(
+            config.validation_errors(),
+            vec![
+                "runtime.container.tools cannot include \"shell\" while tools.shell.sandbox is enabled"
+                    .to_string()
+            ]
+        );
+    }
+
+    #[test]
+    fn test_agent_profiles_parse() {
+        let value: toml::Value = toml::from_str(
+            "[runtime]\n[tools]\n\n[agents.reviewer]\ntools = [\"read_file\"]\nmax_permission = \"read\"\n",
+        )
+        .unwrap();
+        let config = parse_config(value).unwrap();
+        let reviewer = &config.agents["reviewer"];
+        assert_eq!(reviewer.tools, vec!["read_file".to_string()]);
+        assert_eq!(reviewer.max_permission, Some(PermissionLevel::Read));
+    }
+
+    #[test]
+    fn test_agents_dir_files_and_precedence() {
+        let dir = tempfile::tempdir().unwrap();
+        write(
+            dir.path(),
+            "agents/researcher.toml",
+            "description = \"Finds sources\"\ntemperature = 0.2\nmax_iterations = 4\n",
+        );
+        write(dir.path(), "agents/coder.toml", "model = \"gpt-4o\"\n");
+        write(dir.path(), "agents/notes.md", "not an agent");
+        let main = write(
+            dir.path(),
+            "silentclaw.toml",
+            "agents_dir = \"agents\"\n\n[runtime]\n[tools]\n\n[agents.coder]\nmodel = \"claude-sonnet-4\"\n",
+        );
+
+        let config = load_config(Some(&main)).unwrap();
+        assert_eq!(config.agents.len(), 2);
+        let researcher = &config.agents["researcher"];
+        assert_eq!(researcher.description.as_deref(), Some("Finds sources"));
+        assert_eq!(researcher.temperature, Some(0.2));
+        assert_eq!(researcher.max_iterations, Some(4));
+        // The config file's own definition wins
+        assert_eq!(
+            config.agents["coder"].model.as_deref(),
+            Some("claude-sonnet-4")
+        );
+
+        write(dir.path(), "agents/bad.toml", "temperature = 3.0\n");
+        let err = load_config(Some(&main)).unwrap_err().to_string();
+        assert!(err.contains("agents.bad.temperature must be between 0-2"));
+    }
+
+    #[test]
+    fn test_llm_routes_parse_and_validate() {
+        let value: toml::Value = toml::from_str(
+            "[runtime]\n[tools]\n\n[llm.routes.summarize]\nprovider = \"openai\"\nmodel = \"gpt-4o-mini\"\n\n[llm.routes.title]\nprovider = \"mistral\"\n",
+        )
+        .unwrap();
+        let config = parse_config(value).unwrap();
+        assert_eq!(config.llm.routes[&TaskKind::Summarize].model, "gpt-4o-mini");
+
+        let errors = config.validation_errors();
+        assert_eq!(errors.len(), 1);
+        assert!(errors[0].starts_with("llm.routes.title.provider"));
+
+        // Unknown task kinds are rejected at parse time
+        let value: toml::Value =
+            toml::from_str("[runtime]\n[tools]\n\n[llm.routes.poetry]\nprovider = \"openai\"\n")
+                .unwrap();
+        assert!(parse_config(value).is_err());
+    }
+
+    #[test]
+    fn test_llm_reasoning_options() {
+        let value: toml::Value = toml::from_str(
+            "[runtime]\n[tools]\n\n[llm]\nthinking_budget = 512\nreasoning_effort = \"high\"\n",
+        )
+        .unwrap();
+        let config = parse_config(value).unwrap();
+        assert_eq!(config.llm.reasoning_effort, Some(ReasoningEffort::High));
+        assert_eq!(
+            config.validation_errors(),
+            vec!["llm.thinking_budget must be >= 1024".to_string()]
+        );
+    }
+
+    #[test]
+    fn test_llm_context_options() {
+        let value: toml::Value = toml::from_str(
+            "[runtime]\n[tools]\n\n[llm.context]\nstrategy = \"summarize\"\nkeep_recent = 0\n",
+        )
+        .unwrap();
+        let config = parse_config(value).unwrap();
+        assert_eq!(
+            config.llm.context.strategy,
+            operon_runtime::ContextStrategy::Summarize
+        );
+        assert_eq!(config.llm.context.max_tokens, 100_000);
+        assert_eq!(
+            config.validation_errors(),
+            vec!["llm.context.keep_recent must be > 0".to_string()]
+        );
+
+        let value: toml::Value =
+            toml::from_str("[runtime]\n[tools]\n\n[llm]\ncontext_window = 0\n").unwrap();
+        assert_eq!(
+            parse_config(value).unwrap().validation_errors(),
+            vec!["llm.context_window must be > 0".to_string()]
+        );
+
+        let value: toml::Value =
+            toml::from_str("[runtime]\n[tools]\n\n[llm]\nmax_cost_usd = 0.0\n").unwrap();
+        assert_eq!(
+            parse_config(value).unwrap().validation_errors(),
+            vec!["llm.max_cost_usd must be > 0 (got 0)".to_string()]
+        );
+    }
+
+    #[test]
+    fn test_gemini_safety_validation() {
+        let value: toml::Value = toml::from_str(
+            "[runtime]\n[tools]\n\n[llm.gemini_safety]\nHARM_CATEGORY_HARASSMENT = \"BLOCK_ONLY_HIGH\"\nHARM_CATEGORY_HATE_SPEECH = \"SOMETIMES\"\n",
+        )
+        .unwrap();
+        let config = parse_config(value).unwrap();
+        let errors = config.validation_errors();
+        assert_eq!(errors.len(), 1);
+        assert!(errors[0].starts_with("llm.gemini_safety.HARM_CATEGORY_HATE_SPEECH"));
+    }
+
+    #[test]
+    fn test_runtime_resource_limits() {
+        let value: toml::Value = toml::from_str(
+            "[runtime]\n[runtime.resource_limits]\nshell = 2\nnetwork = 0\n\n[tools]\n",
+        )
+        .unwrap();
+        let config = parse_config(value).unwrap();
+        assert_eq!(config.runtime.resource_limits["shell"], 2);
+        assert_eq!(config.runtime.nested_storage, NestedStorage::Shared);
+        assert_eq!(
+            config.validation_errors(),
+            vec!["runtime.resource_limits.network must be > 0".to_string()]
+        );
+    }
+
+    #[test]
+    fn test_tool_composites_parse_and_validate() {
+        let value: toml::Value = toml::from_str(
+            r#"
+[runtime]
+
+[[tools.composites]]
+name = "lint_file"
+steps = [
+    { tool = "read_file", input = { path = "$input.path" } },
+    { tool = "shell", input = { command = "$prev.content" } },
+]
+
+[[tools.composites]]
+name = "broken"
+steps = []
+"#,
+        )
+        .unwrap();
+        let config = parse_config(value).unwrap();
+        assert_eq!(config.tools.composites[0].steps[1].tool, "shell");
+        assert_eq!(
+            config.validation_errors(),
+            vec!["tools.composites: Composite tool 'broken' has no steps".to_string()]
+        );
+    }
+
+    #[test]
+    fn test_tool_aliases_and_renames() {
+        let value: toml::Value = toml::from_str(
+            "[runtime]\n[tools.aliases]\nbash = \"shell\"\nshell = \"shell\"\n\n[tools.renames]\nsearch = \"web_search\"\n",
+        )
+        .unwrap();
+        let config = parse_config(value).unwrap();
+        assert_eq!(config.tools.aliases["bash"], "shell");
+        assert!(config.tools.cache_ttl.is_empty());
+        assert_eq!(config.tools.renames["search"], "web_search");
+        assert_eq!(
+            config.validation_errors(),
+            vec!["tools.aliases.shell cannot point to itself".to_string()]
+        );
+    }
+
+    #[test]
+    fn test_unknown_keys_detected() {
+        let value: toml::Value = toml::from_str("[runtime]\ndry_rn = true\n\n[tools]\n").unwrap();
+        assert_eq!(unknown_keys(value), vec!["runtime.dry_rn".to_string()]);
+    }
+
+    #[test]
+    fn test_schema_includes_all_sections() {
+        let schema = config_schema();
+        let props = &schema["properties"];
+        for section in ["runtime", "tools", "llm", "memory", "tool_policy"] {
+            assert!(props.get(section).is_some(), "missing {}", section);
+        }
+    }
+
+    #[test]
+    fn test_include_cycle_is_error() {
+        let dir = tempfile::tempdir().unwrap();
+        write(dir.path(), "a.toml", "include = [\"b.toml\"]\n");
+        write(dir.path(), "b.toml", "include = [\"a.toml\"]\n");
+        let main = write(
+            dir.path(),
+            "silentclaw.toml",
+            "include = [\"a.toml\"]\n\n[runtime]\n\n[tools]\n",
+        );
+        let err = load_config(Some(&main)).unwrap_err();
+        assert!(format!("{:#}", err).contains("include depth"));
+    }
+}
@@ -0,0 +1,248 @@
+//! Landlock confinement of the warden process (and every tool subprocess it
+//! starts) to the workspace, state and system paths it needs: defense in
+//! depth behind `WorkspaceGuard`'s path checks.
+
+use crate::cli::{Cli, Commands};
+use crate::config::Config;
+use anyhow::Result;
+use std::path::{Path, PathBuf};
+
+/// System locations commands need to read and execute from
+const SYSTEM_READ_PATHS: &[&str] = &[
+    "/usr", "/bin", "/sbin", "/lib", "/lib64", "/opt", "/nix", "/etc", "/proc", "/sys", "/run",
+    "/dev",
+];
+
+/// Device files tools write to
+const DEVICE_WRITE_PATHS: &[&str] = &["/dev/null", "/dev/tty"];
+
+/// Paths the process keeps access to
+#[derive(Debug, Default)]
+pub struct AllowedPaths {
+    /// Read and execute
+    pub read: Vec<PathBuf>,
+    /// Full access
+    pub write: Vec<PathBuf>,
+}
+
+/// Confine the process if `[runtime.landlock]` is enabled and `cli` runs tools.
+/// Landlock only covers the calling thread and threads started after it, so
+/// this must run before the async runtime spawns its workers.
+pub fn apply(config: &Config, cli: &Cli) -> Result<()> {
+    if !config.runtime.landlock.enabled || !runs_tools(&cli.command) {
+        return Ok(());
+    }
+    let paths = allowed_paths(config, cli);
+    // Directories warden writes to must exist for a rule to cover them
+    let state_dir = silentclaw_dir();
+    std::fs::create_dir_all(&state_dir)?;
+    if let Some(record) = &cli.record {
+        std::fs::create_dir_all(record)?;
+    }
+    restrict(&paths, config.runtime.landlock.required)
+}
+
+fn runs_tools(command: &Commands) -> bool {
+    matches!(
+        command,
+        Commands::RunPlan { .. } | Commands::Chat { .. } | Commands::Serve { action: None, .. }
+    )
+}
+
+/// Everything the configured tools and the command's own state need
+pub fn allowed_paths(config: &Config, cli: &Cli) -> AllowedPaths {
+    let mut read: Vec<PathBuf> = SYSTEM_READ_PATHS.iter().map(PathBuf::from).collect();
+    let mut write: Vec<PathBuf> = DEVICE_WRITE_PATHS.iter().map(PathBuf::from).collect();
+
+    // Serve re-executes itself for --daemon
+    read.extend(std::env::current_exe().ok());
+    // Config hot-reload and includes
+    if let Some(config_path) = &cli.config {
+        read.extend(config_path.parent().map(Path::to_path_buf));
+        read.push(config_path.clone());
+    }
+    read.push(PathBuf::from(&config.tools.python.scripts_dir));
+    read.extend(cli.replay.clone());
+
+    write.push(PathBuf::from(&config.tools.filesystem.workspace));
+    // The runtime's state DB (plan checkpoints, audit trail) is
+    // ./silentclaw.db for run-plan, chat and serve alike
+    write.extend(std::env::current_dir().ok());
+    // Sessions, quotas, schedules and the serve PID/log files
+    write.push(silentclaw_dir());
+    write.push(std::env::temp_dir());
+    let memory_db = PathBuf::from(shellexpand::tilde(&config.memory.db_path).to_string());
+    write.extend(memory_db.parent().map(Path::to_path_buf));
+    write.extend(cli.record.clone());
+
+    match &cli.command {
+        Commands::RunPlan {
+            file, watch_paths, ..
+        } => {
+            read.extend(
+                file.iter()
+                    .filter_map(|f| f.parent())
+                    .map(Path::to_path_buf),
+            );
+            read.extend(watch_paths.iter().cloned());
+        }
+        Commands::Serve {
+            pid_file, log_file, ..
+        } => {
+            for path in pid_file.iter().chain(log_file) {
+                write.extend(path.parent().map(Path::to_path_buf));
+            }
+            read.extend(config.schedules.iter().map(|s| PathBuf::from(&s.plan)));
+        }
+        _ => {}
+    }
+
+    // The ssh client reads its keys and config and updates known_hosts
+    if config.tools.ssh.enabled {
+        write.push(home_dir().join(".ssh"));
+        if let Some(identity_file) = &config.tools.ssh.identity_file {
+            let identity_file = PathBuf::from(shellexpand::tilde(identity_file).to_string());
+            read.extend(identity_file.parent().map(Path::to_path_buf));
+        }
+    }
+
+    // kubectl reads its kubeconfig and keeps a discovery cache beside it
+    if config.tools.kubernetes.enabled {
+        write.push(home_dir().join(".kube"));
+        if let Some(template) = &config.tools.kubernetes.template {
+            read.push(PathBuf::from(shellexpand::tilde(template).to_string()));
+        }
+    }
+
+    // git reads the user's config when the github tool pushes a branch
+    if config.tools.github.enabled {
+        read.push(home_dir().join(".gitconfig"));
+        read.push(home_dir().join(".config").join("git"));
+    }
+
+    // Agent example conversations are read when sessions are set up
+    for examples in config.agents.values().filter_map(|a| a.examples.as_deref()) {
+        read.push(PathBuf::from(shellexpand::tilde(examples).to_string()));
+    }
+
+    let landlock = &config.runtime.landlock;
+    read.extend(landlock.read_paths.iter().map(PathBuf::from));
+    write.extend(landlock.write_paths.iter().map(PathBuf::from));
+    AllowedPaths { read, write }
+}
+
+#[cfg(target_os = "linux")]
+fn restrict(paths: &AllowedPaths, required: bool) -> Result<()> {
+    use landlock::{
+        path_beneath_rules, Access, AccessFs, Ruleset, RulesetAttr, RulesetCreatedAttr,
+        RulesetStatus, ABI,
+    };
+
+    let abi = ABI::V3;
+    // Paths that don't exist are skipped
+    let status = Ruleset::default()
+        .handle_access(AccessFs::from_all(abi))?
+        .create()?
+        .add_rules(path_beneath_rules(&paths.read, AccessFs::from_read(abi)))?
+        .add_rules(path_beneath_rules(&paths.write, AccessFs::from_all(abi)))?
+        .restrict_self()?;
+
+    match status.ruleset {
+        RulesetStatus::FullyEnforced => {
+            tracing::info!(write_paths = ?paths.write, "Landlock confinement enforced")
+        }
+        RulesetStatus::PartiallyEnforced => tracing::warn!(
+            "Landlock partially enforced; this kernel lacks some filesystem restrictions"
+        ),
+        RulesetStatus::NotEnforced if required => {
+            anyhow::bail!(
+                "runtime.landlock.required is set but this kernel does not support Landlock"
+            )
+        }
+        RulesetStatus::NotEnforced => {
+            tracing::warn!("Landlock not supported by this kernel; running unconfined")
+        }
+    }
+    Ok(())
+}
+
+#[cfg(not(target_os = "linux"))]
+fn restrict(_paths: &AllowedPaths, required: bool) -> Result<()> {
+    if required {
+        anyhow::bail!("runtime.landlock.required is set but Landlock is only available on Linux");
+    }
+    tracing::warn!("Landlock is only available on Linux; running unconfined");
+    Ok(())
+}
+
+fn home_dir() -> PathBuf {
+    std::env::var("HOME")
+        .map(PathBuf::from)
+        .unwrap_or_else(|_| PathBuf::from("."))
+}
+
+fn silentclaw_dir() -> PathBuf {
+    home_dir().join(".silentclaw")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use clap::Parser;
+
+    #[test]
+    fn test_allowed_paths_cover_workspace_and_command_inputs() {
+        let mut config = Config::default_config();
+        config.tools.filesystem.workspace = "/srv/project".into();
+        config.runtime.landlock.read_paths = vec!["/data/reference".into()];
+        let cli = Cli::parse_from([
+            "warden",
+            "--record",
+            "/tmp/fixtures/run",
+            "run-plan",
+            "--file",
+            "/plans/deploy.json",
+        ]);
+
+        let paths = allowed_paths(&config, &cli);
+        assert!(paths.write.contains(&PathBuf::from("/srv/project")));
+        assert!(paths.write.contains(&PathBuf::from("/tmp/fixtures/run")));
+        assert!(paths.read.contains(&PathBuf::from("/plans")));
+        assert!(paths.read.contains(&PathBuf::from("/data/reference")));
+        assert!(!paths.write.contains(&PathBuf::from("/usr")));
+        assert!(runs_tools(&cli.command));
+        assert!(!runs_tools(&Cli::parse_from(["warden", "doctor"]).command));
+    }
+
+    #[cfg(target_os = "linux")]
+    #[test]
+    fn test_restrict_blocks_paths_outside_rules() {
+        let allowed = tempfile::tempdir().unwrap();
+        let outside = tempfile::tempdir().unwrap();
+        std::fs::write(outside.path().join("secret.txt"), "x").unwrap();
+        let paths = AllowedPaths {
+            read: Vec::new(),
+            write: vec![allowed.path().to_path_buf()],
+        };
+        let secret = outside.path().join("secret.txt");
+        let inside = allowed.path().join("ok.txt");
+
+        // Landlock confines only this thread, leaving the test harness alone
+        let (enforced, read_outside, write_inside) = std::thread::spawn(move || {
+            restrict(&paths, false).unwrap();
+            let enforced = std::fs::read_dir("/").is_err();
+            (
+                enforced,
+                std::fs::read(&secret).is_ok(),
+                std::fs::write(&inside, "ok").is_ok(),
+            )
+        })
+        .join()
+        .unwrap();
+
+        assert!(write_inside);
+        if enforced {
+            assert!(!read_outside);
+        }
+    }
+}
@@ -0,0 +1,229 @@
+//! Background mode for `warden serve`: PID file handling and detaching
+//! from the controlling terminal.
+
+use anyhow::{bail, Context, Result};
+use std::path::{Path, PathBuf};
+
+/// Set on the re-executed background process so it serves instead of forking again
+const DAEMON_CHILD_ENV: &str = "WARDEN_DAEMON_CHILD";
+
+pub fn default_pid_file() -> PathBuf {
+    silentclaw_dir().join("warden-serve.pid")
+}
+
+pub fn default_log_file() -> PathBuf {
+    silentclaw_dir().join("warden-serve.log")
+}
+
+/// Per-schedule plan state and the run history of `[[schedules]]`
+pub fn default_schedule_dir() -> PathBuf {
+    silentclaw_dir().join("schedules")
+}
+
+/// Gateway request/token counters for `[gateway.quotas]`
+pub fn default_quota_db() -> PathBuf {
+    silentclaw_dir().join("quotas.db")
+}
+
+fn silentclaw_dir() -> PathBuf {
+    let home = std::env::var("HOME")
+        .or_else(|_| std::env::var("USERPROFILE"))
+        .unwrap_or_else(|_| ".".to_string());
+    PathBuf::from(home).join(".silentclaw")
+}
+
+/// True inside the background process spawned by `spawn_detached`
+pub fn is_daemon_child() -> bool {
+    std::env::var_os(DAEMON_CHILD_ENV).is_some()
+}
+
+/// PID recorded in `pid_file`, if it holds a valid one
+pub fn read_pid(pid_file: &Path) -> Option<u32> {
+    let pid: u32 = std::fs::read_to_string(pid_file)
+        .ok()?
+        .trim()
+        .parse()
+        .ok()?;
+    // Never hand 0 or values that wrap negative to kill(2)
+    (pid > 0 && pid <= i32::MAX as u32).then_some(pid)
+}
+
+/// PID of the running server; stale PID files are removed
+pub fn running_pid(pid_file: &Path) -> Option<u32> {
+    let pid = read_pid(pid_file)?;
+    if process_alive(pid) {
+        Some(pid)
+    } else {
+        let _ = std::fs::remove_file(pid_file);
+        None
+    }
+}
+
+fn write_pid(pid_file: &Path, pid: u32) -> Result<()> {
+    if let Some(parent) = pid_file.parent() {
+        std::fs::create_dir_all(parent)?;
+    }
+    std::fs::write(pid_file, format!("{}\n", pid))
+        .with_context(|| format!("Failed to write PID file {:?}", pid_file))
+}
+
+/// PID file owned by the serving process, removed on drop
+pub struct PidFile {
+    path: PathBuf,
+}
+
+impl PidFile {
+    pub fn create(path: &Path) -> Result<Self> {
+        let own_pid = std::process::id();
+        if let Some(pid) = running_pid(path) {
+            if pid != own_pid {
+                bail!("warden serve is already running (pid {})", pid);
+            }
+        }
+        write_pid(path, own_pid)?;
+        Ok(Self {
+            path: path.to_path_buf(),
+        })
+    }
+}
+
+impl Drop for PidFile {
+    fn drop(&mut self) {
+        if read_pid(&self.path) == Some(std::process::id()) {
+            let _ = std::fs::remove_file(&self.path);
+        }
+    }
+}
+
+/// Re-run the current command line in a new session with output sent to
+/// `log_file`. Records the child PID in `pid_file` and returns it.
+#[cfg(unix)]
+pub fn spawn_detached(pid_file: &Path, log_file: &Path) -> Result<u32> {
+    use std::os::unix::process::CommandExt;
+    use std::process::{Command, Stdio};
+
+    if let Some(pid) = running_pid(pid_file) {
+        bail!("warden serve is already running (pid {})", pid);
+    }
+
+    if let Some(parent) = log_file.parent() {
+        std::fs::create_dir_all(parent)?;
+    }
+    let log = std::fs::OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(log_file)
+        .with_context(|| format!("Failed to open log file {:?}", log_file))?;
+
+    let exe = std::env::current_exe().context("Failed to locate warden executable")?;
+    let mut cmd = Command::new(exe);
+    cmd.args(std::env::args_os().skip(1))
+        .env(DAEMON_CHILD_ENV, "1")
+        .stdin(Stdio::null())
+        .stdout(log.try_clone()?)
+        .stderr(log);
+    // SAFETY: setsid is async-signal-safe and touches no state shared with the parent
+    unsafe {
+        cmd.pre_exec(|| {
+            if libc::setsid() == -1 {
+                return Err(std::io::Error::last_os_error());
+            }
+            Ok(())
+        });
+    }
+
+    let child = cmd.spawn().context("Failed to spawn background server")?;
+    let pid = child.id();
+    write_pid(pid_file, pid)?;
+    Ok(pid)
+}
+
+#[cfg(not(unix))]
+pub fn spawn_detached(_pid_file: &Path, _log_file: &Path) -> Result<u32> {
+    bail!("--daemon is only supported on Unix; use a service manager instead")
+}
+
+#[cfg(unix)]
+fn process_alive(pid: u32) -> bool {
+    // Signal 0 only checks existence; EPERM still means the process exists
+    let rc = unsafe { libc::kill(pid as libc::pid_t, 0) };
+    rc == 0 || std::io::Error::last_os_error().raw_os_error() == Some(libc::EPERM)
+}
+
+#[cfg(not(unix))]
+fn process_alive(_pid: u32) -> bool {
+    false
+}
+
+/// Ask the server to shut down gracefully (SIGTERM)
+#[cfg(unix)]
+pub fn terminate(pid: u32) -> Result<()> {
+    if unsafe { libc::kill(pid as libc::pid_t, libc::SIGTERM) } != 0 {
+        return Err(std::io::Error::last_os_error())
+            .with_context(|| format!("Failed to signal pid {}", pid));
+    }
+    Ok(())
+}
+
+#[cfg(not(unix))]
+pub fn terminate(_pid: u32) -> Result<()> {
+    bail!("warden serve stop is only supported on Unix")
+}
+
+/// Poll until `pid` exits or `timeout` elapses; returns whether it exited
+pub async fn wait_for_exit(pid: u32, timeout: std::time::Duration) -> bool {
+    let deadline = tokio::time::Instant::now() + timeout;
+    while process_alive(pid) {
+        if tokio::time::Instant::now() >= deadline {
+            return false;
+        }
+        tokio::time::sleep(std::time::Duration::from_millis(200)).await;
+    }
+    true
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_read_pid_rejects_invalid() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("warden.pid");
+
+        assert_eq!(read_pid(&path), None);
+        for bad in ["", "abc", "0", "4294967295"] {
+            std::fs::write(&path, bad).unwrap();
+            assert_eq!(read_pid(&path), None, "accepted {:?}", bad);
+        }
+        std::fs::write(&path, "1234\n").unwrap();
+        assert_eq!(read_pid(&path), Some(1234));
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_pid_file_lifecycle() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("run").join("warden.pid");
+
+        let guard = PidFile::create(&path).unwrap();
+        assert_eq!(running_pid(&path), Some(std::process::id()));
+        // Re-creating from the same process is allowed
+        let again = PidFile::create(&path).unwrap();
+        drop(guard);
+        assert!(!path.exists());
+        drop(again);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_stale_pid_file_removed() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("warden.pid");
+        // Above Linux pid_max, so never a live process
+        std::fs::write(&path, "2147483000").unwrap();
+
+        assert_eq!(running_pid(&path), None);
+        assert!(!path.exists());
+    }
+}
@@ -1,40 +1,187 @@
 mod cli;
 mod commands;
 mod config;
+mod confinement;
+mod daemon;
+mod schedules;
 
-use anyhow::Result;
+use anyhow::{Context, Result};
 use clap::Parser;
-use cli::{Cli, Commands, PluginCommands};
+use cli::{
+    AgentCommands, AuditCommands, Cli, Commands, ConfigCommands, FixtureCommands, MemoryCommands,
+    PlanCommands, PluginCommands, PolicyCommands, ServeCommands, SessionCommands,
+    WorkspaceCommands,
+};
 
-#[tokio::main]
-async fn main() -> Result<()> {
+fn main() -> Result<()> {
     // Initialize logging
     operon_runtime::init_logging();
 
     // Parse CLI args
     let cli = Cli::parse();
 
+    // Commands that need config load it here, so Landlock can confine the
+    // process before the async runtime starts its worker threads
+    let config = match &cli.command {
+        Commands::Init { .. }
+        | Commands::Config { .. }
+        | Commands::Serve {
+            action: Some(_), ..
+        } => None,
+        _ => {
+            let config = config::load_config(cli.config.as_deref())?;
+            confinement::apply(&config, &cli)?;
+            Some(config)
+        }
+    };
+
+    tokio::runtime::Builder::new_multi_thread()
+        .enable_all()
+        .build()?
+        .block_on(run(cli, config))
+}
+
+async fn run(cli: Cli, config: Option<config::Config>) -> Result<()> {
     // Handle init command early (doesn't need config)
     if let Commands::Init { path } = &cli.command {
         return commands::init::run_init(path);
     }
 
-    // Load config
+    // Config commands inspect the file themselves instead of loading it
+    if let Commands::Config { action } = &cli.command {
+        let config_action = match action {
+            ConfigCommands::Validate { path } => {
+                let path = path.clone().or_else(|| cli.config.clone()).ok_or_else(|| {
+                    anyhow::anyhow!("No config file given (pass a path or --config)")
+                })?;
+                commands::config::ConfigAction::Validate(path)
+            }
+            ConfigCommands::Schema => commands::config::ConfigAction::Schema,
+        };
+        return commands::config::execute(config_action, cli.output);
+    }
+
+    // Serve status/stop only talk to the running server
+    if let Commands::Serve {
+        action: Some(action),
+        pid_file,
+        ..
+    } = &cli.command
+    {
+        let pid_file = pid_file.clone().unwrap_or_else(daemon::default_pid_file);
+        return match action {
+            ServeCommands::Status => commands::serve::status(&pid_file, cli.output),
+            ServeCommands::Stop => commands::serve::stop(&pid_file, cli.output).await,
+        };
+    }
+
     let config_path = cli.config.clone();
-    let config = config::load_config(config_path.as_deref())?;
+    let config = config.expect("config is loaded for every remaining command");
 
     // Resolve execution mode (--allow-tools backward compat)
     let execution_mode = cli.effective_execution_mode();
 
     // Dispatch to command
     match cli.command {
-        Commands::Init { .. } => unreachable!(),
-        Commands::RunPlan { file } => {
-            commands::run_plan::execute(file, execution_mode, &config, cli.record, cli.replay)
-                .await?;
+        Commands::Init { .. } | Commands::Config { .. } => unreachable!(),
+        Commands::RunPlan {
+            file,
+            resume: Some(plan_id),
+            ..
+        } => {
+            commands::run_plan::resume(
+                plan_id,
+                file,
+                execution_mode,
+                &config,
+                cli.record,
+                cli.replay,
+                cli.output,
+            )
+            .await?;
         }
-        Commands::Chat { agent, session } => {
-            commands::chat::execute(agent, session, execution_mode, &config, config_path).await?;
+        Commands::RunPlan {
+            file,
+            resume: None,
+            watch,
+            watch_paths,
+            debounce_ms,
+        } => {
+            let file = file.context("--file is required")?;
+            let watch_options = watch.then(|| commands::run_plan::WatchOptions {
+                paths: watch_paths,
+                debounce: std::time::Duration::from_millis(debounce_ms),
+            });
+            commands::run_plan::execute(
+                file,
+                execution_mode,
+                &config,
+                cli.record,
+                cli.replay,
+                cli.output,
+                watch_options,
+            )
+            .await?;
+        }
+        Commands::Bench {
+            fixture,
+            file,
+            iterations,
+            warmup,
+        } => {
+            let options = commands::bench::BenchOptions {
+                fixture,
+                plan: file,
+                iterations,
+                warmup,
+            };
+            commands::bench::execute(options, &config, cli.output).await?;
+        }
+        Commands::Fixture { action } => match action {
+            FixtureCommands::Show { dir, step } => commands::fixture::show(&dir, step, cli.output)?,
+            FixtureCommands::Edit {
+                dir,
+                step,
+                set_output,
+                error,
+            } => {
+                let change = match (set_output, error) {
+                    (Some(output), _) => commands::fixture::StepEdit::Output(output),
+                    (None, Some(error)) => commands::fixture::StepEdit::Error(error),
+                    (None, None) => unreachable!("clap requires --set-output or --error"),
+                };
+                commands::fixture::edit(&dir, step, change, cli.output)?
+            }
+            FixtureCommands::Scrub { dir, max_bytes } => {
+                commands::fixture::scrub(&dir, max_bytes, cli.output)?
+            }
+            FixtureCommands::Migrate { dir } => commands::fixture::migrate(&dir, cli.output)?,
+        },
+        Commands::Plan { action } => {
+            let plan_action = match action {
+                PlanCommands::Validate { file } => commands::plan::PlanAction::Validate(file),
+                PlanCommands::Graph { file } => commands::plan::PlanAction::Graph(file),
+            };
+            commands::plan::execute(plan_action, &config, cli.output)?;
+        }
+        Commands::Chat {
+            agent,
+            session,
+            model,
+            tui,
+            prompt,
+        } => {
+            let mode = commands::chat::ChatMode::resolve(tui, prompt, cli.output)?;
+            commands::chat::execute(
+                agent,
+                session,
+                model,
+                mode,
+                execution_mode,
+                &config,
+                config_path,
+            )
+            .await?;
         }
         Commands::Plugin { action } => {
             let plugin_action = match action {
@@ -42,9 +189,123 @@ async fn main() -> Result<()> {
                 PluginCommands::Load { path } => commands::plugin::PluginAction::Load(path),
                 PluginCommands::Unload { name } => commands::plugin::PluginAction::Unload(name),
             };
-            commands::plugin::execute(plugin_action).await?;
+            commands::plugin::execute(plugin_action, cli.output).await?;
+        }
+        Commands::ExecTool { tool, timeout_secs } => {
+            commands::exec_tool::execute(&tool, timeout_secs, &config).await?
         }
-        Commands::Serve { host, port } => {
+        Commands::Doctor => {
+            commands::doctor::execute(&config, config_path.as_deref(), cli.output).await?;
+        }
+        Commands::Agent { action } => match action {
+            AgentCommands::List => commands::agent::list(&config, cli.output)?,
+        },
+        Commands::Policy { action } => match action {
+            PolicyCommands::Test {
+                file,
+                tool,
+                input,
+                permission,
+            } => {
+                let samples = match (file, tool) {
+                    (Some(file), _) => commands::policy::load_samples(&file)?,
+                    (None, Some(tool)) => vec![commands::policy::sample_call(
+                        tool,
+                        &input,
+                        permission.as_deref(),
+                    )?],
+                    (None, None) => unreachable!("clap requires --file or --tool"),
+                };
+                commands::policy::test(&config, samples, cli.output)?
+            }
+        },
+        Commands::Audit { action } => match action {
+            AuditCommands::List {
+                session,
+                tool,
+                principal,
+                since,
+                until,
+                limit,
+            } => {
+                let filter = operon_runtime::AuditFilter {
+                    session_id: session,
+                    tool,
+                    principal,
+                    since,
+                    until,
+                    limit: Some(limit),
+                };
+                commands::audit::list(filter, cli.output)?
+            }
+            AuditCommands::Show { id } => commands::audit::show(id, cli.output)?,
+        },
+        Commands::Session { action } => match action {
+            SessionCommands::List => commands::session::list(&config, cli.output).await?,
+            SessionCommands::Fork { id, at } => {
+                commands::session::fork(&config, &id, at, cli.output).await?
+            }
+            SessionCommands::Import { dir } => {
+                commands::session::import(dir.as_deref(), cli.output).await?
+            }
+            SessionCommands::Query {
+                agent,
+                since,
+                until,
+                min_tokens,
+                limit,
+            } => {
+                let query = operon_runtime::SessionQuery {
+                    agent,
+                    updated_after: since,
+                    updated_before: until,
+                    min_tokens,
+                    limit: Some(limit),
+                };
+                commands::session::query(&config, query, cli.output).await?
+            }
+            SessionCommands::Prune { older_than_days } => {
+                let cutoff = chrono::Utc::now() - chrono::Duration::days(older_than_days.into());
+                commands::session::prune(&config, cutoff, cli.output).await?
+            }
+        },
+        Commands::Memory { action } => match action {
+            MemoryCommands::Stats { verify, compact } => {
+                commands::memory::stats(&config, verify, compact, cli.output)?
+            }
+            MemoryCommands::Migrate { model, dimensions } => {
+                commands::memory::migrate(&config, model, dimensions, cli.output).await?
+            }
+        },
+        Commands::Workspace { action } => match action {
+            WorkspaceCommands::List => commands::workspace::list(cli.output)?,
+            WorkspaceCommands::Restore { id } => commands::workspace::restore(id, cli.output)?,
+        },
+        Commands::Serve {
+            host,
+            port,
+            daemon,
+            pid_file,
+            log_file,
+            ..
+        } => {
+            let pid_file = pid_file.unwrap_or_else(daemon::default_pid_file);
+            if daemon && !daemon::is_daemon_child() {
+                // Config was validated above, so startup errors surface before detaching
+                let log_file = log_file.unwrap_or_else(daemon::default_log_file);
+                let pid = daemon::spawn_detached(&pid_file, &log_file)?;
+                println!(
+                    "warden serve started in background (pid {}), logging to {}",
+                    pid,
+                    log_file.display()
+                );
+                return Ok(());
+            }
+            let _pid_guard = if daemon {
+                Some(daemon::PidFile::create(&pid_file)?)
+            } else {
+                None
+            };
             commands::serve::execute(host, port, execution_mode, &config, config_path).await?;
         }
     }
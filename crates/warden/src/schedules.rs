@@ -0,0 +1,403 @@
+//! `[[schedules]]` for `warden serve`: plan files run on cron expressions,
+//! each run recorded in a history Storage.
+
+use crate::cli::ExecutionMode;
+use crate::commands::run_plan::build_runtime;
+use crate::config::{AgentProfileConfig, Config, ScheduleConfig};
+use anyhow::{bail, Context, Result};
+use chrono::{DateTime, Local};
+use croner::Cron;
+use futures::stream::{FuturesUnordered, StreamExt};
+use operon_runtime::scheduler::{parse_steps, ScheduledStep};
+use operon_runtime::storage::Storage;
+use operon_runtime::{ExecutionContext, PermissionLevel, PlanResult, Runtime, PLAN_STEP_TOOL};
+use serde_json::{json, Value};
+use std::collections::HashSet;
+use std::path::{Path, PathBuf};
+use std::time::Instant;
+use tracing::{info, warn};
+
+/// Parse a 5-field cron expression (or 6 with leading seconds)
+pub fn parse_cron(expr: &str) -> Result<Cron> {
+    Cron::new(expr)
+        .with_seconds_optional()
+        .parse()
+        .map_err(|e| anyhow::anyhow!("invalid cron expression '{}': {}", expr, e))
+}
+
+struct Entry<'a> {
+    spec: &'a ScheduleConfig,
+    cron: Cron,
+}
+
+impl Entry<'_> {
+    fn next_after(&self, time: &DateTime<Local>) -> Option<DateTime<Local>> {
+        self.cron.find_next_occurrence(time, false).ok()
+    }
+}
+
+/// Runs the enabled `[[schedules]]` of `config` until dropped
+pub struct Scheduler<'a> {
+    config: &'a Config,
+    entries: Vec<Entry<'a>>,
+    state_dir: PathBuf,
+    history: Storage,
+}
+
+impl<'a> Scheduler<'a> {
+    /// Plan state and `history.db` are kept in `state_dir`
+    pub fn new(config: &'a Config, state_dir: &Path) -> Result<Self> {
+        let entries = config
+            .schedules
+            .iter()
+            .filter(|spec| spec.enabled)
+            .map(|spec| {
+                Ok(Entry {
+                    spec,
+                    cron: parse_cron(&spec.cron)?,
+                })
+            })
+            .collect::<Result<Vec<_>>>()?;
+        std::fs::create_dir_all(state_dir)
+            .with_context(|| format!("Failed to create {}", state_dir.display()))?;
+        let history = Storage::open(&state_dir.join("history.db").to_string_lossy())?;
+        Ok(Self {
+            config,
+            entries,
+            state_dir: state_dir.to_path_buf(),
+            history,
+        })
+    }
+
+    /// Wait for each schedule's next time and run its plan. A schedule whose
+    /// previous run is still going skips that time. Never returns normally.
+    pub async fn run(&self) -> Result<()> {
+        if self.entries.is_empty() {
+            return std::future::pending().await;
+        }
+        let now = Local::now();
+        let mut next: Vec<_> = self.entries.iter().map(|e| e.next_after(&now)).collect();
+        for (entry, next) in self.entries.iter().zip(&next) {
+            info!(schedule = %entry.spec.name, cron = %entry.spec.cron, next = ?next, "Plan scheduled");
+        }
+
+        let mut running = HashSet::new();
+        let mut runs = FuturesUnordered::new();
+        loop {
+            let wait = next
+                .iter()
+                .flatten()
+                .min()
+                .map(|due| (*due - Local::now()).to_std().unwrap_or_default());
+            tokio::select! {
+                _ = sleep(wait) => {
+                    let now = Local::now();
+                    for (index, entry) in self.entries.iter().enumerate() {
+                        if next[index].is_none_or(|due| due > now) {
+                            continue;
+                        }
+                        next[index] = entry.next_after(&now);
+                        if !running.insert(index) {
+                            warn!(schedule = %entry.spec.name, "Previous run still in progress, skipping");
+                            continue;
+                        }
+                        runs.push(async move {
+                            self.run_now(index).await;
+                            index
+                        });
+                    }
+                }
+                Some(index) = runs.next() => {
+                    running.remove(&index);
+                }
+            }
+        }
+    }
+
+    /// Run schedule `index` once and record the outcome in the history
+    async fn run_now(&self, index: usize) -> Value {
+        let spec = self.entries[index].spec;
+        let started_at = Local::now();
+        let started = Instant::now();
+        info!(schedule = %spec.name, plan = %spec.plan, "Running scheduled plan");
+
+        let result = self.execute(spec).await;
+        let mut record = json!({
+            "schedule": spec.name,
+            "plan": spec.plan,
+            "agent": spec.agent,
+            "started_at": started_at.to_rfc3339(),
+            "duration_ms": started.elapsed().as_millis() as u64,
+        });
+        match &result {
+            Ok(plan) => {
+                record["status"] = json!("succeeded");
+                record["dry_run"] = json!(plan.dry_run);
+                record["steps"] = json!(plan.steps.len());
+                if !plan.failed.is_empty() {
+                    record["failed_steps"] = json!(plan.failed);
+                }
+                info!(schedule = %spec.name, duration_ms = plan.duration_ms, "Scheduled plan succeeded");
+            }
+            Err(e) => {
+                record["status"] = json!("failed");
+                record["error"] = json!(format!("{:#}", e));
+                warn!(schedule = %spec.name, error = %format!("{:#}", e), "Scheduled plan failed");
+            }
+        }
+
+        let key = format!("{}/{}", spec.name, started_at.to_rfc3339());
+        if let Err(e) = self.history.save_state_async(&key, record.clone()).await {
+            warn!(schedule = %spec.name, error = %e, "Failed to record schedule run");
+        }
+        record
+    }
+
+    async fn execute(&self, spec: &ScheduleConfig) -> Result<PlanResult> {
+        let plan_content = std::fs::read_to_string(&spec.plan)
+            .with_context(|| format!("Failed to read plan file {}", spec.plan))?;
+        let plan: Value =
+            serde_json::from_str(&plan_content).context("Failed to parse plan JSON")?;
+
+        let dry_run = match spec.mode {
+            ExecutionMode::Auto => self.config.runtime.dry_run,
+            ExecutionMode::DryRun => true,
+            ExecutionMode::Execute => false,
+        };
+        let steps = parse_steps(&plan)?;
+        let shell_dry_run = dry_run && !steps.iter().any(|s| s.always_execute);
+
+        // Each schedule keeps its own plan state; serve holds the default DB
+        let db_path = self.state_dir.join(format!("{}.db", spec.name));
+        let runtime = build_runtime(
+            self.config,
+            dry_run,
+            shell_dry_run,
+            ExecutionContext::Normal,
+            &db_path.to_string_lossy(),
+        )?;
+        if let Some(agent) = &spec.agent {
+            let profile = self
+                .config
+                .agents
+                .get(agent)
+                .with_context(|| format!("No [agents.{}] profile", agent))?;
+            check_agent_scope(&runtime, &steps, agent, profile, 0)?;
+        }
+
+        runtime.start().await?;
+        let result = runtime.run_plan(plan).await;
+        runtime.stop().await?;
+        result
+    }
+}
+
+async fn sleep(wait: Option<std::time::Duration>) {
+    match wait {
+        Some(wait) => tokio::time::sleep(wait).await,
+        None => std::future::pending().await,
+    }
+}
+
+/// Nested plans checked by [`check_agent_scope`], as deep as the runtime runs them
+const MAX_PLAN_DEPTH: usize = 8;
+
+/// Fail if a step, or a step of a nested plan, uses a tool outside the
+/// agent's `tools` or above its `max_permission` (default "execute", as for
+/// chat agents)
+fn check_agent_scope(
+    runtime: &Runtime,
+    steps: &[ScheduledStep],
+    agent: &str,
+    profile: &AgentProfileConfig,
+    depth: usize,
+) -> Result<()> {
+    if depth > MAX_PLAN_DEPTH {
+        bail!("Nested plans exceed max depth {}", MAX_PLAN_DEPTH);
+    }
+    let max_permission = profile
+        .max_permission
+        .clone()
+        .unwrap_or(PermissionLevel::Execute);
+    for step in steps {
+        if !profile.tools.is_empty() && !profile.tools.contains(&step.tool) {
+            bail!(
+                "Step '{}' uses tool '{}', which agent '{}' may not use",
+                step.id,
+                step.tool,
+                agent
+            );
+        }
+        let tool = runtime.resolve_tool_name(&step.tool);
+        if let Some(permission) = runtime.tool_permission(&tool) {
+            if permission > max_permission {
+                bail!(
+                    "Step '{}' needs {:?} permission for '{}'; agent '{}' is limited to {:?}",
+                    step.id,
+                    permission,
+                    step.tool,
+                    agent,
+                    max_permission
+                );
+            }
+        }
+        if step.tool == PLAN_STEP_TOOL {
+            let nested = parse_steps(&nested_plan(step, agent)?)
+                .with_context(|| format!("Invalid nested plan in step '{}'", step.id))?;
+            check_agent_scope(runtime, &nested, agent, profile, depth + 1)?;
+        }
+    }
+    Ok(())
+}
+
+/// The plan a `plan` step runs; one loaded from a file named by a placeholder
+/// can't be checked before the run, so it's refused
+fn nested_plan(step: &ScheduledStep, agent: &str) -> Result<Value> {
+    if let Some(file) = step.input["file"].as_str() {
+        if file.contains("{{") {
+            bail!(
+                "Step '{}' picks its nested plan at run time; agent '{}' may only run plans checked up front",
+                step.id,
+                agent
+            );
+        }
+        let content = std::fs::read_to_string(file)
+            .with_context(|| format!("Failed to read nested plan file {}", file))?;
+        serde_json::from_str(&content).context("Failed to parse nested plan JSON")
+    } else if step.input["plan"].is_object() {
+        Ok(step.input["plan"].clone())
+    } else {
+        bail!("Step '{}' needs a 'file' or inline 'plan' input", step.id);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+
+    fn schedule_config(dir: &Path, agent: Option<&str>) -> Config {
+        let plan = dir.join("plan.json");
+        std::fs::write(
+            &plan,
+            r#"{"steps": [{"id": "hello", "tool": "shell", "input": {"command": "echo hi"}}]}"#,
+        )
+        .unwrap();
+        let mut config = Config::default_config();
+        config.schedules.push(ScheduleConfig {
+            name: "nightly".to_string(),
+            cron: "30 2 * * *".to_string(),
+            plan: plan.to_string_lossy().to_string(),
+            agent: agent.map(str::to_string),
+            mode: ExecutionMode::DryRun,
+            enabled: true,
+        });
+        config
+    }
+
+    #[test]
+    fn test_next_run_follows_cron() {
+        let entry = Entry {
+            spec: &ScheduleConfig {
+                name: "n".to_string(),
+                cron: String::new(),
+                plan: String::new(),
+                agent: None,
+                mode: ExecutionMode::Auto,
+                enabled: true,
+            },
+            cron: parse_cron("30 2 * * 1-5").unwrap(),
+        };
+        // Friday 03:00 -> Monday 02:30
+        let friday = Local.with_ymd_and_hms(2026, 10, 16, 3, 0, 0).unwrap();
+        assert_eq!(
+            entry.next_after(&friday).unwrap(),
+            Local.with_ymd_and_hms(2026, 10, 19, 2, 30, 0).unwrap()
+        );
+        assert!(parse_cron("0 */5 * * * *").is_ok());
+        assert!(parse_cron("every day").is_err());
+    }
+
+    #[tokio::test]
+    async fn test_run_is_recorded_in_history() {
+        let dir = tempfile::tempdir().unwrap();
+        let config = schedule_config(dir.path(), None);
+        let scheduler = Scheduler::new(&config, &dir.path().join("state")).unwrap();
+
+        let record = scheduler.run_now(0).await;
+        assert_eq!(record["status"], "succeeded");
+        assert_eq!(record["dry_run"], true);
+        assert_eq!(record["steps"], 1);
+
+        let keys = scheduler.history.list_keys().unwrap();
+        assert_eq!(keys.len(), 1);
+        assert!(keys[0].starts_with("nightly/"));
+        assert_eq!(
+            scheduler.history.load_state(&keys[0]).unwrap().unwrap(),
+            record
+        );
+    }
+
+    #[tokio::test]
+    async fn test_agent_scope_limits_plan_tools() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut config = schedule_config(dir.path(), Some("reader"));
+        config.agents.insert(
+            "reader".to_string(),
+            AgentProfileConfig {
+                max_permission: Some(PermissionLevel::Read),
+                ..AgentProfileConfig::default()
+            },
+        );
+        let scheduler = Scheduler::new(&config, &dir.path().join("state")).unwrap();
+
+        let record = scheduler.run_now(0).await;
+        assert_eq!(record["status"], "failed");
+        assert!(record["error"]
+            .as_str()
+            .unwrap()
+            .contains("agent 'reader' is limited to Read"));
+    }
+
+    #[tokio::test]
+    async fn test_agent_scope_covers_nested_plans() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut config = schedule_config(dir.path(), Some("reader"));
+        config.agents.insert(
+            "reader".to_string(),
+            AgentProfileConfig {
+                tools: vec!["plan".to_string(), "read_file".to_string()],
+                ..AgentProfileConfig::default()
+            },
+        );
+        let child = dir.path().join("child.json");
+        std::fs::write(
+            &child,
+            r#"{"steps": [{"id": "hello", "tool": "shell", "input": {"cmd": "echo hi"}}]}"#,
+        )
+        .unwrap();
+        let write_plan = |input: Value| {
+            let plan = json!({"steps": [{"id": "outer", "tool": "plan", "input": input}]});
+            std::fs::write(dir.path().join("plan.json"), plan.to_string()).unwrap();
+        };
+        let scheduler = Scheduler::new(&config, &dir.path().join("state")).unwrap();
+
+        let inline =
+            json!({"steps": [{"id": "hello", "tool": "shell", "input": {"cmd": "echo hi"}}]});
+        for input in [
+            json!({"file": child.to_string_lossy()}),
+            json!({"plan": {"steps": [{"id": "inner", "tool": "plan", "input": {"plan": inline}}]}}),
+            json!({"file": "{{params.child}}"}),
+        ] {
+            write_plan(input);
+            let record = scheduler.run_now(0).await;
+            assert_eq!(record["status"], "failed");
+            let error = record["error"].as_str().unwrap();
+            assert!(
+                error.contains("uses tool 'shell'") || error.contains("checked up front"),
+                "{}",
+                error
+            );
+        }
+    }
+}
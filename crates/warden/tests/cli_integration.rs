@@ -43,3 +43,179 @@ fn test_warden_help() {
     assert!(stdout.contains("run-plan"));
     assert!(stdout.contains("execution-mode"));
 }
+
+#[test]
+fn test_warden_config_validate_json_output() {
+    let dir = tempfile::tempdir().unwrap();
+    let config_path = dir.path().join("silentclaw.toml");
+    std::fs::write(&config_path, "[runtime]\ntimeout_secs = 0\n\n[tools]\n").unwrap();
+
+    let output = Command::new("cargo")
+        .args([
+            "run", "--bin", "warden", "--", "--output", "json", "config", "validate",
+        ])
+        .arg(&config_path)
+        .output()
+        .unwrap();
+
+    assert!(!output.status.success());
+    let report: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+    assert_eq!(report["valid"], false);
+    assert_eq!(report["errors"][0], "runtime.timeout_secs must be > 0");
+}
+
+#[test]
+fn test_warden_policy_test_reports_deciding_rules() {
+    let dir = tempfile::tempdir().unwrap();
+    let config_path = dir.path().join("silentclaw.toml");
+    std::fs::write(
+        &config_path,
+        "[runtime]\n[tools]\n[tool_policy]\nenabled = true\n\n\
+         [[tool_policy.rules]]\nname = \"no-rm\"\naction = \"deny\"\ntools = [\"shell\"]\n\
+         input = { cmd = '^rm\\s' }\nreason = \"deletes files\"\n",
+    )
+    .unwrap();
+    let samples_path = dir.path().join("samples.json");
+    std::fs::write(
+        &samples_path,
+        r#"[
+            {"tool": "shell", "input": {"cmd": "rm -rf /"}, "expect": "deny"},
+            {"tool": "shell", "input": {"cmd": "ls"}, "expect": "deny"}
+        ]"#,
+    )
+    .unwrap();
+
+    let output = Command::new("cargo")
+        .args([
+            "run", "--bin", "warden", "--", "--output", "json", "--config",
+        ])
+        .arg(&config_path)
+        .args(["policy", "test", "--file"])
+        .arg(&samples_path)
+        .output()
+        .unwrap();
+
+    // The second sample expects a denial no rule gives
+    assert!(!output.status.success());
+    let outcomes: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+    assert_eq!(outcomes[0]["action"], "deny");
+    assert_eq!(outcomes[0]["rule"], "no-rm");
+    assert_eq!(outcomes[0]["passed"], true);
+    assert_eq!(outcomes[1]["action"], "allow");
+    assert!(outcomes[1]["rule"].is_null());
+    assert_eq!(outcomes[1]["passed"], false);
+}
+
+#[test]
+fn test_warden_audit_list_and_show() {
+    let dir = tempfile::tempdir().unwrap();
+    let config_path = dir.path().join("silentclaw.toml");
+    std::fs::write(&config_path, "[runtime]\n[tools]\n").unwrap();
+    {
+        let storage =
+            operon_runtime::Storage::open(dir.path().join("silentclaw.db").to_str().unwrap())
+                .unwrap();
+        let input = serde_json::json!({"cmd": "ls"});
+        storage
+            .append_audit(operon_runtime::AuditRecord::new(
+                "shell",
+                &input,
+                Some("s1"),
+            ))
+            .unwrap();
+        let mut denied = operon_runtime::AuditRecord::new("read_file", &input, None);
+        denied.decision = operon_runtime::AuditDecision::Denied;
+        denied.reason = Some("tool not found: read_file".into());
+        storage.append_audit(denied).unwrap();
+    }
+    let warden = |args: &[&str]| {
+        Command::new("cargo")
+            .args(["run", "--quiet", "--manifest-path"])
+            .arg(concat!(env!("CARGO_MANIFEST_DIR"), "/Cargo.toml"))
+            .args(["--bin", "warden", "--", "--config"])
+            .arg(&config_path)
+            .args(args)
+            .current_dir(dir.path())
+            .output()
+            .unwrap()
+    };
+
+    let output = warden(&["--output", "json", "audit", "list", "--session", "s1"]);
+    assert!(output.status.success());
+    let records: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+    assert_eq!(records.as_array().unwrap().len(), 1);
+    assert_eq!(records[0]["tool"], "shell");
+    assert_eq!(records[0]["decision"], "allowed");
+
+    let output = warden(&["audit", "show", "2"]);
+    assert!(output.status.success());
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(stdout.contains("Decision:   denied"), "{}", stdout);
+    assert!(stdout.contains("Reason:     tool not found: read_file"));
+    assert!(!warden(&["audit", "show", "3"]).status.success());
+}
+
+#[test]
+fn test_warden_session_query_and_prune() {
+    let dir = tempfile::tempdir().unwrap();
+    let config_path = dir.path().join("silentclaw.toml");
+    std::fs::write(
+        &config_path,
+        "[runtime]\n[tools]\n[sessions]\nstore = \"sqlite\"\n",
+    )
+    .unwrap();
+    let runtime = tokio::runtime::Runtime::new().unwrap();
+    runtime.block_on(async {
+        let store = operon_runtime::SqliteSessionStore::open(
+            &dir.path().join(".silentclaw").join("sessions.db"),
+        )
+        .unwrap();
+        for (agent, updated_at) in [
+            ("reviewer", "2020-01-01T00:00:00Z"),
+            ("default", "2999-01-01T00:00:00Z"),
+        ] {
+            let mut session = operon_runtime::Session::new(agent);
+            session.updated_at = updated_at.parse().unwrap();
+            store.save(&session).await.unwrap();
+        }
+    });
+    let warden = |args: &[&str]| {
+        Command::new("cargo")
+            .args(["run", "--quiet", "--manifest-path"])
+            .arg(concat!(env!("CARGO_MANIFEST_DIR"), "/Cargo.toml"))
+            .args(["--bin", "warden", "--", "--config"])
+            .arg(&config_path)
+            .args(args)
+            .env("HOME", dir.path())
+            .output()
+            .unwrap()
+    };
+
+    let output = warden(&[
+        "--output", "json", "session", "query", "--agent", "reviewer",
+    ]);
+    assert!(
+        output.status.success(),
+        "{}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+    let sessions: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+    assert_eq!(sessions.as_array().unwrap().len(), 1);
+    assert_eq!(sessions[0]["agent_name"], "reviewer");
+
+    let output = warden(&[
+        "--output",
+        "json",
+        "session",
+        "prune",
+        "--older-than-days",
+        "30",
+    ]);
+    assert!(output.status.success());
+    let pruned: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+    assert_eq!(pruned["pruned"], 1);
+    let output = warden(&["--output", "json", "session", "list"]);
+    let sessions: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+    assert_eq!(sessions.as_array().unwrap().len(), 1);
+    assert_eq!(sessions[0]["agent"], "default");
+}